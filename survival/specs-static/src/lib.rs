@@ -1,11 +1,13 @@
 // From: https://github.com/torkleyy/specs-static
 
+use std::any::TypeId;
 use std::hash::Hash;
 use std::marker::PhantomData;
 
 use amethyst::ecs::prelude::*;
 use amethyst::core::shrev::EventChannel;
-use amethyst::ecs::storage::{ComponentEvent, UnprotectedStorage, TryDefault};
+use amethyst::ecs::storage::{ComponentEvent, DistinctStorage, UnprotectedStorage, TryDefault};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
 
 type Index = u32;
 
@@ -36,6 +38,40 @@ pub trait Id: Copy + Eq + Hash + Ord + Send + Sync + Sized + std::fmt::Debug + '
 
     /// Returns the `id` integer value.
     fn id(&self) -> u32;
+
+    /// Bits of the id numbering it within its group. The ids of a group are given room in a
+    /// storage together when the first of them is inserted, and the room is given back once the
+    /// group is empty, so a storage is only as large as the groups it has components in.
+    const GROUP_BITS: u32 = 0;
+
+    /// The generation of the id. A group of ids in a storage is of the generation of the ids
+    /// inserted into it, dropping its components of an older generation when one of a newer one
+    /// is inserted, and ids of any other generation find nothing in it. Ids which are never
+    /// reused can leave it at 0.
+    fn generation(&self) -> u32 {
+        0
+    }
+
+    /// The id `value` of `generation`.
+    fn with_generation(value: u32, _generation: u32) -> Self {
+        Self::from_u32(value)
+    }
+}
+
+/// A group of ids in a storage, see `Id::GROUP_BITS`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Group {
+    /// The first slot of the room given to the group in the inner storage, if it has any.
+    start: Option<Index>,
+    /// The number of components the group has.
+    len: u32,
+    generation: u32,
+}
+
+/// The slot of the inner storage the component at `id` is in, if its group has room.
+fn slot_of(groups: &[Group], group_bits: u32, id: Index) -> Option<Index> {
+    let start = groups.get((id >> group_bits) as usize)?.start?;
+    Some(start + (id & ((1 << group_bits) - 1)))
 }
 
 /// A storage for components managed with `specs_static::Id` instead of `Entity`.
@@ -45,20 +81,49 @@ pub trait Id: Copy + Eq + Hash + Ord + Send + Sync + Sized + std::fmt::Debug + '
 ///
 /// These component storages also have to be registered. This can be done using the `WorldExt`
 /// trait and its `register_tile_comp` method.
+///
+/// # Groups and generations
+///
+/// Components are kept in the inner storage by slot rather than by id, each group of ids having
+/// a run of slots of its own while it has components, see `Id::GROUP_BITS`. Lookups only find
+/// the components of ids of the generation of their group, see `Id::generation`.
+///
+/// The runs given back by emptied groups are only given to other groups once the storage has been
+/// maintained twice since, see `maintain`, for the events of their slots to be read as those of
+/// the group they were sent for.
 pub struct Storage<C, D: UnprotectedStorage<C>, I> {
     data: D,
     bitset: BitSet,
+    groups: Vec<Group>,
+    group_bits: u32,
+    /// The group each run of slots was last given to.
+    owners: Vec<Index>,
+    /// The first slots of the runs given back by their groups, free to be given to others.
+    free: Vec<Index>,
+    /// The first slots of the runs given back since the storage was last maintained.
+    freed: Vec<Index>,
+    /// The first slots of the runs given back before it was last maintained, free once it is
+    /// maintained again.
+    cooling: Vec<Index>,
     phantom: PhantomData<(C, I)>,
 }
 
 impl<C, D: UnprotectedStorage<C>, I> Default for Storage<C, D, I>
-    where D: TryDefault
+    where
+        D: TryDefault,
+        I: Id,
 {
     fn default() -> Self {
         Self {
             data: D::unwrap_default(),
             phantom: PhantomData,
             bitset: BitSet::default(),
+            groups: Vec::new(),
+            group_bits: I::GROUP_BITS,
+            owners: Vec::new(),
+            free: Vec::new(),
+            freed: Vec::new(),
+            cooling: Vec::new(),
         }
     }
 }
@@ -69,46 +134,115 @@ impl<C, D, I> Storage<C, D, I>
         D: UnprotectedStorage<C>,
         I: Id,
 {
+    /// The bitset of ids which currently have a component inserted.
+    pub fn mask(&self) -> &BitSet {
+        &self.bitset
+    }
+
+    /// The ids which currently have a component inserted, of the generations of their groups.
+    pub fn ids<'a>(&'a self) -> impl Iterator<Item = I> + 'a {
+        (&self.bitset).join().map(move |id| {
+            I::with_generation(id, self.groups[(id >> self.group_bits) as usize].generation)
+        })
+    }
+
+    /// The id of the component at `slot` of the inner storage, for reading the events of tracked
+    /// storages, which are of slots. Slots of runs since given to another group read as those of
+    /// the new group, which only happens to events left unread over two calls to `maintain`.
+    pub fn id_of_slot(&self, slot: Index) -> Option<I> {
+        let group = *self.owners.get((slot >> self.group_bits) as usize)?;
+        let id = (group << self.group_bits) | (slot & ((1 << self.group_bits) - 1));
+        let generation = self.groups[group as usize].generation;
+        Some(I::with_generation(id, generation))
+    }
+
+    /// The slot of the component at `id`, if it has one of the generation of `id`.
+    fn slot(&self, id: I) -> Option<Index> {
+        let group = self.groups.get((id.id() >> self.group_bits) as usize)?;
+        if group.generation != id.generation() || !self.bitset.contains(id.id()) {
+            return None;
+        }
+        slot_of(&self.groups, self.group_bits, id.id())
+    }
+
+    /// Readies the group of `id` for a component at `id`, emptying it of the components of an
+    /// older generation and giving it room if it has none. Returns the slot of `id`, or `None` if
+    /// `id` is of an older generation than its group.
+    fn claim(&mut self, id: I) -> Option<Index> {
+        let group = (id.id() >> self.group_bits) as usize;
+        if group >= self.groups.len() {
+            self.groups.resize(group + 1, Group::default());
+        }
+
+        if id.generation() < self.groups[group].generation {
+            return None;
+        }
+        if id.generation() > self.groups[group].generation {
+            let first = (group as Index) << self.group_bits;
+            let ids = (first..first + (1 << self.group_bits))
+                .map(|id| I::with_generation(id, self.groups[group].generation))
+                .collect::<Vec<_>>();
+            self.clear_region(ids);
+            self.groups[group].generation = id.generation();
+        }
+
+        if self.groups[group].start.is_none() {
+            let start = match self.free.pop() {
+                Some(start) => {
+                    self.owners[(start >> self.group_bits) as usize] = group as Index;
+                    start
+                }
+                None => {
+                    self.owners.push(group as Index);
+                    ((self.owners.len() - 1) as Index) << self.group_bits
+                }
+            };
+            self.groups[group].start = Some(start);
+        }
+        slot_of(&self.groups, self.group_bits, id.id())
+    }
+
     /// Tries to retrieve a component by its `Id`.
-    /// This will only check whether a component is inserted or not, without doing
-    /// any liveness checks for the id.
+    /// This will only check whether a component is inserted at an id of its generation or not,
+    /// without doing any liveness checks for the id.
     pub fn get(&self, id: I) -> Option<&C> {
-        if self.bitset.contains(id.id()) {
-            unsafe {
-                Some(self.data.get(id.id()))
-            }
-        } else {
-            None
+        let slot = self.slot(id)?;
+        unsafe {
+            Some(self.data.get(slot))
         }
     }
 
     /// Tries to retrieve a component mutably by its `Id`.
-    /// This will only check whether a component is inserted or not, without doing
-    /// any liveness checks for the id.
+    /// This will only check whether a component is inserted at an id of its generation or not,
+    /// without doing any liveness checks for the id.
     pub fn get_mut(&mut self, id: I) -> Option<&mut C> {
-        if self.bitset.contains(id.id()) {
-            unsafe {
-                Some(self.data.get_mut(id.id()))
-            }
-        } else {
-            None
+        let slot = self.slot(id)?;
+        unsafe {
+            Some(self.data.get_mut(slot))
         }
     }
 
     /// Inserts `comp` at `id`. If there already was a value, it will be returned.
     ///
-    /// In contrast to entities, **there are no invalid ids.**
+    /// In contrast to entities, **there are no invalid ids**, only stale ones: those of an older
+    /// generation than their group, which are handed `comp` back rather than inserting it.
     pub fn insert(&mut self, id: I, comp: C) -> Option<C> {
+        let slot = match self.claim(id) {
+            Some(slot) => slot,
+            None => return Some(comp),
+        };
+
         let old = if self.bitset.add(id.id()) {
             unsafe {
-                Some(self.data.remove(id.id()))
+                Some(self.data.remove(slot))
             }
         } else {
+            self.groups[(id.id() >> self.group_bits) as usize].len += 1;
             None
         };
 
         unsafe {
-            self.data.insert(id.id(), comp);
+            self.data.insert(slot, comp);
         }
 
         old
@@ -116,31 +250,267 @@ impl<C, D, I> Storage<C, D, I>
     pub fn insert_default(&mut self, id: I) -> Option<C>
         where C: Component + Default
     {
-        let old = if self.bitset.add(id.id()) {
-            unsafe {
-                Some(self.data.remove(id.id()))
-            }
-        } else {
-            None
-        };
+        self.insert(id, C::default())
+    }
 
-        unsafe {
-            self.data.insert(id.id(), C::default());
+    /// Removes the component at `id`.
+    pub fn remove(&mut self, id: I) -> Option<C> {
+        let slot = self.slot(id)?;
+        self.bitset.remove(id.id());
+        let comp = unsafe { self.data.remove(slot) };
+
+        let group = &mut self.groups[(id.id() >> self.group_bits) as usize];
+        group.len -= 1;
+        if group.len == 0 {
+            self.freed.extend(group.start.take());
         }
 
-        old
+        Some(comp)
     }
 
-    /// Removes the component at `id`.
-    pub fn remove(&mut self, id: I) -> Option<C> {
-        if self.bitset.remove(id.id()) {
-            unsafe {
-                Some(self.data.remove(id.id()))
-            }
-        } else {
-            None
+    /// Frees the runs of slots given back before the storage was last maintained for other
+    /// groups to be given, which is to be done once a frame, after the readers of its events have
+    /// run. Runs are never given to another group without it.
+    pub fn maintain(&mut self) {
+        self.free.append(&mut self.cooling);
+        std::mem::swap(&mut self.cooling, &mut self.freed);
+    }
+
+    /// Removes the components at each of `ids`, as when a region of tiles is unloaded, returning
+    /// how many there were. Ids without a component are skipped over.
+    pub fn clear_region<It>(&mut self, ids: It) -> usize
+        where It: IntoIterator<Item = I>
+    {
+        ids.into_iter().filter(|id| self.remove(*id).is_some()).count()
+    }
+
+    /// Removes the components at each of `ids` as `clear_region` does, returning them with their
+    /// ids in the order of `ids`.
+    pub fn drain_region<It>(&mut self, ids: It) -> Vec<(I, C)>
+        where It: IntoIterator<Item = I>
+    {
+        ids.into_iter()
+            .filter_map(|id| self.remove(id).map(|comp| (id, comp)))
+            .collect()
+    }
+
+    /// Gets the entry of `id`, to look at or change its component in place, or insert one if it
+    /// has none.
+    pub fn entry(&mut self, id: I) -> Entry<C, D, I> {
+        match self.slot(id) {
+            Some(slot) => Entry::Occupied(OccupiedEntry {
+                id,
+                slot,
+                storage: self,
+            }),
+            None => Entry::Vacant(VacantEntry { id, storage: self }),
         }
     }
+
+    /// Retrieves the component at `id` mutably, inserting its default first if there is none.
+    ///
+    /// # Panics
+    ///
+    /// If `id` is stale, see `insert`.
+    pub fn get_mut_or_default(&mut self, id: I) -> &mut C
+        where C: Default
+    {
+        self.entry(id).or_default()
+    }
+
+    /// Replaces the components of this storage with those of `other`. This is done through
+    /// `remove` and `insert`, so that the events of tracked storages are sent and their readers
+    /// stay valid, which they wouldn't if the storage itself was replaced.
+    pub fn replace_with(&mut self, mut other: Self) {
+        let ids = self.ids().collect::<Vec<_>>();
+        self.clear_region(ids);
+        // Take the generations of `other` as they are, older or not
+        for group in &mut self.groups {
+            group.generation = 0;
+        }
+
+        let ids = other.ids().collect::<Vec<_>>();
+        for (id, comp) in other.drain_region(ids) {
+            self.insert(id, comp);
+        }
+    }
+}
+
+/// A view into the component at an id of a `Storage`, as returned by `Storage::entry`.
+pub enum Entry<'a, C, D: UnprotectedStorage<C>, I> {
+    Occupied(OccupiedEntry<'a, C, D, I>),
+    Vacant(VacantEntry<'a, C, D, I>),
+}
+
+/// The entry of an id which has a component.
+pub struct OccupiedEntry<'a, C, D: UnprotectedStorage<C>, I> {
+    id: I,
+    slot: Index,
+    storage: &'a mut Storage<C, D, I>,
+}
+
+/// The entry of an id which has no component.
+pub struct VacantEntry<'a, C, D: UnprotectedStorage<C>, I> {
+    id: I,
+    storage: &'a mut Storage<C, D, I>,
+}
+
+impl<'a, C, D, I> Entry<'a, C, D, I>
+    where
+        C: Component,
+        D: UnprotectedStorage<C>,
+        I: Id,
+{
+    /// The id this is the entry of.
+    pub fn id(&self) -> I {
+        match self {
+            Entry::Occupied(entry) => entry.id,
+            Entry::Vacant(entry) => entry.id,
+        }
+    }
+
+    /// The component of the entry, inserting `default` first if it has none.
+    pub fn or_insert(self, default: C) -> &'a mut C {
+        self.or_insert_with(|| default)
+    }
+
+    /// The component of the entry, inserting the result of `default` first if it has none.
+    pub fn or_insert_with<F: FnOnce() -> C>(self, default: F) -> &'a mut C {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// The component of the entry, inserting its default first if it has none.
+    pub fn or_default(self) -> &'a mut C
+        where C: Default
+    {
+        self.or_insert_with(C::default)
+    }
+}
+
+impl<'a, C, D, I> OccupiedEntry<'a, C, D, I>
+    where
+        C: Component,
+        D: UnprotectedStorage<C>,
+        I: Id,
+{
+    pub fn id(&self) -> I {
+        self.id
+    }
+
+    pub fn get(&self) -> &C {
+        unsafe { self.storage.data.get(self.slot) }
+    }
+
+    pub fn get_mut(&mut self) -> &mut C {
+        unsafe { self.storage.data.get_mut(self.slot) }
+    }
+
+    /// The component of the entry, for as long as the storage is borrowed.
+    pub fn into_mut(self) -> &'a mut C {
+        let storage = self.storage;
+        unsafe { storage.data.get_mut(self.slot) }
+    }
+
+    /// Replaces the component of the entry with `comp`, returning the one it had.
+    pub fn insert(&mut self, comp: C) -> C {
+        std::mem::replace(self.get_mut(), comp)
+    }
+
+    /// Removes the component of the entry.
+    pub fn remove(self) -> C {
+        self.storage
+            .remove(self.id)
+            .expect("Unreachable: the entry is occupied")
+    }
+}
+
+impl<'a, C, D, I> VacantEntry<'a, C, D, I>
+    where
+        C: Component,
+        D: UnprotectedStorage<C>,
+        I: Id,
+{
+    pub fn id(&self) -> I {
+        self.id
+    }
+
+    /// Inserts `comp` at the id of the entry, returning it.
+    ///
+    /// # Panics
+    ///
+    /// If the id of the entry is stale, see `Storage::insert`.
+    pub fn insert(self, comp: C) -> &'a mut C {
+        let storage = self.storage;
+        if storage.insert(self.id, comp).is_some() {
+            panic!("{:?} is stale", self.id);
+        }
+        let slot = storage.slot(self.id).expect("Unreachable: the component was just inserted");
+        unsafe { storage.data.get_mut(slot) }
+    }
+}
+
+/// A storage as it serializes: the ids which have a component in ascending order, and their
+/// generations and components in the same order.
+#[derive(Serialize)]
+struct StorageRef<'a, C> {
+    ids: Vec<Index>,
+    generations: Vec<u32>,
+    components: Vec<&'a C>,
+}
+
+#[derive(Deserialize)]
+struct StorageData<C> {
+    ids: Vec<Index>,
+    generations: Vec<u32>,
+    components: Vec<C>,
+}
+
+impl<C, D, I> Serialize for Storage<C, D, I>
+    where
+        C: Component + Serialize,
+        D: UnprotectedStorage<C>,
+        I: Id,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let ids = self.ids().collect::<Vec<_>>();
+        let components = ids
+            .iter()
+            .map(|id| self.get(*id).expect("Unreachable: the id has a component"))
+            .collect();
+
+        StorageRef {
+            ids: ids.iter().map(Id::id).collect(),
+            generations: ids.iter().map(Id::generation).collect(),
+            components,
+        }.serialize(serializer)
+    }
+}
+
+impl<'de, C, D, I> Deserialize<'de> for Storage<C, D, I>
+    where
+        C: Component + Deserialize<'de>,
+        D: UnprotectedStorage<C> + TryDefault,
+        I: Id,
+{
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let data = StorageData::<C>::deserialize(deserializer)?;
+        if data.ids.len() != data.components.len() || data.ids.len() != data.generations.len() {
+            return Err(serde::de::Error::invalid_length(
+                data.components.len(),
+                &"as many components and generations as ids",
+            ));
+        }
+
+        let mut storage = Self::default();
+        let ids = data.ids.into_iter().zip(data.generations);
+        for ((id, generation), comp) in ids.zip(data.components) {
+            storage.insert(I::with_generation(id, generation), comp);
+        }
+        Ok(storage)
+    }
 }
 
 impl<C, D, I> Tracked for Storage<C, D, I>
@@ -157,8 +527,12 @@ impl<C, D, I> Drop for Storage<C, D, I>
         D: UnprotectedStorage<C>,
 {
     fn drop(&mut self) {
+        let mut slots = BitSet::new();
+        for id in (&self.bitset).join() {
+            slots.add(slot_of(&self.groups, self.group_bits, id).unwrap());
+        }
         unsafe {
-            self.data.clean(&self.bitset);
+            self.data.clean(&slots);
         }
     }
 }
@@ -168,15 +542,15 @@ impl<'a, C, D, I> Join for &'a Storage<C, D, I>
         D: UnprotectedStorage<C>,
 {
     type Type = &'a C;
-    type Value = &'a D;
+    type Value = (&'a D, &'a [Group], u32);
     type Mask = &'a BitSet;
 
     unsafe fn open(self) -> (Self::Mask, Self::Value) {
-        (&self.bitset, &self.data)
+        (&self.bitset, (&self.data, &self.groups, self.group_bits))
     }
 
     unsafe fn get(value: &mut Self::Value, id: Index) -> Self::Type {
-        (*value).get(id)
+        value.0.get(slot_of(value.1, value.2, id).unwrap())
     }
 }
 
@@ -185,33 +559,157 @@ impl<'a, C, D, I> Join for &'a mut Storage<C, D, I>
         D: UnprotectedStorage<C>,
 {
     type Type = &'a mut C;
-    type Value = &'a mut D;
+    type Value = (&'a mut D, &'a [Group], u32);
     type Mask = &'a BitSet;
 
     unsafe fn open(self) -> (Self::Mask, Self::Value) {
-        (&self.bitset, &mut self.data)
+        (&self.bitset, (&mut self.data, &self.groups, self.group_bits))
     }
 
     unsafe fn get(value: &mut Self::Value, id: Index) -> Self::Type {
+        let slot = slot_of(value.1, value.2, id).unwrap();
         // This is horribly unsafe. Unfortunately, Rust doesn't provide a way
         // to abstract mutable/immutable state at the moment, so we have to hack
         // our way through it.
         #[allow(trivial_casts)]
-        let value: *mut Self::Value = value as *mut Self::Value;
-        (*value).get_mut(id)
+        let data: *mut D = value.0 as *mut D;
+        (*data).get_mut(slot)
+    }
+}
+
+// As with specs' storages, joining in parallel only needs the inner storage to be shared between
+// threads, and for mutable joins to give out a distinct component for each id.
+unsafe impl<'a, C, D, I> ParJoin for &'a Storage<C, D, I>
+    where
+        D: UnprotectedStorage<C> + Sync,
+{
+}
+
+unsafe impl<'a, C, D, I> ParJoin for &'a mut Storage<C, D, I>
+    where
+        D: UnprotectedStorage<C> + Send + Sync + DistinctStorage,
+{
+}
+
+/// A storage registered to be persisted, see `Registry`.
+struct Persisted {
+    name: String,
+    save: fn(&Resources) -> bincode::Result<Vec<u8>>,
+    load: fn(&Resources, Option<&[u8]>, u32) -> bincode::Result<()>,
+}
+
+fn save_storage<C, I>(res: &Resources) -> bincode::Result<Vec<u8>>
+    where
+        C: Component + Send + Sync + Serialize,
+        I: Id,
+{
+    bincode::serialize(&*res.fetch::<Storage<C, C::Storage, I>>())
+}
+
+fn load_storage<C, I>(
+    res: &Resources,
+    bytes: Option<&[u8]>,
+    generation: u32,
+) -> bincode::Result<()>
+    where
+        C: Component + Send + Sync + DeserializeOwned,
+        C::Storage: Default,
+        I: Id,
+{
+    let mut loaded = match bytes {
+        Some(bytes) => bincode::deserialize(bytes)?,
+        None => Storage::<C, C::Storage, I>::default(),
+    };
+    for group in &mut loaded.groups {
+        group.generation = generation;
+    }
+    res.fetch_mut::<Storage<C, C::Storage, I>>().replace_with(loaded);
+    Ok(())
+}
+
+fn maintain_storage<C, I>(res: &Resources)
+    where
+        C: Component + Send + Sync,
+        I: Id,
+{
+    res.fetch_mut::<Storage<C, C::Storage, I>>().maintain();
+}
+
+/// The storages registered with `WorldExt`, which are maintained together, and of those the ones
+/// registered with `register_persistent_tile_comp`, which are saved and loaded together, each
+/// under the name it was registered with.
+#[derive(Default)]
+pub struct Registry {
+    storages: Vec<Persisted>,
+    maintained: Vec<(TypeId, fn(&Resources))>,
+}
+
+impl Registry {
+    /// The names of the storages registered, in the order they were registered in.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.storages.iter().map(|storage| storage.name.as_str())
+    }
+
+    /// Serializes each storage registered with bincode, paired with its name.
+    pub fn save(&self, res: &Resources) -> bincode::Result<Vec<(String, Vec<u8>)>> {
+        self.storages
+            .iter()
+            .map(|storage| Ok((storage.name.clone(), (storage.save)(res)?)))
+            .collect()
+    }
+
+    /// Replaces the components of each storage registered with those `saved` under its name by
+    /// `save`, emptying those which weren't saved. Storages saved which are no longer registered
+    /// are skipped. The components are put back at the ids they were saved at, of `generation`
+    /// rather than the generation they were saved in, see `Id::generation`.
+    pub fn load(
+        &self,
+        res: &Resources,
+        saved: &[(String, Vec<u8>)],
+        generation: u32,
+    ) -> bincode::Result<()> {
+        for storage in &self.storages {
+            let bytes = saved
+                .iter()
+                .find(|(name, _)| *name == storage.name)
+                .map(|(_, bytes)| bytes.as_slice());
+            (storage.load)(res, bytes, generation)?;
+        }
+        Ok(())
+    }
+
+    /// Empties each storage registered.
+    pub fn clear(&self, res: &Resources) {
+        self.load(res, &[], 0).expect("Unreachable: nothing is deserialized");
+    }
+
+    /// Maintains each storage registered, see `Storage::maintain`.
+    pub fn maintain(&self, res: &Resources) {
+        for (_, maintain) in &self.maintained {
+            maintain(res);
+        }
     }
 }
 
 /// An extension trait for registering statically managed component storages.
 pub trait WorldExt {
-    /// Registers a `specs_static::Storage` for the components of type `C`.
+    /// Registers a `specs_static::Storage` for the components of type `C`, and records it in the
+    /// `Registry` to be maintained.
     /// This will be done automatically if your storage has a `Default` and you're fetching it with
-    /// `Read` / `Write`.
+    /// `Read` / `Write`, though it won't be maintained then.
     fn register_tile_comp<C, I>(&mut self)
         where
             C: Component + Send + Sync,
             C::Storage: Default,
             I: Id;
+
+    /// Registers a `specs_static::Storage` for the components of type `C` like
+    /// `register_tile_comp`, and records it in the `Registry` to be persisted under `name`.
+    fn register_persistent_tile_comp<C, I>(&mut self, name: &str)
+        where
+            C: Component + Send + Sync + Serialize + DeserializeOwned,
+            C::Storage: Default,
+            I: Id;
 }
 
 impl WorldExt for World {
@@ -222,5 +720,215 @@ impl WorldExt for World {
             I: Id,
     {
         self.add_resource(Storage::<C, C::Storage, I>::default());
+
+        let mut registry = self.res.entry::<Registry>().or_insert_with(Registry::default);
+        let type_id = TypeId::of::<Storage<C, C::Storage, I>>();
+        if registry.maintained.iter().all(|(registered, _)| *registered != type_id) {
+            registry.maintained.push((type_id, maintain_storage::<C, I>));
+        }
+    }
+
+    fn register_persistent_tile_comp<C, I>(&mut self, name: &str)
+        where
+            C: Component + Send + Sync + Serialize + DeserializeOwned,
+            C::Storage: Default,
+            I: Id,
+    {
+        self.register_tile_comp::<C, I>();
+
+        let mut registry = self.res.entry::<Registry>().or_insert_with(Registry::default);
+        registry.storages.retain(|storage| storage.name != name);
+        registry.storages.push(Persisted {
+            name: name.to_string(),
+            save: save_storage::<C, I>,
+            load: load_storage::<C, I>,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    struct TestId(u32);
+    impl Id for TestId {
+        fn from_u32(value: u32) -> Self {
+            TestId(value)
+        }
+
+        fn id(&self) -> u32 {
+            self.0
+        }
+    }
+
+    /// An id in groups of four, with its generation alongside.
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    struct GroupedId(u32, u32);
+    impl Id for GroupedId {
+        const GROUP_BITS: u32 = 2;
+
+        fn from_u32(value: u32) -> Self {
+            GroupedId(value, 0)
+        }
+
+        fn id(&self) -> u32 {
+            self.0
+        }
+
+        fn generation(&self) -> u32 {
+            self.1
+        }
+
+        fn with_generation(value: u32, generation: u32) -> Self {
+            GroupedId(value, generation)
+        }
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Depth(u8);
+    impl Component for Depth {
+        type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+    }
+
+    type DepthStorage = Storage<Depth, <Depth as Component>::Storage, TestId>;
+
+    #[test]
+    fn entries() {
+        let mut depths = DepthStorage::default();
+        depths.insert(TestId(1), Depth(1));
+
+        match depths.entry(TestId(1)) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.insert(Depth(2)), Depth(1));
+                entry.get_mut().0 += 1;
+            }
+            Entry::Vacant(_) => panic!("Tile 1 has a depth"),
+        }
+        assert_eq!(depths.get(TestId(1)), Some(&Depth(3)));
+
+        assert_eq!(depths.entry(TestId(2)).id(), TestId(2));
+        depths.entry(TestId(2)).or_insert(Depth(5)).0 += 1;
+        assert_eq!(depths.entry(TestId(2)).or_insert(Depth(0)), &mut Depth(6));
+        assert_eq!(depths.get_mut_or_default(TestId(3)), &mut Depth(0));
+
+        if let Entry::Occupied(entry) = depths.entry(TestId(1)) {
+            assert_eq!(entry.remove(), Depth(3));
+        }
+        assert_eq!(depths.get(TestId(1)), None);
+        assert_eq!(depths.mask().join().count(), 2);
+    }
+
+    #[test]
+    fn regions() {
+        let mut depths = DepthStorage::default();
+        for id in 0..10 {
+            depths.insert(TestId(id), Depth(id as u8));
+        }
+
+        assert_eq!(depths.clear_region((0..4).map(TestId)), 4);
+        assert_eq!(depths.clear_region((2..6).map(TestId)), 2);
+        assert_eq!(depths.mask().join().collect::<Vec<_>>(), vec![6, 7, 8, 9]);
+
+        let drained = depths.drain_region(vec![TestId(9), TestId(3), TestId(7)]);
+        assert_eq!(drained, vec![(TestId(9), Depth(9)), (TestId(7), Depth(7))]);
+        assert_eq!(depths.get(TestId(6)), Some(&Depth(6)));
+        assert_eq!(depths.get(TestId(7)), None);
+        assert_eq!(depths.mask().join().count(), 2);
+    }
+
+    #[test]
+    fn generations() {
+        let mut depths = Storage::<Depth, <Depth as Component>::Storage, GroupedId>::default();
+        for id in 0..8 {
+            depths.insert(GroupedId(id, 0), Depth(id as u8));
+        }
+
+        // A newer generation empties the group it is inserted into, and only that group
+        assert_eq!(depths.insert(GroupedId(1, 1), Depth(10)), None);
+        assert_eq!(depths.get(GroupedId(1, 1)), Some(&Depth(10)));
+        assert_eq!(depths.get(GroupedId(2, 0)), None);
+        assert_eq!(depths.get(GroupedId(2, 1)), None);
+        assert_eq!(depths.get(GroupedId(5, 0)), Some(&Depth(5)));
+        assert_eq!(depths.ids().count(), 5);
+        assert_eq!(depths.ids().next(), Some(GroupedId(1, 1)));
+
+        // Stale ids find nothing, and are handed back what they insert
+        assert_eq!(depths.get_mut(GroupedId(1, 0)), None);
+        assert_eq!(depths.remove(GroupedId(1, 0)), None);
+        assert_eq!(depths.insert(GroupedId(3, 0), Depth(3)), Some(Depth(3)));
+
+        let copy: Storage<Depth, <Depth as Component>::Storage, GroupedId> =
+            bincode::deserialize(&bincode::serialize(&depths).unwrap()).unwrap();
+        assert_eq!(copy.get(GroupedId(1, 1)), Some(&Depth(10)));
+        assert_eq!(copy.get(GroupedId(1, 0)), None);
+
+        // An emptied group gives its room to another once the storage has been maintained twice,
+        // the events of its slots reading as its own until then
+        assert_eq!(depths.clear_region((4..8).map(|id| GroupedId(id, 0))), 4);
+        depths.insert(GroupedId(9, 0), Depth(9));
+        depths.maintain();
+        depths.insert(GroupedId(13, 0), Depth(13));
+        assert_eq!(depths.id_of_slot(5), Some(GroupedId(5, 0)));
+        depths.maintain();
+        depths.insert(GroupedId(17, 0), Depth(17));
+        assert_eq!(depths.id_of_slot(5), Some(GroupedId(17, 0)));
+        assert_eq!((&depths).join().count(), 4);
+    }
+
+    #[test]
+    fn par_join() {
+        use rayon::prelude::*;
+
+        let mut depths = DepthStorage::default();
+        for id in 0..200 {
+            depths.insert(TestId(id), Depth((id % 10) as u8));
+        }
+
+        (&mut depths).par_join().for_each(|depth| depth.0 += 1);
+        let (count, total) = (depths.mask(), &depths)
+            .par_join()
+            .filter(|(id, _)| id % 2 == 0)
+            .map(|(_, depth)| (1, u32::from(depth.0)))
+            .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+        assert_eq!(count, 100);
+        // The even ids have depths of 1, 3, 5, 7 and 9, each 20 times
+        assert_eq!(total, 500);
+    }
+
+    #[test]
+    fn persist() {
+        let mut world = World::new();
+        world.register_persistent_tile_comp::<Depth, TestId>("depth");
+        let mut reader = world.write_resource::<DepthStorage>().channel_mut().register_reader();
+        {
+            let mut depths = world.write_resource::<DepthStorage>();
+            depths.insert(TestId(3), Depth(1));
+            depths.insert(TestId(70), Depth(4));
+        }
+
+        let saved = world.read_resource::<Registry>().save(&world.res).unwrap();
+        assert_eq!(saved[0].0, "depth");
+        let copy: DepthStorage = bincode::deserialize(&saved[0].1).unwrap();
+        assert_eq!(copy.get(TestId(70)), Some(&Depth(4)));
+        assert_eq!(copy.mask().join().count(), 2);
+
+        {
+            let mut depths = world.write_resource::<DepthStorage>();
+            depths.remove(TestId(3));
+            depths.insert(TestId(5), Depth(9));
+            depths.channel().read(&mut reader).count();
+        }
+        world.read_resource::<Registry>().load(&world.res, &saved, 0).unwrap();
+        let depths = world.read_resource::<DepthStorage>();
+        assert_eq!(depths.get(TestId(3)), Some(&Depth(1)));
+        assert_eq!(depths.get(TestId(5)), None);
+        // The storage was changed in place, its readers seeing the changes
+        assert!(depths.channel().read(&mut reader).count() > 0);
+        drop(depths);
+
+        // Storages not in a save are emptied
+        world.read_resource::<Registry>().clear(&world.res);
+        assert_eq!(world.read_resource::<DepthStorage>().get(TestId(3)), None);
     }
 }
\ No newline at end of file
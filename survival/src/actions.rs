@@ -27,6 +27,21 @@ impl Default for Direction {
         Direction::N
     }
 }
+impl Direction {
+    /// The tile offset of a step this way, with north towards lower `y`.
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::N => (0, -1),
+            Direction::NW => (-1, -1),
+            Direction::NE => (1, -1),
+            Direction::S => (0, 1),
+            Direction::SW => (-1, 1),
+            Direction::SE => (1, 1),
+            Direction::E => (1, 0),
+            Direction::W => (-1, 0),
+        }
+    }
+}
 
 #[derive(Clone, Copy, Debug, PartialEq, strum_macros::Display,)]
 pub enum Target {
@@ -43,6 +58,24 @@ pub enum Action {
     MoveTo,
     Pickup,
     Wait,
+    Equip,
+    Unequip,
+    Craft,
+    Till,
+    Sow,
+    Harvest,
+    Chop,
+    Attack,
+    Mine,
+    Gather,
+    Deconstruct,
+    Haul,
+    Fill,
+    Drink,
+    Pour,
+    Boil,
+    Butcher,
+    Eat,
 }
 impl Default for Action {
     fn default() -> Self {
@@ -50,6 +83,37 @@ impl Default for Action {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionOutcome {
+    Completed,
+    Failed,
+}
+
+/// Published by the system carrying out an `Action` once it finishes, so plans can advance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ActionResult {
+    pub entity: Entity,
+    pub action: Action,
+    pub outcome: ActionOutcome,
+}
+impl ActionResult {
+    pub fn completed(entity: Entity, action: Action) -> Self {
+        Self {
+            entity,
+            action,
+            outcome: ActionOutcome::Completed,
+        }
+    }
+
+    pub fn failed(entity: Entity, action: Action) -> Self {
+        Self {
+            entity,
+            action,
+            outcome: ActionOutcome::Failed,
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct TryAction {
     action: Action,
@@ -75,4 +139,12 @@ pub enum PlayerInputAction {
     MoveRight,
     ZoomIn,
     ZoomOut,
+    ZLevelUp,
+    ZLevelDown,
+    DesignateChop,
+    DesignateMine,
+    DesignateGather,
+    DesignateDeconstruct,
+    EraseDesignation,
+    Select,
 }
@@ -0,0 +1,144 @@
+use amethyst::{
+    assets::{Asset, Handle},
+    ecs::VecStorage,
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::components::TreeKind;
+
+/// A kind of land, by the climate it is found in, the ground it is covered with and what grows
+/// and lives there. Plants and creatures are named by their keys in `plants.ron` and
+/// `creatures.ron`.
+#[derive(Clone, Default, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Biome {
+    pub name: String,
+    /// Ranges of the temperature and moisture maps, from `0.0` to `1.0`, it is found within.
+    pub temperature: (f32, f32),
+    pub moisture: (f32, f32),
+    /// Surface sprites, as indices into the cp437 sheet, and how often each is picked.
+    pub terrain: Vec<(u32, f32)>,
+    /// Trees growing wild, and the share of tiles each grows on.
+    pub trees: Vec<(TreeKind, f32)>,
+    /// Plants growing wild, and the share of tiles each grows on.
+    pub plants: Vec<(String, f32)>,
+    /// Creatures living here, and how many of each there are to a tile.
+    pub creatures: Vec<(String, f32)>,
+}
+impl Biome {
+    pub fn contains(&self, temperature: f32, moisture: f32) -> bool {
+        temperature >= self.temperature.0
+            && temperature <= self.temperature.1
+            && moisture >= self.moisture.0
+            && moisture <= self.moisture.1
+    }
+
+    /// How much of the climate it spans, narrower biomes being picked over those they overlap.
+    pub fn extent(&self) -> f32 {
+        (self.temperature.1 - self.temperature.0) * (self.moisture.1 - self.moisture.0)
+    }
+
+    /// The surface sprite picked by `roll`, from `0.0` to `1.0`, if it has any.
+    pub fn terrain_at(&self, roll: f32) -> Option<u32> {
+        let total = self.terrain.iter().map(|(_, weight)| weight).sum::<f32>();
+        let mut left = roll * total;
+        for (sprite_number, weight) in &self.terrain {
+            if left < *weight {
+                return Some(*sprite_number);
+            }
+            left -= weight;
+        }
+        self.terrain.last().map(|(sprite_number, _)| *sprite_number)
+    }
+}
+
+/// Which of `choices`, each with the share of tiles it is found on, `roll` from `0.0` to `1.0`
+/// lands on, if any.
+pub fn pick<T>(choices: &[(T, f32)], roll: f32) -> Option<&T> {
+    let mut left = roll;
+    for (choice, density) in choices {
+        if left < *density {
+            return Some(choice);
+        }
+        left -= density;
+    }
+    None
+}
+
+/// The narrowest of `biomes` found in a climate of `temperature` and `moisture`.
+pub fn biome_for<'a>(
+    biomes: impl IntoIterator<Item = &'a Biome>,
+    temperature: f32,
+    moisture: f32,
+) -> Option<&'a Biome> {
+    biomes
+        .into_iter()
+        .filter(|biome| biome.contains(temperature, moisture))
+        .min_by(|a, b| {
+            a.extent()
+                .partial_cmp(&b.extent())
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.name.cmp(&b.name))
+        })
+}
+
+impl Asset for Biome {
+    const NAME: &'static str = "survival::Biome";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn biome_definitions() {
+        let file = std::fs::File::open("resources/data/biomes.ron").unwrap();
+        let storage: crate::assets::Storage<Biome> = ron::de::from_reader(file).unwrap();
+
+        let plants = std::fs::File::open("resources/data/plants.ron").unwrap();
+        let plants: crate::assets::Storage<crate::assets::PlantKind> =
+            ron::de::from_reader(plants).unwrap();
+        let creatures = std::fs::File::open("resources/data/creatures.ron").unwrap();
+        let creatures: crate::assets::Storage<crate::assets::CreatureKind> =
+            ron::de::from_reader(creatures).unwrap();
+        for biome in storage.data.values() {
+            assert!(!biome.terrain.is_empty());
+            assert!(biome
+                .plants
+                .iter()
+                .all(|(plant, _)| plants.data.contains_key(plant)));
+            assert!(biome
+                .creatures
+                .iter()
+                .all(|(creature, _)| creatures.data.contains_key(creature)));
+        }
+
+        // Every climate is some biome
+        for temperature in &[0.0, 0.1, 0.5, 0.9, 1.0] {
+            for moisture in &[0.0, 0.2, 0.5, 0.8, 1.0] {
+                assert!(biome_for(storage.data.values(), *temperature, *moisture).is_some());
+            }
+        }
+        let forest = biome_for(storage.data.values(), 0.5, 0.9).unwrap();
+        assert_eq!(forest.name, "Forest");
+    }
+
+    #[test]
+    fn picking() {
+        let biome = Biome {
+            terrain: vec![(34, 3.0), (44, 1.0)],
+            ..Biome::default()
+        };
+        assert_eq!(biome.terrain_at(0.0), Some(34));
+        assert_eq!(biome.terrain_at(0.8), Some(44));
+        assert_eq!(biome.terrain_at(1.0), Some(44));
+        assert_eq!(Biome::default().terrain_at(0.5), None);
+
+        let plants = [("wheat", 0.1), ("potato", 0.05)];
+        assert_eq!(pick(&plants, 0.05), Some(&"wheat"));
+        assert_eq!(pick(&plants, 0.12), Some(&"potato"));
+        assert_eq!(pick(&plants, 0.5), None);
+    }
+}
@@ -0,0 +1,109 @@
+use amethyst::{
+    assets::{Asset, Handle},
+    ecs::VecStorage,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::factions::FactionId;
+use crate::injury::Body;
+
+/// The layout of body parts a creature is built with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BodyPlan {
+    Humanoid,
+    Quadruped,
+}
+impl Default for BodyPlan {
+    fn default() -> Self {
+        BodyPlan::Quadruped
+    }
+}
+impl BodyPlan {
+    pub fn body(self) -> Body {
+        match self {
+            BodyPlan::Humanoid => Body::humanoid(),
+            BodyPlan::Quadruped => Body::quadruped(),
+        }
+    }
+}
+
+/// What drives a creature: the planner, as for colonists, or the instincts of wildlife.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AiProfile {
+    Pawn,
+    Wildlife,
+}
+impl Default for AiProfile {
+    fn default() -> Self {
+        AiProfile::Wildlife
+    }
+}
+
+/// A species of living thing, from which `spawn_creature` builds it. For wildlife it also drives
+/// the behavior of every `Creature` of that kind.
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+pub struct CreatureKind {
+    pub name: String,
+    pub sprite_sheet_number: usize,
+    pub sprite_number: usize,
+    #[serde(default)]
+    pub body: BodyPlan,
+    #[serde(default)]
+    pub ai: AiProfile,
+    /// The faction it is spawned into, if any.
+    #[serde(default)]
+    pub faction: Option<FactionId>,
+
+    /// Body mass, by which what butchering its corpse yields is scaled.
+    pub size: f32,
+    pub sight_range: u32,
+    /// How far from home the creature wanders.
+    pub wander_radius: u32,
+    /// Game time spent grazing between wanderings, or 0 for creatures which don't graze.
+    pub graze_time: u64,
+    /// Flees pawns and predators closer than this, if set.
+    pub flee_distance: Option<u32>,
+    /// Kinds of creature hunted when hungry.
+    pub prey: Vec<String>,
+}
+impl PartialEq for CreatureKind {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+impl CreatureKind {
+    pub fn is_predator(&self) -> bool {
+        !self.prey.is_empty()
+    }
+}
+
+impl Asset for CreatureKind {
+    const NAME: &'static str = "survival::CreatureKind";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creature_definitions() {
+        let file = std::fs::File::open("resources/data/creatures.ron").unwrap();
+        let storage: crate::assets::Storage<CreatureKind> = ron::de::from_reader(file).unwrap();
+
+        let wolf = &storage.data["wolf"];
+        assert!(wolf.is_predator());
+        for prey in &wolf.prey {
+            assert!(storage.data.contains_key(prey));
+        }
+        assert!(storage.data["deer"].flee_distance.is_some());
+        assert_eq!(storage.data["deer"].ai, AiProfile::Wildlife);
+
+        let settler = &storage.data["settler"];
+        assert_eq!(settler.ai, AiProfile::Pawn);
+        assert_eq!(settler.body, BodyPlan::Humanoid);
+        assert!(settler.faction.is_some());
+        assert_eq!(BodyPlan::Quadruped.body().parts.len(), 10);
+    }
+}
@@ -3,17 +3,29 @@ use bitflags::*;
 use amethyst::{
     assets::{Asset, Handle},
     ecs::VecStorage,
+    error::{format_err, Error},
 };
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+use crate::assets::material::{Material, MaterialState};
+use crate::assets::migration::Migration;
+use crate::assets::{Localization, Resolve, Tag};
+
+/// Key of the text naming an item made of a material, filled with the material and the item.
+pub const made_of_key: &str = "item.made_of";
+
+/// Hardness the hammering of tools is measured against, about that of iron.
+pub const reference_impact_yeild: f64 = 500_000.0;
+
 bitflags! {
     #[derive(Serialize, Deserialize)]
     pub struct ItemFlag: u64 {
         const Container = 1;
         const Tool      = 1 << 1;
+        const Liquid    = 1 << 2;
     }
 }
 impl Default for ItemFlag {
@@ -55,6 +67,8 @@ pub enum Property {
     Cooking(OrderedFloat<f32>),
     Boiling(OrderedFloat<f32>),
     Edible,
+    /// A place to butcher corpses at.
+    ButcherSpot,
     None,
 }
 impl Default for Property {
@@ -62,6 +76,28 @@ impl Default for Property {
         Property::None
     }
 }
+impl Property {
+    /// How good the item is at this, for properties which vary.
+    pub fn quality(&self) -> Option<f32> {
+        match self {
+            Property::Chopping(quality)
+            | Property::Cutting(quality)
+            | Property::Hitting(quality)
+            | Property::Hammering(quality)
+            | Property::Cooking(quality)
+            | Property::Boiling(quality) => Some(quality.into_inner()),
+            _ => None,
+        }
+    }
+
+    /// How well an item with this property fights, if it's any use at all.
+    pub fn weapon_quality(&self) -> Option<f32> {
+        match self {
+            Property::Hitting(quality) | Property::Cutting(quality) => Some(quality.into_inner()),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize, strum_macros::EnumString, strum_macros::Display,)]
 pub enum Catagory {
@@ -79,14 +115,17 @@ impl Default for Catagory {
     }
 }
 
+/// An item, as defined in `items.ron`. Fields left out are taken from the item it `extends`,
+/// if any, or left at their defaults.
 #[derive(Clone, Default, Debug, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Details {
     // general information
     pub size: (f32, f32, f32),
     pub weight: f32,
     pub flags: ItemFlag,
 
-    // UI information
+    // UI information, as keys of the text in the `Localization`
     pub name: String,
     pub short_description: String,
     pub long_description: String,
@@ -96,7 +135,303 @@ pub struct Details {
 
     pub properties: Vec<Property>,
     pub interactions: crate::components::InteractionType,
+    /// How the item is worn, if it can be.
+    pub wearable: Option<crate::components::equipment::Wearable>,
+    /// Name of what it is made of, in `Materials`.
+    pub material: Option<String>,
+    /// Key of the item left once this breaks, if anything.
+    pub broken_into: Option<String>,
+    /// What eating it gives, if it can be eaten.
+    pub food: Option<crate::systems::nutrition::Food>,
+    /// Heat it gives off, making it a `HeatSource`, such as a lit hearth.
+    pub heat: Option<f32>,
+    /// Liquid which can be drawn from it without end, such as water from a well.
+    pub liquid_source: Option<String>,
+    /// Game time it keeps for at `reference_spoil_temperature`, for what rots without being
+    /// eaten, such as a corpse.
+    pub shelf_life: Option<u64>,
+    /// Key of the item this is defined as a variation of, which its fields override.
+    pub extends: Option<String>,
+}
+impl Details {
+    pub fn volume(&self) -> f32 {
+        self.size.0 * self.size.1 * self.size.2
+    }
+
+    /// What a container must be able to hold to hold this.
+    pub fn held_as(&self) -> ContainerCanHold {
+        if self.flags.contains(ItemFlag::Liquid) {
+            ContainerCanHold::Liquid
+        } else {
+            ContainerCanHold::Solid
+        }
+    }
+
+    /// Game time it keeps for before rotting away, if it rots at all.
+    pub fn shelf_life(&self) -> Option<u64> {
+        self.food
+            .and_then(|food| food.shelf_life)
+            .or(self.shelf_life)
+    }
+
+    /// What this can hold, if it is a container at all.
+    pub fn can_hold(&self) -> Option<ContainerCanHold> {
+        self.properties.iter().find_map(|property| match property {
+            Property::Container { can_hold } => Some(*can_hold),
+            _ => None,
+        })
+    }
+}
+impl Details {
+    /// This item made of `material` instead, named after it in the language of `locale` and
+    /// weighing what its volume of the material does. Chopping scales with how fine an edge the material holds and
+    /// hammering with how hard it is, while hitting and cutting are left as they are, as blows
+    /// already weigh what they're struck with. Figures the material lacks leave those of the
+    /// item alone.
+    pub fn made_of(&self, material: &Material, locale: &Localization) -> Self {
+        let weight = material
+            .density(&MaterialState::Solid)
+            .map_or(self.weight, |density| {
+                (density * f64::from(self.volume())) as f32
+            });
+        let edge = material
+            .max_edge()
+            .map_or(1.0, |edge| (edge / crate::injury::reference_edge) as f32);
+        let hardness = if material.impact_yeild() > 0.0 {
+            (material.impact_yeild() / reference_impact_yeild) as f32
+        } else {
+            1.0
+        };
+        let properties = self
+            .properties
+            .iter()
+            .map(|property| match property {
+                Property::Chopping(quality) => {
+                    Property::Chopping(OrderedFloat(quality.into_inner() * edge))
+                }
+                Property::Hammering(quality) => {
+                    Property::Hammering(OrderedFloat(quality.into_inner() * hardness))
+                }
+                property => property.clone(),
+            })
+            .collect();
+
+        Self {
+            name: locale.format(made_of_key, &[material.name(), locale.get(&self.name)]),
+            short_description: locale.format(
+                made_of_key,
+                &[material.name(), locale.get(&self.short_description)],
+            ),
+            weight,
+            properties,
+            material: Some(material.name().to_string()),
+            ..self.clone()
+        }
+    }
 }
+impl Details {
+    /// `template` with the fields named in `fields` taken from this instead.
+    fn overriding(&self, template: &Self, fields: &HashSet<String>) -> Self {
+        let mut item = template.clone();
+        for field in fields {
+            match field.as_str() {
+                "size" => item.size = self.size,
+                "weight" => item.weight = self.weight,
+                "flags" => item.flags = self.flags,
+                "name" => item.name = self.name.clone(),
+                "short_description" => item.short_description = self.short_description.clone(),
+                "long_description" => item.long_description = self.long_description.clone(),
+                "catagory" => item.catagory = self.catagory.clone(),
+                "sprite_sheet_number" => item.sprite_sheet_number = self.sprite_sheet_number,
+                "sprite_number" => item.sprite_number = self.sprite_number,
+                "properties" => item.properties = self.properties.clone(),
+                "interactions" => item.interactions = self.interactions,
+                "wearable" => item.wearable = self.wearable,
+                "material" => item.material = self.material.clone(),
+                "broken_into" => item.broken_into = self.broken_into.clone(),
+                "food" => item.food = self.food,
+                "heat" => item.heat = self.heat,
+                "liquid_source" => item.liquid_source = self.liquid_source.clone(),
+                "shelf_life" => item.shelf_life = self.shelf_life,
+                "extends" => item.extends = self.extends.clone(),
+                _ => {}
+            }
+        }
+        item
+    }
+}
+impl Resolve for Details {
+    const MIGRATIONS: &'static [Migration] = migrations;
+
+    /// Items of a pack replace only the fields they spell out in `text` of those they override.
+    fn merge(
+        storage: &mut crate::assets::Storage<Self>,
+        pack: crate::assets::Storage<Self>,
+        text: &str,
+    ) -> Result<(), Error> {
+        let fields: FieldStorage = ron::de::from_str(text)?;
+        for (key, item) in pack.data {
+            let item = match (storage.data.get(&key), fields.data.get(&key)) {
+                (Some(overridden), Some(fields)) => item.overriding(overridden, &fields.0),
+                _ => item,
+            };
+            storage.data.insert(key, item);
+        }
+        Ok(())
+    }
+
+    /// Fills in the fields items extending others leave out, from those of the items they
+    /// extend, by the fields each spells out in any of `texts`.
+    fn resolve(storage: &mut crate::assets::Storage<Self>, texts: &[String]) -> Result<(), Error> {
+        let mut fields = HashMap::<String, Fields>::new();
+        for text in texts {
+            let pack: FieldStorage = ron::de::from_str(text)?;
+            for (key, spelled) in pack.data {
+                fields.entry(key).or_default().0.extend(spelled.0);
+            }
+        }
+        let mut resolved = HashMap::new();
+        for key in storage.data.keys() {
+            let item = inherit(key, &storage.data, &fields, &mut Vec::new())?;
+            resolved.insert(key.clone(), item);
+        }
+        storage.data = resolved;
+        Ok(())
+    }
+
+    /// Tags the items with the kinds of their properties and with their flags.
+    fn index(storage: &mut crate::assets::Storage<Self>) {
+        storage.tags = tags(&storage.data);
+    }
+}
+
+/// The keys of `items` by the kinds of their properties and by their flags, in order.
+fn tags(items: &HashMap<String, Details>) -> HashMap<Tag, Vec<String>> {
+    let flags = (0..64)
+        .map(|bit| ItemFlag::from_bits_truncate(1 << bit))
+        .filter(|flag| !flag.is_empty())
+        .collect::<Vec<_>>();
+    let mut keys = items.keys().collect::<Vec<_>>();
+    keys.sort();
+
+    let mut tags = HashMap::<Tag, Vec<String>>::new();
+    for key in keys {
+        let item = &items[key];
+        for property in &item.properties {
+            let tagged = tags
+                .entry(Tag::Property(std::mem::discriminant(property)))
+                .or_default();
+            // Items can have more than one property of a kind
+            if tagged.last() != Some(key) {
+                tagged.push(key.clone());
+            }
+        }
+        for flag in flags.iter().filter(|flag| item.flags.contains(**flag)) {
+            tags.entry(Tag::Flag(*flag)).or_default().push(key.clone());
+        }
+    }
+    tags
+}
+
+impl crate::assets::Storage<Details> {
+    /// Keys of the items with a property of the kind of `property`, whatever its quality.
+    pub fn find_by_property(&self, property: &Property) -> &[String] {
+        self.tags
+            .get(&Tag::Property(std::mem::discriminant(property)))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Keys of the items with `flag` set, which is looked up one flag at a time.
+    pub fn find_by_flag(&self, flag: ItemFlag) -> &[String] {
+        self.tags
+            .get(&Tag::Flag(flag))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Keys of the items with a property of the kind of `required`, and of at least its quality.
+    pub fn find_satisfying<'a>(&'a self, required: &'a Property) -> impl Iterator<Item = &'a str> {
+        self.find_by_property(required)
+            .iter()
+            .filter(move |key| {
+                self.data[key.as_str()]
+                    .properties
+                    .iter()
+                    .any(|property| crate::assets::recipe::satisfies(property, required))
+            })
+            .map(String::as_str)
+    }
+}
+
+/// The item `key` of `items` with what it extends filled in, `chain` being the keys of the
+/// items extending it on the way there.
+fn inherit(
+    key: &str,
+    items: &HashMap<String, Details>,
+    fields: &HashMap<String, Fields>,
+    chain: &mut Vec<String>,
+) -> Result<Details, Error> {
+    let item = &items[key];
+    let template = match &item.extends {
+        Some(template) => template,
+        None => return Ok(item.clone()),
+    };
+    if chain.iter().any(|extending| extending == key) {
+        return Err(format_err!("{:?} extends itself, through {:?}", key, chain));
+    }
+    if !items.contains_key(template) {
+        return Err(format_err!(
+            "{:?} extends the unknown item {:?}",
+            key,
+            template
+        ));
+    }
+
+    chain.push(key.to_string());
+    let base = inherit(template, items, fields, chain)?;
+    chain.pop();
+    Ok(item.overriding(&base, &fields.get(key).cloned().unwrap_or_default().0))
+}
+
+/// The names of the fields an entry of `items.ron` spells out.
+#[derive(Clone, Debug, Default)]
+struct Fields(HashSet<String>);
+impl<'de> Deserialize<'de> for Fields {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct Visitor;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = Fields;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("an item")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Fields, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut fields = HashSet::new();
+                while let Some(field) = map.next_key::<String>()? {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                    fields.insert(field);
+                }
+                Ok(Fields(fields))
+            }
+        }
+        deserializer.deserialize_struct("Details", &[], Visitor)
+    }
+}
+
+/// The fields spelled out by each entry of `items.ron`.
+#[derive(Deserialize)]
+struct FieldStorage {
+    data: HashMap<String, Fields>,
+}
+
 impl PartialEq for Details {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
@@ -109,6 +444,20 @@ impl Asset for Details {
     type HandleStorage = VecStorage<Handle<Self>>;
 }
 
+/// Changes to the layout of `items.ron`, oldest first.
+pub const migrations: &[Migration] = &[
+    // Files are versioned from here on
+    Migration {
+        version: 1,
+        changes: &[],
+    },
+];
+
+/// The key of the item `item` made of `material`, such as `iron_hand_axe`.
+pub fn material_item_key(item: &str, material: &str) -> String {
+    format!("{}_{}", material.to_lowercase(), item)
+}
+
 #[derive(Clone, Default, Debug, Deserialize, Serialize)]
 pub struct Storage {
     tag: u32,
@@ -164,3 +513,109 @@ pub fn write_test_collection() {
     .unwrap();
     file.write_all(serialized.as_bytes()).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::material::Materials;
+
+    #[test]
+    fn made_of() {
+        let file = std::fs::File::open("resources/data/materials.ron").unwrap();
+        let storage: crate::assets::Storage<Material> = ron::de::from_reader(file).unwrap();
+        let materials = Materials::from_storage(&storage);
+        let iron = materials.get("Iron").unwrap();
+
+        let pick = Details {
+            name: "item.pick.name".to_string(),
+            size: (0.5, 0.1, 0.1),
+            weight: 2.0,
+            properties: vec![
+                Property::Chopping(OrderedFloat(1.0)),
+                Property::Hammering(OrderedFloat(1.0)),
+                Property::Hitting(OrderedFloat(1.0)),
+            ],
+            ..Details::default()
+        };
+        let mut locale = Localization::default();
+        locale
+            .strings
+            .insert(made_of_key.to_string(), "{} {}".to_string());
+        locale
+            .strings
+            .insert("item.pick.name".to_string(), "Pick".to_string());
+        let iron_pick = pick.made_of(iron, &locale);
+        assert_eq!(iron_pick.name, "Iron Pick");
+        assert_eq!(iron_pick.material, Some("Iron".to_string()));
+        assert!(iron_pick.properties[1].quality().unwrap() > 1.0);
+        assert_eq!(
+            iron_pick.properties[2],
+            Property::Hitting(OrderedFloat(1.0))
+        );
+        assert!((iron_pick.weight - 7874.0 * pick.volume()).abs() < 0.01);
+
+        // Nothing is known of basalt but its name
+        let basalt_pick = pick.made_of(materials.get("Basalt").unwrap(), &locale);
+        assert_eq!(basalt_pick.name, "Basalt Pick");
+        assert_eq!(basalt_pick.weight, pick.weight);
+        assert_eq!(basalt_pick.properties, pick.properties);
+
+        assert_eq!(material_item_key("hand_axe", "Iron"), "iron_hand_axe");
+    }
+
+    #[test]
+    fn extends() {
+        let text = r#"(
+            data: {
+                "tool": (weight: 2, properties: [Hitting(1)], material: Some("Iron")),
+                "pick": (extends: Some("tool"), name: "Pick", properties: [Hammering(2)]),
+                "stone_pick": (extends: Some("pick"), name: "Stone Pick", material: None),
+            },
+        )"#;
+        let mut storage: crate::assets::Storage<Details> = ron::de::from_str(text).unwrap();
+        Details::resolve(&mut storage, &[text.to_string()]).unwrap();
+
+        let pick = &storage.data["pick"];
+        assert_eq!(pick.name, "Pick");
+        assert_eq!(pick.weight, 2.0);
+        assert_eq!(
+            pick.properties,
+            vec![Property::Hammering(OrderedFloat(2.0))]
+        );
+        assert_eq!(pick.material, Some("Iron".to_string()));
+
+        // Overriding a field with its default still overrides it
+        let stone_pick = &storage.data["stone_pick"];
+        assert_eq!(stone_pick.name, "Stone Pick");
+        assert_eq!(stone_pick.properties, pick.properties);
+        assert_eq!(stone_pick.material, None);
+
+        let cycle = r#"(data: {"a": (extends: Some("b")), "b": (extends: Some("a"))})"#;
+        let mut storage: crate::assets::Storage<Details> = ron::de::from_str(cycle).unwrap();
+        assert!(Details::resolve(&mut storage, &[cycle.to_string()]).is_err());
+    }
+
+    #[test]
+    fn tags() {
+        let text = r#"(
+            data: {
+                "axe": (weight: 2, flags: (bits: 2), properties: [Chopping(2), Chopping(1)]),
+                "hatchet": (weight: 1, flags: (bits: 2), properties: [Chopping(1), Hitting(1)]),
+                "bucket": (weight: 1, flags: (bits: 1)),
+            },
+        )"#;
+        let mut storage: crate::assets::Storage<Details> = ron::de::from_str(text).unwrap();
+        Details::index(&mut storage);
+
+        let chopping = Property::Chopping(OrderedFloat(1.5));
+        assert_eq!(storage.find_by_property(&chopping), ["axe", "hatchet"]);
+        assert_eq!(
+            storage.find_satisfying(&chopping).collect::<Vec<_>>(),
+            vec!["axe"]
+        );
+        assert!(storage.find_by_property(&Property::Edible).is_empty());
+        assert_eq!(storage.find_by_flag(ItemFlag::Tool), ["axe", "hatchet"]);
+        assert_eq!(storage.find_by_flag(ItemFlag::Container), ["bucket"]);
+        assert!(storage.find_by_flag(ItemFlag::Liquid).is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+use amethyst::error::{format_err, Error, ResultExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The text of the game in one language, as read from `resources/locale/<language>.ron`, by the
+/// keys the data and the UI give in its place. Keys with no text show as they are, so text can
+/// still be given as is where no key is wanted, such as in names made up in game.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Localization {
+    /// Name of the language, in itself.
+    pub language: String,
+    pub strings: HashMap<String, String>,
+}
+impl Localization {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|_| format_err!("Failed to open file {:?}", path))?;
+        Ok(ron::de::from_str(&text)?)
+    }
+
+    /// The file the text of `language` is read from, within `directory`.
+    pub fn path(directory: &Path, language: &str) -> PathBuf {
+        directory.join(format!("{}.ron", language))
+    }
+
+    /// The text of `key`, if it has any.
+    pub fn lookup(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(String::as_str)
+    }
+
+    /// The text of `key`, or the key itself if it has none.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.lookup(key).unwrap_or(key)
+    }
+
+    /// The text of `key` with each `{}` in it filled with `args` in turn.
+    pub fn format(&self, key: &str, args: &[&str]) -> String {
+        let mut pieces = self.get(key).split("{}");
+        let mut text = pieces.next().unwrap_or_default().to_string();
+        let mut args = args.iter();
+        for piece in pieces {
+            text += args.next().unwrap_or(&"");
+            text += piece;
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::Resolve;
+
+    #[test]
+    fn english() {
+        let locale =
+            Localization::load(&Localization::path(Path::new("resources/locale"), "en")).unwrap();
+
+        // Every item has all of its text
+        let text = std::fs::read_to_string("resources/data/items.ron").unwrap();
+        let mut items: crate::assets::Storage<crate::assets::Item> =
+            ron::de::from_str(&text).unwrap();
+        crate::assets::Item::resolve(&mut items, &[text]).unwrap();
+        for item in items.data.values() {
+            assert!(locale.lookup(&item.name).is_some());
+            assert!(locale.lookup(&item.short_description).is_some());
+            assert!(locale.lookup(&item.long_description).is_some());
+        }
+
+        assert_eq!(locale.get("item.log.name"), "Log");
+        assert_eq!(locale.get("Made up"), "Made up");
+        assert_eq!(
+            locale.format("ui.tile.position", &["1", "2", "3"]),
+            "Tile (1, 2, 3)"
+        );
+        assert_eq!(locale.format("{} and {}", &["this"]), "this and ");
+    }
+}
@@ -1,5 +1,12 @@
+use amethyst::{
+    assets::{Asset, Handle},
+    ecs::VecStorage,
+};
 use std::collections::HashMap;
 
+use crate::assets::migration::Migration;
+use crate::assets::Storage;
+
 #[derive(
     strum_macros::EnumString,
     Debug,
@@ -83,6 +90,96 @@ pub struct Material {
     ignite_point: Option<f64>,
 
     densities: HashMap<MaterialState, f64>,
+
+    /// Rusts or rots when wet.
+    #[serde(default)]
+    corrodes: bool,
+}
+impl Material {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn corrodes(&self) -> bool {
+        self.corrodes
+    }
+
+    /// Weight per unit of volume in `state`, if known.
+    pub fn density(&self, state: &MaterialState) -> Option<f64> {
+        self.densities.get(state).cloned()
+    }
+
+    /// Force it takes to dent.
+    pub fn impact_yeild(&self) -> f64 {
+        self.impact_yeild
+    }
+
+    /// Force it takes to shatter.
+    pub fn impact_fracture(&self) -> f64 {
+        self.impact_fracture
+    }
+
+    /// Force it takes to tear apart, which keeps an edge from chipping.
+    pub fn tensile_fracture(&self) -> f64 {
+        self.tensile_fracture
+    }
+
+    /// Sharpest edge it holds, if any.
+    pub fn max_edge(&self) -> Option<f64> {
+        self.max_edge
+    }
+
+    /// Hit points of things made of this, from how much force it takes to deform and break it.
+    pub fn durability(&self) -> f32 {
+        let strength = self.impact_yeild
+            + self.impact_fracture
+            + self.bend_yeild
+            + self.bend_fracture
+            + self.tensile_fracture;
+        ((strength / durability_scale) as f32).max(min_durability)
+    }
+}
+
+impl Asset for Material {
+    const NAME: &'static str = "survival::Material";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+/// Changes to the layout of `materials.ron`, oldest first.
+pub const migrations: &[Migration] = &[
+    // Files are versioned from here on
+    Migration {
+        version: 1,
+        changes: &[],
+    },
+];
+
+/// Material strength for each hit point.
+pub const durability_scale: f64 = 10_000.0;
+/// Hit points of the weakest materials.
+pub const min_durability: f32 = 1.0;
+
+/// Every material, by name, copied out of the `MaterialStorage` for systems to read without
+/// locking it.
+#[derive(Clone, Default, Debug)]
+pub struct Materials {
+    materials: HashMap<String, Material>,
+}
+impl Materials {
+    /// The materials of `storage`, by their names rather than the keys of its database.
+    pub fn from_storage(storage: &Storage<Material>) -> Self {
+        let materials = storage
+            .data
+            .values()
+            .map(|material| (material.name.clone(), material.clone()))
+            .collect();
+        Self { materials }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Material> {
+        self.materials.get(name)
+    }
 }
 
 
@@ -123,4 +220,29 @@ mod tests {
         .unwrap();
         println!("{}", serialized);
     }
+
+    #[test]
+    fn durability() {
+        let file = std::fs::File::open("resources/data/materials.ron").unwrap();
+        let storage: Storage<Material> = ron::de::from_reader(file).unwrap();
+        let materials = Materials::from_storage(&storage);
+
+        let water = materials.get("Water").unwrap();
+        assert_eq!(water.density(&MaterialState::Liquid), Some(1000.0));
+        assert_eq!(water.density(&MaterialState::Solid), None);
+
+        let iron = materials.get("Iron").unwrap();
+        assert!(iron.corrodes());
+        assert!(iron.durability() > min_durability);
+        assert!(!materials.get("Basalt").unwrap().corrodes());
+        assert_eq!(Material::default().durability(), min_durability);
+
+        // Breaks once, when worn through
+        let mut status = crate::components::MaterialStatus::from_material(iron);
+        assert!(!status.damage(1.0));
+        assert!(status.condition() < 1.0);
+        assert!(status.damage(iron.durability()));
+        assert!(status.is_broken());
+        assert!(!status.damage(1.0));
+    }
 }
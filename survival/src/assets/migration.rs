@@ -0,0 +1,177 @@
+use amethyst::error::{format_err, Error};
+use std::fmt;
+
+/// Name of the field giving the version of the layout of a database file.
+pub const version_field: &str = "schema_version";
+
+/// One change to the layout of a database file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A field or enum variant renamed, replaced wherever it appears outside of strings.
+    Rename {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// The whole file moved into a field of a struct, as `(field: <file>)`.
+    Wrap { field: &'static str },
+}
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Rename { from, to } => write!(f, "`{}` is now `{}`", from, to),
+            Change::Wrap { field } => write!(f, "the file is now the `{}` of a struct", field),
+        }
+    }
+}
+
+/// The changes bringing a database file from the version before to `version`. The migrations of
+/// a database are listed oldest first, the last giving the version its files are written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Migration {
+    pub version: u32,
+    pub changes: &'static [Change],
+}
+
+/// The schema version the files of a database with `migrations` are written in.
+pub fn current(migrations: &[Migration]) -> u32 {
+    migrations.last().map_or(0, |migration| migration.version)
+}
+
+/// The `schema_version` of the file `text`, files from before there were versions being 0. The
+/// version is found in the text rather than by parsing it, as the file may not parse until
+/// migrated.
+pub fn version(text: &str) -> u32 {
+    let at = match text.find(version_field) {
+        Some(at) => at + version_field.len(),
+        None => return 0,
+    };
+    let rest = text[at..].trim_start();
+    if !rest.starts_with(':') {
+        return 0;
+    }
+    let digits = rest[1..]
+        .trim_start()
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>();
+    digits.parse().unwrap_or(0)
+}
+
+/// The migrations the file `text` is yet to go through.
+pub fn pending<'a>(text: &str, migrations: &'a [Migration]) -> &'a [Migration] {
+    let version = version(text);
+    let start = migrations
+        .iter()
+        .position(|migration| migration.version > version)
+        .unwrap_or_else(|| migrations.len());
+    &migrations[start..]
+}
+
+/// The file `text` upgraded to the current version of `migrations`, failing if it was written
+/// for a newer version than that.
+pub fn migrate(text: &str, migrations: &[Migration]) -> Result<String, Error> {
+    let version = version(text);
+    if version > current(migrations) {
+        return Err(format_err!(
+            "Schema version {} is newer than {}, the latest known",
+            version,
+            current(migrations)
+        ));
+    }
+
+    let mut migrated = text.to_string();
+    for migration in pending(text, migrations) {
+        for change in migration.changes {
+            migrated = match change {
+                Change::Rename { from, to } => rename(&migrated, from, to),
+                Change::Wrap { field } => format!("({}: {})", field, migrated),
+            };
+        }
+    }
+    Ok(migrated)
+}
+
+/// `text` with each identifier `from` outside of strings replaced by `to`.
+fn rename(text: &str, from: &str, to: &str) -> String {
+    let mut renamed = String::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c == '"' {
+            renamed.push(c);
+            let mut escaped = false;
+            for (_, c) in &mut chars {
+                renamed.push(c);
+                if c == '"' && !escaped {
+                    break;
+                }
+                escaped = c == '\\' && !escaped;
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = start + c.len_utf8();
+            while let Some(&(index, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = index + c.len_utf8();
+                chars.next();
+            }
+            let identifier = &text[start..end];
+            renamed.push_str(if identifier == from { to } else { identifier });
+        } else {
+            renamed.push(c);
+        }
+    }
+    renamed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const migrations: &[Migration] = &[
+        Migration {
+            version: 1,
+            changes: &[Change::Wrap { field: "entries" }],
+        },
+        Migration {
+            version: 2,
+            changes: &[
+                Change::Rename {
+                    from: "colour",
+                    to: "color",
+                },
+                Change::Rename {
+                    from: "Grey",
+                    to: "Gray",
+                },
+            ],
+        },
+    ];
+
+    #[test]
+    fn upgrades() {
+        assert_eq!(current(migrations), 2);
+        assert_eq!(current(&[]), 0);
+
+        let old = r#"[(name: "colour Grey", colour: Grey, colours: [Grey])]"#;
+        assert_eq!(version(old), 0);
+        assert_eq!(pending(old, migrations).len(), 2);
+        assert_eq!(
+            migrate(old, migrations).unwrap(),
+            r#"(entries: [(name: "colour Grey", color: Gray, colours: [Gray])])"#
+        );
+
+        let renamed = r#"(schema_version: 1, entries: [(colour: Some(Grey))])"#;
+        assert_eq!(version(renamed), 1);
+        assert_eq!(
+            migrate(renamed, migrations).unwrap(),
+            r#"(schema_version: 1, entries: [(color: Some(Gray))])"#
+        );
+
+        let latest = r#"(schema_version: 2, entries: [(colour: "\"colour\"")])"#;
+        assert!(pending(latest, migrations).is_empty());
+        assert_eq!(migrate(latest, migrations).unwrap(), latest);
+
+        assert!(migrate("(schema_version: 3, entries: [])", migrations).is_err());
+    }
+}
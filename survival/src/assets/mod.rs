@@ -1,6 +1,15 @@
+pub mod biome;
 pub mod body;
+pub mod creature;
 pub mod item;
+pub mod locale;
 pub mod material;
+pub mod migration;
+pub mod pack;
+pub mod plant;
+pub mod recipe;
+pub mod validation;
+pub mod workshop;
 
 pub mod loader;
 #[allow(unused_imports)]
@@ -12,20 +21,123 @@ use amethyst::{
     error::{format_err, Error, ResultExt},
 };
 use std::collections::HashMap;
-use std::fs::File;
+use std::mem::Discriminant;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
 
 pub type StorageWrapper<T> = Arc<RwLock<Storage<T>>>;
 
 pub use item::Details as Item;
 pub type ItemStorage = StorageWrapper<Item>;
 
+pub use creature::CreatureKind;
+pub type CreatureStorage = StorageWrapper<CreatureKind>;
+
+pub use recipe::Recipe;
+pub type RecipeStorage = StorageWrapper<Recipe>;
+
+pub use plant::PlantKind;
+pub type PlantStorage = StorageWrapper<PlantKind>;
+
+pub use material::Material;
+pub type MaterialStorage = StorageWrapper<Material>;
+
+pub use biome::Biome;
+pub type BiomeStorage = StorageWrapper<Biome>;
+
+pub use locale::Localization;
+
+pub use pack::Packs;
+
+pub use workshop::Workshop;
+pub type WorkshopStorage = StorageWrapper<Workshop>;
+
+/// Finishes the entries of a storage once read, from the text of its files, such as by filling
+/// in items from those they extend.
+pub trait Resolve: Sized {
+    /// Upgrades of the layout of the files of the storage, older files going through those
+    /// newer than their `schema_version` before they're read.
+    const MIGRATIONS: &'static [migration::Migration] = &[];
+
+    /// Lays the entries of the next data pack, read from `text`, over those of the packs before
+    /// it, replacing those with the same key.
+    fn merge(storage: &mut Storage<Self>, pack: Storage<Self>, _text: &str) -> Result<(), Error> {
+        storage.data.extend(pack.data);
+        Ok(())
+    }
+
+    fn resolve(_storage: &mut Storage<Self>, _texts: &[String]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Rebuilds the `tags` of a storage once its entries change.
+    fn index(_storage: &mut Storage<Self>) {}
+}
+impl Resolve for CreatureKind {}
+impl Resolve for Recipe {}
+impl Resolve for PlantKind {}
+impl Resolve for Material {
+    const MIGRATIONS: &'static [migration::Migration] = material::migrations;
+}
+impl Resolve for Biome {}
+impl Resolve for Workshop {}
+
+/// What entries of a storage are looked up by, see `Storage::tags`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Tag {
+    /// Having a property of this kind, whatever its quality.
+    Property(Discriminant<item::Property>),
+    Flag(item::ItemFlag),
+}
+
 #[derive(Default, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Storage<T> {
+    /// Version of the layout of the file read, see `Resolve::MIGRATIONS`.
+    #[serde(default)]
+    pub schema_version: u32,
     pub data: HashMap<String, T>,
     #[serde(skip_serializing, skip_deserializing)]
     pub handles: HashMap<String, Handle<T>>,
+    /// Keys of the entries with each tag, in order, for finding them without going through every
+    /// entry. Kept by `Resolve::index` as entries are read and added.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub tags: HashMap<Tag, Vec<String>>,
+    /// Times the file of the storage has been read again since it was loaded.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub generation: u32,
+}
+impl<T> Storage<T>
+where
+    T: Send + Sync + Asset,
+    <T as Asset>::Data: for<'a> serde::Deserialize<'a>,
+{
+    /// Loads a handle for each entry without one yet, from the source `T::NAME`.
+    pub fn load_handles(&mut self, loader: &Loader, asset_storage: &AssetStorage<T>) {
+        let keys = self
+            .data
+            .keys()
+            .filter(|key| !self.handles.contains_key(*key))
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in keys {
+            let handle = loader.load_from(
+                key.as_str(),
+                amethyst::assets::RonFormat,
+                (),
+                T::NAME,
+                (),
+                asset_storage,
+            );
+            self.handles.insert(key, handle);
+        }
+    }
+}
+
+/// Sent once the file of the storage of the asset `T::NAME` has been read again, for what was
+/// made from its entries to be made again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageReloaded {
+    pub asset: &'static str,
 }
 
 pub trait GetStorage<T> {
@@ -37,20 +149,32 @@ impl<T> GetStorage<T> for Arc<RwLock<Storage<T>>> {
     }
 }
 
+/// Serves the entries of a storage to the `Loader`, reading its files again for them once one
+/// changes, which hot reloading asks for as the strategy of the `HotReloadBundle` has it.
+/// Entries are updated and added, never removed, as entities may still refer to them.
 pub struct StorageSource<T> {
     storage: Arc<RwLock<Storage<T>>>,
-    source: PathBuf,
+    /// The file of the storage in each data pack having one, in load order.
+    sources: Vec<PathBuf>,
+    /// Latest modification time of the files when they were last read.
+    read_at: Mutex<u64>,
 }
 impl<T> StorageSource<T>
 where
-    T: for<'a> serde::Deserialize<'a> + serde::Serialize + Send + Sync + Asset + Sized + Default,
+    T: for<'a> serde::Deserialize<'a>
+        + serde::Serialize
+        + Send
+        + Sync
+        + Asset
+        + Sized
+        + Default
+        + Resolve,
     <T as Asset>::Data: for<'a> serde::Deserialize<'a>,
 {
-    pub fn apply(source: &Path, world: &mut World) -> Result<Arc<RwLock<Storage<T>>>, Error> {
-        let file = File::open(&source)
-            .with_context(|_| format_err!("Failed to open file {:?}", source))?;
-
-        let storage: Arc<RwLock<Storage<T>>> = Arc::new(RwLock::new(ron::de::from_reader(file)?));
+    /// Loads the storage from `sources`, the later files overriding the earlier, as given by
+    /// `Packs::files`.
+    pub fn apply(sources: &[PathBuf], world: &mut World) -> Result<Arc<RwLock<Storage<T>>>, Error> {
+        let storage: Arc<RwLock<Storage<T>>> = Arc::new(RwLock::new(read_storage(sources)?));
 
         {
             world.add_resource(AssetStorage::<T>::default());
@@ -58,57 +182,80 @@ where
             let asset_storage = world.read_resource::<AssetStorage<T>>();
 
             // Start loading all our own assets..lol
-            // TODO: This method prevents us from dynamically loading NEW items
-            // As the handles will stay the same, but we cant add actual new entires because of the clone...we'd have to wrap in
-            // RwLock Instead...?
             let copy = Self {
                 storage: storage.clone(),
-                source: source.to_path_buf(),
+                sources: sources.to_vec(),
+                read_at: Mutex::new(files_modified(sources)?),
             };
-            loader.add_source("items", copy);
-
-            println!("enter");
-            {
-                let mut borrow = storage.write().unwrap();
-                let keys = borrow.data.keys().map(|k| k.clone()).collect::<Vec<_>>();
-                for key in &keys {
-                    let handle = loader.load_from(
-                        key.as_str(),
-                        amethyst::assets::RonFormat,
-                        (),
-                        "items",
-                        (),
-                        &asset_storage,
-                    );
-                    println!("Loading: {} -> {:?}", key, handle);
-                    borrow.handles.insert(key.to_string(), handle);
-                }
-            }
+            loader.add_source(T::NAME, copy);
+
+            storage
+                .write()
+                .unwrap()
+                .load_handles(&loader, &asset_storage);
         }
         world.add_resource(storage.clone());
 
         Ok(storage)
     }
 }
+impl<T> StorageSource<T>
+where
+    T: Send + Sync + Asset + Resolve,
+    <T as Asset>::Data: for<'a> serde::Deserialize<'a>,
+{
+    /// Adds `value` to the storage `apply` loaded under `key`, and loads a handle to it from
+    /// there, as for those read from its file.
+    pub fn insert(world: &World, key: &str, value: T) -> Handle<T> {
+        let storage = world.read_resource::<StorageWrapper<T>>();
+        let loader = world.read_resource::<Loader>();
+        let asset_storage = world.read_resource::<AssetStorage<T>>();
+
+        let mut borrow = storage.write().unwrap();
+        borrow.data.insert(key.to_string(), value);
+        T::index(&mut borrow);
+        borrow.load_handles(&loader, &asset_storage);
+        borrow.handles[key].clone()
+    }
+}
+impl<T> StorageSource<T>
+where
+    T: serde::de::DeserializeOwned + Resolve,
+{
+    /// Reads the files into the storage again if any changed since they were last read.
+    fn refresh(&self) -> Result<(), Error> {
+        let mut read_at = self.read_at.lock().unwrap();
+        let modified = files_modified(&self.sources)?;
+        if modified == *read_at {
+            return Ok(());
+        }
+
+        let fresh: Storage<T> = read_storage(&self.sources)?;
+        let mut storage = self.storage.write().unwrap();
+        storage.data.extend(fresh.data);
+        T::index(&mut storage);
+        storage.generation += 1;
+        *read_at = modified;
+        Ok(())
+    }
+}
 impl<T> Source for StorageSource<T>
 where
-    T: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + Asset + Sized + Default,
+    T: serde::Serialize
+        + serde::de::DeserializeOwned
+        + Send
+        + Sync
+        + Asset
+        + Sized
+        + Default
+        + Resolve,
 {
-    fn modified(&self, path: &str) -> Result<u64, Error> {
-        use std::fs::metadata;
-
-        metadata(&self.source)
-            .with_context(|_| format_err!("Failed to fetch metadata for {:?}", path))?
-            .modified()
-            .with_context(|_| format_err!("Could not get modification time"))?
-            .duration_since(std::time::UNIX_EPOCH)
-            .with_context(|_| {
-                format_err!("Anomalies with the system clock caused `duration_since` to fail")
-            })
-            .map(|d| d.as_secs())
+    fn modified(&self, _path: &str) -> Result<u64, Error> {
+        files_modified(&self.sources)
     }
 
     fn load(&self, path: &str) -> Result<Vec<u8>, Error> {
+        self.refresh()?;
         let borrow = self.storage.borrow();
         let data = borrow
             .data
@@ -124,3 +271,53 @@ where
         Ok((b, m))
     }
 }
+
+/// Reads the storage in the files at `paths`, migrating each to the current schema and merging it
+/// over those before it, and resolves its entries.
+pub(crate) fn read_storage<T>(paths: &[PathBuf]) -> Result<Storage<T>, Error>
+where
+    T: serde::de::DeserializeOwned + Resolve,
+{
+    let mut storage = Storage {
+        schema_version: migration::current(T::MIGRATIONS),
+        data: HashMap::new(),
+        handles: HashMap::new(),
+        tags: HashMap::new(),
+        generation: 0,
+    };
+    let mut texts = Vec::with_capacity(paths.len());
+    for path in paths {
+        let text = std::fs::read_to_string(path)
+            .with_context(|_| format_err!("Failed to open file {:?}", path))?;
+        let text = migration::migrate(&text, T::MIGRATIONS)
+            .map_err(|e| format_err!("Failed to migrate file {:?}: {}", path, e))?;
+        let pack: Storage<T> = ron::de::from_str(&text)?;
+        T::merge(&mut storage, pack, &text)?;
+        texts.push(text);
+    }
+    T::resolve(&mut storage, &texts)?;
+    T::index(&mut storage);
+    Ok(storage)
+}
+
+/// Latest modification time of the files at `paths`, in seconds since the epoch.
+fn files_modified(paths: &[PathBuf]) -> Result<u64, Error> {
+    let mut latest = 0;
+    for path in paths {
+        latest = latest.max(file_modified(path)?);
+    }
+    Ok(latest)
+}
+
+/// Modification time of the file at `path`, in seconds since the epoch.
+fn file_modified(path: &Path) -> Result<u64, Error> {
+    std::fs::metadata(path)
+        .with_context(|_| format_err!("Failed to fetch metadata for {:?}", path))?
+        .modified()
+        .with_context(|_| format_err!("Could not get modification time"))?
+        .duration_since(std::time::UNIX_EPOCH)
+        .with_context(|_| {
+            format_err!("Anomalies with the system clock caused `duration_since` to fail")
+        })
+        .map(|d| d.as_secs())
+}
@@ -0,0 +1,175 @@
+use amethyst::error::{format_err, Error, ResultExt};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::settings::PackSettings;
+
+/// Name of the file describing a data pack, at the top of its directory.
+pub const manifest_file: &str = "pack.ron";
+
+/// What a data pack is, as read from its `pack.ron`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// Names of the packs this one builds on, which have to be loaded before it.
+    #[serde(default)]
+    pub requires: Vec<String>,
+}
+
+/// A directory of databases, laid over those of the packs loaded before it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pack {
+    pub directory: PathBuf,
+    pub manifest: Manifest,
+}
+impl Pack {
+    pub fn read(directory: &Path) -> Result<Self, Error> {
+        let path = directory.join(manifest_file);
+        let text = std::fs::read_to_string(&path)
+            .with_context(|_| format_err!("Failed to open file {:?}", path))?;
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            manifest: ron::de::from_str(&text)?,
+        })
+    }
+}
+
+/// The data packs loaded, the base game first and then the mods in their load order. Entries of
+/// a database in a later pack replace those with the same key in the packs before it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Packs {
+    pub packs: Vec<Pack>,
+}
+impl Packs {
+    /// The base pack and the mods `settings` lists, failing if a pack is missing its manifest or
+    /// comes before a pack it requires.
+    pub fn load(settings: &PackSettings) -> Result<Self, Error> {
+        let mut packs = vec![Pack::read(Path::new(&settings.base))?];
+        for name in &settings.load_order {
+            packs.push(Pack::read(&Path::new(&settings.directory).join(name))?);
+        }
+
+        for (index, pack) in packs.iter().enumerate() {
+            for required in &pack.manifest.requires {
+                if !packs[..index]
+                    .iter()
+                    .any(|loaded| loaded.manifest.name == *required)
+                {
+                    return Err(format_err!(
+                        "{:?} requires {:?}, which isn't loaded before it",
+                        pack.manifest.name,
+                        required
+                    ));
+                }
+            }
+        }
+        Ok(Self { packs })
+    }
+
+    /// The database `file` of each pack having one, in load order. That of the base pack is
+    /// always given, for it not being there to fail loading.
+    pub fn files(&self, file: &str) -> Vec<PathBuf> {
+        self.packs
+            .iter()
+            .enumerate()
+            .map(|(index, pack)| (index, pack.directory.join(file)))
+            .filter(|(index, path)| *index == 0 || path.exists())
+            .map(|(_, path)| path)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::{read_storage, Item};
+
+    /// Writes a pack named `name` with the databases `files` under the temporary directory.
+    fn write_pack(name: &str, requires: &[&str], files: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir()
+            .join(format!("survival-packs-{}", std::process::id()))
+            .join(name);
+        std::fs::create_dir_all(&directory).unwrap();
+        let manifest = Manifest {
+            name: name.to_string(),
+            version: "1.0".to_string(),
+            description: String::new(),
+            requires: requires.iter().map(|name| name.to_string()).collect(),
+        };
+        std::fs::write(
+            directory.join(manifest_file),
+            ron::ser::to_string(&manifest).unwrap(),
+        )
+        .unwrap();
+        for (file, text) in files {
+            std::fs::write(directory.join(file), text).unwrap();
+        }
+        directory
+    }
+
+    #[test]
+    fn packs() {
+        let base = write_pack(
+            "base",
+            &[],
+            &[
+                (
+                    "items.ron",
+                    r#"(data: {
+                        "tool": (weight: 2, properties: [Hitting(1)]),
+                        "pick": (extends: Some("tool"), name: "Pick", weight: 3),
+                        "log": (name: "Log", weight: 20),
+                    })"#,
+                ),
+                ("materials.ron", "(data: {})"),
+            ],
+        );
+        let heavy = write_pack(
+            "heavy",
+            &["base"],
+            &[(
+                "items.ron",
+                r#"(data: {
+                    "tool": (weight: 5),
+                    "pick": (name: "Heavy Pick"),
+                    "stone": (name: "Stone", weight: 8),
+                })"#,
+            )],
+        );
+        let settings = PackSettings {
+            base: base.to_string_lossy().into_owned(),
+            directory: heavy.parent().unwrap().to_string_lossy().into_owned(),
+            load_order: vec!["heavy".to_string()],
+        };
+        let packs = Packs::load(&settings).unwrap();
+        assert_eq!(packs.packs[1].manifest.requires, vec!["base".to_string()]);
+
+        // Only the base pack has materials
+        assert_eq!(
+            packs.files("materials.ron"),
+            vec![base.join("materials.ron")]
+        );
+
+        let items = read_storage::<Item>(&packs.files("items.ron")).unwrap();
+        // Entries are added, and those overridden keep the fields the mod leaves out
+        assert_eq!(items.data["stone"].weight, 8.0);
+        assert_eq!(items.data["log"].weight, 20.0);
+        let pick = &items.data["pick"];
+        assert_eq!(pick.name, "Heavy Pick");
+        assert_eq!(pick.weight, 3.0);
+        assert_eq!(pick.extends, Some("tool".to_string()));
+        assert_eq!(pick.properties, items.data["tool"].properties);
+        assert_eq!(items.data["tool"].weight, 5.0);
+
+        // A pack can't come before one it requires
+        write_pack("early", &["heavy"], &[]);
+        let early = PackSettings {
+            load_order: vec!["early".to_string(), "heavy".to_string()],
+            ..settings
+        };
+        assert!(Packs::load(&early).is_err());
+    }
+}
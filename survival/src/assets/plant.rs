@@ -0,0 +1,120 @@
+use amethyst::{assets::Asset, assets::Handle, ecs::VecStorage};
+use serde::{Deserialize, Serialize};
+
+/// A step in the life of a plant, reached once it has grown enough.
+#[derive(Clone, Default, Debug, PartialEq, Deserialize, Serialize)]
+pub struct GrowthStage {
+    pub name: String,
+    pub sprite_number: usize,
+    /// Growth needed to reach this stage, in game time at the best rate.
+    pub growth: f32,
+}
+
+/// A species of plant, see `PlantGrowthSystem`. Items are named by their key in `items.ron`.
+#[derive(Clone, Default, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PlantKind {
+    pub name: String,
+    /// From sprouting to ripe, in order of the growth needed.
+    pub stages: Vec<GrowthStage>,
+    /// Temperatures it grows between, fastest halfway, in degrees celsius.
+    pub min_temperature: f32,
+    pub max_temperature: f32,
+    /// Share of its growth rate kept with no water nearby.
+    pub drought_tolerance: f32,
+    /// Item sown to grow it.
+    pub seed: String,
+    pub seed_count: usize,
+    /// Item it is harvested for once ripe.
+    pub harvest: String,
+    pub harvest_count: usize,
+}
+impl PlantKind {
+    /// Growth per unit of game time at `temperature`.
+    pub fn growth_rate(&self, temperature: f32, watered: bool) -> f32 {
+        if temperature <= self.min_temperature || temperature >= self.max_temperature {
+            return 0.0;
+        }
+        let half = (self.max_temperature - self.min_temperature) / 2.0;
+        let from_best = (temperature - self.min_temperature - half) / half;
+        let rate = 1.0 - from_best * from_best;
+
+        if watered {
+            rate
+        } else {
+            rate * self.drought_tolerance
+        }
+    }
+
+    /// The stage reached with `growth`.
+    pub fn stage(&self, growth: f32) -> usize {
+        self.stages
+            .iter()
+            .rposition(|stage| growth >= stage.growth)
+            .unwrap_or(0)
+    }
+
+    pub fn is_ripe(&self, stage: usize) -> bool {
+        stage + 1 >= self.stages.len()
+    }
+}
+
+impl Asset for PlantKind {
+    const NAME: &'static str = "survival::PlantKind";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plant_definitions() {
+        let file = std::fs::File::open("resources/data/plants.ron").unwrap();
+        let storage: crate::assets::Storage<PlantKind> = ron::de::from_reader(file).unwrap();
+
+        let items = std::fs::File::open("resources/data/items.ron").unwrap();
+        let items: crate::assets::Storage<crate::assets::Item> =
+            ron::de::from_reader(items).unwrap();
+        for kind in storage.data.values() {
+            assert!(items.data.contains_key(&kind.seed));
+            assert!(items.data.contains_key(&kind.harvest));
+            assert!(!kind.stages.is_empty());
+            assert!(kind
+                .stages
+                .windows(2)
+                .all(|pair| pair[0].growth < pair[1].growth));
+        }
+    }
+
+    #[test]
+    fn growth() {
+        let stage = |name: &str, growth| GrowthStage {
+            name: name.to_string(),
+            sprite_number: 0,
+            growth,
+        };
+        let kind = PlantKind {
+            stages: vec![
+                stage("Sprout", 0.0),
+                stage("Leafy", 50.0),
+                stage("Ripe", 100.0),
+            ],
+            min_temperature: 0.0,
+            max_temperature: 30.0,
+            drought_tolerance: 0.5,
+            ..PlantKind::default()
+        };
+
+        assert_eq!(kind.growth_rate(15.0, true), 1.0);
+        assert_eq!(kind.growth_rate(15.0, false), 0.5);
+        assert!(kind.growth_rate(25.0, true) < 1.0);
+        assert_eq!(kind.growth_rate(-5.0, true), 0.0);
+        assert_eq!(kind.growth_rate(30.0, true), 0.0);
+
+        assert_eq!(kind.stage(0.0), 0);
+        assert_eq!(kind.stage(75.0), 1);
+        assert!(!kind.is_ripe(kind.stage(75.0)));
+        assert!(kind.is_ripe(kind.stage(150.0)));
+    }
+}
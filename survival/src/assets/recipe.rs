@@ -0,0 +1,250 @@
+use amethyst::{
+    assets::{Asset, Handle},
+    ecs::{Entity, VecStorage},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::actions::Action as ActionEvent;
+use crate::assets::item::{Details, Property};
+use crate::components::InteractionType;
+use crate::goap::{
+    self, ActionCatagory, Condition, ConditionEquality, ConditionType, ConditionValue,
+};
+
+/// What an input of a recipe may be made from. Items are named by their key in `items.ron`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Ingredient {
+    /// Any item with a property at least as good as this one.
+    Property(Property),
+    Material(String),
+}
+impl Ingredient {
+    pub fn matches(&self, name: &str, details: &Details, properties: &[Property]) -> bool {
+        match self {
+            Ingredient::Property(required) => details
+                .properties
+                .iter()
+                .chain(properties.iter())
+                .any(|property| satisfies(property, required)),
+            Ingredient::Material(material) => material == name,
+        }
+    }
+}
+
+/// Whether `property` is of the same kind as `required`, and of at least its quality.
+pub fn satisfies(property: &Property, required: &Property) -> bool {
+    if std::mem::discriminant(property) != std::mem::discriminant(required) {
+        return false;
+    }
+    match (property.quality(), required.quality()) {
+        (Some(quality), Some(required)) => quality >= required,
+        _ => property == required,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Input {
+    pub ingredient: Ingredient,
+    pub count: usize,
+}
+
+/// Something pawns can make, see `CraftingSystem`.
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+pub struct Recipe {
+    pub name: String,
+    /// The labor making this is.
+    pub catagory: ActionCatagory,
+    /// Used up in the making, taken from what the crafter carries or is next to.
+    pub inputs: Vec<Input>,
+    /// What the crafter must carry a tool for, if anything.
+    pub tool: InteractionType,
+    /// Furniture the crafter must be next to, with at least this property.
+    pub workstation: Option<Property>,
+    /// Whether the crafter must be next to a `HeatSource`, such as a fire to cook over.
+    #[serde(default)]
+    pub heat: bool,
+    /// Key of the item made.
+    pub output: String,
+    pub output_count: usize,
+    /// Game time the making takes.
+    pub time: u64,
+}
+impl PartialEq for Recipe {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+impl Recipe {
+    /// Name of the planner action crafting this.
+    pub fn action_name(&self) -> String {
+        format!("Craft {}", self.name)
+    }
+
+    /// The world state once this has been made, which jobs to make it plan towards.
+    pub fn goal_conditions(&self) -> Vec<Condition> {
+        vec![Condition::new(
+            ConditionEquality::Is,
+            ConditionType::Has,
+            ConditionValue::Material {
+                material: self.output.clone(),
+                count: self.output_count,
+            },
+        )]
+    }
+
+    /// The planner action crafting this, needing the inputs, tool and workstation at hand.
+    pub fn action(&self) -> goap::Action {
+        let near = |value| Condition::new(ConditionEquality::Is, ConditionType::Near(1), value);
+
+        let mut conditions = self
+            .inputs
+            .iter()
+            .map(|input| {
+                near(match &input.ingredient {
+                    Ingredient::Property(property) => ConditionValue::Property(property.clone()),
+                    Ingredient::Material(material) => ConditionValue::Material {
+                        material: material.clone(),
+                        count: input.count,
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        if !self.tool.is_empty() {
+            conditions.push(Condition::new(
+                ConditionEquality::Is,
+                ConditionType::Has,
+                ConditionValue::Interaction(self.tool),
+            ));
+        }
+        if let Some(workstation) = &self.workstation {
+            conditions.push(near(ConditionValue::Property(workstation.clone())));
+        }
+        if self.heat {
+            conditions.push(near(ConditionValue::Heat));
+        }
+
+        goap::Action::new(
+            self.action_name(),
+            self.catagory.clone(),
+            ActionEvent::Craft,
+            self.time as f32,
+            conditions,
+            self.goal_conditions()
+                .into_iter()
+                .map(|condition| (condition, true))
+                .collect(),
+        )
+    }
+
+    /// Picks the items used up making this from those `available`, named by their key, or `None`
+    /// if there aren't enough.
+    pub fn select<'a, I>(&self, available: I) -> Option<Vec<Entity>>
+    where
+        I: IntoIterator<Item = (Entity, &'a str, &'a Details, &'a [Property])>,
+    {
+        let available = available.into_iter().collect::<Vec<_>>();
+        let mut chosen = HashSet::new();
+        let mut selected = Vec::new();
+        for input in &self.inputs {
+            let found = available
+                .iter()
+                .filter(|(entity, name, details, properties)| {
+                    !chosen.contains(entity) && input.ingredient.matches(name, details, properties)
+                })
+                .map(|(entity, _, _, _)| *entity)
+                .take(input.count)
+                .collect::<Vec<_>>();
+            if found.len() < input.count {
+                return None;
+            }
+            chosen.extend(found.iter().cloned());
+            selected.extend(found);
+        }
+        Some(selected)
+    }
+}
+
+impl Asset for Recipe {
+    const NAME: &'static str = "survival::Recipe";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst::ecs::{Builder, World};
+    use ordered_float::OrderedFloat;
+
+    #[test]
+    fn recipe_definitions() {
+        let file = std::fs::File::open("resources/data/recipes.ron").unwrap();
+        let storage: crate::assets::Storage<Recipe> = ron::de::from_reader(file).unwrap();
+
+        let items = std::fs::File::open("resources/data/items.ron").unwrap();
+        let items: crate::assets::Storage<Details> = ron::de::from_reader(items).unwrap();
+        for recipe in storage.data.values() {
+            assert!(items.data.contains_key(&recipe.output));
+            assert!(recipe.output_count > 0);
+        }
+    }
+
+    #[test]
+    fn ingredients() {
+        let mut world = World::new();
+        let mut entity = || world.create_entity().build();
+        let (log, stone, other_stone, axe) = (entity(), entity(), entity(), entity());
+
+        let plain = Details::default();
+        let sharp = Details {
+            properties: vec![Property::Cutting(OrderedFloat(2.0))],
+            ..Details::default()
+        };
+        let recipe = Recipe {
+            inputs: vec![
+                Input {
+                    ingredient: Ingredient::Material("stone".to_string()),
+                    count: 2,
+                },
+                Input {
+                    ingredient: Ingredient::Property(Property::Cutting(OrderedFloat(1.0))),
+                    count: 1,
+                },
+            ],
+            ..Recipe::default()
+        };
+
+        let none: &[Property] = &[];
+        let available = vec![
+            (log, "log", &plain, none),
+            (stone, "stone", &plain, none),
+            (other_stone, "stone", &plain, none),
+            (axe, "hand_axe", &sharp, none),
+        ];
+        assert_eq!(
+            recipe.select(available.clone()),
+            Some(vec![stone, other_stone, axe])
+        );
+        assert_eq!(recipe.select(available[..3].to_vec()), None);
+
+        // Too blunt
+        let blunt = vec![Property::Cutting(OrderedFloat(0.5))];
+        let available = vec![
+            (stone, "stone", &plain, none),
+            (other_stone, "stone", &plain, none),
+            (axe, "hand_axe", &plain, &blunt[..]),
+        ];
+        assert_eq!(recipe.select(available), None);
+
+        let action = recipe.action();
+        assert_eq!(action.conditions().len(), 2);
+        assert_eq!(action.results()[0].0, recipe.goal_conditions()[0]);
+
+        let cooked = Recipe {
+            heat: true,
+            ..recipe
+        };
+        assert_eq!(cooked.action().conditions().len(), 3);
+    }
+}
@@ -0,0 +1,617 @@
+use amethyst::ecs::World;
+use slog::slog_warn;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::assets::migration::{self, Migration};
+use crate::assets::{
+    item, material, recipe::Ingredient, Item, ItemStorage, Localization, Material, MaterialStorage,
+    Packs, Recipe, RecipeStorage, Storage, Workshop, WorkshopStorage,
+};
+use crate::goap::{self, ConditionValue};
+
+/// Something wrong with the asset databases, by the file and line it was found at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+/// The text of a database file, to point at the lines of its entries.
+#[derive(Clone, Debug, Default)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub text: String,
+}
+impl SourceFile {
+    /// The file at `path`, as empty if it can't be read, its problems then going without lines.
+    pub fn read(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            text: std::fs::read_to_string(path).unwrap_or_default(),
+        }
+    }
+
+    /// The line, counting from 1, of the first line containing `needle`.
+    pub fn line_of(&self, needle: &str) -> Option<usize> {
+        self.text
+            .lines()
+            .position(|line| line.contains(needle))
+            .map(|index| index + 1)
+    }
+
+    /// The keys of the entries of a database, such as `"log": (`, with their lines. Keys
+    /// repeated are listed as often as they are, where parsing would keep only the last.
+    pub fn keys(&self) -> Vec<(&str, usize)> {
+        self.text
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let line = line.trim();
+                if line.len() > 5 && line.starts_with('"') && line.ends_with("\": (") {
+                    Some((&line[1..line.len() - 4], index + 1))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn diagnostic(&self, line: Option<usize>, message: String) -> Diagnostic {
+        Diagnostic {
+            file: self.path.clone(),
+            line,
+            message,
+        }
+    }
+
+    /// A problem with the entry `key`.
+    fn entry(&self, key: &str, message: String) -> Diagnostic {
+        self.diagnostic(self.line_of(&format!("\"{}\":", key)), message)
+    }
+}
+
+/// The files of a database in each data pack having one, in load order.
+#[derive(Clone, Debug, Default)]
+pub struct SourceFiles(pub Vec<SourceFile>);
+impl SourceFiles {
+    pub fn read(paths: &[PathBuf]) -> Self {
+        Self(paths.iter().map(|path| SourceFile::read(path)).collect())
+    }
+
+    /// The last of the files to define the entry `key`, which is the one overriding the others,
+    /// or the first file if none does.
+    fn defining(&self, key: &str) -> Option<&SourceFile> {
+        let needle = format!("\"{}\":", key);
+        self.0
+            .iter()
+            .rev()
+            .find(|file| file.line_of(&needle).is_some())
+            .or_else(|| self.0.first())
+    }
+
+    /// A problem with the entry `key`, pointed at in the file defining it.
+    fn entry(&self, key: &str, message: String) -> Diagnostic {
+        match self.defining(key) {
+            Some(file) => file.entry(key, message),
+            None => Diagnostic {
+                file: PathBuf::new(),
+                line: None,
+                message,
+            },
+        }
+    }
+}
+
+/// Reports keys given to more than one entry of one of `files`, and entries of `data` sharing a
+/// name.
+fn check_duplicates<T>(
+    files: &SourceFiles,
+    data: &HashMap<String, T>,
+    name: impl Fn(&T) -> &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for file in &files.0 {
+        let mut seen = HashSet::new();
+        for (key, line) in file.keys() {
+            if !seen.insert(key) {
+                diagnostics.push(file.diagnostic(
+                    Some(line),
+                    format!("\"{}\" is defined again, replacing the first", key),
+                ));
+            }
+        }
+    }
+
+    let mut by_name = HashMap::<&str, Vec<&str>>::new();
+    for (key, value) in data {
+        by_name.entry(name(value)).or_default().push(key);
+    }
+    for (name, mut keys) in by_name {
+        if keys.len() > 1 {
+            keys.sort();
+            diagnostics.push(files.entry(
+                keys[1],
+                format!("\"{}\" is the name of each of {:?}", name, keys),
+            ));
+        }
+    }
+}
+
+/// Reports files in a layout older than the current, which are upgraded as they load and are
+/// better saved in the current one, and those newer than this game reads.
+pub fn check_schema(
+    files: &SourceFiles,
+    migrations: &[Migration],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let current = migration::current(migrations);
+    for file in &files.0 {
+        let version = migration::version(&file.text);
+        let line = file.line_of(migration::version_field);
+        if version > current {
+            diagnostics.push(file.diagnostic(
+                line,
+                format!(
+                    "Schema version {} is newer than {}, the latest known",
+                    version, current
+                ),
+            ));
+        } else if version < current {
+            let changes = migration::pending(&file.text, migrations)
+                .iter()
+                .flat_map(|migration| migration.changes.iter().map(ToString::to_string))
+                .collect::<Vec<_>>();
+            let mut message = format!(
+                "Schema version {} is upgraded to {} as it loads",
+                version, current
+            );
+            if !changes.is_empty() {
+                message += &format!(": {}", changes.join(", "));
+            }
+            diagnostics.push(file.diagnostic(line, message));
+        }
+    }
+}
+
+/// Checks the items for unknown materials and items, and for weighing nothing.
+pub fn check_items(
+    files: &SourceFiles,
+    items: &Storage<Item>,
+    materials: &HashSet<&str>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    check_duplicates(files, &items.data, |item| item.name.as_str(), diagnostics);
+
+    let mut keys = items.data.keys().collect::<Vec<_>>();
+    keys.sort();
+    for key in keys {
+        let item = &items.data[key];
+        if item.weight <= 0.0 {
+            diagnostics.push(files.entry(key, format!("\"{}\" weighs nothing", key)));
+        }
+        for material in item.material.iter().chain(item.liquid_source.iter()) {
+            if !materials.contains(material.as_str()) {
+                diagnostics.push(files.entry(
+                    key,
+                    format!(
+                        "\"{}\" refers to the unknown material \"{}\"",
+                        key, material
+                    ),
+                ));
+            }
+        }
+        if let Some(broken_into) = &item.broken_into {
+            if !items.data.contains_key(broken_into) {
+                diagnostics.push(files.entry(
+                    key,
+                    format!(
+                        "\"{}\" breaks into the unknown item \"{}\"",
+                        key, broken_into
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Checks the text of the items is in `locale`, reporting those missing in the file of the items.
+pub fn check_text(
+    files: &SourceFiles,
+    items: &Storage<Item>,
+    locale: &Localization,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut keys = items.data.keys().collect::<Vec<_>>();
+    keys.sort();
+    for key in keys {
+        let item = &items.data[key];
+        for text in &[&item.name, &item.short_description, &item.long_description] {
+            if locale.lookup(text).is_none() {
+                diagnostics.push(files.entry(
+                    key,
+                    format!(
+                        "\"{}\" has no {} text for \"{}\"",
+                        key, locale.language, text
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Checks the materials are keyed by their names, which `Materials` finds them by.
+pub fn check_materials(
+    files: &SourceFiles,
+    materials: &Storage<Material>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    check_duplicates(files, &materials.data, Material::name, diagnostics);
+
+    let mut keys = materials.data.keys().collect::<Vec<_>>();
+    keys.sort();
+    for key in keys {
+        let name = materials.data[key].name();
+        if key != name {
+            diagnostics.push(files.entry(
+                key,
+                format!("\"{}\" is named \"{}\", which it is found by", key, name),
+            ));
+        }
+    }
+}
+
+/// Checks the recipes only call for and make known items, or properties some item has, and make
+/// something.
+pub fn check_recipes(
+    files: &SourceFiles,
+    recipes: &Storage<Recipe>,
+    items: &Storage<Item>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    check_duplicates(
+        files,
+        &recipes.data,
+        |recipe| recipe.name.as_str(),
+        diagnostics,
+    );
+
+    let mut keys = recipes.data.keys().collect::<Vec<_>>();
+    keys.sort();
+    for key in keys {
+        let recipe = &recipes.data[key];
+        if !items.data.contains_key(&recipe.output) {
+            diagnostics.push(files.entry(
+                key,
+                format!("\"{}\" makes the unknown item \"{}\"", key, recipe.output),
+            ));
+        }
+        if recipe.output_count == 0 {
+            diagnostics.push(files.entry(key, format!("\"{}\" makes nothing", key)));
+        }
+        for input in &recipe.inputs {
+            match &input.ingredient {
+                Ingredient::Material(ingredient) => {
+                    if !items.data.contains_key(ingredient) {
+                        diagnostics.push(files.entry(
+                            key,
+                            format!("\"{}\" needs the unknown item \"{}\"", key, ingredient),
+                        ));
+                    }
+                }
+                Ingredient::Property(property) => {
+                    if items.find_satisfying(property).next().is_none() {
+                        diagnostics.push(files.entry(
+                            key,
+                            format!("\"{}\" needs {:?}, which no item has", key, property),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Checks the workshops stand as known items, are built of known items and allow known recipes,
+/// and cover ground and speed work up rather than stopping it.
+pub fn check_workshops(
+    files: &SourceFiles,
+    workshops: &Storage<Workshop>,
+    items: &Storage<Item>,
+    recipes: &Storage<Recipe>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    check_duplicates(
+        files,
+        &workshops.data,
+        |workshop| workshop.name.as_str(),
+        diagnostics,
+    );
+
+    let mut keys = workshops.data.keys().collect::<Vec<_>>();
+    keys.sort();
+    for key in keys {
+        let workshop = &workshops.data[key];
+        if !items.data.contains_key(&workshop.item) {
+            diagnostics.push(files.entry(
+                key,
+                format!(
+                    "\"{}\" stands as the unknown item \"{}\"",
+                    key, workshop.item
+                ),
+            ));
+        }
+        if workshop.size.0 == 0 || workshop.size.1 == 0 {
+            diagnostics.push(files.entry(key, format!("\"{}\" covers no tiles", key)));
+        }
+        for input in &workshop.materials {
+            if let Ingredient::Material(ingredient) = &input.ingredient {
+                if !items.data.contains_key(ingredient) {
+                    diagnostics.push(files.entry(
+                        key,
+                        format!(
+                            "\"{}\" is built of the unknown item \"{}\"",
+                            key, ingredient
+                        ),
+                    ));
+                }
+            }
+        }
+        for recipe in &workshop.recipes {
+            if !recipes.data.contains_key(recipe) {
+                diagnostics.push(files.entry(
+                    key,
+                    format!("\"{}\" allows the unknown recipe \"{}\"", key, recipe),
+                ));
+            }
+        }
+        for (catagory, speed) in &workshop.speed {
+            if *speed <= 0.0 {
+                diagnostics.push(files.entry(
+                    key,
+                    format!(
+                        "\"{}\" stops {:?} work with a speed of {}",
+                        key, catagory, speed
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Checks the materials the conditions of the actions call for are known items or materials, and
+/// the properties they call for are those of some item.
+pub fn check_actions(
+    file: &SourceFile,
+    actions: &[goap::Action],
+    items: &Storage<Item>,
+    materials: &HashSet<&str>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut seen = HashSet::new();
+    for action in actions {
+        let line = file.line_of(&format!("name: \"{}\"", action.name()));
+        if !seen.insert(action.name()) {
+            diagnostics.push(file.diagnostic(
+                line,
+                format!("\"{}\" is the name of more than one action", action.name()),
+            ));
+        }
+
+        let conditions = action
+            .conditions()
+            .iter()
+            .chain(action.results().iter().map(|(condition, _)| condition));
+        for condition in conditions {
+            match condition.value() {
+                ConditionValue::Material { material, .. }
+                    if !items.data.contains_key(material)
+                        && !materials.contains(material.as_str()) =>
+                {
+                    diagnostics.push(file.diagnostic(
+                        line,
+                        format!(
+                            "\"{}\" calls for the unknown material \"{}\"",
+                            action.name(),
+                            material
+                        ),
+                    ));
+                }
+                ConditionValue::Property(property)
+                    if items.find_by_property(property).is_empty() =>
+                {
+                    diagnostics.push(file.diagnostic(
+                        line,
+                        format!(
+                            "\"{}\" calls for {:?}, which no item has",
+                            action.name(),
+                            property
+                        ),
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Checks the databases loaded into `world` from `packs`, along with the actions of the base
+/// game.
+pub fn validate(world: &World, packs: &Packs) -> Vec<Diagnostic> {
+    let items = world.read_resource::<ItemStorage>();
+    let items = items.read().unwrap();
+    let materials = world.read_resource::<MaterialStorage>();
+    let materials = materials.read().unwrap();
+    let recipes = world.read_resource::<RecipeStorage>();
+    let recipes = recipes.read().unwrap();
+    let workshops = world.read_resource::<WorkshopStorage>();
+    let workshops = workshops.read().unwrap();
+    let locale = world.read_resource::<Localization>();
+    let material_names = materials
+        .data
+        .values()
+        .map(Material::name)
+        .collect::<HashSet<_>>();
+
+    let item_files = SourceFiles::read(&packs.files("items.ron"));
+    let material_files = SourceFiles::read(&packs.files("materials.ron"));
+
+    let mut diagnostics = Vec::new();
+    check_schema(&item_files, item::migrations, &mut diagnostics);
+    check_items(&item_files, &items, &material_names, &mut diagnostics);
+    check_text(&item_files, &items, &locale, &mut diagnostics);
+    check_schema(&material_files, material::migrations, &mut diagnostics);
+    check_materials(&material_files, &materials, &mut diagnostics);
+    check_recipes(
+        &SourceFiles::read(&packs.files("recipes.ron")),
+        &recipes,
+        &items,
+        &mut diagnostics,
+    );
+    check_workshops(
+        &SourceFiles::read(&packs.files("workshops.ron")),
+        &workshops,
+        &items,
+        &recipes,
+        &mut diagnostics,
+    );
+
+    let actions = SourceFile::read(&packs.files("actions.ron")[0]);
+    let parsed = migration::migrate(&actions.text, goap::action_migrations)
+        .and_then(|text| Ok(ron::de::from_str::<goap::Actions>(&text)?));
+    match parsed {
+        Ok(parsed) => {
+            let files = SourceFiles(vec![actions]);
+            check_schema(&files, goap::action_migrations, &mut diagnostics);
+            check_actions(
+                &files.0[0],
+                &parsed.actions,
+                &items,
+                &material_names,
+                &mut diagnostics,
+            );
+        }
+        Err(e) => diagnostics.push(actions.diagnostic(None, format!("Failed to parse: {}", e))),
+    }
+    diagnostics
+}
+
+/// Logs each of `diagnostics` as a warning.
+pub fn report(diagnostics: &[Diagnostic], log: &slog::Logger) {
+    for diagnostic in diagnostics {
+        slog_warn!(log, "{}", diagnostic);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnostics() {
+        let file = SourceFile {
+            path: PathBuf::from("items.ron"),
+            text: r#"(
+    data: {
+        "log": (
+            name: "Log",
+            weight: 20,
+            material: Some("Oak"),
+        ),
+        "stone": (
+            name: "Log",
+            weight: 0,
+            broken_into: Some("gravel"),
+        ),
+        "log": (
+            name: "Log",
+            weight: 20,
+        ),
+    },
+)"#
+            .to_string(),
+        };
+        assert_eq!(file.keys(), vec![("log", 3), ("stone", 8), ("log", 13)]);
+
+        let mut items = Storage::<Item>::default();
+        items.data.insert(
+            "log".to_string(),
+            Item {
+                name: "Log".to_string(),
+                weight: 20.0,
+                material: Some("Oak".to_string()),
+                ..Item::default()
+            },
+        );
+        items.data.insert(
+            "stone".to_string(),
+            Item {
+                name: "Log".to_string(),
+                broken_into: Some("gravel".to_string()),
+                ..Item::default()
+            },
+        );
+        let mut diagnostics = Vec::new();
+        check_items(
+            &SourceFiles(vec![file]),
+            &items,
+            &HashSet::new(),
+            &mut diagnostics,
+        );
+        let messages = diagnostics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "items.ron:13: \"log\" is defined again, replacing the first",
+                "items.ron:8: \"Log\" is the name of each of [\"log\", \"stone\"]",
+                "items.ron:3: \"log\" refers to the unknown material \"Oak\"",
+                "items.ron:8: \"stone\" weighs nothing",
+                "items.ron:8: \"stone\" breaks into the unknown item \"gravel\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn schema() {
+        let file = |name: &str, text: &str| SourceFile {
+            path: PathBuf::from(name),
+            text: text.to_string(),
+        };
+        let files = SourceFiles(vec![
+            file("base/actions.ron", "(schema_version: 1, actions: [])"),
+            file("old/actions.ron", "[]"),
+            file("new/actions.ron", "(schema_version: 2, actions: [])"),
+        ]);
+        let mut diagnostics = Vec::new();
+        check_schema(&files, goap::action_migrations, &mut diagnostics);
+        let messages = diagnostics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "old/actions.ron: Schema version 0 is upgraded to 1 as it loads: \
+                 the file is now the `actions` of a struct",
+                "new/actions.ron:1: Schema version 2 is newer than 1, the latest known",
+            ]
+        );
+
+        let text = migration::migrate("[]", goap::action_migrations).unwrap();
+        let actions: goap::Actions = ron::de::from_str(&text).unwrap();
+        assert!(actions.actions.is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+use amethyst::{
+    assets::{Asset, Handle},
+    core::math::Vector3,
+    ecs::VecStorage,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::assets::recipe::{Input, Recipe};
+use crate::assets::Storage;
+use crate::goap::ActionCatagory;
+
+/// A workstation pawns can build and then craft at, such as a hearth. Built by the recipe
+/// `build_recipe` makes of it, it stands as the furniture `item` on the tile it was built on.
+/// Items and recipes are named by their keys in `items.ron` and `recipes.ron`.
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+pub struct Workshop {
+    pub name: String,
+    /// Key of the furniture item it stands as.
+    pub item: String,
+    /// Tiles it covers, east and south of the tile it stands on.
+    pub size: (u32, u32),
+    /// Used up building it.
+    pub materials: Vec<Input>,
+    /// Game time building it takes.
+    pub build_time: u64,
+    /// Keys of the recipes which can be made at it.
+    pub recipes: Vec<String>,
+    /// How many times faster work of each labor is done at it than elsewhere.
+    #[serde(default)]
+    pub speed: Vec<(ActionCatagory, f32)>,
+}
+impl Workshop {
+    /// Key of the recipe building the workshop `key`.
+    pub fn build_key(key: &str) -> String {
+        format!("build_{}", key)
+    }
+
+    /// The recipe building it from its materials.
+    pub fn build_recipe(&self) -> Recipe {
+        Recipe {
+            name: format!("Build {}", self.name),
+            catagory: ActionCatagory::Construction,
+            inputs: self.materials.clone(),
+            output: self.item.clone(),
+            output_count: 1,
+            time: self.build_time,
+            ..Recipe::default()
+        }
+    }
+
+    pub fn allows(&self, recipe: &str) -> bool {
+        self.recipes.iter().any(|allowed| allowed == recipe)
+    }
+
+    /// How many times faster work of `catagory` is done at it.
+    pub fn speed(&self, catagory: &ActionCatagory) -> f32 {
+        self.speed
+            .iter()
+            .find(|(sped_up, _)| sped_up == catagory)
+            .map_or(1.0, |(_, speed)| *speed)
+    }
+
+    /// Whether `coord` is on or next to it, standing at `origin`, on the same z-level.
+    pub fn reaches(&self, origin: Vector3<u32>, coord: Vector3<u32>) -> bool {
+        coord.z == origin.z
+            && coord.x + 1 >= origin.x
+            && coord.x <= origin.x + self.size.0
+            && coord.y + 1 >= origin.y
+            && coord.y <= origin.y + self.size.1
+    }
+}
+
+/// Adds the recipes building each of `workshops` to `recipes`, keyed by `Workshop::build_key`.
+pub fn add_build_recipes(workshops: &Storage<Workshop>, recipes: &mut Storage<Recipe>) {
+    for (key, workshop) in &workshops.data {
+        recipes
+            .data
+            .insert(Workshop::build_key(key), workshop.build_recipe());
+    }
+}
+
+impl Asset for Workshop {
+    const NAME: &'static str = "survival::Workshop";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workshop_definitions() {
+        let file = std::fs::File::open("resources/data/workshops.ron").unwrap();
+        let storage: Storage<Workshop> = ron::de::from_reader(file).unwrap();
+
+        let items = std::fs::File::open("resources/data/items.ron").unwrap();
+        let items: Storage<crate::assets::Item> = ron::de::from_reader(items).unwrap();
+        let recipes = std::fs::File::open("resources/data/recipes.ron").unwrap();
+        let mut recipes: Storage<Recipe> = ron::de::from_reader(recipes).unwrap();
+        for workshop in storage.data.values() {
+            assert!(items.data.contains_key(&workshop.item));
+            assert!(workshop
+                .recipes
+                .iter()
+                .all(|recipe| recipes.data.contains_key(recipe)));
+        }
+
+        add_build_recipes(&storage, &mut recipes);
+        let build = &recipes.data[&Workshop::build_key("hearth")];
+        assert_eq!(build.output, "hearth");
+        assert_eq!(build.catagory, ActionCatagory::Construction);
+    }
+
+    #[test]
+    fn footprint() {
+        let workshop = Workshop {
+            size: (2, 1),
+            speed: vec![(ActionCatagory::Cooking, 2.0)],
+            ..Workshop::default()
+        };
+        let origin = Vector3::new(5, 5, 1);
+        assert!(workshop.reaches(origin, Vector3::new(4, 4, 1)));
+        assert!(workshop.reaches(origin, Vector3::new(7, 6, 1)));
+        assert!(!workshop.reaches(origin, Vector3::new(8, 5, 1)));
+        assert!(!workshop.reaches(origin, Vector3::new(5, 7, 1)));
+        assert!(!workshop.reaches(origin, Vector3::new(5, 5, 2)));
+
+        assert_eq!(workshop.speed(&ActionCatagory::Cooking), 2.0);
+        assert_eq!(workshop.speed(&ActionCatagory::Mining), 1.0);
+    }
+}
@@ -0,0 +1,171 @@
+use amethyst::core::math::Vector3;
+
+use crate::actions::Direction;
+use crate::tiles::{TileId, Tiles};
+
+// Autotiled sprites, as indices into the cp437 sheet
+const wall_sprite_number: usize = 219;
+const water_sprite_number: usize = 247;
+const shore_sprite_number: usize = 126;
+const grass_sprite_number: usize = 34;
+const fringe_sprite_number: usize = 44;
+
+/// Double line walls, by which of north, east, south and west they join up with.
+const wall_lines: [usize; 16] = [
+    // None, N, E, NE
+    219, 186, 205, 200, //
+    // S, NS, ES, NES
+    186, 186, 201, 204, //
+    // W, NW, EW, NEW
+    205, 188, 205, 202, //
+    // SW, NSW, ESW, NESW
+    187, 185, 203, 206,
+];
+
+/// The neighbours of a tile, clockwise from north, by their bit in a neighbour mask.
+pub const neighbours: [Direction; 8] = [
+    Direction::N,
+    Direction::NE,
+    Direction::E,
+    Direction::SE,
+    Direction::S,
+    Direction::SW,
+    Direction::W,
+    Direction::NW,
+];
+
+const north: u8 = 1;
+const east: u8 = 1 << 2;
+const south: u8 = 1 << 4;
+const west: u8 = 1 << 6;
+/// Every neighbour being the same.
+pub const surrounded: u8 = 0xFF;
+
+/// A kind of tile whose sprite varies with which of its neighbours are of the same kind, for
+/// walls to join up and water and grass to fringe their edges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, strum_macros::Display)]
+pub enum Autotile {
+    Wall,
+    Water,
+    Grass,
+}
+impl Autotile {
+    /// The kind of tile drawn with `sprite_number`, whichever of its variants it is.
+    pub fn of(sprite_number: usize) -> Option<Self> {
+        match sprite_number {
+            wall_sprite_number => Some(Autotile::Wall),
+            water_sprite_number | shore_sprite_number => Some(Autotile::Water),
+            grass_sprite_number | fringe_sprite_number => Some(Autotile::Grass),
+            n if wall_lines.contains(&n) => Some(Autotile::Wall),
+            _ => None,
+        }
+    }
+
+    /// The sprite of the variant with the neighbours in `mask` of the same kind. Tiles
+    /// surrounded by their own kind are drawn whole, and the rest as edges.
+    pub fn variant(self, mask: u8) -> usize {
+        let mask = reduce(mask);
+        match self {
+            Autotile::Wall if mask == surrounded => wall_sprite_number,
+            Autotile::Wall => wall_lines[cardinals(mask)],
+            Autotile::Water if mask == surrounded => water_sprite_number,
+            Autotile::Water => shore_sprite_number,
+            Autotile::Grass if mask == surrounded => grass_sprite_number,
+            Autotile::Grass => fringe_sprite_number,
+        }
+    }
+}
+
+/// `mask` with the diagonal neighbours dropped unless both neighbours either side of them are
+/// set, as a corner only shows between two joined edges.
+pub fn reduce(mask: u8) -> u8 {
+    (0..8)
+        .filter(|bit| mask & (1 << bit) != 0)
+        .filter(|bit| {
+            bit % 2 == 0 || {
+                let (before, after) = ((bit + 7) % 8, (bit + 1) % 8);
+                mask & (1 << before) != 0 && mask & (1 << after) != 0
+            }
+        })
+        .fold(0, |reduced, bit| reduced | 1 << bit)
+}
+
+/// Which of north, east, south and west are set in `mask`, as an index into `wall_lines`.
+fn cardinals(mask: u8) -> usize {
+    [north, east, south, west]
+        .iter()
+        .enumerate()
+        .filter(|(_, side)| mask & **side != 0)
+        .fold(0, |index, (i, _)| index | 1 << i)
+}
+
+/// The mask of the neighbours on the level of the tile at `coord` which are `same` as it. Off
+/// the edge of the map counts as the same, so the map edges aren't fringed.
+pub fn neighbour_mask<F>(tiles: Tiles, coord: Vector3<u32>, same: F) -> u8
+where
+    F: Fn(TileId) -> bool,
+{
+    let dimensions = tiles.dimensions();
+    neighbours
+        .iter()
+        .enumerate()
+        .filter(|(_, direction)| {
+            let (dx, dy) = direction.offset();
+            let (x, y) = (coord.x as i32 + dx, coord.y as i32 + dy);
+            x < 0
+                || y < 0
+                || x >= dimensions.x as i32
+                || y >= dimensions.y as i32
+                || same(tiles.id(x as u32, y as u32, coord.z))
+        })
+        .fold(0, |mask, (bit, _)| mask | 1 << bit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants() {
+        assert_eq!(reduce(surrounded), surrounded);
+        // Corners without both edges beside them are dropped
+        assert_eq!(reduce(north | 1 << 1), north);
+        assert_eq!(reduce(north | east | 1 << 1), north | east | 1 << 1);
+
+        assert_eq!(Autotile::Wall.variant(surrounded), wall_sprite_number);
+        assert_eq!(Autotile::Wall.variant(east | west), 205);
+        assert_eq!(Autotile::Wall.variant(north | south), 186);
+        assert_eq!(Autotile::Wall.variant(east | south | 1 << 3), 201);
+        assert_eq!(Autotile::Wall.variant(0), wall_sprite_number);
+        assert_eq!(
+            Autotile::Water.variant(surrounded & !north),
+            shore_sprite_number
+        );
+        assert_eq!(Autotile::Grass.variant(surrounded), grass_sprite_number);
+
+        // Every variant is recognised as its kind
+        for kind in &[Autotile::Wall, Autotile::Water, Autotile::Grass] {
+            for mask in 0..=surrounded {
+                assert_eq!(Autotile::of(kind.variant(mask)), Some(*kind));
+            }
+        }
+        assert_eq!(Autotile::of(46), None);
+    }
+
+    #[test]
+    fn mask() {
+        let tiles = Tiles::new(3, 3, 1);
+        let pond = [tiles.id(1, 1, 0), tiles.id(2, 1, 0), tiles.id(2, 2, 0)];
+        let same = |id| pond.contains(&id);
+
+        assert_eq!(
+            neighbour_mask(tiles, Vector3::new(1, 1, 0), same),
+            east | 1 << 3
+        );
+        // The map edge counts as the same
+        assert_eq!(
+            neighbour_mask(tiles, Vector3::new(2, 2, 0), same),
+            surrounded & !west
+        );
+    }
+}
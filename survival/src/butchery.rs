@@ -0,0 +1,137 @@
+use amethyst::{
+    core::math::Vector3,
+    ecs::{Component, DenseVecStorage, LazyUpdate},
+};
+use serde::{Deserialize, Serialize};
+use specs_derive::Component;
+
+use crate::actions::Action as ActionEvent;
+use crate::goap::{
+    self, ActionCatagory, Condition, ConditionEquality, ConditionType, ConditionValue,
+};
+use crate::initializers::{spawn_item, SpawnType};
+
+/// Item left where a creature dies.
+pub const corpse_item: &str = "corpse";
+/// Items butchering a corpse yields, and the body mass of the creature yielding each.
+pub const butchery_yields: [(&str, f32); 3] = [("meat", 10.0), ("hide", 30.0), ("bone", 20.0)];
+/// Game time butchering a corpse takes once at the butcher spot.
+pub const butcher_time: u64 = 50;
+/// Game time carrying a corpse to a butcher spot and butchering it is expected to take, for
+/// planning.
+pub const butcher_plan_time: f32 = 80.0;
+
+/// The remains of a creature, which can be carried to a butcher spot and butchered.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Corpse {
+    /// Name of the `CreatureKind` it was.
+    pub kind: String,
+}
+
+/// What butchering the corpse of a creature of `size` body mass yields, at least one of each.
+pub fn yields(size: f32) -> Vec<(&'static str, u32)> {
+    butchery_yields
+        .iter()
+        .map(|(item, mass)| (*item, (size / mass).round().max(1.0) as u32))
+        .collect()
+}
+
+/// Leaves the corpse of a creature of `kind` at `coord`, once the world is next updated.
+pub fn leave_corpse(lazy: &LazyUpdate, kind: &str, coord: Vector3<u32>) {
+    let kind = kind.to_string();
+    lazy.exec_mut(move |world| {
+        let corpse = spawn_item(world, SpawnType::TilePosition(coord), corpse_item, None);
+        world
+            .write_storage::<Corpse>()
+            .insert(corpse, Corpse { kind })
+            .unwrap();
+    });
+}
+
+/// Having butchered a corpse yourself, which can't be seen, so that jobs need the butchering done.
+pub fn butchered() -> Condition {
+    Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Me,
+        ConditionValue::Corpse,
+    )
+}
+
+/// The planner actions for butchering: moving next to a corpse, then carrying it off to a
+/// butcher spot and butchering it there.
+pub fn actions() -> Vec<goap::Action> {
+    let near_corpse = Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Near(1),
+        ConditionValue::Corpse,
+    );
+
+    vec![
+        goap::Action::new(
+            "Move To Corpse".to_string(),
+            ActionCatagory::Butchery,
+            ActionEvent::MoveTo,
+            1.0,
+            Vec::new(),
+            vec![(near_corpse.clone(), true)],
+        ),
+        goap::Action::new(
+            "Butcher Corpse".to_string(),
+            ActionCatagory::Butchery,
+            ActionEvent::Butcher,
+            butcher_plan_time,
+            vec![near_corpse],
+            vec![(butchered(), true)],
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hibitset::BitSet;
+
+    #[test]
+    fn butchery() {
+        let file = std::fs::File::open("resources/data/creatures.ron").unwrap();
+        let kinds: crate::assets::Storage<crate::assets::CreatureKind> =
+            ron::de::from_reader(file).unwrap();
+        let count = |size, item| {
+            yields(size)
+                .into_iter()
+                .find(|(name, _)| *name == item)
+                .unwrap()
+                .1
+        };
+
+        // Everything yields something, and bigger creatures more of it
+        let (rabbit, deer) = (kinds.data["rabbit"].size, kinds.data["deer"].size);
+        assert!(yields(rabbit).iter().all(|(_, count)| *count >= 1));
+        assert!(count(deer, "meat") > count(rabbit, "meat"));
+        assert_eq!(count(60.0, "meat"), 6);
+        assert_eq!(count(60.0, "hide"), 2);
+
+        let items = std::fs::File::open("resources/data/items.ron").unwrap();
+        let items: crate::assets::Storage<crate::assets::Item> =
+            ron::de::from_reader(items).unwrap();
+        assert!(items.data[corpse_item].shelf_life.is_some());
+        for (item, _) in &butchery_yields {
+            assert!(items.data.contains_key(*item));
+        }
+
+        let mut planner = goap::Planner::default();
+        let mut available = BitSet::new();
+        for action in actions() {
+            available.add(planner.insert(action));
+        }
+        let goal = planner.condition_set(&[butchered()]).unwrap();
+        let plan = planner
+            .plan(&available, &goal, BitSet::new())
+            .unwrap()
+            .iter()
+            .map(|id| planner.get_action_name(*id).unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(plan, vec!["Move To Corpse", "Butcher Corpse"]);
+    }
+}
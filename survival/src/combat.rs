@@ -0,0 +1,151 @@
+use amethyst::ecs::Entity;
+
+use crate::actions::Action as ActionEvent;
+use crate::components::equipment::Slot;
+use crate::goap::{
+    self, ActionCatagory, Condition, ConditionEquality, ConditionType, ConditionValue,
+};
+use crate::injury::{PartKind, WoundKind};
+
+/// Game time a swing takes.
+pub const attack_time: f32 = 10.0;
+/// Chance of landing a blow between fighters of equal skill.
+pub const base_hit_chance: f32 = 0.7;
+/// Chance of landing a blow gained for each skill level over the defender.
+pub const skill_hit_chance: f32 = 0.03;
+pub const min_hit_chance: f32 = 0.05;
+pub const max_hit_chance: f32 = 0.95;
+/// Force added to blows for each skill level.
+pub const skill_force: f32 = 0.05;
+/// Quality of a punch, for those without a weapon.
+pub const unarmed_quality: f32 = 0.2;
+
+/// Chance of an attacker landing a blow on a defender, from their skill at combat.
+pub fn hit_chance(attacker_skill: f32, defender_skill: f32) -> f32 {
+    (base_hit_chance + (attacker_skill - defender_skill) * skill_hit_chance)
+        .max(min_hit_chance)
+        .min(max_hit_chance)
+}
+
+/// Where armor covering a part of the body is worn.
+pub fn armor_slot(part: PartKind) -> Slot {
+    match part {
+        PartKind::Head => Slot::Head,
+        PartKind::Torso | PartKind::Organ => Slot::Torso,
+        PartKind::Arm => Slot::Hands,
+        PartKind::Leg => Slot::Feet,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CombatOutcome {
+    /// The defender got out of the way.
+    Dodged,
+    /// The blow landed on `part`, dealing `wounds` to it and whatever lies inside.
+    Hit {
+        part: String,
+        wounds: Vec<(String, WoundKind)>,
+    },
+}
+
+/// Sent by `CombatSystem` for every blow struck, for the combat log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CombatEvent {
+    pub attacker: Entity,
+    pub defender: Entity,
+    /// Name of the weapon used, if any.
+    pub weapon: Option<String>,
+    pub outcome: CombatOutcome,
+}
+impl std::fmt::Display for CombatEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let weapon = self
+            .weapon
+            .as_ref()
+            .map_or_else(String::new, |weapon| format!(" with {}", weapon));
+        match &self.outcome {
+            CombatOutcome::Dodged => write!(
+                f,
+                "{:?} swings at {:?}{}, who dodges",
+                self.attacker, self.defender, weapon
+            ),
+            CombatOutcome::Hit { part, wounds } => {
+                write!(
+                    f,
+                    "{:?} strikes {:?} in the {}{}",
+                    self.attacker, self.defender, part, weapon
+                )?;
+                for (part, wound) in wounds {
+                    write!(f, ", {:?} to the {}", wound, part)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The planner actions for fighting: closing in on a hostile, and attacking it.
+pub fn actions() -> Vec<goap::Action> {
+    let near_hostile = Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Near(1),
+        ConditionValue::Hostile,
+    );
+
+    vec![
+        goap::Action::new(
+            "Move To Hostile".to_string(),
+            ActionCatagory::Combat,
+            ActionEvent::MoveTo,
+            1.0,
+            Vec::new(),
+            vec![(near_hostile.clone(), true)],
+        ),
+        goap::Action::new(
+            "Attack".to_string(),
+            ActionCatagory::Combat,
+            ActionEvent::Attack,
+            attack_time,
+            vec![near_hostile],
+            vec![(fought(), true)],
+        ),
+    ]
+}
+
+/// Having struck a hostile yourself, which can't be seen, so that fighting needs an attack.
+pub fn fought() -> Condition {
+    Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Me,
+        ConditionValue::Hostile,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hibitset::BitSet;
+
+    #[test]
+    fn combat() {
+        assert_eq!(hit_chance(0.0, 0.0), base_hit_chance);
+        assert!(hit_chance(10.0, 0.0) > hit_chance(0.0, 0.0));
+        assert_eq!(hit_chance(0.0, 100.0), min_hit_chance);
+        assert_eq!(hit_chance(100.0, 0.0), max_hit_chance);
+        assert_eq!(armor_slot(PartKind::Organ), Slot::Torso);
+
+        let mut planner = goap::Planner::default();
+        let mut available = BitSet::new();
+        for action in actions() {
+            available.add(planner.insert(action));
+        }
+        let goal = planner.condition_set(&[fought()]).unwrap();
+        let plan = planner
+            .plan(&available, &goal, BitSet::new())
+            .unwrap()
+            .iter()
+            .map(|id| planner.get_action_name(*id).unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(plan, vec!["Move To Hostile", "Attack"]);
+    }
+}
@@ -1,16 +1,26 @@
 use amethyst::{
-    ecs::{Component, DenseVecStorage, world::Index, },
+    core::math::Vector3,
+    ecs::{Component, DenseVecStorage, Entity, world::Index, },
 };
 
 use specs_derive::Component;
 use crossbeam::queue::SegQueue;
+use hibitset::BitSet;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::collections::BinaryHeap;
 
-use crate::goap::ActionCatagory;
+use crate::goap::{
+    Action, ActionCatagory, Condition, ConditionEquality, ConditionType, ConditionValue,
+    DistanceCost, Goal, Motive,
+};
+use crate::actions::{Action as ActionEvent, Target};
+use crate::jobs::JobId;
+
+use crate::pathfinding::{distance, DijkstraCollection};
 
-use crate::pathfinding::DijkstraCollection;
+/// Game time a pawn waits before replanning after a failed plan, doubled for each failure in a row.
+pub const replan_backoff: u64 = 10;
+pub const max_replan_backoff: u64 = 640;
 
 #[derive(Component, Default, Debug)]
 #[storage(DenseVecStorage)]
@@ -19,18 +29,735 @@ pub struct Pawn {
     pub labor_priorities: HashMap<ActionCatagory, f32>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlanStatus {
+    /// No plan has been given.
+    Idle,
+    Executing,
+    Completed,
+    /// An action of the plan failed, and the rest of it was dropped. The planner picks the entity
+    /// up again once `AI::replan_at` has passed.
+    Failed,
+}
+impl Default for PlanStatus {
+    fn default() -> Self {
+        PlanStatus::Idle
+    }
+}
+
 #[derive(Component, Default, Debug)]
 #[storage(DenseVecStorage)]
 pub struct AI {
     pub action_set: Index,
     pub action_queue: SegQueue<Index>,
     pub dijkstra_maps: DijkstraCollection,
+    /// The planner actions this entity can plan with.
+    pub available_actions: BitSet,
+    /// Name of the goal the current plan works towards.
+    pub goal: Option<String>,
+    /// The job claimed on the `JobBoard`.
+    pub job: Option<JobId>,
+    /// Whether the current plan works towards `job`, rather than something more pressing.
+    pub on_job: bool,
+    /// The plan last set, including the actions already carried out.
+    pub plan: Vec<Index>,
+    /// The planner action currently being carried out.
+    pub current: Option<Index>,
+    pub status: PlanStatus,
+    /// Plans which failed in a row, since the last completed one.
+    pub failures: u32,
+    pub replan_at: u64,
+}
+impl AI {
+    /// Replaces the queued plan.
+    pub fn set_plan(&mut self, plan: &[Index]) {
+        self.clear_plan();
+        for action in plan {
+            self.action_queue.push(*action);
+        }
+        self.plan = plan.to_vec();
+        self.status = PlanStatus::Executing;
+    }
+
+    pub fn clear_plan(&mut self) {
+        while self.action_queue.pop().is_ok() {}
+        self.plan.clear();
+        self.current = None;
+    }
+
+    /// Abandons the plan for something more pressing, replanning straight away.
+    pub fn interrupt(&mut self) {
+        self.clear_plan();
+        self.status = PlanStatus::Idle;
+    }
+
+    /// Drops the plan as invalid, and backs off replanning so a stuck pawn doesn't replan every
+    /// frame.
+    pub fn fail(&mut self, now: u64) {
+        self.clear_plan();
+        self.status = PlanStatus::Failed;
+        self.failures = self.failures.saturating_add(1);
+
+        let backoff = replan_backoff << (self.failures - 1).min(16);
+        self.replan_at = now + backoff.min(max_replan_backoff);
+    }
+
+    pub fn complete(&mut self) {
+        self.clear_plan();
+        self.status = PlanStatus::Completed;
+        self.failures = 0;
+    }
+
+    /// Whether the planner should give this entity a new plan at `now`.
+    pub fn needs_plan(&self, now: u64) -> bool {
+        match self.status {
+            PlanStatus::Idle | PlanStatus::Completed => true,
+            PlanStatus::Executing => false,
+            PlanStatus::Failed => now >= self.replan_at,
+        }
+    }
+
+    /// Abandons the plan for something pressing for `motive`, unless `current` is an action
+    /// which shouldn't be dropped for it. Returns whether the plan was interrupted.
+    pub fn interrupt_for(&mut self, motive: &Motive, current: Option<&CurrentAction>) -> bool {
+        if self.status != PlanStatus::Executing
+            || !current.map_or(true, |current| current.interruptible_by(motive))
+        {
+            return false;
+        }
+        self.interrupt();
+        true
+    }
+}
+
+/// Needs stop interrupting an action once this much of it is done, so it is finished first.
+pub const finish_threshold: f32 = 0.75;
+/// Work speed gained for each skill level.
+pub const skill_speed: f32 = 0.1;
+pub const max_skill: f32 = 20.0;
+
+/// How practiced an entity is at each catagory of action.
+#[derive(Component, Clone, Default, Debug, serde::Serialize, serde::Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Skills {
+    levels: HashMap<ActionCatagory, f32>,
+}
+impl Skills {
+    pub fn level(&self, catagory: &ActionCatagory) -> f32 {
+        self.levels.get(catagory).cloned().unwrap_or(0.0)
+    }
+
+    pub fn set_level(&mut self, catagory: ActionCatagory, level: f32) {
+        self.levels.insert(catagory, level.max(0.0).min(max_skill));
+    }
+
+    /// Multiplier of how fast actions of `catagory` are done.
+    pub fn speed(&self, catagory: &ActionCatagory) -> f32 {
+        1.0 + self.level(catagory) * skill_speed
+    }
+}
+
+/// The action an entity is part way through, and how far along it is, in game time.
+#[derive(Component, Clone, Debug, PartialEq)]
+#[storage(DenseVecStorage)]
+pub struct CurrentAction {
+    /// The planner action.
+    pub action: Index,
+    pub event: ActionEvent,
+    pub catagory: ActionCatagory,
+    pub progress: u64,
+    /// Game time the action takes in total.
+    pub required: u64,
+}
+impl CurrentAction {
+    /// Starts `action`, taking its `base_time` divided by `speed`.
+    pub fn new(action: &Action, speed: f32) -> Self {
+        Self {
+            action: action.id(),
+            event: action.event(),
+            catagory: action.catagory().clone(),
+            progress: 0,
+            required: (action.base_time() / speed.max(0.01)).max(0.0).ceil() as u64,
+        }
+    }
+
+    /// Advances by at most `time`, returning the time actually spent.
+    pub fn advance(&mut self, time: u64) -> u64 {
+        let spent = time.min(self.required.saturating_sub(self.progress));
+        self.progress += spent;
+        spent
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress >= self.required
+    }
+
+    /// How much of the action is done, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.required == 0 {
+            1.0
+        } else {
+            self.progress as f32 / self.required as f32
+        }
+    }
+
+    /// Movement is carried out, and finished, by the movement system instead, as are hauling and
+    /// butchering by the systems carrying the load, and eating by the system walking to the food.
+    pub fn is_movement(&self) -> bool {
+        match self.event {
+            ActionEvent::Move
+            | ActionEvent::MoveTo
+            | ActionEvent::Haul
+            | ActionEvent::Butcher
+            | ActionEvent::Eat => true,
+            _ => false,
+        }
+    }
+
+    /// Whether something pressing for `motive` is worth dropping this action for. Danger, such
+    /// as a fight, interrupts anything. Needs interrupt an action not yet nearly done, and any
+    /// hauling, which is easily picked up again.
+    pub fn interruptible_by(&self, motive: &Motive) -> bool {
+        match motive {
+            Motive::Danger => true,
+            Motive::Hunger | Motive::Thirst | Motive::Fatigue | Motive::Cold => {
+                self.is_hauling() || self.fraction() < finish_threshold
+            }
+            Motive::Labor(_) | Motive::Idle => false,
+        }
+    }
+
+    fn is_hauling(&self) -> bool {
+        match self.catagory {
+            ActionCatagory::HaulingFood
+            | ActionCatagory::HaulingItems
+            | ActionCatagory::HaulingStone
+            | ActionCatagory::HaulingWood
+            | ActionCatagory::HaulingRefuse => true,
+            _ => false,
+        }
+    }
+}
+
+/// Game time a pawn survives with a lethal need at its extreme.
+pub const lethal_deprivation: u64 = 500;
+/// How fast an exhausted pawn works.
+pub const exhausted_work_speed: f32 = 0.5;
+
+/// A need which grows over time, until satisfied.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Need {
+    /// From 0 when fully satisfied, to 1 at its extreme.
+    pub value: f32,
+    /// Increase in `value` per unit of game time.
+    pub rate: f32,
+    /// Past this, the pawn wants to do something about it.
+    pub threshold: f32,
+}
+impl Need {
+    pub fn new(rate: f32, threshold: f32) -> Self {
+        Self {
+            value: 0.0,
+            rate,
+            threshold,
+        }
+    }
+
+    pub fn heat() -> Self {
+        Self::new(0.0, 0.5)
+    }
+
+    /// Grows the need, returning whether it just passed its threshold.
+    pub fn tick(&mut self, elapsed: u64) -> bool {
+        self.tick_scaled(elapsed, 1.0)
+    }
+
+    /// Grows the need at `scale` times its rate.
+    pub fn tick_scaled(&mut self, elapsed: u64, scale: f32) -> bool {
+        let was_urgent = self.is_urgent();
+        self.value = (self.value + self.rate * scale * elapsed as f32).min(1.0);
+        !was_urgent && self.is_urgent()
+    }
+
+    pub fn satisfy(&mut self, amount: f32) {
+        self.value = (self.value - amount).max(0.0);
+    }
+
+    pub fn is_urgent(&self) -> bool {
+        self.value >= self.threshold
+    }
+
+    pub fn is_extreme(&self) -> bool {
+        self.value >= 1.0
+    }
+
+    /// How strongly the need drives goal selection, nothing until it passes its threshold.
+    pub fn urgency(&self) -> f32 {
+        if self.is_urgent() {
+            self.value
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The survival needs of a pawn, grown by `NeedsSystem` as game time passes.
+#[derive(Component, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Needs {
+    pub hunger: Need,
+    pub thirst: Need,
+    pub fatigue: Need,
+    /// Lack of warmth. Follows hypothermia for entities with a `BodyTemperature`.
+    pub cold: Need,
+    /// Heatstroke, set by `TemperatureSystem` rather than growing over time.
+    #[serde(default = "Need::heat")]
+    pub heat: Need,
+    /// Game time spent with hunger, thirst, cold or heat at their extreme.
+    pub deprivation: u64,
+    /// Of the clothes worn, slowing how fast cold grows.
+    #[serde(default)]
+    pub insulation: f32,
+}
+impl Default for Needs {
+    fn default() -> Self {
+        Self {
+            hunger: Need::new(0.0005, 0.5),
+            thirst: Need::new(0.001, 0.5),
+            fatigue: Need::new(0.0007, 0.6),
+            cold: Need::new(0.0002, 0.5),
+            heat: Need::heat(),
+            deprivation: 0,
+            insulation: 0.0,
+        }
+    }
+}
+impl Needs {
+    /// Grows every need, returning whether any of them just became urgent. Cold grows slower the
+    /// better insulated the pawn is.
+    pub fn tick(&mut self, elapsed: u64) -> bool {
+        let mut urgent = false;
+        for need in self.iter_mut() {
+            urgent |= need.tick(elapsed);
+        }
+        let cold_rate = 1.0 / (1.0 + self.insulation.max(0.0));
+        urgent |= self.cold.tick_scaled(elapsed, cold_rate);
+
+        if self.hunger.is_extreme()
+            || self.thirst.is_extreme()
+            || self.cold.is_extreme()
+            || self.heat.is_extreme()
+        {
+            self.deprivation += elapsed;
+        } else {
+            self.deprivation = 0;
+        }
+        urgent
+    }
+
+    pub fn is_dying(&self) -> bool {
+        self.deprivation >= lethal_deprivation
+    }
+
+    /// Multiplier of how fast the pawn works.
+    pub fn work_speed(&self) -> f32 {
+        if self.fatigue.is_extreme() {
+            exhausted_work_speed
+        } else {
+            1.0
+        }
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut Need> {
+        vec![&mut self.hunger, &mut self.thirst, &mut self.fatigue].into_iter()
+    }
+}
+
+/// How urgent fatigue is at least during a scheduled sleep, so pawns go to bed.
+pub const scheduled_sleep_urgency: f32 = 0.5;
+
+/// What a pawn spends an hour of the day on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Activity {
+    Work,
+    Sleep,
+    Leisure,
+    Anything,
 }
+impl Activity {
+    /// Whether goals for `motive` may be chosen. Hunger, thirst, cold and danger can't wait.
+    pub fn allows(self, motive: &Motive) -> bool {
+        match (self, motive) {
+            (_, Motive::Hunger) | (_, Motive::Thirst) | (_, Motive::Cold) => true,
+            (_, Motive::Danger) => true,
+            (Activity::Anything, _) => true,
+            (Activity::Work, Motive::Labor(_)) => true,
+            (Activity::Sleep, Motive::Fatigue) => true,
+            (Activity::Leisure, Motive::Fatigue) | (Activity::Leisure, Motive::Idle) => true,
+            _ => false,
+        }
+    }
+}
+
+/// The daily routine of a pawn, gating which goals it may choose at each hour of the day.
+#[derive(Component, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Schedule {
+    pub hours: [Activity; 24],
+}
+impl Default for Schedule {
+    /// Sleep from 22:00 to 6:00, and work from 8:00 to 18:00.
+    fn default() -> Self {
+        let mut hours = [Activity::Leisure; 24];
+        for (hour, activity) in hours.iter_mut().enumerate() {
+            if hour >= 22 || hour < 6 {
+                *activity = Activity::Sleep;
+            } else if hour >= 8 && hour < 18 {
+                *activity = Activity::Work;
+            }
+        }
+        Self { hours }
+    }
+}
+impl Schedule {
+    pub fn activity(&self, hour: usize) -> Activity {
+        self.hours[hour % 24]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CreatureState {
+    Wander,
+    Graze { until: u64 },
+    Flee,
+    Hunt(Entity),
+}
+impl Default for CreatureState {
+    fn default() -> Self {
+        CreatureState::Wander
+    }
+}
+
+/// Wildlife, driven by simple state machines from its `CreatureKind` rather than the pawn
+/// planner.
+#[derive(Component, Clone, Debug)]
+#[storage(DenseVecStorage)]
+pub struct Creature {
+    /// Name of the `CreatureKind` in the creature asset storage.
+    pub kind: String,
+    pub state: CreatureState,
+    /// Where the creature wanders around.
+    pub home: Vector3<u32>,
+}
+impl Creature {
+    pub fn new(kind: &str, home: Vector3<u32>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            state: CreatureState::default(),
+            home,
+        }
+    }
+}
+
+/// Hostiles within this distance are a threat.
+pub const threat_range: u32 = 16;
+/// Fleeing entities try to get at least this far from anything hostile.
+pub const flee_distance: i32 = 20;
+/// Allied strength needed for each point of hostile strength to stand and fight.
+pub const fight_odds: f32 = 1.0;
+/// Below this fraction of their health, entities flee whatever the odds.
+pub const flee_health: f32 = 0.3;
+
+/// How much each point of armor adds to combat strength.
+pub const armor_strength: f32 = 0.1;
+
+/// How much an entity adds to a fight.
+pub fn combat_strength(health: f32, weapon_quality: f32, armor: f32) -> f32 {
+    health * (1.0 + weapon_quality) * (1.0 + armor * armor_strength)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThreatResponse {
+    Fight(Entity),
+    Flee,
+}
+
+/// Set by `ThreatSystem` on entities facing hostiles, injecting a goal to fight or flee.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[storage(DenseVecStorage)]
+pub struct Threat {
+    pub response: ThreatResponse,
+    /// Allied strength over hostile strength.
+    pub odds: f32,
+}
+impl Threat {
+    /// Fights `nearest` when the odds are good and the entity is healthy enough, else flees.
+    pub fn assess(allied: f32, hostile: f32, health: f32, nearest: Entity) -> Self {
+        let odds = if hostile > 0.0 {
+            allied / hostile
+        } else {
+            std::f32::INFINITY
+        };
+        let response = if odds >= fight_odds && health > flee_health {
+            ThreatResponse::Fight(nearest)
+        } else {
+            ThreatResponse::Flee
+        };
+        Self { response, odds }
+    }
 
-#[derive(Clone, Copy, Default, Debug)]
+    /// Fighting takes landing a blow, see `CombatSystem`, while fleeing is getting far enough away.
+    pub fn goal(&self) -> Goal {
+        let (name, condition) = match self.response {
+            ThreatResponse::Fight(_) => ("Fight", crate::combat::fought()),
+            ThreatResponse::Flee => (
+                "Flee",
+                Condition::new(
+                    ConditionEquality::Not,
+                    ConditionType::Near(flee_distance),
+                    ConditionValue::Hostile,
+                ),
+            ),
+        };
+        Goal {
+            name: name.to_string(),
+            motive: Motive::Danger,
+            weight: 1.0,
+            conditions: vec![condition],
+        }
+    }
+}
+
+/// Something an entity noticed about another.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Percept {
+    /// Something matching a planner condition value, such as food or a tree.
+    Thing(ConditionValue),
+    /// Another creature, which may or may not be a threat.
+    Creature,
+}
+
+/// What was last seen of an entity, and where.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fact {
+    pub entity: Entity,
+    pub percepts: Vec<Percept>,
+    pub position: Vector3<u32>,
+    pub seen_at: u64,
+}
+
+/// The working memory of an entity, filled in by `SensorSystem` from what it can see. Facts are
+/// forgotten once `memory` game time has passed without seeing their entity again.
+#[derive(Component, Clone, Debug)]
+#[storage(DenseVecStorage)]
+pub struct Perception {
+    pub sight_range: u32,
+    /// Game time between scans of the surroundings.
+    pub scan_interval: u64,
+    pub memory: u64,
+    pub last_scan: Option<u64>,
+    facts: HashMap<Entity, Fact>,
+}
+impl Default for Perception {
+    fn default() -> Self {
+        Self {
+            sight_range: 20,
+            scan_interval: 10,
+            memory: 1000,
+            last_scan: None,
+            facts: HashMap::new(),
+        }
+    }
+}
+impl Perception {
+    pub fn needs_scan(&self, now: u64) -> bool {
+        self.last_scan
+            .map_or(true, |last_scan| now >= last_scan + self.scan_interval)
+    }
+
+    pub fn observe(
+        &mut self,
+        entity: Entity,
+        percepts: Vec<Percept>,
+        position: Vector3<u32>,
+        now: u64,
+    ) {
+        self.facts.insert(
+            entity,
+            Fact {
+                entity,
+                percepts,
+                position,
+                seen_at: now,
+            },
+        );
+    }
+
+    /// Drops every fact which has decayed away by `now`.
+    pub fn forget(&mut self, now: u64) {
+        let memory = self.memory;
+        self.facts
+            .retain(|_, fact| now.saturating_sub(fact.seen_at) < memory);
+    }
+
+    pub fn forget_entity(&mut self, entity: Entity) {
+        self.facts.remove(&entity);
+    }
+
+    pub fn facts(&self) -> impl Iterator<Item = &Fact> {
+        self.facts.values()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&Fact> {
+        self.facts.get(&entity)
+    }
+
+    /// How sure the entity still is of a fact, fading from 1 when seen to 0 when forgotten.
+    pub fn confidence(&self, fact: &Fact, now: u64) -> f32 {
+        1.0 - (now.saturating_sub(fact.seen_at) as f32 / self.memory as f32).min(1.0)
+    }
+
+    /// The closest remembered entity matching `percept`.
+    pub fn nearest(&self, percept: &Percept, from: Vector3<u32>) -> Option<&Fact> {
+        self.facts()
+            .filter(|fact| fact.percepts.contains(percept))
+            .min_by_key(|fact| distance(from, fact.position))
+    }
+
+    /// The remembered entity a planner condition is about, the closest to `position` of those
+    /// matching its value.
+    pub fn binding(&self, condition: &Condition, position: Vector3<u32>) -> Option<&Fact> {
+        match condition.kind() {
+            ConditionType::Near(_) => {
+                self.nearest(&Percept::Thing(condition.value().clone()), position)
+            }
+            ConditionType::Has | ConditionType::Me => None,
+        }
+    }
+
+    /// Prices plans by the distances from `position` to the closest remembered entities matching
+    /// `conditions`, those remembering none taken to be out of sight.
+    pub fn distance_cost<'a, I>(&self, conditions: I, position: Vector3<u32>) -> DistanceCost
+    where
+        I: IntoIterator<Item = &'a Condition>,
+    {
+        let mut costs = DistanceCost {
+            default_distance: self.sight_range as f32,
+            ..DistanceCost::default()
+        };
+        for condition in conditions {
+            if let Some(fact) = self.binding(condition, position) {
+                let distance = distance(position, fact.position) as f32;
+                costs.distances.insert(condition.value().clone(), distance);
+            }
+        }
+        costs
+    }
+
+    /// Whether a planner condition holds, as far as the entity at `position` remembers.
+    pub fn satisfies(&self, condition: &Condition, position: Vector3<u32>) -> bool {
+        let holds = match condition.kind() {
+            ConditionType::Near(range) => self.binding(condition, position).map_or(false, |fact| {
+                distance(position, fact.position) <= *range as u32
+            }),
+            // Neither are things that can be seen
+            ConditionType::Has | ConditionType::Me => return false,
+        };
+
+        match condition.equality() {
+            ConditionEquality::Is => holds,
+            ConditionEquality::Not => !holds,
+        }
+    }
+}
+
+/// The concrete entities satisfying the planner conditions of an entity, bound when it plans,
+/// so that its actions are carried out on what the planner reasoned about.
+#[derive(Component, Clone, Debug, Default)]
+#[storage(DenseVecStorage)]
+pub struct Blackboard {
+    bindings: HashMap<Condition, (Entity, Vector3<u32>)>,
+    /// What the action being carried out acts on.
+    pub target: Option<Target>,
+}
+impl Blackboard {
+    pub fn bind(&mut self, condition: Condition, entity: Entity, position: Vector3<u32>) {
+        self.bindings.insert(condition, (entity, position));
+    }
+
+    pub fn get(&self, condition: &Condition) -> Option<Entity> {
+        self.bindings.get(condition).map(|(entity, _)| *entity)
+    }
+
+    /// Where the bound entity was when the binding was made.
+    pub fn position(&self, condition: &Condition) -> Option<Vector3<u32>> {
+        self.bindings.get(condition).map(|(_, position)| *position)
+    }
+
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+        self.target = None;
+    }
+}
+
+/// How much the best or worst mood changes work speed.
+pub const mood_work_speed: f32 = 0.2;
+/// Thoughts held at once; the one closest to fading is dropped for a new one.
+pub const max_thoughts: usize = 16;
+
+/// Something a pawn thinks about after an event, lifting or lowering its mood for a while.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum ThoughtKind {
+    AteGoodMeal,
+    AtePoorMeal,
+    SleptInBed,
+    SleptOnFloor,
+    SawCorpse,
+}
+impl ThoughtKind {
+    /// Mood change of a single fresh impact.
+    pub fn value(self) -> f32 {
+        match self {
+            ThoughtKind::AteGoodMeal => 0.15,
+            ThoughtKind::AtePoorMeal => -0.05,
+            ThoughtKind::SleptInBed => 0.05,
+            ThoughtKind::SleptOnFloor => -0.1,
+            ThoughtKind::SawCorpse => -0.25,
+        }
+    }
+
+    /// Game time the thought lasts, fading as it goes.
+    pub fn duration(self) -> f32 {
+        match self {
+            ThoughtKind::AteGoodMeal | ThoughtKind::AtePoorMeal => 600.0,
+            ThoughtKind::SleptInBed | ThoughtKind::SleptOnFloor => 1200.0,
+            ThoughtKind::SawCorpse => 2400.0,
+        }
+    }
+
+    /// How many times the same thought stacks before repeats only refresh it.
+    pub fn max_impacts(self) -> f32 {
+        match self {
+            ThoughtKind::SawCorpse => 5.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Sent to `MoodSystem` to give an entity a thought.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThoughtEvent {
+    pub entity: Entity,
+    pub kind: ThoughtKind,
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct Thought {
+    pub kind: ThoughtKind,
+    /// Game time left before the thought is forgotten.
     duration: f32,
     value: f32,
+    /// Times the thought has stacked.
     impacts: f32,
 }
 impl PartialEq for Thought {
@@ -43,8 +770,296 @@ impl PartialOrd for Thought {
         self.duration.partial_cmp(&other.duration)
     }
 }
+impl Thought {
+    pub fn new(kind: ThoughtKind) -> Self {
+        Self {
+            kind,
+            duration: kind.duration(),
+            value: kind.value(),
+            impacts: 1.0,
+        }
+    }
 
+    /// Mood change, fading linearly over the duration of the thought.
+    pub fn mood(&self) -> f32 {
+        self.value * self.impacts * self.duration / self.kind.duration()
+    }
+}
+
+/// The thoughts of a pawn, which sum into its mood.
+#[derive(Component, Clone, Debug, Default)]
+#[storage(DenseVecStorage)]
 pub struct Personality {
+    /// Mood with no thoughts at all, from -1 to 1.
+    pub base_mood: f32,
+    thoughts: Vec<Thought>,
+}
+impl Personality {
+    pub fn think(&mut self, kind: ThoughtKind) {
+        if let Some(thought) = self
+            .thoughts
+            .iter_mut()
+            .find(|thought| thought.kind == kind)
+        {
+            thought.impacts = (thought.impacts + 1.0).min(kind.max_impacts());
+            thought.duration = kind.duration();
+            return;
+        }
+
+        if self.thoughts.len() >= max_thoughts {
+            let fading = self
+                .thoughts
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+                .map(|(index, _)| index)
+                .unwrap();
+            self.thoughts.swap_remove(fading);
+        }
+        self.thoughts.push(Thought::new(kind));
+    }
+
+    /// Fades every thought, forgetting those which ran out.
+    pub fn tick(&mut self, elapsed: u64) {
+        for thought in &mut self.thoughts {
+            thought.duration -= elapsed as f32;
+        }
+        self.thoughts.retain(|thought| thought.duration > 0.0);
+    }
+
+    pub fn thoughts(&self) -> impl Iterator<Item = &Thought> {
+        self.thoughts.iter()
+    }
+
+    /// From -1, miserable, to 1, elated.
+    pub fn mood(&self) -> f32 {
+        let thoughts: f32 = self.thoughts.iter().map(Thought::mood).sum();
+        (self.base_mood + thoughts).max(-1.0).min(1.0)
+    }
+
+    /// Multiplier of how fast the pawn works.
+    pub fn work_speed(&self) -> f32 {
+        1.0 + self.mood() * mood_work_speed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replan_backoff_doubles() {
+        let mut ai = AI::default();
+        assert!(ai.needs_plan(0));
+
+        ai.set_plan(&[0, 1]);
+        assert!(!ai.needs_plan(0));
+
+        ai.fail(100);
+        assert_eq!(ai.status, PlanStatus::Failed);
+        assert!(ai.action_queue.pop().is_err());
+        assert!(!ai.needs_plan(100 + replan_backoff - 1));
+        assert!(ai.needs_plan(100 + replan_backoff));
+
+        ai.set_plan(&[0]);
+        ai.fail(200);
+        assert_eq!(ai.replan_at, 200 + replan_backoff * 2);
+        for _ in 0..20 {
+            ai.fail(300);
+        }
+        assert_eq!(ai.replan_at, 300 + max_replan_backoff);
+
+        ai.complete();
+        ai.set_plan(&[0]);
+        ai.fail(400);
+        assert_eq!(ai.replan_at, 400 + replan_backoff);
+    }
+
+    #[test]
+    fn needs_grow_and_kill() {
+        let mut needs = Needs::default();
+        needs.hunger = Need::new(0.125, 0.5);
+        needs.thirst = Need::new(0.0, 0.5);
+
+        assert!(!needs.tick(3));
+        assert_eq!(needs.hunger.urgency(), 0.0);
+        assert!(needs.tick(1));
+        assert!(needs.hunger.urgency() > 0.0);
+        assert!(!needs.tick(1));
+
+        needs.tick(100);
+        assert!(needs.hunger.is_extreme());
+        assert!(!needs.is_dying());
+        needs.tick(lethal_deprivation);
+        assert!(needs.is_dying());
 
-    thoughts: BinaryHeap<Thought>,
-}
\ No newline at end of file
+        needs.hunger.satisfy(1.0);
+        needs.tick(1);
+        assert_eq!(needs.deprivation, 0);
+        assert!(!needs.hunger.is_urgent());
+
+        // Clothing keeps the cold off
+        let (mut bare, mut dressed) = (Needs::default(), Needs::default());
+        dressed.insulation = 1.0;
+        bare.tick(100);
+        dressed.tick(100);
+        assert!((bare.cold.value - dressed.cold.value * 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn schedules() {
+        let schedule = Schedule::default();
+        assert_eq!(schedule.activity(23), Activity::Sleep);
+        assert_eq!(schedule.activity(3), Activity::Sleep);
+        assert_eq!(schedule.activity(12), Activity::Work);
+        assert_eq!(schedule.activity(19), Activity::Leisure);
+
+        let labor = Motive::Labor(ActionCatagory::Mining);
+        assert!(Activity::Work.allows(&labor));
+        assert!(!Activity::Sleep.allows(&labor));
+        assert!(!Activity::Work.allows(&Motive::Idle));
+        assert!(Activity::Sleep.allows(&Motive::Fatigue));
+        assert!(Activity::Sleep.allows(&Motive::Hunger));
+    }
+
+    #[test]
+    fn action_progress_and_interruption() {
+        let mut current = CurrentAction {
+            action: 0,
+            event: ActionEvent::Pickup,
+            catagory: ActionCatagory::Mining,
+            progress: 0,
+            required: 10,
+        };
+        assert_eq!(current.advance(4), 4);
+        assert!(current.interruptible_by(&Motive::Hunger));
+        assert_eq!(current.advance(4), 4);
+        assert!(!current.interruptible_by(&Motive::Hunger));
+        assert!(current.interruptible_by(&Motive::Danger));
+        assert_eq!(current.advance(4), 2);
+        assert!(current.is_finished());
+
+        // Hauling is dropped for needs however far along it is
+        current.catagory = ActionCatagory::HaulingWood;
+        assert!(current.interruptible_by(&Motive::Thirst));
+        assert!(!current.interruptible_by(&Motive::Idle));
+
+        let mut ai = AI::default();
+        ai.set_plan(&[0]);
+        current.catagory = ActionCatagory::Mining;
+        assert!(!ai.interrupt_for(&Motive::Fatigue, Some(&current)));
+        assert!(ai.interrupt_for(&Motive::Danger, Some(&current)));
+        assert_eq!(ai.status, PlanStatus::Idle);
+        assert!(!ai.interrupt_for(&Motive::Danger, None));
+
+        let mut skills = Skills::default();
+        skills.set_level(ActionCatagory::Mining, 50.0);
+        assert_eq!(skills.speed(&ActionCatagory::Mining), 3.0);
+        assert_eq!(skills.speed(&ActionCatagory::Farming), 1.0);
+    }
+
+    #[test]
+    fn threat_assessment() {
+        use amethyst::ecs::{Builder, World};
+
+        let mut world = World::new();
+        let wolf = world.create_entity().build();
+
+        let armed = combat_strength(1.0, 1.0, 0.0);
+        let threat = Threat::assess(armed, combat_strength(1.0, 0.0, 0.0), 1.0, wolf);
+        assert_eq!(threat.response, ThreatResponse::Fight(wolf));
+        assert_eq!(threat.odds, 2.0);
+        assert_eq!(threat.goal().motive, Motive::Danger);
+
+        // Outnumbered, or too hurt to fight
+        let pack = combat_strength(1.0, 0.0, 0.0) * 3.0;
+        assert_eq!(
+            Threat::assess(armed, pack, 1.0, wolf).response,
+            ThreatResponse::Flee
+        );
+        assert_eq!(
+            Threat::assess(armed, 1.0, flee_health, wolf).response,
+            ThreatResponse::Flee
+        );
+        assert_eq!(
+            *Threat::assess(1.0, 2.0, 1.0, wolf).goal().conditions[0].equality(),
+            ConditionEquality::Not
+        );
+    }
+
+    #[test]
+    fn perception_memory() {
+        use amethyst::ecs::{Builder, World};
+
+        let mut world = World::new();
+        let tree = world.create_entity().build();
+        let far_tree = world.create_entity().build();
+
+        let mut perception = Perception {
+            memory: 100,
+            ..Perception::default()
+        };
+        let percepts = vec![Percept::Thing(ConditionValue::Tree)];
+        perception.observe(tree, percepts.clone(), Vector3::new(5, 5, 0), 0);
+        perception.observe(far_tree, percepts, Vector3::new(50, 5, 0), 50);
+
+        let near = |range| {
+            Condition::new(
+                ConditionEquality::Is,
+                ConditionType::Near(range),
+                ConditionValue::Tree,
+            )
+        };
+        let position = Vector3::new(4, 4, 0);
+        assert!(perception.satisfies(&near(1), position));
+        assert!(!perception.satisfies(
+            &Condition::new(
+                ConditionEquality::Not,
+                ConditionType::Near(1),
+                ConditionValue::Tree
+            ),
+            position
+        ));
+        assert_eq!(
+            perception.confidence(perception.get(tree).unwrap(), 50),
+            0.5
+        );
+
+        let mut blackboard = Blackboard::default();
+        let fact = perception.binding(&near(1), position).unwrap();
+        blackboard.bind(near(1), fact.entity, fact.position);
+        assert_eq!(blackboard.get(&near(1)), Some(tree));
+        assert_eq!(blackboard.position(&near(1)), Some(Vector3::new(5, 5, 0)));
+
+        // The close tree is forgotten, but the far one is still remembered
+        perception.forget(100);
+        assert!(perception.get(tree).is_none());
+        assert!(!perception.satisfies(&near(1), position));
+        assert!(perception.satisfies(&near(46), position));
+    }
+
+    #[test]
+    fn thoughts_and_mood() {
+        let mut personality = Personality::default();
+        assert_eq!(personality.mood(), 0.0);
+        assert_eq!(personality.work_speed(), 1.0);
+
+        personality.think(ThoughtKind::AteGoodMeal);
+        personality.think(ThoughtKind::AteGoodMeal);
+        assert_eq!(personality.mood(), ThoughtKind::AteGoodMeal.value());
+        for _ in 0..10 {
+            personality.think(ThoughtKind::SawCorpse);
+        }
+        assert_eq!(personality.thoughts().last().unwrap().impacts, 5.0);
+        assert_eq!(personality.mood(), -1.0);
+        assert!(personality.work_speed() < 1.0);
+
+        // Thoughts fade, and are forgotten once they run out
+        personality.tick(600);
+        assert_eq!(personality.thoughts().count(), 1);
+        assert!(personality.mood() > -1.0);
+        personality.tick(1800);
+        assert_eq!(personality.thoughts().count(), 0);
+        assert_eq!(personality.mood(), 0.0);
+    }
+}
@@ -0,0 +1,225 @@
+use amethyst::ecs::{Component, DenseVecStorage, Entity};
+use failure::Fail;
+use specs_derive::Component;
+
+/// Where on the body something is worn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Slot {
+    Head,
+    Torso,
+    Hands,
+    Feet,
+    /// Carried in hand, such as a tool or weapon. Only one thing is held at a time.
+    Held,
+}
+impl Default for Slot {
+    fn default() -> Self {
+        Slot::Held
+    }
+}
+
+/// Slots armor is spread over.
+pub const body_slots: [Slot; 4] = [Slot::Head, Slot::Torso, Slot::Hands, Slot::Feet];
+
+/// How close to the skin clothing is worn. Each slot takes a layer of each, put on from the inside
+/// out and taken off from the outside in.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum ClothingLayer {
+    Underwear,
+    Shirt,
+    Coat,
+}
+impl Default for ClothingLayer {
+    fn default() -> Self {
+        ClothingLayer::Shirt
+    }
+}
+
+/// How an item is worn, and what wearing it does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Wearable {
+    pub slot: Slot,
+    pub layer: ClothingLayer,
+    /// Slows how fast the wearer gets cold.
+    pub insulation: f32,
+    pub armor: f32,
+}
+
+#[derive(Debug, Fail, PartialEq)]
+pub enum EquipError {
+    #[fail(display = "{:?} can't be worn", _0)]
+    NotWearable(Entity),
+    #[fail(display = "{:?} is already equipped", _0)]
+    AlreadyEquipped(Entity),
+    #[fail(display = "{:?} is not equipped", _0)]
+    NotEquipped(Entity),
+    #[fail(display = "something is already worn on the {:?} as {:?}", _0, _1)]
+    SlotTaken(Slot, ClothingLayer),
+    #[fail(display = "the {:?} is covered by something worn over it", _0)]
+    Covered(Slot),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Worn {
+    pub item: Entity,
+    pub wearable: Wearable,
+}
+
+/// What an entity is wearing and holding, see `WearingSystem`.
+#[derive(Component, Clone, Debug, Default)]
+#[storage(DenseVecStorage)]
+pub struct Equipped {
+    worn: Vec<Worn>,
+}
+impl Equipped {
+    pub fn worn(&self) -> impl Iterator<Item = &Worn> {
+        self.worn.iter()
+    }
+
+    pub fn contains(&self, item: Entity) -> bool {
+        self.worn.iter().any(|worn| worn.item == item)
+    }
+
+    pub fn get(&self, slot: Slot, layer: ClothingLayer) -> Option<Entity> {
+        self.worn
+            .iter()
+            .find(|worn| worn.wearable.slot == slot && worn.wearable.layer == layer)
+            .map(|worn| worn.item)
+    }
+
+    pub fn held(&self) -> Option<Entity> {
+        self.worn
+            .iter()
+            .find(|worn| worn.wearable.slot == Slot::Held)
+            .map(|worn| worn.item)
+    }
+
+    fn is_covered(&self, wearable: &Wearable) -> bool {
+        wearable.slot != Slot::Held
+            && self.worn.iter().any(|worn| {
+                worn.wearable.slot == wearable.slot && worn.wearable.layer > wearable.layer
+            })
+    }
+
+    pub fn equip(&mut self, item: Entity, wearable: Wearable) -> Result<(), EquipError> {
+        if self.contains(item) {
+            return Err(EquipError::AlreadyEquipped(item));
+        }
+        let taken = if wearable.slot == Slot::Held {
+            self.held().is_some()
+        } else {
+            self.get(wearable.slot, wearable.layer).is_some()
+        };
+        if taken {
+            return Err(EquipError::SlotTaken(wearable.slot, wearable.layer));
+        }
+        if self.is_covered(&wearable) {
+            return Err(EquipError::Covered(wearable.slot));
+        }
+
+        self.worn.push(Worn { item, wearable });
+        Ok(())
+    }
+
+    pub fn unequip(&mut self, item: Entity) -> Result<Wearable, EquipError> {
+        let index = self
+            .worn
+            .iter()
+            .position(|worn| worn.item == item)
+            .ok_or(EquipError::NotEquipped(item))?;
+        let wearable = self.worn[index].wearable;
+        if self.is_covered(&wearable) {
+            return Err(EquipError::Covered(wearable.slot));
+        }
+
+        self.worn.remove(index);
+        Ok(wearable)
+    }
+
+    /// Forgets items for which `keep` no longer holds, such as those destroyed or dropped.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(Entity) -> bool,
+    {
+        self.worn.retain(|worn| keep(worn.item));
+    }
+
+    pub fn insulation(&self) -> f32 {
+        self.worn.iter().map(|worn| worn.wearable.insulation).sum()
+    }
+
+    /// Armor over `slot`, from every layer worn there.
+    pub fn armor(&self, slot: Slot) -> f32 {
+        self.worn
+            .iter()
+            .filter(|worn| worn.wearable.slot == slot)
+            .map(|worn| worn.wearable.armor)
+            .sum()
+    }
+
+    /// Armor averaged over the body.
+    pub fn body_armor(&self) -> f32 {
+        body_slots.iter().map(|slot| self.armor(*slot)).sum::<f32>() / body_slots.len() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst::ecs::{Builder, World};
+
+    #[test]
+    fn layering() {
+        let mut world = World::new();
+        let mut item = || world.create_entity().build();
+        let (undershirt, shirt, coat, vest, axe, sword) =
+            (item(), item(), item(), item(), item(), item());
+        let torso = |layer, insulation, armor| Wearable {
+            slot: Slot::Torso,
+            layer,
+            insulation,
+            armor,
+        };
+
+        let mut equipped = Equipped::default();
+        equipped
+            .equip(shirt, torso(ClothingLayer::Shirt, 1.0, 0.0))
+            .unwrap();
+        equipped
+            .equip(coat, torso(ClothingLayer::Coat, 2.0, 4.0))
+            .unwrap();
+        assert_eq!(
+            equipped.equip(undershirt, torso(ClothingLayer::Underwear, 1.0, 0.0)),
+            Err(EquipError::Covered(Slot::Torso))
+        );
+        assert_eq!(
+            equipped.equip(vest, torso(ClothingLayer::Coat, 1.0, 2.0)),
+            Err(EquipError::SlotTaken(Slot::Torso, ClothingLayer::Coat))
+        );
+        assert_eq!(equipped.insulation(), 3.0);
+        assert_eq!(equipped.armor(Slot::Torso), 4.0);
+        assert_eq!(equipped.body_armor(), 1.0);
+
+        // Taken off from the outside in
+        assert_eq!(
+            equipped.unequip(shirt),
+            Err(EquipError::Covered(Slot::Torso))
+        );
+        equipped.unequip(coat).unwrap();
+        equipped.unequip(shirt).unwrap();
+        assert_eq!(equipped.unequip(shirt), Err(EquipError::NotEquipped(shirt)));
+
+        let held = Wearable {
+            slot: Slot::Held,
+            ..Wearable::default()
+        };
+        equipped.equip(axe, held).unwrap();
+        assert_eq!(
+            equipped.equip(sword, held),
+            Err(EquipError::SlotTaken(Slot::Held, ClothingLayer::Shirt))
+        );
+        assert_eq!(equipped.held(), Some(axe));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::systems::time::Season;
 use crate::utils::HasChannel;
 use amethyst::{
     assets::Handle,
@@ -11,11 +12,40 @@ use serde::{Deserialize, Serialize};
 use specs_derive::Component;
 
 pub mod ai;
+pub mod equipment;
 
+/// Marks the actors whose turn it is, see `InitiativeSystem`.
 #[derive(Component, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[storage(NullStorage)]
 pub struct IsTurn;
 
+/// How quickly an actor earns `TimeAvailable` as game time passes, 1 being a unit of time for
+/// each unit passed.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Initiative {
+    pub speed: f32,
+    /// Time earned short of a whole unit.
+    carry: f32,
+}
+impl Default for Initiative {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+impl Initiative {
+    pub fn new(speed: f32) -> Self {
+        Self { speed, carry: 0.0 }
+    }
+
+    /// The whole units of time earned over `elapsed` game time.
+    pub fn earn(&mut self, elapsed: u64) -> u64 {
+        let earned = self.carry + self.speed.max(0.0) * elapsed as f32;
+        self.carry = earned.fract();
+        earned.trunc() as u64
+    }
+}
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct Actionable {
     #[serde(skip_serializing, skip_deserializing)]
@@ -35,7 +65,14 @@ impl HasChannel<crate::actions::Action> for Actionable {
 }
 
 #[derive(
-    Clone, Debug, PartialEq, Deserialize, Serialize, strum_macros::EnumString, strum_macros::Display,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    strum_macros::EnumString,
+    strum_macros::Display,
 )]
 pub enum TreeFamily {
     Deciduous,
@@ -43,7 +80,14 @@ pub enum TreeFamily {
 }
 
 #[derive(
-    Clone, Debug, PartialEq, Deserialize, Serialize, strum_macros::EnumString, strum_macros::Display,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    strum_macros::EnumString,
+    strum_macros::Display,
 )]
 pub enum TreeKind {
     Pine,   //(TreeFamily::Coniferous),
@@ -57,6 +101,20 @@ pub enum TreeKind {
     Birch,  //(TreeFamily::Deciduous),
     Willow, //(TreeFamily::Deciduous)
 }
+impl TreeKind {
+    pub fn family(self) -> TreeFamily {
+        match self {
+            TreeKind::Pine | TreeKind::Fur | TreeKind::Spruce | TreeKind::Cedar => {
+                TreeFamily::Coniferous
+            }
+            TreeKind::Oak
+            | TreeKind::Elm
+            | TreeKind::Maple
+            | TreeKind::Birch
+            | TreeKind::Willow => TreeFamily::Deciduous,
+        }
+    }
+}
 
 #[derive(
     Component,
@@ -82,6 +140,212 @@ impl Default for Obstruction {
 #[storage(NullStorage)]
 pub struct ZTransition;
 
+/// The ground underfoot of a tile, which slows walkers down.
+#[derive(
+    Component,
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    strum_macros::EnumString,
+    strum_macros::Display,
+)]
+#[storage(DenseVecStorage)]
+pub enum Footing {
+    Mud,
+    Ice,
+    Snow,
+}
+impl Footing {
+    /// Multiplier applied to the cost of walking onto the tile.
+    pub fn cost(self) -> f32 {
+        match self {
+            Footing::Mud => 2.0,
+            Footing::Ice => 1.5,
+            Footing::Snow => 1.5,
+        }
+    }
+}
+
+/// Depth of standing fluid on a tile, from 1 to `max_fluid_depth`.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FluidDepth(pub u8);
+impl Component for FluidDepth {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+pub const max_fluid_depth: u8 = 7;
+
+/// Light cast on a tile by fires, from 0.0 to 1.0, a fully lit tile being drawn at full
+/// brightness whatever the daylight.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LightLevel(pub f32);
+impl Component for LightLevel {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+/// A tile which pawns can open to pass through.
+#[derive(Component, Default, Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Doorway {
+    pub locked: bool,
+    /// Creatures other than pawns aren't let through.
+    pub forbid_pets: bool,
+}
+impl Doorway {
+    /// Whether the door opens for someone, `pet` being whether they are a creature.
+    pub fn admits(self, pet: bool) -> bool {
+        !self.locked && !(pet && self.forbid_pets)
+    }
+}
+
+/// Game time a door takes to swing open.
+pub const door_open_time: u64 = 2;
+/// Game time a door stays open after the last one through.
+pub const door_close_delay: u64 = 10;
+/// Sprites of closed and open doors, as indices into the cp437 sheet.
+pub const door_closed_sprite_number: usize = 43;
+pub const door_open_sprite_number: usize = 39;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DoorState {
+    Closed,
+    Opening { since: u64 },
+    Open { until: u64 },
+}
+impl Default for DoorState {
+    fn default() -> Self {
+        DoorState::Closed
+    }
+}
+
+/// A door standing in the `Doorway` of its tile, which is `Impassable` while the door is shut.
+/// `DoorSystem` opens it for those about to pass, and closes it again a while after.
+#[derive(Component, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Door {
+    pub state: DoorState,
+    pub locked: bool,
+    pub forbid_pets: bool,
+}
+impl Door {
+    pub fn new(locked: bool, forbid_pets: bool) -> Self {
+        Self {
+            state: DoorState::Closed,
+            locked,
+            forbid_pets,
+        }
+    }
+
+    pub fn doorway(&self) -> Doorway {
+        Doorway {
+            locked: self.locked,
+            forbid_pets: self.forbid_pets,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        match self.state {
+            DoorState::Open { .. } => true,
+            DoorState::Closed | DoorState::Opening { .. } => false,
+        }
+    }
+
+    pub fn sprite_number(&self) -> usize {
+        if self.is_open() {
+            door_open_sprite_number
+        } else {
+            door_closed_sprite_number
+        }
+    }
+
+    /// Swings the door at time `now`, `waiting` being whether anyone it admits wants through and
+    /// `blocked` whether anyone stands in the doorway. Returns whether it opened or shut.
+    pub fn update(&mut self, now: u64, waiting: bool, blocked: bool) -> bool {
+        let was_open = self.is_open();
+        self.state = match self.state {
+            DoorState::Closed if waiting && !self.locked => DoorState::Opening { since: now },
+            DoorState::Opening { .. } if !waiting || self.locked => DoorState::Closed,
+            DoorState::Opening { since } if now.saturating_sub(since) >= door_open_time => {
+                DoorState::Open {
+                    until: now + door_close_delay,
+                }
+            }
+            // Doors can't shut on someone in the way
+            DoorState::Open { .. } if blocked || (waiting && !self.locked) => DoorState::Open {
+                until: now + door_close_delay,
+            },
+            DoorState::Open { until } if now >= until || self.locked => DoorState::Closed,
+            state => state,
+        };
+        was_open != self.is_open()
+    }
+}
+
+/// Per-player field of view state of a tile, one bit per player index.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Visibility {
+    pub visible: u32,
+    pub explored: u32,
+}
+impl Component for Visibility {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+impl Visibility {
+    pub fn is_visible(self, player: u8) -> bool {
+        self.visible & (1 << player) != 0
+    }
+
+    pub fn is_explored(self, player: u8) -> bool {
+        self.explored & (1 << player) != 0
+    }
+
+    pub fn reveal(&mut self, player: u8) {
+        self.visible |= 1 << player;
+        self.explored |= 1 << player;
+    }
+
+    pub fn hide(&mut self, player: u8) {
+        self.visible &= !(1 << player);
+    }
+}
+
+/// An entity moved by the player's input, which the camera follows.
+#[derive(Component, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(NullStorage)]
+pub struct PlayerControlled;
+
+/// A player controlled entity, whose sight updates tile `Visibility`.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Player {
+    /// Bit index of this player in `Visibility`, below 32.
+    pub index: u8,
+    pub sight_range: u32,
+}
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            index: 0,
+            sight_range: 20,
+        }
+    }
+}
+
+/// Size of a tree when it sprouts, and when fully grown.
+pub const sapling_size: f32 = 0.1;
+pub const max_tree_size: f32 = 1.0;
+/// Size a tree grows per unit of game time, in the season it grows fastest.
+pub const tree_growth: f32 = 0.00005;
+/// Logs, and branches, a fully grown tree yields.
+pub const logs_per_tree: f32 = 4.0;
+pub const branches_per_tree: f32 = 6.0;
+/// Game time after being felled before a stump sprouts a sapling.
+pub const stump_regrowth_time: u64 = crate::systems::time::year_length;
+
+/// A growing tree, felled for logs and branches by its size.
 #[derive(Component, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 #[storage(DenseVecStorage)]
 pub struct Tree {
@@ -89,6 +353,67 @@ pub struct Tree {
     size: f32,
     branches: f32,
 }
+impl Tree {
+    pub fn new(kind: TreeKind, size: f32) -> Self {
+        let size = size.max(0.0).min(max_tree_size);
+        Self {
+            kind,
+            size,
+            branches: size * branches_per_tree,
+        }
+    }
+
+    pub fn kind(&self) -> TreeKind {
+        self.kind
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    /// Share of its growth rate a tree keeps in `season`. Conifers keep growing a little through
+    /// the cold, while broadleaves go dormant.
+    pub fn seasonal_growth(&self, season: Season) -> f32 {
+        match (season, self.kind.family()) {
+            (Season::Spring, _) => 1.0,
+            (Season::Summer, _) => 0.8,
+            (Season::Autumn, TreeFamily::Coniferous) => 0.4,
+            (Season::Autumn, TreeFamily::Deciduous) => 0.2,
+            (Season::Winter, TreeFamily::Coniferous) => 0.1,
+            (Season::Winter, TreeFamily::Deciduous) => 0.0,
+        }
+    }
+
+    /// Grows over `elapsed` game time in `season`, regrowing branches broken off as it goes.
+    pub fn grow(&mut self, elapsed: u64, season: Season) {
+        let growth = tree_growth * self.seasonal_growth(season) * elapsed as f32;
+        self.size = (self.size + growth).min(max_tree_size);
+        self.branches =
+            (self.branches + growth * branches_per_tree).min(self.size * branches_per_tree);
+    }
+
+    /// How much longer than usual felling takes, for its size and an axe of `quality`.
+    pub fn chop_time(&self, quality: f32) -> f32 {
+        self.size / quality.max(0.01)
+    }
+
+    /// The logs and branches the tree yields when felled.
+    pub fn yields(&self) -> (usize, usize) {
+        (
+            ((self.size * logs_per_tree).floor() as usize).max(1),
+            self.branches.floor() as usize,
+        )
+    }
+}
+
+/// What is left of a felled tree, on its tile, until it regrows.
+#[derive(Component, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Stump {
+    pub kind: TreeKind,
+    /// The game time a sapling sprouts from it.
+    pub regrows_at: u64,
+}
 
 #[derive(Component, Default, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 #[storage(DenseVecStorage)]
@@ -103,6 +428,10 @@ impl TimeAvailable {
     pub fn add(&mut self, time: u64) {
         self.0 += time;
     }
+    /// Adds `time`, banking no more than `max`.
+    pub fn add_capped(&mut self, time: u64, max: u64) {
+        self.0 = (self.0 + time).min(max.max(self.0));
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -117,9 +446,148 @@ impl Component for FlaggedSpriteRender {
     type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
 }
 
+/// The layers a tile is composed of, in the order they are drawn.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    strum_macros::EnumString,
+    strum_macros::Display,
+)]
+pub enum TileLayer {
+    Terrain,
+    Floor,
+    Construction,
+    Fluid,
+}
+impl TileLayer {
+    pub const ALL: [TileLayer; 4] = [
+        TileLayer::Terrain,
+        TileLayer::Floor,
+        TileLayer::Construction,
+        TileLayer::Fluid,
+    ];
+
+    /// Depth bias applied so later layers are composited over earlier ones.
+    pub fn depth_offset(self) -> f32 {
+        self as u8 as f32 * 0.01
+    }
+}
+
+/// A tile storage holding the sprite of one `TileLayer`.
+pub trait TileLayerSprite: Component {
+    const LAYER: TileLayer;
+
+    fn new(sprite: FlaggedSpriteRender) -> Self;
+    fn sprite(&self) -> &FlaggedSpriteRender;
+}
+
+macro_rules! tile_layer {
+    ($name:ident, $layer:expr) => {
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $name(pub FlaggedSpriteRender);
+        impl Component for $name {
+            type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+        }
+        impl TileLayerSprite for $name {
+            const LAYER: TileLayer = $layer;
+
+            fn new(sprite: FlaggedSpriteRender) -> Self {
+                $name(sprite)
+            }
+
+            fn sprite(&self) -> &FlaggedSpriteRender {
+                &self.0
+            }
+        }
+    };
+}
+
+tile_layer!(TerrainLayer, TileLayer::Terrain);
+tile_layer!(FloorLayer, TileLayer::Floor);
+tile_layer!(ConstructionLayer, TileLayer::Construction);
+tile_layer!(FluidLayer, TileLayer::Fluid);
+
+/// A tile whose sprite on `layer` cycles through `frames`, each shown for `frame_duration`
+/// seconds of real time. Neighbouring tiles are a frame apart, so animated water ripples.
 #[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[storage(DenseVecStorage)]
-pub struct Container;
+pub struct AnimatedTile {
+    pub layer: TileLayer,
+    /// Sprite numbers on the sheet of the layer's sprite.
+    pub frames: Vec<usize>,
+    pub frame_duration: f32,
+}
+impl AnimatedTile {
+    /// The sprite number shown `seconds` into the animation, `phase` frames ahead.
+    pub fn frame(&self, seconds: f64, phase: usize) -> Option<usize> {
+        if self.frames.is_empty() || self.frame_duration <= 0.0 {
+            return self.frames.first().cloned();
+        }
+        let elapsed = (seconds / f64::from(self.frame_duration)) as usize;
+        Some(self.frames[(elapsed + phase) % self.frames.len()])
+    }
+}
+
+/// Share of a container's outer volume it can be filled with.
+pub const container_fill: f32 = 0.8;
+/// Weight a container holds for each unit of volume it can be filled with, enough to fill it with
+/// water.
+pub const container_load: f32 = 1200.0;
+
+/// An item which other items can be put in, see `inventory::insert`.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Container {
+    pub volume: f32,
+    pub weight: f32,
+    /// Liquid can only be held by watertight containers.
+    pub can_hold: crate::assets::item::ContainerCanHold,
+}
+impl Container {
+    /// The container an item of these details makes, sized by its own size.
+    pub fn from_details(details: &crate::assets::Item) -> Option<Self> {
+        let can_hold = details.can_hold()?;
+        let volume = details.volume() * container_fill;
+        Some(Self {
+            volume,
+            weight: volume * container_load,
+            can_hold,
+        })
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            current: 100.0,
+            max: 100.0,
+        }
+    }
+}
+impl Health {
+    pub fn fraction(&self) -> f32 {
+        (self.current / self.max).max(0.0)
+    }
+}
+
+/// Somewhere for a pawn to sleep.
+#[derive(Component, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(NullStorage)]
+pub struct Bed;
 
 #[derive(Component, Clone, Debug)]
 #[storage(DenseVecStorage)]
@@ -161,6 +629,57 @@ impl TilePosition {
     }
 }
 
+/// A computed path, written by `PathfindingSystem` in response to a `PathRequest`. Points are
+/// adjacent tiles, unless the path was smoothed into waypoints with `PathFlags::Smooth`.
+#[derive(Component, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Path {
+    pub points: Vec<Vector3<u32>>,
+    pub cost: f32,
+    /// Index of the next point to move to.
+    pub current: usize,
+}
+impl Path {
+    pub fn new(points: Vec<Vector3<u32>>, cost: f32) -> Self {
+        // The first point is where the path started
+        Self {
+            points,
+            cost,
+            current: 1,
+        }
+    }
+
+    pub fn next(&self) -> Option<Vector3<u32>> {
+        self.points.get(self.current).cloned()
+    }
+
+    pub fn advance(&mut self) {
+        self.current += 1;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.points.len()
+    }
+
+    pub fn goal(&self) -> Option<Vector3<u32>> {
+        self.points.last().cloned()
+    }
+}
+
+/// Incremental planner kept for entities which requested `PathFlags::Incremental`, so their
+/// `Path` is repaired rather than recomputed when tiles change.
+#[derive(Component, Clone, Debug)]
+#[storage(DenseVecStorage)]
+pub struct Replanner {
+    pub planner: crate::pathfinding::DStarLite,
+    pub profile: crate::pathfinding::MovementProfile,
+}
+
+/// Marks that the last `PathRequest` of an entity found no path.
+#[derive(Component, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(NullStorage)]
+pub struct PathFailed;
+
 bitflags_serial! {
     pub struct InteractionType: u64 {
         const None =                0;
@@ -179,11 +698,58 @@ bitflags_serial! {
 #[storage(DenseVecStorage)]
 pub struct Interactable(InteractionType);
 
-#[derive(Component, Default, Clone, Debug, Serialize, Deserialize)]
+/// Wear on something made of a material, see `DurabilitySystem`.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MaterialStatus {
-    // TODO: Deterioration? Damage? HP?
+    pub hp: f32,
+    pub max_hp: f32,
+}
+impl Default for MaterialStatus {
+    fn default() -> Self {
+        Self::new(crate::assets::material::min_durability)
+    }
+}
+impl MaterialStatus {
+    pub fn new(max_hp: f32) -> Self {
+        Self { hp: max_hp, max_hp }
+    }
+
+    pub fn from_material(material: &crate::assets::material::Material) -> Self {
+        Self::new(material.durability())
+    }
+
+    /// Wears it down, returning whether this broke it.
+    pub fn damage(&mut self, amount: f32) -> bool {
+        let was_broken = self.is_broken();
+        self.hp = (self.hp - amount).max(0.0);
+        !was_broken && self.is_broken()
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.hp <= 0.0
+    }
+
+    /// From 1 when new to 0 when broken.
+    pub fn condition(&self) -> f32 {
+        self.hp / self.max_hp
+    }
+}
+
+/// Wear from using an item, such as a tool.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wear {
+    pub item: Entity,
+    pub amount: f32,
 }
 
+/// Sent by `DurabilitySystem` as it destroys a worn out item, naming what it was replaced by.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ItemBroken {
+    pub item: Entity,
+    pub replaced_by: Option<String>,
+}
+
+/// What something is made of, by its handle in the `MaterialStorage`, and how worn it is.
 #[derive(Component, Clone, Debug)]
 #[storage(DenseVecStorage)]
 pub struct TileMaterial {
@@ -191,3 +757,101 @@ pub struct TileMaterial {
     status: MaterialStatus,
 
 }
+impl TileMaterial {
+    /// Made of `details`, found in the `MaterialStorage` under `material`, and unworn.
+    pub fn new(
+        material: Handle<crate::assets::material::Material>,
+        details: &crate::assets::material::Material,
+    ) -> Self {
+        Self {
+            material,
+            status: MaterialStatus::from_material(details),
+        }
+    }
+
+    pub fn material(&self) -> &Handle<crate::assets::material::Material> {
+        &self.material
+    }
+
+    pub fn status(&self) -> &MaterialStatus {
+        &self.status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animated_tile() {
+        let water = AnimatedTile {
+            layer: TileLayer::Fluid,
+            frames: vec![247, 126],
+            frame_duration: 0.5,
+        };
+        assert_eq!(water.frame(0.0, 0), Some(247));
+        assert_eq!(water.frame(0.7, 0), Some(126));
+        assert_eq!(water.frame(1.2, 0), Some(247));
+        assert_eq!(water.frame(0.0, 3), Some(126));
+
+        let still = AnimatedTile {
+            frame_duration: 0.0,
+            ..water
+        };
+        assert_eq!(still.frame(10.0, 1), Some(247));
+    }
+
+    #[test]
+    fn tree_lifecycle() {
+        let mut oak = Tree::new(TreeKind::Oak, sapling_size);
+        let mut pine = Tree::new(TreeKind::Pine, sapling_size);
+        oak.grow(1000, Season::Winter);
+        pine.grow(1000, Season::Winter);
+        assert_eq!(oak.size(), sapling_size);
+        assert!(pine.size() > sapling_size);
+
+        oak.grow(1000, Season::Spring);
+        assert!(oak.size() > sapling_size);
+        oak.grow(std::u32::MAX as u64, Season::Summer);
+        assert_eq!(oak.size(), max_tree_size);
+
+        // Bigger trees yield more, and take longer without a good axe
+        let sapling = Tree::new(TreeKind::Oak, sapling_size);
+        assert!(oak.yields().0 > sapling.yields().0);
+        assert!(oak.yields().1 > sapling.yields().1);
+        assert!(oak.chop_time(1.0) > sapling.chop_time(1.0));
+        assert!(oak.chop_time(5.0) < oak.chop_time(1.0));
+    }
+
+    #[test]
+    fn doors() {
+        let mut door = Door::new(false, true);
+        assert!(door.doorway().admits(false));
+        assert!(!door.doorway().admits(true));
+
+        // Opens after a delay for those waiting, and shuts a while after they're through
+        assert!(!door.update(0, true, false));
+        assert!(!door.update(1, true, false));
+        assert!(door.update(door_open_time, true, false));
+        assert_eq!(door.sprite_number(), door_open_sprite_number);
+        assert!(!door.update(5, false, true));
+        assert!(!door.update(5 + door_close_delay - 1, false, false));
+        assert!(door.update(5 + door_close_delay, false, false));
+        assert_eq!(door.state, DoorState::Closed);
+
+        // Giving up on it leaves it shut
+        door.update(100, true, false);
+        door.update(101, false, false);
+        assert!(!door.update(100 + door_open_time, false, false));
+
+        // Locking it shuts it once clear, and it won't open again
+        door.update(200, true, false);
+        door.update(200 + door_open_time, true, false);
+        door.locked = true;
+        assert!(!door.update(210, false, true));
+        assert!(door.update(211, false, false));
+        assert!(!door.update(300, true, false));
+        assert!(!door.update(300 + door_open_time, true, false));
+        assert!(!door.doorway().admits(false));
+    }
+}
@@ -0,0 +1,179 @@
+use amethyst::ecs::{Component, DenseVecStorage, NullStorage};
+use serde::{Deserialize, Serialize};
+use specs_derive::Component;
+
+use crate::actions::Action as ActionEvent;
+use crate::goap::{
+    self, ActionCatagory, Condition, ConditionEquality, ConditionType, ConditionValue,
+};
+use crate::systems::nutrition::Food;
+
+/// Quality of a meal cooked without any skill.
+pub const base_meal_quality: f32 = 0.3;
+/// Quality gained for each level of cooking skill.
+pub const skill_meal_quality: f32 = 0.05;
+
+/// Food keeps for its shelf life at this temperature, spoiling faster when warmer and not at all
+/// once frozen.
+pub const reference_spoil_temperature: f32 = 20.0;
+pub const freezing_temperature: f32 = 0.0;
+/// Tiles at most this cold are cold storage, which food is hauled to.
+pub const cold_storage_temperature: f32 = 4.0;
+/// How far around pawns look for cold storage.
+pub const cold_storage_range: u32 = 32;
+/// Game time hauling is expected to take, for planning.
+pub const haul_time: f32 = 10.0;
+
+/// Quality of a meal cooked by someone of the given skill at cooking.
+pub fn meal_quality(skill: f32) -> f32 {
+    (base_meal_quality + skill * skill_meal_quality).min(1.0)
+}
+
+/// How many times faster than at `reference_spoil_temperature` food spoils.
+pub fn spoilage_rate(temperature: f32) -> f32 {
+    ((temperature - freezing_temperature) / (reference_spoil_temperature - freezing_temperature))
+        .max(0.0)
+}
+
+/// A meal of `base` cooked from `ingredients`, giving all their nutrition together.
+pub fn cook<'a, I>(base: &Food, ingredients: I, quality: f32) -> Food
+where
+    I: IntoIterator<Item = &'a Food>,
+{
+    let mut meal = *base;
+    for food in ingredients {
+        for (vitamin, amount) in meal.vitamins.iter_mut().zip(food.vitamins.iter()) {
+            *vitamin += amount;
+        }
+        meal.calories += food.calories;
+        meal.sugars += food.sugars;
+        meal.fats += food.fats;
+    }
+    meal.quality = quality;
+    meal
+}
+
+/// What eating a cooked item gives, in place of the `food` of its details.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Meal(pub Food);
+
+/// Food on its way to spoiling, by the `shelf_life` of its `Food`.
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Perishable {
+    /// Game time it has kept for, at `reference_spoil_temperature`.
+    pub age: f32,
+}
+impl Perishable {
+    /// Ages the food over `elapsed` game time kept at `temperature`.
+    pub fn update(&mut self, elapsed: u64, temperature: f32) {
+        self.age += spoilage_rate(temperature) * elapsed as f32;
+    }
+
+    pub fn is_spoiled(&self, shelf_life: u64) -> bool {
+        self.age >= shelf_life as f32
+    }
+}
+
+/// Marks food lying where it will spoil, with a job posted to haul it to cold storage.
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(NullStorage)]
+pub struct Spoiling;
+
+/// Having hauled spoiling food yourself, which can't be seen, so that jobs need the hauling done.
+pub fn stored() -> Condition {
+    Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Me,
+        ConditionValue::Spoiling,
+    )
+}
+
+/// The planner actions for hauling food to cold storage: moving next to it, then carrying it
+/// off.
+pub fn actions() -> Vec<goap::Action> {
+    let near_spoiling = Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Near(1),
+        ConditionValue::Spoiling,
+    );
+
+    vec![
+        goap::Action::new(
+            "Move To Spoiling Food".to_string(),
+            ActionCatagory::HaulingFood,
+            ActionEvent::MoveTo,
+            1.0,
+            Vec::new(),
+            vec![(near_spoiling.clone(), true)],
+        ),
+        goap::Action::new(
+            "Haul Food".to_string(),
+            ActionCatagory::HaulingFood,
+            ActionEvent::Haul,
+            haul_time,
+            vec![near_spoiling],
+            vec![(stored(), true)],
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hibitset::BitSet;
+
+    #[test]
+    fn meals() {
+        assert_eq!(meal_quality(0.0), base_meal_quality);
+        assert!(meal_quality(10.0) > meal_quality(0.0));
+        assert_eq!(meal_quality(100.0), 1.0);
+
+        let potato = Food {
+            calories: 150,
+            sugars: 2,
+            shelf_life: Some(1000),
+            ..Food::default()
+        };
+        let stew = Food {
+            calories: 20,
+            shelf_life: Some(300),
+            ..Food::default()
+        };
+        let meal = cook(&stew, &[potato, potato], 0.7);
+        assert_eq!(meal.calories, 320);
+        assert_eq!(meal.sugars, 4);
+        assert_eq!(meal.quality, 0.7);
+        assert_eq!(meal.shelf_life, Some(300));
+    }
+
+    #[test]
+    fn spoilage() {
+        let mut warm = Perishable::default();
+        let mut cold = Perishable::default();
+        let mut frozen = Perishable::default();
+        warm.update(100, reference_spoil_temperature);
+        cold.update(100, cold_storage_temperature);
+        frozen.update(100, -10.0);
+        assert_eq!(warm.age, 100.0);
+        assert!(cold.age > 0.0 && cold.age < warm.age);
+        assert_eq!(frozen.age, 0.0);
+        assert!(warm.is_spoiled(100));
+        assert!(!cold.is_spoiled(100));
+
+        let mut planner = goap::Planner::default();
+        let mut available = BitSet::new();
+        for action in actions() {
+            available.add(planner.insert(action));
+        }
+        let goal = planner.condition_set(&[stored()]).unwrap();
+        let plan = planner
+            .plan(&available, &goal, BitSet::new())
+            .unwrap()
+            .iter()
+            .map(|id| planner.get_action_name(*id).unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(plan, vec!["Move To Spoiling Food", "Haul Food"]);
+    }
+}
@@ -0,0 +1,93 @@
+use amethyst::{
+    ecs::{storage::FlaggedStorage, Component, DenseVecStorage},
+    renderer::Rgba,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::tiles::{TileId, TileStorage};
+
+/// A mark drawn over a tile, on top of all its layers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Decal {
+    Cracks,
+    Blood,
+    Snow,
+    Scaffolding,
+}
+impl Decal {
+    /// The sprite the decal is drawn with, as an index into the cp437 sheet.
+    pub fn sprite_number(self) -> usize {
+        match self {
+            Decal::Cracks => 47,
+            Decal::Blood => 7,
+            Decal::Snow => 176,
+            Decal::Scaffolding => 35,
+        }
+    }
+
+    /// The color the decal is drawn in, blended over the tile by its alpha.
+    pub fn color(self) -> Rgba {
+        match self {
+            Decal::Cracks => Rgba(0.2, 0.2, 0.2, 0.6),
+            Decal::Blood => Rgba(0.6, 0.0, 0.0, 0.8),
+            Decal::Snow => Rgba(1.0, 1.0, 1.0, 0.7),
+            Decal::Scaffolding => Rgba(0.6, 0.45, 0.25, 0.9),
+        }
+    }
+}
+
+/// The decals over a tile, drawn in the order they were added.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Decals(pub Vec<Decal>);
+impl Component for Decals {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+/// Puts `decal` over the tile `id`, unless it already has one.
+pub fn add_decal(decals: &mut TileStorage<Decals>, id: TileId, decal: Decal) {
+    let mut tile_decals = decals.get(id).cloned().unwrap_or_default();
+    if !tile_decals.0.contains(&decal) {
+        tile_decals.0.push(decal);
+        decals.insert(id, tile_decals);
+    }
+}
+
+/// Takes `decal` off the tile `id`, dropping its decals once none are left.
+pub fn remove_decal(decals: &mut TileStorage<Decals>, id: TileId, decal: Decal) {
+    let mut tile_decals = match decals.get(id) {
+        Some(tile_decals) if tile_decals.0.contains(&decal) => tile_decals.clone(),
+        _ => return,
+    };
+    tile_decals.0.retain(|other| *other != decal);
+    if tile_decals.0.is_empty() {
+        decals.remove(id);
+    } else {
+        decals.insert(id, tile_decals);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tiles::Tiles;
+
+    #[test]
+    fn decals() {
+        let tiles = Tiles::new(4, 4, 1);
+        let id = tiles.id(1, 2, 0);
+        let mut decals = TileStorage::<Decals>::default();
+
+        add_decal(&mut decals, id, Decal::Blood);
+        add_decal(&mut decals, id, Decal::Cracks);
+        add_decal(&mut decals, id, Decal::Blood);
+        assert_eq!(
+            decals.get(id),
+            Some(&Decals(vec![Decal::Blood, Decal::Cracks]))
+        );
+
+        remove_decal(&mut decals, id, Decal::Blood);
+        assert_eq!(decals.get(id), Some(&Decals(vec![Decal::Cracks])));
+        remove_decal(&mut decals, id, Decal::Cracks);
+        assert_eq!(decals.get(id), None);
+    }
+}
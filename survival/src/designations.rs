@@ -0,0 +1,246 @@
+use amethyst::{
+    core::math::Vector3,
+    ecs::{storage::FlaggedStorage, Component, DenseVecStorage},
+};
+use serde::{Deserialize, Serialize};
+use specs_derive::Component;
+
+use crate::actions::Action as ActionEvent;
+use crate::goap::{
+    self, ActionCatagory, Condition, ConditionEquality, ConditionType, ConditionValue,
+};
+use crate::tiles::{TileStorage, Tiles};
+
+/// Game time mining out a tile, and taking down a construction, takes.
+pub const mine_time: f32 = 40.0;
+pub const deconstruct_time: f32 = 30.0;
+/// Item left behind by mining out a tile.
+pub const mined_item: &str = "stone";
+
+/// Work the player has ordered done on a tile, painted with the mouse and carried out as jobs
+/// posted by `DesignationSystem`.
+#[derive(
+    Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize, strum_macros::Display,
+)]
+pub enum Designation {
+    /// Fell the tree on the tile.
+    ChopTree,
+    /// Dig out the rock of an impassable tile.
+    Mine,
+    /// Reap the ripe plant on the tile.
+    GatherPlants,
+    /// Take down the construction on the tile.
+    Deconstruct,
+}
+impl Component for Designation {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+impl Default for Designation {
+    fn default() -> Self {
+        Designation::ChopTree
+    }
+}
+impl Designation {
+    pub const ALL: [Designation; 4] = [
+        Designation::ChopTree,
+        Designation::Mine,
+        Designation::GatherPlants,
+        Designation::Deconstruct,
+    ];
+
+    /// The labor the job carrying it out is posted as.
+    pub fn catagory(self) -> ActionCatagory {
+        match self {
+            Designation::ChopTree => ActionCatagory::Woodcutting,
+            Designation::Mine => ActionCatagory::Mining,
+            Designation::GatherPlants => ActionCatagory::Farming,
+            Designation::Deconstruct => ActionCatagory::Construction,
+        }
+    }
+
+    /// The action event doing the work.
+    pub fn event(self) -> ActionEvent {
+        match self {
+            Designation::ChopTree => ActionEvent::Chop,
+            Designation::Mine => ActionEvent::Mine,
+            Designation::GatherPlants => ActionEvent::Gather,
+            Designation::Deconstruct => ActionEvent::Deconstruct,
+        }
+    }
+
+    pub fn work_time(self) -> f32 {
+        match self {
+            Designation::ChopTree => crate::systems::trees::chop_time,
+            Designation::Mine => mine_time,
+            Designation::GatherPlants => crate::farming::harvest_time,
+            Designation::Deconstruct => deconstruct_time,
+        }
+    }
+}
+
+/// Marks the entity a designated tile is worked on, such as its tree or plant, so pawns can find
+/// it.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Designated(pub Designation);
+
+/// What dragging the mouse over tiles does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Brush {
+    Designate(Designation),
+    Erase,
+}
+
+/// The brush the player is painting designations with over the tiles they select.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DesignationTool {
+    pub brush: Option<Brush>,
+}
+impl DesignationTool {
+    /// Picks up `brush`, or puts it down if it was already held.
+    pub fn toggle(&mut self, brush: Brush) {
+        self.brush = if self.brush == Some(brush) {
+            None
+        } else {
+            Some(brush)
+        };
+    }
+}
+
+/// Paints `brush` over the rectangle between `from` and `to`, on the z-level of `from`.
+pub fn paint(
+    designations: &mut TileStorage<Designation>,
+    tiles: Tiles,
+    from: Vector3<u32>,
+    to: Vector3<u32>,
+    brush: Brush,
+) {
+    let min = Vector3::new(from.x.min(to.x), from.y.min(to.y), from.z);
+    let max = Vector3::new(from.x.max(to.x), from.y.max(to.y), from.z);
+    for id in tiles.iter_volume(min, max) {
+        match brush {
+            Brush::Designate(designation) => {
+                designations.insert(id, designation);
+            }
+            Brush::Erase => {
+                designations.remove(id);
+            }
+        }
+    }
+}
+
+/// Having worked a designation yourself, which can't be seen, so that jobs need the work done.
+pub fn worked(designation: Designation) -> Condition {
+    Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Me,
+        ConditionValue::Designated(designation),
+    )
+}
+
+/// The planner actions for working each designation: moving next to it, then doing the work.
+pub fn actions() -> Vec<goap::Action> {
+    Designation::ALL
+        .iter()
+        .flat_map(|designation| {
+            let near = Condition::new(
+                ConditionEquality::Is,
+                ConditionType::Near(1),
+                ConditionValue::Designated(*designation),
+            );
+            vec![
+                goap::Action::new(
+                    format!("Move To {}", designation),
+                    designation.catagory(),
+                    ActionEvent::MoveTo,
+                    1.0,
+                    Vec::new(),
+                    vec![(near.clone(), true)],
+                ),
+                goap::Action::new(
+                    format!("Work {}", designation),
+                    designation.catagory(),
+                    designation.event(),
+                    designation.work_time(),
+                    vec![near],
+                    vec![(worked(*designation), true)],
+                ),
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hibitset::BitSet;
+
+    #[test]
+    fn painting() {
+        let tiles = Tiles::new(10, 10, 2);
+        let mut designations = TileStorage::<Designation>::default();
+
+        paint(
+            &mut designations,
+            tiles,
+            Vector3::new(4, 4, 1),
+            Vector3::new(2, 3, 0),
+            Brush::Designate(Designation::Mine),
+        );
+        for x in 2..=4 {
+            for y in 3..=4 {
+                assert_eq!(
+                    designations.get(tiles.id(x, y, 1)),
+                    Some(&Designation::Mine)
+                );
+                assert_eq!(designations.get(tiles.id(x, y, 0)), None);
+            }
+        }
+        assert_eq!(designations.get(tiles.id(5, 4, 1)), None);
+
+        paint(
+            &mut designations,
+            tiles,
+            Vector3::new(3, 3, 1),
+            Vector3::new(4, 4, 1),
+            Brush::Erase,
+        );
+        assert_eq!(
+            designations.get(tiles.id(2, 3, 1)),
+            Some(&Designation::Mine)
+        );
+        assert_eq!(designations.get(tiles.id(3, 3, 1)), None);
+
+        let mut tool = DesignationTool::default();
+        tool.toggle(Brush::Erase);
+        assert_eq!(tool.brush, Some(Brush::Erase));
+        tool.toggle(Brush::Erase);
+        assert_eq!(tool.brush, None);
+    }
+
+    #[test]
+    fn designation_plans() {
+        let mut planner = goap::Planner::default();
+        let mut available = BitSet::new();
+        for action in actions() {
+            available.add(planner.insert(action));
+        }
+
+        for designation in &Designation::ALL {
+            let goal = planner.condition_set(&[worked(*designation)]).unwrap();
+            let plan = planner
+                .plan(&available, &goal, BitSet::new())
+                .unwrap()
+                .iter()
+                .map(|id| planner.get_action_name(*id).unwrap().to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                plan,
+                vec![
+                    format!("Move To {}", designation),
+                    format!("Work {}", designation)
+                ]
+            );
+        }
+    }
+}
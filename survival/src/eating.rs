@@ -0,0 +1,88 @@
+use crate::actions::Action as ActionEvent;
+use crate::assets::item::Property;
+use crate::goap::{
+    self, ActionCatagory, Condition, ConditionEquality, ConditionType, ConditionValue, Goal, Motive,
+};
+use crate::systems::nutrition::Food;
+
+/// Hunger each calorie eaten satisfies.
+pub const hunger_per_calorie: f32 = 0.002;
+/// Game time between rebuilding the map leading pawns to the nearest food.
+pub const food_map_interval: u64 = 20;
+/// Walking cost past which food is too far away to go and eat.
+pub const food_map_range: f32 = 64.0;
+/// Game time eating takes once at the food.
+pub const eat_time: u64 = 10;
+/// Game time walking to food and eating it is expected to take, for planning.
+pub const eat_plan_time: f32 = 30.0;
+
+/// How much eating `food` satisfies hunger.
+pub fn satiety(food: &Food) -> f32 {
+    food.calories as f32 * hunger_per_calorie
+}
+
+/// Having eaten yourself, which can't be seen, so that goals need the eating done.
+pub fn ate() -> Condition {
+    Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Me,
+        ConditionValue::Property(Property::Edible),
+    )
+}
+
+/// The goal of eating when hungry.
+pub fn goals() -> Vec<Goal> {
+    vec![Goal {
+        name: "Eat".to_string(),
+        motive: Motive::Hunger,
+        weight: 1.0,
+        conditions: vec![ate()],
+    }]
+}
+
+/// The planner action for eating, which finds its own way to the nearest food, or eats what is
+/// carried.
+pub fn actions() -> Vec<goap::Action> {
+    vec![goap::Action::new(
+        "Eat Food".to_string(),
+        ActionCatagory::Cooking,
+        ActionEvent::Eat,
+        eat_plan_time,
+        Vec::new(),
+        vec![(ate(), true)],
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hibitset::BitSet;
+
+    #[test]
+    fn eating() {
+        let meat = Food {
+            calories: 250,
+            ..Food::default()
+        };
+        let berry = Food {
+            calories: 20,
+            ..Food::default()
+        };
+        assert!((satiety(&meat) - 0.5).abs() < 1e-6);
+        assert!(satiety(&berry) < satiety(&meat));
+
+        let mut planner = goap::Planner::default();
+        let mut available = BitSet::new();
+        for action in actions() {
+            available.add(planner.insert(action));
+        }
+        let goal = planner.condition_set(&goals()[0].conditions).unwrap();
+        let plan = planner
+            .plan(&available, &goal, BitSet::new())
+            .unwrap()
+            .iter()
+            .map(|id| planner.get_action_name(*id).unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(plan, vec!["Eat Food"]);
+    }
+}
@@ -0,0 +1,188 @@
+use amethyst::ecs::{Component, DenseVecStorage, Entity};
+use specs_derive::Component;
+use std::collections::{HashMap, HashSet};
+
+pub type FactionId = u32;
+
+/// The side an entity is on.
+#[derive(
+    Component, Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[storage(DenseVecStorage)]
+pub struct Faction(pub FactionId);
+
+/// Below this opinion of another faction an entity treats its members as hostile.
+pub const hostile_opinion: f32 = -50.0;
+/// Above this opinion of another faction an entity treats its members as allies.
+pub const ally_opinion: f32 = 50.0;
+
+/// How two factions stand towards each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Relation {
+    Ally,
+    Neutral,
+    Hostile,
+}
+
+/// What an entity personally thinks of other factions, on top of where its own faction stands.
+#[derive(Component, Clone, Default, Debug, serde::Serialize, serde::Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Opinions {
+    opinions: HashMap<FactionId, f32>,
+}
+impl Opinions {
+    pub fn get(&self, faction: FactionId) -> f32 {
+        self.opinions.get(&faction).cloned().unwrap_or(0.0)
+    }
+
+    /// Shifts the opinion of `faction` by `change`, clamped to ±100.
+    pub fn adjust(&mut self, faction: FactionId, change: f32) {
+        let opinion = self.opinions.entry(faction).or_insert(0.0);
+        *opinion = (*opinion + change).max(-100.0).min(100.0);
+    }
+}
+
+/// The standing between every pair of factions. Pairs never set take the default relation, so
+/// that without any diplomacy different factions fight as they always have. Saved with the game.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Relations {
+    pub default: Relation,
+    relations: HashMap<(FactionId, FactionId), Relation>,
+}
+impl Default for Relations {
+    fn default() -> Self {
+        Self {
+            default: Relation::Hostile,
+            relations: HashMap::new(),
+        }
+    }
+}
+impl Relations {
+    fn key(a: FactionId, b: FactionId) -> (FactionId, FactionId) {
+        (a.min(b), a.max(b))
+    }
+
+    /// Sets how `a` and `b` stand towards each other, both ways.
+    pub fn set(&mut self, a: FactionId, b: FactionId, relation: Relation) {
+        if a != b {
+            self.relations.insert(Self::key(a, b), relation);
+        }
+    }
+
+    /// How `a` and `b` stand. A faction is always its own ally.
+    pub fn get(&self, a: FactionId, b: FactionId) -> Relation {
+        if a == b {
+            return Relation::Ally;
+        }
+        self.relations
+            .get(&Self::key(a, b))
+            .cloned()
+            .unwrap_or(self.default)
+    }
+
+    /// How an entity of faction `me` stands towards one of faction `other`, its own `opinions`
+    /// overriding its faction when strong enough. Those with no faction are left alone.
+    pub fn relation(
+        &self,
+        me: Option<&Faction>,
+        opinions: Option<&Opinions>,
+        other: Option<&Faction>,
+    ) -> Relation {
+        let (me, other) = match (me, other) {
+            (Some(me), Some(other)) => (me.0, other.0),
+            _ => return Relation::Neutral,
+        };
+        let relation = self.get(me, other);
+        if me == other {
+            return relation;
+        }
+        match opinions.map_or(0.0, |opinions| opinions.get(other)) {
+            opinion if opinion <= hostile_opinion => Relation::Hostile,
+            opinion if opinion >= ally_opinion => Relation::Ally,
+            _ => relation,
+        }
+    }
+
+    /// Whether these entities fight each other.
+    pub fn is_hostile(
+        &self,
+        me: Option<&Faction>,
+        opinions: Option<&Opinions>,
+        other: Option<&Faction>,
+    ) -> bool {
+        self.relation(me, opinions, other) == Relation::Hostile
+    }
+
+    /// Whether these entities would trade with each other: anyone not hostile, factionless or not.
+    pub fn trades_with(
+        &self,
+        me: Option<&Faction>,
+        opinions: Option<&Opinions>,
+        other: Option<&Faction>,
+    ) -> bool {
+        !self.is_hostile(me, opinions, other)
+    }
+}
+
+/// The hostile entities perceived by any member of each faction, shared so that members know
+/// what they face together. Rebuilt by `ThreatSystem`.
+#[derive(Default, Debug)]
+pub struct FactionThreats {
+    threats: HashMap<FactionId, HashSet<Entity>>,
+}
+impl FactionThreats {
+    pub fn clear(&mut self) {
+        self.threats.clear();
+    }
+
+    pub fn insert(&mut self, faction: FactionId, hostile: Entity) {
+        self.threats.entry(faction).or_default().insert(hostile);
+    }
+
+    pub fn get(&self, faction: FactionId) -> impl Iterator<Item = Entity> + '_ {
+        self.threats
+            .get(&faction)
+            .into_iter()
+            .flat_map(|threats| threats.iter().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relations() {
+        let (colony, raiders, traders) = (Faction(0), Faction(1), Faction(2));
+        let mut relations = Relations::default();
+        relations.set(colony.0, traders.0, Relation::Neutral);
+        relations.set(raiders.0, traders.0, Relation::Ally);
+
+        assert_eq!(relations.get(colony.0, colony.0), Relation::Ally);
+        assert!(relations.is_hostile(Some(&colony), None, Some(&raiders)));
+        assert!(!relations.is_hostile(Some(&traders), None, Some(&colony)));
+        assert_eq!(relations.get(traders.0, raiders.0), Relation::Ally);
+        assert!(!relations.is_hostile(None, None, Some(&raiders)));
+
+        // Strong enough opinions override the faction
+        let mut opinions = Opinions::default();
+        opinions.adjust(traders.0, -40.0);
+        assert!(relations.trades_with(Some(&colony), Some(&opinions), Some(&traders)));
+        opinions.adjust(traders.0, -40.0);
+        assert!(!relations.trades_with(Some(&colony), Some(&opinions), Some(&traders)));
+        opinions.adjust(raiders.0, 500.0);
+        assert_eq!(opinions.get(raiders.0), 100.0);
+        assert_eq!(
+            relations.relation(Some(&colony), Some(&opinions), Some(&raiders)),
+            Relation::Ally
+        );
+
+        let serialized = ron::ser::to_string(&relations).unwrap();
+        let loaded: Relations = ron::de::from_str(&serialized).unwrap();
+        assert_eq!(loaded.get(traders.0, raiders.0), Relation::Ally);
+        assert_eq!(loaded.get(colony.0, raiders.0), Relation::Hostile);
+        let serialized = ron::ser::to_string(&opinions).unwrap();
+        let loaded: Opinions = ron::de::from_str(&serialized).unwrap();
+        assert_eq!(loaded.get(traders.0), -80.0);
+    }
+}
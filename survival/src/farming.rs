@@ -0,0 +1,266 @@
+use amethyst::{
+    core::{math::Vector3, Transform},
+    ecs::{Builder, Component, DenseVecStorage, Entity, SystemData, World},
+};
+use serde::{Deserialize, Serialize};
+use specs_derive::Component;
+
+use crate::actions::Action as ActionEvent;
+use crate::assets::{PlantKind, Storage};
+use crate::components::TilePosition;
+use crate::goap::{
+    self, ActionCatagory, Condition, ConditionEquality, ConditionType, ConditionValue,
+};
+use crate::jobs::JobId;
+use crate::settings::Config;
+use crate::tiles::{TileEntities, Tiles, WriteTiles};
+
+/// Game time each step of working a plot takes.
+pub const till_time: f32 = 20.0;
+pub const sow_time: f32 = 10.0;
+pub const harvest_time: f32 = 15.0;
+/// Share of its growth rate a plant keeps on ground which isn't tilled.
+pub const untilled_growth: f32 = 0.5;
+/// Tiles within which fluid waters a plant.
+pub const irrigation_range: u32 = 3;
+
+/// How far along a `FarmPlot` is, as the planner sees it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum FieldState {
+    Untilled,
+    Tilled,
+    /// Growing, and waiting to ripen.
+    Sown,
+    Ripe,
+}
+impl Default for FieldState {
+    fn default() -> Self {
+        FieldState::Untilled
+    }
+}
+
+/// Ground broken up for sowing, on which plants grow at their full rate.
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Tilled;
+
+/// A tile set aside for growing `crop`, a key in `plants.ron`, which pawns till, sow and harvest
+/// as jobs posted by `FarmingSystem`.
+#[derive(Component, Clone, Debug, PartialEq)]
+#[storage(DenseVecStorage)]
+pub struct FarmPlot {
+    pub crop: String,
+    pub state: FieldState,
+    /// What is growing on it, once sown.
+    pub plant: Option<Entity>,
+    /// The job posted for its next step.
+    pub job: Option<JobId>,
+}
+impl FarmPlot {
+    pub fn new(crop: &str) -> Self {
+        Self {
+            crop: crop.to_string(),
+            state: FieldState::Untilled,
+            plant: None,
+            job: None,
+        }
+    }
+
+    /// The world state reached by its next step, which jobs plan towards, or `None` while the crop
+    /// grows.
+    pub fn next_step(&self, kind: &PlantKind) -> Option<Vec<Condition>> {
+        match self.state {
+            FieldState::Untilled => Some(vec![worked(field(&self.crop, FieldState::Tilled))]),
+            FieldState::Tilled => Some(vec![worked(field(&self.crop, FieldState::Sown))]),
+            FieldState::Sown => None,
+            FieldState::Ripe => Some(vec![has(ConditionValue::Material {
+                material: kind.harvest.clone(),
+                count: kind.harvest_count,
+            })]),
+        }
+    }
+}
+
+/// A growing plant of a species in `plants.ron`, see `PlantGrowthSystem`.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Plant {
+    pub kind: String,
+    /// Game time grown, at the best rate.
+    pub growth: f32,
+    /// Index into the growth stages of its kind.
+    pub stage: usize,
+}
+impl Plant {
+    pub fn new(kind: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            growth: 0.0,
+            stage: 0,
+        }
+    }
+}
+
+pub fn field(crop: &str, state: FieldState) -> ConditionValue {
+    ConditionValue::Field {
+        crop: crop.to_string(),
+        state,
+    }
+}
+
+fn near(value: ConditionValue) -> Condition {
+    Condition::new(ConditionEquality::Is, ConditionType::Near(1), value)
+}
+
+fn has(value: ConditionValue) -> Condition {
+    Condition::new(ConditionEquality::Is, ConditionType::Has, value)
+}
+
+/// Having brought a field to a state yourself, which can't be seen, so that other fields already
+/// there don't count.
+fn worked(value: ConditionValue) -> Condition {
+    Condition::new(ConditionEquality::Is, ConditionType::Me, value)
+}
+
+/// The planner actions farming `crop`: moving to its fields, then tilling, sowing and harvesting
+/// them.
+pub fn actions(crop: &str, kind: &PlantKind) -> Vec<goap::Action> {
+    let step = |name: String, event, time, from, to: Condition| {
+        goap::Action::new(
+            name,
+            ActionCatagory::Farming,
+            event,
+            time,
+            vec![near(field(crop, from))],
+            vec![(to, true)],
+        )
+    };
+
+    let mut actions = [FieldState::Untilled, FieldState::Tilled, FieldState::Ripe]
+        .iter()
+        .map(|state| {
+            goap::Action::new(
+                format!("Move To {} Field ({:?})", kind.name, state),
+                ActionCatagory::Farming,
+                ActionEvent::MoveTo,
+                1.0,
+                Vec::new(),
+                vec![(near(field(crop, *state)), true)],
+            )
+        })
+        .collect::<Vec<_>>();
+    actions.push(step(
+        format!("Till {} Field", kind.name),
+        ActionEvent::Till,
+        till_time,
+        FieldState::Untilled,
+        worked(field(crop, FieldState::Tilled)),
+    ));
+    actions.push(step(
+        format!("Sow {}", kind.name),
+        ActionEvent::Sow,
+        sow_time,
+        FieldState::Tilled,
+        worked(field(crop, FieldState::Sown)),
+    ));
+    actions.push(step(
+        format!("Harvest {}", kind.name),
+        ActionEvent::Harvest,
+        harvest_time,
+        FieldState::Ripe,
+        has(ConditionValue::Material {
+            material: kind.harvest.clone(),
+            count: kind.harvest_count,
+        }),
+    ));
+    actions
+}
+
+/// Adds the actions farming each of `kinds` to `planner`, for any pawn to take once they are loaded.
+pub fn insert_actions(planner: &mut Planner, kinds: &Storage<PlantKind>) {
+    for (crop, kind) in &kinds.data {
+        for action in actions(crop, kind) {
+            planner.insert_pawn_action(action);
+        }
+    }
+}
+
+/// Sets the tile at `coord` aside as a plot for growing `crop`.
+pub fn designate_plot(world: &mut World, coord: Vector3<u32>, crop: &str) -> Entity {
+    let tiles = *world.res.fetch::<Tiles>();
+    let translation = tiles.tile_to_world(coord, &world.res.fetch::<Config>());
+    let mut transform = Transform::default();
+    transform.set_translation(translation);
+
+    let plot = world
+        .create_entity()
+        .with(FarmPlot::new(crop))
+        .with(TilePosition::new(coord))
+        .with(transform)
+        .build();
+
+    let mut occupants: WriteTiles<TileEntities> = SystemData::fetch(&world.res);
+    if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(coord)) {
+        occupants.0.insert(plot);
+    }
+    plot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hibitset::BitSet;
+
+    #[test]
+    fn farming_plan() {
+        let kind = PlantKind {
+            name: "Wheat".to_string(),
+            harvest: "wheat".to_string(),
+            harvest_count: 2,
+            ..PlantKind::default()
+        };
+
+        let mut planner = goap::Planner::default();
+        let mut available = BitSet::new();
+        for action in actions("wheat", &kind) {
+            available.add(planner.insert(action));
+        }
+
+        let mut plot = FarmPlot::new("wheat");
+        let plan_names = |plot: &FarmPlot| {
+            let goal = planner
+                .condition_set(&plot.next_step(&kind).unwrap())
+                .unwrap();
+            planner
+                .plan(&available, &goal, BitSet::new())
+                .unwrap()
+                .iter()
+                .map(|id| planner.get_action_name(*id).unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            plan_names(&plot),
+            vec!["Move To Wheat Field (Untilled)", "Till Wheat Field"]
+        );
+        plot.state = FieldState::Tilled;
+        assert_eq!(
+            plan_names(&plot),
+            vec!["Move To Wheat Field (Tilled)", "Sow Wheat"]
+        );
+        plot.state = FieldState::Sown;
+        assert_eq!(plot.next_step(&kind), None);
+        plot.state = FieldState::Ripe;
+        assert_eq!(
+            plan_names(&plot),
+            vec!["Move To Wheat Field (Ripe)", "Harvest Wheat"]
+        );
+
+        // The plot is found by the field it is on
+        let till = planner.lookup("Till Wheat Field").unwrap();
+        assert_eq!(
+            till.target(),
+            Some(&near(field("wheat", FieldState::Untilled)))
+        );
+    }
+}
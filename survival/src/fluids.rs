@@ -0,0 +1,165 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use crate::components::max_fluid_depth;
+use crate::tiles::{TileId, Tiles};
+
+/// Full tiles searched for an outlet when fluid is pushed down into them.
+pub const max_pressure_search: usize = 64;
+/// Game time a puddle, the shallowest fluid, takes to evaporate.
+pub const evaporation_time: u64 = 200;
+
+/// Depth of fluid on every wet tile. Tile ids order bottom up, so fluid settles before what lands
+/// on it.
+pub type FluidDepths = BTreeMap<TileId, u8>;
+
+/// Moves fluid a step, in the way of cellular automata: it falls into the tile below when there
+/// is room, otherwise spreads a unit to its shallowest neighbour. Fluid falling onto full tiles is
+/// pushed through them to the nearest tile with room no higher than it fell from, like water
+/// rising in a pipe. Tiles for which `blocked` holds, such as walls, take no fluid.
+pub fn flow<F>(tiles: Tiles, depths: &mut FluidDepths, blocked: F)
+where
+    F: Fn(TileId) -> bool,
+{
+    let depth = |depths: &FluidDepths, id: TileId| depths.get(&id).cloned().unwrap_or(0);
+    let wet = depths.keys().cloned().collect::<Vec<_>>();
+
+    for id in wet {
+        let here = depth(depths, id);
+        if here == 0 {
+            continue;
+        }
+
+        let target = match tiles.offset(id, 0, 0, -1).filter(|below| !blocked(*below)) {
+            Some(below) if depth(depths, below) < max_fluid_depth => {
+                let moved = here.min(max_fluid_depth - depth(depths, below));
+                Some((below, moved))
+            }
+            Some(below) => {
+                pressure_outlet(tiles, depths, &blocked, below, tiles.coord(id).z).map(|to| (to, 1))
+            }
+            None => None,
+        }
+        .or_else(|| {
+            tiles
+                .neighbors4(id)
+                .filter(|neighbour| !blocked(*neighbour) && depth(depths, *neighbour) + 1 < here)
+                .min_by_key(|neighbour| depth(depths, *neighbour))
+                .map(|neighbour| (neighbour, 1))
+        });
+
+        if let Some((to, moved)) = target {
+            *depths.entry(to).or_insert(0) += moved;
+            if here == moved {
+                depths.remove(&id);
+            } else {
+                depths.insert(id, here - moved);
+            }
+        }
+    }
+}
+
+/// The nearest tile with room reached through the full tiles from `start`, below level `z`.
+fn pressure_outlet<F>(
+    tiles: Tiles,
+    depths: &FluidDepths,
+    blocked: &F,
+    start: TileId,
+    z: u32,
+) -> Option<TileId>
+where
+    F: Fn(TileId) -> bool,
+{
+    let depth = |id: TileId| depths.get(&id).cloned().unwrap_or(0);
+
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(start);
+    queue.push_back(start);
+    while let Some(id) = queue.pop_front() {
+        if visited.len() > max_pressure_search {
+            break;
+        }
+        let next = tiles
+            .neighbors4(id)
+            .chain(tiles.offset(id, 0, 0, -1))
+            .chain(tiles.offset(id, 0, 0, 1))
+            .filter(|next| tiles.coord(*next).z < z && !blocked(*next));
+        for next in next {
+            if !visited.insert(next) {
+                continue;
+            }
+            if depth(next) < max_fluid_depth {
+                return Some(next);
+            }
+            queue.push_back(next);
+        }
+    }
+    None
+}
+
+/// Dries up every puddle, fluid at the shallowest depth.
+pub fn evaporate(depths: &mut FluidDepths) {
+    let puddles = depths
+        .iter()
+        .filter(|(_, depth)| **depth <= 1)
+        .map(|(key, _)| *key)
+        .collect::<Vec<_>>();
+    for puddle in puddles {
+        depths.remove(&puddle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total(depths: &FluidDepths) -> u32 {
+        depths.values().map(|depth| u32::from(*depth)).sum()
+    }
+
+    #[test]
+    fn falls_and_spreads() {
+        let tiles = Tiles::new(5, 1, 2);
+        let key = |x, z| tiles.id(x, 0, z);
+        let wall = key(3, 0);
+
+        let mut depths = FluidDepths::new();
+        depths.insert(key(1, 1), max_fluid_depth);
+        for _ in 0..50 {
+            flow(tiles, &mut depths, |id| id == wall);
+        }
+
+        // Everything fell, then levelled out on the floor up to the wall
+        assert_eq!(total(&depths), u32::from(max_fluid_depth));
+        assert!(depths.keys().all(|id| tiles.coord(*id).z == 0));
+        assert!(!depths.contains_key(&key(3, 0)));
+        assert!(!depths.contains_key(&key(4, 0)));
+        let levels = (0..3)
+            .map(|x| depths.get(&key(x, 0)).cloned().unwrap_or(0))
+            .collect::<Vec<_>>();
+        assert!(levels.iter().max().unwrap() - levels.iter().min().unwrap() <= 1);
+
+        evaporate(&mut depths);
+        assert!(depths.values().all(|depth| *depth > 1));
+    }
+
+    #[test]
+    fn pressure() {
+        // A U-bend: full floor, walls on either side of the column poured into
+        let tiles = Tiles::new(3, 1, 3);
+        let key = |x, z| tiles.id(x, 0, z);
+        let walls = [key(1, 1)];
+
+        let mut depths = FluidDepths::new();
+        for x in 0..3 {
+            depths.insert(key(x, 0), max_fluid_depth);
+        }
+        depths.insert(key(0, 1), max_fluid_depth);
+        depths.insert(key(0, 2), 1);
+
+        flow(tiles, &mut depths, |id| walls.contains(&id));
+        // Pushed up the far side of the bend, no higher than it fell from
+        assert_eq!(depths.get(&key(2, 1)), Some(&1));
+        assert_eq!(depths.get(&key(0, 2)), None);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::tiles::{TileId, Tiles};
+
+/// The player whose view the tile pass renders.
+pub const local_player: u8 = 0;
+
+// Transforms mapping octant 0 onto each of the 8 octants, as (xx, xy, yx, yy)
+const octants: [(i32, i32, i32, i32); 8] = [
+    (1, 0, 0, 1),
+    (0, 1, 1, 0),
+    (0, -1, 1, 0),
+    (-1, 0, 0, 1),
+    (-1, 0, 0, -1),
+    (0, -1, -1, 0),
+    (0, 1, -1, 0),
+    (1, 0, 0, -1),
+];
+
+/// Recursive shadowcasting on the z-level of `origin`. `visit` is called for every tile visible
+/// within `range`, including blocking tiles that are themselves seen.
+pub fn shadowcast<B, V>(tiles: Tiles, origin: TileId, range: u32, blocks: B, mut visit: V)
+where
+    B: Fn(TileId) -> bool,
+    V: FnMut(TileId),
+{
+    visit(origin);
+
+    for transform in &octants {
+        cast_light(
+            tiles,
+            origin,
+            range as i32,
+            1,
+            1.0,
+            0.0,
+            *transform,
+            &blocks,
+            &mut visit,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cast_light<B, V>(
+    tiles: Tiles,
+    origin: TileId,
+    range: i32,
+    row: i32,
+    mut start: f32,
+    end: f32,
+    (xx, xy, yx, yy): (i32, i32, i32, i32),
+    blocks: &B,
+    visit: &mut V,
+) where
+    B: Fn(TileId) -> bool,
+    V: FnMut(TileId),
+{
+    if start < end {
+        return;
+    }
+
+    let mut new_start = 0.0;
+    for distance in row..=range {
+        let dy = -distance;
+        let mut blocked = false;
+
+        for dx in -distance..=0 {
+            let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+            let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+
+            if start < right_slope {
+                continue;
+            } else if end > left_slope {
+                break;
+            }
+
+            let tile = tiles.offset(origin, dx * xx + dy * xy, dx * yx + dy * yy, 0);
+            if let Some(tile) = tile {
+                if dx * dx + dy * dy <= range * range {
+                    visit(tile);
+                }
+            }
+
+            // The edge of the map blocks sight like a wall
+            let is_blocked = tile.map_or(true, |tile| blocks(tile));
+            if blocked {
+                if is_blocked {
+                    new_start = right_slope;
+                } else {
+                    blocked = false;
+                    start = new_start;
+                }
+            } else if is_blocked && distance < range {
+                blocked = true;
+                cast_light(
+                    tiles,
+                    origin,
+                    range,
+                    distance + 1,
+                    start,
+                    left_slope,
+                    (xx, xy, yx, yy),
+                    blocks,
+                    visit,
+                );
+                new_start = right_slope;
+            }
+        }
+
+        if blocked {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn shadowcast_wall() {
+        let tiles = Tiles::new(20, 20, 1);
+        let origin = tiles.id(5, 10, 0);
+        let walls = (5..15).map(|y| tiles.id(8, y, 0)).collect::<HashSet<_>>();
+
+        let mut visible = HashSet::new();
+        shadowcast(
+            tiles,
+            origin,
+            10,
+            |id| walls.contains(&id),
+            |id| {
+                visible.insert(id);
+            },
+        );
+
+        assert!(visible.contains(&origin));
+        assert!(visible.contains(&tiles.id(7, 10, 0)));
+        assert!(visible.contains(&tiles.id(8, 10, 0)));
+        assert!(!visible.contains(&tiles.id(9, 10, 0)));
+        assert!(!visible.contains(&tiles.id(12, 11, 0)));
+        assert!(visible.contains(&tiles.id(5, 3, 0)));
+        assert!(!visible.contains(&tiles.id(0, 19, 0)));
+    }
+}
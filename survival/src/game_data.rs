@@ -39,9 +39,18 @@ impl<'a, 'b> SurvivalData<'a, 'b> {
         self.level_dispatcher.dispatch(&world.res);
         //self.overworld_dispatcher.dispatch(&world.res);
         self.core_dispatcher.dispatch(&world.res);
+        if let Some(registry) = world.res.try_fetch::<specs_static::Registry>() {
+            registry.maintain(&world.res);
+        }
 
         world.res.fetch::<SurvivalState>().clone()
     }
+
+    /// Update only the core systems, for the menus shown before a level is laid out, which the
+    /// level systems need to run.
+    pub fn update_core(&mut self, world: &World) {
+        self.core_dispatcher.dispatch(&world.res);
+    }
 }
 
 pub struct SurvivalDataBuilder<'a, 'b> {
@@ -1,14 +1,18 @@
 use amethyst::ecs::{
-    storage::UnprotectedStorage, world::Index, DenseVecStorage, Entity, World,
+    storage::UnprotectedStorage, world::Index, DenseVecStorage, Entity, Resources, World,
 };
 use hibitset::BitSet;
 use indexmap::IndexSet;
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
 
 use crate::assets::item::Property as ItemProperty;
 use crate::actions::Action as ActionEvent;
+use crate::assets::migration::{Change, Migration};
 use crate::components::InteractionType;
+use crate::designations::Designation;
+use crate::farming::FieldState;
 use bitflags::*;
 
 bitflags_serial! {
@@ -47,6 +51,9 @@ pub enum ActionCatagory {
     Cleaning,
     Doctoring,
     Construction,
+    Combat,
+    Cooking,
+    Butchery,
 }
 impl Default for ActionCatagory {
     fn default() -> Self {
@@ -102,10 +109,51 @@ pub enum ConditionValue {
     Pawn { kind: String, count: usize },
     Location(TileType),
     Tree,
+    /// A `FarmPlot` growing `crop`, at the given step of being farmed.
+    Field { crop: String, state: FieldState },
+    /// Anything hostile to the entity, see `Relations::is_hostile`.
+    Hostile,
+    /// Something marked for work by a `Designation`.
+    Designated(Designation),
+    /// Something giving off heat, such as a fire to cook over.
+    Heat,
+    /// Food lying where it will spoil, to be hauled to cold storage.
+    Spoiling,
+    /// Something liquid can be drawn from, such as a well.
+    LiquidSource,
+    /// A container of `material`, or as `Me`, having drunk it.
+    Liquid { material: String, boiled: bool },
+    /// The remains of a creature, to be butchered.
+    Corpse,
+    /// Refers to a goal named in the `Planner`. As an action result, this makes a compound
+    /// action which expands into the plan reaching that goal.
+    Goal(String),
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Condition(ConditionEquality, ConditionType, ConditionValue);
+impl Condition {
+    pub fn new(equality: ConditionEquality, kind: ConditionType, value: ConditionValue) -> Self {
+        Condition(equality, kind, value)
+    }
+
+    pub fn equality(&self) -> &ConditionEquality {
+        &self.0
+    }
+
+    pub fn kind(&self) -> &ConditionType {
+        &self.1
+    }
+
+    pub fn value(&self) -> &ConditionValue {
+        &self.2
+    }
+}
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?} {:?} {:?}", self.0, self.1, self.2)
+    }
+}
 
 #[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
 pub enum ActionSourceType {
@@ -143,20 +191,297 @@ impl PartialEq for Action {
         false
     }
 }
+impl Action {
+    /// An action made at runtime rather than loaded, such as crafting a recipe.
+    pub fn new(
+        name: String,
+        catagory: ActionCatagory,
+        event: ActionEvent,
+        base_time: f32,
+        conditions: Vec<Condition>,
+        result: Vec<(Condition, bool)>,
+    ) -> Self {
+        Self {
+            catagory,
+            event: (event, None),
+            name,
+            base_time,
+            conditions,
+            result,
+            ..Self::default()
+        }
+    }
+
+    pub fn id(&self) -> Index {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The event sent to the entity's `Actionable` channel to carry this action out.
+    pub fn event(&self) -> ActionEvent {
+        self.event.0
+    }
+
+    pub fn catagory(&self) -> &ActionCatagory {
+        &self.catagory
+    }
+
+    pub fn base_time(&self) -> f32 {
+        self.base_time
+    }
+
+    pub fn conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    pub fn results(&self) -> &[(Condition, bool)] {
+        &self.result
+    }
+
+    /// The condition bound to what the action acts on: that of its event, else the first thing it
+    /// needs to be, or ends up, near.
+    pub fn target(&self) -> Option<&Condition> {
+        let is_near = |condition: &&Condition| match condition.kind() {
+            ConditionType::Near(_) => true,
+            _ => false,
+        };
+        self.event.1.as_ref().or_else(|| {
+            self.conditions
+                .iter()
+                .chain(self.result.iter().map(|(condition, _)| condition))
+                .find(is_near)
+        })
+    }
+
+    /// Names of the goals this action expands into, in order.
+    pub fn subgoals(&self) -> impl Iterator<Item = &str> {
+        self.result
+            .iter()
+            .filter_map(|(condition, _)| match condition.value() {
+                ConditionValue::Goal(name) => Some(name.as_str()),
+                _ => None,
+            })
+    }
+
+    pub fn is_compound(&self) -> bool {
+        self.subgoals().next().is_some()
+    }
+}
+
+/// The actions of `actions.ron`.
+#[derive(Clone, Default, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Actions {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub actions: Vec<Action>,
+}
+
+/// Changes to the layout of `actions.ron`, oldest first.
+pub const action_migrations: &[Migration] = &[
+    // The list of actions became a struct, to be versioned
+    Migration {
+        version: 1,
+        changes: &[Change::Wrap { field: "actions" }],
+    },
+];
+
+/// Actions never cost less than this, so that of two otherwise free plans the shorter wins.
+const min_action_cost: f32 = 0.001;
+
+/// Prices the edges of the `Planner` search.
+pub trait CostModel: Sync {
+    /// Estimated distance to travel to get within `range` of something matching `value`.
+    fn distance_estimate(&self, value: &ConditionValue, range: i32) -> f32;
+
+    /// The cost of performing an action: its `base_time`, plus the travel needed to satisfy any
+    /// `Near` preconditions.
+    fn action_cost(&self, action: &Action) -> f32 {
+        let travel: f32 = action
+            .conditions
+            .iter()
+            .filter_map(|Condition(equality, kind, value)| match (equality, kind) {
+                (ConditionEquality::Is, ConditionType::Near(range)) => {
+                    Some(self.distance_estimate(value, *range))
+                }
+                _ => None,
+            })
+            .sum();
+
+        (action.base_time + travel).max(min_action_cost)
+    }
+}
+
+/// Prices actions by their `base_time` alone.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct TimeCost;
+impl CostModel for TimeCost {
+    fn distance_estimate(&self, _: &ConditionValue, _: i32) -> f32 {
+        0.0
+    }
+}
+
+/// Prices actions by `base_time` plus a known, or default, distance to what they need to be
+/// near.
+#[derive(Default, Clone, Debug)]
+pub struct DistanceCost {
+    pub distances: HashMap<ConditionValue, f32>,
+    pub default_distance: f32,
+}
+impl CostModel for DistanceCost {
+    fn distance_estimate(&self, value: &ConditionValue, range: i32) -> f32 {
+        let distance = self
+            .distances
+            .get(value)
+            .cloned()
+            .unwrap_or(self.default_distance);
+        (distance - range as f32).max(0.0)
+    }
+}
+
+/// What makes a pawn want to pursue a goal.
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Motive {
+    Hunger,
+    Thirst,
+    Fatigue,
+    Cold,
+    Labor(ActionCatagory),
+    /// Wanted a little all the time, for something to do when nothing else is pressing.
+    Idle,
+    /// Fighting or fleeing from a threat.
+    Danger,
+}
+
+/// How pressing each motive is for a pawn right now, from 0 (not at all) to 1.
+#[derive(Clone, Copy, Debug)]
+pub struct Drives<'a> {
+    pub hunger: f32,
+    pub thirst: f32,
+    pub fatigue: f32,
+    pub cold: f32,
+    pub danger: f32,
+    pub labor_priorities: &'a HashMap<ActionCatagory, f32>,
+    /// From -1 to 1. Unhappy pawns neglect their labors for idling.
+    pub mood: f32,
+}
+impl<'a> Drives<'a> {
+    pub fn urgency(&self, motive: &Motive) -> f32 {
+        match motive {
+            Motive::Hunger => self.hunger,
+            Motive::Thirst => self.thirst,
+            Motive::Fatigue => self.fatigue,
+            Motive::Cold => self.cold,
+            Motive::Danger => self.danger,
+            Motive::Labor(catagory) => {
+                let priority = self.labor_priorities.get(catagory).cloned().unwrap_or(0.0);
+                priority * (1.0 + self.mood * mood_labor_influence).max(0.0)
+            }
+            Motive::Idle => idle_urgency + (-self.mood).max(0.0) * unhappy_idle_urgency,
+        }
+    }
+}
+
+const idle_urgency: f32 = 0.01;
+/// How much mood scales the urgency of labor.
+const mood_labor_influence: f32 = 0.5;
+/// Urgency idling gains at the worst mood.
+const unhappy_idle_urgency: f32 = 0.5;
+
+/// A world state a pawn may plan towards.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Goal {
+    pub name: String,
+    pub motive: Motive,
+    /// Scales the urgency of the motive, to favour one goal over another serving the same one.
+    pub weight: f32,
+    pub conditions: Vec<Condition>,
+}
+
+/// Picks which goal a pawn plans for, by scoring every candidate goal against its drives.
+#[derive(Clone, Debug, Default)]
+pub struct GoalSelector {
+    goals: Vec<Goal>,
+}
+impl GoalSelector {
+    pub fn insert(&mut self, goal: Goal) {
+        self.goals.push(goal);
+    }
+
+    pub fn goals(&self) -> &[Goal] {
+        &self.goals
+    }
+
+    pub fn utility(goal: &Goal, drives: &Drives) -> f32 {
+        goal.weight * drives.urgency(&goal.motive)
+    }
+
+    /// Candidate goals with any utility, best first.
+    pub fn rank<'a>(&'a self, drives: &Drives) -> Vec<(&'a Goal, f32)> {
+        Self::rank_goals(&self.goals, drives)
+    }
+
+    /// The highest utility goal which the planner's actions can reach, with its goal state to
+    /// feed `Planner::plan_live`.
+    pub fn select<'a>(&'a self, planner: &Planner, drives: &Drives) -> Option<(&'a Goal, BitSet)> {
+        Self::select_goals(&self.goals, planner, drives)
+    }
+
+    pub fn rank_goals<'a, I>(goals: I, drives: &Drives) -> Vec<(&'a Goal, f32)>
+    where
+        I: IntoIterator<Item = &'a Goal>,
+    {
+        let mut ranked = goals
+            .into_iter()
+            .map(|goal| (goal, Self::utility(goal, drives)))
+            .filter(|(_, utility)| *utility > 0.0)
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        ranked
+    }
+
+    /// Like `select`, from goals other than the selector's own, such as a claimed job.
+    pub fn select_goals<'a, I>(
+        goals: I,
+        planner: &Planner,
+        drives: &Drives,
+    ) -> Option<(&'a Goal, BitSet)>
+    where
+        I: IntoIterator<Item = &'a Goal>,
+    {
+        Self::rank_goals(goals, drives).into_iter().find_map(|(goal, _)| {
+            planner
+                .condition_set(&goal.conditions)
+                .map(|conditions| (goal, conditions))
+        })
+    }
+}
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 struct PlannerNode {
     pub action_id: Option<Index>,
     pub state: BitSet,
+    /// The actions taken to get here, those a compound action expanded into.
+    pub actions: Vec<Index>,
 }
 impl PlannerNode {
     pub fn new(action_id: Index, state: &BitSet) -> Self {
         Self {
             action_id: Some(action_id),
             state: state.clone(),
+            actions: vec![action_id],
         }
     }
 }
+
+/// A plan with its compound actions expanded, with the state it ends in and what it costs.
+struct Expansion {
+    plan: Vec<Index>,
+    state: BitSet,
+    cost: f32,
+}
 impl std::hash::Hash for PlannerNode {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         for id in &self.state {
@@ -165,12 +490,94 @@ impl std::hash::Hash for PlannerNode {
     }
 }
 
+/// The inputs of a `Planner::plan_costed` query.
+#[derive(PartialEq, Eq, Clone, Debug)]
+struct PlanKey {
+    available_actions: BitSet,
+    goal_conditions: BitSet,
+    state: BitSet,
+    /// The bits of the distance estimates of the cost model, see `Planner::travel`.
+    travel: Vec<u32>,
+}
+impl std::hash::Hash for PlanKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        for set in &[&self.available_actions, &self.goal_conditions, &self.state] {
+            for id in *set {
+                id.hash(state);
+            }
+            // Separates the sets, so bits moving from one to the next change the hash
+            std::u32::MAX.hash(state);
+        }
+        self.travel.hash(state);
+    }
+}
+
+/// Recent plans, evicting the least recently used past `capacity`. Pawns with the same actions,
+/// goal and state share the plan rather than each running the search.
+#[derive(Debug)]
+pub struct PlanCache {
+    pub capacity: usize,
+    plans: HashMap<PlanKey, (Option<Vec<Index>>, u64)>,
+    clock: u64,
+}
+impl Default for PlanCache {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            plans: HashMap::new(),
+            clock: 0,
+        }
+    }
+}
+impl PlanCache {
+    pub fn len(&self) -> usize {
+        self.plans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plans.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.plans.clear();
+    }
+
+    fn get(&mut self, key: &PlanKey) -> Option<Option<Vec<Index>>> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.plans.get_mut(key).map(|(plan, last_used)| {
+            *last_used = clock;
+            plan.clone()
+        })
+    }
+
+    fn insert(&mut self, key: PlanKey, plan: Option<Vec<Index>>) {
+        if self.plans.len() >= self.capacity {
+            let oldest = self
+                .plans
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.plans.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.plans.insert(key, (plan, self.clock));
+    }
+}
 
 pub struct Planner {
     cur_action: Index,
     actions: DenseVecStorage<Action>,
     conditions: IndexSet<Condition>,
     name_table: HashMap<String, Index>,
+    /// Named goals which compound actions expand into.
+    goals: HashMap<String, Vec<Condition>>,
+    /// Plans found by `plan`, cleared whenever the action library changes.
+    cache: Mutex<PlanCache>,
+    /// Actions any `Pawn` may take, besides those of its own `AI`.
+    pawn_actions: BitSet,
 }
 
 impl Planner {
@@ -180,6 +587,7 @@ impl Planner {
 
     pub fn insert(&mut self, mut obj: Action) -> Index {
         let action_id = self.cur_action;
+        self.cache.get_mut().unwrap().clear();
 
         self.cur_action += 1;
         obj.id = action_id;
@@ -190,6 +598,13 @@ impl Planner {
         for condition in &obj.conditions {
             self.conditions.insert(condition.clone());
         }
+        for (condition, _) in &obj.result {
+            // Goals are indexed by their own conditions
+            if let ConditionValue::Goal(_) = condition.value() {
+                continue;
+            }
+            self.conditions.insert(condition.clone());
+        }
 
         unsafe {
             self.actions.insert(action_id, obj);
@@ -198,6 +613,47 @@ impl Planner {
         action_id
     }
 
+    /// Adds an action any `Pawn` may take.
+    pub fn insert_pawn_action(&mut self, action: Action) -> Index {
+        let id = self.insert(action);
+        self.pawn_actions.add(id);
+        id
+    }
+
+    /// Adds `actions` to the planner in `res` for any `Pawn` to take, for systems carrying them
+    /// out to call as they are set up.
+    pub fn setup_pawn_actions<I>(res: &mut Resources, actions: I)
+    where
+        I: IntoIterator<Item = Action>,
+    {
+        let mut planner = res.entry::<Self>().or_insert_with(Self::default);
+        for action in actions {
+            planner.insert_pawn_action(action);
+        }
+    }
+
+    /// The actions an entity may take: those `own` to its `AI`, and if it is a `Pawn`, those any
+    /// pawn may.
+    pub fn available_to(&self, own: &BitSet, pawn: bool) -> BitSet {
+        let mut available = own.clone();
+        if pawn {
+            for id in &self.pawn_actions {
+                available.add(id);
+            }
+        }
+        available
+    }
+
+    /// Names a goal for compound actions to refer to.
+    pub fn insert_goal(&mut self, name: &str, conditions: Vec<Condition>) {
+        self.cache.get_mut().unwrap().clear();
+
+        for condition in &conditions {
+            self.conditions.insert(condition.clone());
+        }
+        self.goals.insert(name.to_string(), conditions);
+    }
+
     pub fn lookup(&self, name: &str) -> Option<&Action> {
         if let Some(id) = self.name_table.get(name) {
             return self.get(*id);
@@ -223,6 +679,8 @@ impl Planner {
         if action > self.cur_action {
             return None;
         }
+        // The action may be changed in ways which change plans
+        self.cache.get_mut().unwrap().clear();
         unsafe { Some(self.actions.get_mut(action)) }
     }
 
@@ -251,6 +709,11 @@ impl Planner {
         self.check_condition_live(self.conditions.get_index(condition_id as usize).unwrap(), entity, world)
     }
 
+    /// The condition indexed as `condition_id` in condition sets.
+    pub fn condition(&self, condition_id: Index) -> Option<&Condition> {
+        self.conditions.get_index(condition_id as usize)
+    }
+
     pub fn get_condition_set(&self, action_id: Index) -> BitSet {
         let mut set = BitSet::new();
         if let Some(action) = self.get(action_id) {
@@ -261,11 +724,68 @@ impl Planner {
         set
     }
 
+    /// The set of every precondition of `available_actions` for which `check` holds.
+    pub fn evaluate<F>(&self, available_actions: &BitSet, check: F) -> BitSet
+    where
+        F: Fn(&Condition) -> bool,
+    {
+        use hibitset::BitSetLike;
+
+        let mut state = BitSet::new();
+        for id in available_actions.iter() {
+            if let Some(action) = self.get(id) {
+                for condition in &action.conditions {
+                    if check(condition) {
+                        state.add(self.conditions.get_full(condition).unwrap().0 as u32);
+                    }
+                }
+            }
+        }
+        state
+    }
+
+    /// Every distinct condition needed or produced by `available_actions`.
+    pub fn referenced<'a>(&'a self, available_actions: &BitSet) -> HashSet<&'a Condition> {
+        use hibitset::BitSetLike;
+
+        available_actions
+            .iter()
+            .filter_map(|id| self.get(id))
+            .flat_map(|action| {
+                action
+                    .conditions
+                    .iter()
+                    .chain(action.result.iter().map(|(condition, _)| condition))
+            })
+            .collect()
+    }
+
+    /// The indexed set of `conditions`, or `None` if any of them is neither needed nor produced by
+    /// an action.
+    pub fn condition_set(&self, conditions: &[Condition]) -> Option<BitSet> {
+        let mut set = BitSet::new();
+        for condition in conditions {
+            set.add(self.conditions.get_full(condition)?.0 as u32);
+        }
+        Some(set)
+    }
+
+    /// The conditions of the goal named `name`.
+    pub fn goal_set(&self, name: &str) -> Option<BitSet> {
+        self.condition_set(self.goals.get(name)?)
+    }
+
+    /// The conditions an action makes hold. Those of a compound action are the conditions of its
+    /// goals.
     pub fn get_result_set(&self, action_id: Index) -> BitSet {
         let mut set = BitSet::new();
         if let Some(action) = self.get(action_id) {
             action.result.iter().for_each(|condition| {
-                if condition.1 {
+                if let ConditionValue::Goal(name) = condition.0.value() {
+                    if let Some(goal) = self.goal_set(name) {
+                        set.extend(goal);
+                    }
+                } else if condition.1 {
                     set.add(self.conditions.get_full(&condition.0).unwrap().0 as u32);
                 } else {
                     set.remove(self.conditions.get_full(&condition.0).unwrap().0 as u32);
@@ -292,46 +812,223 @@ impl Planner {
 
         self.plan(available_actions, goal_conditions, state)
     }
+    /// Plans with the default `TimeCost`, reusing the plan of any identical recent query.
     pub fn plan(&self, available_actions: &BitSet, goal_conditions: &BitSet, state: BitSet) -> Option<Vec<Index>> {
-        use pathfinding::prelude::*;
+        self.plan_costed(available_actions, goal_conditions, state, &TimeCost)
+    }
+
+    /// Plans as priced by `costs`, reusing the plan of any identical recent query priced the same.
+    pub fn plan_costed<C: CostModel>(
+        &self,
+        available_actions: &BitSet,
+        goal_conditions: &BitSet,
+        state: BitSet,
+        costs: &C,
+    ) -> Option<Vec<Index>> {
+        let key = PlanKey {
+            available_actions: available_actions.clone(),
+            goal_conditions: goal_conditions.clone(),
+            state,
+            travel: self.travel(available_actions, costs),
+        };
+        if let Some(plan) = self.cache.lock().unwrap().get(&key) {
+            return plan;
+        }
+
+        // The lock isn't held while searching, so others can plan at the same time
+        let plan = self.plan_with(available_actions, goal_conditions, key.state.clone(), costs);
+        self.cache.lock().unwrap().insert(key, plan.clone());
+        plan
+    }
+
+    /// The bits of the distance estimates `costs` gives the `Near` preconditions of
+    /// `available_actions`, in order, which are all of a cost model that plans depend on.
+    fn travel<C: CostModel>(&self, available_actions: &BitSet, costs: &C) -> Vec<u32> {
         use hibitset::BitSetLike;
 
+        available_actions
+            .iter()
+            .filter_map(|id| self.get(id))
+            .flat_map(|action| action.conditions.iter())
+            .filter_map(|Condition(equality, kind, value)| match (equality, kind) {
+                (ConditionEquality::Is, ConditionType::Near(range)) => {
+                    Some(costs.distance_estimate(value, *range).to_bits())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn plan_cache_len(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    pub fn set_plan_cache_capacity(&mut self, capacity: usize) {
+        let cache = self.cache.get_mut().unwrap();
+        cache.capacity = capacity;
+        cache.clear();
+    }
+
+    /// Plans the cheapest sequence of actions reaching `goal_conditions`, as priced by `costs`.
+    /// Compound actions in the plan are expanded into the actions reaching their goals.
+    pub fn plan_with<C: CostModel>(
+        &self,
+        available_actions: &BitSet,
+        goal_conditions: &BitSet,
+        state: BitSet,
+        costs: &C,
+    ) -> Option<Vec<Index>> {
+        self.plan_expanding(available_actions, goal_conditions, state, costs, &[])
+            .map(|expansion| expansion.plan)
+    }
+
+    /// Plans with the compound actions of the plan expanded. `expanding` holds the compound
+    /// actions being expanded further up, which are left out so that a compound action can't
+    /// expand into itself.
+    fn plan_expanding<C: CostModel>(
+        &self,
+        available_actions: &BitSet,
+        goal_conditions: &BitSet,
+        state: BitSet,
+        costs: &C,
+        expanding: &[Index],
+    ) -> Option<Expansion> {
+        let mut available_actions = available_actions.clone();
+        for id in expanding {
+            available_actions.remove(*id);
+        }
+
+        self.search(&available_actions, goal_conditions, state, costs, expanding)
+    }
+
+    /// Plans each goal of a compound action in turn, from where the last left off.
+    fn expand<C: CostModel>(
+        &self,
+        action_id: Index,
+        available_actions: &BitSet,
+        state: BitSet,
+        costs: &C,
+        expanding: &[Index],
+    ) -> Option<Expansion> {
+        let mut expanding = expanding.to_vec();
+        expanding.push(action_id);
+
+        let mut expansion = Expansion {
+            plan: Vec::new(),
+            state,
+            cost: 0.0,
+        };
+        for name in self.get(action_id).unwrap().subgoals() {
+            let goal = match self.goal_set(name) {
+                Some(goal) => goal,
+                None => {
+                    let action = self.get_action_name(action_id).unwrap();
+                    log::warn!("{} refers to unknown goal {}", action, name);
+                    return None;
+                }
+            };
+
+            let next =
+                self.plan_expanding(available_actions, &goal, expansion.state, costs, &expanding)?;
+            expansion.plan.extend(next.plan);
+            expansion.state = next.state;
+            expansion.cost += next.cost;
+        }
+        Some(expansion)
+    }
+
+    /// A* search over the actions. Compound actions are expanded as they are reached and priced
+    /// by what their expansion costs, those which can't be expanded being passed over.
+    fn search<C: CostModel>(
+        &self,
+        available_actions: &BitSet,
+        goal_conditions: &BitSet,
+        state: BitSet,
+        costs: &C,
+        expanding: &[Index],
+    ) -> Option<Expansion> {
+        use hibitset::BitSetLike;
+        use ordered_float::NotNan;
+        use pathfinding::prelude::*;
+
         let start = PlannerNode {
             action_id: None,
             state,
+            actions: Vec::new(),
+        };
+
+        // Each unmet goal needs at least the cheapest action making it hold. One action can meet
+        // several goals, so only the dearest of those is certain to be paid, keeping the
+        // heuristic admissible. Compound actions cost at least the least any action does
+        let lower_bound = |action: &Action| {
+            if action.is_compound() {
+                min_action_cost
+            } else {
+                costs.action_cost(action)
+            }
         };
+        let cheapest = goal_conditions
+            .iter()
+            .map(|condition| {
+                let cost = available_actions
+                    .iter()
+                    .filter(|id| self.get_result_set(*id).contains(condition))
+                    .filter_map(|id| self.get(id))
+                    .map(&lower_bound)
+                    .fold(std::f32::INFINITY, f32::min);
+                (condition, cost)
+            })
+            .collect::<Vec<_>>();
+        if cheapest
+            .iter()
+            .any(|(condition, cost)| !cost.is_finite() && !start.state.contains(*condition))
+        {
+            return None;
+        }
 
-        if let Some((plan, _)) = astar(
+        let (nodes, cost) = astar(
             &start,
             |node| {
                 let next = available_actions.par_iter().filter_map(|id| {
                     log::trace!("Checking can={}", id);
-                    match self.can_occur(id, &node.state) {
-                        true => {
-                            log::trace!("\tAdding successor: {}", self.get_action_name(id).unwrap());
-
-                            let mut new_state = node.state.clone();
-                            new_state.extend(self.get_result_set(id));
-
-                            Some((PlannerNode {
-                                action_id: Some(id),
-                                state: new_state.clone(),
-                            }, 1))
-                        },
-                        false => None,
+                    if !self.can_occur(id, &node.state) {
+                        return None;
                     }
+                    log::trace!("\tAdding successor: {}", self.get_action_name(id).unwrap());
+
+                    let action = self.get(id).unwrap();
+                    let (state, cost, actions) = if action.is_compound() {
+                        let expansion = self.expand(
+                            id,
+                            available_actions,
+                            node.state.clone(),
+                            costs,
+                            expanding,
+                        )?;
+                        let cost = expansion.cost.max(min_action_cost);
+                        (expansion.state, cost, expansion.plan)
+                    } else {
+                        let mut state = node.state.clone();
+                        state.extend(self.get_result_set(id));
+                        (state, costs.action_cost(action), vec![id])
+                    };
+
+                    Some((PlannerNode {
+                        action_id: Some(id),
+                        state,
+                        actions,
+                    }, NotNan::new(cost).unwrap()))
                 }).collect::<Vec<_>>();
                 log::trace!("Returning next: {}", next.len());
                 next
             },
             |node| {
-                let distance = goal_conditions.par_iter().map(|condition| {
-                    if ! node.state.contains(condition) {
-                        return 1;
-                    }
-                    0
-                }).sum();
-                distance
+                let estimate = cheapest
+                    .iter()
+                    .filter(|(condition, _)| !node.state.contains(*condition))
+                    .map(|(_, cost)| *cost)
+                    .fold(0.0, f32::max);
+                NotNan::new(estimate).unwrap()
             },
             |node| {
                 for condition in goal_conditions {
@@ -340,13 +1037,16 @@ impl Planner {
                     }
                 }
                 log::trace!("matching state");
-                return true;
+                true
             },
-        ) {
-            return Some(plan.into_iter().skip(1).map(|ref node| node.action_id.unwrap()).collect());
-        }
+        )?;
 
-        None
+        let state = nodes.last().unwrap().state.clone();
+        Some(Expansion {
+            plan: nodes.into_iter().flat_map(|node| node.actions).collect(),
+            state,
+            cost: cost.into_inner(),
+        })
     }
 }
 
@@ -355,8 +1055,11 @@ impl Default for Planner {
         Self {
             actions: DenseVecStorage::default(),
             name_table: HashMap::new(),
+            goals: HashMap::new(),
             conditions: IndexSet::new(),
             cur_action: 0,
+            cache: Mutex::new(PlanCache::default()),
+            pawn_actions: BitSet::new(),
         }
     }
 }
@@ -405,6 +1108,357 @@ mod tests {
         log::trace!("Plan = {}", res);
     }
 
+    #[test]
+    pub fn goap_weighted_plan() {
+        let mut planner = Planner::default();
+        let axe_property = ConditionValue::Property(ItemProperty::Chopping(OrderedFloat(1.0)));
+        let tree = Condition(ConditionEquality::Is, ConditionType::Near(1), ConditionValue::Tree);
+        let near_axe = Condition(ConditionEquality::Is, ConditionType::Near(1), axe_property.clone());
+        let has_axe = Condition(ConditionEquality::Is, ConditionType::Has, axe_property.clone());
+        let wood = Condition(
+            ConditionEquality::Is,
+            ConditionType::Has,
+            ConditionValue::Material {
+                material: "Wood".to_string(),
+                count: 1,
+            },
+        );
+
+        let mut move_to_tree = Action::default();
+        move_to_tree.name = "Move To Tree".to_string();
+        move_to_tree.base_time = 1.0;
+        move_to_tree.result.push((tree.clone(), true));
+
+        // Breaking branches off by hand is one step but slow, an axe is faster overall
+        let mut break_branches = Action::default();
+        break_branches.name = "Break Branches".to_string();
+        break_branches.base_time = 20.0;
+        break_branches.conditions.push(tree.clone());
+        break_branches.result.push((wood.clone(), true));
+
+        let mut get_axe = Action::default();
+        get_axe.name = "Get Axe".to_string();
+        get_axe.base_time = 2.0;
+        get_axe.conditions.push(near_axe.clone());
+        get_axe.result.push((has_axe.clone(), true));
+
+        let mut chop = Action::default();
+        chop.name = "Chop Tree".to_string();
+        chop.base_time = 5.0;
+        chop.conditions.push(has_axe);
+        chop.conditions.push(tree);
+        chop.result.push((wood.clone(), true));
+
+        let mut available = BitSet::new();
+        for action in vec![move_to_tree, break_branches, get_axe, chop] {
+            available.add(planner.insert(action));
+        }
+        planner.conditions.insert(wood.clone());
+
+        let mut goal = BitSet::new();
+        goal.add(planner.conditions.get_full(&wood).unwrap().0 as u32);
+        let mut state = BitSet::new();
+        state.add(planner.conditions.get_full(&near_axe).unwrap().0 as u32);
+
+        let names = |plan: Vec<Index>| {
+            plan.into_iter()
+                .map(|id| planner.get_action_name(id).unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        let mut plan = names(planner.plan(&available, &goal, state.clone()).unwrap());
+        assert_eq!(plan.pop(), Some("Chop Tree".to_string()));
+        plan.sort();
+        assert_eq!(plan, vec!["Get Axe", "Move To Tree"]);
+
+        // When the axe is far away, walking to it costs more than the time it saves
+        let mut costs = DistanceCost::default();
+        costs.distances.insert(axe_property, 100.0);
+        let plan = planner
+            .plan_with(&available, &goal, state.clone(), &costs)
+            .unwrap();
+        assert_eq!(names(plan), vec!["Move To Tree", "Break Branches"]);
+
+        // Plans priced differently aren't shared
+        let plan = planner
+            .plan_costed(&available, &goal, state.clone(), &costs)
+            .unwrap();
+        assert_eq!(names(plan), vec!["Move To Tree", "Break Branches"]);
+        let plan = planner.plan(&available, &goal, state).unwrap();
+        assert_eq!(
+            plan.last().and_then(|id| planner.get_action_name(*id)),
+            Some("Chop Tree")
+        );
+    }
+
+    #[test]
+    pub fn shared_results() {
+        let has = |material: &str| {
+            Condition::new(
+                ConditionEquality::Is,
+                ConditionType::Has,
+                ConditionValue::Material {
+                    material: material.to_string(),
+                    count: 1,
+                },
+            )
+        };
+        let tree = Condition::new(
+            ConditionEquality::Is,
+            ConditionType::Near(1),
+            ConditionValue::Tree,
+        );
+        let action = |name: &str, base_time, conditions: &[&Condition], results: &[&Condition]| {
+            let mut action = Action::default();
+            action.name = name.to_string();
+            action.base_time = base_time;
+            action.conditions = conditions.iter().map(|c| (*c).clone()).collect();
+            action.result = results.iter().map(|c| ((*c).clone(), true)).collect();
+            action
+        };
+        let (wood, sap) = (has("Wood"), has("Sap"));
+
+        // Felling a tree yields both wood and sap, so walking to one first is cheaper than
+        // gathering either on its own
+        let mut planner = Planner::default();
+        let mut available = BitSet::new();
+        for action in vec![
+            action("Move To Tree", 1.0, &[], &[&tree]),
+            action("Fell Tree", 1.0, &[&tree], &[&wood, &sap]),
+            action("Gather Sticks", 1.2, &[], &[&wood]),
+            action("Tap Resin", 1.2, &[], &[&sap]),
+        ] {
+            available.add(planner.insert(action));
+        }
+
+        let goal = planner.condition_set(&[wood, sap]).unwrap();
+        let plan = planner.plan(&available, &goal, BitSet::new()).unwrap();
+        let names = plan
+            .into_iter()
+            .map(|id| planner.get_action_name(id).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Move To Tree", "Fell Tree"]);
+    }
+
+    #[test]
+    pub fn plan_cache() {
+        let mut planner = Planner::default();
+        let mut available = BitSet::new();
+        for action in gen_test_actions() {
+            available.add(planner.insert(action));
+        }
+        planner.set_plan_cache_capacity(2);
+
+        let tree = planner.get_result_set(planner.lookup("Move To Tree").unwrap().id());
+        let axe = planner.get_result_set(planner.lookup("Get Axe").unwrap().id());
+        let plan = planner.plan(&available, &tree, BitSet::new());
+        assert!(plan.is_some());
+        assert_eq!(planner.plan(&available, &tree, BitSet::new()), plan);
+        assert_eq!(planner.plan_cache_len(), 1);
+
+        // Failed searches are remembered too, and the oldest query is evicted
+        assert_eq!(planner.plan(&available, &axe, BitSet::new()), None);
+        planner.plan(&available, &tree, tree.clone());
+        assert_eq!(planner.plan_cache_len(), 2);
+        assert!(!planner.cache.lock().unwrap().plans.contains_key(&PlanKey {
+            available_actions: available.clone(),
+            goal_conditions: tree.clone(),
+            state: BitSet::new(),
+            travel: planner.travel(&available, &TimeCost),
+        }));
+
+        let mut action = Action::default();
+        action.name = "Nothing".to_string();
+        planner.insert(action);
+        assert_eq!(planner.plan_cache_len(), 0);
+    }
+
+    #[test]
+    pub fn pawn_actions() {
+        let mut planner = Planner::default();
+        let mut actions = gen_test_actions().into_iter();
+        let mut own = BitSet::new();
+        own.add(planner.insert(actions.next().unwrap()));
+        let shared = planner.insert_pawn_action(actions.next().unwrap());
+
+        assert_eq!(planner.available_to(&own, false), own);
+        let available = planner.available_to(&own, true);
+        assert!(available.contains(shared));
+        assert!((&own).into_iter().all(|id| available.contains(id)));
+    }
+
+    #[test]
+    pub fn goal_selection() {
+        let mut planner = Planner::default();
+        for action in gen_test_actions() {
+            planner.insert(action);
+        }
+
+        let axe = ConditionValue::Property(ItemProperty::Chopping(OrderedFloat(1.0)));
+        let goal = |name: &str, motive, value| Goal {
+            name: name.to_string(),
+            motive,
+            weight: 1.0,
+            conditions: vec![Condition::new(ConditionEquality::Is, ConditionType::Has, value)],
+        };
+        let mut selector = GoalSelector::default();
+        selector.insert(goal("Wander", Motive::Idle, axe.clone()));
+        selector.insert(goal(
+            "Eat",
+            Motive::Hunger,
+            ConditionValue::Property(ItemProperty::Edible),
+        ));
+        selector.insert(goal(
+            "Fetch Axe",
+            Motive::Labor(ActionCatagory::Woodcutting),
+            axe,
+        ));
+
+        let mut labor_priorities = HashMap::new();
+        labor_priorities.insert(ActionCatagory::Woodcutting, 0.5);
+        let drives = Drives {
+            hunger: 0.9,
+            thirst: 0.0,
+            fatigue: 0.0,
+            cold: 0.0,
+            danger: 0.0,
+            labor_priorities: &labor_priorities,
+            mood: 0.0,
+        };
+
+        // Nothing produces food, so the hungry pawn falls back to work it can plan for
+        let ranked = selector
+            .rank(&drives)
+            .into_iter()
+            .map(|(goal, _)| goal.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ranked, vec!["Eat", "Fetch Axe", "Wander"]);
+        let (goal, conditions) = selector.select(&planner, &drives).unwrap();
+        assert_eq!(goal.name, "Fetch Axe");
+        assert_eq!(Some(conditions), planner.condition_set(&goal.conditions));
+
+        let idle = HashMap::new();
+        let drives = Drives {
+            labor_priorities: &idle,
+            ..drives
+        };
+        assert_eq!(selector.select(&planner, &drives).unwrap().0.name, "Wander");
+
+        // Miserable pawns would rather idle than work
+        let drives = Drives {
+            labor_priorities: &labor_priorities,
+            mood: -1.0,
+            ..drives
+        };
+        assert_eq!(selector.select(&planner, &drives).unwrap().0.name, "Wander");
+    }
+
+    #[test]
+    pub fn compound_actions() {
+        let has = |value| Condition::new(ConditionEquality::Is, ConditionType::Has, value);
+        let near = |value| Condition::new(ConditionEquality::Is, ConditionType::Near(1), value);
+        let goal = |name: &str| {
+            Condition::new(
+                ConditionEquality::Is,
+                ConditionType::Me,
+                ConditionValue::Goal(name.to_string()),
+            )
+        };
+        let food = ConditionValue::Property(ItemProperty::Edible);
+        let stove = ConditionValue::Property(ItemProperty::Cooking(OrderedFloat(5.0)));
+        let meal = ConditionValue::Material {
+            material: "Meal".to_string(),
+            count: 1,
+        };
+        let gold = ConditionValue::Material {
+            material: "Gold".to_string(),
+            count: 1,
+        };
+
+        let action = |name: &str, conditions: Vec<Condition>, result: Vec<Condition>| {
+            let mut a = Action::default();
+            a.name = name.to_string();
+            a.base_time = 1.0;
+            a.conditions = conditions;
+            a.result = result
+                .into_iter()
+                .map(|condition| (condition, true))
+                .collect();
+            a
+        };
+        let mut planner = Planner::default();
+        let mut available = BitSet::new();
+        for a in vec![
+            action("Pick Vegetables", vec![], vec![has(food.clone())]),
+            action("Light Stove", vec![], vec![near(stove.clone())]),
+            action(
+                "Cook",
+                vec![has(food.clone()), near(stove.clone())],
+                vec![has(meal.clone())],
+            ),
+            action(
+                "Cook Meal",
+                vec![],
+                vec![goal("Ingredients"), goal("Stove"), goal("Meal")],
+            ),
+            action("Wish", vec![], vec![goal("Riches")]),
+        ] {
+            available.add(planner.insert(a));
+        }
+        planner.lookup_mut("Cook Meal").unwrap().base_time = 0.0;
+        planner.insert_goal("Ingredients", vec![has(food)]);
+        planner.insert_goal("Stove", vec![near(stove.clone())]);
+        planner.insert_goal("Meal", vec![has(meal.clone())]);
+        planner.insert_goal("Riches", vec![has(gold.clone())]);
+
+        // Compound actions expand into the plans reaching their goals, in order
+        let names = |planner: &Planner, plan: Vec<Index>| {
+            plan.into_iter()
+                .map(|id| planner.get_action_name(id).unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        let cook_meal = planner.lookup("Cook Meal").unwrap().id();
+        let expansion = planner
+            .expand(cook_meal, &available, BitSet::new(), &TimeCost, &[])
+            .unwrap();
+        assert_eq!(
+            names(&planner, expansion.plan),
+            vec!["Pick Vegetables", "Light Stove", "Cook"]
+        );
+        assert!((expansion.cost - 3.0).abs() < std::f32::EPSILON);
+        assert_eq!(planner.lookup("Cook").unwrap().target(), Some(&near(stove)));
+        assert_eq!(planner.referenced(&available).len(), 7);
+
+        let meal_set = planner.condition_set(&[has(meal.clone())]).unwrap();
+        let plan = planner.plan(&available, &meal_set, BitSet::new()).unwrap();
+        let mut plan = names(&planner, plan);
+        assert_eq!(plan.pop(), Some("Cook".to_string()));
+        plan.sort();
+        assert_eq!(plan, vec!["Light Stove", "Pick Vegetables"]);
+
+        // Wishing for riches could only expand into wishing again
+        let gold_set = planner.condition_set(&[has(gold.clone())]).unwrap();
+        assert_eq!(planner.plan(&available, &gold_set, BitSet::new()), None);
+
+        // Compound actions cost what their expansion does rather than their own time, and those
+        // which can't be expanded are passed over for the actions which can be taken
+        let blessing = ConditionValue::Material {
+            material: "Blessing".to_string(),
+            count: 1,
+        };
+        planner.insert_goal("Favor", vec![has(blessing)]);
+        let mut pray = action("Pray", vec![], vec![goal("Favor"), goal("Riches")]);
+        pray.base_time = 0.0;
+        let mut mine = action("Mine Gold", vec![], vec![has(gold)]);
+        mine.base_time = 5.0;
+        for a in vec![action("Buy Meal", vec![], vec![has(meal)]), pray, mine] {
+            available.add(planner.insert(a));
+        }
+        let plan = planner.plan(&available, &meal_set, BitSet::new()).unwrap();
+        assert_eq!(names(&planner, plan), vec!["Buy Meal"]);
+        let plan = planner.plan(&available, &gold_set, BitSet::new()).unwrap();
+        assert_eq!(names(&planner, plan), vec!["Mine Gold"]);
+    }
+
     pub fn gen_test_actions() -> Vec<Action> {
         let _ = env_logger::builder().is_test(true).try_init();
 
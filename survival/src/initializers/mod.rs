@@ -1,13 +1,23 @@
-use crate::assets;
+use crate::assets::{self, creature::AiProfile};
 use crate::components;
+use crate::persistence::Prototype;
+use crate::settings::{Config, Context};
+use crate::tiles::{TileChangeEvent, TileEntities, Tiles, WriteTiles};
 use amethyst::{
     core::{
-        components::Parent,
-        math::{Vector3},
+        components::{Parent, Transform},
+        math::Vector3,
     },
-    ecs::{Builder, Entity, World},
+    ecs::{Builder, Entity, SystemData, World},
+    renderer::SpriteRender,
+    shrev::EventChannel,
 };
 
+/// Sprite of trees, as an index into the cp437 sheet.
+const tree_sprite_number: usize = 5;
+
+/// Where a spawned entity is put: on the ground at a tile, or at a point of the world which
+/// lands it on the tile there, or inside another entity.
 #[derive(Copy, Clone, Debug, strum_macros::Display)]
 pub enum SpawnType {
     TilePosition(Vector3<u32>),
@@ -15,48 +25,272 @@ pub enum SpawnType {
     Parent(Entity),
 }
 
+/// Creates an item from its details `name`, with the components they call for. Items spawned on
+/// the ground are given a `Transform` and `TilePosition`, and added to the `TileEntities` of
+/// their tile.
 pub fn spawn_item(
     world: &mut World,
     spawn_type: SpawnType,
     name: &str,
     properties: Option<Vec<crate::assets::item::Property>>,
 ) -> Entity {
-    let (details_handle, is_container) = {
+    let (details_handle, container, status, heat, perishable, source) = {
         let item_storage = world.res.fetch::<assets::ItemStorage>();
         let item_details = item_storage.read().unwrap();
+        let details = item_details.data.get(name).unwrap();
+        let materials = world.res.try_fetch::<assets::material::Materials>();
 
         (
             item_details.handles.get(name).unwrap().clone(),
-            item_details
-                .data
-                .get(name)
-                .unwrap()
-                .flags
-                .contains(assets::item::ItemFlag::Container),
+            if details.flags.contains(assets::item::ItemFlag::Container) {
+                components::Container::from_details(details)
+            } else {
+                None
+            },
+            details
+                .material
+                .as_ref()
+                .and_then(|material| materials.as_ref()?.get(material))
+                .map(components::MaterialStatus::from_material),
+            details.heat.map(crate::temperature::HeatSource),
+            details
+                .shelf_life()
+                .map(|_| crate::cooking::Perishable::default()),
+            details
+                .liquid_source
+                .clone()
+                .map(crate::liquids::LiquidSource),
         )
     };
+    let tiles = *world.res.fetch::<Tiles>();
+    let placed = {
+        let config = world.res.fetch::<Config>();
+        match spawn_type {
+            SpawnType::TilePosition(coord) => Some((coord, tiles.tile_to_world(coord, &config))),
+            SpawnType::TransformPosition(translation) => {
+                Some((tiles.world_to_tile(&translation, &config), translation))
+            }
+            SpawnType::Parent(_) => None,
+        }
+    };
+
+    let mut builder = world
+        .create_entity()
+        .with(components::Item {
+            handle: details_handle,
+            properties: match properties {
+                Some(p) => p,
+                None => Vec::new(),
+            },
+        })
+        .with(Prototype::Item(name.to_string()));
+
+    if let Some(container) = container {
+        builder = builder.with(container);
+    }
+    if let Some(status) = status {
+        builder = builder.with(status);
+    }
+    if let Some(heat) = heat {
+        builder = builder.with(heat);
+    }
+    if let Some(perishable) = perishable {
+        builder = builder.with(perishable);
+    }
+    if let Some(source) = source {
+        builder = builder.with(source);
+    }
+
+    if let Some((coord, translation)) = placed {
+        let mut transform = Transform::default();
+        transform.set_translation(translation);
+        builder = builder
+            .with(components::TilePosition::new(coord))
+            .with(transform);
+    }
+    if let SpawnType::Parent(parent_entity) = spawn_type {
+        builder = builder.with(Parent {
+            entity: parent_entity,
+        });
+    }
+    let item = builder.build();
+
+    if let Some((coord, _)) = placed {
+        let mut occupants: WriteTiles<TileEntities> = SystemData::fetch(&world.res);
+        if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(coord)) {
+            occupants.0.insert(item);
+        }
+    }
+    item
+}
+
+/// Adds the item `item` made of `material` to the item assets, unless it already is, giving
+/// the key to spawn it by.
+pub fn item_made_of(world: &mut World, item: &str, material: &str) -> String {
+    let key = assets::item::material_item_key(item, material);
+    let details = {
+        let item_storage = world.res.fetch::<assets::ItemStorage>();
+        let item_details = item_storage.read().unwrap();
+        if item_details.data.contains_key(&key) {
+            return key;
+        }
+        let materials = world.res.fetch::<assets::material::Materials>();
+        let locale = world.res.fetch::<assets::Localization>();
+        item_details
+            .data
+            .get(item)
+            .unwrap()
+            .made_of(materials.get(material).unwrap(), &locale)
+    };
+    assets::StorageSource::<assets::Item>::insert(world, &key, details);
+    key
+}
+
+/// Creates a creature of the `kind` in the creature assets, with the body, sprite and faction they
+/// give it. Colonists get a `Pawn` and `AI` to plan with, and wildlife a `Creature` at home where
+/// it is spawned. Spawning with a `Parent` puts it on the ground by the parent, such as a mother.
+pub fn spawn_creature(world: &mut World, kind: &str, spawn_type: SpawnType) -> Entity {
+    let details = {
+        let creature_storage = world.res.fetch::<assets::CreatureStorage>();
+        let kinds = creature_storage.read().unwrap();
+        kinds.data.get(kind).unwrap().clone()
+    };
+    let tiles = *world.res.fetch::<Tiles>();
+    let config = world.res.fetch::<Config>().clone();
+    let sprite_sheet = world.res.fetch::<Context>().spritesheet.clone();
+
+    let (coord, translation) = match spawn_type {
+        SpawnType::TilePosition(coord) => (coord, tiles.tile_to_world(coord, &config)),
+        SpawnType::TransformPosition(translation) => {
+            (tiles.world_to_tile(&translation, &config), translation)
+        }
+        SpawnType::Parent(parent) => {
+            let coord = world
+                .read_storage::<components::TilePosition>()
+                .get(parent)
+                .expect("Creatures are spawned by a parent on the ground")
+                .coord;
+            (coord, tiles.tile_to_world(coord, &config))
+        }
+    };
+    let mut transform = Transform::default();
+    transform.set_translation(translation);
+    transform.set_scale(config.graphics.scale, config.graphics.scale, 1.);
+
+    let mut builder = world
+        .create_entity()
+        .with(components::TilePosition::new(coord))
+        .with(transform)
+        .with(components::TimeAvailable::default())
+        .with(components::Initiative::default())
+        .with(components::Actionable::default())
+        .with(components::ai::Blackboard::default())
+        .with(components::ai::Needs::default())
+        .with(crate::temperature::BodyTemperature::default())
+        .with(details.body.body())
+        .with(Prototype::Creature(kind.to_string()));
+    if let Some(sprite_sheet) = sprite_sheet {
+        builder = builder.with(SpriteRender {
+            sprite_sheet,
+            sprite_number: details.sprite_number,
+        });
+    }
+    if let Some(faction) = details.faction {
+        builder = builder.with(crate::factions::Faction(faction));
+    }
+    builder = match details.ai {
+        AiProfile::Pawn => builder
+            .with(components::ai::Pawn::default())
+            .with(components::ai::AI::default()),
+        AiProfile::Wildlife => builder.with(components::ai::Creature::new(kind, coord)),
+    };
+    let creature = builder.build();
+
+    let mut occupants: WriteTiles<TileEntities> = SystemData::fetch(&world.res);
+    if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(coord)) {
+        occupants.0.insert(creature);
+    }
+    creature
+}
+
+pub fn spawn_tree(
+    world: &mut World,
+    coord: Vector3<u32>,
+    kind: components::TreeKind,
+    size: f32,
+) -> Entity {
+    let tiles = *world.res.fetch::<Tiles>();
+    let mut transform = Transform::default();
+    transform.set_translation(tiles.tile_to_world(coord, &world.res.fetch::<Config>()));
+    let sprite_sheet = world.res.fetch::<Context>().spritesheet.clone();
+
+    let mut builder = world
+        .create_entity()
+        .with(components::Tree::new(kind, size))
+        .with(components::TilePosition::new(coord))
+        .with(transform)
+        .with(Prototype::Tree);
+    if let Some(sprite_sheet) = sprite_sheet {
+        builder = builder.with(SpriteRender {
+            sprite_sheet,
+            sprite_number: tree_sprite_number,
+        });
+    }
+    let tree = builder.build();
+
+    // Trees don't move, so are only ever on this tile
+    let mut occupants: WriteTiles<TileEntities> = SystemData::fetch(&world.res);
+    if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(coord)) {
+        occupants.0.insert(tree);
+    }
+    tree
+}
+
+/// Hangs a shut `Door` in the tile at `coord`, making it a `Doorway` which is impassable until
+/// opened.
+pub fn spawn_door(
+    world: &mut World,
+    coord: Vector3<u32>,
+    locked: bool,
+    forbid_pets: bool,
+) -> Entity {
+    let tiles = *world.res.fetch::<Tiles>();
+    let mut transform = Transform::default();
+    transform.set_translation(tiles.tile_to_world(coord, &world.res.fetch::<Config>()));
+    let sprite_sheet = world.res.fetch::<Context>().spritesheet.clone();
+
+    let door = components::Door::new(locked, forbid_pets);
+    let doorway = door.doorway();
+    let mut builder = world
+        .create_entity()
+        .with(components::TilePosition::new(coord))
+        .with(transform)
+        .with(Prototype::Door);
+    if let Some(sprite_sheet) = sprite_sheet {
+        builder = builder.with(SpriteRender {
+            sprite_sheet,
+            sprite_number: door.sprite_number(),
+        });
+    }
+    let entity = builder.with(door).build();
 
-    let mut builder = world.create_entity().with(components::Item {
-        handle: details_handle,
-        properties: match properties {
-            Some(p) => p,
-            None => Vec::new(),
-        },
-    });
-
-    if is_container {
-        builder = builder.with(components::Container {});
-    }
-
-    match spawn_type {
-        SpawnType::TilePosition(_pos) => unimplemented!("Not implemented"),
-        SpawnType::TransformPosition(_pos) => unimplemented!("Not implemented"),
-        SpawnType::Parent(parent_entity) => {
-            builder = builder.with(Parent {
-                entity: parent_entity,
-            });
+    let id = tiles.id_from_vector(coord);
+    {
+        let mut occupants: WriteTiles<TileEntities> = SystemData::fetch(&world.res);
+        if let Some(occupants) = occupants.get_mut(id) {
+            occupants.0.insert(entity);
         }
     }
+    let (mut doorways, mut obstructions): (
+        WriteTiles<components::Doorway>,
+        WriteTiles<components::Obstruction>,
+    ) = SystemData::fetch(&world.res);
+    doorways.insert(id, doorway);
+    obstructions.insert(id, components::Obstruction::Impassable);
+    world
+        .res
+        .fetch_mut::<EventChannel<TileChangeEvent>>()
+        .single_write(TileChangeEvent { id });
 
-    builder.build()
+    entity
 }
@@ -0,0 +1,421 @@
+use amethyst::ecs::{Component, DenseVecStorage};
+use serde::{Deserialize, Serialize};
+use specs_derive::Component;
+
+use crate::assets::material::Material;
+
+/// Hit points a blow of quality 1 from something as hard as the reference material deals.
+pub const blow_damage: f32 = 10.0;
+/// Material strengths a weapon is measured against, about those of iron.
+pub const reference_impact_fracture: f64 = 1_000_000.0;
+pub const reference_tensile_fracture: f64 = 300_000.0;
+pub const reference_edge: f64 = 10_000.0;
+/// Sharpness of weapons made of nothing known.
+pub const default_edge: f32 = 0.5;
+/// Sharpness a blow needs to cut rather than bludgeon.
+pub const cut_threshold: f32 = 0.2;
+/// Share of the hit points of a part a single blow must deal to break its bones.
+pub const fracture_share: f32 = 0.4;
+/// Share of the hit points of a part a cut must deal to reach the organs inside.
+pub const penetration_share: f32 = 0.3;
+
+/// Blood lost per unit of game time by a wound bleeding at 1.
+pub const bleed_rate: f32 = 0.0005;
+/// Bleeding stopped per unit of game time as the wound clots.
+pub const clotting_rate: f32 = 0.0005;
+/// Blood made back per unit of game time, while nothing bleeds.
+pub const blood_regeneration: f32 = 0.00002;
+/// Blood left at which a body bleeds out.
+pub const lethal_blood: f32 = 0.4;
+/// Infection an open cut takes on per unit of game time, for each hit point of damage.
+pub const infection_rate: f32 = 0.00002;
+/// Hit points an infected wound eats into its part per unit of game time.
+pub const infection_damage: f32 = 0.002;
+/// Hit points of damage a bruise heals per unit of game time.
+pub const healing_rate: f32 = 0.002;
+
+/// The kind of a body part, deciding what happens when it is hit.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum PartKind {
+    Head,
+    Torso,
+    Arm,
+    Leg,
+    /// Inside another part, only reached by cuts deep into it.
+    Organ,
+}
+impl PartKind {
+    pub fn has_bone(self) -> bool {
+        self != PartKind::Organ
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum WoundKind {
+    Bruise,
+    /// Bleeds until it clots, and festers if left open too long.
+    Cut,
+    Fracture,
+}
+impl WoundKind {
+    /// Multiplier of how fast the wound heals.
+    pub fn healing(self) -> f32 {
+        match self {
+            WoundKind::Bruise => 1.0,
+            WoundKind::Cut => 0.5,
+            WoundKind::Fracture => 0.1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Wound {
+    pub kind: WoundKind,
+    /// Hit points taken from its part.
+    pub damage: f32,
+    /// How fast it bleeds, until it clots.
+    pub bleeding: f32,
+    /// Infected once it reaches 1, after which it worsens rather than heals.
+    pub infection: f32,
+}
+impl Wound {
+    pub fn new(kind: WoundKind, damage: f32, bleeding: f32) -> Self {
+        Self {
+            kind,
+            damage,
+            bleeding,
+            infection: 0.0,
+        }
+    }
+
+    pub fn is_bleeding(&self) -> bool {
+        self.bleeding > 0.0
+    }
+
+    pub fn is_infected(&self) -> bool {
+        self.infection >= 1.0
+    }
+
+    /// Bleeds, clots, festers and heals over `elapsed` game time, returning the blood lost.
+    fn update(&mut self, elapsed: u64) -> f32 {
+        let elapsed = elapsed as f32;
+        let lost = self.bleeding * bleed_rate * elapsed;
+        self.bleeding = (self.bleeding - clotting_rate * elapsed).max(0.0);
+
+        if self.kind == WoundKind::Cut {
+            self.infection += infection_rate * self.damage * elapsed;
+        }
+        if self.is_infected() {
+            self.damage += infection_damage * elapsed;
+        } else {
+            self.damage = (self.damage - healing_rate * self.kind.healing() * elapsed).max(0.0);
+        }
+        lost
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BodyPart {
+    pub name: String,
+    pub kind: PartKind,
+    pub max_hp: f32,
+    /// Share of the body it covers, and so how likely it is to be hit.
+    pub size: f32,
+    /// Index of the part an organ is inside.
+    pub inside: Option<usize>,
+    /// Whether losing it kills.
+    pub vital: bool,
+    pub wounds: Vec<Wound>,
+}
+impl BodyPart {
+    pub fn new(name: &str, kind: PartKind, max_hp: f32, size: f32, vital: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            max_hp,
+            size,
+            inside: None,
+            vital,
+            wounds: Vec::new(),
+        }
+    }
+
+    pub fn organ(name: &str, max_hp: f32, inside: usize, vital: bool) -> Self {
+        Self {
+            inside: Some(inside),
+            ..Self::new(name, PartKind::Organ, max_hp, 0.0, vital)
+        }
+    }
+
+    pub fn hp(&self) -> f32 {
+        self.max_hp - self.wounds.iter().map(|wound| wound.damage).sum::<f32>()
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.hp() <= 0.0
+    }
+}
+
+/// A blow struck at a body, from how hard and how sharp it is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Blow {
+    /// Hit points it deals.
+    pub force: f32,
+    /// How sharp its edge is, nothing for blunt blows.
+    pub edge: f32,
+}
+impl Blow {
+    /// A blow from a weapon of `quality` made of `material`, struck with its edge if `cutting`.
+    /// Harder materials strike harder, and those which hold a fine edge without chipping cut
+    /// deeper.
+    pub fn new(quality: f32, cutting: bool, material: Option<&Material>) -> Self {
+        let hardness = material.map_or(1.0, |material| {
+            (material.impact_fracture() / reference_impact_fracture) as f32
+        });
+        let edge = if cutting {
+            material.map_or(default_edge, |material| {
+                let sharpness = material.max_edge().unwrap_or(0.0) / reference_edge;
+                let toughness = (material.tensile_fracture() / reference_tensile_fracture).min(1.0);
+                (sharpness * toughness) as f32
+            })
+        } else {
+            0.0
+        };
+
+        Self {
+            force: quality * hardness.max(0.1) * blow_damage,
+            edge: quality * edge,
+        }
+    }
+
+    /// The blow left once `armor` has absorbed some of it.
+    pub fn through(self, armor: f32) -> Self {
+        let kept = 1.0 / (1.0 + armor.max(0.0));
+        Self {
+            force: self.force * kept,
+            edge: self.edge * kept,
+        }
+    }
+}
+
+/// The parts of a living thing and the wounds dealt to them, see `InjurySystem`. Cuts bleed, and
+/// the body dies once it has bled out or lost a vital part.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Body {
+    pub parts: Vec<BodyPart>,
+    /// Share of its blood left.
+    pub blood: f32,
+}
+impl Default for Body {
+    fn default() -> Self {
+        Self::humanoid()
+    }
+}
+impl Body {
+    /// A head, torso, two arms and two legs, with the organs inside them.
+    pub fn humanoid() -> Self {
+        Self {
+            parts: vec![
+                BodyPart::new("Head", PartKind::Head, 30.0, 0.1, true),
+                BodyPart::new("Torso", PartKind::Torso, 60.0, 0.34, true),
+                BodyPart::new("Left Arm", PartKind::Arm, 40.0, 0.13, false),
+                BodyPart::new("Right Arm", PartKind::Arm, 40.0, 0.13, false),
+                BodyPart::new("Left Leg", PartKind::Leg, 50.0, 0.15, false),
+                BodyPart::new("Right Leg", PartKind::Leg, 50.0, 0.15, false),
+                BodyPart::organ("Brain", 10.0, 0, true),
+                BodyPart::organ("Heart", 15.0, 1, true),
+                BodyPart::organ("Lungs", 20.0, 1, true),
+                BodyPart::organ("Stomach", 20.0, 1, false),
+            ],
+            blood: 1.0,
+        }
+    }
+
+    /// A head, torso and four legs, with the organs inside them.
+    pub fn quadruped() -> Self {
+        Self {
+            parts: vec![
+                BodyPart::new("Head", PartKind::Head, 30.0, 0.12, true),
+                BodyPart::new("Torso", PartKind::Torso, 60.0, 0.4, true),
+                BodyPart::new("Left Foreleg", PartKind::Leg, 40.0, 0.12, false),
+                BodyPart::new("Right Foreleg", PartKind::Leg, 40.0, 0.12, false),
+                BodyPart::new("Left Hind Leg", PartKind::Leg, 40.0, 0.12, false),
+                BodyPart::new("Right Hind Leg", PartKind::Leg, 40.0, 0.12, false),
+                BodyPart::organ("Brain", 10.0, 0, true),
+                BodyPart::organ("Heart", 15.0, 1, true),
+                BodyPart::organ("Lungs", 20.0, 1, true),
+                BodyPart::organ("Stomach", 20.0, 1, false),
+            ],
+            blood: 1.0,
+        }
+    }
+
+    /// The part `roll`, between 0 and 1, lands on, weighted by the size of each.
+    pub fn part_at(&self, roll: f32) -> usize {
+        let total = self.parts.iter().map(|part| part.size).sum::<f32>();
+        let mut remaining = roll * total;
+        for (index, part) in self.parts.iter().enumerate() {
+            if part.size > 0.0 && remaining < part.size {
+                return index;
+            }
+            remaining -= part.size;
+        }
+        self.parts
+            .iter()
+            .rposition(|part| part.size > 0.0)
+            .unwrap_or(0)
+    }
+
+    /// Wounds the part at `index` with `blow`, returning the wounds dealt. Sharp blows cut and
+    /// bleed, deep enough to reach the organs inside, while blunt ones bruise or break bones.
+    pub fn injure(&mut self, index: usize, blow: &Blow) -> Vec<(usize, WoundKind)> {
+        let part = match self.parts.get_mut(index) {
+            Some(part) => part,
+            None => return Vec::new(),
+        };
+        if blow.force <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut dealt = Vec::new();
+        if blow.edge >= cut_threshold {
+            let damage = blow.force * (1.0 + blow.edge);
+            let bleeding = (damage / part.max_hp).min(1.0);
+            part.wounds
+                .push(Wound::new(WoundKind::Cut, damage, bleeding));
+            dealt.push((index, WoundKind::Cut));
+
+            // What the part didn't stop is shared between the organs inside
+            let excess = damage - part.max_hp * penetration_share;
+            let organs = self
+                .parts
+                .iter()
+                .enumerate()
+                .filter(|(_, organ)| organ.inside == Some(index))
+                .map(|(organ, _)| organ)
+                .collect::<Vec<_>>();
+            if excess > 0.0 && !organs.is_empty() {
+                let damage = excess / organs.len() as f32;
+                for organ in organs {
+                    let bleeding = (damage / self.parts[organ].max_hp).min(1.0);
+                    self.parts[organ]
+                        .wounds
+                        .push(Wound::new(WoundKind::Cut, damage, bleeding));
+                    dealt.push((organ, WoundKind::Cut));
+                }
+            }
+        } else if part.kind.has_bone() && blow.force >= part.max_hp * fracture_share {
+            part.wounds
+                .push(Wound::new(WoundKind::Fracture, blow.force, 0.0));
+            dealt.push((index, WoundKind::Fracture));
+        } else {
+            part.wounds
+                .push(Wound::new(WoundKind::Bruise, blow.force * 0.5, 0.0));
+            dealt.push((index, WoundKind::Bruise));
+        }
+        dealt
+    }
+
+    pub fn is_bleeding(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|part| part.wounds.iter().any(Wound::is_bleeding))
+    }
+
+    /// Bleeds, festers and heals every wound over `elapsed` game time, making back blood while
+    /// none bleed.
+    pub fn update(&mut self, elapsed: u64) {
+        let bleeding = self.is_bleeding();
+        let mut lost = 0.0;
+        for part in &mut self.parts {
+            for wound in &mut part.wounds {
+                lost += wound.update(elapsed);
+            }
+            part.wounds.retain(|wound| wound.damage > 0.0);
+        }
+
+        self.blood -= lost;
+        if !bleeding {
+            self.blood += blood_regeneration * elapsed as f32;
+        }
+        self.blood = self.blood.max(0.0).min(1.0);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.blood <= lethal_blood
+            || self
+                .parts
+                .iter()
+                .any(|part| part.vital && part.is_destroyed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wounds_from_blows() {
+        let torso = 1;
+        let arm = 2;
+
+        let mut body = Body::default();
+        assert_eq!(
+            body.injure(arm, &Blow::new(0.5, false, None)),
+            vec![(arm, WoundKind::Bruise)]
+        );
+        assert_eq!(
+            body.injure(arm, &Blow::new(2.0, false, None)),
+            vec![(arm, WoundKind::Fracture)]
+        );
+        assert!(!body.is_bleeding());
+        assert!(body.parts[arm].hp() < body.parts[arm].max_hp);
+
+        // A deep cut to the torso reaches the organs inside it
+        let dealt = body.injure(torso, &Blow::new(2.0, true, None));
+        assert_eq!(dealt[0], (torso, WoundKind::Cut));
+        assert!(dealt.len() > 1);
+        assert!(dealt[1..]
+            .iter()
+            .all(|(organ, _)| body.parts[*organ].inside == Some(torso)));
+        assert!(body.is_bleeding());
+
+        // Armor takes the edge off
+        let blow = Blow::new(1.0, true, None).through(2.0);
+        assert!(blow.edge < cut_threshold);
+
+        let torso_roll = 0.2;
+        assert_eq!(body.part_at(torso_roll), torso);
+        assert_eq!(body.part_at(0.0), 0);
+        assert!(body.parts[body.part_at(0.999)].size > 0.0);
+    }
+
+    #[test]
+    fn bleeding_and_healing() {
+        let leg = 4;
+
+        // A shallow cut clots and heals
+        let mut body = Body::default();
+        body.injure(leg, &Blow::new(0.5, true, None));
+        for _ in 0..100 {
+            body.update(100);
+        }
+        assert!(!body.is_dead());
+        assert!(body.parts[leg].wounds.is_empty());
+        assert!(body.blood > lethal_blood);
+
+        // A body cut to pieces bleeds out
+        let mut body = Body::default();
+        for part in 2..6 {
+            body.injure(part, &Blow::new(3.0, true, None));
+        }
+        let mut elapsed = 0;
+        while !body.is_dead() && elapsed < 10_000 {
+            body.update(10);
+            elapsed += 10;
+        }
+        assert!(body.is_dead());
+        assert!(body.blood <= lethal_blood);
+    }
+}
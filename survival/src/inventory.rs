@@ -2,11 +2,238 @@
 
 use amethyst::{
     assets::AssetStorage,
-    core::ParentHierarchy,
-    ecs::{storage::GenericReadStorage, BitSet, Entities, Entity, Join},
+    core::{Parent, ParentHierarchy},
+    ecs::{storage::GenericReadStorage, BitSet, Entities, Entity, Join, WriteStorage},
 };
+use failure::Fail;
 
+use crate::assets::item::{ContainerCanHold, Details};
 use crate::components;
+use crate::liquids::Liquid;
+
+/// Containers can be nested this deep, counting the outermost.
+pub const max_nesting_depth: usize = 3;
+
+#[derive(Debug, Fail, PartialEq)]
+pub enum InventoryError {
+    #[fail(display = "{:?} is not a container", _0)]
+    NotAContainer(Entity),
+    #[fail(display = "{:?} is not an item with known details", _0)]
+    UnknownItem(Entity),
+    #[fail(display = "the container can't hold {:?}", _0)]
+    CannotHold(ContainerCanHold),
+    #[fail(display = "needs {} volume, but only {} is free", needed, free)]
+    TooLarge { needed: f32, free: f32 },
+    #[fail(display = "needs {} weight, but only {} is free", needed, free)]
+    TooHeavy { needed: f32, free: f32 },
+    #[fail(display = "containers would be nested too deep")]
+    TooDeep,
+    #[fail(display = "a container can't be put inside itself")]
+    IntoItself,
+    #[fail(display = "{:?} is not in a container", _0)]
+    NotContained(Entity),
+    #[fail(display = "the container already holds {}", _0)]
+    HoldsOther(String),
+}
+
+/// The size of an item, or of what is held in a container.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Bulk {
+    pub volume: f32,
+    pub weight: f32,
+}
+
+impl components::Container {
+    /// Whether `item` fits alongside the `used` bulk already held.
+    pub fn accepts(
+        &self,
+        used: Bulk,
+        item: Bulk,
+        held_as: ContainerCanHold,
+    ) -> Result<(), InventoryError> {
+        if !self.can_hold.contains(held_as) {
+            return Err(InventoryError::CannotHold(held_as));
+        }
+        let free = self.volume - used.volume;
+        if item.volume > free {
+            return Err(InventoryError::TooLarge {
+                needed: item.volume,
+                free,
+            });
+        }
+        let free = self.weight - used.weight;
+        if item.weight > free {
+            return Err(InventoryError::TooHeavy {
+                needed: item.weight,
+                free,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// What the inventory rules are checked against.
+pub struct Contents<'a, C, I, L> {
+    pub hierarchy: &'a ParentHierarchy,
+    pub containers: C,
+    pub items: I,
+    pub liquids: L,
+    pub details: &'a AssetStorage<Details>,
+}
+impl<'a, C, I, L> Contents<'a, C, I, L>
+where
+    C: GenericReadStorage<Component = components::Container>,
+    I: GenericReadStorage<Component = components::Item>,
+    L: GenericReadStorage<Component = Liquid>,
+{
+    fn details(&self, item: Entity) -> Result<&'a Details, InventoryError> {
+        self.items
+            .get(item)
+            .and_then(|item| self.details.get(&item.handle))
+            .ok_or(InventoryError::UnknownItem(item))
+    }
+
+    /// The outer volume of `item`, and its weight along with everything in it.
+    pub fn bulk(&self, item: Entity) -> Result<Bulk, InventoryError> {
+        let details = self.details(item)?;
+        Ok(Bulk {
+            volume: details.volume(),
+            weight: details.weight + self.held(item).weight,
+        })
+    }
+
+    /// The bulk of everything directly in `container`, liquid included.
+    pub fn held(&self, container: Entity) -> Bulk {
+        let liquid = self.liquids.get(container).map(Liquid::bulk);
+        self.hierarchy
+            .children(container)
+            .iter()
+            .filter_map(|child| self.bulk(*child).ok())
+            .chain(liquid)
+            .fold(Bulk::default(), |total, bulk| Bulk {
+                volume: total.volume + bulk.volume,
+                weight: total.weight + bulk.weight,
+            })
+    }
+
+    /// How many containers deep `entity` is, counting itself if it is one.
+    pub fn depth(&self, entity: Entity) -> usize {
+        let mut depth = 0;
+        let mut current = Some(entity);
+        while let Some(entity) = current {
+            if self.containers.get(entity).is_some() {
+                depth += 1;
+            }
+            current = self.hierarchy.parent(entity);
+        }
+        depth
+    }
+
+    /// How many containers deep the contents of `item` go, counting itself if it is one.
+    pub fn height(&self, item: Entity) -> usize {
+        if self.containers.get(item).is_none() {
+            return 0;
+        }
+        1 + self
+            .hierarchy
+            .children(item)
+            .iter()
+            .map(|child| self.height(*child))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether `item` can be put in `container`, besides wherever it is now.
+    pub fn can_insert(&self, item: Entity, container: Entity) -> Result<(), InventoryError> {
+        let holder = self
+            .containers
+            .get(container)
+            .ok_or(InventoryError::NotAContainer(container))?;
+        if item == container || self.hierarchy.all_children(item).contains(container.id()) {
+            return Err(InventoryError::IntoItself);
+        }
+        if nesting_depth(self.depth(container), self.height(item)) > max_nesting_depth {
+            return Err(InventoryError::TooDeep);
+        }
+
+        let details = self.details(item)?;
+        let mut used = self.held(container);
+        // Moving within the same container takes no more room
+        if self.hierarchy.parent(item) == Some(container) {
+            let bulk = self.bulk(item)?;
+            used.volume -= bulk.volume;
+            used.weight -= bulk.weight;
+        }
+        holder.accepts(used, self.bulk(item)?, details.held_as())
+    }
+}
+
+/// How deep containers would nest with something `height` containers tall put in a container
+/// `depth` deep.
+fn nesting_depth(depth: usize, height: usize) -> usize {
+    depth + height
+}
+
+/// Puts `item` in `container`, if it fits.
+pub fn insert<C, I, L>(
+    contents: &Contents<C, I, L>,
+    parents: &mut WriteStorage<Parent>,
+    item: Entity,
+    container: Entity,
+) -> Result<(), InventoryError>
+where
+    C: GenericReadStorage<Component = components::Container>,
+    I: GenericReadStorage<Component = components::Item>,
+    L: GenericReadStorage<Component = Liquid>,
+{
+    contents.can_insert(item, container)?;
+    parents
+        .insert(item, Parent { entity: container })
+        .map_err(|_| InventoryError::UnknownItem(item))?;
+    Ok(())
+}
+
+/// Takes `item` out of the container it is in, returning that container.
+pub fn remove<C, I, L>(
+    contents: &Contents<C, I, L>,
+    parents: &mut WriteStorage<Parent>,
+    item: Entity,
+) -> Result<Entity, InventoryError>
+where
+    C: GenericReadStorage<Component = components::Container>,
+    I: GenericReadStorage<Component = components::Item>,
+    L: GenericReadStorage<Component = Liquid>,
+{
+    let container = parents
+        .get(item)
+        .map(|parent| parent.entity)
+        .filter(|parent| contents.containers.get(*parent).is_some())
+        .ok_or(InventoryError::NotContained(item))?;
+    parents.remove(item);
+    Ok(container)
+}
+
+/// Moves `item` from the container it is in to `to`, leaving it where it was if it doesn't fit.
+pub fn transfer<C, I, L>(
+    contents: &Contents<C, I, L>,
+    parents: &mut WriteStorage<Parent>,
+    item: Entity,
+    to: Entity,
+) -> Result<(), InventoryError>
+where
+    C: GenericReadStorage<Component = components::Container>,
+    I: GenericReadStorage<Component = components::Item>,
+    L: GenericReadStorage<Component = Liquid>,
+{
+    let contained = contents
+        .hierarchy
+        .parent(item)
+        .map_or(false, |parent| contents.containers.get(parent).is_some());
+    if !contained {
+        return Err(InventoryError::NotContained(item));
+    }
+    insert(contents, parents, item, to)
+}
 
 pub fn in_container<S>(item: Entity, hierarchy: &ParentHierarchy, container_storage: &S) -> bool
 where
@@ -107,3 +334,60 @@ where
 
     inv
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn container_capacity() {
+        let bottle = components::Container {
+            volume: 2.0,
+            weight: 4.0,
+            can_hold: ContainerCanHold::Liquid,
+        };
+        let water = Bulk {
+            volume: 1.5,
+            weight: 1.5,
+        };
+        assert_eq!(
+            bottle.accepts(Bulk::default(), water, ContainerCanHold::Liquid),
+            Ok(())
+        );
+        assert_eq!(
+            bottle.accepts(Bulk::default(), water, ContainerCanHold::Solid),
+            Err(InventoryError::CannotHold(ContainerCanHold::Solid))
+        );
+        assert_eq!(
+            bottle.accepts(water, water, ContainerCanHold::Liquid),
+            Err(InventoryError::TooLarge {
+                needed: 1.5,
+                free: 0.5
+            })
+        );
+
+        let sack = components::Container {
+            volume: 10.0,
+            weight: 5.0,
+            can_hold: ContainerCanHold::Solid,
+        };
+        let stone = Bulk {
+            volume: 1.0,
+            weight: 3.0,
+        };
+        assert!(sack
+            .accepts(Bulk::default(), stone, ContainerCanHold::Solid)
+            .is_ok());
+        assert_eq!(
+            sack.accepts(stone, stone, ContainerCanHold::Solid),
+            Err(InventoryError::TooHeavy {
+                needed: 3.0,
+                free: 2.0
+            })
+        );
+
+        // A sack in a chest in a cart is as deep as it goes
+        assert!(nesting_depth(2, 1) <= max_nesting_depth);
+        assert!(nesting_depth(2, 2) > max_nesting_depth);
+    }
+}
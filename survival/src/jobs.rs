@@ -0,0 +1,215 @@
+use amethyst::{core::math::Vector3, ecs::Entity};
+use std::collections::HashMap;
+
+use crate::goap::{ActionCatagory, Condition, Goal, Motive};
+
+pub type JobId = u32;
+
+/// Outstanding work, done by any pawn whose labor priorities include its catagory.
+#[derive(Clone, Debug)]
+pub struct Job {
+    id: JobId,
+    catagory: ActionCatagory,
+    /// The world state which finishes the job.
+    conditions: Vec<Condition>,
+    pub priority: f32,
+    pub position: Option<Vector3<u32>>,
+    claimed_by: Option<Entity>,
+}
+impl Job {
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    pub fn catagory(&self) -> &ActionCatagory {
+        &self.catagory
+    }
+
+    pub fn claimed_by(&self) -> Option<Entity> {
+        self.claimed_by
+    }
+
+    /// The goal a pawn plans towards to do this job.
+    pub fn goal(&self) -> Goal {
+        Goal {
+            name: format!("{:?} job {}", self.catagory, self.id),
+            motive: Motive::Labor(self.catagory.clone()),
+            weight: self.priority,
+            conditions: self.conditions.clone(),
+        }
+    }
+}
+
+/// Every outstanding job, by catagory. A job is claimed by a single pawn at a time, and
+/// released again if the pawn gives up on it.
+#[derive(Default, Debug)]
+pub struct JobBoard {
+    next_id: JobId,
+    jobs: HashMap<ActionCatagory, Vec<Job>>,
+}
+impl JobBoard {
+    pub fn post(
+        &mut self,
+        catagory: ActionCatagory,
+        conditions: Vec<Condition>,
+        position: Option<Vector3<u32>>,
+    ) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.jobs.entry(catagory.clone()).or_default().push(Job {
+            id,
+            catagory,
+            conditions,
+            priority: 1.0,
+            position,
+            claimed_by: None,
+        });
+        id
+    }
+
+    pub fn get(&self, id: JobId) -> Option<&Job> {
+        self.iter().find(|job| job.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: JobId) -> Option<&mut Job> {
+        self.jobs
+            .values_mut()
+            .flat_map(|jobs| jobs.iter_mut())
+            .find(|job| job.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.values().flat_map(|jobs| jobs.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Unclaimed jobs of a catagory, oldest first.
+    pub fn claimable<'a>(&'a self, catagory: &ActionCatagory) -> impl Iterator<Item = &'a Job> {
+        self.jobs
+            .get(catagory)
+            .into_iter()
+            .flat_map(|jobs| jobs.iter())
+            .filter(|job| job.claimed_by.is_none())
+    }
+
+    /// Claims a job for `entity`, unless someone else already has it.
+    pub fn claim(&mut self, id: JobId, entity: Entity) -> bool {
+        match self.get_mut(id) {
+            Some(job) if job.claimed_by.map_or(true, |owner| owner == entity) => {
+                job.claimed_by = Some(entity);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Puts a job back on the board for others to claim.
+    pub fn release(&mut self, id: JobId) {
+        if let Some(job) = self.get_mut(id) {
+            job.claimed_by = None;
+        }
+    }
+
+    /// Releases every job claimed by entities for which `is_alive` no longer holds.
+    pub fn release_abandoned<F>(&mut self, is_alive: F)
+    where
+        F: Fn(Entity) -> bool,
+    {
+        for job in self.jobs.values_mut().flat_map(|jobs| jobs.iter_mut()) {
+            if job.claimed_by.map_or(false, |owner| !is_alive(owner)) {
+                job.claimed_by = None;
+            }
+        }
+    }
+
+    /// Takes a finished job off the board.
+    pub fn complete(&mut self, id: JobId) -> Option<Job> {
+        for jobs in self.jobs.values_mut() {
+            if let Some(index) = jobs.iter().position(|job| job.id == id) {
+                return Some(jobs.remove(index));
+            }
+        }
+        None
+    }
+
+    /// The claimable job `labor_priorities` most favour, weighing each by its own priority.
+    pub fn best_for(&self, labor_priorities: &HashMap<ActionCatagory, f32>) -> Option<&Job> {
+        labor_priorities
+            .iter()
+            .filter(|(_, priority)| **priority > 0.0)
+            .flat_map(|(catagory, priority)| {
+                self.claimable(catagory)
+                    .map(move |job| (job, priority * job.priority))
+            })
+            // Older jobs first between equals
+            .max_by(|(a, a_score), (b, b_score)| {
+                a_score
+                    .partial_cmp(b_score)
+                    .unwrap()
+                    .then_with(|| b.id.cmp(&a.id))
+            })
+            .map(|(job, _)| job)
+    }
+}
+
+/// Recipes asked to be made, by their key in `recipes.ron`. `CraftingSystem` posts each to the
+/// `JobBoard`.
+#[derive(Default, Debug)]
+pub struct CraftOrders {
+    pending: Vec<String>,
+}
+impl CraftOrders {
+    pub fn order(&mut self, recipe: &str) {
+        self.pending.push(recipe.to_string());
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = String> + '_ {
+        self.pending.drain(..)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst::ecs::{Builder, World};
+
+    #[test]
+    fn claiming_jobs() {
+        let mut world = World::new();
+        let first = world.create_entity().build();
+        let second = world.create_entity().build();
+
+        let mut board = JobBoard::default();
+        let chop = board.post(ActionCatagory::Woodcutting, Vec::new(), None);
+        let mine = board.post(ActionCatagory::Mining, Vec::new(), None);
+        board.get_mut(mine).unwrap().priority = 2.0;
+
+        let mut priorities = HashMap::new();
+        priorities.insert(ActionCatagory::Woodcutting, 1.5);
+        priorities.insert(ActionCatagory::Mining, 0.5);
+        assert_eq!(board.best_for(&priorities).unwrap().id(), chop);
+
+        assert!(board.claim(chop, first));
+        assert!(!board.claim(chop, second));
+        assert_eq!(board.best_for(&priorities).unwrap().id(), mine);
+
+        board.release_abandoned(|entity| entity != first);
+        assert_eq!(board.get(chop).unwrap().claimed_by(), None);
+
+        assert!(board.claim(chop, second));
+        assert!(board.complete(chop).is_some());
+        assert!(board.get(chop).is_none());
+        assert_eq!(board.len(), 1);
+
+        priorities.remove(&ActionCatagory::Mining);
+        assert!(board.best_for(&priorities).is_none());
+    }
+}
@@ -19,6 +19,7 @@ pub mod bitflags_serial;
 
 pub mod goap;
 pub mod mapgen;
+pub mod fov;
 pub mod pathfinding;
 pub mod system_chain;
 
@@ -33,21 +34,40 @@ pub mod utils;
 
 pub mod actions;
 
+pub mod factions;
+pub mod farming;
+pub mod temperature;
+pub mod injury;
+pub mod combat;
+pub mod butchery;
+pub mod cooking;
+pub mod decals;
+pub mod designations;
+pub mod eating;
+pub mod fluids;
 pub mod inventory;
+pub mod jobs;
+pub mod liquids;
+pub mod weather;
+pub mod autotile;
+pub mod minimap;
+pub mod lighting;
+pub mod selection;
 
 pub mod game_data;
 pub mod initializers;
 
 pub mod map;
+pub mod persistence;
 
 pub use game_data::{SurvivalData, SurvivalDataBuilder, SurvivalState};
 
 use amethyst::{
-    assets::{HotReloadBundle, PrefabLoaderSystem},
+    assets::{HotReloadBundle, PrefabLoaderSystem, Processor},
     core::{frame_limiter::FrameRateLimitStrategy, TransformBundle},
     input::InputBundle,
     prelude::*,
-    renderer::{DisplayConfig, DrawFlat2D, Pipeline, PosNormTex, RenderBundle, Stage},
+    renderer::{DisplayConfig, Pipeline, PosNormTex, RenderBundle, Stage},
     ui::UiBundle,
     utils::application_root_dir,
     utils::{fps_counter::FPSCounterBundle, scene::BasicScenePrefab},
@@ -64,7 +84,9 @@ pub fn run(root_logger: &slog::Logger) -> amethyst::Result<()> {
         Stage::with_backbuffer()
             .clear_target([0.1, 0.1, 0.1, 1.0], 1.0)
             .with_pass(crate::render::tiles::Pass::new())
-            .with_pass(DrawFlat2D::new())
+            .with_pass(crate::render::tiles::EntityPass::new())
+            .with_pass(crate::render::tiles::SelectionPass::new())
+            .with_pass(crate::render::tiles::PrecipitationPass::new())
             .with_pass(amethyst::ui::DrawUi::new())
             //.with_pass(amethyst_imgui::DrawUi::default().docking()),
     );
@@ -94,6 +116,12 @@ pub fn run(root_logger: &slog::Logger) -> amethyst::Result<()> {
             &["imgui_begin_frame"],
         )
         .with_core_bundle(HotReloadBundle::default())?
+        .with_core(Processor::<assets::Item>::new(), "item_processor", &[])
+        .with_core(
+            Processor::<assets::Material>::new(),
+            "material_processor",
+            &[],
+        )
         .with_core_bundle(UiBundle::<
             actions::PlayerInputAction,
             actions::PlayerInputAction,
@@ -121,12 +149,175 @@ pub fn run(root_logger: &slog::Logger) -> amethyst::Result<()> {
             "imgui_end_frame",
             &["imgui_begin_frame", "ui", "debug"],
         ) // All systems which use imgui must be here.
-        .with_level(systems::WearingSystem::default(), "wearing", &[])
-        .with_level(systems::InputSystem::default(), "input", &[])
+        // The windows of a game only queue their drawing, for `ui` to draw them within the frame
+        // of the core systems, so they are left out of the menus shown before one is started
+        .with_level(
+            systems::ui::GoapDebugWindow::default(),
+            "goap_debug_window",
+            &[],
+        )
+        .with_level(systems::ui::ClockWindow::default(), "clock_window", &[])
+        .with_level(systems::ui::TileWindow::default(), "tile_window", &[])
+        .with_level(systems::ui::MinimapWindow::default(), "minimap_window", &[])
+        .with_level(systems::ui::SaveWindow::default(), "save_window", &[])
+        .with_level(systems::SelectionSystem::default(), "selection", &[])
+        .with_level(systems::InputSystem::default(), "input", &["selection"])
         .with_level(systems::TilePositionSystem::default(), "tile_position", &[])
-        .with_level(systems::MovementSystem::default(), "movement", &[])
+        .with_level(systems::FovSystem::default(), "fov", &["tile_position"])
+        .with_level(systems::ConnectivitySystem::default(), "connectivity", &[])
+        .with_level(systems::PathCacheSystem::default(), "path_cache", &[])
+        .with_level(
+            systems::TileEntityCleanupSystem::default(),
+            "tile_entity_cleanup",
+            &[],
+        )
+        .with_level(systems::SensorSystem::default(), "sensor", &["tile_position"])
+        .with_level(systems::ThreatSystem::default(), "threat", &["sensor"])
+        .with_level(systems::NeedsSystem::default(), "needs", &[])
+        .with_level(
+            systems::TemperatureSystem::default(),
+            "temperature",
+            &["needs"],
+        )
+        .with_level(systems::InjurySystem::default(), "injury", &[])
+        .with_level(systems::NutritionSystem::default(), "nutrition", &[])
+        .with_level(systems::MoodSystem::default(), "mood", &["needs", "nutrition"])
+        .with_level(systems::JobSystem::default(), "jobs", &["needs"])
+        .with_level(
+            systems::CreatureSystem::default(),
+            "creature",
+            &["needs", "tile_position"],
+        )
+        .with_level(
+            systems::PathfindingSystem::default(),
+            "pathfinding",
+            &["path_cache", "creature"],
+        )
+        .with_level(
+            systems::GoapPlannerSystem::default(),
+            "goap_planner",
+            &["sensor", "threat", "needs", "jobs", "mood"],
+        )
+        .with_level(
+            systems::PlanExecutionSystem::default(),
+            "plan_execution",
+            &["goap_planner"],
+        )
+        .with_level(
+            systems::WearingSystem::default(),
+            "wearing",
+            &["plan_execution"],
+        )
+        .with_level(
+            systems::CraftingSystem::default(),
+            "crafting",
+            &["plan_execution"],
+        )
+        .with_level(
+            systems::FarmingSystem::default(),
+            "farming",
+            &["plan_execution"],
+        )
+        .with_level(systems::TreeSystem::default(), "trees", &["plan_execution"])
+        .with_level(
+            systems::CombatSystem::default(),
+            "combat",
+            &["plan_execution"],
+        )
+        .with_level(
+            systems::DesignationSystem::default(),
+            "designations",
+            &["plan_execution"],
+        )
+        .with_level(
+            systems::SpoilageSystem::default(),
+            "spoilage",
+            &["plan_execution", "temperature"],
+        )
+        .with_level(
+            systems::LiquidSystem::default(),
+            "liquids",
+            &["plan_execution"],
+        )
+        .with_level(
+            systems::ButcherySystem::default(),
+            "butchery",
+            &["plan_execution", "spoilage"],
+        )
+        .with_level(
+            systems::EatingSystem::default(),
+            "eating",
+            &["plan_execution", "spoilage"],
+        )
+        .with_level(
+            systems::DurabilitySystem::default(),
+            "durability",
+            &["crafting", "trees", "combat"],
+        )
+        .with_level(
+            systems::ActionProgressSystem::default(),
+            "action_progress",
+            &["plan_execution"],
+        )
+        .with_level(
+            systems::MovementSystem::default(),
+            "movement",
+            &["input", "plan_execution", "pathfinding"],
+        )
         .with_level(systems::TimeSystem::default(), "time", &[])
-        .with_level(systems::InitiativeSystem::default(), "initiative", &[]);
+        .with_level(
+            systems::DoorSystem::default(),
+            "doors",
+            &["time", "movement"],
+        )
+        .with_level(
+            systems::CameraFollowSystem::default(),
+            "camera_follow",
+            &["input", "movement"],
+        )
+        .with_level(systems::AutosaveSystem::default(), "autosave", &["time"])
+        .with_level(systems::AssetReloadSystem::default(), "asset_reload", &[])
+        .with_level(systems::WeatherSystem::default(), "weather", &["time"])
+        .with_level(
+            systems::FluidSystem::default(),
+            "fluids",
+            &["time", "weather"],
+        )
+        .with_level(
+            systems::AutotileSystem::default(),
+            "autotile",
+            &["fluids"],
+        )
+        .with_level(
+            systems::AnimatedTileSystem::default(),
+            "animated_tiles",
+            &["fluids", "autotile"],
+        )
+        .with_level(
+            systems::LightingSystem::default(),
+            "lighting",
+            &["tile_position"],
+        )
+        .with_level(
+            systems::MinimapSystem::default(),
+            "minimap",
+            &["fov", "autotile"],
+        )
+        .with_level(
+            systems::RenderChunkSystem::default(),
+            "render_chunks",
+            &["autotile", "animated_tiles", "lighting"],
+        )
+        .with_level(
+            systems::PlantGrowthSystem::default(),
+            "plant_growth",
+            &["time", "temperature", "fluids"],
+        )
+        .with_level(
+            systems::InitiativeSystem::default(),
+            "initiative",
+            &["time", "movement", "action_progress"],
+        );
 
     let mut game = Application::build(root, crate::states::FirstLoad::new(root_logger.clone()))?
         .with_frame_limit(FrameRateLimitStrategy::Unlimited, 9999)
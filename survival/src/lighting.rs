@@ -0,0 +1,71 @@
+use amethyst::core::math::Vector3;
+
+use crate::tiles::{TileId, Tiles};
+
+/// Tiles from a fire its light reaches, fading out towards the edge.
+pub const fire_light_radius: u32 = 6;
+
+/// The light a source at `source` with `radius` casts on the tiles of its level within reach
+/// it has a line of sight to, through tiles which aren't `blocked`. Full on the source itself,
+/// and fading with distance.
+pub fn illuminate<F>(
+    tiles: Tiles,
+    source: Vector3<u32>,
+    radius: u32,
+    blocked: F,
+) -> Vec<(TileId, f32)>
+where
+    F: Fn(TileId) -> bool,
+{
+    let from = tiles.id_from_vector(source);
+    let min = Vector3::new(
+        source.x.saturating_sub(radius),
+        source.y.saturating_sub(radius),
+        source.z,
+    );
+    let max = Vector3::new(source.x + radius, source.y + radius, source.z);
+    tiles
+        .iter_volume(min, max)
+        .filter_map(|id| {
+            let coord = tiles.coord(id);
+            let (dx, dy) = (
+                coord.x as f32 - source.x as f32,
+                coord.y as f32 - source.y as f32,
+            );
+            let level = 1.0 - (dx * dx + dy * dy).sqrt() / (radius as f32 + 1.0);
+            if level > 0.0 && tiles.line_of_sight(from, id, &blocked) {
+                Some((id, level))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn light() {
+        let tiles = Tiles::new(20, 20, 2);
+        let wall = |id| {
+            let coord = tiles.coord(id);
+            coord.x == 12 && coord.y <= 12
+        };
+        let lit = illuminate(tiles, Vector3::new(10, 10, 1), 3, wall);
+        let level = |x, y| {
+            lit.iter()
+                .find(|(id, _)| *id == tiles.id(x, y, 1))
+                .map(|(_, level)| *level)
+        };
+
+        assert_eq!(level(10, 10), Some(1.0));
+        assert!(level(11, 10).unwrap() > level(10, 13).unwrap());
+        // Out of reach, and behind the wall
+        assert_eq!(level(14, 14), None);
+        assert!(level(12, 10).is_some());
+        assert_eq!(level(13, 10), None);
+        assert!(lit.iter().all(|(id, _)| tiles.coord(*id).z == 1));
+    }
+}
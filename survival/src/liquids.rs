@@ -0,0 +1,284 @@
+use amethyst::ecs::{Component, DenseVecStorage};
+use serde::{Deserialize, Serialize};
+use specs_derive::Component;
+
+use crate::actions::Action as ActionEvent;
+use crate::assets::item::ContainerCanHold;
+use crate::components::Container;
+use crate::goap::{
+    self, ActionCatagory, Condition, ConditionEquality, ConditionType, ConditionValue, Goal, Motive,
+};
+use crate::inventory::{Bulk, InventoryError};
+
+/// What is drawn from rivers and other standing fluid.
+pub const river_liquid: &str = "Water";
+/// Weight per unit of volume of liquids whose material has no known density.
+pub const default_liquid_density: f32 = 1000.0;
+/// Volume drunk at once, and how much thirst it quenches.
+pub const draught: f32 = 0.0005;
+pub const draught_thirst: f32 = 0.5;
+/// Degrees a boiled drink warms the body, up to a normal temperature.
+pub const hot_drink_warmth: f32 = 1.0;
+/// Least volume poured out on the ground which leaves a puddle, rather than soaking away.
+pub const puddle_volume: f32 = 0.01;
+/// Game time filling a container, boiling what is in it and drinking from it take.
+pub const fill_time: f32 = 10.0;
+pub const boil_time: f32 = 30.0;
+pub const drink_time: f32 = 5.0;
+
+/// Liquid held in a `Container`, which must be able to hold `ContainerCanHold::Liquid`.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct Liquid {
+    /// Name of what it is, in `Materials`.
+    pub material: String,
+    pub volume: f32,
+    /// Weight per unit of volume.
+    pub density: f32,
+    /// Whether it has been boiled, making it a hot drink.
+    pub boiled: bool,
+}
+impl Liquid {
+    pub fn new(material: &str, volume: f32, density: f32) -> Self {
+        Self {
+            material: material.to_string(),
+            volume,
+            density,
+            boiled: false,
+        }
+    }
+
+    pub fn bulk(&self) -> Bulk {
+        Bulk {
+            volume: self.volume,
+            weight: self.volume * self.density,
+        }
+    }
+
+    /// Takes out up to `volume`, returning how much was taken.
+    pub fn take(&mut self, volume: f32) -> f32 {
+        let taken = volume.min(self.volume).max(0.0);
+        self.volume -= taken;
+        taken
+    }
+
+    /// Pours in `volume` more of the same liquid, which stays boiled only if both were.
+    pub fn add(&mut self, volume: f32, boiled: bool) {
+        self.volume += volume;
+        self.boiled &= boiled;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.volume <= 0.0
+    }
+}
+
+/// Something liquid can be drawn from without end, such as a well.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct LiquidSource(pub String);
+
+/// How much of `material` can be poured into `container`, alongside the `used` bulk already in
+/// it, which includes any `held` liquid. Liquids don't mix.
+pub fn room(
+    container: &Container,
+    used: Bulk,
+    held: Option<&Liquid>,
+    material: &str,
+    density: f32,
+) -> Result<f32, InventoryError> {
+    if !container.can_hold.contains(ContainerCanHold::Liquid) {
+        return Err(InventoryError::CannotHold(ContainerCanHold::Liquid));
+    }
+    if let Some(held) = held.filter(|held| !held.is_empty() && held.material != material) {
+        return Err(InventoryError::HoldsOther(held.material.clone()));
+    }
+    let volume = container.volume - used.volume;
+    let weight = (container.weight - used.weight) / density;
+    Ok(volume.min(weight).max(0.0))
+}
+
+fn liquid(material: &str, boiled: bool) -> ConditionValue {
+    ConditionValue::Liquid {
+        material: material.to_string(),
+        boiled,
+    }
+}
+
+/// Carrying a container of `material`, boiled or not.
+pub fn carrying(material: &str, boiled: bool) -> Condition {
+    Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Has,
+        liquid(material, boiled),
+    )
+}
+
+/// Having drunk `material` yourself, which can't be seen, so that goals need the drinking done.
+pub fn drank(material: &str, boiled: bool) -> Condition {
+    Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Me,
+        liquid(material, boiled),
+    )
+}
+
+/// Goals of drinking water when thirsty, and something hot when cold.
+pub fn goals() -> Vec<Goal> {
+    vec![
+        Goal {
+            name: "Quench Thirst".to_string(),
+            motive: Motive::Thirst,
+            weight: 1.0,
+            conditions: vec![drank(river_liquid, false)],
+        },
+        Goal {
+            name: "Have A Hot Drink".to_string(),
+            motive: Motive::Cold,
+            weight: 0.5,
+            conditions: vec![drank(river_liquid, true)],
+        },
+    ]
+}
+
+/// The planner actions for drinking water: filling a container at a source, perhaps boiling it
+/// over a fire, then drinking it.
+pub fn actions() -> Vec<goap::Action> {
+    let near = |value| Condition::new(ConditionEquality::Is, ConditionType::Near(1), value);
+    let near_source = near(ConditionValue::LiquidSource);
+    let near_heat = near(ConditionValue::Heat);
+    let water = |boiled| carrying(river_liquid, boiled);
+
+    vec![
+        goap::Action::new(
+            "Move To Liquid Source".to_string(),
+            ActionCatagory::Cooking,
+            ActionEvent::MoveTo,
+            1.0,
+            Vec::new(),
+            vec![(near_source.clone(), true)],
+        ),
+        goap::Action::new(
+            "Fill Container".to_string(),
+            ActionCatagory::Cooking,
+            ActionEvent::Fill,
+            fill_time,
+            vec![near_source],
+            vec![(water(false), true)],
+        ),
+        goap::Action::new(
+            "Move To Heat".to_string(),
+            ActionCatagory::Cooking,
+            ActionEvent::MoveTo,
+            1.0,
+            Vec::new(),
+            vec![(near_heat.clone(), true)],
+        ),
+        goap::Action::new(
+            "Boil Water".to_string(),
+            ActionCatagory::Cooking,
+            ActionEvent::Boil,
+            boil_time,
+            vec![water(false), near_heat],
+            vec![(water(true), true)],
+        ),
+        goap::Action::new(
+            "Drink Water".to_string(),
+            ActionCatagory::Cooking,
+            ActionEvent::Drink,
+            drink_time,
+            vec![water(false)],
+            vec![(drank(river_liquid, false), true)],
+        ),
+        goap::Action::new(
+            "Drink Hot Water".to_string(),
+            ActionCatagory::Cooking,
+            ActionEvent::Drink,
+            drink_time,
+            vec![water(true)],
+            vec![
+                (drank(river_liquid, false), true),
+                (drank(river_liquid, true), true),
+            ],
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hibitset::BitSet;
+
+    #[test]
+    fn containers() {
+        let bucket = Container {
+            volume: 0.01,
+            weight: 12.0,
+            can_hold: ContainerCanHold::Liquid | ContainerCanHold::Solid,
+        };
+        let sack = Container {
+            can_hold: ContainerCanHold::Solid,
+            ..bucket.clone()
+        };
+        assert_eq!(
+            room(&sack, Bulk::default(), None, river_liquid, 1000.0),
+            Err(InventoryError::CannotHold(ContainerCanHold::Liquid))
+        );
+        assert_eq!(
+            room(&bucket, Bulk::default(), None, river_liquid, 1000.0),
+            Ok(0.01)
+        );
+        // Heavier liquids fill it by weight before volume
+        assert_eq!(
+            room(&bucket, Bulk::default(), None, "Mercury", 2000.0),
+            Ok(0.006)
+        );
+
+        let mut water = Liquid::new(river_liquid, 0.004, 1000.0);
+        assert!((water.bulk().weight - 4.0).abs() < 1e-4);
+        assert!(
+            (room(&bucket, water.bulk(), Some(&water), river_liquid, 1000.0).unwrap() - 0.006)
+                .abs()
+                < 1e-6
+        );
+        assert_eq!(
+            room(&bucket, water.bulk(), Some(&water), "Oil", 900.0),
+            Err(InventoryError::HoldsOther(river_liquid.to_string()))
+        );
+
+        water.boiled = true;
+        water.add(0.001, false);
+        assert!(!water.boiled);
+        assert_eq!(water.take(0.002), 0.002);
+        assert!((water.take(1.0) - 0.003).abs() < 1e-6);
+        assert!(water.is_empty());
+    }
+
+    #[test]
+    fn drinking() {
+        let mut planner = goap::Planner::default();
+        let mut available = BitSet::new();
+        for action in actions() {
+            available.add(planner.insert(action));
+        }
+        let names = |goal: &Goal| {
+            let goal = planner.condition_set(&goal.conditions).unwrap();
+            planner
+                .plan(&available, &goal, BitSet::new())
+                .unwrap()
+                .iter()
+                .map(|id| planner.get_action_name(*id).unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let goals = goals();
+        assert_eq!(
+            names(&goals[0]),
+            vec!["Move To Liquid Source", "Fill Container", "Drink Water"]
+        );
+        let hot = names(&goals[1]);
+        assert!(hot.contains(&"Fill Container".to_string()));
+        assert!(hot.contains(&"Boil Water".to_string()));
+        assert_eq!(hot.last().unwrap(), "Drink Hot Water");
+    }
+}
@@ -1,9 +1,15 @@
-use crate::mapgen::GeneratorSettings;
+use crate::assets::{biome, Biome};
+use crate::components::TreeKind;
+use crate::mapgen::{GeneratorSettings, HeightmapFormat, Point, Road, Site};
 use crate::tiles::{TileId, Tiles};
 use amethyst::core::math::{Vector3, Vector4};
-use specs_static::Id;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Clone, Copy, Debug, Hash, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize,
+)]
 pub struct Tile {
     pub sprite_number: u32,
     pub sprite_sheet_number: u32,
@@ -12,22 +18,242 @@ pub struct Tile {
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct WorldMap {
-    pub heightmap: Vec<u8>,
+    pub heightmap: Vec<f32>,
     pub moisture: Vec<u8>,
+    pub temperature: Vec<u8>,
     pub seed: String,
     pub settings: GeneratorSettings,
+    pub sites: Vec<Site>,
+    pub roads: Vec<Road>,
+
+    /// Directory chunks are persisted to. Chunks are only generated in memory when unset.
+    #[serde(skip)]
+    pub region_path: Option<PathBuf>,
+    /// Biomes the surface is painted and populated by. Falls back to fixed climate rules when
+    /// unset.
+    #[serde(skip)]
+    pub biomes: Vec<Biome>,
 
     inner: Tiles,
 }
 
-#[derive(Default, serde::Serialize, serde::Deserialize)]
+/// Palette + run-length encoded tiles. Regions are almost entirely air and rock, so this is
+/// usually a handful of runs per z-level.
+#[derive(Clone, Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PaletteTiles {
+    palette: Vec<Tile>,
+    /// `(length, palette index)` pairs.
+    runs: Vec<(u32, u16)>,
+}
+impl PaletteTiles {
+    pub fn encode(tiles: &[Tile]) -> Self {
+        let mut encoded = Self::default();
+
+        for tile in tiles {
+            let index = match encoded.palette.iter().position(|t| t == tile) {
+                Some(index) => index as u16,
+                None => {
+                    encoded.palette.push(*tile);
+                    (encoded.palette.len() - 1) as u16
+                }
+            };
+
+            match encoded.runs.last_mut() {
+                Some((length, last)) if *last == index => *length += 1,
+                _ => encoded.runs.push((1, index)),
+            }
+        }
+
+        encoded
+    }
+
+    pub fn decode(&self) -> Vec<Tile> {
+        let mut tiles = Vec::with_capacity(self.len());
+        for (length, index) in &self.runs {
+            let tile = self.palette[*index as usize];
+            tiles.extend(std::iter::repeat(tile).take(*length as usize));
+        }
+        tiles
+    }
+
+    pub fn get(&self, index: usize) -> Option<Tile> {
+        let mut start = 0;
+        for (length, palette_index) in &self.runs {
+            start += *length as usize;
+            if index < start {
+                return Some(self.palette[*palette_index as usize]);
+            }
+        }
+        None
+    }
+
+    pub fn len(&self) -> usize {
+        self.runs.iter().map(|(length, _)| *length as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+}
+
+/// What grows and lives on a freshly generated chunk, by the biomes of its columns, at the
+/// surface tiles of the chunk they are found on.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Population {
+    pub trees: Vec<(TreeKind, Vector3<u32>)>,
+    pub plants: Vec<(String, Vector3<u32>)>,
+    pub creatures: Vec<(String, Vector3<u32>)>,
+}
+
+/// A single z-level of a region, either dense for editing or compressed while out of view.
+#[derive(Clone, Debug)]
+pub enum RegionLevel {
+    Active(Vec<Tile>),
+    Cold(PaletteTiles),
+}
+impl RegionLevel {
+    pub fn get(&self, index: usize) -> Option<Tile> {
+        match self {
+            RegionLevel::Active(tiles) => tiles.get(index).cloned(),
+            RegionLevel::Cold(tiles) => tiles.get(index),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        match self {
+            RegionLevel::Active(_) => true,
+            RegionLevel::Cold(_) => false,
+        }
+    }
+
+    pub fn freeze(&mut self) {
+        if let RegionLevel::Active(tiles) = self {
+            *self = RegionLevel::Cold(PaletteTiles::encode(tiles));
+        }
+    }
+
+    pub fn thaw(&mut self) -> &mut Vec<Tile> {
+        if let RegionLevel::Cold(tiles) = self {
+            *self = RegionLevel::Active(tiles.decode());
+        }
+        match self {
+            RegionLevel::Active(tiles) => tiles,
+            RegionLevel::Cold(_) => unreachable!(),
+        }
+    }
+
+    fn encode(&self) -> PaletteTiles {
+        match self {
+            RegionLevel::Active(tiles) => PaletteTiles::encode(tiles),
+            RegionLevel::Cold(tiles) => tiles.clone(),
+        }
+    }
+}
+
+/// A chunk of `size * size * z_depth` tiles. Serialized palette encoded, and loaded cold; thaw
+/// the z-levels being played on with `set_active_levels`.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(into = "EncodedRegion", from = "EncodedRegion")]
 pub struct Region {
     pub id: u32,
-    pub tiles: Vec<Tile>,
+    size: usize,
+    levels: Vec<RegionLevel>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct EncodedRegion {
+    id: u32,
+    size: usize,
+    levels: Vec<PaletteTiles>,
+}
+impl From<Region> for EncodedRegion {
+    fn from(region: Region) -> Self {
+        Self {
+            id: region.id,
+            size: region.size,
+            levels: region.levels.iter().map(RegionLevel::encode).collect(),
+        }
+    }
+}
+impl From<EncodedRegion> for Region {
+    fn from(region: EncodedRegion) -> Self {
+        Self {
+            id: region.id,
+            size: region.size,
+            levels: region.levels.into_iter().map(RegionLevel::Cold).collect(),
+        }
+    }
+}
+
+impl Region {
+    pub fn new(id: u32, size: usize) -> Self {
+        Self {
+            id,
+            size,
+            levels: (0..z_depth)
+                .map(|_| RegionLevel::Active(vec![Tile::default(); size * size]))
+                .collect(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn levels(&self) -> &[RegionLevel] {
+        &self.levels
+    }
+
+    pub fn get(&self, x: u32, y: u32, z: u32) -> Option<Tile> {
+        self.levels
+            .get(z as usize)?
+            .get(y as usize * self.size + x as usize)
+    }
+
+    /// Mutable access to a tile, thawing its z-level if it is cold.
+    pub fn get_mut(&mut self, x: u32, y: u32, z: u32) -> Option<&mut Tile> {
+        let index = y as usize * self.size + x as usize;
+        self.levels.get_mut(z as usize)?.thaw().get_mut(index)
+    }
+
+    /// Thaws the z-levels in `active` and compresses every other level.
+    pub fn set_active_levels(&mut self, active: std::ops::Range<u32>) {
+        for (z, level) in self.levels.iter_mut().enumerate() {
+            if active.contains(&(z as u32)) {
+                level.thaw();
+            } else {
+                level.freeze();
+            }
+        }
+    }
 }
 
 const z_depth: usize = 20;
 
+const region_magic: [u8; 4] = *b"SRGN";
+const region_version: u32 = 2;
+const region_compression_level: i32 = 3;
+
+const road_width: f64 = 1.5;
+
+// Surface sprites, as indices into the cp437 sheet
+const road_sprite_number: u32 = 176;
+const water_sprite_number: u32 = 247;
+const sand_sprite_number: u32 = 250;
+const dirt_sprite_number: u32 = 46;
+const grass_sprite_number: u32 = 34;
+const tree_sprite_number: u32 = 5;
+const mountain_sprite_number: u32 = 94;
+const snow_sprite_number: u32 = 42;
+const rock_sprite_number: u32 = 219;
+
+/// Land within this height of sea level is beach.
+const beach_height: f32 = 0.02;
+
+/// Surface temperatures, in degrees celsius, at the coldest and hottest of the temperature map.
+const coldest_surface: f32 = -20.0;
+const hottest_surface: f32 = 40.0;
+
 impl WorldMap {
     pub fn new(settings: &GeneratorSettings) -> Self {
         Self {
@@ -35,6 +261,11 @@ impl WorldMap {
             heightmap: Vec::new(),
             seed: String::new(),
             moisture: Vec::new(),
+            temperature: Vec::new(),
+            sites: Vec::new(),
+            roads: Vec::new(),
+            region_path: None,
+            biomes: Vec::new(),
             inner: Tiles::new(
                 settings.world_pixels as u32,
                 settings.world_pixels as u32,
@@ -43,6 +274,42 @@ impl WorldMap {
         }
     }
 
+    pub fn export_heightmap(
+        &self,
+        path: &std::path::Path,
+        format: HeightmapFormat,
+    ) -> Result<(), failure::Error> {
+        crate::mapgen::save_heightmap(
+            &self.heightmap,
+            self.settings.world_pixels as u32,
+            self.settings.world_pixels as u32,
+            path,
+            format,
+        )
+    }
+
+    pub fn import_heightmap(
+        &mut self,
+        path: &std::path::Path,
+        format: HeightmapFormat,
+    ) -> Result<(), failure::Error> {
+        self.heightmap = crate::mapgen::load_heightmap(
+            path,
+            self.settings.world_pixels as u32,
+            self.settings.world_pixels as u32,
+            format,
+        )?;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn height_at(&self, x: u32, y: u32) -> f32 {
+        self.heightmap
+            .get((y * self.settings.world_pixels as u32 + x) as usize)
+            .cloned()
+            .unwrap_or(0.)
+    }
+
     pub fn coord_to_region_id(&self, coord: Vector3<u32>) -> TileId {
         let region_coord = amethyst::core::math::convert::<Vector3<u32>, Vector3<f32>>(coord)
             / self.settings.region_pixels as f32;
@@ -55,84 +322,395 @@ impl WorldMap {
         self.inner.id_from_vector(absolute)
     }
 
-    pub fn generate_chunk(&self, id: u32) -> Region {
-        use rbf_interp::{DistanceFunction, PtValue, Rbf};
+    /// The chunk grid, in regions, that the world heightmap is divided into.
+    pub fn region_grid(&self) -> Tiles {
+        let regions = (self.settings.world_pixels as usize / self.settings.region_pixels) as u32;
+        Tiles::new(regions, regions, 1)
+    }
+
+    /// The heightmap window `(x, y, x2, y2)` a chunk covers, in heightmap pixels.
+    pub fn region_window(&self, id: u32) -> Vector4<f64> {
+        let regions = self.region_grid().dimensions().x;
+        let size = self.settings.region_pixels as f64;
+        let x = f64::from(id % regions) * size;
+        let y = f64::from(id / regions) * size;
 
+        Vector4::new(x, y, x + size, y + size)
+    }
+
+    /// Bilinearly interpolates the heightmap at a fractional heightmap coordinate.
+    pub fn sample_height(&self, x: f64, y: f64) -> f32 {
+        let max = (self.settings.world_pixels - 1.).max(0.);
+        let (x, y) = (x.max(0.).min(max), y.max(0.).min(max));
+        let (x0, y0) = (x.floor(), y.floor());
+        let (x1, y1) = ((x0 + 1.).min(max), (y0 + 1.).min(max));
+        let (tx, ty) = ((x - x0) as f32, (y - y0) as f32);
+
+        let top = self.height_at(x0 as u32, y0 as u32) * (1. - tx)
+            + self.height_at(x1 as u32, y0 as u32) * tx;
+        let bottom = self.height_at(x0 as u32, y1 as u32) * (1. - tx)
+            + self.height_at(x1 as u32, y1 as u32) * tx;
+
+        top * (1. - ty) + bottom * ty
+    }
+
+    /// Generates the tiles of a chunk. z-levels count up from bedrock, so everything below the
+    /// surface level of a column is filled.
+    pub fn generate_chunk(&self, id: u32) -> Region {
         let _seed = self.region_seed(id);
 
-        let mut region = Region::default();
-        region.tiles.resize(
-            self.settings.region_size * self.settings.region_size * z_depth,
-            Tile::default(),
-        );
+        let size = self.settings.region_size;
+        let mut region = Region::new(id, size);
 
-        // Collect the points in the region
-        let region_tiles = Tiles::new(
-            self.settings.region_size as u32,
-            self.settings.region_size as u32,
-            z_depth as u32,
-        );
-        let world_tiles = Tiles::new(
-            self.settings.world_pixels as u32,
-            self.settings.world_pixels as u32,
-            z_depth as u32,
+        let window = self.region_window(id);
+
+        slog::slog_trace!(
+            slog_scope::logger(),
+            "Generating chunk {} window={:?} heightmap samples={}",
+            id,
+            window,
+            self.heightmap.len()
         );
 
-        // load the heightmap into an image
-        let imgbuf = image::ImageBuffer::<image::Luma<u8>, Vec<u8>>::from_raw(
-            self.settings.world_pixels as u32,
-            self.settings.world_pixels as u32,
-            self.heightmap.clone(),
-        )
-        .unwrap();
-        slog::slog_trace!(slog_scope::logger(), "Dimensions={:?}", imgbuf.dimensions());
-
-        let region_range = Vector4::new(0, 0, 10, 10);
-
-        let mut points = Vec::new();
-        world_tiles.iter_region(region_range, 1).for_each(|id| {
-            let coord = id.vector(region_tiles.dimensions());
-            //slog::slog_trace!(slog_scope::logger(), "Collected coord: {:?}", coord);
-            let height = imgbuf.get_pixel(coord.x as u32, coord.y as u32);
-
-            points.push(PtValue::new(coord.x, coord.y, (height[0] as f32) / 255.));
-        });
-
-        let rbf = Rbf::new(&points, DistanceFunction::Linear, None);
-        region_tiles.iter_all().for_each(|id| {
-            let coord = id.vector(region_tiles.dimensions());
-            let z = rbf.interp_point((coord.x, coord.y));
-            if (coord.z as f32) / z_depth as f32 > z {
-                if let Some(tile) = region.tiles.get_mut(id.id() as usize) {
-                    *tile = Tile {
-                        sprite_number: 1,
-                        sprite_sheet_number: 1,
-                        filled: true,
+        for y in 0..size as u32 {
+            for x in 0..size as u32 {
+                let (world_x, world_y, height, surface) = self.column(&window, x, y);
+                let surface_tile = self.surface_tile(world_x, world_y, height);
+
+                for z in 0..z_depth as u32 {
+                    let tile = if z < surface {
+                        Tile {
+                            sprite_number: rock_sprite_number,
+                            sprite_sheet_number: 1,
+                            filled: true,
+                        }
+                    } else if z == surface {
+                        surface_tile
+                    } else {
+                        Tile::default()
                     };
+
+                    if let Some(t) = region.get_mut(x, y, z) {
+                        *t = tile;
+                    }
                 }
+            }
+        }
+
+        region
+    }
+
+    /// The point of the world under the column `(x, y)` of the chunk covering `window`, its
+    /// height, and the z-level of its surface.
+    fn column(&self, window: &Vector4<f64>, x: u32, y: u32) -> (f64, f64, f32, u32) {
+        let scale = (window.z - window.x) / self.settings.region_size as f64;
+        let world_x = window.x + f64::from(x) * scale;
+        let world_y = window.y + f64::from(y) * scale;
+
+        let height = self.sample_height(world_x, world_y);
+        let surface = (height * (z_depth - 1) as f32).round() as u32;
+        (world_x, world_y, height, surface)
+    }
+
+    /// Replaces the biomes the surface is generated from, ordered by key so picks don't depend on
+    /// the order they were loaded in.
+    pub fn set_biomes(&mut self, biomes: &std::collections::HashMap<String, Biome>) {
+        let mut keys = biomes.keys().collect::<Vec<_>>();
+        keys.sort();
+        self.biomes = keys.into_iter().map(|key| biomes[key].clone()).collect();
+    }
+
+    /// The biome of the climate at a point of the world, if any is loaded for it.
+    pub fn biome_at(&self, x: f64, y: f64) -> Option<&Biome> {
+        let moisture = self.sample_climate(&self.moisture, x, y);
+        let temperature = self.sample_climate(&self.temperature, x, y);
+        biome::biome_for(&self.biomes, temperature, moisture)
+    }
+
+    /// A roll from `0.0` to `1.0` fixed by the seed and a point of the world, `salt` telling the
+    /// rolls of one point apart.
+    fn roll(&self, x: f64, y: f64, salt: u32) -> f32 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        (self.seed.as_str(), x.to_bits(), y.to_bits(), salt).hash(&mut hasher);
+        (hasher.finish() % 1_000_000) as f32 / 1_000_000.
+    }
+
+    /// Whether the surface at a point of the world is under water or paved, where nothing grows
+    /// or is spawned.
+    fn is_barren(&self, x: f64, y: f64, height: f32) -> bool {
+        height <= self.settings.sea_level as f32 || self.road_at(&Point::new(x, y))
+    }
+
+    /// Picks the surface tile of a column from its height, then from the terrain of its biome, or
+    /// from its moisture and temperature with no biome loaded for its climate.
+    fn surface_tile(&self, x: f64, y: f64, height: f32) -> Tile {
+        let sea_level = self.settings.sea_level as f32;
+        let moisture = self.sample_climate(&self.moisture, x, y);
+        let temperature = self.sample_climate(&self.temperature, x, y);
+        let biome_sprite = self
+            .biome_at(x, y)
+            .and_then(|biome| biome.terrain_at(self.roll(x, y, 0)));
+
+        let sprite_number = if height <= sea_level {
+            water_sprite_number
+        } else if self.road_at(&Point::new(x, y)) {
+            road_sprite_number
+        } else if height - sea_level < beach_height {
+            sand_sprite_number
+        } else if height > 0.9 {
+            snow_sprite_number
+        } else if height > 0.75 {
+            mountain_sprite_number
+        } else if let Some(sprite_number) = biome_sprite {
+            sprite_number
+        } else if temperature < 0.2 {
+            snow_sprite_number
+        } else if moisture < 0.3 {
+            if temperature > 0.7 {
+                sand_sprite_number
             } else {
-                if let Some(tile) = region.tiles.get_mut(id.id() as usize) {
-                    *tile = Tile {
-                        sprite_number: 1,
-                        sprite_sheet_number: 1,
-                        filled: false,
-                    };
+                dirt_sprite_number
+            }
+        } else if moisture > 0.7 {
+            tree_sprite_number
+        } else {
+            grass_sprite_number
+        };
+
+        Tile {
+            sprite_number,
+            sprite_sheet_number: 1,
+            filled: false,
+        }
+    }
+
+    /// Rolls each surface column of a chunk against the trees, plants and creatures of its biome,
+    /// for spawning when the chunk is first generated.
+    pub fn populate_chunk(&self, id: u32) -> Population {
+        let mut population = Population::default();
+        let window = self.region_window(id);
+        let size = self.settings.region_size as u32;
+
+        for y in 0..size {
+            for x in 0..size {
+                let (world_x, world_y, height, surface) = self.column(&window, x, y);
+                if self.is_barren(world_x, world_y, height) {
+                    continue;
+                }
+                let biome = match self.biome_at(world_x, world_y) {
+                    Some(biome) => biome,
+                    None => continue,
+                };
+                let coord = Vector3::new(x, y, surface);
+
+                if let Some(kind) = biome::pick(&biome.trees, self.roll(world_x, world_y, 1)) {
+                    population.trees.push((*kind, coord));
+                }
+                if let Some(kind) = biome::pick(&biome.plants, self.roll(world_x, world_y, 2)) {
+                    population.plants.push((kind.clone(), coord));
+                }
+                if let Some(kind) = biome::pick(&biome.creatures, self.roll(world_x, world_y, 3)) {
+                    population.creatures.push((kind.clone(), coord));
                 }
             }
-        });
+        }
+
+        population
+    }
+
+    /// Surface temperature in degrees celsius at a point of the world, for a `TemperatureGrid`.
+    pub fn surface_temperature(&self, x: f64, y: f64) -> f32 {
+        let climate = self.sample_climate(&self.temperature, x, y);
+        coldest_surface + climate * (hottest_surface - coldest_surface)
+    }
+
+    /// Moisture at a point of the world, from `0.0` in deserts to `1.0` in swamps.
+    pub fn surface_moisture(&self, x: f64, y: f64) -> f32 {
+        self.sample_climate(&self.moisture, x, y)
+    }
+
+    /// Samples a climate map as `0.0..=1.0`, treating a missing map as temperate.
+    fn sample_climate(&self, map: &[u8], x: f64, y: f64) -> f32 {
+        let index = y as usize * self.settings.world_pixels as usize + x as usize;
+        map.get(index).map_or(0.5, |v| f32::from(*v) / 255.)
+    }
+
+    pub fn road_at(&self, point: &Point) -> bool {
+        self.roads
+            .iter()
+            .any(|r| r.distance_to(point) <= road_width)
+    }
+
+    /// The on-disk location of a chunk within `directory`.
+    pub fn chunk_path(directory: &Path, id: u32) -> PathBuf {
+        directory.join(format!("{}.region", id))
+    }
+
+    /// Writes a chunk as a versioned header followed by the zstd compressed bincode region.
+    pub fn save_chunk(&self, directory: &Path, region: &Region) -> Result<(), failure::Error> {
+        std::fs::create_dir_all(directory)?;
+
+        let mut file = std::fs::File::create(Self::chunk_path(directory, region.id))?;
+        file.write_all(&region_magic)?;
+        file.write_all(&region_version.to_le_bytes())?;
+        file.write_all(&region.id.to_le_bytes())?;
+
+        let mut encoder = zstd::Encoder::new(file, region_compression_level)?;
+        bincode::serialize_into(&mut encoder, region)?;
+        encoder.finish()?;
 
-        Region::default()
+        Ok(())
     }
 
-    pub fn save_chunk() {
+    /// Reads a chunk previously written by `save_chunk`, returning `None` if it doesn't exist.
+    pub fn load_chunk(&self, directory: &Path, id: u32) -> Result<Option<Region>, failure::Error> {
+        let path = Self::chunk_path(directory, id);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let mut file = std::fs::File::open(&path)?;
+        let mut header = [0; 12];
+        file.read_exact(&mut header)?;
+
+        if header[0..4] != region_magic {
+            return Err(failure::format_err!("{:?} is not a region file", path));
+        }
+        let version = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if version != region_version {
+            return Err(failure::format_err!(
+                "Unsupported region version {} in {:?}, expected {}",
+                version,
+                path,
+                region_version
+            ));
+        }
+        let stored_id = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        if stored_id != id {
+            return Err(failure::format_err!(
+                "Region file {:?} contains region {}",
+                path,
+                stored_id
+            ));
+        }
 
+        let region = bincode::deserialize_from(zstd::Decoder::new(file)?)?;
+        Ok(Some(region))
     }
 
-    pub fn load_chunk() {
+    /// Loads a chunk from `region_path`, generating and writing it back if it hasn't been saved.
+    /// Chunks generated come with what to populate them with; those loaded were populated when
+    /// they were first generated.
+    pub fn ensure_chunk(&self, id: u32) -> Result<(Region, Option<Population>), failure::Error> {
+        let directory = match &self.region_path {
+            Some(directory) => directory,
+            None => return Ok((self.generate_chunk(id), Some(self.populate_chunk(id)))),
+        };
+
+        if let Some(region) = self.load_chunk(directory, id)? {
+            return Ok((region, None));
+        }
+
+        let region = self.generate_chunk(id);
+        self.save_chunk(directory, &region)?;
 
+        Ok((region, Some(self.populate_chunk(id))))
     }
 
     fn region_seed(&self, id: u32) -> Vec<u8> {
         crate::mapgen::seed_from_string(&format!("{}{}", id, self.seed))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_tiles_roundtrip() {
+        let rock = Tile {
+            sprite_number: rock_sprite_number,
+            sprite_sheet_number: 1,
+            filled: true,
+        };
+        let mut tiles = vec![Tile::default(); 100];
+        tiles[10..50].iter_mut().for_each(|t| *t = rock);
+        tiles[75] = rock;
+
+        let encoded = PaletteTiles::encode(&tiles);
+        assert_eq!(encoded.palette.len(), 2);
+        assert_eq!(encoded.runs.len(), 5);
+        assert_eq!(encoded.len(), tiles.len());
+        assert_eq!(encoded.get(75), Some(rock));
+        assert_eq!(encoded.get(100), None);
+        assert_eq!(encoded.decode(), tiles);
+    }
+
+    #[test]
+    fn region_cold_levels() {
+        let mut region = Region::new(0, 4);
+        region.get_mut(1, 2, 3).unwrap().sprite_number = 5;
+
+        region.set_active_levels(0..1);
+        assert!(!region.levels()[3].is_active());
+        assert_eq!(region.get(1, 2, 3).unwrap().sprite_number, 5);
+
+        region.get_mut(1, 2, 3).unwrap().sprite_number = 6;
+        assert!(region.levels()[3].is_active());
+        assert_eq!(region.get(1, 2, 3).unwrap().sprite_number, 6);
+    }
+    #[test]
+    fn biome_population() {
+        let settings = GeneratorSettings {
+            world_pixels: 8.0,
+            region_pixels: 4,
+            region_size: 16,
+            ..GeneratorSettings::default()
+        };
+        let mut map = WorldMap::new(&settings);
+        map.heightmap = vec![0.5; 64];
+
+        // Without biomes the missing climate maps are temperate grassland, with nothing on it
+        assert_eq!(
+            map.surface_tile(1.0, 1.0, 0.5).sprite_number,
+            grass_sprite_number
+        );
+        assert_eq!(map.populate_chunk(0), Population::default());
+
+        let mut biomes = std::collections::HashMap::new();
+        biomes.insert(
+            "moor".to_string(),
+            Biome {
+                name: "Moor".to_string(),
+                temperature: (0.0, 1.0),
+                moisture: (0.0, 1.0),
+                terrain: vec![(dirt_sprite_number, 1.0)],
+                trees: vec![(TreeKind::Birch, 1.0)],
+                plants: vec![("heather".to_string(), 0.5)],
+                creatures: Vec::new(),
+            },
+        );
+        map.set_biomes(&biomes);
+        assert_eq!(map.biome_at(1.0, 1.0).unwrap().name, "Moor");
+        assert_eq!(
+            map.surface_tile(1.0, 1.0, 0.5).sprite_number,
+            dirt_sprite_number
+        );
+        // Heights still win over the biome, as does the sea
+        assert_eq!(
+            map.surface_tile(1.0, 1.0, 0.8).sprite_number,
+            mountain_sprite_number
+        );
+        assert_eq!(
+            map.surface_tile(1.0, 1.0, 0.0).sprite_number,
+            water_sprite_number
+        );
+
+        let population = map.populate_chunk(0);
+        assert_eq!(population.trees.len(), 16 * 16);
+        assert!(population.trees.iter().all(|(_, coord)| coord.z == 10));
+        assert!(!population.plants.is_empty() && population.plants.len() < 16 * 16);
+        assert!(population.creatures.is_empty());
+        assert_eq!(map.populate_chunk(0), population);
+    }
+}
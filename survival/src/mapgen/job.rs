@@ -0,0 +1,205 @@
+use rand::SeedableRng;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crate::map::WorldMap;
+
+use super::{
+    seed_from_string, CellData, CoastGeneratorSettings, Generator, GeneratorSettings,
+    IslandGeneratorSettings, NoiseGeneratorSettings, RiverGeneratorSettings, RoadGeneratorSettings,
+    SiteGeneratorSettings,
+};
+
+/// The steps of generating a world, in the order they are run.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Cells,
+    Island,
+    Coast,
+    Rivers,
+    Heights,
+    Climate,
+    Sites,
+    Roads,
+    Done,
+}
+impl Stage {
+    const all: [Stage; 9] = [
+        Stage::Cells,
+        Stage::Island,
+        Stage::Coast,
+        Stage::Rivers,
+        Stage::Heights,
+        Stage::Climate,
+        Stage::Sites,
+        Stage::Roads,
+        Stage::Done,
+    ];
+
+    /// How far through generating the world the stage starts, from 0 to 1.
+    pub fn fraction(self) -> f32 {
+        self.index() as f32 / (Self::all.len() - 1) as f32
+    }
+
+    /// The localization key of the name of the stage.
+    pub fn key(self) -> &'static str {
+        match self {
+            Stage::Cells => "ui.worldgen.stage.cells",
+            Stage::Island => "ui.worldgen.stage.island",
+            Stage::Coast => "ui.worldgen.stage.coast",
+            Stage::Rivers => "ui.worldgen.stage.rivers",
+            Stage::Heights => "ui.worldgen.stage.heights",
+            Stage::Climate => "ui.worldgen.stage.climate",
+            Stage::Sites => "ui.worldgen.stage.sites",
+            Stage::Roads => "ui.worldgen.stage.roads",
+            Stage::Done => "ui.worldgen.stage.done",
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::all.iter().position(|stage| *stage == self).unwrap()
+    }
+}
+
+/// Generates the heightmap, climate, sites and roads of a world from `seed`, calling `report` as
+/// each stage starts.
+pub fn generate<F>(
+    seed: &str,
+    settings: &GeneratorSettings,
+    noise: &NoiseGeneratorSettings,
+    report: F,
+) -> Result<WorldMap, failure::Error>
+where
+    F: Fn(Stage),
+{
+    let mut rng_seed = [0; 32];
+    rng_seed.copy_from_slice(&seed_from_string(seed));
+    let mut generator = Generator::new(rand::rngs::StdRng::from_seed(rng_seed));
+
+    report(Stage::Cells);
+    let mut cells = generator.gen_voronoi::<CellData>(settings);
+
+    report(Stage::Island);
+    generator.create_island(settings, &IslandGeneratorSettings::default(), &mut cells);
+
+    report(Stage::Coast);
+    generator.shape_coast(settings, &CoastGeneratorSettings::default(), &mut cells);
+
+    report(Stage::Rivers);
+    generator.carve_rivers(settings, &RiverGeneratorSettings::default(), &mut cells);
+
+    report(Stage::Heights);
+    let mut heightmap = generator.generate_height_map_f32(settings, &cells)?;
+    generator.apply_height_detail(settings, noise, &mut heightmap);
+
+    report(Stage::Climate);
+    let moisture = generator.generate_moisture_map(settings, &noise.moisture, &cells)?;
+    let temperature =
+        generator.generate_temperature_map(settings, &noise.temperature, &heightmap)?;
+
+    report(Stage::Sites);
+    let sites = generator.place_sites(settings, &SiteGeneratorSettings::default(), &cells);
+
+    report(Stage::Roads);
+    let roads =
+        generator.generate_roads(settings, &RoadGeneratorSettings::default(), &sites, &cells);
+
+    let mut map = WorldMap::new(settings);
+    map.seed = seed.to_string();
+    map.heightmap = heightmap;
+    map.moisture = moisture;
+    map.temperature = temperature;
+    map.sites = sites;
+    map.roads = roads;
+
+    report(Stage::Done);
+    Ok(map)
+}
+
+/// A world being generated by `generate` on a thread of its own, for the game to keep drawing
+/// while it runs.
+pub struct GenerationJob {
+    stage: Arc<AtomicUsize>,
+    handle: Option<JoinHandle<Result<WorldMap, failure::Error>>>,
+}
+impl GenerationJob {
+    pub fn start(seed: &str, settings: GeneratorSettings, noise: NoiseGeneratorSettings) -> Self {
+        let stage = Arc::new(AtomicUsize::new(Stage::Cells.index()));
+        let seed = seed.to_string();
+
+        let reported = stage.clone();
+        let handle = std::thread::spawn(move || {
+            generate(&seed, &settings, &noise, |stage| {
+                reported.store(stage.index(), Ordering::Relaxed)
+            })
+        });
+
+        Self {
+            stage,
+            handle: Some(handle),
+        }
+    }
+
+    /// The stage the generation is at.
+    pub fn stage(&self) -> Stage {
+        Stage::all[self.stage.load(Ordering::Relaxed)]
+    }
+
+    /// The generated world once the generation is done, or its error. `None` while it is still
+    /// running, and after the world was taken.
+    pub fn take(&mut self) -> Option<Result<WorldMap, failure::Error>> {
+        if self.stage() != Stage::Done && !self.failed() {
+            return None;
+        }
+
+        self.handle.take().map(|handle| {
+            handle
+                .join()
+                .unwrap_or_else(|_| Err(failure::format_err!("World generation panicked")))
+        })
+    }
+
+    /// Whether the generation ended before it was done, through an error or a panic.
+    fn failed(&self) -> bool {
+        // A finished thread has dropped its half of the stage
+        Arc::strong_count(&self.stage) == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages() {
+        assert!(Stage::Cells.fraction().abs() < std::f32::EPSILON);
+        assert!((Stage::Done.fraction() - 1.0).abs() < std::f32::EPSILON);
+        assert!(Stage::all
+            .windows(2)
+            .all(|pair| pair[0].fraction() < pair[1].fraction()));
+    }
+
+    #[test]
+    fn generation_job() {
+        let settings = GeneratorSettings {
+            world_pixels: 64.0,
+            num_points: 50,
+            ..GeneratorSettings::default()
+        };
+
+        let mut job = GenerationJob::start("seed", settings, NoiseGeneratorSettings::default());
+        let map = loop {
+            if let Some(map) = job.take() {
+                break map.unwrap();
+            }
+            std::thread::yield_now();
+        };
+        assert_eq!(job.stage(), Stage::Done);
+        assert!(job.take().is_none());
+
+        assert_eq!(map.seed, "seed");
+        assert_eq!(map.heightmap.len(), 64 * 64);
+        assert_eq!(map.moisture.len(), 64 * 64);
+    }
+}
@@ -0,0 +1,149 @@
+use noise::{NoiseFn, OpenSimplex, Perlin, Seedable, Worley};
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum_macros::EnumString,
+    strum_macros::Display,
+)]
+pub enum NoiseType {
+    OpenSimplex,
+    Perlin,
+    Worley,
+}
+impl Default for NoiseType {
+    fn default() -> Self {
+        NoiseType::OpenSimplex
+    }
+}
+
+/// A single fractal noise source. Each octave doubles (by `lacunarity`) the frequency and
+/// halves (by `persistence`) the amplitude of the previous one.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseLayer {
+    pub noise: NoiseType,
+    pub octaves: usize,
+    pub frequency: f64,
+    pub amplitude: f64,
+    pub persistence: f64,
+    pub lacunarity: f64,
+    /// Mixed into the world seed so layers of the same type don't correlate.
+    pub seed: u32,
+}
+impl Default for NoiseLayer {
+    fn default() -> Self {
+        Self {
+            noise: NoiseType::default(),
+            octaves: 4,
+            frequency: 0.01,
+            amplitude: 1.0,
+            persistence: 0.5,
+            lacunarity: 2.0,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseStack {
+    pub layers: Vec<NoiseLayer>,
+}
+impl NoiseStack {
+    pub fn new(layers: Vec<NoiseLayer>) -> Self {
+        Self { layers }
+    }
+
+    /// Builds the noise functions for this stack. Construct once and reuse it for every sample.
+    pub fn sampler(&self, seed: u32) -> NoiseSampler {
+        NoiseSampler {
+            layers: self
+                .layers
+                .iter()
+                .map(|layer| {
+                    let seed = seed ^ layer.seed;
+                    let source: Box<dyn NoiseFn<[f64; 2]>> = match layer.noise {
+                        NoiseType::OpenSimplex => Box::new(OpenSimplex::new().set_seed(seed)),
+                        NoiseType::Perlin => Box::new(Perlin::new().set_seed(seed)),
+                        NoiseType::Worley => Box::new(Worley::new().set_seed(seed)),
+                    };
+                    (source, layer.clone())
+                })
+                .collect(),
+        }
+    }
+}
+
+pub struct NoiseSampler {
+    layers: Vec<(Box<dyn NoiseFn<[f64; 2]>>, NoiseLayer)>,
+}
+impl NoiseSampler {
+    /// Sums every layer at the given point, roughly in `-1.0..=1.0`.
+    pub fn get(&self, x: f64, y: f64) -> f64 {
+        let mut total = 0.;
+        let mut max = 0.;
+
+        for (source, layer) in &self.layers {
+            let mut frequency = layer.frequency;
+            let mut amplitude = layer.amplitude;
+            for _ in 0..layer.octaves {
+                total += source.get([x * frequency, y * frequency]) * amplitude;
+                max += amplitude;
+
+                frequency *= layer.lacunarity;
+                amplitude *= layer.persistence;
+            }
+        }
+
+        if max > 0. {
+            total / max
+        } else {
+            0.
+        }
+    }
+
+    /// Samples the stack remapped into `0.0..=1.0`.
+    pub fn get_normalized(&self, x: f64, y: f64) -> f64 {
+        (self.get(x, y) / 2. + 0.5).max(0.).min(1.)
+    }
+}
+
+/// The noise stacks used by each stage of world generation, loadable from RON.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseGeneratorSettings {
+    pub moisture: NoiseStack,
+    pub temperature: NoiseStack,
+    /// Perturbs the rasterized heightmap to break up flat voronoi cells.
+    pub detail: NoiseStack,
+    /// Scale applied to the detail stack before it is added to heights.
+    pub detail_strength: f64,
+}
+impl Default for NoiseGeneratorSettings {
+    fn default() -> Self {
+        Self {
+            moisture: NoiseStack::new(vec![NoiseLayer::default()]),
+            temperature: NoiseStack::new(vec![NoiseLayer {
+                noise: NoiseType::Perlin,
+                octaves: 2,
+                frequency: 0.005,
+                seed: 1,
+                ..NoiseLayer::default()
+            }]),
+            detail: NoiseStack::new(vec![NoiseLayer {
+                octaves: 6,
+                frequency: 0.05,
+                seed: 2,
+                ..NoiseLayer::default()
+            }]),
+            detail_strength: 0.05,
+        }
+    }
+}
@@ -4,6 +4,12 @@ use std::collections::{HashMap, HashSet};
 
 use rayon::prelude::*;
 
+pub mod layers;
+pub use layers::{NoiseGeneratorSettings, NoiseLayer, NoiseStack, NoiseType};
+
+pub mod job;
+pub use job::GenerationJob;
+
 pub type Point = amethyst::core::math::Point2<f64>;
 pub type IndexPoint = amethyst::core::math::Point2<OrderedFloat<f64>>;
 
@@ -16,6 +22,15 @@ pub struct GeneratorSettings {
     // Interpolation settings
     pub region_pixels: usize,
     pub region_size: usize,
+
+    // Elevation settings
+    /// Heights at or below this value, after remapping, are ocean.
+    #[serde(default)]
+    pub sea_level: f64,
+    /// Exponent of the hypsometric curve applied to raw heights. Values above 1 flatten
+    /// lowlands and sharpen peaks, values below 1 do the opposite.
+    #[serde(default = "GeneratorSettings::linear_curve")]
+    pub hypsometric_exponent: f64,
 }
 impl Default for GeneratorSettings {
     fn default() -> Self {
@@ -25,14 +40,73 @@ impl Default for GeneratorSettings {
             world_pixels: 500.0,
             region_pixels: 100,
             region_size: 500,
+            sea_level: 0.0,
+            hypsometric_exponent: Self::linear_curve(),
         }
     }
 }
+impl GeneratorSettings {
+    /// The exponent of a hypsometric curve leaving heights as they are.
+    fn linear_curve() -> f64 {
+        1.0
+    }
+
+    /// Applies the hypsometric curve to a raw `0.0..=1.0` height.
+    pub fn remap_height(&self, height: f64) -> f64 {
+        height.max(0.).min(1.).powf(self.hypsometric_exponent)
+    }
+
+    /// A remapped height as a height above the sea, from 0 at the coast and under the sea to 1.
+    pub fn height_above_sea(&self, height: f64) -> f64 {
+        ((height - self.sea_level) / (1. - self.sea_level).max(0.01))
+            .max(0.)
+            .min(1.)
+    }
+}
+
+/// On-disk encodings supported for heightmap export and import.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    strum_macros::EnumString,
+    strum_macros::Display,
+)]
+pub enum HeightmapFormat {
+    /// 8-bit grayscale PNG. Lossy, but easy to view.
+    Png8,
+    /// 16-bit grayscale PNG.
+    Png16,
+    /// Headerless, row-major little-endian `f32` samples.
+    RawF32,
+}
+impl Default for HeightmapFormat {
+    fn default() -> Self {
+        HeightmapFormat::RawF32
+    }
+}
 
 #[derive(Copy, Clone, Default, Debug, serde::Serialize, serde::Deserialize)]
 pub struct CellData {
     height: f64,
     used: bool,
+
+    pub is_coast: bool,
+    pub is_beach: bool,
+    pub is_river: bool,
+}
+impl CellData {
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    pub fn is_land(&self, sea_level: f64) -> bool {
+        self.height > sea_level
+    }
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -64,6 +138,123 @@ impl Default for IslandGeneratorSettings {
     }
 }
 
+#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CoastGeneratorSettings {
+    /// Number of relaxation passes run over the shoreline.
+    pub smoothing: usize,
+    /// Width of the beach band, in cells inland from the coast.
+    pub beach_width: usize,
+}
+impl Default for CoastGeneratorSettings {
+    fn default() -> Self {
+        Self {
+            smoothing: 2,
+            beach_width: 1,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct RiverGeneratorSettings {
+    /// Number of rivers run down from the high ground.
+    pub count: usize,
+    /// Rivers rise from land at least this high.
+    pub source_height: f64,
+}
+impl Default for RiverGeneratorSettings {
+    fn default() -> Self {
+        Self {
+            count: 8,
+            source_height: 0.5,
+        }
+    }
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    strum_macros::EnumString,
+    strum_macros::Display,
+)]
+pub enum SiteKind {
+    Settlement,
+    Ruin,
+    DungeonEntrance,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Site {
+    pub name: String,
+    pub kind: SiteKind,
+    pub position: Point,
+}
+
+#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SiteGeneratorSettings {
+    pub settlements: usize,
+    pub ruins: usize,
+    pub dungeons: usize,
+    /// Minimum distance, in world pixels, between any two sites.
+    pub min_spacing: f64,
+}
+impl Default for SiteGeneratorSettings {
+    fn default() -> Self {
+        Self {
+            settlements: 6,
+            ruins: 4,
+            dungeons: 3,
+            min_spacing: 25.0,
+        }
+    }
+}
+
+/// A road polyline between two sites, indexes into `WorldMap::sites`.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Road {
+    pub from: usize,
+    pub to: usize,
+    pub points: Vec<Point>,
+}
+impl Road {
+    /// Shortest distance from `point` to any segment of the road.
+    pub fn distance_to(&self, point: &Point) -> f64 {
+        self.points
+            .windows(2)
+            .map(|w| distance_to_segment(point, &w[0], &w[1]))
+            .fold(std::f64::MAX, f64::min)
+    }
+}
+
+#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct RoadGeneratorSettings {
+    /// Cost multiplier applied to the height difference between cells.
+    pub slope_cost: f64,
+    /// Flat cost added when a road steps onto a river cell.
+    pub river_cost: f64,
+    /// How many of its nearest neighbors each settlement connects to.
+    pub connections: usize,
+}
+impl Default for RoadGeneratorSettings {
+    fn default() -> Self {
+        Self {
+            slope_cost: 50.0,
+            river_cost: 20.0,
+            connections: 2,
+        }
+    }
+}
+
+const SITE_SYLLABLES: [&str; 16] = [
+    "ash", "bel", "dor", "en", "fal", "gar", "hol", "is", "kel", "mor", "nal", "or", "ril", "sun",
+    "tor", "wen",
+];
+
 impl<R> Generator<R>
 where
     R: Rng + Send + Sync + Clone + ?Sized,
@@ -133,6 +324,392 @@ where
 
             i += 1;
         }
+
+        for cell in cells.values_mut() {
+            cell.data.height = config.remap_height(cell.data.height);
+        }
+    }
+
+    /// Relaxes jagged shorelines, then marks coastal cells and the beach band behind them.
+    pub fn shape_coast(
+        &mut self,
+        config: &GeneratorSettings,
+        settings: &CoastGeneratorSettings,
+        cells: &mut HashMap<IndexPoint, Cell<CellData>>,
+    ) {
+        // Relax against a snapshot each pass so results don't depend on iteration order
+        for _ in 0..settings.smoothing {
+            let snapshot = cells
+                .iter()
+                .map(|(k, c)| (*k, c.data))
+                .collect::<HashMap<_, _>>();
+
+            for cell in cells.values_mut() {
+                let neighbors = cell
+                    .neighbors
+                    .iter()
+                    .filter_map(|n| snapshot.get(n))
+                    .collect::<Vec<_>>();
+                if neighbors.is_empty() {
+                    continue;
+                }
+
+                let land = neighbors
+                    .iter()
+                    .filter(|n| n.is_land(config.sea_level))
+                    .collect::<Vec<_>>();
+                let land_ratio = land.len() as f64 / neighbors.len() as f64;
+
+                if cell.data.is_land(config.sea_level) && land_ratio < 0.34 {
+                    // Lone spit of land, sink it
+                    cell.data.height = config.sea_level;
+                } else if !cell.data.is_land(config.sea_level) && land_ratio > 0.66 {
+                    // Inlet surrounded by land, fill it in
+                    cell.data.height =
+                        land.iter().map(|n| n.height).sum::<f64>() / land.len() as f64;
+                }
+            }
+        }
+
+        // Mark the coast
+        let coast = cells
+            .iter()
+            .filter(|(_, c)| {
+                c.data.is_land(config.sea_level)
+                    && c.neighbors.iter().any(|n| {
+                        cells
+                            .get(n)
+                            .map_or(false, |n| !n.data.is_land(config.sea_level))
+                    })
+            })
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>();
+
+        for cell in cells.values_mut() {
+            cell.data.is_coast = false;
+            cell.data.is_beach = false;
+        }
+
+        // Walk inland from the coast to lay down the beach band
+        let mut frontier = coast;
+        for depth in 0..settings.beach_width.max(1) {
+            let mut next = Vec::new();
+            for key in &frontier {
+                let cell = cells.get_mut(key).unwrap();
+                if cell.data.is_beach {
+                    continue;
+                }
+                cell.data.is_coast = depth == 0;
+                cell.data.is_beach = settings.beach_width > 0;
+
+                next.extend(cell.neighbors.iter().cloned());
+            }
+
+            frontier = next
+                .into_iter()
+                .filter(|n| {
+                    cells.get(n).map_or(false, |c| {
+                        c.data.is_land(config.sea_level) && !c.data.is_beach
+                    })
+                })
+                .collect();
+        }
+    }
+
+    /// Runs rivers down from high ground, each taking the steepest way down from its source until
+    /// it reaches the sea, another river or a hollow it can't flow out of.
+    pub fn carve_rivers(
+        &mut self,
+        config: &GeneratorSettings,
+        settings: &RiverGeneratorSettings,
+        cells: &mut HashMap<IndexPoint, Cell<CellData>>,
+    ) {
+        use rand::seq::SliceRandom;
+        use std::cmp::Ordering;
+
+        for cell in cells.values_mut() {
+            cell.data.is_river = false;
+        }
+
+        // Sorted first, for the sources picked to only depend on the rng
+        let mut sources = cells
+            .values()
+            .filter(|c| c.data.is_land(config.sea_level) && c.data.height >= settings.source_height)
+            .map(|c| c.position)
+            .collect::<Vec<_>>();
+        sources.sort_by_key(|p| (p.x, p.y));
+        sources.shuffle(&mut self.rng);
+
+        for source in sources.into_iter().take(settings.count) {
+            let mut current = source;
+            loop {
+                let cell = cells.get_mut(&current).unwrap();
+                if !cell.data.is_land(config.sea_level) || cell.data.is_river {
+                    break;
+                }
+                cell.data.is_river = true;
+
+                let cell = &cells[&current];
+                let lowest = cell
+                    .neighbors
+                    .iter()
+                    .filter_map(|n| cells.get(n))
+                    .min_by(|a, b| {
+                        a.data
+                            .height
+                            .partial_cmp(&b.data.height)
+                            .unwrap_or(Ordering::Equal)
+                    });
+                match lowest {
+                    Some(next) if next.data.height < cell.data.height => current = next.position,
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    /// Scores land cells and places settlements, ruins and dungeon entrances on them.
+    pub fn place_sites(
+        &mut self,
+        config: &GeneratorSettings,
+        settings: &SiteGeneratorSettings,
+        cells: &HashMap<IndexPoint, Cell<CellData>>,
+    ) -> Vec<Site> {
+        use rand::seq::SliceRandom;
+        use std::cmp::Ordering;
+
+        let mut sites: Vec<Site> = Vec::new();
+
+        let mut land = cells
+            .values()
+            .filter(|c| c.data.is_land(config.sea_level))
+            .map(|c| (c.position, Self::score_site(c, cells), c.data.height))
+            .collect::<Vec<_>>();
+        // Sorted first, for cells scoring the same to keep their order and the sites picked to
+        // only depend on the rng
+        land.sort_by_key(|c| (c.0.x, c.0.y));
+
+        // Settlements take the best scoring cells
+        land.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        let candidates = land.iter().map(|c| c.0).collect::<Vec<_>>();
+        self.pick_sites(
+            SiteKind::Settlement,
+            settings.settlements,
+            &candidates,
+            settings,
+            &mut sites,
+        );
+
+        // Dungeons dig into the high ground
+        land.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal));
+        let candidates = land.iter().map(|c| c.0).collect::<Vec<_>>();
+        self.pick_sites(
+            SiteKind::DungeonEntrance,
+            settings.dungeons,
+            &candidates,
+            settings,
+            &mut sites,
+        );
+
+        // Ruins can be anywhere
+        let mut candidates = land.iter().map(|c| c.0).collect::<Vec<_>>();
+        candidates.shuffle(&mut self.rng);
+        self.pick_sites(
+            SiteKind::Ruin,
+            settings.ruins,
+            &candidates,
+            settings,
+            &mut sites,
+        );
+
+        sites
+    }
+
+    fn pick_sites(
+        &mut self,
+        kind: SiteKind,
+        count: usize,
+        candidates: &[IndexPoint],
+        settings: &SiteGeneratorSettings,
+        sites: &mut Vec<Site>,
+    ) {
+        use amethyst::core::math as na;
+
+        let mut placed = 0;
+        for candidate in candidates {
+            if placed >= count {
+                break;
+            }
+
+            let position = Point::new(candidate.x.into_inner(), candidate.y.into_inner());
+            if sites
+                .iter()
+                .any(|s| na::distance(&s.position, &position) < settings.min_spacing)
+            {
+                continue;
+            }
+
+            sites.push(Site {
+                name: self.site_name(),
+                kind,
+                position,
+            });
+            placed += 1;
+        }
+    }
+
+    fn score_site(cell: &Cell<CellData>, cells: &HashMap<IndexPoint, Cell<CellData>>) -> f64 {
+        let neighbors = cell
+            .neighbors
+            .iter()
+            .filter_map(|n| cells.get(n))
+            .collect::<Vec<_>>();
+        if neighbors.is_empty() {
+            return 0.;
+        }
+
+        // Flat ground is easy to build on
+        let slope = neighbors
+            .iter()
+            .map(|n| (n.data.height - cell.data.height).abs())
+            .sum::<f64>()
+            / neighbors.len() as f64;
+        let mut score = (1. - slope * 10.).max(0.);
+
+        if cell.data.is_coast {
+            score += 0.5;
+        }
+        if cell.data.is_river || neighbors.iter().any(|n| n.data.is_river) {
+            score += 0.75;
+        }
+        // Hills nearby mean stone and ore
+        if neighbors.iter().any(|n| n.data.height > 0.6) {
+            score += 0.25;
+        }
+
+        score
+    }
+
+    fn site_name(&mut self) -> String {
+        let syllables = self.rng.gen_range(2, 4);
+        let mut name = (0..syllables)
+            .map(|_| SITE_SYLLABLES[self.rng.gen_range(0, SITE_SYLLABLES.len())])
+            .collect::<String>();
+        if let Some(first) = name.get_mut(0..1) {
+            first.make_ascii_uppercase();
+        }
+        name
+    }
+
+    /// Connects settlements to their nearest neighbors with least-cost roads over the cell graph.
+    pub fn generate_roads(
+        &self,
+        config: &GeneratorSettings,
+        settings: &RoadGeneratorSettings,
+        sites: &[Site],
+        cells: &HashMap<IndexPoint, Cell<CellData>>,
+    ) -> Vec<Road> {
+        use amethyst::core::math as na;
+        use ordered_float::NotNan;
+        use pathfinding::prelude::astar;
+
+        let nearest_cell = |p: &Point| {
+            cells
+                .keys()
+                .min_by_key(|k| {
+                    let distance = na::distance(p, &Point::new(k.x.into_inner(), k.y.into_inner()));
+                    // Ties go to the same cell whatever order the cells are in
+                    (NotNan::new(distance).unwrap(), k.x, k.y)
+                })
+                .cloned()
+        };
+
+        let settlements = sites
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.kind == SiteKind::Settlement)
+            .collect::<Vec<_>>();
+
+        let mut pairs = HashSet::new();
+        for (i, site) in &settlements {
+            let mut others = settlements
+                .iter()
+                .filter(|(j, _)| j != i)
+                .map(|(j, other)| {
+                    (
+                        *j,
+                        NotNan::new(na::distance(&site.position, &other.position)).unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            others.sort_by_key(|o| o.1);
+
+            for (j, _) in others.iter().take(settings.connections) {
+                pairs.insert((*i.min(j), *i.max(j)));
+            }
+        }
+        // Sorted, for the roads to be laid in the same order for a seed
+        let mut pairs = pairs.into_iter().collect::<Vec<_>>();
+        pairs.sort();
+
+        let mut roads = Vec::new();
+        for (from, to) in pairs {
+            let (start, goal) = match (
+                nearest_cell(&sites[from].position),
+                nearest_cell(&sites[to].position),
+            ) {
+                (Some(start), Some(goal)) => (start, goal),
+                _ => continue,
+            };
+            let goal_point = Point::new(goal.x.into_inner(), goal.y.into_inner());
+
+            let result = astar(
+                &start,
+                |key| {
+                    let cell = &cells[key];
+                    cell.neighbors
+                        .iter()
+                        .filter_map(|n| cells.get(n))
+                        .filter(|n| n.data.is_land(config.sea_level))
+                        .map(|n| {
+                            let mut cost = na::distance(
+                                &Point::new(
+                                    cell.position.x.into_inner(),
+                                    cell.position.y.into_inner(),
+                                ),
+                                &Point::new(n.position.x.into_inner(), n.position.y.into_inner()),
+                            );
+                            cost += (n.data.height - cell.data.height).abs() * settings.slope_cost;
+                            if n.data.is_river {
+                                cost += settings.river_cost;
+                            }
+                            (n.position, NotNan::new(cost).unwrap())
+                        })
+                        .collect::<Vec<_>>()
+                },
+                |key| {
+                    NotNan::new(na::distance(
+                        &Point::new(key.x.into_inner(), key.y.into_inner()),
+                        &goal_point,
+                    ))
+                    .unwrap()
+                },
+                |key| *key == goal,
+            );
+
+            if let Some((path, _)) = result {
+                roads.push(Road {
+                    from,
+                    to,
+                    points: path
+                        .iter()
+                        .map(|p| Point::new(p.x.into_inner(), p.y.into_inner()))
+                        .collect(),
+                });
+            }
+        }
+
+        roads
     }
 
     pub fn gen_voronoi<T: Default>(
@@ -222,32 +799,93 @@ where
     }
 
     pub fn generate_moisture_map(
-        &self,
+        &mut self,
         config: &GeneratorSettings,
+        noise: &NoiseStack,
         _cells: &HashMap<IndexPoint, Cell<CellData>>,
     ) -> Result<Vec<u8>, failure::Error> {
         let mut imgbuf =
             image::ImageBuffer::new(config.world_pixels as u32, config.world_pixels as u32);
 
-        // for now we ignore anything in the map about moisture and just randomly generate it
-        use noise::NoiseFn;
-        let simplex = noise::OpenSimplex::new();
+        // for now we ignore anything in the map about moisture and just generate it from noise
+        let sampler = noise.sampler(self.rng.gen());
 
         for (x, y, pixel) in imgbuf.enumerate_pixels_mut() {
-            let moisture = simplex.get([x as f64, y as f64]);
-            *pixel = image::Luma([(moisture / 2.0 + 0.5) as u8]);
+            let moisture = sampler.get_normalized(f64::from(x), f64::from(y));
+            *pixel = image::Luma([(moisture * 255.) as u8]);
         }
 
         Ok(imgbuf.into_raw())
     }
 
+    /// Temperature falls off towards the poles (top and bottom of the map) and with altitude,
+    /// perturbed by the noise stack.
+    pub fn generate_temperature_map(
+        &mut self,
+        config: &GeneratorSettings,
+        noise: &NoiseStack,
+        heights: &[f32],
+    ) -> Result<Vec<u8>, failure::Error> {
+        let mut imgbuf =
+            image::ImageBuffer::new(config.world_pixels as u32, config.world_pixels as u32);
+
+        let sampler = noise.sampler(self.rng.gen());
+        let width = imgbuf.width();
+
+        for (x, y, pixel) in imgbuf.enumerate_pixels_mut() {
+            let latitude = (f64::from(y) / config.world_pixels * 2. - 1.).abs();
+            let height = f64::from(heights.get((y * width + x) as usize).cloned().unwrap_or(0.));
+            let altitude = (height - config.sea_level).max(0.);
+
+            let temperature = (1. - latitude) * 0.7 - altitude * 0.5
+                + (sampler.get_normalized(f64::from(x), f64::from(y)) - 0.5) * 0.3
+                + 0.3;
+            *pixel = image::Luma([(temperature.max(0.).min(1.) * 255.) as u8]);
+        }
+
+        Ok(imgbuf.into_raw())
+    }
+
+    /// Adds fine detail to a rasterized heightmap so cells aren't perfectly flat.
+    pub fn apply_height_detail(
+        &mut self,
+        config: &GeneratorSettings,
+        noise: &NoiseGeneratorSettings,
+        heights: &mut [f32],
+    ) {
+        let sampler = noise.detail.sampler(self.rng.gen());
+        let width = config.world_pixels as usize;
+
+        for (i, height) in heights.iter_mut().enumerate() {
+            let detail = sampler.get((i % width) as f64, (i / width) as f64);
+            *height = (f64::from(*height) + detail * noise.detail_strength)
+                .max(0.)
+                .min(1.) as f32;
+        }
+    }
+
     pub fn generate_height_map(
         &self,
         config: &GeneratorSettings,
         cells: &HashMap<IndexPoint, Cell<CellData>>,
     ) -> Result<Vec<u8>, failure::Error> {
-        let mut imgbuf =
-            image::ImageBuffer::new(config.world_pixels as u32, config.world_pixels as u32);
+        Ok(self
+            .generate_height_map_f32(config, cells)?
+            .iter()
+            .map(|h| (h * 255.) as u8)
+            .collect())
+    }
+
+    /// Rasterizes the cell heights at full precision, in the range `0.0..=1.0`.
+    pub fn generate_height_map_f32(
+        &self,
+        config: &GeneratorSettings,
+        cells: &HashMap<IndexPoint, Cell<CellData>>,
+    ) -> Result<Vec<f32>, failure::Error> {
+        let mut imgbuf = image::ImageBuffer::<image::Luma<f32>, Vec<f32>>::new(
+            config.world_pixels as u32,
+            config.world_pixels as u32,
+        );
 
         for (_n, (_point, cell)) in cells.iter().enumerate() {
             let mut points = cell
@@ -265,7 +903,7 @@ where
             imageproc::drawing::draw_convex_polygon_mut(
                 &mut imgbuf,
                 &points,
-                image::Luma([(cell.data.height * 255.) as u8]),
+                image::Luma([cell.data.height as f32]),
             );
         }
 
@@ -290,6 +928,30 @@ where
         Ok(())
     }
 
+    /// Writes the heights of `cells` above the sea to `path`, the sea masked out at 0, see
+    /// `GeneratorSettings::height_above_sea`.
+    pub fn export_height_map(
+        &self,
+        config: &GeneratorSettings,
+        path: &std::path::Path,
+        cells: &HashMap<IndexPoint, Cell<CellData>>,
+        format: HeightmapFormat,
+    ) -> Result<(), failure::Error> {
+        let heightmap = self
+            .generate_height_map_f32(config, cells)?
+            .iter()
+            .map(|h| config.height_above_sea(f64::from(*h)) as f32)
+            .collect::<Vec<_>>();
+
+        save_heightmap(
+            &heightmap,
+            config.world_pixels as u32,
+            config.world_pixels as u32,
+            path,
+            format,
+        )
+    }
+
     fn sample_point(&mut self, config: &GeneratorSettings) -> (f64, f64) {
         let x: f64 = self.rng.gen();
         let y: f64 = self.rng.gen();
@@ -297,6 +959,108 @@ where
     }
 }
 
+/// Writes a row-major heightmap of `0.0..=1.0` samples to `path` in the given format.
+pub fn save_heightmap(
+    heights: &[f32],
+    width: u32,
+    height: u32,
+    path: &std::path::Path,
+    format: HeightmapFormat,
+) -> Result<(), failure::Error> {
+    use std::io::Write;
+
+    if heights.len() != (width * height) as usize {
+        return Err(failure::format_err!(
+            "Heightmap has {} samples, expected {}x{}",
+            heights.len(),
+            width,
+            height
+        ));
+    }
+
+    match format {
+        HeightmapFormat::Png8 => {
+            let bytes = heights
+                .iter()
+                .map(|h| (h.max(0.).min(1.) * 255.) as u8)
+                .collect::<Vec<_>>();
+            image::save_buffer(path, &bytes, width, height, image::ColorType::Gray(8))?;
+        }
+        HeightmapFormat::Png16 => {
+            // PNG stores 16-bit samples big-endian
+            let mut bytes = Vec::with_capacity(heights.len() * 2);
+            for h in heights {
+                bytes.extend_from_slice(&((h.max(0.).min(1.) * 65535.) as u16).to_be_bytes());
+            }
+            let file = std::fs::File::create(path)?;
+            image::png::PNGEncoder::new(file).encode(
+                &bytes,
+                width,
+                height,
+                image::ColorType::Gray(16),
+            )?;
+        }
+        HeightmapFormat::RawF32 => {
+            let mut bytes = Vec::with_capacity(heights.len() * 4);
+            for h in heights {
+                bytes.extend_from_slice(&h.to_bits().to_le_bytes());
+            }
+            std::fs::File::create(path)?.write_all(&bytes)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a heightmap written by `save_heightmap`, returning `0.0..=1.0` samples.
+pub fn load_heightmap(
+    path: &std::path::Path,
+    width: u32,
+    height: u32,
+    format: HeightmapFormat,
+) -> Result<Vec<f32>, failure::Error> {
+    use image::ImageDecoder;
+
+    let heights = match format {
+        HeightmapFormat::Png8 => image::open(path)?
+            .to_luma()
+            .into_raw()
+            .iter()
+            .map(|v| f32::from(*v) / 255.)
+            .collect::<Vec<_>>(),
+        HeightmapFormat::Png16 => {
+            let decoder = image::png::PNGDecoder::new(std::fs::File::open(path)?)?;
+            if decoder.colortype() != image::ColorType::Gray(16) {
+                return Err(failure::format_err!(
+                    "{:?} is not a 16-bit grayscale PNG",
+                    path
+                ));
+            }
+            decoder
+                .read_image()?
+                .chunks(2)
+                .map(|b| f32::from(u16::from_be_bytes([b[0], b[1]])) / 65535.)
+                .collect::<Vec<_>>()
+        }
+        HeightmapFormat::RawF32 => std::fs::read(path)?
+            .chunks(4)
+            .map(|b| f32::from_bits(u32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+            .collect::<Vec<_>>(),
+    };
+
+    if heights.len() != (width * height) as usize {
+        return Err(failure::format_err!(
+            "{:?} has {} samples, expected {}x{}",
+            path,
+            heights.len(),
+            width,
+            height
+        ));
+    }
+
+    Ok(heights)
+}
+
 pub fn seed_from_string(seed: &str) -> Vec<u8> {
     use sha2::{Digest, Sha256};
 
@@ -310,6 +1074,132 @@ mod tests {
     use super::*;
     use rand::SeedableRng;
 
+    /// A row of land cells, each the neighbor of the ones beside it, falling away from the first.
+    fn row(count: usize) -> HashMap<IndexPoint, Cell<CellData>> {
+        let position = |i: usize| IndexPoint::new(OrderedFloat(i as f64), OrderedFloat(0.));
+        (0..count)
+            .map(|i| {
+                let neighbors = [i.checked_sub(1), Some(i + 1).filter(|n| *n < count)]
+                    .iter()
+                    .filter_map(|n| n.map(position))
+                    .collect();
+                let cell = Cell {
+                    position: position(i),
+                    polygon: Vec::new(),
+                    neighbors,
+                    data: CellData {
+                        height: 0.9 - i as f64 * 0.1,
+                        ..CellData::default()
+                    },
+                };
+                (position(i), cell)
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn rivers_raise_site_scores() {
+        let config = GeneratorSettings {
+            sea_level: 0.35,
+            ..Default::default()
+        };
+        let mut generator = Generator::new(rand::rngs::StdRng::from_seed([7; 32]));
+        let mut cells = row(8);
+        let score = |cells: &HashMap<_, _>, i: f64| {
+            let key = IndexPoint::new(OrderedFloat(i), OrderedFloat(0.));
+            Generator::<rand::rngs::StdRng>::score_site(&cells[&key], cells)
+        };
+        let dry = score(&cells, 3.);
+
+        let settings = RiverGeneratorSettings {
+            count: 1,
+            source_height: 0.85,
+        };
+        generator.carve_rivers(&config, &settings, &mut cells);
+
+        // The river runs from the one source down the row to the sea
+        let river = (0..8)
+            .map(|i| cells[&IndexPoint::new(OrderedFloat(f64::from(i)), OrderedFloat(0.))].data)
+            .map(|data| data.is_river)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            river,
+            vec![true, true, true, true, true, true, false, false]
+        );
+        assert!(score(&cells, 3.) > dry);
+    }
+
+    #[test]
+    pub fn sites_only_depend_on_seed() {
+        // A flat row, its cells scoring the same, in a map of its own iteration order each time
+        let place = || {
+            let mut cells = row(40);
+            for cell in cells.values_mut() {
+                cell.data.height = 0.5;
+            }
+            let settings = SiteGeneratorSettings {
+                min_spacing: 2.,
+                ..SiteGeneratorSettings::default()
+            };
+            Generator::new(rand::rngs::StdRng::from_seed([7; 32]))
+                .place_sites(&GeneratorSettings::default(), &settings, &cells)
+                .into_iter()
+                .map(|site| site.position)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(place(), place());
+    }
+
+    #[test]
+    pub fn roads_cross_rivers_reluctantly() {
+        let point = |x: f64, y: f64| IndexPoint::new(OrderedFloat(x), OrderedFloat(y));
+        // Straight across the river from (0, 0) to (2, 0), or around it through (1, 1)
+        let links = [
+            ((0., 0.), vec![(1., 0.), (1., 1.)]),
+            ((1., 0.), vec![(0., 0.), (2., 0.)]),
+            ((2., 0.), vec![(1., 0.), (1., 1.)]),
+            ((1., 1.), vec![(0., 0.), (2., 0.)]),
+        ];
+        let cells = links
+            .iter()
+            .map(|((x, y), neighbors)| {
+                let cell = Cell {
+                    position: point(*x, *y),
+                    polygon: Vec::new(),
+                    neighbors: neighbors.iter().map(|(x, y)| point(*x, *y)).collect(),
+                    data: CellData {
+                        height: 0.5,
+                        is_river: (*x, *y) == (1., 0.),
+                        ..CellData::default()
+                    },
+                };
+                (cell.position, cell)
+            })
+            .collect::<HashMap<_, _>>();
+        let sites = [(0., 0.), (2., 0.)]
+            .iter()
+            .map(|(x, y)| Site {
+                name: String::new(),
+                kind: SiteKind::Settlement,
+                position: Point::new(*x, *y),
+            })
+            .collect::<Vec<_>>();
+
+        let generator = Generator::new(rand::rngs::StdRng::from_seed([7; 32]));
+        let road = |river_cost| {
+            let settings = RoadGeneratorSettings {
+                river_cost,
+                connections: 1,
+                ..RoadGeneratorSettings::default()
+            };
+            let roads =
+                generator.generate_roads(&GeneratorSettings::default(), &settings, &sites, &cells);
+            roads[0].points[1]
+        };
+        assert_eq!(road(0.), Point::new(1., 0.));
+        assert_eq!(road(20.), Point::new(1., 1.));
+    }
+
     #[test]
     pub fn rng_sample_test() {
         use rand::SeedableRng;
@@ -354,6 +1244,71 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    pub fn sea_level_settings() {
+        // Settings from before the sea level could be set load as they were generated
+        let settings: GeneratorSettings = ron::de::from_str(
+            "(num_points: 100, num_lloyd: 0, world_pixels: 64.0, region_pixels: 16, region_size: 64)",
+        )
+        .unwrap();
+        assert!(settings.sea_level.abs() < 1e-9);
+        assert!((settings.remap_height(0.25) - 0.25).abs() < 1e-9);
+
+        // Exported heights are above the sea
+        let settings = GeneratorSettings {
+            sea_level: 0.2,
+            ..settings
+        };
+        for (height, above) in &[(0.1, 0.), (0.6, 0.5), (1., 1.)] {
+            assert!((settings.height_above_sea(*height) - above).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    pub fn heightmap_format_roundtrip() {
+        use std::path::Path;
+
+        let heights = (0..16).map(|n| n as f32 / 15.).collect::<Vec<_>>();
+
+        save_heightmap(
+            &heights,
+            4,
+            4,
+            &Path::new("/tmp/test.f32"),
+            HeightmapFormat::RawF32,
+        )
+        .unwrap();
+        let raw =
+            load_heightmap(&Path::new("/tmp/test.f32"), 4, 4, HeightmapFormat::RawF32).unwrap();
+        assert_eq!(heights, raw);
+
+        save_heightmap(
+            &heights,
+            4,
+            4,
+            &Path::new("/tmp/test16.png"),
+            HeightmapFormat::Png16,
+        )
+        .unwrap();
+        let png16 =
+            load_heightmap(&Path::new("/tmp/test16.png"), 4, 4, HeightmapFormat::Png16).unwrap();
+        for (a, b) in heights.iter().zip(png16.iter()) {
+            assert!((a - b).abs() < 1. / 65535.);
+        }
+    }
+}
+
+fn distance_to_segment(p: &Point, a: &Point, b: &Point) -> f64 {
+    use amethyst::core::math as na;
+
+    let ab = b - a;
+    let len = ab.norm_squared();
+    if len == 0. {
+        return na::distance(p, a);
+    }
+
+    let t = ((p - a).dot(&ab) / len).max(0.).min(1.);
+    na::distance(p, &(a + ab * t))
 }
 
 fn convert_point(other: Point) -> IndexPoint {
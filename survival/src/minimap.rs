@@ -0,0 +1,132 @@
+use amethyst::{core::math::Vector3, renderer::Rgba};
+use std::sync::Arc;
+
+use crate::tiles::Tiles;
+
+/// Cells along the longer side of the minimap, each standing for a square of tiles.
+pub const minimap_size: u32 = 96;
+/// Color of cells nothing of has been explored.
+pub const unexplored_color: Rgba = Rgba(0.0, 0.0, 0.0, 1.0);
+/// Color of blips of the player's pawns, and of other creatures in sight.
+pub const pawn_blip_color: Rgba = Rgba(1.0, 1.0, 1.0, 1.0);
+pub const creature_blip_color: Rgba = Rgba(1.0, 0.2, 0.2, 1.0);
+/// Share of its color the lowest terrain keeps, the highest being drawn at full brightness.
+const lowest_shade: f32 = 0.5;
+
+/// The color the minimap shows terrain drawn with `sprite_number` in, by the cp437 sheet.
+pub fn terrain_color(sprite_number: usize) -> Rgba {
+    match sprite_number {
+        // Grass, and its fringe
+        34 | 44 => Rgba(0.3, 0.7, 0.2, 1.0),
+        // Trees
+        5 => Rgba(0.1, 0.4, 0.1, 1.0),
+        // Water, and its shore
+        247 | 126 => Rgba(0.2, 0.4, 0.9, 1.0),
+        // Sand
+        250 => Rgba(0.9, 0.8, 0.5, 1.0),
+        // Dirt
+        46 => Rgba(0.5, 0.35, 0.2, 1.0),
+        // Road
+        176 => Rgba(0.6, 0.6, 0.6, 1.0),
+        // Mountain
+        94 => Rgba(0.5, 0.45, 0.4, 1.0),
+        // Snow
+        42 => Rgba(0.95, 0.95, 1.0, 1.0),
+        // Rock and walls
+        _ => Rgba(0.35, 0.35, 0.35, 1.0),
+    }
+}
+
+/// A downsampled picture of the map as the local player knows it, one color a cell of tiles,
+/// with blips of the creatures on it.
+#[derive(Clone, Debug, Default)]
+pub struct Minimap {
+    /// Tiles along each side of a cell.
+    pub cell: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Colors of the cells, row by row, shared with the window drawing them.
+    pub pixels: Arc<Vec<Rgba>>,
+    /// Cells with creatures in them, and the color of their blip.
+    pub blips: Vec<(u32, u32, Rgba)>,
+}
+impl Minimap {
+    /// A minimap of the map of `tiles`, nothing of it explored.
+    pub fn new(tiles: Tiles) -> Self {
+        let dimensions = tiles.dimensions();
+        let longest = dimensions.x.max(dimensions.y).max(1);
+        let cell = (longest + minimap_size - 1) / minimap_size;
+        let width = (dimensions.x + cell - 1) / cell;
+        let height = (dimensions.y + cell - 1) / cell;
+        Self {
+            cell,
+            width,
+            height,
+            pixels: Arc::new(vec![unexplored_color; (width * height) as usize]),
+            blips: Vec::new(),
+        }
+    }
+
+    /// The cell the tile at `coord` is drawn in.
+    pub fn cell_of(&self, coord: Vector3<u32>) -> (u32, u32) {
+        (coord.x / self.cell, coord.y / self.cell)
+    }
+
+    /// The column of tiles the color of the cell at `(x, y)` is taken from, at its centre.
+    pub fn sample(&self, tiles: Tiles, x: u32, y: u32) -> (u32, u32) {
+        let dimensions = tiles.dimensions();
+        (
+            (x * self.cell + self.cell / 2).min(dimensions.x - 1),
+            (y * self.cell + self.cell / 2).min(dimensions.y - 1),
+        )
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Rgba {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Rgba) {
+        let width = self.width;
+        Arc::make_mut(&mut self.pixels)[(y * width + x) as usize] = color;
+    }
+}
+
+/// The color of terrain drawn with `sprite_number` at height `z` of a map `depth` levels deep,
+/// lower ground being darker.
+pub fn shade(sprite_number: usize, z: u32, depth: u32) -> Rgba {
+    let color = terrain_color(sprite_number);
+    let height = if depth > 1 {
+        z as f32 / (depth - 1) as f32
+    } else {
+        1.0
+    };
+    let shade = lowest_shade + (1.0 - lowest_shade) * height;
+    Rgba(color.0 * shade, color.1 * shade, color.2 * shade, color.3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells() {
+        let tiles = Tiles::new(200, 100, 4);
+        let mut minimap = Minimap::new(tiles);
+        assert_eq!(minimap.cell, 3);
+        assert_eq!((minimap.width, minimap.height), (67, 34));
+        assert_eq!(minimap.cell_of(Vector3::new(199, 99, 2)), (66, 33));
+        // The last cell, short of tiles, samples the edge of the map
+        assert_eq!(minimap.sample(tiles, 66, 33), (199, 99));
+        assert_eq!(minimap.sample(tiles, 1, 1), (4, 4));
+
+        let shared = minimap.pixels.clone();
+        minimap.set_pixel(2, 3, terrain_color(34));
+        assert_eq!(minimap.pixel(2, 3), terrain_color(34));
+        assert_eq!(shared[(3 * minimap.width + 2) as usize], unexplored_color);
+
+        let low = shade(34, 0, 4);
+        let high = shade(34, 3, 4);
+        assert!(low.1 < high.1);
+        assert_eq!(high, terrain_color(34));
+    }
+}
@@ -0,0 +1,316 @@
+use ordered_float::NotNan;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::{distance, PathCache};
+use crate::tiles::{TileId, Tiles};
+
+type Key = (NotNan<f32>, NotNan<f32>);
+
+/// Incremental D* Lite planner for a single agent. The search runs backwards from the goal, so
+/// when tiles change only the part of the search they affect is repaired, and the agent can keep
+/// moving without invalidating what was already computed.
+#[derive(Clone, Debug)]
+pub struct DStarLite {
+    start: TileId,
+    goal: TileId,
+    last: TileId,
+    km: f32,
+    g: HashMap<TileId, f32>,
+    rhs: HashMap<TileId, f32>,
+    open: BinaryHeap<Reverse<(Key, TileId)>>,
+    /// The current key of every tile in `open`; heap entries with any other key are stale.
+    open_keys: HashMap<TileId, Key>,
+}
+impl DStarLite {
+    pub fn new<C>(
+        tiles: Tiles,
+        start: TileId,
+        goal: TileId,
+        transitions: Option<&PathCache>,
+        cost: &C,
+    ) -> Self
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        let mut planner = Self {
+            start,
+            goal,
+            last: start,
+            km: 0.0,
+            g: HashMap::new(),
+            rhs: HashMap::new(),
+            open: BinaryHeap::new(),
+            open_keys: HashMap::new(),
+        };
+        planner.rhs.insert(goal, 0.0);
+        let key = planner.key(tiles, goal);
+        planner.push(goal, key);
+        planner.compute(tiles, transitions, cost);
+
+        planner
+    }
+
+    pub fn start(&self) -> TileId {
+        self.start
+    }
+
+    pub fn goal(&self) -> TileId {
+        self.goal
+    }
+
+    /// Moves the agent to `start`, without replanning.
+    pub fn advance(&mut self, tiles: Tiles, start: TileId) {
+        if start != self.start {
+            self.start = start;
+            self.km += distance(tiles.coord(self.last), tiles.coord(start)) as f32;
+            self.last = start;
+        }
+    }
+
+    /// Repairs the plan after the cost of entering `changed` tiles was changed.
+    pub fn update<C>(
+        &mut self,
+        tiles: Tiles,
+        changed: &[TileId],
+        transitions: Option<&PathCache>,
+        cost: &C,
+    ) where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        for id in changed {
+            // Every edge into a changed tile changed, so its predecessors need new estimates
+            self.update_vertex(tiles, *id, transitions, cost);
+            for (predecessor, _) in Self::predecessors(tiles, *id, transitions, cost) {
+                self.update_vertex(tiles, predecessor, transitions, cost);
+            }
+        }
+        self.compute(tiles, transitions, cost);
+    }
+
+    /// The cost to the goal from the current start, if it is reachable.
+    pub fn cost(&self) -> Option<f32> {
+        Some(self.g(self.start)).filter(|cost| cost.is_finite())
+    }
+
+    /// The current best path from the start to the goal.
+    pub fn path<C>(
+        &self,
+        tiles: Tiles,
+        transitions: Option<&PathCache>,
+        cost: &C,
+    ) -> Option<(Vec<TileId>, f32)>
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        let total = self.cost()?;
+
+        let mut path = vec![self.start];
+        let mut current = self.start;
+        while current != self.goal {
+            let (next, _) = Self::successors(tiles, current, transitions, cost)
+                .into_iter()
+                .map(|(next, step)| (next, step + self.g(next)))
+                .filter(|(next, _)| !path.contains(next))
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .filter(|(_, total)| total.is_finite())?;
+
+            path.push(next);
+            current = next;
+        }
+
+        Some((path, total))
+    }
+
+    fn g(&self, id: TileId) -> f32 {
+        self.g.get(&id).cloned().unwrap_or(std::f32::INFINITY)
+    }
+
+    fn rhs(&self, id: TileId) -> f32 {
+        self.rhs.get(&id).cloned().unwrap_or(std::f32::INFINITY)
+    }
+
+    fn is_consistent(&self, id: TileId) -> bool {
+        let (g, rhs) = (self.g(id), self.rhs(id));
+        g == rhs || (g - rhs).abs() <= std::f32::EPSILON
+    }
+
+    fn key(&self, tiles: Tiles, id: TileId) -> Key {
+        let best = self.g(id).min(self.rhs(id));
+        let heuristic = distance(tiles.coord(self.start), tiles.coord(id)) as f32;
+        (
+            NotNan::new(best + heuristic + self.km).unwrap(),
+            NotNan::new(best).unwrap(),
+        )
+    }
+
+    fn push(&mut self, id: TileId, key: Key) {
+        self.open_keys.insert(id, key);
+        self.open.push(Reverse((key, id)));
+    }
+
+    /// The lowest key in the open list, dropping stale heap entries.
+    fn top(&mut self) -> Option<(Key, TileId)> {
+        while let Some(Reverse((key, id))) = self.open.peek().cloned() {
+            if self.open_keys.get(&id) == Some(&key) {
+                return Some((key, id));
+            }
+            self.open.pop();
+        }
+        None
+    }
+
+    fn successors<C>(
+        tiles: Tiles,
+        id: TileId,
+        transitions: Option<&PathCache>,
+        cost: &C,
+    ) -> Vec<(TileId, f32)>
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        let mut successors = tiles
+            .neighbors8(id)
+            .filter_map(|neighbor| cost(neighbor).map(|c| (neighbor, c)))
+            .collect::<Vec<_>>();
+        if let Some(transitions) = transitions {
+            successors.extend(
+                transitions
+                    .z_neighbors(tiles.coord(id))
+                    .into_iter()
+                    .map(|(coord, speed)| (tiles.id_from_vector(coord), speed)),
+            );
+        }
+        successors
+    }
+
+    fn predecessors<C>(
+        tiles: Tiles,
+        id: TileId,
+        transitions: Option<&PathCache>,
+        cost: &C,
+    ) -> Vec<(TileId, f32)>
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        let enter = cost(id).unwrap_or(std::f32::INFINITY);
+        let mut predecessors = tiles
+            .neighbors8(id)
+            .map(|neighbor| (neighbor, enter))
+            .collect::<Vec<_>>();
+        if let Some(transitions) = transitions {
+            if let Some(speed) = transitions.transition(tiles.coord(id)) {
+                predecessors.extend(
+                    transitions
+                        .z_neighbors(tiles.coord(id))
+                        .into_iter()
+                        .map(|(coord, _)| (tiles.id_from_vector(coord), speed)),
+                );
+            }
+        }
+        predecessors
+    }
+
+    fn update_vertex<C>(
+        &mut self,
+        tiles: Tiles,
+        id: TileId,
+        transitions: Option<&PathCache>,
+        cost: &C,
+    ) where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        if id != self.goal {
+            let rhs = Self::successors(tiles, id, transitions, cost)
+                .into_iter()
+                .map(|(next, step)| step + self.g(next))
+                .fold(std::f32::INFINITY, f32::min);
+            self.rhs.insert(id, rhs);
+        }
+
+        self.open_keys.remove(&id);
+        if !self.is_consistent(id) {
+            let key = self.key(tiles, id);
+            self.push(id, key);
+        }
+    }
+
+    fn compute<C>(&mut self, tiles: Tiles, transitions: Option<&PathCache>, cost: &C)
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        while let Some((old_key, id)) = self.top() {
+            let start = self.start;
+            if old_key >= self.key(tiles, start) && self.is_consistent(start) {
+                break;
+            }
+
+            let new_key = self.key(tiles, id);
+            if old_key < new_key {
+                self.push(id, new_key);
+            } else if self.g(id) > self.rhs(id) {
+                self.open_keys.remove(&id);
+                let rhs = self.rhs(id);
+                self.g.insert(id, rhs);
+                for (predecessor, _) in Self::predecessors(tiles, id, transitions, cost) {
+                    self.update_vertex(tiles, predecessor, transitions, cost);
+                }
+            } else {
+                self.g.insert(id, std::f32::INFINITY);
+                self.update_vertex(tiles, id, transitions, cost);
+                for (predecessor, _) in Self::predecessors(tiles, id, transitions, cost) {
+                    self.update_vertex(tiles, predecessor, transitions, cost);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::astar_tiles;
+    use std::collections::HashSet;
+
+    #[test]
+    fn dstar_repairs_after_changes() {
+        let tiles = Tiles::new(16, 16, 1);
+        let mut walls = (0..12).map(|y| tiles.id(8, y, 0)).collect::<HashSet<_>>();
+        let start = tiles.id(0, 0, 0);
+        let goal = tiles.id(15, 0, 0);
+
+        let mut planner = {
+            let cost = |id| if walls.contains(&id) { None } else { Some(1.0) };
+            DStarLite::new(tiles, start, goal, None, &cost)
+        };
+        {
+            let cost = |id| if walls.contains(&id) { None } else { Some(1.0) };
+            let (path, total) = planner.path(tiles, None, &cost).unwrap();
+            let (_, expected) = astar_tiles(tiles, start, goal, None, None, &cost).unwrap();
+            assert_eq!(total, expected);
+            assert_eq!(path.len() as f32, total + 1.0);
+        }
+
+        // Walk part of the way, then close the gap the path was heading for
+        let moved = tiles.id(4, 4, 0);
+        planner.advance(tiles, moved);
+        let closed = (12..16).map(|y| tiles.id(8, y, 0)).collect::<Vec<_>>();
+        walls.extend(closed.iter().cloned());
+        {
+            let cost = |id| if walls.contains(&id) { None } else { Some(1.0) };
+            planner.update(tiles, &closed, None, &cost);
+            assert_eq!(planner.cost(), None);
+            assert!(planner.path(tiles, None, &cost).is_none());
+        }
+
+        // Open a door lower down the wall
+        let door = tiles.id(8, 2, 0);
+        walls.remove(&door);
+        let cost = |id| if walls.contains(&id) { None } else { Some(1.0) };
+        planner.update(tiles, &[door], None, &cost);
+        let (path, total) = planner.path(tiles, None, &cost).unwrap();
+        let (_, expected) = astar_tiles(tiles, moved, goal, None, None, &cost).unwrap();
+        assert_eq!(total, expected);
+        assert!(path.contains(&door));
+    }
+}
@@ -0,0 +1,262 @@
+//! Small maps drawn as ASCII for pathfinding tests. Levels are separated by blank lines, starting
+//! from z = 0. `#` is a wall, `.` floor, `<` stairs up and `>` stairs down; `S` and `G` mark the
+//! start and goal on the floor.
+
+use super::*;
+
+struct Fixture {
+    tiles: Tiles,
+    walls: HashSet<TileId>,
+    cache: PathCache,
+    start: Option<TileId>,
+    goal: Option<TileId>,
+}
+impl Fixture {
+    fn parse(map: &str) -> Self {
+        let levels = map
+            .trim()
+            .split("\n\n")
+            .map(|level| {
+                level
+                    .lines()
+                    .map(|line| line.trim().chars().collect::<Vec<_>>())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let height = levels[0].len() as u32;
+        let width = levels[0][0].len() as u32;
+        let tiles = Tiles::new(width, height, levels.len() as u32);
+
+        let mut fixture = Self {
+            tiles,
+            walls: HashSet::new(),
+            cache: PathCache::default(),
+            start: None,
+            goal: None,
+        };
+        for (z, level) in levels.iter().enumerate() {
+            for (y, row) in level.iter().enumerate() {
+                for (x, c) in row.iter().enumerate() {
+                    let coord = Vector3::new(x as u32, y as u32, z as u32);
+                    let id = tiles.id_from_vector(coord);
+                    match c {
+                        '#' => {
+                            fixture.walls.insert(id);
+                        }
+                        '<' | '>' => fixture.cache.insert_transition(coord, 1.0),
+                        'S' => fixture.start = Some(id),
+                        'G' => fixture.goal = Some(id),
+                        '.' => (),
+                        _ => panic!("Unknown fixture tile '{}'", c),
+                    }
+                }
+            }
+        }
+
+        fixture
+    }
+
+    fn cost(&self, id: TileId) -> Option<f32> {
+        if self.walls.contains(&id) {
+            None
+        } else {
+            Some(1.0)
+        }
+    }
+
+    fn shortest(&self) -> Option<f32> {
+        astar_tiles(
+            self.tiles,
+            self.start.unwrap(),
+            self.goal.unwrap(),
+            None,
+            Some(&self.cache),
+            &|id| self.cost(id),
+        )
+        .map(|(_, cost)| cost)
+    }
+}
+
+#[test]
+fn open_room() {
+    let fixture = Fixture::parse(
+        "
+        S.....
+        ......
+        .....G
+        ",
+    );
+    assert_eq!(fixture.shortest(), Some(5.));
+}
+
+#[test]
+fn detour_around_wall() {
+    let fixture = Fixture::parse(
+        "
+        ......
+        .####.
+        .#S.#.
+        .#..#.
+        .#G.#.
+        .##.#.
+        ",
+    );
+    assert_eq!(fixture.shortest(), Some(2.));
+
+    let fixture = Fixture::parse(
+        "
+        S.#...
+        ..#.#.
+        ..#.#G
+        ....#.
+        ",
+    );
+    assert_eq!(fixture.shortest(), Some(8.));
+}
+
+#[test]
+fn unreachable() {
+    let fixture = Fixture::parse(
+        "
+        S.#...
+        ..#...
+        ###..G
+        ",
+    );
+    assert_eq!(fixture.shortest(), None);
+
+    // Stairs which lead nowhere don't help
+    let fixture = Fixture::parse(
+        "
+        S<#.G
+
+        ..#..
+        ",
+    );
+    assert_eq!(fixture.shortest(), None);
+}
+
+#[test]
+fn z_transitions() {
+    let fixture = Fixture::parse(
+        "
+        S..#.G
+        ..<#..
+
+        ......
+        ..>...
+        ",
+    );
+
+    // Crossing over the wall on the level above would need a second staircase back down
+    assert_eq!(fixture.shortest(), None);
+
+    let fixture = Fixture::parse(
+        "
+        S..#..
+        ..<#<G
+
+        ......
+        ..>.>.
+        ",
+    );
+    let (path, cost) = astar_tiles(
+        fixture.tiles,
+        fixture.start.unwrap(),
+        fixture.goal.unwrap(),
+        None,
+        Some(&fixture.cache),
+        &|id| fixture.cost(id),
+    )
+    .unwrap();
+    assert_eq!(cost, 2. + 1. + 2. + 1. + 1.);
+    assert!(path.iter().any(|id| fixture.tiles.coord(*id).z == 1));
+
+    // Without the stairs the search stays on its level
+    assert!(astar_tiles(
+        fixture.tiles,
+        fixture.start.unwrap(),
+        fixture.goal.unwrap(),
+        None,
+        None,
+        &|id| fixture.cost(id),
+    )
+    .is_none());
+}
+
+#[test]
+fn nearest_of_many() {
+    let fixture = Fixture::parse(
+        "
+        S.#G
+        ..#.
+        ....
+        G...
+        ",
+    );
+    let goals = fixture
+        .tiles
+        .iter_all()
+        .filter(|id| {
+            let coord = fixture.tiles.coord(*id);
+            (coord.x, coord.y) == (3, 0) || (coord.x, coord.y) == (0, 3)
+        })
+        .collect::<Vec<_>>();
+
+    let (path, cost) = astar_to_any(fixture.tiles, fixture.start.unwrap(), &goals, None, &|id| {
+        fixture.cost(id)
+    })
+    .unwrap();
+    assert_eq!(cost, 3.);
+    assert_eq!(
+        fixture.tiles.coord(*path.last().unwrap()),
+        Vector3::new(0, 3, 0)
+    );
+}
+
+/// Every search agrees on the cost between every pair of floor tiles of a maze.
+#[test]
+fn algorithms_agree() {
+    let fixture = Fixture::parse(
+        "
+        ..#.....#.
+        ..#.###.#.
+        ....#...#.
+        ###.#.###.
+        ....#.....
+        .####.###.
+        ......#...
+        ",
+    );
+    let tiles = fixture.tiles;
+    let cost = |id| fixture.cost(id);
+    let mut graph = HierarchicalGraph::new(tiles, 4);
+    graph.build(&cost);
+
+    let floor = tiles
+        .iter_all()
+        .filter(|id| !fixture.walls.contains(id))
+        .collect::<Vec<_>>();
+    for goal in floor.iter().step_by(3) {
+        let field = FlowField::build(tiles, &[*goal], None, 1000., &cost);
+        for start in floor.iter().step_by(2) {
+            let expected = astar_tiles(tiles, *start, *goal, None, None, &cost).map(|(_, c)| c);
+
+            assert_eq!(
+                jps::jps_tiles(tiles, *start, *goal, &cost).map(|(_, c)| c),
+                expected
+            );
+            assert_eq!(field.cost_to_goal(*start), expected);
+            assert_eq!(
+                DStarLite::new(tiles, *start, *goal, None, &cost).cost(),
+                expected
+            );
+            // The hierarchical search may be slightly longer, but never finds paths which
+            // don't exist
+            assert_eq!(
+                graph.find_path(*start, *goal, &cost).is_some(),
+                expected.is_some()
+            );
+        }
+    }
+}
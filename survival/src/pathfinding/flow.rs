@@ -0,0 +1,250 @@
+use amethyst::core::math::Vector3;
+use ordered_float::NotNan;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::{MovementProfile, PathCache};
+use crate::tiles::{TileId, Tiles};
+
+/// The destination a flow field leads to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FlowGoal {
+    Tile(TileId),
+    /// Any tile in the inclusive box, such as a stockpile.
+    Area {
+        min: Vector3<u32>,
+        max: Vector3<u32>,
+    },
+}
+impl FlowGoal {
+    fn tiles(self, tiles: Tiles) -> Vec<TileId> {
+        match self {
+            FlowGoal::Tile(id) => vec![id],
+            FlowGoal::Area { min, max } => tiles.iter_volume(min, max).collect(),
+        }
+    }
+}
+
+/// The next step towards a goal from every tile within reach of it, shared by every entity
+/// heading there.
+#[derive(Clone, Debug, Default)]
+pub struct FlowField {
+    flow: HashMap<TileId, (TileId, f32)>,
+}
+impl FlowField {
+    /// Builds the field with a reverse Dijkstra out from the goal, stopping at `max_cost`.
+    pub fn build<C>(
+        tiles: Tiles,
+        goals: &[TileId],
+        transitions: Option<&PathCache>,
+        max_cost: f32,
+        cost: &C,
+    ) -> Self
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        let mut flow = HashMap::new();
+        let mut open = BinaryHeap::new();
+        for goal in goals {
+            if cost(*goal).is_some() {
+                flow.insert(*goal, (*goal, 0.0));
+                open.push(Reverse((NotNan::new(0.0).unwrap(), *goal)));
+            }
+        }
+
+        while let Some(Reverse((distance, id))) = open.pop() {
+            let distance = distance.into_inner();
+            if flow.get(&id).map_or(false, |(_, best)| *best < distance) {
+                continue;
+            }
+
+            // Stepping from a neighbor onto this tile costs entering this tile
+            let mut previous = Vec::new();
+            if let Some(enter) = cost(id) {
+                previous.extend(tiles.neighbors8(id).map(|neighbor| (neighbor, enter)));
+            }
+            if let Some(transitions) = transitions {
+                if let Some(speed) = transitions.transition(tiles.coord(id)) {
+                    previous.extend(
+                        transitions
+                            .z_neighbors(tiles.coord(id))
+                            .into_iter()
+                            .map(|(coord, _)| (tiles.id_from_vector(coord), speed)),
+                    );
+                }
+            }
+
+            for (neighbor, step) in previous {
+                let total = distance + step;
+                if total > max_cost || cost(neighbor).is_none() {
+                    continue;
+                }
+                if flow.get(&neighbor).map_or(true, |(_, best)| total < *best) {
+                    flow.insert(neighbor, (id, total));
+                    open.push(Reverse((NotNan::new(total).unwrap(), neighbor)));
+                }
+            }
+        }
+
+        Self { flow }
+    }
+
+    /// The tile to move to from `id`, or `None` if the goal is out of reach. At the goal, this
+    /// is the goal itself.
+    pub fn next_step(&self, id: TileId) -> Option<TileId> {
+        self.flow.get(&id).map(|(next, _)| *next)
+    }
+
+    pub fn cost_to_goal(&self, id: TileId) -> Option<f32> {
+        self.flow.get(&id).map(|(_, cost)| *cost)
+    }
+
+    pub fn contains(&self, id: TileId) -> bool {
+        self.flow.contains_key(&id)
+    }
+
+    /// Follows the field from `start` to the goal, returning every tile on the way.
+    pub fn path_from(&self, start: TileId) -> Option<(Vec<TileId>, f32)> {
+        let total = self.cost_to_goal(start)?;
+
+        let mut path = vec![start];
+        let mut current = start;
+        loop {
+            let next = self.next_step(current)?;
+            if next == current {
+                return Some((path, total));
+            }
+            path.push(next);
+            current = next;
+        }
+    }
+}
+
+/// Cache of flow fields, evicting the least recently used past `capacity` and any field a tile
+/// change might affect.
+#[derive(Clone, Debug)]
+pub struct FlowFields {
+    pub capacity: usize,
+    /// Fields stop expanding past this cost; further entities have to path on their own.
+    pub max_cost: f32,
+    fields: HashMap<(FlowGoal, MovementProfile), (FlowField, u64)>,
+    clock: u64,
+}
+impl Default for FlowFields {
+    fn default() -> Self {
+        Self {
+            capacity: 16,
+            max_cost: 512.0,
+            fields: HashMap::new(),
+            clock: 0,
+        }
+    }
+}
+impl FlowFields {
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn get_or_build<C>(
+        &mut self,
+        tiles: Tiles,
+        goal: FlowGoal,
+        profile: MovementProfile,
+        transitions: Option<&PathCache>,
+        cost: &C,
+    ) -> &FlowField
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        self.clock += 1;
+        let clock = self.clock;
+        let key = (goal, profile);
+
+        if !self.fields.contains_key(&key) {
+            if self.fields.len() >= self.capacity {
+                self.evict_oldest();
+            }
+            let field =
+                FlowField::build(tiles, &goal.tiles(tiles), transitions, self.max_cost, cost);
+            self.fields.insert(key, (field, clock));
+        }
+
+        let entry = self.fields.get_mut(&key).unwrap();
+        entry.1 = clock;
+        &entry.0
+    }
+
+    /// Drops every field which reaches, or borders, a changed tile.
+    pub fn invalidate(&mut self, tiles: Tiles, changed: TileId) {
+        self.fields.retain(|_, (field, _)| {
+            !field.contains(changed) && !tiles.neighbors8(changed).any(|id| field.contains(id))
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.fields.clear();
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .fields
+            .iter()
+            .min_by_key(|(_, (_, last_used))| *last_used)
+            .map(|(key, _)| *key);
+        if let Some(key) = oldest {
+            self.fields.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::astar_tiles;
+    use std::collections::HashSet;
+
+    #[test]
+    fn flow_matches_astar() {
+        let tiles = Tiles::new(16, 16, 1);
+        let walls = (2..16).map(|x| tiles.id(x, 8, 0)).collect::<HashSet<_>>();
+        let cost = |id| if walls.contains(&id) { None } else { Some(1.0) };
+
+        let goal = tiles.id(15, 15, 0);
+        let field = FlowField::build(tiles, &[goal], None, 1000.0, &cost);
+        for start in tiles.iter_all().filter(|id| !walls.contains(id)) {
+            let (path, total) = field.path_from(start).unwrap();
+            let (_, expected) = astar_tiles(tiles, start, goal, None, None, &cost).unwrap();
+
+            assert_eq!(total, expected);
+            assert_eq!(path.len() as f32, total + 1.0);
+            assert_eq!(path.last(), Some(&goal));
+        }
+        assert_eq!(field.next_step(tiles.id(2, 8, 0)), None);
+    }
+
+    #[test]
+    fn flow_fields_lru() {
+        let tiles = Tiles::new(8, 8, 1);
+        let profile = MovementProfile::default();
+        let mut fields = FlowFields {
+            capacity: 2,
+            ..FlowFields::default()
+        };
+        let goal = |x| FlowGoal::Tile(tiles.id(x, 0, 0));
+
+        fields.get_or_build(tiles, goal(0), profile, None, &|_| Some(1.0));
+        fields.get_or_build(tiles, goal(1), profile, None, &|_| Some(1.0));
+        fields.get_or_build(tiles, goal(0), profile, None, &|_| Some(1.0));
+        fields.get_or_build(tiles, goal(2), profile, None, &|_| Some(1.0));
+        assert_eq!(fields.len(), 2);
+        assert!(fields.fields.contains_key(&(goal(0), profile)));
+        assert!(!fields.fields.contains_key(&(goal(1), profile)));
+
+        fields.invalidate(tiles, tiles.id(4, 4, 0));
+        assert!(fields.is_empty());
+    }
+}
@@ -0,0 +1,484 @@
+use amethyst::core::math::Vector3;
+use std::collections::{HashMap, HashSet};
+
+use super::astar_tiles;
+use crate::tiles::{TileId, Tiles};
+
+/// Entrances wider than this get a portal at each end instead of one in the middle.
+const max_single_portal_width: u32 = 6;
+
+/// A hierarchical (HPA*) abstraction of the tile grid. Each z-level is divided into square
+/// clusters, connected through portals where their borders are passable. Paths are planned over
+/// the portal graph and refined into tiles one cluster at a time.
+#[derive(Clone, Debug)]
+pub struct HierarchicalGraph {
+    tiles: Tiles,
+    cluster_size: u32,
+    clusters: Vector3<u32>,
+
+    /// Portal pairs on the border between two adjacent clusters, keyed by `(lower, higher)`
+    /// cluster index, with the cost of crossing from one to the other.
+    borders: HashMap<(usize, usize), Vec<(TileId, TileId, f32)>>,
+    /// Portal to portal costs within each cluster.
+    intra: Vec<HashMap<TileId, Vec<(TileId, f32)>>>,
+    dirty: HashSet<usize>,
+    built: bool,
+}
+impl HierarchicalGraph {
+    pub fn new(tiles: Tiles, cluster_size: u32) -> Self {
+        let dimensions = tiles.dimensions();
+        let clusters = Vector3::new(
+            (dimensions.x + cluster_size - 1) / cluster_size,
+            (dimensions.y + cluster_size - 1) / cluster_size,
+            dimensions.z,
+        );
+
+        Self {
+            tiles,
+            cluster_size,
+            clusters,
+            borders: HashMap::new(),
+            intra: vec![HashMap::new(); (clusters.x * clusters.y * clusters.z) as usize],
+            dirty: HashSet::new(),
+            built: false,
+        }
+    }
+
+    pub fn is_built(&self) -> bool {
+        self.built
+    }
+
+    pub fn tiles(&self) -> Tiles {
+        self.tiles
+    }
+
+    /// Computes every border and cluster from scratch.
+    pub fn build<C>(&mut self, cost: &C)
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        self.borders.clear();
+        self.dirty.clear();
+
+        let count = self.intra.len();
+        for cluster in 0..count {
+            self.build_borders(cluster, cost);
+        }
+        for cluster in 0..count {
+            self.build_intra(cluster, cost);
+        }
+
+        self.built = true;
+    }
+
+    /// Marks the cluster containing `id` as needing a rebuild.
+    pub fn invalidate(&mut self, id: TileId) {
+        let cluster = self.cluster_of(id);
+        self.dirty.insert(cluster);
+    }
+
+    /// Rebuilds the borders of every invalidated cluster, and the portal costs of those clusters
+    /// and their neighbors.
+    pub fn rebuild_dirty<C>(&mut self, cost: &C)
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        if self.dirty.is_empty() {
+            return;
+        }
+
+        let dirty = self.dirty.drain().collect::<Vec<_>>();
+        let mut affected = HashSet::new();
+        for cluster in dirty {
+            self.build_borders(cluster, cost);
+
+            affected.insert(cluster);
+            affected.extend(self.adjacent_clusters(cluster));
+        }
+
+        for cluster in affected {
+            self.build_intra(cluster, cost);
+        }
+    }
+
+    /// Finds a path from `start` to `goal`, which must be on the same z-level.
+    pub fn find_path<C>(&self, start: TileId, goal: TileId, cost: &C) -> Option<(Vec<TileId>, f32)>
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        use ordered_float::NotNan;
+        use pathfinding::prelude::*;
+
+        let start_cluster = self.cluster_of(start);
+        let goal_cluster = self.cluster_of(goal);
+
+        if start_cluster == goal_cluster {
+            let local = astar_tiles(
+                self.tiles,
+                start,
+                goal,
+                Some(self.cluster_bounds(start_cluster)),
+                None,
+                cost,
+            );
+            if local.is_some() {
+                return local;
+            }
+        }
+
+        // Temporarily connect the start and goal to the portals of their clusters
+        let start_links = self.connect(start, start_cluster, cost);
+        let goal_links = self
+            .connect(goal, goal_cluster, cost)
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        let goal_coord = self.tiles.coord(goal);
+        let (abstract_path, _) = astar(
+            &start,
+            |node| {
+                let mut successors = self.successors(*node);
+                if *node == start {
+                    successors.extend(start_links.iter().cloned());
+                }
+                if let Some(cost) = goal_links.get(node) {
+                    successors.push((goal, *cost));
+                }
+
+                successors
+                    .into_iter()
+                    .map(|(node, cost)| (node, NotNan::new(cost).unwrap()))
+                    .collect::<Vec<_>>()
+            },
+            |node| {
+                let coord = self.tiles.coord(*node);
+                NotNan::new(
+                    absdiff(coord.x, goal_coord.x).max(absdiff(coord.y, goal_coord.y)) as f32,
+                )
+                .unwrap()
+            },
+            |node| *node == goal,
+        )?;
+
+        self.refine(&abstract_path, cost)
+    }
+
+    /// Expands an abstract path of portals into tiles.
+    fn refine<C>(&self, abstract_path: &[TileId], cost: &C) -> Option<(Vec<TileId>, f32)>
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        let mut path = vec![abstract_path[0]];
+        let mut total = 0.;
+
+        for step in abstract_path.windows(2) {
+            let (from, to) = (step[0], step[1]);
+            let cluster = self.cluster_of(from);
+
+            if cluster == self.cluster_of(to) {
+                let (segment, segment_cost) = astar_tiles(
+                    self.tiles,
+                    from,
+                    to,
+                    Some(self.cluster_bounds(cluster)),
+                    None,
+                    cost,
+                )?;
+                path.extend(segment.into_iter().skip(1));
+                total += segment_cost;
+            } else {
+                // Crossing a border between adjacent portals
+                path.push(to);
+                total += cost(to)?;
+            }
+        }
+
+        Some((path, total))
+    }
+
+    /// Costs from `id` to every portal of `cluster` reachable from it.
+    fn connect<C>(&self, id: TileId, cluster: usize, cost: &C) -> Vec<(TileId, f32)>
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        let bounds = self.cluster_bounds(cluster);
+
+        self.portals(cluster)
+            .into_iter()
+            .filter_map(|portal| {
+                astar_tiles(self.tiles, id, portal, Some(bounds), None, cost)
+                    .map(|(_, portal_cost)| (portal, portal_cost))
+            })
+            .collect()
+    }
+
+    fn successors(&self, node: TileId) -> Vec<(TileId, f32)> {
+        let cluster = self.cluster_of(node);
+
+        let mut successors = self.intra[cluster].get(&node).cloned().unwrap_or_default();
+
+        for other in self.adjacent_clusters(cluster) {
+            let key = (cluster.min(other), cluster.max(other));
+            for (a, b, crossing) in self.borders.get(&key).into_iter().flatten() {
+                if *a == node {
+                    successors.push((*b, *crossing));
+                } else if *b == node {
+                    successors.push((*a, *crossing));
+                }
+            }
+        }
+
+        successors
+    }
+
+    /// Every portal tile on the inside of `cluster`.
+    fn portals(&self, cluster: usize) -> Vec<TileId> {
+        let mut portals = Vec::new();
+
+        for other in self.adjacent_clusters(cluster) {
+            let key = (cluster.min(other), cluster.max(other));
+            for (a, b, _) in self.borders.get(&key).into_iter().flatten() {
+                let portal = if self.cluster_of(*a) == cluster {
+                    *a
+                } else {
+                    *b
+                };
+                if !portals.contains(&portal) {
+                    portals.push(portal);
+                }
+            }
+        }
+
+        portals
+    }
+
+    fn build_intra<C>(&mut self, cluster: usize, cost: &C)
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        let portals = self.portals(cluster);
+        let bounds = self.cluster_bounds(cluster);
+
+        let mut edges: HashMap<TileId, Vec<(TileId, f32)>> = HashMap::new();
+        for (i, a) in portals.iter().enumerate() {
+            for b in &portals[i + 1..] {
+                if let Some((_, path_cost)) =
+                    astar_tiles(self.tiles, *a, *b, Some(bounds), None, cost)
+                {
+                    edges.entry(*a).or_default().push((*b, path_cost));
+                    edges.entry(*b).or_default().push((*a, path_cost));
+                }
+            }
+        }
+
+        self.intra[cluster] = edges;
+    }
+
+    /// Finds the entrances on the east and south borders of `cluster`, and the west and north
+    /// borders shared with its neighbors.
+    fn build_borders<C>(&mut self, cluster: usize, cost: &C)
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        for other in self.adjacent_clusters(cluster) {
+            let key = (cluster.min(other), cluster.max(other));
+            let entrances = self.find_entrances(key.0, key.1, cost);
+            self.borders.insert(key, entrances);
+        }
+    }
+
+    /// Portals across the border between `lower` and the cluster east or south of it.
+    fn find_entrances<C>(&self, lower: usize, higher: usize, cost: &C) -> Vec<(TileId, TileId, f32)>
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        let (min, max) = self.cluster_bounds(lower);
+        let east = self.cluster_coord(higher).x > self.cluster_coord(lower).x;
+
+        // Pairs of tiles facing each other across the border
+        let pairs = if east {
+            (min.y..=max.y)
+                .map(|y| {
+                    (
+                        self.tiles.id(max.x, y, min.z),
+                        self.tiles.id(max.x + 1, y, min.z),
+                    )
+                })
+                .collect::<Vec<_>>()
+        } else {
+            (min.x..=max.x)
+                .map(|x| {
+                    (
+                        self.tiles.id(x, max.y, min.z),
+                        self.tiles.id(x, max.y + 1, min.z),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Each run of passable pairs is one entrance
+        let mut entrances = Vec::new();
+        let mut run = Vec::new();
+        for (a, b) in pairs {
+            match (cost(a), cost(b)) {
+                (Some(a_cost), Some(b_cost)) => run.push((a, b, a_cost.max(b_cost))),
+                _ => Self::close_entrance(&mut run, &mut entrances),
+            }
+        }
+        Self::close_entrance(&mut run, &mut entrances);
+
+        entrances
+    }
+
+    fn close_entrance(
+        run: &mut Vec<(TileId, TileId, f32)>,
+        entrances: &mut Vec<(TileId, TileId, f32)>,
+    ) {
+        if run.is_empty() {
+            return;
+        }
+
+        if run.len() as u32 > max_single_portal_width {
+            entrances.push(run[0]);
+            entrances.push(run[run.len() - 1]);
+        } else {
+            entrances.push(run[run.len() / 2]);
+        }
+        run.clear();
+    }
+
+    fn cluster_of(&self, id: TileId) -> usize {
+        let coord = self.tiles.coord(id);
+        self.cluster_index(Vector3::new(
+            coord.x / self.cluster_size,
+            coord.y / self.cluster_size,
+            coord.z,
+        ))
+    }
+
+    fn cluster_index(&self, coord: Vector3<u32>) -> usize {
+        ((coord.z * self.clusters.x * self.clusters.y) + (coord.y * self.clusters.x) + coord.x)
+            as usize
+    }
+
+    fn cluster_coord(&self, cluster: usize) -> Vector3<u32> {
+        let cluster = cluster as u32;
+        let layer = self.clusters.x * self.clusters.y;
+        let idx = cluster % layer;
+
+        Vector3::new(
+            idx % self.clusters.x,
+            idx / self.clusters.x,
+            cluster / layer,
+        )
+    }
+
+    /// The inclusive tile bounds of `cluster`.
+    fn cluster_bounds(&self, cluster: usize) -> (Vector3<u32>, Vector3<u32>) {
+        let coord = self.cluster_coord(cluster);
+        let dimensions = self.tiles.dimensions();
+
+        let min = Vector3::new(
+            coord.x * self.cluster_size,
+            coord.y * self.cluster_size,
+            coord.z,
+        );
+        let max = Vector3::new(
+            (min.x + self.cluster_size - 1).min(dimensions.x - 1),
+            (min.y + self.cluster_size - 1).min(dimensions.y - 1),
+            coord.z,
+        );
+
+        (min, max)
+    }
+
+    /// The orthogonally adjacent clusters on the same z-level.
+    fn adjacent_clusters(&self, cluster: usize) -> Vec<usize> {
+        let coord = self.cluster_coord(cluster);
+        let mut adjacent = Vec::with_capacity(4);
+
+        if coord.x > 0 {
+            adjacent.push(self.cluster_index(Vector3::new(coord.x - 1, coord.y, coord.z)));
+        }
+        if coord.x + 1 < self.clusters.x {
+            adjacent.push(self.cluster_index(Vector3::new(coord.x + 1, coord.y, coord.z)));
+        }
+        if coord.y > 0 {
+            adjacent.push(self.cluster_index(Vector3::new(coord.x, coord.y - 1, coord.z)));
+        }
+        if coord.y + 1 < self.clusters.y {
+            adjacent.push(self.cluster_index(Vector3::new(coord.x, coord.y + 1, coord.z)));
+        }
+
+        adjacent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hpa_matches_flat_astar() {
+        let tiles = Tiles::new(40, 40, 1);
+
+        // A wall across the map with a single gap at the bottom
+        let cost = |id: TileId| {
+            let coord = tiles.coord(id);
+            if coord.x == 20 && coord.y < 35 {
+                None
+            } else {
+                Some(1.0)
+            }
+        };
+
+        let mut graph = HierarchicalGraph::new(tiles, 8);
+        graph.build(&cost);
+
+        let start = tiles.id(2, 2, 0);
+        let goal = tiles.id(38, 2, 0);
+        let (path, hpa_cost) = graph.find_path(start, goal, &cost).unwrap();
+        let (_, flat_cost) = astar_tiles(tiles, start, goal, None, None, &cost).unwrap();
+
+        assert_eq!(path[0], start);
+        assert_eq!(*path.last().unwrap(), goal);
+        assert!(path.iter().all(|id| cost(*id).is_some()));
+        assert!(path
+            .windows(2)
+            .all(|w| tiles.neighbors8(w[0]).any(|n| n == w[1])));
+        assert!(hpa_cost >= flat_cost);
+        assert!(hpa_cost <= flat_cost * 1.3);
+    }
+
+    #[test]
+    fn hpa_invalidation() {
+        let tiles = Tiles::new(32, 32, 1);
+        let mut blocked = HashSet::new();
+
+        let mut graph = HierarchicalGraph::new(tiles, 8);
+        graph.build(&|id| {
+            if blocked.contains(&id) {
+                None
+            } else {
+                Some(1.0)
+            }
+        });
+
+        // Wall off the goal completely
+        let goal = tiles.id(30, 30, 0);
+        for neighbor in tiles.neighbors8(goal) {
+            blocked.insert(neighbor);
+            graph.invalidate(neighbor);
+        }
+        let cost = |id| {
+            if blocked.contains(&id) {
+                None
+            } else {
+                Some(1.0)
+            }
+        };
+        graph.rebuild_dirty(&cost);
+
+        assert!(graph.find_path(tiles.id(1, 1, 0), goal, &cost).is_none());
+    }
+}
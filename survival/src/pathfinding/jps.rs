@@ -0,0 +1,250 @@
+use amethyst::core::math::Vector3;
+
+use crate::tiles::{TileId, Tiles};
+
+/// Extra tiles around the start and goal checked for uniform cost before using jump point search.
+const uniform_window_margin: u32 = 8;
+
+/// Whether every passable tile in the window spanning `start` and `goal` costs the same, which is
+/// what jump point search requires to find optimal paths.
+pub fn is_uniform<C>(tiles: Tiles, start: TileId, goal: TileId, cost: &C) -> bool
+where
+    C: Fn(TileId) -> Option<f32>,
+{
+    let (start, goal) = (tiles.coord(start), tiles.coord(goal));
+    let min = Vector3::new(
+        start.x.min(goal.x).saturating_sub(uniform_window_margin),
+        start.y.min(goal.y).saturating_sub(uniform_window_margin),
+        start.z.min(goal.z),
+    );
+    let max = Vector3::new(
+        start.x.max(goal.x) + uniform_window_margin,
+        start.y.max(goal.y) + uniform_window_margin,
+        start.z.max(goal.z),
+    );
+
+    tiles
+        .iter_volume(min, max)
+        .all(|id| cost(id).map_or(true, |c| (c - 1.0).abs() < std::f32::EPSILON))
+}
+
+/// Jump point search on the z-level of `start`, returning every tile of the path. Passability
+/// comes from `cost`, but every passable tile is treated as costing 1; check `is_uniform` first.
+/// The returned cost is the true cost of the path.
+pub fn jps_tiles<C>(
+    tiles: Tiles,
+    start: TileId,
+    goal: TileId,
+    cost: &C,
+) -> Option<(Vec<TileId>, f32)>
+where
+    C: Fn(TileId) -> Option<f32>,
+{
+    use ordered_float::NotNan;
+    use pathfinding::prelude::*;
+
+    let z = tiles.coord(start).z;
+    let goal_coord = tiles.coord(goal);
+    if goal_coord.z != z {
+        return None;
+    }
+
+    let search = Search {
+        tiles,
+        z,
+        goal: (goal_coord.x as i32, goal_coord.y as i32),
+        cost,
+    };
+
+    // Nodes are a jump point and the direction it was reached from
+    let to_point = |id: TileId| {
+        let coord = tiles.coord(id);
+        (coord.x as i32, coord.y as i32)
+    };
+    let (jump_points, _) = astar(
+        &(to_point(start), (0, 0)),
+        |(point, direction)| {
+            search
+                .successors(*point, *direction)
+                .into_iter()
+                .map(|next| {
+                    let direction = ((next.0 - point.0).signum(), (next.1 - point.1).signum());
+                    let distance = (next.0 - point.0).abs().max((next.1 - point.1).abs());
+                    ((next, direction), NotNan::new(distance as f32).unwrap())
+                })
+                .collect::<Vec<_>>()
+        },
+        |((x, y), _)| {
+            NotNan::new((x - search.goal.0).abs().max((y - search.goal.1).abs()) as f32).unwrap()
+        },
+        |(point, _)| *point == search.goal,
+    )?;
+
+    // Expand the straight and diagonal runs between jump points back into tiles
+    let mut path = vec![start];
+    let mut total = 0.0;
+    for pair in jump_points.windows(2) {
+        let ((mut x, mut y), _) = pair[0];
+        let ((to_x, to_y), direction) = pair[1];
+        while (x, y) != (to_x, to_y) {
+            x += direction.0;
+            y += direction.1;
+
+            let id = tiles.id(x as u32, y as u32, z);
+            total += cost(id)?;
+            path.push(id);
+        }
+    }
+
+    Some((path, total))
+}
+
+struct Search<'a, C> {
+    tiles: Tiles,
+    z: u32,
+    goal: (i32, i32),
+    cost: &'a C,
+}
+impl<'a, C> Search<'a, C>
+where
+    C: Fn(TileId) -> Option<f32>,
+{
+    fn walkable(&self, x: i32, y: i32) -> bool {
+        let dimensions = self.tiles.dimensions();
+        x >= 0
+            && y >= 0
+            && (x as u32) < dimensions.x
+            && (y as u32) < dimensions.y
+            && (self.cost)(self.tiles.id(x as u32, y as u32, self.z)).is_some()
+    }
+
+    /// The jump points reachable from `point`, pruning directions which a path through the
+    /// parent reaches at least as cheaply.
+    fn successors(&self, point: (i32, i32), (dx, dy): (i32, i32)) -> Vec<(i32, i32)> {
+        let (x, y) = point;
+        let mut directions = Vec::with_capacity(8);
+
+        if (dx, dy) == (0, 0) {
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    if (dx, dy) != (0, 0) {
+                        directions.push((dx, dy));
+                    }
+                }
+            }
+        } else if dx != 0 && dy != 0 {
+            directions.extend_from_slice(&[(dx, 0), (0, dy), (dx, dy)]);
+            if !self.walkable(x - dx, y) {
+                directions.push((-dx, dy));
+            }
+            if !self.walkable(x, y - dy) {
+                directions.push((dx, -dy));
+            }
+        } else if dx != 0 {
+            directions.push((dx, 0));
+            if !self.walkable(x, y + 1) {
+                directions.push((dx, 1));
+            }
+            if !self.walkable(x, y - 1) {
+                directions.push((dx, -1));
+            }
+        } else {
+            directions.push((0, dy));
+            if !self.walkable(x + 1, y) {
+                directions.push((1, dy));
+            }
+            if !self.walkable(x - 1, y) {
+                directions.push((-1, dy));
+            }
+        }
+
+        directions
+            .into_iter()
+            .filter_map(|direction| self.jump(point, direction))
+            .collect()
+    }
+
+    /// Steps from `point` in a direction until reaching the goal, a tile with a forced neighbor,
+    /// or (when moving diagonally) a tile from which a straight jump finds one.
+    fn jump(&self, (mut x, mut y): (i32, i32), (dx, dy): (i32, i32)) -> Option<(i32, i32)> {
+        loop {
+            x += dx;
+            y += dy;
+
+            if !self.walkable(x, y) {
+                return None;
+            }
+            if (x, y) == self.goal {
+                return Some((x, y));
+            }
+
+            if dx != 0 && dy != 0 {
+                if (self.walkable(x - dx, y + dy) && !self.walkable(x - dx, y))
+                    || (self.walkable(x + dx, y - dy) && !self.walkable(x, y - dy))
+                {
+                    return Some((x, y));
+                }
+                if self.jump((x, y), (dx, 0)).is_some() || self.jump((x, y), (0, dy)).is_some() {
+                    return Some((x, y));
+                }
+            } else if dx != 0 {
+                if (self.walkable(x + dx, y + 1) && !self.walkable(x, y + 1))
+                    || (self.walkable(x + dx, y - 1) && !self.walkable(x, y - 1))
+                {
+                    return Some((x, y));
+                }
+            } else if (self.walkable(x + 1, y + dy) && !self.walkable(x + 1, y))
+                || (self.walkable(x - 1, y + dy) && !self.walkable(x - 1, y))
+            {
+                return Some((x, y));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::astar_tiles;
+    use std::collections::HashSet;
+
+    #[test]
+    fn jps_matches_astar() {
+        let tiles = Tiles::new(32, 32, 1);
+
+        // A scattering of walls from a simple LCG so the test is deterministic
+        let mut state = 12345_u32;
+        let mut walls = HashSet::new();
+        for _ in 0..300 {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let x = (state >> 8) % 32;
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let y = (state >> 8) % 32;
+            walls.insert(tiles.id(x, y, 0));
+        }
+        let start = tiles.id(0, 0, 0);
+        walls.remove(&start);
+
+        let cost = |id| {
+            if walls.contains(&id) {
+                None
+            } else {
+                Some(1.0)
+            }
+        };
+
+        for goal in tiles.iter_all().step_by(7) {
+            let flat = astar_tiles(tiles, start, goal, None, None, &cost).map(|(_, c)| c);
+            let jump = jps_tiles(tiles, start, goal, &cost);
+
+            assert_eq!(flat, jump.as_ref().map(|(_, c)| *c));
+            if let Some((path, c)) = jump {
+                assert_eq!(path.len() as f32, c + 1.0);
+                assert!(path.windows(2).all(|step| {
+                    let (a, b) = (tiles.coord(step[0]), tiles.coord(step[1]));
+                    (a.x as i32 - b.x as i32).abs() <= 1 && (a.y as i32 - b.y as i32).abs() <= 1
+                }));
+            }
+        }
+    }
+}
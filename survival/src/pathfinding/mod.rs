@@ -0,0 +1,1092 @@
+use amethyst::{
+    core::math::{Vector3, Vector4},
+    ecs::{Component, DenseVecStorage, Entity, Join, ReadExpect, SystemData, World},
+};
+use crossbeam::queue::SegQueue;
+use std::collections::{HashMap, HashSet};
+
+use crate::components::{Doorway, FluidDepth, Footing, Obstruction, ZTransition};
+use crate::tiles::{ReadTiles, TileEntities, TileId, TileStorage, Tiles};
+use specs_static::Id;
+
+pub mod dstar;
+#[cfg(test)]
+mod fixtures;
+pub mod flow;
+pub mod hpa;
+pub mod jps;
+pub use dstar::DStarLite;
+pub use flow::{FlowField, FlowFields, FlowGoal};
+pub use hpa::HierarchicalGraph;
+
+/// Index of the stairs and ramps connecting z-levels, with their speed modifiers.
+#[derive(Default, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PathCache {
+    z_transitions: Vec<(Vector3<u32>, f32)>,
+    z_transitions_index: HashMap<u32, Vec<u32>>,
+    z_transitions_coords: HashMap<Vector3<u32>, u32>,
+    built: bool,
+}
+impl PathCache {
+    pub fn insert_transition(&mut self, coord: Vector3<u32>, speed: f32) {
+        let index = self.z_transitions.len() as u32;
+        let z = coord.z;
+        self.z_transitions.push((coord, speed));
+        self.z_transitions_coords.insert(coord, index);
+
+        let z_list = {
+            match self.z_transitions_index.get_mut(&z) {
+                Some(list) => list,
+                None => {
+                    self.z_transitions_index.insert(z, Vec::new());
+                    self.z_transitions_index.get_mut(&z).unwrap()
+                }
+            }
+        };
+        z_list.push(index);
+    }
+
+    pub fn clear(&mut self) {
+        self.z_transitions.clear();
+        self.z_transitions_index.clear();
+        self.z_transitions_coords.clear();
+        self.built = false;
+    }
+
+    pub fn is_built(&self) -> bool {
+        self.built
+    }
+
+    pub fn rebuild(&mut self, world: &World, _region: Vector4<u32>) {
+        let tiles: ReadExpect<Tiles> = SystemData::fetch(&world.res);
+        let z_transitions: ReadTiles<ZTransition> = SystemData::fetch(&world.res);
+        let obstructions: ReadTiles<Obstruction> = SystemData::fetch(&world.res);
+
+        self.rebuild_from(*tiles, &z_transitions, &obstructions);
+    }
+
+    pub fn rebuild_from(
+        &mut self,
+        tiles: Tiles,
+        z_transitions: &ReadTiles<ZTransition>,
+        obstructions: &ReadTiles<Obstruction>,
+    ) {
+        self.clear();
+
+        // Find all ZTransition's, index them + their obstruction value if any
+        for index in z_transitions.mask().join() {
+            let tile_id = tiles.id_at(index);
+            let speed = match obstructions.get(tile_id) {
+                Some(Obstruction::Slow(rate)) => *rate,
+                _ => 1.0,
+            };
+            self.insert_transition(tiles.coord(tile_id), speed);
+        }
+
+        self.built = true;
+    }
+
+    /// The speed modifier of the transition at `coord`, if there is one.
+    pub fn transition(&self, coord: Vector3<u32>) -> Option<f32> {
+        self.z_transitions_coords
+            .get(&coord)
+            .map(|index| self.z_transitions[*index as usize].1)
+    }
+
+    /// All the transitions on z-level `z`.
+    pub fn transitions_on(&self, z: u32) -> impl Iterator<Item = &(Vector3<u32>, f32)> {
+        self.z_transitions_index
+            .get(&z)
+            .into_iter()
+            .flatten()
+            .map(move |index| &self.z_transitions[*index as usize])
+    }
+
+    /// The levels reachable from `coord` by stairs, with the cost of moving onto them. A
+    /// transition connects to the tile directly above or below when that is also a transition.
+    pub fn z_neighbors(&self, coord: Vector3<u32>) -> Vec<(Vector3<u32>, f32)> {
+        if self.transition(coord).is_none() {
+            return Vec::new();
+        }
+
+        let mut neighbors = Vec::with_capacity(2);
+        let above = Vector3::new(coord.x, coord.y, coord.z + 1);
+        if let Some(speed) = self.transition(above) {
+            neighbors.push((above, speed));
+        }
+        if coord.z > 0 {
+            let below = Vector3::new(coord.x, coord.y, coord.z - 1);
+            if let Some(speed) = self.transition(below) {
+                neighbors.push((below, speed));
+            }
+        }
+
+        neighbors
+    }
+}
+
+bitflags_serial! {
+    pub struct PathFlags: u8 {
+        const None =            0;
+        /// Plan over the `HierarchicalGraph`. Faster for long paths, but not always optimal and
+        /// limited to a single z-level.
+        const Hierarchical =    1 << 0;
+        /// String-pull the path into waypoints with `smooth_path`.
+        const Smooth =          1 << 1;
+        /// Follow a shared `FlowField` to the goal; for when many entities head to the same tile.
+        const Flow =            1 << 2;
+        /// Keep a `Replanner` which repairs the path as tiles along it change.
+        const Incremental =     1 << 3;
+    }
+}
+impl Default for PathFlags {
+    fn default() -> Self {
+        PathFlags::None
+    }
+}
+
+bitflags_serial! {
+    /// How a creature is able to move, which decides the tiles it can path through.
+    pub struct MovementProfile: u8 {
+        const Walk =            1 << 0;
+        const Swim =            1 << 1;
+        const Fly =             1 << 2;
+        /// Paths through impassable tiles by digging them out.
+        const Dig =             1 << 3;
+        const OpenDoors =       1 << 4;
+    }
+}
+impl Default for MovementProfile {
+    fn default() -> Self {
+        MovementProfile::Walk | MovementProfile::OpenDoors
+    }
+}
+impl Component for MovementProfile {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The tile storages which make up the cost of moving across the map.
+pub struct TerrainCost<'a> {
+    pub obstructions: &'a TileStorage<Obstruction>,
+    pub footing: &'a TileStorage<Footing>,
+    pub fluids: &'a TileStorage<FluidDepth>,
+    pub doors: &'a TileStorage<Doorway>,
+    pub occupants: &'a TileStorage<TileEntities>,
+}
+impl<'a> TerrainCost<'a> {
+    const dig_cost: f32 = 10.0;
+    const door_cost: f32 = 1.0;
+    const swim_cost: f32 = 2.0;
+    /// Fluid at least this deep has to be swum.
+    const swim_depth: u8 = 4;
+    /// Added per entity standing on the tile, so crowds are routed around rather than through.
+    const occupied_cost: f32 = 0.5;
+
+    /// The cost for a creature moving with `profile` to enter a tile, or `None` if it can't.
+    pub fn cost(&self, profile: MovementProfile, id: TileId) -> Option<f32> {
+        Self::cost_of(profile, &self.tile(id))
+    }
+
+    /// The cost as `cost` gives it, less the entities standing on the tile, for costs cached
+    /// until the tile itself changes.
+    pub fn settled_cost(&self, profile: MovementProfile, id: TileId) -> Option<f32> {
+        Self::cost_of(
+            profile,
+            &TerrainTile {
+                occupants: 0,
+                ..self.tile(id)
+            },
+        )
+    }
+
+    /// Copies the terrain of every tile, for searches carried on past the frame they began in.
+    pub fn snapshot(&self, tiles: Tiles) -> TerrainSnapshot {
+        TerrainSnapshot(
+            (0..tiles.id_bound())
+                .map(|id| self.tile(tiles.id_at(id)))
+                .collect(),
+        )
+    }
+
+    fn tile(&self, id: TileId) -> TerrainTile {
+        TerrainTile {
+            obstruction: self.obstructions.get(id).cloned(),
+            footing: self.footing.get(id).cloned(),
+            depth: self.fluids.get(id).map_or(0, |depth| depth.0),
+            door: self.doors.get(id).cloned(),
+            occupants: self
+                .occupants
+                .get(id)
+                .map_or(0, |occupants| occupants.0.len()),
+        }
+    }
+
+    fn cost_of(profile: MovementProfile, tile: &TerrainTile) -> Option<f32> {
+        if let Some(door) = tile.door {
+            if door.locked || !profile.contains(MovementProfile::OpenDoors) {
+                return None;
+            }
+        }
+
+        let mut cost = match tile.obstruction {
+            // Shut doors are opened on the way through
+            Some(Obstruction::Impassable) if tile.door.is_some() => 1.0,
+            Some(Obstruction::Impassable) if profile.contains(MovementProfile::Dig) => {
+                return Some(Self::dig_cost);
+            }
+            Some(Obstruction::Impassable) => return None,
+            Some(Obstruction::Slow(rate)) => rate,
+            None => 1.0,
+        };
+        if tile.door.is_some() {
+            cost += Self::door_cost;
+        }
+
+        if profile.contains(MovementProfile::Fly) {
+            return Some(cost);
+        }
+
+        match tile.depth {
+            depth if depth >= Self::swim_depth => {
+                if !profile.contains(MovementProfile::Swim) {
+                    return None;
+                }
+                cost *= Self::swim_cost;
+            }
+            depth if depth > 0 => {
+                cost *= 1.0 + f32::from(depth) * 0.5;
+            }
+            _ => {
+                if !profile.contains(MovementProfile::Walk) {
+                    return None;
+                }
+                if let Some(footing) = tile.footing {
+                    cost *= footing.cost();
+                }
+            }
+        }
+
+        Some(cost + Self::occupied_cost * tile.occupants as f32)
+    }
+}
+
+/// What `TerrainCost` reads of a single tile.
+#[derive(Clone, Copy, Debug, Default)]
+struct TerrainTile {
+    obstruction: Option<Obstruction>,
+    footing: Option<Footing>,
+    depth: u8,
+    door: Option<Doorway>,
+    occupants: usize,
+}
+
+/// The terrain of every tile as it was when taken by `TerrainCost::snapshot`, owned so searches
+/// can run on other threads across frames.
+#[derive(Clone, Debug, Default)]
+pub struct TerrainSnapshot(Vec<TerrainTile>);
+impl TerrainSnapshot {
+    /// The same cost `TerrainCost::cost` gave when the snapshot was taken.
+    pub fn cost(&self, profile: MovementProfile, id: TileId) -> Option<f32> {
+        TerrainCost::cost_of(profile, self.0.get(id.id() as usize)?)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathRequest {
+    pub entity: Entity,
+    pub start: Vector3<u32>,
+    pub goal: Vector3<u32>,
+    pub flags: PathFlags,
+    pub profile: MovementProfile,
+}
+
+/// Queue of paths waiting to be computed by `PathfindingSystem`. Results are written to the
+/// requesting entity's `Path` component, or `PathFailed` if there is no path.
+#[derive(Default)]
+pub struct PathRequests {
+    queue: SegQueue<PathRequest>,
+}
+impl PathRequests {
+    pub fn request(&self, request: PathRequest) {
+        self.queue.push(request);
+    }
+
+    /// Takes every pending request, keeping only the latest one per entity.
+    pub fn drain(&self) -> Vec<PathRequest> {
+        let mut latest = HashMap::new();
+        while let Ok(request) = self.queue.pop() {
+            latest.insert(request.entity, request);
+        }
+        latest.into_iter().map(|(_, request)| request).collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum PathfindingType {
+    Astar,
+    /// Jump point search, used when the terrain between start and goal has uniform cost and
+    /// otherwise falling back to A*.
+    JumpPoint,
+}
+impl Default for PathfindingType {
+    fn default() -> Self {
+        PathfindingType::JumpPoint
+    }
+}
+
+#[derive(Default, Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Pathfinding {
+    pub kind: PathfindingType,
+}
+
+impl Pathfinding {
+    pub fn new(kind: PathfindingType) -> Self {
+        Self { kind }
+    }
+
+    /// Finds a path between tiles with the configured algorithm.
+    pub fn find<C>(
+        &self,
+        tiles: Tiles,
+        start: TileId,
+        goal: TileId,
+        cache: Option<&PathCache>,
+        cost: &C,
+    ) -> Option<(Vec<TileId>, f32)>
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        if self.kind == PathfindingType::JumpPoint
+            && tiles.coord(start).z == tiles.coord(goal).z
+            && jps::is_uniform(tiles, start, goal, cost)
+        {
+            // Jump point search stays on one level, so a path may still exist through stairs
+            if let Some(path) = jps::jps_tiles(tiles, start, goal, cost) {
+                return Some(path);
+            }
+        }
+
+        astar_tiles(tiles, start, goal, None, cache, cost)
+    }
+
+    pub fn shortest_path(
+        &self,
+        terrain: &TerrainCost,
+        profile: MovementProfile,
+        cache: &PathCache,
+        tiles: &Tiles,
+        start: &Vector3<u32>,
+        goal: &Vector3<u32>,
+    ) -> Option<(Vec<Vector3<u32>>, f32)> {
+        let (path, cost) = self.find(
+            *tiles,
+            tiles.id_from_vector(*start),
+            tiles.id_from_vector(*goal),
+            Some(cache),
+            &|id| terrain.cost(profile, id),
+        )?;
+
+        Some((path.into_iter().map(|id| tiles.coord(id)).collect(), cost))
+    }
+
+    /// The cheapest path to whichever of `goals` is nearest, e.g. the closest of several beds.
+    pub fn path_to_any(
+        &self,
+        terrain: &TerrainCost,
+        profile: MovementProfile,
+        cache: &PathCache,
+        tiles: &Tiles,
+        start: &Vector3<u32>,
+        goals: &[Vector3<u32>],
+    ) -> Option<(Vec<Vector3<u32>>, f32)> {
+        let goals = goals
+            .iter()
+            .map(|goal| tiles.id_from_vector(*goal))
+            .collect::<Vec<_>>();
+        let (path, cost) = astar_to_any(
+            *tiles,
+            tiles.id_from_vector(*start),
+            &goals,
+            Some(cache),
+            &|id| terrain.cost(profile, id),
+        )?;
+
+        Some((path.into_iter().map(|id| tiles.coord(id)).collect(), cost))
+    }
+}
+
+/// The cost of entering a tile from its obstruction alone, or `None` if it can't be entered. Used
+/// where costs are cached independently of who is moving; see `TerrainCost` for the full model.
+pub fn tile_cost(obstruction: Option<&Obstruction>) -> Option<f32> {
+    match obstruction {
+        Some(Obstruction::Impassable) => None,
+        Some(Obstruction::Slow(rate)) => Some(*rate),
+        None => Some(1.0),
+    }
+}
+
+/// Grid A* over 8-connected z-levels, optionally restricted to the inclusive `bounds`. Paths
+/// only change z-level through the stairs in `transitions`, so without them the search stays on
+/// the level of `start`. `cost` returns the cost of entering a tile, or `None` if it is
+/// impassable.
+pub fn astar_tiles<C>(
+    tiles: Tiles,
+    start: TileId,
+    goal: TileId,
+    bounds: Option<(Vector3<u32>, Vector3<u32>)>,
+    transitions: Option<&PathCache>,
+    cost: &C,
+) -> Option<(Vec<TileId>, f32)>
+where
+    C: Fn(TileId) -> Option<f32>,
+{
+    let goal_coord = tiles.coord(goal);
+    astar_search(
+        tiles,
+        start,
+        bounds,
+        transitions,
+        cost,
+        |coord| distance(coord, goal_coord),
+        |id| id == goal,
+    )
+}
+
+/// Like `astar_tiles`, but searching for whichever of `goals` is cheapest to reach in a single
+/// search rather than one per goal.
+pub fn astar_to_any<C>(
+    tiles: Tiles,
+    start: TileId,
+    goals: &[TileId],
+    transitions: Option<&PathCache>,
+    cost: &C,
+) -> Option<(Vec<TileId>, f32)>
+where
+    C: Fn(TileId) -> Option<f32>,
+{
+    // Past this many goals the heuristic costs more than the nodes it saves
+    const max_heuristic_goals: usize = 32;
+
+    let goal_coords = goals.iter().map(|id| tiles.coord(*id)).collect::<Vec<_>>();
+    let goal_set = goals.iter().collect::<HashSet<_>>();
+    astar_search(
+        tiles,
+        start,
+        None,
+        transitions,
+        cost,
+        |coord| {
+            if goal_coords.len() > max_heuristic_goals {
+                return 0;
+            }
+            goal_coords
+                .iter()
+                .map(|goal| distance(coord, *goal))
+                .min()
+                .unwrap_or(0)
+        },
+        |id| goal_set.contains(&id),
+    )
+}
+
+/// Diagonal moves cost the same as orthogonal ones, so chebyshev distance is exact on a level,
+/// plus a move for every level between.
+pub fn distance(a: Vector3<u32>, b: Vector3<u32>) -> u32 {
+    use pathfinding::utils::absdiff;
+
+    absdiff(a.x, b.x).max(absdiff(a.y, b.y)) + absdiff(a.z, b.z)
+}
+
+fn astar_search<C, H, S>(
+    tiles: Tiles,
+    start: TileId,
+    bounds: Option<(Vector3<u32>, Vector3<u32>)>,
+    transitions: Option<&PathCache>,
+    cost: &C,
+    heuristic: H,
+    success: S,
+) -> Option<(Vec<TileId>, f32)>
+where
+    C: Fn(TileId) -> Option<f32>,
+    H: Fn(Vector3<u32>) -> u32,
+    S: Fn(TileId) -> bool,
+{
+    use ordered_float::NotNan;
+    use pathfinding::prelude::*;
+
+    let in_bounds = |id: TileId| match bounds {
+        Some((min, max)) => {
+            let coord = tiles.coord(id);
+            coord.x >= min.x
+                && coord.y >= min.y
+                && coord.z >= min.z
+                && coord.x <= max.x
+                && coord.y <= max.y
+                && coord.z <= max.z
+        }
+        None => true,
+    };
+
+    let (path, total) = astar(
+        &start,
+        |id| {
+            let mut successors = tiles
+                .neighbors8(*id)
+                .filter(|neighbor| in_bounds(*neighbor))
+                .filter_map(|neighbor| cost(neighbor).map(|c| (neighbor, NotNan::new(c).unwrap())))
+                .collect::<Vec<_>>();
+
+            if let Some(transitions) = transitions {
+                successors.extend(
+                    transitions
+                        .z_neighbors(tiles.coord(*id))
+                        .into_iter()
+                        .map(|(coord, speed)| (tiles.id_from_vector(coord), speed))
+                        .filter(|(neighbor, _)| in_bounds(*neighbor))
+                        .map(|(neighbor, speed)| (neighbor, NotNan::new(speed).unwrap())),
+                );
+            }
+
+            successors
+        },
+        |id| NotNan::new(heuristic(tiles.coord(*id)) as f32).unwrap(),
+        |id| success(*id),
+    )?;
+
+    Some((path, total.into_inner()))
+}
+
+/// Simplifies a tile path into waypoints by string pulling: each waypoint is joined to the
+/// furthest later tile it has a clear line to. Changes of z-level are always kept as waypoints.
+pub fn smooth_path<F>(tiles: Tiles, path: &[TileId], blocked: F) -> Vec<TileId>
+where
+    F: Fn(TileId) -> bool,
+{
+    if path.len() < 3 {
+        return path.to_vec();
+    }
+
+    let mut waypoints = vec![path[0]];
+    let mut anchor = path[0];
+    for window in path.windows(2).skip(1) {
+        let (current, next) = (window[0], window[1]);
+        if tiles.coord(anchor).z != tiles.coord(next).z
+            || tiles.raycast(anchor, next, &blocked).is_some()
+        {
+            waypoints.push(current);
+            anchor = current;
+        }
+    }
+    waypoints.push(path[path.len() - 1]);
+
+    waypoints
+}
+
+/// Connected component labels of passable tiles, for answering reachability queries without
+/// running a full search. Kept current by `ConnectivitySystem`.
+#[derive(Default, Clone, Debug)]
+pub struct Connectivity {
+    labels: Vec<u32>,
+    next_label: u32,
+}
+impl Connectivity {
+    const unreachable: u32 = 0;
+
+    pub fn is_built(&self) -> bool {
+        !self.labels.is_empty()
+    }
+
+    pub fn label(&self, id: TileId) -> Option<u32> {
+        match self.labels.get(id.id() as usize) {
+            Some(&label) if label != Self::unreachable => Some(label),
+            _ => None,
+        }
+    }
+
+    pub fn is_reachable(&self, from: TileId, to: TileId) -> bool {
+        match (self.label(from), self.label(to)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    pub fn rebuild<F>(&mut self, tiles: Tiles, passable: F)
+    where
+        F: Fn(TileId) -> bool,
+    {
+        self.labels.clear();
+        self.labels
+            .resize(tiles.id_bound() as usize, Self::unreachable);
+        self.next_label = Self::unreachable;
+
+        for index in 0..self.labels.len() {
+            let id = tiles.id_at(index as u32);
+            if self.labels[index] == Self::unreachable && tiles.contains_id(id) {
+                self.fill(tiles, id, &passable);
+            }
+        }
+    }
+
+    /// Relabels the components around a changed tile. A tile opening up joins the components
+    /// around it, filling only when it connects more than one; a tile closing off fills once per
+    /// piece its component splits into.
+    pub fn update<F>(&mut self, tiles: Tiles, changed: TileId, passable: F)
+    where
+        F: Fn(TileId) -> bool,
+    {
+        match (self.label(changed), passable(changed)) {
+            (None, true) => self.join(tiles, changed, &passable),
+            (Some(label), false) => self.split(tiles, changed, label, &passable),
+            _ => (),
+        }
+    }
+
+    fn join<F>(&mut self, tiles: Tiles, changed: TileId, passable: &F)
+    where
+        F: Fn(TileId) -> bool,
+    {
+        let mut around = tiles
+            .neighbors8(changed)
+            .filter_map(|neighbor| self.label(neighbor))
+            .collect::<Vec<_>>();
+        around.sort_unstable();
+        around.dedup();
+
+        match around.as_slice() {
+            [] => {
+                self.next_label += 1;
+                self.labels[changed.id() as usize] = self.next_label;
+            }
+            [label] => self.labels[changed.id() as usize] = *label,
+            _ => self.fill(tiles, changed, passable),
+        }
+    }
+
+    fn split<F>(&mut self, tiles: Tiles, changed: TileId, label: u32, passable: &F)
+    where
+        F: Fn(TileId) -> bool,
+    {
+        self.labels[changed.id() as usize] = Self::unreachable;
+
+        // Neighbors reached by an earlier fill have been relabelled, and are skipped
+        for neighbor in tiles.neighbors8(changed) {
+            if self.label(neighbor) == Some(label) {
+                self.fill(tiles, neighbor, passable);
+            }
+        }
+    }
+
+    fn fill<F>(&mut self, tiles: Tiles, start: TileId, passable: &F)
+    where
+        F: Fn(TileId) -> bool,
+    {
+        let filled = crate::tiles::flood_fill(tiles, start, passable);
+        if filled.is_empty() {
+            return;
+        }
+
+        self.next_label += 1;
+        for id in filled {
+            self.labels[id.id() as usize] = self.next_label;
+        }
+    }
+}
+
+type DijstraMap = HashMap<Vector3<u32>, f32>;
+
+/// Scales an inverted map when deriving a flee map; past 1, fleeing prefers escape routes which
+/// keep leading away over dead ends which are merely far for now.
+const flee_coefficient: f32 = 1.2;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum DijkstraMapType {
+    Movement,
+    /// Distance to the nearest threat.
+    Threat,
+    /// Descended to flee from threats.
+    Flee,
+    /// Distance to the nearest food lying about.
+    Food,
+}
+
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DijkstraCollection {
+    maps: HashMap<DijkstraMapType, DijstraMap>,
+}
+impl DijkstraCollection {
+    pub fn value(&self, kind: &DijkstraMapType, coord: Vector3<u32>) -> Option<f32> {
+        self.maps.get(kind).and_then(|map| map.get(&coord)).cloned()
+    }
+
+    pub fn remove(&mut self, kind: &DijkstraMapType) {
+        self.maps.remove(kind);
+    }
+
+    /// Builds a map of the cost from every tile to the nearest of `sources`, out to `max_cost`.
+    pub fn build<C>(
+        &mut self,
+        kind: DijkstraMapType,
+        tiles: Tiles,
+        sources: &[Vector3<u32>],
+        max_cost: f32,
+        cost: &C,
+    ) where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        let seeds = sources.iter().map(|coord| (*coord, 0.0)).collect();
+        let map = Self::relax(tiles, seeds, max_cost, None, cost);
+        self.maps.insert(kind, map);
+    }
+
+    /// Derives a map which leads away from the sources of `from` when descended, by inverting it
+    /// and relaxing the result again.
+    pub fn build_flee<C>(
+        &mut self,
+        from: &DijkstraMapType,
+        kind: DijkstraMapType,
+        tiles: Tiles,
+        cost: &C,
+    ) where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        let source = match self.maps.get(from) {
+            Some(source) => source,
+            None => return,
+        };
+        let seeds = source
+            .iter()
+            .map(|(coord, value)| (*coord, -value * flee_coefficient))
+            .collect();
+        let map = Self::relax(tiles, seeds, std::f32::INFINITY, Some(source), cost);
+        self.maps.insert(kind, map);
+    }
+
+    /// The neighbor with the lowest value below that of `from`.
+    pub fn descend(
+        &self,
+        kind: &DijkstraMapType,
+        tiles: Tiles,
+        from: Vector3<u32>,
+    ) -> Option<Vector3<u32>> {
+        let map = self.maps.get(kind)?;
+        let current = map.get(&from).cloned().unwrap_or(std::f32::INFINITY);
+
+        tiles
+            .neighbors8(tiles.id_from_vector(from))
+            .map(|id| tiles.coord(id))
+            .filter_map(|coord| map.get(&coord).map(|value| (coord, *value)))
+            .filter(|(_, value)| *value < current)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(coord, _)| coord)
+    }
+
+    /// The source reached by descending from `from` as far as the map leads, if it reaches `from`
+    /// at all.
+    pub fn source(
+        &self,
+        kind: &DijkstraMapType,
+        tiles: Tiles,
+        from: Vector3<u32>,
+    ) -> Option<Vector3<u32>> {
+        self.value(kind, from)?;
+        let mut current = from;
+        while let Some(next) = self.descend(kind, tiles, current) {
+            current = next;
+        }
+        Some(current)
+    }
+
+    /// Dijkstra from seeds with starting values, optionally confined to the tiles of `within`.
+    fn relax<C>(
+        tiles: Tiles,
+        seeds: Vec<(Vector3<u32>, f32)>,
+        max_cost: f32,
+        within: Option<&DijstraMap>,
+        cost: &C,
+    ) -> DijstraMap
+    where
+        C: Fn(TileId) -> Option<f32>,
+    {
+        use ordered_float::NotNan;
+        use std::cmp::Reverse;
+        use std::collections::BinaryHeap;
+
+        let mut map = DijstraMap::new();
+        let mut open = BinaryHeap::new();
+        for (coord, value) in seeds {
+            if map.get(&coord).map_or(true, |best| value < *best) {
+                map.insert(coord, value);
+                open.push(Reverse((
+                    NotNan::new(value).unwrap(),
+                    tiles.id_from_vector(coord),
+                )));
+            }
+        }
+
+        while let Some(Reverse((value, id))) = open.pop() {
+            let value = value.into_inner();
+            let coord = tiles.coord(id);
+            if map.get(&coord).map_or(false, |best| *best < value) {
+                continue;
+            }
+
+            for neighbor in tiles.neighbors8(id) {
+                let next = match cost(neighbor) {
+                    Some(step) => value + step,
+                    None => continue,
+                };
+                let neighbor_coord = tiles.coord(neighbor);
+                if next > max_cost || within.map_or(false, |map| !map.contains_key(&neighbor_coord))
+                {
+                    continue;
+                }
+                if map.get(&neighbor_coord).map_or(true, |best| next < *best) {
+                    map.insert(neighbor_coord, next);
+                    open.push(Reverse((NotNan::new(next).unwrap(), neighbor)));
+                }
+            }
+        }
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    pub fn connectivity_update() {
+        let tiles = Tiles::new(10, 10, 1);
+        let mut walls = (0..10).map(|y| tiles.id(5, y, 0)).collect::<HashSet<_>>();
+
+        let mut connectivity = Connectivity::default();
+        connectivity.rebuild(tiles, |id| !walls.contains(&id));
+        assert!(!connectivity.is_reachable(tiles.id(0, 0, 0), tiles.id(9, 9, 0)));
+        assert!(connectivity.is_reachable(tiles.id(0, 0, 0), tiles.id(4, 9, 0)));
+
+        // Open a door in the wall
+        let door = tiles.id(5, 4, 0);
+        walls.remove(&door);
+        connectivity.update(tiles, door, |id| !walls.contains(&id));
+        assert!(connectivity.is_reachable(tiles.id(0, 0, 0), tiles.id(9, 9, 0)));
+        assert!(connectivity.is_reachable(door, tiles.id(0, 0, 0)));
+
+        // Walling off a tile without cutting anything off keeps the rest together
+        let pillar = tiles.id(1, 1, 0);
+        walls.insert(pillar);
+        connectivity.update(tiles, pillar, |id| !walls.contains(&id));
+        assert!(connectivity.is_reachable(tiles.id(0, 0, 0), tiles.id(9, 9, 0)));
+        assert_eq!(connectivity.label(pillar), None);
+        walls.remove(&pillar);
+        connectivity.update(tiles, pillar, |id| !walls.contains(&id));
+        assert!(connectivity.is_reachable(pillar, tiles.id(9, 9, 0)));
+
+        // And close it again
+        walls.insert(door);
+        connectivity.update(tiles, door, |id| !walls.contains(&id));
+        assert!(!connectivity.is_reachable(tiles.id(0, 0, 0), tiles.id(9, 9, 0)));
+        assert_eq!(connectivity.label(door), None);
+    }
+
+    #[test]
+    pub fn movement_profiles() {
+        let tiles = Tiles::new(10, 10, 1);
+        let mut obstructions = TileStorage::<Obstruction>::default();
+        let footing = TileStorage::<Footing>::default();
+        let mut fluids = TileStorage::<FluidDepth>::default();
+        let mut doors = TileStorage::<Doorway>::default();
+        let occupants = TileStorage::<TileEntities>::default();
+
+        // A river across the map, with a wall beside it holding a shut door
+        for y in 0..10 {
+            fluids.insert(tiles.id(4, y, 0), FluidDepth(6));
+            obstructions.insert(tiles.id(6, y, 0), Obstruction::Impassable);
+        }
+        doors.insert(tiles.id(6, 5, 0), Doorway::default());
+
+        let terrain = TerrainCost {
+            obstructions: &obstructions,
+            footing: &footing,
+            fluids: &fluids,
+            doors: &doors,
+            occupants: &occupants,
+        };
+        let path = |profile| {
+            Pathfinding::default().shortest_path(
+                &terrain,
+                profile,
+                &PathCache::default(),
+                &tiles,
+                &Vector3::new(0, 5, 0),
+                &Vector3::new(9, 5, 0),
+            )
+        };
+
+        assert!(path(MovementProfile::default()).is_none());
+        assert!(path(MovementProfile::default() | MovementProfile::Swim).is_some());
+        assert!(path(MovementProfile::Fly).is_none());
+        assert!(path(MovementProfile::Fly | MovementProfile::OpenDoors).is_some());
+        assert!(
+            path(MovementProfile::Walk | MovementProfile::Swim | MovementProfile::Dig).is_some()
+        );
+
+        // Searches off the main thread see the same costs
+        let snapshot = terrain.snapshot(tiles);
+        for profile in &[
+            MovementProfile::default(),
+            MovementProfile::Fly | MovementProfile::OpenDoors,
+            MovementProfile::Walk | MovementProfile::Swim | MovementProfile::Dig,
+        ] {
+            for id in (0..tiles.id_bound()).map(|index| tiles.id_at(index)) {
+                assert_eq!(snapshot.cost(*profile, id), terrain.cost(*profile, id));
+            }
+        }
+    }
+
+    #[test]
+    pub fn smoothing() {
+        let tiles = Tiles::new(10, 10, 1);
+        let walls = (0..8).map(|y| tiles.id(5, y, 0)).collect::<HashSet<_>>();
+        let cost = |id| if walls.contains(&id) { None } else { Some(1.0) };
+
+        let (path, _) = astar_tiles(
+            tiles,
+            tiles.id(0, 0, 0),
+            tiles.id(9, 0, 0),
+            None,
+            None,
+            &cost,
+        )
+        .unwrap();
+        let waypoints = smooth_path(tiles, &path, |id| walls.contains(&id));
+
+        assert!(waypoints.len() < path.len());
+        assert_eq!(waypoints.first(), path.first());
+        assert_eq!(waypoints.last(), path.last());
+        for pair in waypoints.windows(2) {
+            assert!(tiles.line_of_sight(pair[0], pair[1], |id| walls.contains(&id)));
+        }
+
+        // Open ground needs no intermediate waypoints, however much the grid path zigzags
+        let (path, _) = astar_tiles(
+            tiles,
+            tiles.id(0, 0, 0),
+            tiles.id(9, 5, 0),
+            None,
+            None,
+            &|_| Some(1.0),
+        )
+        .unwrap();
+        assert_eq!(
+            smooth_path(tiles, &path, |_| false),
+            vec![tiles.id(0, 0, 0), tiles.id(9, 5, 0)]
+        );
+    }
+
+    #[test]
+    pub fn nearest_goal() {
+        let tiles = Tiles::new(20, 20, 1);
+        let walls = (0..19).map(|y| tiles.id(10, y, 0)).collect::<HashSet<_>>();
+        let cost = |id| if walls.contains(&id) { None } else { Some(1.0) };
+
+        // The goal just past the wall is closer as the crow flies, but further to walk to
+        let start = tiles.id(8, 0, 0);
+        let near = tiles.id(12, 0, 0);
+        let far = tiles.id(0, 10, 0);
+        let (path, cost) = astar_to_any(tiles, start, &[near, far], None, &cost).unwrap();
+        assert_eq!(path.last(), Some(&far));
+        assert_eq!(cost, 10.);
+
+        assert!(astar_to_any(tiles, start, &[], None, &|_| Some(1.0)).is_none());
+        assert_eq!(
+            astar_to_any(tiles, start, &[start, far], None, &|_| Some(1.0)),
+            Some((vec![start], 0.))
+        );
+    }
+
+    #[test]
+    fn flee_map() {
+        let tiles = Tiles::new(16, 1, 1);
+        let cost = |_| Some(1.0);
+        let mut maps = DijkstraCollection::default();
+        maps.build(
+            DijkstraMapType::Threat,
+            tiles,
+            &[Vector3::new(4, 0, 0)],
+            10.0,
+            &cost,
+        );
+        assert_eq!(
+            maps.value(&DijkstraMapType::Threat, Vector3::new(7, 0, 0)),
+            Some(3.0)
+        );
+        assert_eq!(
+            maps.value(&DijkstraMapType::Threat, Vector3::new(15, 0, 0)),
+            None
+        );
+        assert_eq!(
+            maps.source(&DijkstraMapType::Threat, tiles, Vector3::new(7, 0, 0)),
+            Some(Vector3::new(4, 0, 0))
+        );
+        assert_eq!(
+            maps.source(&DijkstraMapType::Threat, tiles, Vector3::new(15, 0, 0)),
+            None
+        );
+
+        maps.build_flee(
+            &DijkstraMapType::Threat,
+            DijkstraMapType::Flee,
+            tiles,
+            &cost,
+        );
+        // Fleeing leads away from the threat, until the edge of the map
+        let mut position = Vector3::new(6, 0, 0);
+        for _ in 0..12 {
+            position = maps
+                .descend(&DijkstraMapType::Flee, tiles, position)
+                .unwrap_or(position);
+        }
+        assert_eq!(position, Vector3::new(14, 0, 0));
+    }
+
+    #[test]
+    pub fn pathcache_test() {
+        let tiles = Tiles::new(10, 10, 3);
+        let mut cache = PathCache::default();
+
+        // Stairs from z0 to z1 at (2, 2), and a slow ramp from z1 to z2 at (8, 8)
+        cache.insert_transition(Vector3::new(2, 2, 0), 1.0);
+        cache.insert_transition(Vector3::new(2, 2, 1), 1.0);
+        cache.insert_transition(Vector3::new(8, 8, 1), 3.0);
+        cache.insert_transition(Vector3::new(8, 8, 2), 3.0);
+
+        assert_eq!(cache.z_neighbors(Vector3::new(2, 2, 1)).len(), 1);
+        assert_eq!(cache.transitions_on(1).count(), 2);
+
+        let (path, cost) = astar_tiles(
+            tiles,
+            tiles.id(0, 0, 0),
+            tiles.id(9, 9, 2),
+            None,
+            Some(&cache),
+            &|_| Some(1.0),
+        )
+        .unwrap();
+
+        assert!(path.contains(&tiles.id(2, 2, 1)));
+        assert!(path.contains(&tiles.id(8, 8, 2)));
+        assert_eq!(cost, 2. + 1. + 6. + 3. + 1.);
+
+        assert!(astar_tiles(
+            tiles,
+            tiles.id(0, 0, 0),
+            tiles.id(9, 9, 2),
+            None,
+            None,
+            &|_| Some(1.0)
+        )
+        .is_none());
+    }
+}
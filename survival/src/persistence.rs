@@ -0,0 +1,817 @@
+//! Saving the game to a file and loading it back. Entities are saved by how they were spawned,
+//! their `Prototype`, along with those of their components which serialize, and are spawned
+//! again from it as the game loads before the components are put back on them. The tile storages
+//! registered with `register_persistent_tile_comp` are saved whole.
+
+use amethyst::{
+    core::{components::Parent, math::Vector3},
+    ecs::{
+        error::NoError,
+        saveload::{
+            DeserializeComponents, Marker, MarkerAllocator, SerializeComponents, SimpleMarker,
+            SimpleMarkerAllocator,
+        },
+        Component, DenseVecStorage, Entities, Entity, Join, ReadStorage, SystemData, World,
+        WriteStorage,
+    },
+};
+use hibitset::BitSetOr;
+use serde::{Deserialize, Serialize};
+use specs_static::Registry;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::components::{
+    ai::{Needs, Pawn, AI},
+    Container, Door, Health, Initiative, MaterialStatus, Player, PlayerControlled, TileLayerSprite,
+    TilePosition, TimeAvailable, Tree,
+};
+use crate::components::{ConstructionLayer, FlaggedSpriteRender, FloorLayer};
+use crate::components::{FluidLayer, TerrainLayer};
+use crate::cooking::Perishable;
+use crate::goap::Planner;
+use crate::initializers::{spawn_creature, spawn_door, spawn_item, spawn_tree, SpawnType};
+use crate::injury::Body;
+use crate::map::WorldMap;
+use crate::minimap::Minimap;
+use crate::settings::{Config, Context};
+use crate::systems::time::TimeState;
+use crate::temperature::BodyTemperature;
+use crate::tiles::{Tiles, WriteTiles};
+
+const save_magic: [u8; 4] = *b"SSAV";
+/// Version of the layout of save files, those of other versions failing to load.
+pub const save_version: u32 = 5;
+const save_compression_level: i32 = 3;
+/// Extension of save files, in the directory of `SaveSettings`.
+pub const save_extension: &str = "save";
+/// Name of the autosave slots, followed by their number from 1, the latest.
+const autosave_prefix: &str = "autosave-";
+
+/// Marks the entities written to a save file, for those referring to others to be linked again.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Saved;
+pub type SaveMarker = SimpleMarker<Saved>;
+pub type SaveMarkerAllocator = SimpleMarkerAllocator<Saved>;
+
+/// How an entity was spawned, for it to be spawned the same way as the game loads. Entities
+/// without one, such as the camera, aren't saved.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(DenseVecStorage)]
+pub enum Prototype {
+    /// The colonist the player starts with.
+    Player,
+    /// A creature of a kind in `creatures.ron`.
+    Creature(String),
+    /// An item by its key in `items.ron`.
+    Item(String),
+    Tree,
+    Door,
+}
+
+/// The components saved of each entity, in the order of `SavedComponents`.
+type SavedStorages<'a> = (
+    ReadStorage<'a, Prototype>,
+    ReadStorage<'a, TilePosition>,
+    ReadStorage<'a, Initiative>,
+    ReadStorage<'a, TimeAvailable>,
+    ReadStorage<'a, Player>,
+    ReadStorage<'a, PlayerControlled>,
+    ReadStorage<'a, Needs>,
+    ReadStorage<'a, Body>,
+    ReadStorage<'a, BodyTemperature>,
+    ReadStorage<'a, Health>,
+    ReadStorage<'a, Perishable>,
+    ReadStorage<'a, Tree>,
+    ReadStorage<'a, Door>,
+    ReadStorage<'a, Container>,
+    ReadStorage<'a, MaterialStatus>,
+);
+type LoadedStorages<'a> = (
+    WriteStorage<'a, Prototype>,
+    WriteStorage<'a, TilePosition>,
+    WriteStorage<'a, Initiative>,
+    WriteStorage<'a, TimeAvailable>,
+    WriteStorage<'a, Player>,
+    WriteStorage<'a, PlayerControlled>,
+    WriteStorage<'a, Needs>,
+    WriteStorage<'a, Body>,
+    WriteStorage<'a, BodyTemperature>,
+    WriteStorage<'a, Health>,
+    WriteStorage<'a, Perishable>,
+    WriteStorage<'a, Tree>,
+    WriteStorage<'a, Door>,
+    WriteStorage<'a, Container>,
+    WriteStorage<'a, MaterialStatus>,
+);
+pub type SavedComponents = (
+    Option<Prototype>,
+    Option<TilePosition>,
+    Option<Initiative>,
+    Option<TimeAvailable>,
+    Option<Player>,
+    Option<PlayerControlled>,
+    Option<Needs>,
+    Option<Body>,
+    Option<BodyTemperature>,
+    Option<Health>,
+    Option<Perishable>,
+    Option<Tree>,
+    Option<Door>,
+    Option<Container>,
+    Option<MaterialStatus>,
+);
+
+/// The plan a pawn was carrying out, by the names of its actions, as the ids the planner gives
+/// them differ from one run to the next.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedPlan {
+    pub goal: Option<String>,
+    /// The actions of the plan left to carry out, the first being under way.
+    pub remaining: Vec<String>,
+    pub failures: u32,
+    pub replan_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedEntity {
+    pub marker: SaveMarker,
+    /// The entity this one is inside of, such as the creature carrying it.
+    pub parent: Option<SaveMarker>,
+    pub components: SavedComponents,
+    pub plan: Option<SavedPlan>,
+}
+
+/// The tile storages saved, those of the `specs_static::Registry` by the name they were
+/// registered under. The sprites of the tile layers, whose sprite sheet handles don't serialize,
+/// are kept by their number on the sprite sheet.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SavedTiles {
+    pub terrain: Vec<(u32, usize)>,
+    pub floor: Vec<(u32, usize)>,
+    pub construction: Vec<(u32, usize)>,
+    pub fluid: Vec<(u32, usize)>,
+    pub storages: Vec<(String, Vec<u8>)>,
+}
+
+/// The minimap as it was when the game was saved, the save browser's picture of it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    /// Colors of the cells, row by row.
+    pub pixels: Vec<[u8; 3]>,
+}
+impl Thumbnail {
+    pub fn from_minimap(minimap: &Minimap) -> Self {
+        let channel = |value: f32| (value.max(0.0).min(1.0) * 255.0).round() as u8;
+        Self {
+            width: minimap.width,
+            height: minimap.height,
+            pixels: minimap
+                .pixels
+                .iter()
+                .map(|color| [channel(color.0), channel(color.1), channel(color.2)])
+                .collect(),
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+/// What a save is, kept ahead of the game in its file for saves to be listed without reading
+/// them whole.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SaveInfo {
+    /// Name of the slot it was saved to.
+    pub name: String,
+    /// The game time, for the calendar date it was saved on.
+    pub time: TimeState,
+    pub pawns: u32,
+    /// Seconds since the Unix epoch it was saved at.
+    pub saved_at: u64,
+    pub thumbnail: Thumbnail,
+}
+impl SaveInfo {
+    /// Describes the game in `world` as saved to the slot `name`.
+    pub fn new(world: &World, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            time: *world.read_resource::<TimeState>(),
+            pawns: world.read_storage::<Pawn>().join().count() as u32,
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            thumbnail: world
+                .res
+                .try_fetch::<Minimap>()
+                .map(|minimap| Thumbnail::from_minimap(&minimap))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// A save asked to be loaded, which the running state switches to `LoadGame` for.
+#[derive(Clone, Debug, Default)]
+pub struct LoadRequest(pub Option<PathBuf>);
+
+/// Everything a save file holds.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub dimensions: (u32, u32, u32),
+    pub time: TimeState,
+    pub map: Option<WorldMap>,
+    pub tiles: SavedTiles,
+    pub entities: Vec<SavedEntity>,
+}
+
+/// The sprite numbers of the tiles of the layer `L`.
+fn save_sprites<L: TileLayerSprite>(world: &World) -> Vec<(u32, usize)> {
+    let storage: WriteTiles<L> = SystemData::fetch(&world.res);
+    (storage.mask(), &*storage)
+        .join()
+        .map(|(id, layer)| (id, layer.sprite().sprite_number))
+        .collect()
+}
+
+fn clear_tiles<C: Component>(world: &World) {
+    let mut storage: WriteTiles<C> = SystemData::fetch(&world.res);
+    let ids = storage.ids().collect::<Vec<_>>();
+    for id in ids {
+        storage.remove(id);
+    }
+}
+
+fn load_sprites<L: TileLayerSprite>(world: &World, saved: &[(u32, usize)]) {
+    let sprite_sheet = match &world.res.fetch::<Context>().spritesheet {
+        Some(sprite_sheet) => sprite_sheet.clone(),
+        None => return,
+    };
+    let tiles = *world.read_resource::<Tiles>();
+    let mut storage: WriteTiles<L> = SystemData::fetch(&world.res);
+    for (id, sprite_number) in saved {
+        storage.insert(
+            tiles.id_at(*id),
+            L::new(FlaggedSpriteRender {
+                sprite_sheet: sprite_sheet.clone(),
+                sprite_number: *sprite_number,
+            }),
+        );
+    }
+}
+
+/// The plan `ai` is carrying out, if any.
+fn save_plan(ai: &AI, planner: &Planner) -> Option<SavedPlan> {
+    let start = match ai.current {
+        Some(current) => ai.plan.iter().position(|action| *action == current)?,
+        None => return None,
+    };
+    Some(SavedPlan {
+        goal: ai.goal.clone(),
+        remaining: ai.plan[start..]
+            .iter()
+            .filter_map(|action| planner.get_action_name(*action))
+            .map(str::to_string)
+            .collect(),
+        failures: ai.failures,
+        replan_at: ai.replan_at,
+    })
+}
+
+/// Takes a snapshot of the game in `world`, marking the entities with a `Prototype` to be saved.
+pub fn save(world: &World) -> Result<SaveGame, failure::Error> {
+    let tiles = *world.read_resource::<Tiles>();
+    let dimensions = tiles.dimensions();
+
+    let entities = {
+        let (entities, storages, parents, ais, planner, mut markers, mut allocator): (
+            Entities,
+            SavedStorages,
+            ReadStorage<Parent>,
+            ReadStorage<AI>,
+            amethyst::ecs::Read<Planner>,
+            WriteStorage<SaveMarker>,
+            amethyst::ecs::Write<SaveMarkerAllocator>,
+        ) = SystemData::fetch(&world.res);
+
+        let saved = (&entities, &storages.0)
+            .join()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in &saved {
+            allocator.mark(*entity, &mut markers);
+        }
+
+        saved
+            .into_iter()
+            .map(|entity| SavedEntity {
+                marker: *markers.get(entity).unwrap(),
+                parent: parents
+                    .get(entity)
+                    .and_then(|parent| markers.get(parent.entity).cloned()),
+                components: SerializeComponents::<NoError, SaveMarker>::serialize_entity(
+                    &storages,
+                    entity,
+                    |other| markers.get(other).cloned(),
+                )
+                .unwrap(),
+                plan: ais.get(entity).and_then(|ai| save_plan(ai, &planner)),
+            })
+            .collect()
+    };
+
+    Ok(SaveGame {
+        dimensions: (dimensions.x, dimensions.y, dimensions.z),
+        time: *world.read_resource::<TimeState>(),
+        map: world
+            .res
+            .try_fetch::<WorldMap>()
+            .map(|map| WorldMap::clone(&map)),
+        tiles: SavedTiles {
+            terrain: save_sprites::<TerrainLayer>(world),
+            floor: save_sprites::<FloorLayer>(world),
+            construction: save_sprites::<ConstructionLayer>(world),
+            fluid: save_sprites::<FluidLayer>(world),
+            storages: world.read_resource::<Registry>().save(&world.res)?,
+        },
+        entities,
+    })
+}
+
+/// Removes the game in `world` for another to be loaded into it: the entities it would have saved,
+/// the camera, and the tiles of the storages it would have saved.
+pub fn clear(world: &mut World) {
+    let removed = {
+        let (entities, prototypes, cameras) = (
+            world.entities(),
+            world.read_storage::<Prototype>(),
+            world.read_storage::<amethyst::renderer::Camera>(),
+        );
+        (&entities, BitSetOr(prototypes.mask(), cameras.mask()))
+            .join()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>()
+    };
+    world.delete_entities(&removed).unwrap();
+    world.maintain();
+
+    clear_tiles::<FloorLayer>(world);
+    clear_tiles::<ConstructionLayer>(world);
+    clear_tiles::<FluidLayer>(world);
+    world.read_resource::<Registry>().clear(&world.res);
+}
+
+/// Spawns `saved` again from its prototype, inside `parent` if it was inside of something.
+fn respawn(world: &mut World, saved: &SavedEntity, parent: Option<Entity>) -> Option<Entity> {
+    let (prototype, position, tree) = (
+        saved.components.0.as_ref()?,
+        saved.components.1.as_ref(),
+        saved.components.11.as_ref(),
+    );
+    let spawn_type = match (parent, position) {
+        (Some(parent), _) => SpawnType::Parent(parent),
+        (None, Some(position)) => SpawnType::TilePosition(position.coord),
+        (None, None) => return None,
+    };
+    let coord = position.map_or_else(|| Vector3::new(0, 0, 0), |position| position.coord);
+
+    Some(match prototype {
+        Prototype::Player => {
+            let sprite_sheet = world.res.fetch::<Context>().spritesheet.clone()?;
+            let tiles = *world.read_resource::<Tiles>();
+            let config = world.read_resource::<Config>().clone();
+            crate::states::level::init_player(world, &sprite_sheet, tiles, &config, coord)
+        }
+        Prototype::Creature(kind) => spawn_creature(world, kind, spawn_type),
+        Prototype::Item(key) => spawn_item(world, spawn_type, key, None),
+        Prototype::Tree => {
+            let tree = tree?;
+            spawn_tree(world, coord, tree.kind(), tree.size())
+        }
+        Prototype::Door => spawn_door(world, coord, false, false),
+    })
+}
+
+/// Puts the plan `saved` back on `ai`, dropping it if the planner no longer knows its actions.
+fn load_plan(ai: &mut AI, saved: SavedPlan, planner: &Planner) {
+    let plan = saved
+        .remaining
+        .iter()
+        .map(|name| planner.lookup(name).map(crate::goap::Action::id))
+        .collect::<Option<Vec<_>>>();
+    ai.failures = saved.failures;
+    ai.replan_at = saved.replan_at;
+    if let Some(plan) = plan {
+        ai.goal = saved.goal;
+        ai.set_plan(&plan);
+    }
+}
+
+/// Restores the game saved in `save` into `world`, whose level has been laid out with
+/// `states::level::init_tiles` over tiles of the saved dimensions. Entities are spawned again
+/// before the tiles are restored, for those to be as saved rather than as spawning left them.
+pub fn load(world: &mut World, save: SaveGame) -> Result<(), failure::Error> {
+    *world.write_resource::<TimeState>() = save.time;
+    if let Some(mut map) = save.map {
+        map.set_biomes(
+            &world
+                .read_resource::<crate::assets::BiomeStorage>()
+                .read()
+                .unwrap()
+                .data,
+        );
+        world.add_resource(map);
+    }
+
+    // Entities are spawned once what they are inside of is
+    let mut spawned = HashMap::new();
+    let mut pending = save.entities;
+    loop {
+        let count = pending.len();
+        let mut waiting = Vec::new();
+        for saved in pending {
+            let parent = match saved
+                .parent
+                .as_ref()
+                .map(|parent| spawned.get(&parent.id()))
+            {
+                Some(Some((entity, _))) => Some(*entity),
+                Some(None) => {
+                    waiting.push(saved);
+                    continue;
+                }
+                None => None,
+            };
+            if let Some(entity) = respawn(world, &saved, parent) {
+                spawned.insert(saved.marker.id(), (entity, saved));
+            }
+        }
+        pending = waiting;
+        if pending.is_empty() || pending.len() == count {
+            break;
+        }
+    }
+
+    let ids = spawned
+        .iter()
+        .map(|(id, (entity, _))| (*id, *entity))
+        .collect::<HashMap<_, _>>();
+    {
+        let (mut storages, mut ais, planner, mut markers, mut allocator): (
+            LoadedStorages,
+            WriteStorage<AI>,
+            amethyst::ecs::Read<Planner>,
+            WriteStorage<SaveMarker>,
+            amethyst::ecs::Write<SaveMarkerAllocator>,
+        ) = SystemData::fetch(&world.res);
+        for (id, (entity, saved)) in spawned {
+            let marker = allocator.allocate(entity, Some(id));
+            markers.insert(entity, marker).unwrap();
+
+            DeserializeComponents::<NoError, SaveMarker>::deserialize_entity(
+                &mut storages,
+                entity,
+                saved.components,
+                |marker| ids.get(&marker.id()).cloned(),
+            )
+            .unwrap();
+            if let (Some(ai), Some(plan)) = (ais.get_mut(entity), saved.plan) {
+                load_plan(ai, plan, &planner);
+            }
+        }
+    }
+
+    load_tiles(world, &save.tiles)
+}
+
+/// Restores the tiles saved in `saved` into the tiles of `world`, at the ids of their generation
+/// whichever the ids were saved in.
+fn load_tiles(world: &World, saved: &SavedTiles) -> Result<(), failure::Error> {
+    load_sprites::<TerrainLayer>(world, &saved.terrain);
+    load_sprites::<FloorLayer>(world, &saved.floor);
+    load_sprites::<ConstructionLayer>(world, &saved.construction);
+    load_sprites::<FluidLayer>(world, &saved.fluid);
+    let generation = world.read_resource::<Tiles>().generation();
+    world
+        .read_resource::<Registry>()
+        .load(&world.res, &saved.storages, generation)?;
+
+    Ok(())
+}
+
+/// Writes `save` to `path` as a versioned header and its bincode `info`, followed by the zstd
+/// compressed bincode save.
+pub fn write(path: &Path, info: &SaveInfo, save: &SaveGame) -> Result<(), failure::Error> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(&save_magic)?;
+    file.write_all(&save_version.to_le_bytes())?;
+    bincode::serialize_into(&mut file, info)?;
+
+    let mut encoder = zstd::Encoder::new(file, save_compression_level)?;
+    bincode::serialize_into(&mut encoder, save)?;
+    encoder.finish()?;
+
+    Ok(())
+}
+
+/// Opens the save at `path`, checking its header.
+fn open(path: &Path) -> Result<std::fs::File, failure::Error> {
+    let mut file = std::fs::File::open(path)?;
+    let mut header = [0; 8];
+    file.read_exact(&mut header)?;
+
+    if header[0..4] != save_magic {
+        return Err(failure::format_err!("{:?} is not a save file", path));
+    }
+    let version = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    if version != save_version {
+        return Err(failure::format_err!(
+            "Unsupported save version {} in {:?}, expected {}",
+            version,
+            path,
+            save_version
+        ));
+    }
+
+    Ok(file)
+}
+
+/// Reads the `SaveInfo` of a save written by `write`.
+pub fn read_info(path: &Path) -> Result<SaveInfo, failure::Error> {
+    Ok(bincode::deserialize_from(open(path)?)?)
+}
+
+/// Reads a save written by `write`.
+pub fn read(path: &Path) -> Result<SaveGame, failure::Error> {
+    let mut file = open(path)?;
+    let _: SaveInfo = bincode::deserialize_from(&mut file)?;
+    Ok(bincode::deserialize_from(zstd::Decoder::new(file)?)?)
+}
+
+/// The file of the save slot `name`, the characters of it unsafe in a file name replaced.
+pub fn slot_path(directory: &Path, name: &str) -> PathBuf {
+    let file = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    directory.join(file).with_extension(save_extension)
+}
+
+/// The file of the autosave `index`, 1 being the latest.
+pub fn autosave_path(directory: &Path, index: u32) -> PathBuf {
+    slot_path(directory, &format!("{}{}", autosave_prefix, index))
+}
+
+/// Saves the game in `world` to the slot `name`, replacing any save there.
+pub fn save_slot(world: &World, name: &str) -> Result<PathBuf, failure::Error> {
+    let directory = PathBuf::from(&world.read_resource::<Config>().saves.directory);
+    let path = slot_path(&directory, name);
+    write(&path, &SaveInfo::new(world, name), &save(world)?)?;
+    Ok(path)
+}
+
+/// Moves each of the `count` autosaves in `directory` along a slot to make room for a new
+/// latest, the oldest being replaced.
+pub fn rotate(directory: &Path, count: u32) -> Result<(), failure::Error> {
+    for index in (1..count).rev() {
+        let path = autosave_path(directory, index);
+        if path.exists() {
+            std::fs::rename(&path, autosave_path(directory, index + 1))?;
+        }
+    }
+    Ok(())
+}
+
+/// Saves the game in `world` as the latest autosave, keeping as many as `SaveSettings` asks.
+pub fn autosave(world: &World) -> Result<(), failure::Error> {
+    let settings = world.read_resource::<Config>().saves.clone();
+    if settings.autosaves == 0 {
+        return Ok(());
+    }
+
+    let directory = PathBuf::from(&settings.directory);
+    rotate(&directory, settings.autosaves)?;
+    let path = autosave_path(&directory, 1);
+    let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+    write(&path, &SaveInfo::new(world, &name), &save(world)?)
+}
+
+/// The saves in `directory` which can be read, the latest first.
+pub fn list(directory: &Path) -> Vec<(PathBuf, SaveInfo)> {
+    let mut saves = std::fs::read_dir(directory)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == save_extension)
+        })
+        .filter_map(|path| read_info(&path).ok().map(|info| (path, info)))
+        .collect::<Vec<_>>();
+    saves.sort_by(|(_, a), (_, b)| b.saved_at.cmp(&a.saved_at));
+    saves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_file() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let marker = SaveMarkerAllocator::default().allocate(entity, Some(7));
+
+        let mut save = SaveGame {
+            dimensions: (10, 10, 2),
+            time: TimeState::new(2400),
+            map: None,
+            tiles: SavedTiles::default(),
+            entities: vec![SavedEntity {
+                marker,
+                parent: None,
+                components: Default::default(),
+                plan: Some(SavedPlan {
+                    goal: Some("Eat".to_string()),
+                    remaining: vec!["Pick Up".to_string(), "Eat Food".to_string()],
+                    failures: 1,
+                    replan_at: 30,
+                }),
+            }],
+        };
+        save.time.current_time = 1234;
+        save.tiles.terrain.push((3, 34));
+        save.tiles
+            .storages
+            .push(("footing".to_string(), vec![1, 2, 3]));
+        save.entities[0].components.0 = Some(Prototype::Creature("deer".to_string()));
+        save.entities[0].components.1 = Some(TilePosition::new(Vector3::new(4, 5, 1)));
+
+        let info = SaveInfo {
+            name: "Colony".to_string(),
+            time: save.time,
+            pawns: 3,
+            saved_at: 100,
+            thumbnail: Thumbnail {
+                width: 2,
+                height: 1,
+                pixels: vec![[0, 0, 0], [255, 128, 0]],
+            },
+        };
+
+        let path = std::env::temp_dir().join(format!("survival-{}.save", std::process::id()));
+        write(&path, &info, &save).unwrap();
+        assert_eq!(read_info(&path).unwrap(), info);
+        assert_eq!(info.thumbnail.pixel(1, 0), [255, 128, 0]);
+        let loaded = read(&path).unwrap();
+        assert_eq!(loaded.dimensions, save.dimensions);
+        assert_eq!(loaded.time, save.time);
+        assert_eq!(loaded.tiles.terrain, save.tiles.terrain);
+        assert_eq!(loaded.tiles.storages, save.tiles.storages);
+        let entity = &loaded.entities[0];
+        assert_eq!(entity.marker.id(), 7);
+        assert_eq!(entity.components.0, save.entities[0].components.0);
+        assert_eq!(
+            entity.components.1.as_ref().map(|position| position.coord),
+            Some(Vector3::new(4, 5, 1))
+        );
+        assert_eq!(entity.plan, save.entities[0].plan);
+
+        // Saves of another version aren't read
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[4] += 1;
+        std::fs::write(&path, bytes).unwrap();
+        assert!(read(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn slots() {
+        let directory = std::env::temp_dir().join(format!("survival-saves-{}", std::process::id()));
+        assert_eq!(
+            slot_path(&directory, " My colony/2 "),
+            directory.join("My_colony_2.save")
+        );
+        assert_eq!(
+            autosave_path(&directory, 2),
+            directory.join("autosave-2.save")
+        );
+
+        let save = SaveGame {
+            dimensions: (1, 1, 1),
+            time: TimeState::default(),
+            map: None,
+            tiles: SavedTiles::default(),
+            entities: Vec::new(),
+        };
+        let autosave_at = |saved_at| {
+            rotate(&directory, 2).unwrap();
+            let info = SaveInfo {
+                saved_at,
+                ..SaveInfo::default()
+            };
+            write(&autosave_path(&directory, 1), &info, &save).unwrap();
+        };
+        autosave_at(1);
+        autosave_at(2);
+        autosave_at(3);
+        write(
+            &slot_path(&directory, "manual"),
+            &SaveInfo {
+                saved_at: 2,
+                ..SaveInfo::default()
+            },
+            &save,
+        )
+        .unwrap();
+        std::fs::write(directory.join("notes.txt"), "").unwrap();
+
+        // The oldest autosave was rotated out, and the latest saves are listed first
+        let saves = list(&directory)
+            .into_iter()
+            .map(|(path, info)| (path, info.saved_at))
+            .collect::<Vec<_>>();
+        assert_eq!(saves[0], (autosave_path(&directory, 1), 3));
+        assert_eq!(saves.len(), 3);
+        assert!(!autosave_path(&directory, 3).exists());
+        assert_eq!(
+            read_info(&autosave_path(&directory, 2)).unwrap().saved_at,
+            2
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn load_after_new_level() {
+        use crate::farming::Tilled;
+        use crate::states::level::next_generation;
+        use crate::tiles::{ReadTiles, TileEntities, TileId};
+        use specs_static::{Id, WorldExt};
+
+        let mut world = World::new();
+        world.register::<Prototype>();
+        world.register::<amethyst::renderer::Camera>();
+        world.register_tile_comp::<FloorLayer, TileId>();
+        world.register_tile_comp::<ConstructionLayer, TileId>();
+        world.register_tile_comp::<FluidLayer, TileId>();
+        world.register_tile_comp::<TileEntities, TileId>();
+        world.register_persistent_tile_comp::<Tilled, TileId>("tilled");
+        world.add_resource(Context {
+            logs: crate::settings::Logs {
+                root: slog::Logger::root(slog::Discard, slog::o!()),
+            },
+            spritesheet: None,
+        });
+
+        // Lays out a level as `init_tiles` does, returning whether each of its tiles took
+        let lay_out = |world: &mut World| {
+            let tiles = Tiles::new(4, 4, 1).with_generation(next_generation(world));
+            world.add_resource(tiles);
+            let mut tile_entities: WriteTiles<TileEntities> = SystemData::fetch(&world.res);
+            tiles
+                .iter_all()
+                .all(|id| tile_entities.insert_default(id).is_none())
+        };
+
+        assert!(lay_out(&mut world));
+        let tilled = world.read_resource::<Tiles>().iter_all().nth(5).unwrap();
+        {
+            let mut storage: WriteTiles<Tilled> = SystemData::fetch(&world.res);
+            storage.insert(tilled, Tilled);
+        }
+        let saved = SavedTiles {
+            storages: world.read_resource::<Registry>().save(&world.res).unwrap(),
+            ..SavedTiles::default()
+        };
+
+        // A new game is started, then the save loaded over it
+        assert!(lay_out(&mut world));
+        clear(&mut world);
+        assert!(lay_out(&mut world));
+        load_tiles(&world, &saved).unwrap();
+
+        let tiles = *world.read_resource::<Tiles>();
+        let storage: ReadTiles<Tilled> = SystemData::fetch(&world.res);
+        assert_eq!(storage.get(tiles.id_at(tilled.id())), Some(&Tilled));
+        assert_eq!(storage.get(tilled), None);
+        let tile_entities: ReadTiles<TileEntities> = SystemData::fetch(&world.res);
+        assert_eq!(tile_entities.ids().count(), 16);
+    }
+}
@@ -0,0 +1,118 @@
+//! Conversions between points on the screen, in the world and on the tile grid, through the
+//! camera's projection and transform, so they hold whatever the zoom and graphics scale.
+
+use amethyst::{
+    core::{
+        math::{Vector2, Vector3, Vector4},
+        Transform,
+    },
+    renderer::{Camera, ScreenDimensions},
+};
+
+use crate::settings::Config;
+use crate::tiles::Tiles;
+
+/// World units along each side of a tile, before the graphics scale.
+pub const tile_size: f32 = 20.0;
+
+/// World units along each side of a tile, at the graphics scale of `config`.
+pub fn tile_world_size(config: &Config) -> f32 {
+    tile_size * config.graphics.scale
+}
+
+/// The tile the world position `world` is in. Tiles run east along `x` and south and down
+/// along `-y` and `-z`.
+pub fn world_to_tile(world: &Vector3<f32>, config: &Config) -> Vector3<u32> {
+    let size = tile_world_size(config);
+    Vector3::new(
+        (world.x / size) as u32,
+        (world.y / size).abs() as u32,
+        (world.z / size).abs() as u32,
+    )
+}
+
+/// The world position of the centre of the tile at `coord`, which `world_to_tile` maps back.
+pub fn tile_to_world(coord: Vector3<u32>, config: &Config) -> Vector3<f32> {
+    let size = tile_world_size(config);
+    Vector3::new(
+        (coord.x as f32 + 0.5) * size,
+        -(coord.y as f32 + 0.5) * size,
+        -(coord.z as f32 + 0.5) * size,
+    )
+}
+
+/// The world position at `ndc` in normalized device coordinates, unprojected through the
+/// camera. None if its projection can't be inverted.
+pub fn ndc_to_world(
+    ndc: Vector2<f32>,
+    (camera, transform): (&Camera, &Transform),
+) -> Option<Vector3<f32>> {
+    let world = transform.0 * camera.proj.try_inverse()? * Vector4::new(ndc.x, ndc.y, 0.0, 1.0);
+    Some(world.xyz() / world.w)
+}
+
+/// The world position under the pixel `(x, y)` of the screen, from its top left.
+pub fn screen_to_world(
+    (x, y): (f32, f32),
+    dimensions: &ScreenDimensions,
+    camera: (&Camera, &Transform),
+) -> Option<Vector3<f32>> {
+    let ndc = Vector2::new(
+        (x / dimensions.width()) * 2.0 - 1.0,
+        1.0 - (y / dimensions.height()) * 2.0,
+    );
+    ndc_to_world(ndc, camera)
+}
+
+/// The pixel of the screen, from its top left, the world position `world` is drawn at. None if
+/// the camera's transform can't be inverted.
+pub fn world_to_screen(
+    world: Vector3<f32>,
+    dimensions: &ScreenDimensions,
+    (camera, transform): (&Camera, &Transform),
+) -> Option<(f32, f32)> {
+    let clip =
+        camera.proj * transform.0.try_inverse()? * Vector4::new(world.x, world.y, world.z, 1.0);
+    let (x, y) = (clip.x / clip.w, clip.y / clip.w);
+    Some((
+        (x + 1.0) / 2.0 * dimensions.width(),
+        (1.0 - y) / 2.0 * dimensions.height(),
+    ))
+}
+
+/// The column of tiles under the pixel `(x, y)` of the screen, on level 0. None if it's off the
+/// map.
+pub fn screen_to_tile(
+    screen: (f32, f32),
+    dimensions: &ScreenDimensions,
+    camera: (&Camera, &Transform),
+    tiles: Tiles,
+    config: &Config,
+) -> Option<Vector3<u32>> {
+    let world = screen_to_world(screen, dimensions, camera)?;
+    if world.x < 0.0 || world.y > 0.0 {
+        return None;
+    }
+    let coord = world_to_tile(&Vector3::new(world.x, world.y, 0.0), config);
+    if coord.x >= tiles.dimensions().x || coord.y >= tiles.dimensions().y {
+        return None;
+    }
+    Some(coord)
+}
+
+/// The lower and upper corners of the world rectangle in view of `camera`, by unprojecting the
+/// edges of clip space through its projection and transform, zoom included.
+pub fn view_bounds(camera: Option<(&Camera, &Transform)>) -> Option<(Vector2<f32>, Vector2<f32>)> {
+    let camera = camera?;
+    let corners = [(-1., -1.), (1., -1.), (-1., 1.), (1., 1.)]
+        .iter()
+        .map(|(x, y)| ndc_to_world(Vector2::new(*x, *y), camera).map(|corner| corner.xy()))
+        .collect::<Option<Vec<_>>>()?;
+    let min = corners.iter().fold(corners[0], |min, c| {
+        Vector2::new(min.x.min(c.x), min.y.min(c.y))
+    });
+    let max = corners.iter().fold(corners[0], |max, c| {
+        Vector2::new(max.x.max(c.x), max.y.max(c.y))
+    });
+    Some((min, max))
+}
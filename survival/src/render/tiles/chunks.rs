@@ -0,0 +1,79 @@
+use amethyst::{
+    assets::AssetStorage,
+    core::math::Vector3,
+    renderer::{pipe::Effect, Encoder, Resources, Texture, TextureHandle},
+};
+use std::collections::HashSet;
+
+use super::interleaved::DrawFlat2D;
+use super::util::add_texture;
+
+type Slice = gfx::Slice<Resources>;
+
+/// Width and height in tiles of the chunks whose sprite instances the tile pass keeps between
+/// frames.
+pub const chunk_size: u32 = 16;
+
+/// A chunk of tiles on one z-level, by its column, row and level.
+pub type ChunkKey = (u32, u32, u32);
+
+/// The chunk the tile at `coord` is in.
+pub fn chunk_of(coord: Vector3<u32>) -> ChunkKey {
+    (coord.x / chunk_size, coord.y / chunk_size, coord.z)
+}
+
+/// Chunks with tiles changed since the tile pass last drew, for it to rebuild.
+#[derive(Clone, Default, Debug)]
+pub struct DirtyChunks(pub HashSet<ChunkKey>);
+impl DirtyChunks {
+    pub fn mark(&mut self, coord: Vector3<u32>) {
+        self.0.insert(chunk_of(coord));
+    }
+}
+
+/// Sprite instances uploaded into a vertex buffer, in runs sharing a texture each drawn in one
+/// instanced draw. Those of a chunk are uploaded once and drawn every frame until it changes.
+#[derive(Clone, Default, Debug)]
+pub(super) struct ChunkBatch {
+    pub buffer: Option<gfx::handle::Buffer<Resources, f32>>,
+    /// The texture of each run, the first of its instances and how many there are.
+    pub runs: Vec<(TextureHandle, u32, u32)>,
+}
+impl ChunkBatch {
+    pub fn draw(
+        &self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        tex_storage: &AssetStorage<Texture>,
+    ) {
+        use gfx::memory::Typed;
+
+        let vbuf = match &self.buffer {
+            Some(vbuf) => vbuf,
+            None => return,
+        };
+        for (texture_handle, first, instances) in &self.runs {
+            let texture = tex_storage
+                .get(texture_handle)
+                .expect("Unable to get texture of sprite");
+            add_texture(effect, texture);
+
+            for _ in DrawFlat2D::attributes() {
+                effect.data.vertex_bufs.push(vbuf.raw().clone());
+            }
+
+            effect.draw(
+                &Slice {
+                    start: 0,
+                    end: 6,
+                    base_vertex: 0,
+                    instances: Some((*instances, *first)),
+                    buffer: Default::default(),
+                },
+                encoder,
+            );
+
+            effect.clear();
+        }
+    }
+}
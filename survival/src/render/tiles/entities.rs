@@ -0,0 +1,211 @@
+//! Pass drawing the sprites of entities among the tiles, on the slice in view.
+
+use derivative::Derivative;
+use gfx::pso::buffer::ElemStride;
+use gfx_core::state::{Blend, ColorMask};
+use glsl_layout::Uniform;
+
+use amethyst::assets::AssetStorage;
+use amethyst::core::{
+    ecs::prelude::{Join, Read, ReadExpect, ReadStorage},
+    transform::Transform,
+};
+use amethyst::error::Error;
+
+use amethyst::renderer::{
+    get_camera,
+    pipe::{
+        pass::{Pass, PassData},
+        DepthMode, Effect, NewEffect,
+    },
+    ActiveCamera, Camera, Encoder, Factory, Flipped, Hidden, HiddenPropagate, Rgba, SpriteRender,
+    SpriteSheet, Texture, VertexFormat,
+};
+
+use crate::components::{FlaggedSpriteRender, LightLevel, TileLayer, TilePosition, Visibility};
+use crate::fov::local_player;
+use crate::systems::time::TimeState;
+use crate::tiles::{ReadTiles, Tiles, ViewZ};
+
+use super::interleaved::{DrawFlat2D, TextureBatch};
+use super::util::{default_transparency, setup_textures, AmbientArgs, ViewArgs};
+use super::*;
+
+/// Depth entities are drawn above their tile, over all its layers and its designation.
+const entity_depth: f32 = 0.015;
+/// Depth each row of tiles southwards brings entities forward, so those further south are drawn
+/// over those behind them. Small enough that the last row stays under the selection.
+const row_depth: f32 = 0.000_01;
+
+/// Draws the sprites of entities on the slice in view, over the tile they stand on, in place of
+/// the stock sprite pass. Entities on other levels, or on tiles out of the local player's sight,
+/// aren't drawn, and those further south are drawn over those behind them. Entities not on a tile
+/// are drawn where they are.
+#[derive(Derivative, Clone, Debug)]
+#[derivative(Default(bound = "Self: Pass"))]
+pub struct DrawEntities {
+    #[derivative(Default(value = "default_transparency()"))]
+    transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    batch: TextureBatch,
+}
+
+impl DrawEntities
+where
+    Self: Pass,
+{
+    /// Create instance of `DrawEntities` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+#[allow(clippy::type_complexity)]
+impl<'a> PassData<'a> for DrawEntities {
+    type Data = (
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, Transform>,
+        ReadStorage<'a, SpriteRender>,
+        ReadStorage<'a, TilePosition>,
+        ReadStorage<'a, Flipped>,
+        ReadStorage<'a, Rgba>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+        Read<'a, AssetStorage<SpriteSheet>>,
+        Read<'a, AssetStorage<Texture>>,
+        ReadExpect<'a, Tiles>,
+        ReadTiles<'a, Transform>,
+        ReadTiles<'a, Visibility>,
+        ReadTiles<'a, LightLevel>,
+        Read<'a, ViewZ>,
+        Read<'a, TimeState>,
+    );
+}
+
+impl Pass for DrawEntities {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
+        use std::mem;
+
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder
+            .without_back_face_culling()
+            .with_raw_constant_buffer(
+                "ViewArgs",
+                mem::size_of::<<ViewArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_constant_buffer(
+                "AmbientArgs",
+                mem::size_of::<<AmbientArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_vertex_buffer(
+                DrawFlat2D::attributes(),
+                SpriteInstance::size() as ElemStride,
+                1,
+            );
+        setup_textures(&mut builder, &TEXTURES);
+        match self.transparency {
+            Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
+            None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
+        };
+
+        builder.build()
+    }
+
+    #[allow(clippy::extra_unused_lifetimes)]
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            active,
+            camera,
+            global,
+            sprite_renders,
+            tile_positions,
+            flipped,
+            rgba,
+            hidden,
+            hidden_propagate,
+            sprite_sheet_storage,
+            tex_storage,
+            tiles,
+            tile_globals,
+            tiles_visibility,
+            tiles_light,
+            view_z,
+            time,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let z = view_z.z.min(tiles.dimensions().z - 1);
+        let mut sprites = Vec::new();
+        for (sprite_render, transform, position, flipped, rgba, _, _) in (
+            &sprite_renders,
+            &global,
+            (&tile_positions).maybe(),
+            (&flipped).maybe(),
+            (&rgba).maybe(),
+            !&hidden,
+            !&hidden_propagate,
+        )
+            .join()
+        {
+            // Entities on a tile are drawn over it, if it's on the slice and in sight
+            let (transform, row, light) = match position {
+                Some(position) => {
+                    if position.coord.z != z {
+                        continue;
+                    }
+                    let tile_id = tiles.id_from_vector(position.coord);
+                    let in_sight = tiles_visibility
+                        .get(tile_id)
+                        .map_or(false, |seen| seen.is_visible(local_player));
+                    let tile_global = match tile_globals.get(tile_id) {
+                        Some(tile_global) if in_sight => tile_global,
+                        _ => continue,
+                    };
+                    let mut transform = *tile_global;
+                    transform.0[(2, 3)] += TileLayer::Fluid.depth_offset()
+                        + entity_depth
+                        + position.coord.y as f32 * row_depth;
+                    let light = tiles_light.get(tile_id).map_or(0.0, |light| light.0);
+                    (transform, position.coord.y, light)
+                }
+                None => (*transform, 0, 0.0),
+            };
+            let sprite = FlaggedSpriteRender {
+                sprite_sheet: sprite_render.sprite_sheet.clone(),
+                sprite_number: sprite_render.sprite_number,
+            };
+            sprites.push((row, sprite, transform, flipped, rgba, light));
+        }
+
+        // Drawn back to front, as the transparent edges of sprites blend over those behind
+        sprites.sort_by_key(|(row, ..)| *row);
+        for (_, sprite, transform, flipped, rgba, light) in &sprites {
+            self.batch.add_sprite(
+                sprite,
+                Some(transform),
+                *flipped,
+                *rgba,
+                *light,
+                &sprite_sheet_storage,
+                &tex_storage,
+            );
+        }
+
+        let camera_g = get_camera(active, &camera, &global);
+        self.batch.encode(
+            encoder,
+            &mut factory,
+            effect,
+            camera_g,
+            time.ambient(),
+            &sprite_sheet_storage,
+            &tex_storage,
+        );
+        self.batch.reset();
+    }
+}
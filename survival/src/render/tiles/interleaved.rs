@@ -7,8 +7,8 @@ use glsl_layout::Uniform;
 
 use amethyst::assets::{AssetStorage, Handle};
 use amethyst::core::{
-    ecs::prelude::{Read, ReadExpect, ReadStorage},
-    math::{Vector3, Vector4},
+    ecs::prelude::{Read, ReadExpect, ReadStorage, Write},
+    math::{Vector2, Vector3},
     transform::{Transform},
 };
 use amethyst::error::Error;
@@ -19,21 +19,47 @@ use amethyst::renderer::{
         pass::{Pass, PassData},
         DepthMode, Effect, NewEffect,
     },
-    ActiveCamera, Attributes, Camera, Color, DisplayConfig, Encoder, Factory, Flipped, Query,
-    Resources, Rgba, SpriteSheet, Texture, TextureHandle, VertexFormat,
+    ActiveCamera, Attributes, Camera, Color, Encoder, Factory, Flipped, Query, Resources, Rgba,
+    SpriteSheet, Texture, TextureHandle, VertexFormat,
 };
 
-use crate::components::{FlaggedSpriteRender, TilePosition};
-use crate::settings::Config;
-
-use super::util::{add_texture, default_transparency, set_view_args, setup_textures, ViewArgs};
+use crate::components::{
+    max_fluid_depth, ConstructionLayer, FlaggedSpriteRender, FloorLayer, FluidDepth, FluidLayer,
+    LightLevel, TerrainLayer, TileLayer, TileLayerSprite, TilePosition, Visibility,
+};
+use crate::decals::Decals;
+use crate::designations::Designation;
+use crate::fov::local_player;
+use crate::render::camera::view_bounds;
+use crate::settings::Context;
+use crate::systems::time::TimeState;
+
+use super::chunks::{chunk_size, ChunkBatch, ChunkKey, DirtyChunks};
+use super::util::{
+    default_transparency, set_ambient_args, set_view_args, setup_textures, AmbientArgs, ViewArgs,
+};
 use super::*;
 
 use crate::tiles::*;
-
-type Slice = gfx::Slice<Resources>;
-
-/// Draws sprites on a 2D quad.
+use std::collections::HashMap;
+
+/// Color multiplier for explored tiles the local player can't currently see.
+const explored_dimming: f32 = 0.4;
+/// Share of their color explored tiles out of sight lose towards grey.
+const explored_desaturation: f32 = 0.7;
+/// Color of the shallowest and deepest fluid, tinting the fluid layer by its depth.
+const shallow_fluid: (f32, f32, f32) = (0.6, 0.8, 1.0);
+const deep_fluid: (f32, f32, f32) = (0.1, 0.2, 0.6);
+/// Opacity of the overlay marking designated tiles.
+const designation_alpha: f32 = 0.5;
+/// Depth decals are drawn above the tile's layers, under the designation overlay.
+const decal_depth: f32 = 0.005;
+
+/// Draws the layers of the tiles in view, a chunk at a time. The sprite instances of each chunk
+/// are kept between frames, and only rebuilt once its tiles are in the `DirtyChunks`, or the
+/// view slice changes. The daylight and season grade them as they're drawn, through the ambient
+/// light, so the chunks needn't be rebuilt as the day goes by. The `Decals` of each chunk are
+/// kept in a batch of their own, drawn once the tiles of every chunk are, to blend over them.
 #[derive(Derivative, Clone, Debug)]
 #[derivative(Default(bound = "Self: Pass"))]
 pub struct DrawFlat2D {
@@ -41,6 +67,11 @@ pub struct DrawFlat2D {
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
     batch: TextureBatch,
     map_transform: Option<Transform>,
+    chunks: HashMap<ChunkKey, ChunkBatch>,
+    decal_batch: TextureBatch,
+    decal_chunks: HashMap<ChunkKey, ChunkBatch>,
+    /// The view slice, and levels peeked below it, the chunks were built for.
+    built_for: Option<(u32, u32)>,
 }
 
 impl DrawFlat2D
@@ -79,16 +110,14 @@ where
         self
     }
 
-    fn attributes() -> Attributes<'static> {
-        <SpriteInstance as Query<(DirX, DirY, Pos, OffsetU, OffsetV, Depth, Color)>>::QUERIED_ATTRIBUTES
+    pub(super) fn attributes() -> Attributes<'static> {
+        <SpriteInstance as Query<(DirX, DirY, Pos, OffsetU, OffsetV, Depth, Color, Light)>>::QUERIED_ATTRIBUTES
     }
 }
 
 #[allow(clippy::type_complexity)]
 impl<'a> PassData<'a> for DrawFlat2D {
     type Data = (
-        Read<'a, Config>,
-        Read<'a, DisplayConfig>,
         Read<'a, ActiveCamera>,
         ReadStorage<'a, Camera>,
         ReadStorage<'a, TilePosition>,
@@ -97,10 +126,22 @@ impl<'a> PassData<'a> for DrawFlat2D {
         ReadStorage<'a, Transform>,
         ReadStorage<'a, Transform>,
         ReadExpect<'a, Tiles>,
-        ReadTiles<'a, FlaggedSpriteRender>,
+        ReadTiles<'a, TerrainLayer>,
+        ReadTiles<'a, FloorLayer>,
+        ReadTiles<'a, ConstructionLayer>,
+        ReadTiles<'a, FluidLayer>,
+        ReadTiles<'a, FluidDepth>,
         ReadTiles<'a, Flipped>,
         ReadTiles<'a, Rgba>,
         ReadTiles<'a, Transform>,
+        ReadTiles<'a, Visibility>,
+        ReadTiles<'a, Designation>,
+        ReadTiles<'a, LightLevel>,
+        ReadTiles<'a, Decals>,
+        ReadExpect<'a, Context>,
+        Read<'a, ViewZ>,
+        Read<'a, TimeState>,
+        Write<'a, DirtyChunks>,
     );
 }
 
@@ -116,6 +157,11 @@ impl Pass for DrawFlat2D {
                 mem::size_of::<<ViewArgs as Uniform>::Std140>(),
                 1,
             )
+            .with_raw_constant_buffer(
+                "AmbientArgs",
+                mem::size_of::<<AmbientArgs as Uniform>::Std140>(),
+                1,
+            )
             .with_raw_vertex_buffer(Self::attributes(), SpriteInstance::size() as ElemStride, 1);
         setup_textures(&mut builder, &TEXTURES);
         match self.transparency {
@@ -135,8 +181,6 @@ impl Pass for DrawFlat2D {
         effect: &mut Effect,
         mut factory: Factory,
         (
-            game_settings,
-            display_config,
             active,
             camera,
             _,
@@ -145,78 +189,272 @@ impl Pass for DrawFlat2D {
             global,
             _local,
             tiles,
-            tiles_sprites,
+            tiles_terrain,
+            tiles_floor,
+            tiles_construction,
+            tiles_fluid,
+            tiles_fluid_depth,
             tiles_flipped,
             tiles_rgba,
             tile_globals,
+            tiles_visibility,
+            tiles_designation,
+            tiles_light,
+            tiles_decals,
+            context,
+            view_z,
+            time,
+            mut dirty,
         ): <Self as PassData<'a>>::Data,
     ) {
         let camera_g = get_camera(active, &camera, &global);
 
-        let (_, g) = camera_g.as_ref().unwrap();
-        let camera_world_position = g.translation();
-        let camera_tile_position =
-            tiles.world_to_tile(&camera_world_position.xyz(), &game_settings);
-        //let (_, camera_tile_position) = (&camera, &tile_positions).join().next().unwrap();
-
-        //let translation: amethyst::core::math::Translation3<f32> = amethyst::core::math::convert(transform);
-
-        // Calculate the scale of how much we can view...from...what?
-        // this should be resolution / (tile width * scale(
-        // TODO: dont hardcode the tileset size multiplier, this should be stored in Tiles
-        let view_tiles =
-            display_config.dimensions.unwrap().0 as f32 / (16. * game_settings.graphics.scale); // Hardcoded for now, these should be out of the sprites and into the Tiles object
-
-        let view_x = (camera_tile_position.x as f32 - view_tiles - 16.)
-            .max(0.)
-            .min(tiles.dimensions().x as f32) as u32;
-        let view_y = (camera_tile_position.y as f32 - view_tiles - 16.)
-            .max(0.)
-            .min(tiles.dimensions().y as f32) as u32;
-
-        let view_e_x = (camera_tile_position.x as f32 + view_tiles)
-            .max(0.)
-            .min(tiles.dimensions().x as f32) as u32;
-        let view_e_y = (camera_tile_position.y as f32 + view_tiles)
-            .max(0.)
-            .min(tiles.dimensions().y as f32) as u32;
-
-        //println!("Viewing: camera=({}, {}), {}, {}, {}, {}", camera_tile_position.x, camera_tile_position.y, view_x, view_y, view_e_x, view_e_y);
-        //println!("World: {:?}", camera_world_position);
-        // TODO: we should scale this to viewport from teh camera
-        for tile_id in tiles.iter_region(Vector4::new(view_x, view_y, view_e_x, view_e_y), 0) {
-            let sprite_render = tiles_sprites.get(tile_id);
-            if sprite_render.is_none() {
-                continue;
+        let (view_min, view_max) = match view_bounds(camera_g)
+            .and_then(|(min, max)| visible_tiles(*tiles, &tile_globals, &view_z, min, max))
+        {
+            Some(view) => view,
+            None => return,
+        };
+
+        // Drop the chunks built for another slice, or whose tiles have changed since
+        let built_for = (view_z.z, view_z.peek_levels);
+        if self.built_for != Some(built_for) {
+            self.built_for = Some(built_for);
+            self.chunks.clear();
+            self.decal_chunks.clear();
+        }
+        // Tiles show through the empty tiles of the levels above them it peeks down from
+        for (x, y, z) in dirty.0.drain() {
+            for above in z..=z + view_z.peek_levels {
+                self.chunks.remove(&(x, y, above));
+                self.decal_chunks.remove(&(x, y, above));
+            }
+        }
+
+        // Sprite vertex shader
+        set_view_args(effect, encoder, camera_g);
+        // Sprite fragment shader
+        set_ambient_args(effect, encoder, time.ambient());
+
+        let dimensions = tiles.dimensions();
+        let (first_x, first_y) = (view_min.x / chunk_size, view_min.y / chunk_size);
+        let (last_x, last_y) = (view_max.x / chunk_size, view_max.y / chunk_size);
+        let z = view_max.z;
+        let keys = (first_y..=last_y)
+            .flat_map(|y| (first_x..=last_x).map(move |x| (x, y, z)))
+            .collect::<Vec<ChunkKey>>();
+        for key in &keys {
+            if !self.chunks.contains_key(key) {
+                let (chunk_x, chunk_y, z) = *key;
+                let chunk_min = Vector3::new(chunk_x * chunk_size, chunk_y * chunk_size, z);
+                let chunk_max = Vector3::new(
+                    (chunk_min.x + chunk_size).min(dimensions.x) - 1,
+                    (chunk_min.y + chunk_size).min(dimensions.y) - 1,
+                    z,
+                );
+                for column in tiles.iter_volume(chunk_min, chunk_max) {
+                    // Empty tiles of the slice show the first filled tile below them
+                    let coord = tiles.coord(column);
+                    let filled = |id| {
+                        tiles_terrain.get(id).is_some()
+                            || tiles_floor.get(id).is_some()
+                            || tiles_construction.get(id).is_some()
+                            || tiles_fluid.get(id).is_some()
+                    };
+                    let (tile_id, depth) = match view_z.peek(*tiles, coord.x, coord.y, filled) {
+                        Some(peeked) => peeked,
+                        None => continue,
+                    };
+                    let global = match tile_globals.get(tile_id) {
+                        Some(global) => global,
+                        None => continue,
+                    };
+                    // Skip unexplored tiles, and fog those explored but out of sight
+                    let visibility = tiles_visibility.get(tile_id).cloned().unwrap_or_default();
+                    if !visibility.is_explored(local_player) {
+                        continue;
+                    }
+                    let fog = |rgba| {
+                        if visibility.is_visible(local_player) {
+                            rgba
+                        } else {
+                            fog_tint(rgba)
+                        }
+                    };
+
+                    let flipped = tiles_flipped.get(tile_id).unwrap_or(&Flipped::None);
+                    let rgba = *tiles_rgba.get(tile_id).unwrap_or(&Rgba::WHITE);
+                    let tint = view_z.tint(depth);
+                    let rgba = Rgba(rgba.0 * tint, rgba.1 * tint, rgba.2 * tint, rgba.3);
+                    let light = tiles_light.get(tile_id).map_or(0.0, |light| light.0);
+
+                    // Composite the layers bottom up
+                    let layers = [
+                        tiles_terrain.get(tile_id).map(TileLayerSprite::sprite),
+                        tiles_floor.get(tile_id).map(TileLayerSprite::sprite),
+                        tiles_construction.get(tile_id).map(TileLayerSprite::sprite),
+                        tiles_fluid.get(tile_id).map(TileLayerSprite::sprite),
+                    ];
+                    for (layer, sprite_render) in TileLayer::ALL.iter().zip(layers.iter()) {
+                        if let Some(sprite_render) = sprite_render {
+                            let mut transform = *global;
+                            transform.0[(2, 3)] += layer.depth_offset();
+
+                            let rgba = match (layer, tiles_fluid_depth.get(tile_id)) {
+                                (TileLayer::Fluid, Some(depth)) => fluid_tint(rgba, depth.0),
+                                _ => rgba,
+                            };
+                            let rgba = fog(rgba);
+                            self.batch.add_sprite(
+                                sprite_render,
+                                Some(&transform),
+                                Some(flipped),
+                                Some(&rgba),
+                                light,
+                                &sprite_sheet_storage,
+                                &tex_storage,
+                            );
+                        }
+                    }
+
+                    // Designated tiles are overlaid with their terrain, tinted by the work ordered
+                    if let (Some(designation), Some(terrain)) =
+                        (tiles_designation.get(tile_id), tiles_terrain.get(tile_id))
+                    {
+                        let mut transform = *global;
+                        transform.0[(2, 3)] += TileLayer::Fluid.depth_offset() + 0.01;
+                        self.batch.add_sprite(
+                            terrain.sprite(),
+                            Some(&transform),
+                            Some(flipped),
+                            Some(&fog(designation_tint(rgba, *designation))),
+                            light,
+                            &sprite_sheet_storage,
+                            &tex_storage,
+                        );
+                    }
+
+                    if let (Some(decals), Some(sprite_sheet)) =
+                        (tiles_decals.get(tile_id), &context.spritesheet)
+                    {
+                        let mut transform = *global;
+                        transform.0[(2, 3)] += TileLayer::Fluid.depth_offset() + decal_depth;
+                        for decal in &decals.0 {
+                            let sprite = FlaggedSpriteRender {
+                                sprite_sheet: sprite_sheet.clone(),
+                                sprite_number: decal.sprite_number(),
+                            };
+                            let color = decal.color();
+                            let rgba = Rgba(
+                                rgba.0 * color.0,
+                                rgba.1 * color.1,
+                                rgba.2 * color.2,
+                                rgba.3 * color.3,
+                            );
+                            self.decal_batch.add_sprite(
+                                &sprite,
+                                Some(&transform),
+                                Some(flipped),
+                                Some(&fog(rgba)),
+                                light,
+                                &sprite_sheet_storage,
+                                &tex_storage,
+                            );
+                        }
+                    }
+                }
+                let chunk = self.batch.upload(&mut factory, &sprite_sheet_storage);
+                self.batch.reset();
+                self.chunks.insert(*key, chunk);
+                let decals = self.decal_batch.upload(&mut factory, &sprite_sheet_storage);
+                self.decal_batch.reset();
+                self.decal_chunks.insert(*key, decals);
             }
-            let sprite_render = sprite_render.as_ref().unwrap();
-
-            let flipped = tiles_flipped.get(tile_id).unwrap_or(&Flipped::None);
-            let rgba = tiles_rgba.get(tile_id).unwrap_or(&Rgba::WHITE);
-
-            let global = tile_globals.get(tile_id).unwrap();
-
-            self.batch.add_sprite(
-                sprite_render,
-                Some(&global),
-                Some(flipped),
-                Some(rgba),
-                &sprite_sheet_storage,
-                &tex_storage,
-            );
-            //self.batch.sort();
+            self.chunks[key].draw(encoder, effect, &tex_storage);
         }
+        // Decals blend over the tiles of every chunk in view, so are drawn after all of them
+        for key in &keys {
+            self.decal_chunks[key].draw(encoder, effect, &tex_storage);
+        }
+    }
+}
+
+/// The lowest and highest tiles of the slice in view, and the levels peeked below it, drawn
+/// within the world rectangle from `view_min` to `view_max`. The tile grid is taken from where
+/// the first tiles of the slice are placed, each drawn centred on its transform across a step of
+/// the grid. None if no tile is in view.
+fn visible_tiles(
+    tiles: Tiles,
+    tile_globals: &ReadTiles<'_, Transform>,
+    view_z: &ViewZ,
+    view_min: Vector2<f32>,
+    view_max: Vector2<f32>,
+) -> Option<(Vector3<u32>, Vector3<u32>)> {
+    let dimensions = tiles.dimensions();
+    let z = view_z.z.min(dimensions.z - 1);
+    let position = |x, y| {
+        tile_globals
+            .get(tiles.id(x, y, z))
+            .map(|global| Vector2::new(global.translation().x, global.translation().y))
+    };
+    let origin = position(0, 0)?;
+    let step_x = position(1.min(dimensions.x - 1), 0)?.x - origin.x;
+    let step_y = position(0, 1.min(dimensions.y - 1))?.y - origin.y;
+
+    let (first_x, last_x) = span(view_min.x, view_max.x, origin.x, step_x, dimensions.x)?;
+    let (first_y, last_y) = span(view_min.y, view_max.y, origin.y, step_y, dimensions.y)?;
+    Some((
+        Vector3::new(first_x, first_y, z.saturating_sub(view_z.peek_levels)),
+        Vector3::new(last_x, last_y, z),
+    ))
+}
 
-        self.batch.encode(
-            encoder,
-            &mut factory,
-            effect,
-            camera_g,
-            &sprite_sheet_storage,
-            &tex_storage,
-        );
-        self.batch.reset();
+/// The first and last of `len` tiles placed `step` apart from `origin` overlapping `min..max`
+/// along an axis. A single tile is taken to be in view wherever it is.
+fn span(min: f32, max: f32, origin: f32, step: f32, len: u32) -> Option<(u32, u32)> {
+    if step.abs() < std::f32::EPSILON {
+        return Some((0, len - 1));
+    }
+    // In steps from the first tile, which may run backwards, as rows do down the screen
+    let (a, b) = ((min - origin) / step, (max - origin) / step);
+    let (low, high) = (a.min(b), a.max(b));
+    // Tile `i` covers from half a step before to half a step after `i`
+    let first = (low - 0.5).floor() + 1.;
+    let last = (high + 0.5).ceil() - 1.;
+    if last < 0. || first >= len as f32 || first > last {
+        return None;
     }
+    Some((first.max(0.) as u32, last.min(len as f32 - 1.) as u32))
+}
+
+/// `rgba` greyed and darkened, as the local player remembers a tile out of their sight.
+fn fog_tint(rgba: Rgba) -> Rgba {
+    let grey = 0.3 * rgba.0 + 0.59 * rgba.1 + 0.11 * rgba.2;
+    let fade = |c: f32| (c + (grey - c) * explored_desaturation) * explored_dimming;
+    Rgba(fade(rgba.0), fade(rgba.1), fade(rgba.2), rgba.3)
+}
+
+/// `rgba` shaded from light to dark blue as fluid deepens.
+fn fluid_tint(rgba: Rgba, depth: u8) -> Rgba {
+    let t = f32::from(depth.min(max_fluid_depth)) / f32::from(max_fluid_depth);
+    let mix = |shallow: f32, deep: f32| shallow + (deep - shallow) * t;
+    Rgba(
+        rgba.0 * mix(shallow_fluid.0, deep_fluid.0),
+        rgba.1 * mix(shallow_fluid.1, deep_fluid.1),
+        rgba.2 * mix(shallow_fluid.2, deep_fluid.2),
+        rgba.3,
+    )
+}
+
+/// `rgba` tinted by the color marking `designation`.
+fn designation_tint(rgba: Rgba, designation: Designation) -> Rgba {
+    let (r, g, b) = match designation {
+        Designation::ChopTree => (0.4, 1.0, 0.3),
+        Designation::Mine => (1.0, 0.8, 0.2),
+        Designation::GatherPlants => (0.9, 0.4, 1.0),
+        Designation::Deconstruct => (1.0, 0.3, 0.2),
+    };
+    Rgba(rgba.0 * r, rgba.1 * g, rgba.2 * b, designation_alpha)
 }
 
 #[derive(Clone, Debug)]
@@ -226,6 +464,7 @@ enum TextureDrawData {
         render: FlaggedSpriteRender,
         flipped: Option<Flipped>,
         rgba: Option<Rgba>,
+        light: f32,
         transform: Transform,
     },
     Image {
@@ -233,6 +472,7 @@ enum TextureDrawData {
         transform: Transform,
         flipped: Option<Flipped>,
         rgba: Option<Rgba>,
+        light: f32,
         width: usize,
         height: usize,
     },
@@ -260,11 +500,19 @@ impl TextureDrawData {
             }
         }
     }
+
+    pub fn light(&self) -> f32 {
+        match self {
+            TextureDrawData::Image { light, .. } | TextureDrawData::Sprite { light, .. } => *light,
+        }
+    }
 }
 
 #[derive(Clone, Default, Debug)]
-struct TextureBatch {
+pub(super) struct TextureBatch {
     textures: Vec<TextureDrawData>,
+    /// Vertex buffer `encode` writes the instances of each frame into, grown as more are drawn.
+    buffer: Option<gfx::handle::Buffer<Resources, f32>>,
 }
 
 impl TextureBatch {
@@ -274,6 +522,7 @@ impl TextureBatch {
         global: Option<&Transform>,
         flipped: Option<&Flipped>,
         rgba: Option<&Rgba>,
+        light: f32,
         tex_storage: &AssetStorage<Texture>,
     ) {
         let global = match global {
@@ -296,6 +545,7 @@ impl TextureBatch {
             transform: *global,
             flipped: flipped.cloned(),
             rgba: rgba.cloned(),
+            light,
             width: texture_dims.0,
             height: texture_dims.1,
         });
@@ -307,6 +557,7 @@ impl TextureBatch {
         global: Option<&Transform>,
         flipped: Option<&Flipped>,
         rgba: Option<&Rgba>,
+        light: f32,
         sprite_sheet_storage: &AssetStorage<SpriteSheet>,
         tex_storage: &AssetStorage<Texture>,
     ) {
@@ -344,6 +595,7 @@ impl TextureBatch {
             render: sprite_render.clone(),
             flipped: flipped.cloned(),
             rgba: rgba.cloned(),
+            light,
             transform: *global,
         });
     }
@@ -354,20 +606,19 @@ impl TextureBatch {
         self.textures.sort_by(|a, b| a.tex_id().cmp(&b.tex_id()));
     }
 
+    /// Draws the sprites added through the vertex buffer kept for them, sorted so those sharing a
+    /// texture are drawn in one instanced draw.
     pub fn encode(
-        &self,
+        &mut self,
         encoder: &mut Encoder,
         factory: &mut Factory,
         effect: &mut Effect,
         camera: Option<(&Camera, &Transform)>,
+        ambient: (f32, f32, f32),
         sprite_sheet_storage: &AssetStorage<SpriteSheet>,
         tex_storage: &AssetStorage<Texture>,
     ) {
-        use gfx::{
-            buffer,
-            memory::{Bind, Typed},
-            Factory,
-        };
+        use gfx::{buffer, memory, Factory};
 
         if self.textures.is_empty() {
             return;
@@ -375,22 +626,70 @@ impl TextureBatch {
 
         // Sprite vertex shader
         set_view_args(effect, encoder, camera);
+        // Sprite fragment shader
+        set_ambient_args(effect, encoder, ambient);
+
+        self.sort();
+        let (instance_data, runs) = self.instances(sprite_sheet_storage);
+        // The buffer is only replaced once the frame's sprites outgrow it
+        let vbuf = match self.buffer.take() {
+            Some(vbuf) if vbuf.len() >= instance_data.len() => vbuf,
+            _ => factory
+                .create_buffer(
+                    instance_data.len().next_power_of_two(),
+                    buffer::Role::Vertex,
+                    memory::Usage::Dynamic,
+                    memory::Bind::TRANSFER_DST,
+                )
+                .expect("Unable to create dynamic buffer for `TextureBatch`"),
+        };
+        encoder
+            .update_buffer(&vbuf, &instance_data, 0)
+            .expect("Unable to update dynamic buffer for `TextureBatch`");
+
+        let batch = ChunkBatch {
+            buffer: Some(vbuf),
+            runs,
+        };
+        batch.draw(encoder, effect, tex_storage);
+        self.buffer = batch.buffer;
+    }
+
+    /// Uploads the instances of the sprites added, in runs of those sharing a texture, into a
+    /// vertex buffer which can be drawn as many times as needed.
+    pub fn upload(
+        &self,
+        factory: &mut Factory,
+        sprite_sheet_storage: &AssetStorage<SpriteSheet>,
+    ) -> ChunkBatch {
+        use gfx::{buffer, memory::Bind, Factory};
 
-        // We might be able to improve performance here if we
-        // preallocate the maximum needed capacity. We need to
-        // iterate over the sprites though to find out the longest
-        // chain of sprites with the same texture, so we would need
-        // to check if it actually results in an improvement over just
-        // doing the allocations.
+        if self.textures.is_empty() {
+            return ChunkBatch::default();
+        }
+
+        let (instance_data, runs) = self.instances(sprite_sheet_storage);
+        let vbuf = factory
+            .create_buffer_immutable(&instance_data, buffer::Role::Vertex, Bind::empty())
+            .expect("Unable to create immutable buffer for `TextureBatch`");
+        ChunkBatch {
+            buffer: Some(vbuf),
+            runs,
+        }
+    }
+
+    /// The instance data of the sprites added, in the order added, and the runs of them sharing
+    /// a texture, by the first instance of each and how many there are.
+    fn instances(
+        &self,
+        sprite_sheet_storage: &AssetStorage<SpriteSheet>,
+    ) -> (Vec<f32>, Vec<(TextureHandle, u32, u32)>) {
         let mut instance_data = Vec::<f32>::new();
-        let mut num_instances = 0;
+        let mut runs = Vec::new();
+        let mut first = 0;
         let num_quads = self.textures.len();
 
         for (i, quad) in self.textures.iter().enumerate() {
-            let texture = tex_storage
-                .get(&quad.texture_handle())
-                .expect("Unable to get texture of sprite");
-
             let (flip_horizontal, flip_vertical) = match quad.flipped() {
                 Some(Flipped::Horizontal) => (true, false),
                 Some(Flipped::Vertical) => (false, true),
@@ -471,45 +770,20 @@ impl TextureBatch {
             let rgba = rgba.unwrap_or(Rgba::WHITE);
             instance_data.extend(&[
                 0, 0, 0, 0, pos.x.into(), pos.y.into(), uv_left, uv_right, uv_bottom,
-                uv_top, pos.z.into(), rgba.0, rgba.1, rgba.2, rgba.3,
+                uv_top, pos.z.into(), rgba.0, rgba.1, rgba.2, rgba.3, quad.light(),
             ]);
-            num_instances += 1;
 
-            // Need to flush outstanding draw calls due to state switch (texture).
-            //
-            // 1. We are at the last sprite and want to submit all pending work.
-            // 2. The next sprite will use a different texture triggering a flush.
+            // End the run when the texture changes, or at the last sprite.
             let need_flush = i >= num_quads - 1
                 || self.textures[i + 1].texture_handle().id() != quad.texture_handle().id();
 
             if need_flush {
-                add_texture(effect, texture);
-
-                let vbuf = factory
-                    .create_buffer_immutable(&instance_data, buffer::Role::Vertex, Bind::empty())
-                    .expect("Unable to create immutable buffer for `TextureBatch`");
-
-                for _ in DrawFlat2D::attributes() {
-                    effect.data.vertex_bufs.push(vbuf.raw().clone());
-                }
-
-                effect.draw(
-                    &Slice {
-                        start: 0,
-                        end: 6,
-                        base_vertex: 0,
-                        instances: Some((num_instances, 0)),
-                        buffer: Default::default(),
-                    },
-                    encoder,
-                );
-
-                effect.clear();
-
-                num_instances = 0;
-                instance_data.clear();
+                let end = i as u32 + 1;
+                runs.push((quad.texture_handle().clone(), first, end - first));
+                first = end;
             }
         }
+        (instance_data, runs)
     }
 
     pub fn reset(&mut self) {
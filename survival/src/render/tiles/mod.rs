@@ -1,6 +1,14 @@
+pub use self::chunks::{chunk_of, chunk_size, ChunkKey, DirtyChunks};
+pub use self::entities::DrawEntities as EntityPass;
 pub use self::interleaved::DrawFlat2D as Pass;
+pub use self::precipitation::DrawPrecipitation as PrecipitationPass;
+pub use self::selection::DrawSelection as SelectionPass;
 
+mod chunks;
+mod entities;
 mod interleaved;
+mod precipitation;
+mod selection;
 mod util;
 
 use gfx::{
@@ -73,6 +81,15 @@ impl Attribute for Depth {
     type Repr = f32;
 }
 
+#[derive(Clone, Debug)]
+enum Light {}
+impl Attribute for Light {
+    const NAME: &'static str = "light";
+    const FORMAT: Format = Format(SurfaceType::R32, ChannelType::Float);
+    const SIZE: u32 = 4;
+    type Repr = f32;
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 struct SpriteInstance {
@@ -83,6 +100,7 @@ struct SpriteInstance {
     pub v_offset: [f32; 2],
     pub depth: f32,
     pub color: [f32; 4],
+    pub light: f32,
 }
 
 unsafe impl Pod for SpriteInstance {}
@@ -96,6 +114,7 @@ impl VertexFormat for SpriteInstance {
         (OffsetV::NAME, <Self as With<OffsetV>>::FORMAT),
         (Depth::NAME, <Self as With<Depth>>::FORMAT),
         (Color::NAME, <Self as With<Color>>::FORMAT),
+        (Light::NAME, <Self as With<Light>>::FORMAT),
     ];
 }
 
@@ -147,3 +166,16 @@ impl With<Color> for SpriteInstance {
         format: Color::FORMAT,
     };
 }
+
+impl With<Light> for SpriteInstance {
+    const FORMAT: AttributeFormat = Element {
+        offset: DirX::SIZE
+            + DirY::SIZE
+            + Pos::SIZE
+            + OffsetU::SIZE
+            + OffsetV::SIZE
+            + Depth::SIZE
+            + Color::SIZE,
+        format: Light::FORMAT,
+    };
+}
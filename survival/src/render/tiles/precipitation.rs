@@ -0,0 +1,220 @@
+//! Overlay pass drawing the rain or snow of the `Weather` falling over the view.
+
+use derivative::Derivative;
+use gfx::pso::buffer::ElemStride;
+use gfx_core::state::{Blend, ColorMask};
+use glsl_layout::Uniform;
+
+use amethyst::assets::AssetStorage;
+use amethyst::core::{
+    ecs::prelude::{Read, ReadExpect, ReadStorage},
+    math::Vector3,
+    timing::Time,
+    transform::Transform,
+};
+use amethyst::error::Error;
+
+use amethyst::renderer::{
+    get_camera,
+    pipe::{
+        pass::{Pass, PassData},
+        DepthMode, Effect, NewEffect,
+    },
+    ActiveCamera, Camera, DisplayConfig, Encoder, Factory, Rgba, SpriteSheet, Texture,
+    VertexFormat,
+};
+
+use crate::components::FlaggedSpriteRender;
+use crate::settings::{Config, Context};
+use crate::systems::time::TimeState;
+use crate::tiles::{Tiles, ViewZ};
+use crate::weather::{Precipitation, Weather};
+
+use super::interleaved::{DrawFlat2D, TextureBatch};
+use super::util::{default_transparency, setup_textures, AmbientArgs, ViewArgs};
+use super::*;
+
+/// Particles falling over the view at the heaviest rain or snow.
+const max_particles: usize = 400;
+/// Sprites of raindrops and snowflakes, as indices into the cp437 sheet.
+const rain_sprite_number: usize = 124;
+const snow_sprite_number: usize = 42;
+/// World units a second raindrops and snowflakes fall.
+const rain_speed: f32 = 600.0;
+const snow_speed: f32 = 80.0;
+/// World units a second particles drift sideways, at the strongest wind.
+const wind_drift: f32 = 200.0;
+/// Color of raindrops and snowflakes.
+const rain_color: Rgba = Rgba(0.6, 0.7, 1.0, 0.6);
+const snow_color: Rgba = Rgba(1.0, 1.0, 1.0, 0.9);
+/// Depth particles are drawn above the current slice, over all its layers.
+const precipitation_depth: f32 = 0.1;
+
+/// Draws rain or snow over the view, as sprites scattered about the camera which fall, and
+/// drift with the wind, as real time passes. Nothing is drawn in dry weather.
+#[derive(Derivative, Clone, Debug)]
+#[derivative(Default(bound = "Self: Pass"))]
+pub struct DrawPrecipitation {
+    #[derivative(Default(value = "default_transparency()"))]
+    transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    batch: TextureBatch,
+}
+
+impl DrawPrecipitation
+where
+    Self: Pass,
+{
+    /// Create instance of `DrawPrecipitation` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+#[allow(clippy::type_complexity)]
+impl<'a> PassData<'a> for DrawPrecipitation {
+    type Data = (
+        Read<'a, Config>,
+        Read<'a, DisplayConfig>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, Transform>,
+        Read<'a, AssetStorage<SpriteSheet>>,
+        Read<'a, AssetStorage<Texture>>,
+        ReadExpect<'a, Context>,
+        ReadExpect<'a, Tiles>,
+        Read<'a, ViewZ>,
+        Read<'a, Time>,
+        Read<'a, Weather>,
+        Read<'a, TimeState>,
+    );
+}
+
+impl Pass for DrawPrecipitation {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
+        use std::mem;
+
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder
+            .without_back_face_culling()
+            .with_raw_constant_buffer(
+                "ViewArgs",
+                mem::size_of::<<ViewArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_constant_buffer(
+                "AmbientArgs",
+                mem::size_of::<<AmbientArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_vertex_buffer(
+                DrawFlat2D::attributes(),
+                SpriteInstance::size() as ElemStride,
+                1,
+            );
+        setup_textures(&mut builder, &TEXTURES);
+        match self.transparency {
+            Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
+            None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
+        };
+
+        builder.build()
+    }
+
+    #[allow(clippy::extra_unused_lifetimes)]
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            game_settings,
+            display_config,
+            active,
+            camera,
+            global,
+            sprite_sheet_storage,
+            tex_storage,
+            context,
+            tiles,
+            view_z,
+            time,
+            weather,
+            time_state,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let (sprite_number, speed, rgba) = match weather.precipitation {
+            Precipitation::Rain => (rain_sprite_number, rain_speed, rain_color),
+            Precipitation::Snow => (snow_sprite_number, snow_speed, snow_color),
+            Precipitation::None => return,
+        };
+        let sprite = match &context.spritesheet {
+            Some(sprite_sheet) => FlaggedSpriteRender {
+                sprite_sheet: sprite_sheet.clone(),
+                sprite_number,
+            },
+            None => return,
+        };
+        let camera_g = get_camera(active, &camera, &global);
+        let (centre_x, centre_y) = match camera_g.as_ref() {
+            Some((_, g)) => (g.translation().x, g.translation().y),
+            None => return,
+        };
+
+        let (width, height) = display_config
+            .dimensions
+            .map_or((1024., 768.), |(w, h)| (w as f32, h as f32));
+        let depth = tiles
+            .tile_to_world(Vector3::new(0, 0, view_z.z), &game_settings)
+            .z
+            + precipitation_depth;
+        // North is towards lower tile `y`, which is higher in the world
+        let (wind_x, wind_y) = weather.wind.offset();
+        let drift = weather.wind_speed * wind_drift;
+        let elapsed = time.absolute_time_seconds() as f32;
+
+        let count = (weather.intensity * max_particles as f32) as usize;
+        for i in 0..count {
+            let fall = speed * (0.8 + 0.4 * scatter(i, 2.0));
+            let x = scatter(i, 0.0) * width + wind_x as f32 * drift * elapsed;
+            let y = scatter(i, 1.0) * height - (fall + wind_y as f32 * drift) * elapsed;
+
+            let mut transform = Transform::default();
+            transform.set_translation(Vector3::new(
+                centre_x - width / 2. + wrap(x, width),
+                centre_y - height / 2. + wrap(y, height),
+                depth,
+            ));
+            self.batch.add_sprite(
+                &sprite,
+                Some(&transform),
+                None,
+                Some(&rgba),
+                0.0,
+                &sprite_sheet_storage,
+                &tex_storage,
+            );
+        }
+
+        self.batch.encode(
+            encoder,
+            &mut factory,
+            effect,
+            camera_g,
+            time_state.ambient(),
+            &sprite_sheet_storage,
+            &tex_storage,
+        );
+        self.batch.reset();
+    }
+}
+
+/// A fixed pseudo random `0.0..1.0` for particle `i`, different for each `salt`.
+fn scatter(i: usize, salt: f32) -> f32 {
+    let v = ((i as f32 + salt * 101.0) * 12.9898).sin() * 43_758.547;
+    v - v.floor()
+}
+
+/// `v` wrapped around into `0.0..len`.
+fn wrap(v: f32, len: f32) -> f32 {
+    v - (v / len).floor() * len
+}
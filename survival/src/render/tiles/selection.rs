@@ -0,0 +1,169 @@
+//! Overlay pass highlighting the tile under the mouse, and the rectangle being dragged out.
+
+use derivative::Derivative;
+use gfx::pso::buffer::ElemStride;
+use gfx_core::state::{Blend, ColorMask};
+use glsl_layout::Uniform;
+
+use amethyst::assets::AssetStorage;
+use amethyst::core::{
+    ecs::prelude::{Read, ReadExpect, ReadStorage},
+    math::Vector3,
+    transform::Transform,
+};
+use amethyst::error::Error;
+
+use amethyst::renderer::{
+    get_camera,
+    pipe::{
+        pass::{Pass, PassData},
+        DepthMode, Effect, NewEffect,
+    },
+    ActiveCamera, Camera, Encoder, Factory, Rgba, SpriteSheet, Texture, VertexFormat,
+};
+
+use crate::components::{FlaggedSpriteRender, TileLayer};
+use crate::selection::Selection;
+use crate::settings::Context;
+use crate::tiles::{ReadTiles, Tiles};
+
+use super::interleaved::{DrawFlat2D, TextureBatch};
+use super::util::{default_transparency, setup_textures, AmbientArgs, ViewArgs};
+use super::*;
+
+/// Sprite the highlights are drawn with, as an index into the cp437 sheet: a full block.
+const highlight_sprite_number: usize = 219;
+/// Color of the highlight of the tile under the mouse, and of the tiles being dragged over.
+const hover_color: Rgba = Rgba(1.0, 1.0, 0.6, 0.35);
+const region_color: Rgba = Rgba(0.4, 0.7, 1.0, 0.3);
+/// Depth the highlights are drawn above the tile, over all its layers and its designation.
+const highlight_depth: f32 = 0.02;
+
+/// Draws the `Selection`: a highlight over the tile under the mouse, and over every tile of the
+/// rectangle being dragged out. Highlights are drawn fully lit, whatever the daylight.
+#[derive(Derivative, Clone, Debug)]
+#[derivative(Default(bound = "Self: Pass"))]
+pub struct DrawSelection {
+    #[derivative(Default(value = "default_transparency()"))]
+    transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    batch: TextureBatch,
+}
+
+impl DrawSelection
+where
+    Self: Pass,
+{
+    /// Create instance of `DrawSelection` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+#[allow(clippy::type_complexity)]
+impl<'a> PassData<'a> for DrawSelection {
+    type Data = (
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, Transform>,
+        Read<'a, AssetStorage<SpriteSheet>>,
+        Read<'a, AssetStorage<Texture>>,
+        ReadExpect<'a, Context>,
+        ReadExpect<'a, Tiles>,
+        ReadTiles<'a, Transform>,
+        Read<'a, Selection>,
+    );
+}
+
+impl Pass for DrawSelection {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
+        use std::mem;
+
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder
+            .without_back_face_culling()
+            .with_raw_constant_buffer(
+                "ViewArgs",
+                mem::size_of::<<ViewArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_constant_buffer(
+                "AmbientArgs",
+                mem::size_of::<<AmbientArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_vertex_buffer(
+                DrawFlat2D::attributes(),
+                SpriteInstance::size() as ElemStride,
+                1,
+            );
+        setup_textures(&mut builder, &TEXTURES);
+        match self.transparency {
+            Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
+            None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
+        };
+
+        builder.build()
+    }
+
+    #[allow(clippy::extra_unused_lifetimes)]
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            active,
+            camera,
+            global,
+            sprite_sheet_storage,
+            tex_storage,
+            context,
+            tiles,
+            tile_globals,
+            selection,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let sprite = match &context.spritesheet {
+            Some(sprite_sheet) => FlaggedSpriteRender {
+                sprite_sheet: sprite_sheet.clone(),
+                sprite_number: highlight_sprite_number,
+            },
+            None => return,
+        };
+
+        let mut highlight = |coord: Vector3<u32>, rgba: Rgba| {
+            if let Some(global) = tile_globals.get(tiles.id_from_vector(coord)) {
+                let mut transform = *global;
+                transform.0[(2, 3)] += TileLayer::Fluid.depth_offset() + highlight_depth;
+                self.batch.add_sprite(
+                    &sprite,
+                    Some(&transform),
+                    None,
+                    Some(&rgba),
+                    1.0,
+                    &sprite_sheet_storage,
+                    &tex_storage,
+                );
+            }
+        };
+        if let Some((min, max)) = selection.dragged() {
+            for id in tiles.iter_volume(min, max) {
+                highlight(tiles.coord(id), region_color);
+            }
+        } else if let Some(hover) = selection.hover {
+            highlight(hover, hover_color);
+        }
+
+        let camera_g = get_camera(active, &camera, &global);
+        self.batch.encode(
+            encoder,
+            &mut factory,
+            effect,
+            camera_g,
+            (1.0, 1.0, 1.0),
+            &sprite_sheet_storage,
+            &tex_storage,
+        );
+        self.batch.reset();
+    }
+}
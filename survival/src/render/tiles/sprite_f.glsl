@@ -4,13 +4,20 @@
 
 uniform sampler2D albedo;
 
+// Light of the time of day and season, which tiles lit up by fires are drawn brighter than.
+layout (std140) uniform AmbientArgs {
+    vec4 ambient;
+};
+
 in VertexData {
     vec2 tex_uv;
     vec4 color;
+    float light;
 } vertex;
 
 out vec4 color;
 
 void main() {
-    color = texture(albedo, vertex.tex_uv) * vertex.color;
+    vec3 lighting = mix(ambient.rgb, vec3(1.0), vertex.light);
+    color = texture(albedo, vertex.tex_uv) * vertex.color * vec4(lighting, 1.0);
 }
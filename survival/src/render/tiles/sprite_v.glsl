@@ -16,11 +16,13 @@ in vec2 u_offset;
 in vec2 v_offset;
 
 in vec4 color;
+in float light;
 
 
 out VertexData {
     vec2 tex_uv;
     vec4 color;
+    float light;
 } vertex;
 
 const vec2 positions[6] = vec2[](
@@ -47,6 +49,7 @@ void main() {
     vec2 uv = pos + tex_u * dir_x + tex_v * dir_y;
     vertex.tex_uv = texture_coords(vec2(tex_u, tex_v), u_offset, v_offset);
     vertex.color = color;
+    vertex.light = light;
     vec4 vertex = vec4(uv, depth, 1.0);
     gl_Position = proj * view * vertex;
 }
@@ -37,6 +37,13 @@ pub(crate) struct ViewArgs {
     view: mat4,
 }
 
+/// Light of the time of day and season, the color tiles not lit up by fires are multiplied by.
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, Uniform)]
+pub(crate) struct AmbientArgs {
+    ambient: vec4,
+}
+
 #[repr(C, align(16))]
 #[derive(Clone, Copy, Debug, Uniform)]
 pub(crate) struct VertexArgs {
@@ -323,6 +330,13 @@ pub fn set_view_args(
     effect.update_constant_buffer("ViewArgs", &view_args.std140(), encoder);
 }
 
+pub fn set_ambient_args(effect: &mut Effect, encoder: &mut Encoder, (r, g, b): (f32, f32, f32)) {
+    let ambient_args = AmbientArgs {
+        ambient: [r, g, b, 1.0].into(),
+    };
+    effect.update_constant_buffer("AmbientArgs", &ambient_args.std140(), encoder);
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn draw_mesh(
     encoder: &mut Encoder,
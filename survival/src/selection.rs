@@ -0,0 +1,63 @@
+use amethyst::core::math::Vector3;
+
+/// A tile clicked on without dragging.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileSelected {
+    pub coord: Vector3<u32>,
+}
+
+/// A rectangle of tiles dragged out with the mouse, from its lowest to highest corner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RegionSelected {
+    pub min: Vector3<u32>,
+    pub max: Vector3<u32>,
+}
+
+/// The tile under the mouse cursor, and where the drag being made started, highlighted by the
+/// selection pass.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Selection {
+    pub hover: Option<Vector3<u32>>,
+    pub drag_start: Option<Vector3<u32>>,
+}
+impl Selection {
+    /// The rectangle being dragged out, from its lowest to highest corner.
+    pub fn dragged(&self) -> Option<(Vector3<u32>, Vector3<u32>)> {
+        match (self.drag_start, self.hover) {
+            (Some(from), Some(to)) => Some(region(from, to)),
+            _ => None,
+        }
+    }
+}
+
+/// The lowest and highest corners of the rectangle between `from` and `to`, on the z-level of
+/// `from`.
+pub fn region(from: Vector3<u32>, to: Vector3<u32>) -> (Vector3<u32>, Vector3<u32>) {
+    (
+        Vector3::new(from.x.min(to.x), from.y.min(to.y), from.z),
+        Vector3::new(from.x.max(to.x), from.y.max(to.y), from.z),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dragging() {
+        let mut selection = Selection::default();
+        selection.hover = Some(Vector3::new(2, 7, 3));
+        assert_eq!(selection.dragged(), None);
+
+        selection.drag_start = Some(Vector3::new(5, 4, 3));
+        assert_eq!(
+            selection.dragged(),
+            Some((Vector3::new(2, 4, 3), Vector3::new(5, 7, 3)))
+        );
+        // The drag stays on the level it started on
+        assert_eq!(
+            region(Vector3::new(1, 1, 0), Vector3::new(1, 1, 2)),
+            (Vector3::new(1, 1, 0), Vector3::new(1, 1, 0))
+        );
+    }
+}
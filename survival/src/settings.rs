@@ -4,6 +4,11 @@ use serde::{Deserialize, Serialize};
 #[serde(default)]
 pub struct Config {
     pub graphics: Graphics,
+    pub camera: CameraSettings,
+    pub time: TimeSettings,
+    pub locale: LocaleSettings,
+    pub packs: PackSettings,
+    pub saves: SaveSettings,
 }
 
 #[derive(Clone, Default, Debug, Deserialize, PartialEq, Serialize)]
@@ -12,6 +17,87 @@ pub struct Graphics {
     pub scale: f32,
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// How far, in world units, the followed entity may stray from the centre of the view before
+    /// the camera moves.
+    pub deadzone: f32,
+}
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self { deadzone: 100.0 }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct TimeSettings {
+    /// Game time in a day, which sets how long hours, seasons and years last too.
+    pub day_length: u64,
+}
+impl Default for TimeSettings {
+    fn default() -> Self {
+        Self {
+            day_length: crate::systems::time::day_length,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct LocaleSettings {
+    /// Language the text of the game is shown in, as the name of its file in `resources/locale`.
+    pub language: String,
+}
+impl Default for LocaleSettings {
+    fn default() -> Self {
+        Self {
+            language: "en".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct PackSettings {
+    /// Directory of the data pack of the base game, loaded first.
+    pub base: String,
+    /// Directory the mods are found in, each in a directory of its name.
+    pub directory: String,
+    /// Mods to load over the base game, in order, each overriding the entries of those before.
+    pub load_order: Vec<String>,
+}
+impl Default for PackSettings {
+    fn default() -> Self {
+        Self {
+            base: "resources/data".to_string(),
+            directory: "resources/mods".to_string(),
+            load_order: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct SaveSettings {
+    /// Directory the save slots and autosaves are written to.
+    pub directory: String,
+    /// Game time between autosaves, none being made if 0.
+    pub autosave_interval: u64,
+    /// Autosaves kept, each new one replacing the oldest.
+    pub autosaves: u32,
+}
+impl Default for SaveSettings {
+    fn default() -> Self {
+        Self {
+            directory: "saves".to_string(),
+            autosave_interval: crate::systems::time::day_length,
+            autosaves: 3,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Logs {
     pub root: slog::Logger,
@@ -11,7 +11,14 @@ use specs_static::WorldExt;
 
 use slog::slog_trace;
 
+use crate::components::{Doorway, FluidDepth, Footing, LightLevel, Obstruction, Stump, Visibility};
+use crate::decals::Decals;
+use crate::designations::Designation;
+use crate::farming::Tilled;
+use crate::goap::Planner;
 use crate::settings;
+use crate::systems::crafting::RecipeActions;
+use crate::tiles::TileId;
 use crate::SurvivalData;
 
 fn load_sprite_sheet(
@@ -70,22 +77,113 @@ impl<'a, 'b> amethyst::State<SurvivalData<'a, 'b>, StateEvent> for State {
         // How do we pass this along?
         world.res.fetch_mut::<settings::Context>().spritesheet = Some(default_sprite_sheet);
 
+        let day_length = world.read_resource::<settings::Config>().time.day_length;
+        world.add_resource(crate::systems::time::TimeState::new(day_length));
+        world.add_resource(crate::weather::Weather::default());
+
+        let language = world
+            .read_resource::<settings::Config>()
+            .locale
+            .language
+            .clone();
+        world.add_resource(
+            crate::assets::Localization::load(&crate::assets::Localization::path(
+                std::path::Path::new("resources/locale"),
+                &language,
+            ))
+            .unwrap(),
+        );
+        let packs =
+            crate::assets::Packs::load(&world.read_resource::<settings::Config>().packs).unwrap();
         crate::assets::StorageSource::<crate::assets::Item>::apply(
-            &std::path::Path::new("resources/data/items.ron"),
+            &packs.files("items.ron"),
+            world,
+        )
+        .unwrap();
+        crate::assets::StorageSource::<crate::assets::CreatureKind>::apply(
+            &packs.files("creatures.ron"),
+            world,
+        )
+        .unwrap();
+        let recipes = crate::assets::StorageSource::<crate::assets::Recipe>::apply(
+            &packs.files("recipes.ron"),
+            world,
+        )
+        .unwrap();
+        let workshops = crate::assets::StorageSource::<crate::assets::Workshop>::apply(
+            &packs.files("workshops.ron"),
             world,
         )
         .unwrap();
+        crate::assets::workshop::add_build_recipes(
+            &workshops.read().unwrap(),
+            &mut recipes.write().unwrap(),
+        );
+        let plants = crate::assets::StorageSource::<crate::assets::PlantKind>::apply(
+            &packs.files("plants.ron"),
+            world,
+        )
+        .unwrap();
+        // Actions any pawn may take which are made from loaded assets
+        let recipe_actions = {
+            let mut planner = world
+                .res
+                .entry::<Planner>()
+                .or_insert_with(Planner::default);
+            crate::farming::insert_actions(&mut planner, &plants.read().unwrap());
+            RecipeActions::insert(&mut planner, &recipes.read().unwrap())
+        };
+        world.add_resource(recipe_actions);
+        crate::assets::StorageSource::<crate::assets::Biome>::apply(
+            &packs.files("biomes.ron"),
+            world,
+        )
+        .unwrap();
+        let materials = crate::assets::StorageSource::<crate::assets::Material>::apply(
+            &packs.files("materials.ron"),
+            world,
+        )
+        .unwrap();
+        world.add_resource(crate::assets::material::Materials::from_storage(
+            &materials.read().unwrap(),
+        ));
+        crate::assets::validation::report(
+            &crate::assets::validation::validate(world, &packs),
+            &self.log,
+        );
+        world.add_resource(packs);
 
         // Register tile components
-        world.register_tile_comp::<crate::components::FlaggedSpriteRender, crate::tiles::TileId>();
+        world.register_tile_comp::<crate::components::TerrainLayer, crate::tiles::TileId>();
+        world.register_tile_comp::<crate::components::FloorLayer, crate::tiles::TileId>();
+        world.register_tile_comp::<crate::components::ConstructionLayer, crate::tiles::TileId>();
+        world.register_tile_comp::<crate::components::FluidLayer, crate::tiles::TileId>();
+        world.register_tile_comp::<crate::components::AnimatedTile, crate::tiles::TileId>();
         world.register_tile_comp::<amethyst::renderer::Flipped, crate::tiles::TileId>();
         world.register_tile_comp::<amethyst::renderer::Rgba, crate::tiles::TileId>();
         world
             .register_tile_comp::<amethyst::core::transform::Transform, crate::tiles::TileId>(
             );
         world.register_tile_comp::<crate::tiles::TileEntities, crate::tiles::TileId>();
+        world.register_tile_comp::<crate::tiles::TileEntity, crate::tiles::TileId>();
+
+        // Tile components saved with the game
+        world.register_persistent_tile_comp::<Obstruction, TileId>("obstruction");
+        world.register_persistent_tile_comp::<Footing, TileId>("footing");
+        world.register_persistent_tile_comp::<FluidDepth, TileId>("fluid_depth");
+        world.register_persistent_tile_comp::<Doorway, TileId>("doorway");
+        world.register_persistent_tile_comp::<Visibility, TileId>("visibility");
+        world.register_persistent_tile_comp::<LightLevel, TileId>("light_level");
+        world.register_persistent_tile_comp::<Tilled, TileId>("tilled");
+        world.register_persistent_tile_comp::<Stump, TileId>("stump");
+        world.register_persistent_tile_comp::<Designation, TileId>("designation");
+        world.register_persistent_tile_comp::<Decals, TileId>("decals");
 
-        world.register_tile_comp::<crate::components::Obstruction, crate::tiles::TileId>();
+        // Register the components of saving, which no system sets up
+        world.register::<crate::persistence::Prototype>();
+        world.register::<crate::persistence::SaveMarker>();
+        world.add_resource(crate::persistence::SaveMarkerAllocator::default());
+        world.add_resource(crate::persistence::LoadRequest::default());
     }
 
     fn handle_event(
@@ -103,7 +201,7 @@ impl<'a, 'b> amethyst::State<SurvivalData<'a, 'b>, StateEvent> for State {
     ) -> Trans<SurvivalData<'a, 'b>, StateEvent> {
         //if self.progress_counter.num_assets() == self.progress_counter.num_finished() {
         println!("Transition away from load");
-        return Trans::Switch(Box::new(super::Level::new(self.log.clone())));
+        return Trans::Switch(Box::new(super::MainMenu::new(self.log.clone())));
         //}
         //Trans::None
     }
@@ -1,6 +1,6 @@
 use amethyst::{
     assets::ProgressCounter,
-    core::components::{Transform},
+    core::{components::Transform, math::Vector3},
     ecs::{Builder, Entity, SystemData, World},
     renderer::{Camera, Projection, Rgba, SpriteRender, SpriteSheetHandle, Transparent},
     StateData, StateEvent, Trans,
@@ -8,21 +8,43 @@ use amethyst::{
 
 use slog::slog_trace;
 
-use crate::components::{Actionable, FlaggedSpriteRender, TilePosition, TimeAvailable};
+use crate::components::{
+    ai::{Blackboard, Pawn},
+    Actionable, FlaggedSpriteRender, Initiative, Player, PlayerControlled, TerrainLayer,
+    TilePosition, TimeAvailable,
+};
+use crate::initializers::{spawn_creature, SpawnType};
+use crate::injury::Body;
+use crate::persistence::Prototype;
 use crate::settings;
+use crate::temperature::{default_surface_temperature, TemperatureGrid};
 use crate::tiles::TileEntities;
 use crate::tiles::{Tiles, WriteTiles};
+use crate::weather::RegionClimate;
 use crate::SurvivalData;
 
-fn init_player(
+/// Width and height, in tiles, of the hierarchical pathfinding clusters.
+const path_cluster_size: u32 = 16;
+/// Wildlife the level starts with, by creature kind and where it lives.
+const wildlife: [(&str, [u32; 3]); 4] = [
+    ("deer", [40, 30, 0]),
+    ("deer", [44, 33, 0]),
+    ("rabbit", [25, 60, 0]),
+    ("wolf", [80, 80, 0]),
+];
+
+/// Creates the player's colonist on the tile at `coord`.
+pub(crate) fn init_player(
     world: &mut World,
     sprite_sheet: &SpriteSheetHandle,
     tiles: Tiles,
     game_settings: &settings::Config,
+    coord: Vector3<u32>,
 ) -> Entity {
+    let start = tiles.tile_to_world(coord, game_settings);
     let mut transform = Transform::default();
-    transform.set_translation_x(100.0);
-    transform.set_translation_y(-100.0);
+    transform.set_translation_x(start.x);
+    transform.set_translation_y(start.y);
     transform.set_scale(
         game_settings.graphics.scale,
         game_settings.graphics.scale,
@@ -41,13 +63,25 @@ fn init_player(
             sprite_number: 25,
         })
         .with(TimeAvailable::default())
+        .with(Initiative::default())
         .with(Actionable::default())
+        .with(Blackboard::default())
+        .with(Pawn::default())
+        .with(Body::default())
+        .with(Player::default())
+        .with(PlayerControlled)
+        .with(Prototype::Player)
         .with(Transparent)
         .with(Rgba::RED)
         .build()
 }
 
-fn init_camera(world: &mut World, _: Entity, tiles: Tiles, game_settings: &settings::Config) {
+pub(crate) fn init_camera(
+    world: &mut World,
+    _: Entity,
+    tiles: Tiles,
+    game_settings: &settings::Config,
+) {
     let mut transform = Transform::default();
     transform.set_translation_z(1.0);
     //*transform.scale_mut() = transform.scale() * 4.0;
@@ -67,6 +101,87 @@ fn init_camera(world: &mut World, _: Entity, tiles: Tiles, game_settings: &setti
         .build();
 }
 
+/// The generation of the tiles of the next level laid out in `world`, newer than any before it
+/// for the ids kept from those to find nothing in the tile storages.
+pub(crate) fn next_generation(world: &World) -> u32 {
+    world
+        .res
+        .try_fetch::<Tiles>()
+        .map_or(0, |tiles| tiles.generation() + 1)
+}
+
+/// Lays out the tiles of a fresh level: their terrain, transforms and occupants, with the edges
+/// of the map impassable, and adds `tiles` and the resources kept over them.
+pub(crate) fn init_tiles(world: &mut World, tiles: Tiles) {
+    {
+        let context = world.res.fetch::<settings::Context>().clone();
+        let map_sprite_sheet_handle = context.spritesheet.as_ref().unwrap();
+        let game_settings = world.res.fetch::<settings::Config>().clone();
+
+        let mut sprites: WriteTiles<TerrainLayer> = SystemData::fetch(&world.res);
+        let mut transforms: WriteTiles<Transform> = SystemData::fetch(&world.res);
+        let mut tile_entities_map: WriteTiles<TileEntities> = SystemData::fetch(&world.res);
+        //let tile_rgb: WriteTiles<Rgba> = SystemData::fetch(&world.res);
+        for tile_id in tiles.iter_all() {
+            tile_entities_map.insert_default(tile_id);
+
+            sprites.insert(
+                tile_id,
+                TerrainLayer(FlaggedSpriteRender {
+                    sprite_sheet: map_sprite_sheet_handle.clone(),
+                    sprite_number: 11,
+                }),
+            );
+
+            // tile_rgb.insert(tile_id, Rgba::GREEN);
+
+            let coords = tile_id.coords(tiles.dimensions());
+            let mut transform = Transform::default();
+
+            let width = 16.;
+            let height = 16.;
+            transform.set_translation_xyz(
+                coords.0 * width * game_settings.graphics.scale,
+                -1. * (coords.1 * height * game_settings.graphics.scale),
+                0.,
+            );
+            transform.set_scale(
+                game_settings.graphics.scale,
+                game_settings.graphics.scale,
+                game_settings.graphics.scale,
+            );
+
+            let mut global = Transform::default();
+            global.0 = transform.matrix();
+            transforms.insert(tile_id, global);
+        }
+
+        let mut impassable_tiles: WriteTiles<crate::components::Obstruction> =
+            SystemData::fetch(&world.res);
+        // Set all the edges to impassable
+        for x in &[0, tiles.dimensions().x - 1] {
+            for y in 0..tiles.dimensions().y {
+                impassable_tiles.insert_default(tiles.id(*x, y, 0));
+            }
+        }
+        for y in &[0, tiles.dimensions().y - 1] {
+            for x in 0..tiles.dimensions().x {
+                impassable_tiles.insert_default(tiles.id(x, *y, 0));
+            }
+        }
+    }
+
+    world.add_resource(tiles);
+    world.add_resource(TemperatureGrid::new(tiles.dimensions(), |_, _| {
+        default_surface_temperature
+    }));
+    world.add_resource(RegionClimate::default());
+    world.add_resource(crate::pathfinding::HierarchicalGraph::new(
+        tiles,
+        path_cluster_size,
+    ));
+}
+
 pub struct State {
     progress_counter: ProgressCounter,
     log: slog::Logger,
@@ -85,69 +200,30 @@ impl<'a, 'b> amethyst::State<SurvivalData<'a, 'b>, StateEvent> for State {
         slog_trace!(self.log, "Changed state to Level");
 
         // Load the level
-        let tiles = Tiles::new(100, 100, 100);
+        let tiles = Tiles::new(100, 100, 100).with_generation(next_generation(world));
+        init_tiles(world, tiles);
         {
             let context = world.res.fetch::<settings::Context>().clone();
             let map_sprite_sheet_handle = context.spritesheet.as_ref().unwrap();
             let game_settings = world.res.fetch::<settings::Config>().clone();
 
-            let player = init_player(world, map_sprite_sheet_handle, tiles, &game_settings);
+            let player = init_player(
+                world,
+                map_sprite_sheet_handle,
+                tiles,
+                &game_settings,
+                Vector3::new(5, 5, 0),
+            );
             init_camera(world, player, tiles, &game_settings);
-
-            let mut sprites: WriteTiles<FlaggedSpriteRender> = SystemData::fetch(&world.res);
-            let mut transforms: WriteTiles<Transform> = SystemData::fetch(&world.res);
-            let mut tile_entities_map: WriteTiles<TileEntities> = SystemData::fetch(&world.res);
-            //let tile_rgb: WriteTiles<Rgba> = SystemData::fetch(&world.res);
-            for tile_id in tiles.iter_all() {
-                tile_entities_map.insert_default(tile_id);
-
-                sprites.insert(
-                    tile_id,
-                    FlaggedSpriteRender {
-                        sprite_sheet: map_sprite_sheet_handle.clone(),
-                        sprite_number: 11,
-                    },
-                );
-
-                // tile_rgb.insert(tile_id, Rgba::GREEN);
-
-                let coords = tile_id.coords(tiles.dimensions());
-                let mut transform = Transform::default();
-
-                let width = 16.;
-                let height = 16.;
-                transform.set_translation_xyz(
-                    coords.0 * width * game_settings.graphics.scale,
-                    -1. * (coords.1 * height * game_settings.graphics.scale),
-                    0.,
-                );
-                transform.set_scale(
-                    game_settings.graphics.scale,
-                    game_settings.graphics.scale,
-                    game_settings.graphics.scale,
-                );
-
-                let mut global = Transform::default();
-                global.0 = transform.matrix();
-                transforms.insert(tile_id, global);
-            }
-
-            let mut impassable_tiles: WriteTiles<crate::components::Obstruction> =
-                SystemData::fetch(&world.res);
-            // Set all the edges to impassable
-            for x in &[0, tiles.dimensions().x - 1] {
-                for y in 0..tiles.dimensions().y {
-                    impassable_tiles.insert_default(tiles.id(*x, y, 0));
-                }
-            }
-            for y in &[0, tiles.dimensions().y - 1] {
-                for x in 0..tiles.dimensions().x {
-                    impassable_tiles.insert_default(tiles.id(x, *y, 0));
-                }
-            }
         }
 
-        world.add_resource(tiles);
+        for (kind, [x, y, z]) in &wildlife {
+            spawn_creature(
+                world,
+                kind,
+                SpawnType::TilePosition(Vector3::new(*x, *y, *z)),
+            );
+        }
     }
 
     fn handle_event(
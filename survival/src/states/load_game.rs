@@ -0,0 +1,85 @@
+use amethyst::{
+    assets::ProgressCounter,
+    ecs::{Join, World},
+    StateData, StateEvent, Trans,
+};
+use std::path::PathBuf;
+
+use slog::{slog_error, slog_trace};
+
+use crate::components::Player;
+use crate::persistence;
+use crate::settings;
+use crate::states::level::{init_camera, init_tiles, next_generation};
+use crate::tiles::Tiles;
+use crate::SurvivalData;
+
+/// Lays out the level saved at `path` and restores the game into it, as `Level` does for a new
+/// game, replacing any game being played.
+pub struct State {
+    progress_counter: ProgressCounter,
+    log: slog::Logger,
+    path: PathBuf,
+    /// Whether the save was read, the game played before it being returned to if it wasn't.
+    loaded: bool,
+}
+impl State {
+    pub fn new(root_logger: slog::Logger, path: PathBuf) -> Self {
+        Self {
+            progress_counter: ProgressCounter::default(),
+            log: root_logger,
+            path,
+            loaded: false,
+        }
+    }
+
+    fn load(&self, world: &mut World) -> Result<(), failure::Error> {
+        let save = persistence::read(&self.path)?;
+
+        persistence::clear(world);
+        let (x, y, z) = save.dimensions;
+        let tiles = Tiles::new(x, y, z).with_generation(next_generation(world));
+        init_tiles(world, tiles);
+        persistence::load(world, save)?;
+
+        let player = (&world.entities(), &world.read_storage::<Player>())
+            .join()
+            .map(|(entity, _)| entity)
+            .next()
+            .ok_or_else(|| failure::format_err!("{:?} has no player", self.path))?;
+        let game_settings = world.res.fetch::<settings::Config>().clone();
+        init_camera(world, player, tiles, &game_settings);
+
+        Ok(())
+    }
+}
+impl<'a, 'b> amethyst::State<SurvivalData<'a, 'b>, StateEvent> for State {
+    fn on_start(&mut self, data: StateData<'_, SurvivalData<'_, '_>>) {
+        slog_trace!(self.log, "Changed state to LoadGame");
+
+        match self.load(data.world) {
+            Ok(()) => self.loaded = true,
+            Err(e) => slog_error!(self.log, "Failed to load {:?}: {}", self.path, e),
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        _: StateData<'_, SurvivalData<'_, '_>>,
+        _: StateEvent,
+    ) -> Trans<SurvivalData<'a, 'b>, StateEvent> {
+        slog_trace!(self.log, "Event LoadGame");
+        Trans::None
+    }
+
+    fn update(
+        &mut self,
+        _: StateData<'_, SurvivalData<'_, '_>>,
+    ) -> Trans<SurvivalData<'a, 'b>, StateEvent> {
+        if self.loaded {
+            Trans::Push(Box::new(super::Paused::new(self.log.clone())))
+        } else {
+            Trans::Pop
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use amethyst::{
+    config::Config,
+    ecs::{LazyUpdate, World},
+    shrev::EventChannel,
+    utils::application_root_dir,
+    StateData, StateEvent, Trans,
+};
+use amethyst_imgui::imgui::{self, im_str, ImString};
+use slog::{slog_error, slog_trace};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::assets::Localization;
+use crate::persistence::{self, SaveInfo};
+use crate::settings;
+use crate::systems::ui::ImGuiDraw;
+use crate::SurvivalData;
+
+/// What was picked in the menu, acted on by the state on its next update.
+enum Choice {
+    NewGame,
+    Load(PathBuf),
+    Quit,
+}
+
+/// What is shown below the buttons of the menu.
+#[derive(Clone, Copy, PartialEq)]
+enum Panel {
+    None,
+    Saves,
+    Settings,
+}
+
+/// The menu the game starts in, to generate a world for a new game, load a saved one, change the
+/// settings or quit.
+pub struct State {
+    log: slog::Logger,
+    choice: Arc<Mutex<Option<Choice>>>,
+    panel: Arc<Mutex<Panel>>,
+    /// The settings being changed, only applied once saved.
+    config: Arc<Mutex<settings::Config>>,
+    /// The saves listed, read again each time the menu is returned to.
+    saves: Arc<Vec<(PathBuf, SaveInfo)>>,
+}
+impl State {
+    pub fn new(root_logger: slog::Logger) -> Self {
+        Self {
+            log: root_logger,
+            choice: Arc::default(),
+            panel: Arc::new(Mutex::new(Panel::None)),
+            config: Arc::default(),
+            saves: Arc::default(),
+        }
+    }
+
+    fn refresh(&mut self, world: &World) {
+        let config = world.read_resource::<settings::Config>().clone();
+        self.saves = Arc::new(persistence::list(&PathBuf::from(&config.saves.directory)));
+        *self.config.lock().unwrap() = config;
+    }
+
+    fn draw(&self, world: &World) {
+        let locale = world.read_resource::<Localization>();
+        let title = ImString::new(locale.get("ui.menu.title"));
+        let new_game = locale.get("ui.menu.new_game").to_string();
+        let load = locale.get("ui.menu.load").to_string();
+        let settings_label = locale.get("ui.menu.settings").to_string();
+        let quit = locale.get("ui.menu.quit").to_string();
+        let no_saves = locale.get("ui.saves.none").to_string();
+        let descriptions = self
+            .saves
+            .iter()
+            .map(|(_, info)| {
+                format!(
+                    "{} - {}",
+                    info.name,
+                    locale.format("ui.saves.pawns", &[&info.pawns.to_string()])
+                )
+            })
+            .collect::<Vec<_>>();
+        let labels = SettingsLabels {
+            scale: ImString::new(locale.get("ui.menu.scale")),
+            deadzone: ImString::new(locale.get("ui.menu.deadzone")),
+            autosaves: ImString::new(locale.get("ui.menu.autosaves")),
+            save: locale.get("ui.menu.save_settings").to_string(),
+        };
+
+        let log = self.log.clone();
+        let (choice, panel, config, saves) = (
+            self.choice.clone(),
+            self.panel.clone(),
+            self.config.clone(),
+            self.saves.clone(),
+        );
+        world
+            .write_resource::<EventChannel<ImGuiDraw>>()
+            .single_write(Arc::new(
+                move |ui: &amethyst_imgui::imgui::Ui, lazy: &LazyUpdate| {
+                    ui.window(&title)
+                        .size((320.0, 360.0), imgui::ImGuiCond::FirstUseEver)
+                        .build(|| {
+                            let mut panel = panel.lock().unwrap();
+                            if ui.button(&im_str!("{}", new_game), (-1.0, 0.0)) {
+                                *choice.lock().unwrap() = Some(Choice::NewGame);
+                            }
+                            for (label, shown) in
+                                &[(&load, Panel::Saves), (&settings_label, Panel::Settings)]
+                            {
+                                if ui.button(&im_str!("{}", label), (-1.0, 0.0)) {
+                                    *panel = if *panel == *shown {
+                                        Panel::None
+                                    } else {
+                                        *shown
+                                    };
+                                }
+                            }
+                            if ui.button(&im_str!("{}", quit), (-1.0, 0.0)) {
+                                *choice.lock().unwrap() = Some(Choice::Quit);
+                            }
+
+                            match *panel {
+                                Panel::Saves => {
+                                    ui.separator();
+                                    if saves.is_empty() {
+                                        ui.text(&no_saves);
+                                    }
+                                    draw_saves(ui, &saves, &descriptions, &choice);
+                                }
+                                Panel::Settings => {
+                                    ui.separator();
+                                    draw_settings(ui, lazy, &labels, &config, &log);
+                                }
+                                Panel::None => (),
+                            }
+                        })
+                },
+            ));
+    }
+}
+impl<'a, 'b> amethyst::State<SurvivalData<'a, 'b>, StateEvent> for State {
+    fn on_start(&mut self, data: StateData<'_, SurvivalData<'_, '_>>) {
+        slog_trace!(self.log, "Changed state to MainMenu");
+        self.refresh(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, SurvivalData<'_, '_>>) {
+        self.refresh(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        _: StateData<'_, SurvivalData<'_, '_>>,
+        _: StateEvent,
+    ) -> Trans<SurvivalData<'a, 'b>, StateEvent> {
+        Trans::None
+    }
+
+    fn update(
+        &mut self,
+        data: StateData<'_, SurvivalData<'_, '_>>,
+    ) -> Trans<SurvivalData<'a, 'b>, StateEvent> {
+        match self.choice.lock().unwrap().take() {
+            Some(Choice::NewGame) => {
+                return Trans::Push(Box::new(super::WorldGen::new(self.log.clone())));
+            }
+            Some(Choice::Load(path)) => {
+                return Trans::Push(Box::new(super::LoadGame::new(self.log.clone(), path)));
+            }
+            Some(Choice::Quit) => return Trans::Quit,
+            None => (),
+        }
+
+        self.draw(data.world);
+        data.data.update_core(&data.world);
+
+        Trans::None
+    }
+}
+
+/// The saves as buttons to load them with.
+fn draw_saves(
+    ui: &amethyst_imgui::imgui::Ui,
+    saves: &[(PathBuf, SaveInfo)],
+    descriptions: &[String],
+    choice: &Mutex<Option<Choice>>,
+) {
+    for (i, ((path, _), description)) in saves.iter().zip(descriptions).enumerate() {
+        if ui.button(&im_str!("{}##save{}", description, i), (-1.0, 0.0)) {
+            *choice.lock().unwrap() = Some(Choice::Load(path.clone()));
+        }
+    }
+}
+
+struct SettingsLabels {
+    scale: ImString,
+    deadzone: ImString,
+    autosaves: ImString,
+    save: String,
+}
+
+/// The settings changed in the menu, and a button to apply them and write them to the settings
+/// file.
+fn draw_settings(
+    ui: &amethyst_imgui::imgui::Ui,
+    lazy: &LazyUpdate,
+    labels: &SettingsLabels,
+    config: &Mutex<settings::Config>,
+    log: &slog::Logger,
+) {
+    let mut config = config.lock().unwrap();
+    ui.slider_float(&labels.scale, &mut config.graphics.scale, 0.5, 4.0)
+        .build();
+    ui.slider_float(&labels.deadzone, &mut config.camera.deadzone, 0.0, 400.0)
+        .build();
+    let mut autosaves = config.saves.autosaves as i32;
+    ui.slider_int(&labels.autosaves, &mut autosaves, 0, 10)
+        .build();
+    config.saves.autosaves = autosaves as u32;
+
+    if ui.button(&im_str!("{}", labels.save), (0.0, 0.0)) {
+        let (config, log) = (config.clone(), log.clone());
+        lazy.exec_mut(move |world| {
+            if let Err(e) = write_settings(&config) {
+                slog_error!(log, "Failed to save settings: {}", e);
+            }
+            world.add_resource(config);
+        });
+    }
+}
+
+/// Writes `config` over the settings file the game loads them from.
+fn write_settings(config: &settings::Config) -> Result<(), failure::Error> {
+    let path = application_root_dir()?
+        .join("resources")
+        .join("game_settings.ron");
+    config.write(path)?;
+    Ok(())
+}
@@ -4,8 +4,17 @@ pub use first_load::State as FirstLoad;
 pub mod level;
 pub use level::State as Level;
 
+pub mod load_game;
+pub use load_game::State as LoadGame;
+
+pub mod main_menu;
+pub use main_menu::State as MainMenu;
+
 pub mod paused;
 pub use paused::State as Paused;
 
 pub mod running;
 pub use running::State as Running;
+
+pub mod world_generation;
+pub use world_generation::State as WorldGen;
@@ -3,6 +3,7 @@ use amethyst::{assets::ProgressCounter, StateData, StateEvent, Trans};
 use slog::slog_trace;
 
 use crate::game_data::SurvivalState;
+use crate::persistence::LoadRequest;
 use crate::SurvivalData;
 
 pub struct State {
@@ -41,6 +42,9 @@ impl<'a, 'b> amethyst::State<SurvivalData<'a, 'b>, StateEvent> for State {
         &mut self,
         data: StateData<'_, SurvivalData<'_, '_>>,
     ) -> Trans<SurvivalData<'a, 'b>, StateEvent> {
+        if let Some(path) = data.world.write_resource::<LoadRequest>().0.take() {
+            return Trans::Switch(Box::new(super::LoadGame::new(self.log.clone(), path)));
+        }
         if data.data.update(&data.world, SurvivalState::Paused) != SurvivalState::Paused {
             return Trans::Push(Box::new(super::Running::new(self.log.clone())));
         }
@@ -1,6 +1,7 @@
 use amethyst::{assets::ProgressCounter, StateData, StateEvent, Trans};
 
 use crate::game_data::SurvivalState;
+use crate::persistence::LoadRequest;
 use crate::SurvivalData;
 
 pub struct State {
@@ -36,6 +37,10 @@ impl<'a, 'b> amethyst::State<SurvivalData<'a, 'b>, StateEvent> for State {
         &mut self,
         data: StateData<'_, SurvivalData<'_, '_>>,
     ) -> Trans<SurvivalData<'a, 'b>, StateEvent> {
+        // Saves are loaded from `Paused`
+        if data.world.read_resource::<LoadRequest>().0.is_some() {
+            return Trans::Pop;
+        }
         if data.data.update(&data.world, SurvivalState::Running) != SurvivalState::Running {
             return Trans::Pop;
         }
@@ -0,0 +1,266 @@
+use amethyst::{
+    ecs::{LazyUpdate, World},
+    shrev::EventChannel,
+    StateData, StateEvent, Trans,
+};
+use amethyst_imgui::imgui::{self, im_str, ImString};
+use slog::{slog_error, slog_trace};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::assets::{BiomeStorage, Localization};
+use crate::map::WorldMap;
+use crate::mapgen::{GenerationJob, GeneratorSettings, NoiseGeneratorSettings};
+use crate::persistence::Thumbnail;
+use crate::systems::ui::{save_window::draw_thumbnail, ImGuiDraw};
+use crate::SurvivalData;
+
+/// Cells along each side of the preview of a generated world.
+const preview_size: u32 = 160;
+/// Characters a seed can be typed with.
+const seed_capacity: usize = 64;
+
+/// What was picked in the window, acted on by the state on its next update.
+enum Choice {
+    Generate,
+    Start,
+    Back,
+}
+
+/// The seed and generator settings the next world is generated from.
+struct Form {
+    seed: ImString,
+    settings: GeneratorSettings,
+}
+
+/// Generates the world of a new game from a seed and generator settings picked in its window,
+/// previewing each world generated for one to be started in, or gone back to the menu from.
+pub struct State {
+    log: slog::Logger,
+    choice: Arc<Mutex<Option<Choice>>>,
+    form: Arc<Mutex<Form>>,
+    job: Option<GenerationJob>,
+    /// The world last generated, and its preview.
+    generated: Option<(WorldMap, Arc<Thumbnail>)>,
+    error: Option<String>,
+}
+impl State {
+    pub fn new(root_logger: slog::Logger) -> Self {
+        let mut seed = ImString::with_capacity(seed_capacity);
+        seed.push_str(
+            &SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs())
+                .to_string(),
+        );
+
+        Self {
+            log: root_logger,
+            choice: Arc::default(),
+            form: Arc::new(Mutex::new(Form {
+                seed,
+                settings: GeneratorSettings::default(),
+            })),
+            job: None,
+            generated: None,
+            error: None,
+        }
+    }
+
+    /// Takes the world being generated once the job is done with it.
+    fn poll(&mut self) {
+        let result = match self.job.as_mut().and_then(GenerationJob::take) {
+            Some(result) => result,
+            None => return,
+        };
+        self.job = None;
+
+        match result {
+            Ok(map) => {
+                let preview = Arc::new(preview(&map));
+                self.generated = Some((map, preview));
+            }
+            Err(e) => {
+                slog_error!(self.log, "Failed to generate the world: {}", e);
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    fn draw(&self, world: &World) {
+        let locale = world.read_resource::<Localization>();
+        let title = ImString::new(locale.get("ui.worldgen.title"));
+        let seed_label = ImString::new(locale.get("ui.worldgen.seed"));
+        let points = ImString::new(locale.get("ui.worldgen.points"));
+        let relaxation = ImString::new(locale.get("ui.worldgen.relaxation"));
+        let size = ImString::new(locale.get("ui.worldgen.size"));
+        let sea_level = ImString::new(locale.get("ui.worldgen.sea_level"));
+        let flatness = ImString::new(locale.get("ui.worldgen.flatness"));
+        let generate = locale.get("ui.worldgen.generate").to_string();
+        let start = locale.get("ui.worldgen.start").to_string();
+        let back = locale.get("ui.worldgen.back").to_string();
+        let stage = self.job.as_ref().map(|job| {
+            let stage = job.stage();
+            (stage.fraction(), ImString::new(locale.get(stage.key())))
+        });
+        let error = self
+            .error
+            .as_ref()
+            .map(|e| locale.format("ui.worldgen.failed", &[e.as_str()]));
+        let preview = self.generated.as_ref().map(|(_, preview)| preview.clone());
+
+        let (choice, form) = (self.choice.clone(), self.form.clone());
+        world
+            .write_resource::<EventChannel<ImGuiDraw>>()
+            .single_write(Arc::new(
+                move |ui: &amethyst_imgui::imgui::Ui, _: &LazyUpdate| {
+                    ui.window(&title)
+                        .size((360.0, 560.0), imgui::ImGuiCond::FirstUseEver)
+                        .build(|| {
+                            let pick = |picked| *choice.lock().unwrap() = Some(picked);
+
+                            {
+                                let mut form = form.lock().unwrap();
+                                let settings = &mut form.settings;
+                                let mut num_points = settings.num_points as i32;
+                                ui.slider_int(&points, &mut num_points, 500, 20000).build();
+                                settings.num_points = num_points as usize;
+                                let mut num_lloyd = settings.num_lloyd as i32;
+                                ui.slider_int(&relaxation, &mut num_lloyd, 0, 5).build();
+                                settings.num_lloyd = num_lloyd as usize;
+                                let mut world_pixels = settings.world_pixels as f32;
+                                ui.slider_float(&size, &mut world_pixels, 128.0, 1024.0)
+                                    .build();
+                                settings.world_pixels = f64::from(world_pixels.round());
+                                let mut sea = settings.sea_level as f32;
+                                ui.slider_float(&sea_level, &mut sea, 0.0, 0.5).build();
+                                settings.sea_level = f64::from(sea);
+                                let mut exponent = settings.hypsometric_exponent as f32;
+                                ui.slider_float(&flatness, &mut exponent, 0.25, 4.0).build();
+                                settings.hypsometric_exponent = f64::from(exponent);
+
+                                ui.input_text(&seed_label, &mut form.seed).build();
+                            }
+
+                            match &stage {
+                                Some((fraction, name)) => {
+                                    ui.progress_bar(*fraction).overlay_text(name).build();
+                                }
+                                None => {
+                                    if ui.button(&im_str!("{}", generate), (0.0, 0.0)) {
+                                        pick(Choice::Generate);
+                                    }
+                                    if preview.is_some() {
+                                        ui.same_line(0.0);
+                                        if ui.button(&im_str!("{}", start), (0.0, 0.0)) {
+                                            pick(Choice::Start);
+                                        }
+                                    }
+                                }
+                            }
+                            ui.same_line(0.0);
+                            if ui.button(&im_str!("{}", back), (0.0, 0.0)) {
+                                pick(Choice::Back);
+                            }
+
+                            if let Some(error) = &error {
+                                ui.text(error);
+                            }
+                            if let Some(preview) = &preview {
+                                ui.separator();
+                                draw_thumbnail(ui, preview, 0);
+                            }
+                        })
+                },
+            ));
+    }
+}
+impl<'a, 'b> amethyst::State<SurvivalData<'a, 'b>, StateEvent> for State {
+    fn on_start(&mut self, _: StateData<'_, SurvivalData<'_, '_>>) {
+        slog_trace!(self.log, "Changed state to WorldGen");
+    }
+
+    fn handle_event(
+        &mut self,
+        _: StateData<'_, SurvivalData<'_, '_>>,
+        _: StateEvent,
+    ) -> Trans<SurvivalData<'a, 'b>, StateEvent> {
+        Trans::None
+    }
+
+    fn update(
+        &mut self,
+        data: StateData<'_, SurvivalData<'_, '_>>,
+    ) -> Trans<SurvivalData<'a, 'b>, StateEvent> {
+        self.poll();
+
+        match self.choice.lock().unwrap().take() {
+            Some(Choice::Generate) if self.job.is_none() => {
+                let form = self.form.lock().unwrap();
+                self.job = Some(GenerationJob::start(
+                    form.seed.to_str().trim(),
+                    form.settings,
+                    NoiseGeneratorSettings::default(),
+                ));
+                self.generated = None;
+                self.error = None;
+            }
+            Some(Choice::Start) => {
+                if let Some((mut map, _)) = self.generated.take() {
+                    map.set_biomes(
+                        &data
+                            .world
+                            .read_resource::<BiomeStorage>()
+                            .read()
+                            .unwrap()
+                            .data,
+                    );
+                    data.world.add_resource(map);
+                    return Trans::Switch(Box::new(super::Level::new(self.log.clone())));
+                }
+            }
+            Some(Choice::Back) => return Trans::Pop,
+            _ => (),
+        }
+
+        self.draw(data.world);
+        data.data.update_core(&data.world);
+
+        Trans::None
+    }
+}
+
+/// A map of the heights of `map`, the sea in blues and the land from green to white.
+fn preview(map: &WorldMap) -> Thumbnail {
+    let world_pixels = map.settings.world_pixels;
+    let sea_level = map.settings.sea_level as f32;
+    let shade = |low: [f32; 3], high: [f32; 3], t: f32| {
+        let t = t.max(0.0).min(1.0);
+        let channel = |i: usize| ((low[i] + (high[i] - low[i]) * t) * 255.0).round() as u8;
+        [channel(0), channel(1), channel(2)]
+    };
+
+    let mut pixels = Vec::with_capacity((preview_size * preview_size) as usize);
+    for y in 0..preview_size {
+        for x in 0..preview_size {
+            let scale = world_pixels / f64::from(preview_size);
+            let height = map.sample_height(f64::from(x) * scale, f64::from(y) * scale);
+            pixels.push(if height <= sea_level {
+                shade(
+                    [0.05, 0.1, 0.35],
+                    [0.2, 0.4, 0.75],
+                    height / sea_level.max(0.01),
+                )
+            } else {
+                let t = (height - sea_level) / (1.0 - sea_level).max(0.01);
+                shade([0.25, 0.55, 0.2], [0.95, 0.95, 0.95], t)
+            });
+        }
+    }
+
+    Thumbnail {
+        width: preview_size,
+        height: preview_size,
+        pixels,
+    }
+}
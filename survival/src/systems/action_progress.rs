@@ -0,0 +1,76 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{Entities, Join, Read, ReadStorage, Write, WriteStorage},
+    shrev::EventChannel,
+};
+
+use crate::actions::ActionResult;
+use crate::components::{
+    ai::{CurrentAction, AI},
+    TimeAvailable,
+};
+use crate::systems::time::TimeState;
+
+/// Works through every `CurrentAction` other than movement. Entities taking turns spend their
+/// `TimeAvailable` on it, the rest the game time passed, and an `ActionResult` is sent once it is
+/// done. Actions the `AI` is no longer carrying out were interrupted, and are dropped.
+#[derive(Default)]
+pub struct System {
+    last_time: Option<u64>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, TimeState>,
+        Write<'s, EventChannel<ActionResult>>,
+        ReadStorage<'s, AI>,
+        WriteStorage<'s, TimeAvailable>,
+        WriteStorage<'s, CurrentAction>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, time, mut results, ais, mut times, mut current_actions): Self::SystemData,
+    ) {
+        let now = time.current_time;
+        let elapsed = now.saturating_sub(self.last_time.unwrap_or(now));
+        self.last_time = Some(now);
+
+        let mut finished = Vec::new();
+        for (entity, current, ai, mut time) in (
+            &entities,
+            &mut current_actions,
+            ais.maybe(),
+            (&mut times).maybe(),
+        )
+            .join()
+        {
+            if ai.map_or(false, |ai| ai.current != Some(current.action)) {
+                finished.push(entity);
+                continue;
+            }
+            if current.is_movement() {
+                continue;
+            }
+
+            match time.as_mut() {
+                Some(time) => {
+                    let spent = current.advance(time.0);
+                    time.consume(spent);
+                }
+                None => {
+                    current.advance(elapsed);
+                }
+            }
+            if current.is_finished() {
+                results.single_write(ActionResult::completed(entity, current.event));
+                finished.push(entity);
+            }
+        }
+
+        for entity in finished {
+            current_actions.remove(entity);
+        }
+    }
+}
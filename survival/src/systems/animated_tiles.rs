@@ -0,0 +1,67 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::timing::Time,
+    ecs::{Join, Read},
+};
+use specs_static::{Id, Storage};
+
+use crate::components::{
+    AnimatedTile, ConstructionLayer, FlaggedSpriteRender, FloorLayer, FluidLayer, TerrainLayer,
+    TileLayer, TileLayerSprite,
+};
+use crate::tiles::{ReadTiles, TileId, WriteTiles};
+
+/// Steps the sprite of every `AnimatedTile` on to the frame due by the real time elapsed, only
+/// writing those whose frame changed, for the tile pass to rebuild their chunks.
+#[derive(Default)]
+pub struct System;
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Read<'s, Time>,
+        ReadTiles<'s, AnimatedTile>,
+        WriteTiles<'s, TerrainLayer>,
+        WriteTiles<'s, FloorLayer>,
+        WriteTiles<'s, ConstructionLayer>,
+        WriteTiles<'s, FluidLayer>,
+    );
+
+    fn run(
+        &mut self,
+        (time, animations, mut terrain, mut floor, mut construction, mut fluid): Self::SystemData,
+    ) {
+        let seconds = time.absolute_real_time_seconds();
+        for id in animations.ids() {
+            let animation = animations.get(id).unwrap();
+            let sprite_number = match animation.frame(seconds, id.id() as usize) {
+                Some(sprite_number) => sprite_number,
+                None => continue,
+            };
+            match animation.layer {
+                TileLayer::Terrain => animate(&mut terrain, id, sprite_number),
+                TileLayer::Floor => animate(&mut floor, id, sprite_number),
+                TileLayer::Construction => animate(&mut construction, id, sprite_number),
+                TileLayer::Fluid => animate(&mut fluid, id, sprite_number),
+            }
+        }
+    }
+}
+
+/// Sets the sprite of the tile `id` on the layer `storage` to `sprite_number`, if it has one
+/// and it isn't already.
+fn animate<C>(storage: &mut Storage<C, C::Storage, TileId>, id: TileId, sprite_number: usize)
+where
+    C: TileLayerSprite,
+{
+    let sprite = match storage.get(id) {
+        Some(layer) if layer.sprite().sprite_number != sprite_number => layer.sprite().clone(),
+        _ => return,
+    };
+    storage.insert(
+        id,
+        C::new(FlaggedSpriteRender {
+            sprite_number,
+            ..sprite
+        }),
+    );
+}
@@ -0,0 +1,82 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    assets::{Asset, AssetStorage, Loader},
+    ecs::{Read, ReadExpect, Write},
+    shrev::EventChannel,
+};
+
+use crate::assets::{
+    self, material::Materials, ItemStorage, MaterialStorage, StorageReloaded, StorageWrapper,
+};
+
+/// Follows the item and material storages as hot reloading reads their files again, loading
+/// handles for the entries added, copying the materials out into `Materials` again and sending
+/// a `StorageReloaded` for each storage read.
+#[derive(Default)]
+pub struct System {
+    item_generation: u32,
+    material_generation: u32,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        ReadExpect<'s, Loader>,
+        ReadExpect<'s, ItemStorage>,
+        ReadExpect<'s, MaterialStorage>,
+        Read<'s, AssetStorage<assets::Item>>,
+        Read<'s, AssetStorage<assets::Material>>,
+        Write<'s, Materials>,
+        Write<'s, EventChannel<StorageReloaded>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            loader,
+            items,
+            materials,
+            item_assets,
+            material_assets,
+            mut by_name,
+            mut reloaded,
+        ): Self::SystemData,
+    ) {
+        if reload(&items, &mut self.item_generation, &loader, &item_assets) {
+            reloaded.single_write(StorageReloaded {
+                asset: assets::Item::NAME,
+            });
+        }
+        if reload(
+            &materials,
+            &mut self.material_generation,
+            &loader,
+            &material_assets,
+        ) {
+            *by_name = Materials::from_storage(&materials.read().unwrap());
+            reloaded.single_write(StorageReloaded {
+                asset: assets::Material::NAME,
+            });
+        }
+    }
+}
+
+/// Whether `storage` was read again since `generation`, loading handles for what it gained if
+/// so.
+fn reload<T>(
+    storage: &StorageWrapper<T>,
+    generation: &mut u32,
+    loader: &Loader,
+    asset_storage: &AssetStorage<T>,
+) -> bool
+where
+    T: Send + Sync + Asset,
+    <T as Asset>::Data: for<'a> serde::Deserialize<'a>,
+{
+    if storage.read().unwrap().generation == *generation {
+        return false;
+    }
+    let mut storage = storage.write().unwrap();
+    *generation = storage.generation;
+    storage.load_handles(loader, asset_storage);
+    true
+}
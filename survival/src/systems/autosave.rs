@@ -0,0 +1,46 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::ecs::{LazyUpdate, Read, ReadExpect};
+use slog::slog_error;
+
+use crate::persistence;
+use crate::settings::{Config, Context};
+use crate::systems::time::TimeState;
+
+/// Autosaves the game each `autosave_interval` of game time, see `persistence::autosave`. The
+/// save is made once the frame's systems have run, as it needs the whole world.
+#[derive(Default)]
+pub struct System {
+    /// Game time of the last autosave, or of when the game was started or loaded.
+    last_save: Option<u64>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Read<'s, TimeState>,
+        ReadExpect<'s, Config>,
+        Read<'s, LazyUpdate>,
+    );
+
+    fn run(&mut self, (time, config, lazy): Self::SystemData) {
+        let interval = config.saves.autosave_interval;
+        let last_save = match self.last_save {
+            // A game loaded from before the last autosave starts counting again
+            Some(last_save) if last_save <= time.current_time => last_save,
+            _ => {
+                self.last_save = Some(time.current_time);
+                return;
+            }
+        };
+        if interval == 0 || time.current_time < last_save + interval {
+            return;
+        }
+
+        self.last_save = Some(time.current_time);
+        lazy.exec_mut(|world| {
+            if let Err(e) = persistence::autosave(world) {
+                let log = world.read_resource::<Context>().logs.root.clone();
+                slog_error!(log, "Failed to autosave: {}", e);
+            }
+        });
+    }
+}
@@ -0,0 +1,159 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::math::Vector3,
+    ecs::{storage::Tracked, Join, Read, ReadExpect, Resources, SystemData, Write},
+    shrev::{EventChannel, ReaderId},
+};
+use std::collections::HashSet;
+
+use crate::autotile::{neighbour_mask, neighbours, Autotile};
+use crate::components::{
+    AnimatedTile, ConstructionLayer, FlaggedSpriteRender, FluidLayer, TerrainLayer, TileLayerSprite,
+};
+use crate::tiles::{
+    ReadTiles, TileChangeEvent, TileId, TileStorage, Tiles, TrackedTiles, WriteTiles,
+};
+
+/// Picks the variant of the sprite of each `Autotile` tile in the terrain, construction and fluid
+/// layers by which of its neighbours are of the same kind, so walls join up and water and grass
+/// fringe their edges. The whole map is autotiled once the level loads, and after that only the
+/// neighbourhoods of tiles changed in those layers, or with a `TileChangeEvent`, are redone.
+/// Tiles with an `AnimatedTile` are left to their animation.
+#[derive(Default)]
+pub struct System {
+    terrain: TrackedTiles<TerrainLayer>,
+    construction: TrackedTiles<ConstructionLayer>,
+    fluid: TrackedTiles<FluidLayer>,
+    change_reader: Option<ReaderId<TileChangeEvent>>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        ReadExpect<'s, Tiles>,
+        Read<'s, EventChannel<TileChangeEvent>>,
+        WriteTiles<'s, TerrainLayer>,
+        WriteTiles<'s, ConstructionLayer>,
+        WriteTiles<'s, FluidLayer>,
+        ReadTiles<'s, AnimatedTile>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.change_reader =
+            Some(Write::<EventChannel<TileChangeEvent>>::fetch(&res).register_reader());
+    }
+
+    fn run(
+        &mut self,
+        (tiles, changes, mut terrain, mut construction, mut fluid, animations): Self::SystemData,
+    ) {
+        let changed = changes
+            .read(self.change_reader.as_mut().unwrap())
+            .map(|event| event.id)
+            .collect::<Vec<_>>();
+
+        update(
+            *tiles,
+            &mut terrain,
+            &mut self.terrain,
+            &animations,
+            &changed,
+        );
+        update(
+            *tiles,
+            &mut construction,
+            &mut self.construction,
+            &animations,
+            &changed,
+        );
+        update(*tiles, &mut fluid, &mut self.fluid, &animations, &changed);
+    }
+}
+
+/// Autotiles the neighbourhoods of the tiles of the layer `storage` changed since `tracked` last
+/// looked at it, and of those `changed` otherwise. The tile storages are only registered once the
+/// level loads, so the first time the layer is looked at it is autotiled whole.
+fn update<C>(
+    tiles: Tiles,
+    storage: &mut TileStorage<C>,
+    tracked: &mut TrackedTiles<C>,
+    animations: &ReadTiles<'_, AnimatedTile>,
+    changed: &[TileId],
+) where
+    C: TileLayerSprite,
+    C::Storage: Tracked,
+{
+    let events = match tracked.changed(storage) {
+        Some(dirty) => dirty
+            .join()
+            .map(|index| tiles.id_at(index))
+            .collect::<Vec<_>>(),
+        None => {
+            let everything = tiles
+                .iter_volume(
+                    Vector3::new(0, 0, 0),
+                    tiles.dimensions() - Vector3::new(1, 1, 1),
+                )
+                .collect::<HashSet<_>>();
+            refresh(tiles, storage, animations, &everything);
+            // The sprites just picked don't need their neighbourhoods redone
+            tracked.changed(storage);
+            return;
+        }
+    };
+
+    let dimensions = tiles.dimensions();
+    let mut neighbourhoods = HashSet::new();
+    for id in events.iter().chain(changed) {
+        let coord = tiles.coord(*id);
+        neighbourhoods.insert(*id);
+        for direction in &neighbours {
+            let (dx, dy) = direction.offset();
+            let (x, y) = (coord.x as i32 + dx, coord.y as i32 + dy);
+            if x >= 0 && y >= 0 && x < dimensions.x as i32 && y < dimensions.y as i32 {
+                neighbourhoods.insert(tiles.id(x as u32, y as u32, coord.z));
+            }
+        }
+    }
+    refresh(tiles, storage, animations, &neighbourhoods);
+}
+
+/// Sets the sprite of each `Autotile` tile of `ids` in the layer `storage` to its variant, leaving
+/// those already right, or animated, untouched so they aren't flagged again.
+fn refresh<C>(
+    tiles: Tiles,
+    storage: &mut TileStorage<C>,
+    animations: &ReadTiles<'_, AnimatedTile>,
+    ids: &HashSet<TileId>,
+) where
+    C: TileLayerSprite,
+{
+    for id in ids.iter().filter(|id| animations.get(**id).is_none()) {
+        let sprite = match storage.get(*id) {
+            Some(layer) => layer.sprite().clone(),
+            None => continue,
+        };
+        let kind = match Autotile::of(sprite.sprite_number) {
+            Some(kind) => kind,
+            None => continue,
+        };
+
+        let mask = neighbour_mask(tiles, tiles.coord(*id), |other| {
+            storage.get(other).map_or(false, |layer| {
+                layer.sprite().sprite_sheet == sprite.sprite_sheet
+                    && Autotile::of(layer.sprite().sprite_number) == Some(kind)
+            })
+        });
+        let sprite_number = kind.variant(mask);
+        if sprite_number != sprite.sprite_number {
+            storage.insert(
+                *id,
+                C::new(FlaggedSpriteRender {
+                    sprite_number,
+                    ..sprite
+                }),
+            );
+        }
+    }
+}
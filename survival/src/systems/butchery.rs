@@ -0,0 +1,278 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    assets::AssetStorage,
+    core::{
+        components::{Parent, Transform},
+        math::Vector3,
+    },
+    ecs::{
+        Entities, Entity, Join, LazyUpdate, Read, ReadExpect, ReadStorage, Resources, SystemData,
+        Write, WriteStorage,
+    },
+    shrev::EventChannel,
+};
+use std::collections::HashMap;
+
+use crate::actions::{Action, ActionResult, Target};
+use crate::assets::{self, item::Property, CreatureStorage, GetStorage};
+use crate::butchery::{self, butcher_time, yields, Corpse};
+use crate::components::{
+    self,
+    ai::{Blackboard, CurrentAction},
+    PathFailed, TilePosition,
+};
+use crate::goap::{ActionCatagory, Planner};
+use crate::initializers::{spawn_item, SpawnType};
+use crate::jobs::{JobBoard, JobId};
+use crate::pathfinding::distance;
+use crate::pathfinding::{MovementProfile, PathFlags, PathRequest, PathRequests};
+use crate::settings::Config;
+use crate::systems::time::TimeState;
+use crate::tiles::{TileEntities, Tiles, WriteTiles};
+use crate::utils::ComponentEventReader;
+
+/// A corpse being carried to a butcher spot, and butchered once there.
+#[derive(Clone, Copy, Debug)]
+struct Butchering {
+    corpse: Entity,
+    spot: Vector3<u32>,
+    /// When the butchering is done, once started.
+    until: Option<u64>,
+}
+
+/// Posts a job to butcher every `Corpse` left lying about, while there is a butcher spot to do it
+/// at. A `Butcher` event picks up the targeted corpse next to the pawn and carries it to the
+/// nearest butcher spot, where it is butchered into meat, hide and bone by the size of the
+/// creature it was. Giving up on the way sets the corpse down wherever the pawn is.
+#[derive(Default)]
+pub struct System {
+    action_reader: ComponentEventReader<components::Actionable, Action>,
+    orders: HashMap<Entity, JobId>,
+    butchering: HashMap<Entity, Butchering>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        ReadExpect<'s, Config>,
+        ReadExpect<'s, CreatureStorage>,
+        Read<'s, TimeState>,
+        Read<'s, LazyUpdate>,
+        Read<'s, AssetStorage<assets::Item>>,
+        Read<'s, PathRequests>,
+        Write<'s, JobBoard>,
+        Write<'s, EventChannel<ActionResult>>,
+        WriteTiles<'s, TileEntities>,
+        ReadStorage<'s, Blackboard>,
+        ReadStorage<'s, CurrentAction>,
+        ReadStorage<'s, PathFailed>,
+        ReadStorage<'s, components::Item>,
+        ReadStorage<'s, Corpse>,
+        WriteStorage<'s, Parent>,
+        WriteStorage<'s, TilePosition>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, components::Actionable>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.action_reader.setup(res);
+
+        Planner::setup_pawn_actions(res, butchery::actions());
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            config,
+            kind_storage,
+            time,
+            lazy,
+            details,
+            requests,
+            mut board,
+            mut results,
+            mut occupants,
+            blackboards,
+            current_actions,
+            path_failures,
+            items,
+            corpses,
+            mut parents,
+            mut tile_positions,
+            mut transforms,
+            mut actionables,
+        ): Self::SystemData,
+    ) {
+        let spots = (&items, &tile_positions, !&parents)
+            .join()
+            .filter(|(item, _, _)| {
+                details.get(&item.handle).map_or(false, |details| {
+                    details.properties.contains(&Property::ButcherSpot)
+                })
+            })
+            .map(|(_, position, _)| position.coord)
+            .collect::<Vec<_>>();
+
+        // Drop the jobs of corpses which are gone, and post them for those left lying about
+        let finished = self
+            .orders
+            .keys()
+            .filter(|corpse| !entities.is_alive(**corpse))
+            .cloned()
+            .collect::<Vec<_>>();
+        for corpse in finished {
+            board.complete(self.orders.remove(&corpse).unwrap());
+        }
+        if !spots.is_empty() {
+            let lying = (&entities, &corpses, &tile_positions, !&parents)
+                .join()
+                .map(|(corpse, _, position, _)| (corpse, position.coord))
+                .collect::<Vec<_>>();
+            for (corpse, coord) in lying {
+                let job = self.orders.get(&corpse).cloned();
+                if job.map_or(true, |job| board.get(job).is_none()) {
+                    let job = board.post(
+                        ActionCatagory::Butchery,
+                        vec![butchery::butchered()],
+                        Some(coord),
+                    );
+                    self.orders.insert(corpse, job);
+                }
+            }
+        }
+
+        // Pick up the corpse to butcher
+        self.action_reader.maintain(&entities, &mut actionables);
+        let mut butchers = Vec::new();
+        for (entity, actionable) in (&entities, &mut actionables).join() {
+            for action in self.action_reader.read(entity, actionable) {
+                if *action == Action::Butcher {
+                    butchers.push(entity);
+                }
+            }
+        }
+        for pawn in butchers {
+            let here = tile_positions.get(pawn).map(|position| position.coord);
+            let corpse = match blackboards
+                .get(pawn)
+                .and_then(|blackboard| blackboard.target)
+            {
+                Some(Target::Entity(corpse))
+                    if corpses.contains(corpse)
+                        && !parents.contains(corpse)
+                        && tile_positions.get(corpse).map_or(false, |position| {
+                            here.map_or(false, |here| distance(here, position.coord) <= 1)
+                        }) =>
+                {
+                    corpse
+                }
+                _ => {
+                    results.single_write(ActionResult::failed(pawn, Action::Butcher));
+                    continue;
+                }
+            };
+            let (here, spot) = match here.and_then(|here| {
+                let spot = spots.iter().min_by_key(|spot| distance(here, **spot))?;
+                Some((here, *spot))
+            }) {
+                Some(found) => found,
+                None => {
+                    results.single_write(ActionResult::failed(pawn, Action::Butcher));
+                    continue;
+                }
+            };
+
+            let coord = tile_positions.remove(corpse).unwrap().coord;
+            if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(coord)) {
+                occupants.0.remove(&corpse);
+            }
+            parents.insert(corpse, Parent { entity: pawn }).unwrap();
+            if let Some(transform) = transforms.get_mut(corpse) {
+                *transform = Transform::default();
+            }
+            requests.request(PathRequest {
+                entity: pawn,
+                start: here,
+                goal: spot,
+                flags: PathFlags::None,
+                profile: MovementProfile::default(),
+            });
+            self.butchering.insert(
+                pawn,
+                Butchering {
+                    corpse,
+                    spot,
+                    until: None,
+                },
+            );
+        }
+
+        // Butcher it once there, or set it down wherever the pawn gave up
+        let now = time.current_time;
+        let kinds = kind_storage.borrow();
+        let butchering = self.butchering.drain().collect::<Vec<_>>();
+        for (pawn, mut work) in butchering {
+            if !entities.is_alive(work.corpse) {
+                if entities.is_alive(pawn) {
+                    results.single_write(ActionResult::failed(pawn, Action::Butcher));
+                }
+                continue;
+            }
+            let here = tile_positions.get(pawn).map(|position| position.coord);
+            let working = entities.is_alive(pawn)
+                && current_actions
+                    .get(pawn)
+                    .map_or(false, |current| current.event == Action::Butcher);
+            if working && !path_failures.contains(pawn) && here == Some(work.spot) {
+                let until = *work.until.get_or_insert(now + butcher_time);
+                if now < until {
+                    self.butchering.insert(pawn, work);
+                    continue;
+                }
+
+                let size = corpses
+                    .get(work.corpse)
+                    .and_then(|corpse| kinds.data.get(&corpse.kind))
+                    .map_or(0.0, |kind| kind.size);
+                let products = yields(size);
+                let spot = work.spot;
+                lazy.exec_mut(move |world| {
+                    for (item, count) in &products {
+                        for _ in 0..*count {
+                            spawn_item(world, SpawnType::TilePosition(spot), item, None);
+                        }
+                    }
+                });
+                log::info!("{:?} butchered {:?}", pawn, work.corpse);
+                entities.delete(work.corpse).unwrap();
+                results.single_write(ActionResult::completed(pawn, Action::Butcher));
+                continue;
+            }
+            if working && !path_failures.contains(pawn) {
+                self.butchering.insert(pawn, work);
+                continue;
+            }
+
+            parents.remove(work.corpse);
+            if let Some(coord) = here {
+                tile_positions
+                    .insert(work.corpse, TilePosition::new(coord))
+                    .unwrap();
+                if let Some(transform) = transforms.get_mut(work.corpse) {
+                    transform.set_translation(tiles.tile_to_world(coord, &config));
+                }
+                if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(coord)) {
+                    occupants.0.insert(work.corpse);
+                }
+            }
+            if working {
+                results.single_write(ActionResult::failed(pawn, Action::Butcher));
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::{components::Transform, math::Vector3},
+    ecs::{Join, ReadExpect, ReadStorage, WriteStorage},
+    renderer::Camera,
+};
+
+use crate::components::PlayerControlled;
+use crate::settings::Config;
+
+/// Keeps the `PlayerControlled` entity in view, moving the camera only once the entity strays
+/// further than the configured deadzone from the centre of the view. The camera is left where it
+/// is until the entity moves, so it can be moved elsewhere, as by the minimap.
+#[derive(Default)]
+pub struct System {
+    last_target: Option<Vector3<f32>>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        ReadExpect<'s, Config>,
+        ReadStorage<'s, Camera>,
+        ReadStorage<'s, PlayerControlled>,
+        WriteStorage<'s, Transform>,
+    );
+
+    fn run(&mut self, (game_config, cameras, controlled, mut transforms): Self::SystemData) {
+        let target = match (&controlled, &transforms).join().next() {
+            Some((_, transform)) => *transform.translation(),
+            None => return,
+        };
+        if self.last_target == Some(target) {
+            return;
+        }
+        self.last_target = Some(target);
+
+        let deadzone = game_config.camera.deadzone;
+        for (_, transform) in (&cameras, &mut transforms).join() {
+            let x = follow(transform.translation().x, target.x, deadzone);
+            let y = follow(transform.translation().y, target.y, deadzone);
+            transform.set_translation_x(x);
+            transform.set_translation_y(y);
+        }
+    }
+}
+
+/// Where the camera moves along an axis to keep `target` within `deadzone` of it.
+fn follow(camera: f32, target: f32, deadzone: f32) -> f32 {
+    let offset = target - camera;
+    if offset > deadzone {
+        target - deadzone
+    } else if offset < -deadzone {
+        target + deadzone
+    } else {
+        camera
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadzone() {
+        assert_eq!(follow(0.0, 50.0, 100.0), 0.0);
+        assert_eq!(follow(0.0, -100.0, 100.0), 0.0);
+        assert_eq!(follow(0.0, 150.0, 100.0), 50.0);
+        assert_eq!(follow(0.0, -250.0, 100.0), -150.0);
+        assert_eq!(follow(10.0, 30.0, 0.0), 30.0);
+    }
+}
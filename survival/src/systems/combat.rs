@@ -0,0 +1,210 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    assets::AssetStorage,
+    ecs::{
+        Entities, Entity, Join, Read, ReadExpect, ReadStorage, Resources, SystemData, Write,
+        WriteStorage,
+    },
+    shrev::EventChannel,
+};
+use rand::Rng;
+
+use crate::actions::{Action, ActionResult, Target};
+use crate::assets::{self, item::Property, material::Materials, Localization};
+use crate::combat::{
+    self, armor_slot, hit_chance, skill_force, unarmed_quality, CombatEvent, CombatOutcome,
+};
+use crate::components::{
+    self,
+    ai::{Blackboard, CurrentAction, Skills},
+    equipment::Equipped,
+    TilePosition, Wear,
+};
+use crate::decals::{add_decal, Decal, Decals};
+use crate::goap::{ActionCatagory, Planner};
+use crate::injury::{Blow, Body};
+use crate::pathfinding::distance;
+use crate::systems::durability::tool_wear;
+use crate::tiles::{Tiles, WriteTiles};
+use crate::utils::ComponentEventReader;
+
+/// Adds the planner actions for fighting to every `Pawn`, and resolves each `Attack` against the
+/// `Body` the `Blackboard` targets next to the attacker. Whether the blow lands depends on the
+/// combat skill of both sides, and how hard and sharp it is on the best `Hitting` or `Cutting`
+/// property of the weapon held and what it is made of, less the armor over the part struck. A
+/// `CombatEvent` is sent for every swing, and wounding blows spatter blood on the defender's tile.
+#[derive(Default)]
+pub struct System {
+    action_reader: ComponentEventReader<components::Actionable, Action>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        Read<'s, AssetStorage<assets::Item>>,
+        Read<'s, Materials>,
+        Read<'s, Localization>,
+        Write<'s, EventChannel<ActionResult>>,
+        Write<'s, EventChannel<CombatEvent>>,
+        Write<'s, EventChannel<Wear>>,
+        ReadStorage<'s, Blackboard>,
+        ReadStorage<'s, CurrentAction>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, Skills>,
+        ReadStorage<'s, Equipped>,
+        ReadStorage<'s, components::Item>,
+        WriteStorage<'s, Body>,
+        WriteStorage<'s, components::Actionable>,
+        WriteTiles<'s, Decals>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.action_reader.setup(res);
+
+        Planner::setup_pawn_actions(res, combat::actions());
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            details,
+            materials,
+            locale,
+            mut results,
+            mut combat_log,
+            mut wear,
+            blackboards,
+            current_actions,
+            tile_positions,
+            skills,
+            equipped,
+            items,
+            mut bodies,
+            mut actionables,
+            mut decals,
+        ): Self::SystemData,
+    ) {
+        self.action_reader.maintain(&entities, &mut actionables);
+        let mut attacks = Vec::new();
+        for (entity, actionable) in (&entities, &mut actionables).join() {
+            for action in self.action_reader.read(entity, actionable) {
+                if *action == Action::Attack {
+                    attacks.push(entity);
+                }
+            }
+        }
+
+        let mut rng = rand::thread_rng();
+        for attacker in attacks {
+            let here = tile_positions.get(attacker).map(|position| position.coord);
+            let defender = match blackboards
+                .get(attacker)
+                .and_then(|blackboard| blackboard.target)
+            {
+                Some(Target::Entity(defender))
+                    if bodies.contains(defender)
+                        && tile_positions.get(defender).map_or(false, |position| {
+                            here.map_or(false, |here| distance(here, position.coord) <= 1)
+                        }) =>
+                {
+                    defender
+                }
+                _ => {
+                    results.single_write(ActionResult::failed(attacker, Action::Attack));
+                    continue;
+                }
+            };
+
+            // The held weapon, struck with whichever of its edge or weight is better
+            let weapon = equipped
+                .get(attacker)
+                .and_then(Equipped::held)
+                .and_then(|held| Some((held, items.get(held)?)))
+                .and_then(|(held, item)| {
+                    let details = details.get(&item.handle)?;
+                    let (quality, cutting) = details
+                        .properties
+                        .iter()
+                        .chain(item.properties.iter())
+                        .filter_map(|property| match property {
+                            Property::Cutting(quality) => Some((quality.into_inner(), true)),
+                            Property::Hitting(quality) => Some((quality.into_inner(), false)),
+                            _ => None,
+                        })
+                        .max_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap())?;
+                    let material = details
+                        .material
+                        .as_ref()
+                        .and_then(|material| materials.get(material));
+                    let name = locale.get(&details.name).to_string();
+                    Some((held, name, quality, cutting, material))
+                });
+
+            let skill = |entity| {
+                skills
+                    .get(entity)
+                    .map_or(0.0, |skills| skills.level(&ActionCatagory::Combat))
+            };
+            let (attacker_skill, defender_skill) = (skill(attacker), skill(defender));
+
+            let outcome = if rng.gen::<f32>() < hit_chance(attacker_skill, defender_skill) {
+                let body = bodies.get_mut(defender).unwrap();
+                let part = body.part_at(rng.gen());
+                let armor = equipped.get(defender).map_or(0.0, |equipped| {
+                    equipped.armor(armor_slot(body.parts[part].kind))
+                });
+                let force = 1.0 + attacker_skill * skill_force;
+                let blow = match &weapon {
+                    Some((_, _, quality, cutting, material)) => {
+                        Blow::new(quality * force, *cutting, *material)
+                    }
+                    None => Blow::new(unarmed_quality * force, false, None),
+                }
+                .through(armor);
+
+                let wounds = body
+                    .injure(part, &blow)
+                    .into_iter()
+                    .map(|(part, wound)| (body.parts[part].name.clone(), wound))
+                    .collect::<Vec<_>>();
+                // Wounds bleed onto the ground
+                if !wounds.is_empty() {
+                    if let Some(position) = tile_positions.get(defender) {
+                        let id = tiles.id_from_vector(position.coord);
+                        add_decal(&mut decals, id, Decal::Blood);
+                    }
+                }
+                CombatOutcome::Hit {
+                    part: body.parts[part].name.clone(),
+                    wounds,
+                }
+            } else {
+                CombatOutcome::Dodged
+            };
+
+            if let Some((item, _, _, _, _)) = &weapon {
+                wear.single_write(Wear {
+                    item: *item,
+                    amount: tool_wear,
+                });
+            }
+            let event = CombatEvent {
+                attacker,
+                defender,
+                weapon: weapon.map(|(_, name, _, _, _)| name),
+                outcome,
+            };
+            log::info!("{}", event);
+            combat_log.single_write(event);
+
+            if !current_actions.contains(attacker) {
+                results.single_write(ActionResult::completed(attacker, Action::Attack));
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{Read, ReadExpect, Resources, SystemData, Write},
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::components::{Doorway, Obstruction};
+use crate::pathfinding::Connectivity;
+use crate::tiles::{ReadTiles, TileChangeEvent, Tiles};
+
+/// Keeps the `Connectivity` labeling in sync with tile passability. Doorways count as passable
+/// whether their door is open or shut, unless it is locked.
+#[derive(Default)]
+pub struct System {
+    change_reader: Option<ReaderId<TileChangeEvent>>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        ReadExpect<'s, Tiles>,
+        Read<'s, EventChannel<TileChangeEvent>>,
+        ReadTiles<'s, Obstruction>,
+        ReadTiles<'s, Doorway>,
+        Write<'s, Connectivity>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.change_reader =
+            Some(Write::<EventChannel<TileChangeEvent>>::fetch(&res).register_reader());
+    }
+
+    fn run(
+        &mut self,
+        (tiles, changes, obstructions, doorways, mut connectivity): Self::SystemData,
+    ) {
+        let passable = |id| match (obstructions.get(id), doorways.get(id)) {
+            (Some(Obstruction::Impassable), Some(doorway)) => !doorway.locked,
+            (Some(Obstruction::Impassable), None) => false,
+            _ => true,
+        };
+
+        let events = changes.read(self.change_reader.as_mut().unwrap());
+        if !connectivity.is_built() {
+            connectivity.rebuild(*tiles, passable);
+            return;
+        }
+
+        for event in events {
+            connectivity.update(*tiles, event.id, passable);
+        }
+    }
+}
@@ -0,0 +1,297 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    assets::{AssetStorage, Handle},
+    core::ParentHierarchy,
+    ecs::{
+        world::Index, Entities, Entity, Join, LazyUpdate, Read, ReadExpect, ReadStorage, Resources,
+        SystemData, Write, WriteStorage,
+    },
+    shrev::EventChannel,
+};
+use std::collections::HashMap;
+
+use crate::actions::{Action, ActionResult};
+use crate::assets::{
+    self, recipe::satisfies, GetStorage, ItemStorage, Recipe, RecipeStorage, Storage, Workshop,
+    WorkshopStorage,
+};
+use crate::components::{
+    self,
+    ai::{CurrentAction, Skills, AI},
+    Container, TilePosition, Wear,
+};
+use crate::cooking::{cook, meal_quality, Meal};
+use crate::goap::{ActionCatagory, Planner};
+use crate::initializers::{spawn_item, SpawnType};
+use crate::inventory;
+use crate::jobs::{CraftOrders, JobBoard};
+use crate::pathfinding::distance;
+use crate::systems::durability::tool_wear;
+use crate::temperature::HeatSource;
+use crate::utils::ComponentEventReader;
+
+/// Recipe keys of the planner actions crafting them, which any `Pawn` may take.
+#[derive(Default)]
+pub struct RecipeActions(HashMap<Index, String>);
+impl RecipeActions {
+    /// Adds an action crafting each of `recipes` to `planner`, once they are loaded.
+    pub fn insert(planner: &mut Planner, recipes: &Storage<Recipe>) -> Self {
+        Self(
+            recipes
+                .data
+                .iter()
+                .map(|(key, recipe)| (planner.insert_pawn_action(recipe.action()), key.clone()))
+                .collect(),
+        )
+    }
+}
+
+/// Posts `CraftOrders` as jobs. A `Craft` event makes the recipe of the action the entity is
+/// carrying out, if the ingredients are carried or next to it, along with any tool, workstation and
+/// heat needed. A `Workshop` allowing the recipe serves as its workstation, and speeds the work up.
+/// The ingredients are used up, the tool is worn, and what is made is given to the crafter, or put
+/// down where they stand if it is a workshop they built. Food is cooked into a `Meal` with the
+/// nutrition of its ingredients, as good as the cooking skill of the crafter.
+#[derive(Default)]
+pub struct System {
+    action_reader: ComponentEventReader<components::Actionable, Action>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, ParentHierarchy>,
+        ReadExpect<'s, ItemStorage>,
+        ReadExpect<'s, RecipeStorage>,
+        ReadExpect<'s, WorkshopStorage>,
+        Read<'s, AssetStorage<assets::Item>>,
+        Read<'s, LazyUpdate>,
+        Read<'s, RecipeActions>,
+        Write<'s, JobBoard>,
+        Write<'s, CraftOrders>,
+        Write<'s, EventChannel<ActionResult>>,
+        Write<'s, EventChannel<Wear>>,
+        ReadStorage<'s, AI>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, Container>,
+        ReadStorage<'s, components::Item>,
+        ReadStorage<'s, Skills>,
+        ReadStorage<'s, HeatSource>,
+        ReadStorage<'s, Meal>,
+        WriteStorage<'s, CurrentAction>,
+        WriteStorage<'s, components::Actionable>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.action_reader.setup(res);
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            hierarchy,
+            item_storage,
+            recipe_storage,
+            workshop_storage,
+            details,
+            lazy,
+            recipe_actions,
+            mut board,
+            mut orders,
+            mut results,
+            mut wear,
+            ais,
+            tile_positions,
+            containers,
+            items,
+            skills,
+            heat_sources,
+            meals,
+            mut current_actions,
+            mut actionables,
+        ): Self::SystemData,
+    ) {
+        let recipes = recipe_storage.borrow();
+
+        for key in orders.drain() {
+            match recipes.data.get(&key) {
+                Some(recipe) => {
+                    board.post(recipe.catagory.clone(), recipe.goal_conditions(), None);
+                }
+                None => log::warn!("Ordered unknown recipe {:?}", key),
+            }
+        }
+
+        self.action_reader.maintain(&entities, &mut actionables);
+        let mut requests = Vec::new();
+        for (entity, actionable) in (&entities, &mut actionables).join() {
+            for action in self.action_reader.read(entity, actionable) {
+                if *action == Action::Craft {
+                    requests.push(entity);
+                }
+            }
+        }
+        if requests.is_empty() {
+            return;
+        }
+
+        let item_storage = item_storage.borrow();
+        let names = item_storage
+            .handles
+            .iter()
+            .map(|(name, handle)| (handle, name.as_str()))
+            .collect::<HashMap<&Handle<assets::Item>, &str>>();
+        let workshop_storage = workshop_storage.borrow();
+        let workshops = workshop_storage
+            .data
+            .values()
+            .map(|workshop| (workshop.item.as_str(), workshop))
+            .collect::<HashMap<&str, &Workshop>>();
+
+        for entity in requests {
+            let recipe = ais
+                .get(entity)
+                .and_then(|ai| ai.current)
+                .and_then(|action| recipe_actions.0.get(&action))
+                .and_then(|key| Some((key.as_str(), recipes.data.get(key)?)));
+            let (key, recipe) = match recipe {
+                Some(recipe) => recipe,
+                None => {
+                    results.single_write(ActionResult::failed(entity, Action::Craft));
+                    continue;
+                }
+            };
+
+            // Everything carried, and lying loose next to the crafter
+            let carried = inventory::get_all_items(entity, &hierarchy, &containers, &items);
+            let here = tile_positions.get(entity).map(|position| position.coord);
+            let nearby = (&entities, &items, &tile_positions)
+                .join()
+                .filter(|(item, _, position)| {
+                    hierarchy.parent(*item).is_none()
+                        && here.map_or(false, |here| distance(here, position.coord) <= 1)
+                })
+                .map(|(item, _, _)| item)
+                .collect::<Vec<_>>();
+            let describe = |item: Entity| {
+                let component = items.get(item)?;
+                Some((
+                    item,
+                    *names.get(&component.handle)?,
+                    details.get(&component.handle)?,
+                    component.properties.as_slice(),
+                ))
+            };
+            let held = (&entities, &carried)
+                .join()
+                .filter_map(|(item, _)| describe(item))
+                .collect::<Vec<_>>();
+            let around = nearby
+                .iter()
+                .filter_map(|item| describe(*item))
+                .collect::<Vec<_>>();
+
+            let tool = held
+                .iter()
+                .find(|(_, _, details, _)| details.interactions.contains(recipe.tool))
+                .map(|(tool, _, _, _)| *tool);
+            let has_tool = recipe.tool.is_empty() || tool.is_some();
+            // A workshop allowing the recipe which the crafter is on or next to
+            let workshop = here.and_then(|here| {
+                (&entities, &items, &tile_positions)
+                    .join()
+                    .filter(|(item, _, _)| hierarchy.parent(*item).is_none())
+                    .filter_map(|(_, item, position)| {
+                        Some((*workshops.get(names.get(&item.handle)?)?, position.coord))
+                    })
+                    .find(|(workshop, origin)| {
+                        workshop.allows(key) && workshop.reaches(*origin, here)
+                    })
+                    .map(|(workshop, _)| workshop)
+            });
+            let at_workstation = workshop.is_some()
+                || recipe.workstation.as_ref().map_or(true, |workstation| {
+                    around.iter().any(|(_, _, details, properties)| {
+                        details
+                            .properties
+                            .iter()
+                            .chain(properties.iter())
+                            .any(|property| satisfies(property, workstation))
+                    })
+                });
+            let heated = !recipe.heat
+                || (&heat_sources, &tile_positions)
+                    .join()
+                    .any(|(_, position)| {
+                        here.map_or(false, |here| distance(here, position.coord) <= 1)
+                    });
+            if !has_tool || !at_workstation || !heated {
+                results.single_write(ActionResult::failed(entity, Action::Craft));
+                continue;
+            }
+            let ingredients = match recipe.select(held.iter().chain(around.iter()).cloned()) {
+                Some(ingredients) => ingredients,
+                None => {
+                    results.single_write(ActionResult::failed(entity, Action::Craft));
+                    continue;
+                }
+            };
+
+            // Cooking combines the nutrition of what goes in
+            let meal = item_storage.data.get(&recipe.output).and_then(|output| {
+                let base = output.food?;
+                let foods = ingredients
+                    .iter()
+                    .filter_map(|ingredient| {
+                        meals.get(*ingredient).map(|meal| meal.0).or_else(|| {
+                            let component = items.get(*ingredient)?;
+                            details.get(&component.handle)?.food
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let skill = skills
+                    .get(entity)
+                    .map_or(0.0, |skills| skills.level(&ActionCatagory::Cooking));
+                Some(Meal(cook(&base, &foods, meal_quality(skill))))
+            });
+
+            for ingredient in ingredients {
+                entities.delete(ingredient).unwrap();
+            }
+            if let (Some(item), false) = (tool, recipe.tool.is_empty()) {
+                wear.single_write(Wear {
+                    item,
+                    amount: tool_wear,
+                });
+            }
+            if let (Some(workshop), Some(current)) = (workshop, current_actions.get_mut(entity)) {
+                let speed = workshop.speed(&recipe.catagory).max(0.01);
+                current.required = (current.required as f32 / speed).ceil() as u64;
+            }
+
+            let (output, count) = (recipe.output.clone(), recipe.output_count);
+            let spawn_type = match here {
+                Some(here) if workshops.contains_key(output.as_str()) => {
+                    SpawnType::TilePosition(here)
+                }
+                _ => SpawnType::Parent(entity),
+            };
+            lazy.exec_mut(move |world| {
+                for _ in 0..count {
+                    let item = spawn_item(world, spawn_type, &output, None);
+                    if let Some(meal) = meal {
+                        world.write_storage::<Meal>().insert(item, meal).unwrap();
+                    }
+                }
+            });
+
+            // Pawns finish once the time the recipe takes is up
+            if !current_actions.contains(entity) {
+                results.single_write(ActionResult::completed(entity, Action::Craft));
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::math::Vector3,
+    ecs::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, WriteStorage},
+};
+use rand::Rng;
+use std::collections::HashSet;
+
+use crate::assets::{CreatureStorage, GetStorage};
+use crate::components::{
+    ai::{Creature, CreatureState, Needs, Pawn},
+    Obstruction, Path, TilePosition,
+};
+use crate::pathfinding::{
+    distance, tile_cost, DijkstraCollection, DijkstraMapType, MovementProfile, PathFlags,
+    PathRequest, PathRequests,
+};
+use crate::systems::time::TimeState;
+use crate::tiles::{ReadTiles, Tiles};
+
+/// Threats are tracked out to this distance, past any creature's flee distance.
+const threat_range: f32 = 32.0;
+/// Tiles of the flee map followed before checking for threats again.
+const flee_steps: usize = 8;
+/// How much grazing satisfies hunger.
+const graze_satiation: f32 = 0.25;
+
+/// Drives wildlife: creatures wander around their home and graze, flee from pawns and predators
+/// down a shared flee map, and predators hunt their prey when hungry.
+#[derive(Default)]
+pub struct System {
+    threats: DijkstraCollection,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        Read<'s, TimeState>,
+        ReadExpect<'s, CreatureStorage>,
+        Read<'s, PathRequests>,
+        ReadTiles<'s, Obstruction>,
+        ReadStorage<'s, Pawn>,
+        ReadStorage<'s, TilePosition>,
+        WriteStorage<'s, Needs>,
+        WriteStorage<'s, Creature>,
+        WriteStorage<'s, Path>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            time,
+            kinds,
+            requests,
+            obstructions,
+            pawns,
+            tile_positions,
+            mut needs,
+            mut creatures,
+            mut paths,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+        let kinds = kinds.borrow();
+        let cost = |id| tile_cost(obstructions.get(id));
+
+        // Everything a creature may meet, by kind, and every threat
+        let others = (&entities, &creatures, &tile_positions)
+            .join()
+            .map(|(entity, creature, position)| (entity, creature.kind.clone(), position.coord))
+            .collect::<Vec<_>>();
+        let threats = (&entities, &tile_positions)
+            .join()
+            .filter(|(entity, _)| {
+                pawns.contains(*entity)
+                    || creatures.get(*entity).map_or(false, |creature| {
+                        kinds
+                            .data
+                            .get(&creature.kind)
+                            .map_or(false, |kind| kind.is_predator())
+                    })
+            })
+            .map(|(_, position)| position.coord)
+            .collect::<Vec<_>>();
+        self.threats.build(
+            DijkstraMapType::Threat,
+            *tiles,
+            &threats,
+            threat_range,
+            &cost,
+        );
+        self.threats.build_flee(
+            &DijkstraMapType::Threat,
+            DijkstraMapType::Flee,
+            *tiles,
+            &cost,
+        );
+
+        let mut rng = rand::thread_rng();
+        let mut eaten = HashSet::new();
+        for (entity, creature, position, mut needs) in (
+            &entities,
+            &mut creatures,
+            &tile_positions,
+            (&mut needs).maybe(),
+        )
+            .join()
+        {
+            if eaten.contains(&entity) {
+                continue;
+            }
+            let kind = match kinds.data.get(&creature.kind) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            let here = position.coord;
+            let idle = paths.get(entity).map_or(true, Path::is_finished);
+
+            // Fleeing overrides everything else
+            let threat = self.threats.value(&DijkstraMapType::Threat, here);
+            if let (Some(flee_distance), Some(threat)) = (kind.flee_distance, threat) {
+                if threat <= flee_distance as f32 {
+                    let was_fleeing = creature.state == CreatureState::Flee;
+                    creature.state = CreatureState::Flee;
+                    if idle || !was_fleeing {
+                        let route = self.flee_route(*tiles, here);
+                        if route.len() > 1 {
+                            paths.insert(entity, Path::new(route, 0.0)).unwrap();
+                        }
+                    }
+                    continue;
+                }
+            }
+            if creature.state == CreatureState::Flee {
+                creature.state = CreatureState::Wander;
+            }
+
+            let hungry = needs
+                .as_ref()
+                .map_or(false, |needs| needs.hunger.is_urgent());
+            if kind.is_predator() && hungry {
+                let prey = others
+                    .iter()
+                    .filter(|(other, other_kind, _)| {
+                        *other != entity && !eaten.contains(other) && kind.prey.contains(other_kind)
+                    })
+                    .map(|(other, _, coord)| (*other, *coord, distance(here, *coord)))
+                    .filter(|(_, _, d)| *d <= kind.sight_range)
+                    .min_by_key(|(_, _, d)| *d);
+                if let Some((prey, coord, d)) = prey {
+                    creature.state = CreatureState::Hunt(prey);
+                    if d <= 1 {
+                        entities.delete(prey).unwrap();
+                        eaten.insert(prey);
+                        if let Some(needs) = needs.as_mut() {
+                            needs.hunger.satisfy(1.0);
+                        }
+                        creature.state = CreatureState::Wander;
+                    } else if paths.get(entity).and_then(Path::goal) != Some(coord) {
+                        request_path(&requests, entity, here, coord);
+                    }
+                    continue;
+                }
+            }
+
+            match creature.state {
+                CreatureState::Graze { until } => {
+                    if now >= until {
+                        if let Some(needs) = needs.as_mut() {
+                            needs.hunger.satisfy(graze_satiation);
+                        }
+                        creature.state = CreatureState::Wander;
+                    }
+                }
+                CreatureState::Wander | CreatureState::Flee | CreatureState::Hunt(_) => {
+                    if !idle {
+                        continue;
+                    }
+                    if kind.graze_time > 0 && creature.state == CreatureState::Wander {
+                        creature.state = CreatureState::Graze {
+                            until: now + kind.graze_time,
+                        };
+                        paths.remove(entity);
+                        continue;
+                    }
+
+                    creature.state = CreatureState::Wander;
+                    let target = wander_target(*tiles, creature.home, kind.wander_radius, &mut rng);
+                    request_path(&requests, entity, here, target);
+                }
+            }
+        }
+    }
+}
+
+impl System {
+    /// Tiles down the flee map from `from`, starting with `from` itself.
+    fn flee_route(&self, tiles: Tiles, from: Vector3<u32>) -> Vec<Vector3<u32>> {
+        let mut route = vec![from];
+        while route.len() <= flee_steps {
+            match self
+                .threats
+                .descend(&DijkstraMapType::Flee, tiles, *route.last().unwrap())
+            {
+                Some(next) => route.push(next),
+                None => break,
+            }
+        }
+        route
+    }
+}
+
+fn request_path(requests: &PathRequests, entity: Entity, start: Vector3<u32>, goal: Vector3<u32>) {
+    requests.request(PathRequest {
+        entity,
+        start,
+        goal,
+        flags: PathFlags::None,
+        profile: MovementProfile::default(),
+    });
+}
+
+fn wander_target<R: Rng>(
+    tiles: Tiles,
+    home: Vector3<u32>,
+    radius: u32,
+    rng: &mut R,
+) -> Vector3<u32> {
+    let dimensions = tiles.dimensions();
+    let mut offset = |center: u32, max: u32| {
+        let low = center.saturating_sub(radius);
+        let high = (center + radius).min(max - 1);
+        rng.gen_range(low, high + 1)
+    };
+    let x = offset(home.x, dimensions.x);
+    let y = offset(home.y, dimensions.y);
+    Vector3::new(x, y, home.z)
+}
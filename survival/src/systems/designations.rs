@@ -0,0 +1,347 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::Transform,
+    ecs::{
+        Entities, Entity, Join, LazyUpdate, Read, ReadExpect, ReadStorage, Resources, SystemData,
+        Write, WriteStorage,
+    },
+    shrev::{EventChannel, ReaderId},
+};
+use std::collections::HashMap;
+
+use crate::actions::{Action, ActionOutcome, ActionResult, Target};
+use crate::assets::{GetStorage, PlantStorage};
+use crate::components::{
+    self,
+    ai::{Blackboard, CurrentAction},
+    ConstructionLayer, Obstruction, TilePosition, Tree,
+};
+use crate::designations::{self, mined_item, Designated, Designation};
+use crate::farming::Plant;
+use crate::goap::Planner;
+use crate::initializers::{spawn_item, SpawnType};
+use crate::jobs::{JobBoard, JobId};
+use crate::pathfinding::distance;
+use crate::settings::Config;
+use crate::tiles::{TileChangeEvent, TileEntities, TileId, Tiles, WriteTiles};
+use crate::utils::ComponentEventReader;
+
+/// A designated tile being worked, and the job posted for it.
+struct Order {
+    designation: Designation,
+    /// What the work is done on, such as the tree on the tile.
+    target: Entity,
+    /// Whether `target` was created just to mark the tile.
+    marker: bool,
+    job: JobId,
+}
+
+/// Work on the target of an order, done once the action doing it completes.
+struct Work {
+    worker: Entity,
+    action: Action,
+    tile: TileId,
+    target: Entity,
+}
+
+/// Turns every tile in the `Designation` tile storage into a job, marking what it is worked on as
+/// `Designated` so pawns can find it: the tree or plant on the tile, or an entity created to mark
+/// rock to mine or a construction to take down. Designations with nothing to work on are cleared,
+/// as are those once the work is done, and erasing one takes its job off the board. `Mine`,
+/// `Gather` and `Deconstruct` events start work on the targeted designation next to the pawn,
+/// which is done once the action completes, while chopping is left to `TreeSystem`.
+#[derive(Default)]
+pub struct System {
+    action_reader: ComponentEventReader<components::Actionable, Action>,
+    result_reader: Option<ReaderId<ActionResult>>,
+    orders: HashMap<TileId, Order>,
+    /// The work of each entity part way through working a designation.
+    working: HashMap<Entity, Work>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        ReadExpect<'s, Config>,
+        ReadExpect<'s, PlantStorage>,
+        Read<'s, LazyUpdate>,
+        Write<'s, JobBoard>,
+        Write<'s, EventChannel<ActionResult>>,
+        Write<'s, EventChannel<TileChangeEvent>>,
+        WriteTiles<'s, Designation>,
+        WriteTiles<'s, Obstruction>,
+        WriteTiles<'s, ConstructionLayer>,
+        WriteTiles<'s, TileEntities>,
+        ReadStorage<'s, Blackboard>,
+        ReadStorage<'s, CurrentAction>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, Tree>,
+        ReadStorage<'s, Plant>,
+        WriteStorage<'s, Designated>,
+        WriteStorage<'s, components::Actionable>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.action_reader.setup(res);
+        self.result_reader =
+            Some(Write::<EventChannel<ActionResult>>::fetch(&res).register_reader());
+
+        Planner::setup_pawn_actions(res, designations::actions());
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            config,
+            plant_storage,
+            lazy,
+            mut board,
+            mut results,
+            mut changes,
+            mut designations,
+            mut obstructions,
+            mut constructions,
+            mut occupants,
+            blackboards,
+            current_actions,
+            tile_positions,
+            trees,
+            plants,
+            mut designated,
+            mut actionables,
+        ): Self::SystemData,
+    ) {
+        // Drop orders which were erased, or whose work is done
+        let finished = self
+            .orders
+            .iter()
+            .filter(|(id, order)| {
+                designations.get(**id) != Some(&order.designation)
+                    || !entities.is_alive(order.target)
+            })
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in finished {
+            let order = self.orders.remove(&id).unwrap();
+            board.complete(order.job);
+            if !entities.is_alive(order.target) {
+                if designations.get(id) == Some(&order.designation) {
+                    designations.remove(id);
+                }
+            } else if order.marker {
+                entities.delete(order.target).unwrap();
+                if let Some(occupants) = occupants.get_mut(id) {
+                    occupants.0.remove(&order.target);
+                }
+            } else {
+                designated.remove(order.target);
+            }
+        }
+
+        // Take up new designations, and repost the jobs of those still to do
+        let painted = designations
+            .ids()
+            .filter_map(|id| Some((id, *designations.get(id)?)))
+            .collect::<Vec<_>>();
+        for (id, designation) in painted {
+            let coord = tiles.coord(id);
+            if let Some(order) = self.orders.get_mut(&id) {
+                if board.get(order.job).is_none() {
+                    order.job = board.post(
+                        designation.catagory(),
+                        vec![designations::worked(designation)],
+                        Some(coord),
+                    );
+                }
+                continue;
+            }
+
+            let on_tile = |is: &dyn Fn(Entity) -> bool| {
+                occupants
+                    .get(id)
+                    .and_then(|occupants| occupants.0.iter().cloned().find(|entity| is(*entity)))
+            };
+            let target = match designation {
+                Designation::ChopTree => on_tile(&|entity| trees.contains(entity)),
+                Designation::GatherPlants => on_tile(&|entity| plants.contains(entity)),
+                Designation::Mine => match (obstructions.get(id), constructions.get(id)) {
+                    (Some(Obstruction::Impassable), None) => Some(entities.create()),
+                    _ => None,
+                },
+                Designation::Deconstruct => constructions.get(id).map(|_| entities.create()),
+            };
+            let target = match target {
+                Some(target) => target,
+                None => {
+                    designations.remove(id);
+                    continue;
+                }
+            };
+
+            let marker = match designation {
+                Designation::Mine | Designation::Deconstruct => true,
+                Designation::ChopTree | Designation::GatherPlants => false,
+            };
+            if marker {
+                let mut transform = Transform::default();
+                transform.set_translation(tiles.tile_to_world(coord, &config));
+                lazy.insert(target, transform);
+                lazy.insert(target, TilePosition::new(coord));
+                if let Some(occupants) = occupants.get_mut(id) {
+                    occupants.0.insert(target);
+                }
+            }
+            designated.insert(target, Designated(designation)).unwrap();
+
+            let job = board.post(
+                designation.catagory(),
+                vec![designations::worked(designation)],
+                Some(coord),
+            );
+            self.orders.insert(
+                id,
+                Order {
+                    designation,
+                    target,
+                    marker,
+                    job,
+                },
+            );
+        }
+
+        let mut work = Vec::new();
+        for result in results.read(self.result_reader.as_mut().unwrap()) {
+            match result.action {
+                Action::Mine | Action::Gather | Action::Deconstruct => (),
+                _ => continue,
+            }
+            if let Some(started) = self.working.remove(&result.entity) {
+                if result.outcome == ActionOutcome::Completed {
+                    work.push(started);
+                }
+            }
+        }
+
+        self.action_reader.maintain(&entities, &mut actionables);
+        let mut requests = Vec::new();
+        for (entity, actionable) in (&entities, &mut actionables).join() {
+            for action in self.action_reader.read(entity, actionable) {
+                match action {
+                    Action::Mine | Action::Gather | Action::Deconstruct => {
+                        requests.push((entity, *action))
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        // What gathering a plant yields, if it is ripe
+        let kinds = plant_storage.borrow();
+        let yields = |target: Entity| {
+            let plant = plants.get(target)?;
+            let kind = kinds.data.get(&plant.kind)?;
+            if kind.is_ripe(plant.stage) {
+                Some([
+                    (kind.harvest.clone(), kind.harvest_count),
+                    (kind.seed.clone(), kind.seed_count),
+                ])
+            } else {
+                None
+            }
+        };
+
+        for (entity, action) in requests {
+            let here = tile_positions.get(entity).map(|position| position.coord);
+            let order = match blackboards
+                .get(entity)
+                .and_then(|blackboard| blackboard.target)
+            {
+                Some(Target::Entity(target)) => self
+                    .orders
+                    .iter()
+                    .find(|(_, order)| {
+                        order.target == target && order.designation.event() == action
+                    })
+                    .map(|(id, order)| (*id, order.target)),
+                _ => None,
+            };
+            let (tile, target) = match order {
+                Some((id, target))
+                    if here.map_or(false, |here| distance(here, tiles.coord(id)) <= 1)
+                        && (action != Action::Gather || yields(target).is_some()) =>
+                {
+                    (id, target)
+                }
+                _ => {
+                    results.single_write(ActionResult::failed(entity, action));
+                    continue;
+                }
+            };
+
+            let started = Work {
+                worker: entity,
+                action,
+                tile,
+                target,
+            };
+            if current_actions.contains(entity) {
+                self.working.insert(entity, started);
+            } else {
+                work.push(started);
+                results.single_write(ActionResult::completed(entity, action));
+            }
+        }
+
+        for Work {
+            worker,
+            action,
+            tile: id,
+            target,
+        } in work
+        {
+            // Worked by someone else first
+            if !entities.is_alive(target) {
+                continue;
+            }
+            let coord = tiles.coord(id);
+            match action {
+                Action::Mine => {
+                    obstructions.remove(id);
+                    changes.single_write(TileChangeEvent { id });
+                    lazy.exec_mut(move |world| {
+                        spawn_item(world, SpawnType::TilePosition(coord), mined_item, None);
+                    });
+                }
+                Action::Deconstruct => {
+                    constructions.remove(id);
+                    obstructions.remove(id);
+                    changes.single_write(TileChangeEvent { id });
+                }
+                Action::Gather => match yields(target) {
+                    Some(yields) => {
+                        lazy.exec_mut(move |world| {
+                            for (item, count) in &yields {
+                                for _ in 0..*count {
+                                    spawn_item(world, SpawnType::Parent(worker), item, None);
+                                }
+                            }
+                        });
+                    }
+                    None => continue,
+                },
+                _ => continue,
+            }
+
+            // The work is done, which clears the designation
+            entities.delete(target).unwrap();
+            if let Some(occupants) = occupants.get_mut(id) {
+                occupants.0.remove(&target);
+            }
+        }
+    }
+}
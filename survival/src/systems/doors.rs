@@ -0,0 +1,96 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{Entities, Join, Read, ReadExpect, ReadStorage, Write, WriteStorage},
+    renderer::SpriteRender,
+    shrev::EventChannel,
+};
+
+use crate::components::{ai::Creature, Door, Doorway, Obstruction, Path, TilePosition};
+use crate::systems::time::TimeState;
+use crate::tiles::{ReadTiles, TileChangeEvent, TileEntities, Tiles, WriteTiles};
+
+/// Swings every `Door` open for those whose `Path` steps through it next, and shut again once
+/// they are through, making its tile passable while open. Each change of the `Obstruction` sends
+/// a `TileChangeEvent`, so the path caches follow, and swaps the sprite of the door. The
+/// `Doorway` of the tile is kept in step with whether the door is locked or forbidden to pets.
+#[derive(Default)]
+pub struct System;
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        Read<'s, TimeState>,
+        Write<'s, EventChannel<TileChangeEvent>>,
+        WriteTiles<'s, Obstruction>,
+        WriteTiles<'s, Doorway>,
+        ReadTiles<'s, TileEntities>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, Path>,
+        ReadStorage<'s, Creature>,
+        WriteStorage<'s, Door>,
+        WriteStorage<'s, SpriteRender>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            time,
+            mut changes,
+            mut obstructions,
+            mut doorways,
+            occupants,
+            tile_positions,
+            paths,
+            creatures,
+            mut doors,
+            mut sprites,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+
+        // Where everyone on the move steps next, and whether they are a pet
+        let approaching = (&entities, &paths)
+            .join()
+            .filter_map(|(entity, path)| Some((path.next()?, creatures.contains(entity))))
+            .collect::<Vec<_>>();
+
+        for (entity, door, position, sprite) in (
+            &entities,
+            &mut doors,
+            &tile_positions,
+            (&mut sprites).maybe(),
+        )
+            .join()
+        {
+            let id = tiles.id_from_vector(position.coord);
+            let doorway = door.doorway();
+            if doorways.get(id) != Some(&doorway) {
+                doorways.insert(id, doorway);
+                changes.single_write(TileChangeEvent { id });
+            }
+
+            let waiting = approaching
+                .iter()
+                .any(|(coord, pet)| *coord == position.coord && doorway.admits(*pet));
+            let blocked = occupants
+                .get(id)
+                .map_or(false, |occupants| occupants.0.iter().any(|e| *e != entity));
+            if !door.update(now, waiting, blocked) {
+                continue;
+            }
+
+            if door.is_open() {
+                obstructions.remove(id);
+            } else {
+                obstructions.insert(id, Obstruction::Impassable);
+            }
+            changes.single_write(TileChangeEvent { id });
+            if let Some(sprite) = sprite {
+                sprite.sprite_number = door.sprite_number();
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    assets::AssetStorage,
+    core::Parent,
+    ecs::{
+        Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, Resources, SystemData, Write,
+        WriteStorage,
+    },
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::assets::{self, material::Materials};
+use crate::components::{FluidDepth, Item, ItemBroken, MaterialStatus, TilePosition, Wear};
+use crate::initializers::{spawn_item, SpawnType};
+use crate::systems::time::TimeState;
+use crate::tiles::{ReadTiles, Tiles};
+
+/// Hit points a tool loses each time it is used.
+pub const tool_wear: f32 = 1.0;
+/// Hit points lost per unit of game time by items which corrode, while lying in water.
+pub const corrosion_rate: f32 = 0.01;
+
+/// Wears down items by their `MaterialStatus`, from the `Wear` of being used and from corroding
+/// while wet. Worn out items are destroyed, leaving what they break into in their place, and an
+/// `ItemBroken` is sent.
+#[derive(Default)]
+pub struct System {
+    wear_reader: Option<ReaderId<Wear>>,
+    last_time: Option<u64>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        Read<'s, TimeState>,
+        Read<'s, Materials>,
+        Read<'s, AssetStorage<assets::Item>>,
+        Read<'s, LazyUpdate>,
+        Read<'s, EventChannel<Wear>>,
+        Write<'s, EventChannel<ItemBroken>>,
+        ReadTiles<'s, FluidDepth>,
+        ReadStorage<'s, Item>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, Parent>,
+        WriteStorage<'s, MaterialStatus>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.wear_reader = Some(Write::<EventChannel<Wear>>::fetch(&res).register_reader());
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            time,
+            materials,
+            details,
+            lazy,
+            wear,
+            mut broken,
+            fluids,
+            items,
+            tile_positions,
+            parents,
+            mut statuses,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+        let elapsed = now.saturating_sub(self.last_time.unwrap_or(now));
+        self.last_time = Some(now);
+
+        let mut worn_out = Vec::new();
+        for event in wear.read(self.wear_reader.as_mut().unwrap()) {
+            if let Some(status) = statuses.get_mut(event.item) {
+                if status.damage(event.amount) {
+                    worn_out.push(event.item);
+                }
+            }
+        }
+
+        if elapsed > 0 {
+            for (entity, item, position, status) in
+                (&entities, &items, &tile_positions, &mut statuses).join()
+            {
+                let wet = fluids
+                    .get(tiles.id_from_vector(position.coord))
+                    .map_or(false, |depth| depth.0 > 0);
+                let corrodes = details
+                    .get(&item.handle)
+                    .and_then(|details| details.material.as_ref())
+                    .and_then(|material| materials.get(material))
+                    .map_or(false, |material| material.corrodes());
+                if wet && corrodes && status.damage(corrosion_rate * elapsed as f32) {
+                    worn_out.push(entity);
+                }
+            }
+        }
+
+        for item in worn_out {
+            let replaced_by = items
+                .get(item)
+                .and_then(|item| details.get(&item.handle))
+                .and_then(|details| details.broken_into.clone());
+            if let Some(replacement) = &replaced_by {
+                let spawn_type = match (parents.get(item), tile_positions.get(item)) {
+                    (Some(parent), _) => Some(SpawnType::Parent(parent.entity)),
+                    (None, Some(position)) => Some(SpawnType::TilePosition(position.coord)),
+                    (None, None) => None,
+                };
+                if let Some(spawn_type) = spawn_type {
+                    let replacement = replacement.clone();
+                    lazy.exec_mut(move |world| {
+                        spawn_item(world, spawn_type, &replacement, None);
+                    });
+                }
+            }
+
+            entities.delete(item).unwrap();
+            broken.single_write(ItemBroken { item, replaced_by });
+        }
+    }
+}
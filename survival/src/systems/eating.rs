@@ -0,0 +1,261 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    assets::AssetStorage,
+    core::{components::Parent, ParentHierarchy},
+    ecs::{
+        Entities, Entity, Join, Read, ReadExpect, ReadStorage, Resources, SystemData, Write,
+        WriteStorage,
+    },
+    shrev::EventChannel,
+};
+use std::collections::HashMap;
+
+use crate::actions::{Action, ActionResult};
+use crate::assets::{self, item::Property};
+use crate::components::{
+    self,
+    ai::{CurrentAction, Needs},
+    Container, Obstruction, PathFailed, TilePosition,
+};
+use crate::cooking::Meal;
+use crate::eating::{self, eat_time, food_map_interval, food_map_range, satiety};
+use crate::goap::{GoalSelector, Planner};
+use crate::inventory;
+use crate::pathfinding::distance;
+use crate::pathfinding::{
+    tile_cost, DijkstraCollection, DijkstraMapType, MovementProfile, PathFlags, PathRequest,
+    PathRequests,
+};
+use crate::systems::nutrition::Food;
+use crate::systems::time::TimeState;
+use crate::tiles::{ReadTiles, TileEntities, Tiles, WriteTiles};
+use crate::utils::ComponentEventReader;
+
+/// Food being walked to, or eaten once there.
+#[derive(Clone, Copy, Debug)]
+struct Eating {
+    food: Entity,
+    /// When the eating is done, once started.
+    until: Option<u64>,
+}
+
+/// Feeds hungry pawns, and adds the planner action and goal for eating. Every
+/// `food_map_interval` a Dijkstra map is built out from all the `Edible` food lying about. An
+/// `Eat` event eats food the pawn carries if it has any, or else descends the map to the nearest
+/// food and walks there. Once at it, and `eat_time` later, the food is eaten up: hunger is
+/// satisfied by its calories and the `Food` is sent on to the nutrition system.
+#[derive(Default)]
+pub struct System {
+    action_reader: ComponentEventReader<components::Actionable, Action>,
+    food_map: DijkstraCollection,
+    last_map: Option<u64>,
+    eating: HashMap<Entity, Eating>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        ReadExpect<'s, ParentHierarchy>,
+        Read<'s, TimeState>,
+        Read<'s, AssetStorage<assets::Item>>,
+        Read<'s, PathRequests>,
+        Write<'s, EventChannel<ActionResult>>,
+        Write<'s, EventChannel<(Entity, Food)>>,
+        ReadTiles<'s, Obstruction>,
+        WriteTiles<'s, TileEntities>,
+        ReadStorage<'s, CurrentAction>,
+        ReadStorage<'s, PathFailed>,
+        ReadStorage<'s, Container>,
+        ReadStorage<'s, components::Item>,
+        ReadStorage<'s, Meal>,
+        ReadStorage<'s, Parent>,
+        ReadStorage<'s, TilePosition>,
+        WriteStorage<'s, Needs>,
+        WriteStorage<'s, components::Actionable>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.action_reader.setup(res);
+
+        Planner::setup_pawn_actions(res, eating::actions());
+        let mut selector = res
+            .entry::<GoalSelector>()
+            .or_insert_with(GoalSelector::default);
+        for goal in eating::goals() {
+            selector.insert(goal);
+        }
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            hierarchy,
+            time,
+            details,
+            requests,
+            mut results,
+            mut consumed,
+            obstructions,
+            mut occupants,
+            current_actions,
+            path_failures,
+            containers,
+            items,
+            meals,
+            parents,
+            tile_positions,
+            mut needs,
+            mut actionables,
+        ): Self::SystemData,
+    ) {
+        // Cooked meals give what they were cooked from, anything else its details
+        let food_of = |entity: Entity| -> Option<Food> {
+            if let Some(meal) = meals.get(entity) {
+                return Some(meal.0);
+            }
+            details
+                .get(&items.get(entity)?.handle)
+                .filter(|details| details.properties.contains(&Property::Edible))
+                .and_then(|details| details.food)
+        };
+
+        let now = time.current_time;
+        if self
+            .last_map
+            .map_or(true, |last| now >= last + food_map_interval)
+        {
+            self.last_map = Some(now);
+            let food = (&entities, &items, &tile_positions, !&parents)
+                .join()
+                .filter(|(entity, _, _, _)| food_of(*entity).is_some())
+                .map(|(_, _, position, _)| position.coord)
+                .collect::<Vec<_>>();
+            let cost = |id| tile_cost(obstructions.get(id));
+            self.food_map
+                .build(DijkstraMapType::Food, *tiles, &food, food_map_range, &cost);
+        }
+
+        // Find something to eat, on hand or else the nearest food lying about
+        self.action_reader.maintain(&entities, &mut actionables);
+        let mut hungry = Vec::new();
+        for (entity, actionable) in (&entities, &mut actionables).join() {
+            for action in self.action_reader.read(entity, actionable) {
+                if *action == Action::Eat {
+                    hungry.push(entity);
+                }
+            }
+        }
+        for pawn in hungry {
+            let here = match tile_positions.get(pawn) {
+                Some(position) => position.coord,
+                None => {
+                    results.single_write(ActionResult::failed(pawn, Action::Eat));
+                    continue;
+                }
+            };
+            let carried = inventory::get_all_items(pawn, &hierarchy, &containers, &items);
+            let on_hand = (&entities, &carried)
+                .join()
+                .map(|(food, _)| food)
+                .find(|food| food_of(*food).is_some());
+            let food = on_hand.or_else(|| {
+                let coord = self.food_map.source(&DijkstraMapType::Food, *tiles, here)?;
+                let food = occupants
+                    .get(tiles.id_from_vector(coord))?
+                    .0
+                    .iter()
+                    .cloned()
+                    .find(|food| !parents.contains(*food) && food_of(*food).is_some())?;
+                if coord != here {
+                    requests.request(PathRequest {
+                        entity: pawn,
+                        start: here,
+                        goal: coord,
+                        flags: PathFlags::None,
+                        profile: MovementProfile::default(),
+                    });
+                }
+                Some(food)
+            });
+            match food {
+                Some(food) => {
+                    self.eating.insert(pawn, Eating { food, until: None });
+                }
+                None => results.single_write(ActionResult::failed(pawn, Action::Eat)),
+            }
+        }
+
+        // Eat it once there
+        let eating = self.eating.drain().collect::<Vec<_>>();
+        for (pawn, mut meal) in eating {
+            let working = entities.is_alive(pawn)
+                && current_actions
+                    .get(pawn)
+                    .map_or(false, |current| current.event == Action::Eat);
+            if !working {
+                continue;
+            }
+            let here = tile_positions.get(pawn).map(|position| position.coord);
+            let reached = entities.is_alive(meal.food)
+                && match tile_positions.get(meal.food) {
+                    Some(position) => {
+                        !parents.contains(meal.food)
+                            && here.map_or(false, |here| distance(here, position.coord) <= 1)
+                    }
+                    None => held_by(meal.food, pawn, &parents),
+                };
+            let lost = !entities.is_alive(meal.food)
+                || (parents.contains(meal.food) && !held_by(meal.food, pawn, &parents));
+            if lost || (!reached && path_failures.contains(pawn)) {
+                results.single_write(ActionResult::failed(pawn, Action::Eat));
+                continue;
+            }
+            if !reached {
+                self.eating.insert(pawn, meal);
+                continue;
+            }
+            let until = *meal.until.get_or_insert(now + eat_time);
+            if now < until {
+                self.eating.insert(pawn, meal);
+                continue;
+            }
+
+            let food = match food_of(meal.food) {
+                Some(food) => food,
+                None => {
+                    results.single_write(ActionResult::failed(pawn, Action::Eat));
+                    continue;
+                }
+            };
+            if let Some(needs) = needs.get_mut(pawn) {
+                needs.hunger.satisfy(satiety(&food));
+            }
+            consumed.single_write((pawn, food));
+            if let Some(position) = tile_positions.get(meal.food) {
+                if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(position.coord)) {
+                    occupants.0.remove(&meal.food);
+                }
+            }
+            log::info!("{:?} ate {:?}", pawn, meal.food);
+            entities.delete(meal.food).unwrap();
+            results.single_write(ActionResult::completed(pawn, Action::Eat));
+        }
+    }
+}
+
+/// Whether `entity` is carried by `holder`, directly or in something it carries.
+fn held_by(entity: Entity, holder: Entity, parents: &ReadStorage<'_, Parent>) -> bool {
+    let mut current = entity;
+    while let Some(parent) = parents.get(current) {
+        if parent.entity == holder {
+            return true;
+        }
+        current = parent.entity;
+    }
+    false
+}
@@ -0,0 +1,294 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    assets::Handle,
+    core::{math::Vector3, ParentHierarchy, Transform},
+    ecs::{
+        Entities, Entity, Join, LazyUpdate, Read, ReadExpect, ReadStorage, Resources, SystemData,
+        Write, WriteStorage,
+    },
+    renderer::SpriteRender,
+    shrev::EventChannel,
+};
+use std::collections::HashMap;
+
+use crate::actions::{Action, ActionResult, Target};
+use crate::assets::{self, GetStorage, ItemStorage, PlantKind, PlantStorage};
+use crate::components::{
+    self,
+    ai::{Blackboard, CurrentAction},
+    Container, TilePosition,
+};
+use crate::farming::{FarmPlot, FieldState, Plant, Tilled};
+use crate::goap::ActionCatagory;
+use crate::initializers::{spawn_item, SpawnType};
+use crate::inventory;
+use crate::jobs::JobBoard;
+use crate::pathfinding::distance;
+use crate::settings::{Config, Context};
+use crate::tiles::{TileEntities, Tiles, WriteTiles};
+use crate::utils::ComponentEventReader;
+
+/// Keeps a job posted for the next step of each `FarmPlot`. `Till`, `Sow` and `Harvest` events work
+/// the plot the `Blackboard` targets, next to the farmer: tilling it, sowing a seed carried or at
+/// hand, and reaping a ripe crop for its harvest and seeds.
+#[derive(Default)]
+pub struct System {
+    action_reader: ComponentEventReader<components::Actionable, Action>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        ReadExpect<'s, Config>,
+        ReadExpect<'s, Context>,
+        ReadExpect<'s, ParentHierarchy>,
+        ReadExpect<'s, ItemStorage>,
+        ReadExpect<'s, PlantStorage>,
+        Read<'s, LazyUpdate>,
+        Write<'s, JobBoard>,
+        Write<'s, EventChannel<ActionResult>>,
+        WriteTiles<'s, Tilled>,
+        WriteTiles<'s, TileEntities>,
+        ReadStorage<'s, Blackboard>,
+        ReadStorage<'s, CurrentAction>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, Container>,
+        ReadStorage<'s, components::Item>,
+        ReadStorage<'s, Plant>,
+        WriteStorage<'s, FarmPlot>,
+        WriteStorage<'s, components::Actionable>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.action_reader.setup(res);
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            config,
+            context,
+            hierarchy,
+            item_storage,
+            plant_storage,
+            lazy,
+            mut board,
+            mut results,
+            mut tilled,
+            mut occupants,
+            blackboards,
+            current_actions,
+            tile_positions,
+            containers,
+            items,
+            plants,
+            mut plots,
+            mut actionables,
+        ): Self::SystemData,
+    ) {
+        let kinds = plant_storage.borrow();
+        // Catch up with crops which ripened or were lost, and post the next step of each plot
+        for (plot, position) in (&mut plots, &tile_positions).join() {
+            let kind = match kinds.data.get(&plot.crop) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            match plot.plant.filter(|plant| entities.is_alive(*plant)) {
+                Some(plant) => {
+                    let ripe = plants
+                        .get(plant)
+                        .map_or(false, |plant| kind.is_ripe(plant.stage));
+                    plot.state = if ripe {
+                        FieldState::Ripe
+                    } else {
+                        FieldState::Sown
+                    };
+                }
+                None if plot.plant.is_some() => {
+                    plot.plant = None;
+                    plot.state = FieldState::Tilled;
+                }
+                None => (),
+            }
+
+            if plot.job.map_or(false, |job| board.get(job).is_some()) {
+                continue;
+            }
+            plot.job = plot.next_step(kind).map(|conditions| {
+                board.post(ActionCatagory::Farming, conditions, Some(position.coord))
+            });
+        }
+
+        self.action_reader.maintain(&entities, &mut actionables);
+        let mut requests = Vec::new();
+        for (entity, actionable) in (&entities, &mut actionables).join() {
+            for action in self.action_reader.read(entity, actionable) {
+                match action {
+                    Action::Till | Action::Sow | Action::Harvest => {
+                        requests.push((entity, *action))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        if requests.is_empty() {
+            return;
+        }
+
+        let item_storage = item_storage.borrow();
+        let names = item_storage
+            .handles
+            .iter()
+            .map(|(name, handle)| (handle, name.as_str()))
+            .collect::<HashMap<&Handle<assets::Item>, &str>>();
+
+        for (entity, action) in requests {
+            let plot = match blackboards
+                .get(entity)
+                .and_then(|blackboard| blackboard.target)
+            {
+                Some(Target::Entity(plot)) => plot,
+                _ => {
+                    results.single_write(ActionResult::failed(entity, action));
+                    continue;
+                }
+            };
+            let here = tile_positions.get(entity).map(|position| position.coord);
+            let coord = match tile_positions.get(plot) {
+                Some(position)
+                    if here.map_or(false, |here| distance(here, position.coord) <= 1) =>
+                {
+                    position.coord
+                }
+                _ => {
+                    results.single_write(ActionResult::failed(entity, action));
+                    continue;
+                }
+            };
+            let plot = match plots.get_mut(plot) {
+                Some(plot) => plot,
+                None => {
+                    results.single_write(ActionResult::failed(entity, action));
+                    continue;
+                }
+            };
+            let kind = match kinds.data.get(&plot.crop) {
+                Some(kind) => kind,
+                None => {
+                    results.single_write(ActionResult::failed(entity, action));
+                    continue;
+                }
+            };
+
+            let worked = match (action, plot.state) {
+                (Action::Till, FieldState::Untilled) => {
+                    tilled.insert(tiles.id_from_vector(coord), Tilled);
+                    plot.state = FieldState::Tilled;
+                    true
+                }
+                (Action::Sow, FieldState::Tilled) => {
+                    // A seed carried, else one lying next to the farmer
+                    let carried = inventory::get_all_items(entity, &hierarchy, &containers, &items);
+                    let is_seed = |item: Entity| {
+                        items
+                            .get(item)
+                            .and_then(|item| names.get(&item.handle))
+                            .map_or(false, |name| *name == kind.seed)
+                    };
+                    let seed = (&entities, &carried)
+                        .join()
+                        .map(|(item, _)| item)
+                        .chain(
+                            (&entities, &items, &tile_positions)
+                                .join()
+                                .filter(|(item, _, position)| {
+                                    hierarchy.parent(*item).is_none()
+                                        && here.map_or(false, |here| {
+                                            distance(here, position.coord) <= 1
+                                        })
+                                })
+                                .map(|(item, _, _)| item),
+                        )
+                        .find(|item| is_seed(*item));
+
+                    match seed {
+                        Some(seed) => {
+                            entities.delete(seed).unwrap();
+                            let mut transform = Transform::default();
+                            transform.set_translation(tiles.tile_to_world(coord, &config));
+                            let plant = sow(
+                                &entities, &lazy, &context, &plot.crop, kind, coord, transform,
+                            );
+                            plot.plant = Some(plant);
+                            plot.state = FieldState::Sown;
+                            if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(coord))
+                            {
+                                occupants.0.insert(plant);
+                            }
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                (Action::Harvest, FieldState::Ripe) => {
+                    if let Some(plant) = plot.plant.take() {
+                        entities.delete(plant).unwrap();
+                    }
+                    plot.state = FieldState::Tilled;
+
+                    let yields = [
+                        (kind.harvest.clone(), kind.harvest_count),
+                        (kind.seed.clone(), kind.seed_count),
+                    ];
+                    lazy.exec_mut(move |world| {
+                        for (item, count) in &yields {
+                            for _ in 0..*count {
+                                spawn_item(world, SpawnType::Parent(entity), item, None);
+                            }
+                        }
+                    });
+                    true
+                }
+                _ => false,
+            };
+
+            if !worked {
+                results.single_write(ActionResult::failed(entity, action));
+            } else if !current_actions.contains(entity) {
+                // Pawns finish once the time the step takes is up
+                results.single_write(ActionResult::completed(entity, action));
+            }
+        }
+    }
+}
+
+/// Creates a plant of `crop` at `coord`, its components added once the world is next maintained.
+fn sow(
+    entities: &Entities,
+    lazy: &LazyUpdate,
+    context: &Context,
+    crop: &str,
+    kind: &PlantKind,
+    coord: Vector3<u32>,
+    transform: Transform,
+) -> Entity {
+    let plant = entities.create();
+    lazy.insert(plant, transform);
+    lazy.insert(plant, TilePosition::new(coord));
+    lazy.insert(plant, Plant::new(crop));
+    if let (Some(sprite_sheet), Some(stage)) = (&context.spritesheet, kind.stages.first()) {
+        lazy.insert(
+            plant,
+            SpriteRender {
+                sprite_sheet: sprite_sheet.clone(),
+                sprite_number: stage.sprite_number,
+            },
+        );
+    }
+    plant
+}
@@ -0,0 +1,121 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::ecs::{ParJoin, Read, ReadExpect};
+use rayon::prelude::*;
+use specs_static::Entry;
+
+use crate::components::{
+    AnimatedTile, FlaggedSpriteRender, FluidDepth, FluidLayer, Obstruction, TileLayer,
+};
+use crate::fluids::{evaporate, evaporation_time, flow, FluidDepths};
+use crate::settings::Context;
+use crate::systems::time::TimeState;
+use crate::tiles::{ReadTiles, Tiles, WriteTiles};
+
+/// Most flow steps run in one frame, the rest of the elapsed time being skipped.
+pub const max_flow_steps: u64 = 4;
+/// Sprite drawn on the `FluidLayer` of wet tiles, as an index into the cp437 sheet.
+const water_sprite_number: usize = 247;
+/// Frames the water of wet tiles ripples through, and the seconds each is shown.
+const water_frames: [usize; 2] = [water_sprite_number, 126];
+const water_frame_duration: f32 = 0.6;
+
+/// Flows every `FluidDepth` once per unit of elapsed game time and dries up puddles every
+/// `evaporation_time`, keeping the `FluidLayer` sprite, and its `AnimatedTile` ripple, in step
+/// with which tiles are wet.
+#[derive(Default)]
+pub struct System {
+    last_time: Option<u64>,
+    evaporation: u64,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        ReadExpect<'s, Tiles>,
+        ReadExpect<'s, Context>,
+        Read<'s, TimeState>,
+        ReadTiles<'s, Obstruction>,
+        WriteTiles<'s, FluidDepth>,
+        WriteTiles<'s, FluidLayer>,
+        WriteTiles<'s, AnimatedTile>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            tiles,
+            context,
+            time,
+            obstructions,
+            mut fluids,
+            mut layers,
+            mut animations,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+        let elapsed = now.saturating_sub(self.last_time.unwrap_or(now));
+        self.last_time = Some(now);
+        if elapsed == 0 {
+            return;
+        }
+
+        let mut depths = (fluids.mask(), &*fluids)
+            .par_join()
+            .map(|(id, depth)| (tiles.id_at(id), depth.0))
+            .filter(|(_, depth)| *depth > 0)
+            .collect::<FluidDepths>();
+        if depths.is_empty() {
+            return;
+        }
+        let before = depths.keys().cloned().collect::<Vec<_>>();
+
+        let blocked = |id| match obstructions.get(id) {
+            Some(Obstruction::Impassable) => true,
+            _ => false,
+        };
+        for _ in 0..elapsed.min(max_flow_steps) {
+            flow(*tiles, &mut depths, blocked);
+        }
+
+        self.evaporation += elapsed;
+        if self.evaporation >= evaporation_time {
+            self.evaporation = 0;
+            evaporate(&mut depths);
+        }
+
+        for id in before {
+            if !depths.contains_key(&id) {
+                fluids.remove(id);
+                layers.remove(id);
+                animations.remove(id);
+            }
+        }
+        for (id, depth) in &depths {
+            match fluids.entry(*id) {
+                Entry::Occupied(mut fluid) => {
+                    if fluid.get().0 != *depth {
+                        fluid.insert(FluidDepth(*depth));
+                    }
+                }
+                Entry::Vacant(fluid) => {
+                    fluid.insert(FluidDepth(*depth));
+                }
+            }
+            if let Entry::Vacant(layer) = layers.entry(*id) {
+                if let Some(sprite_sheet) = &context.spritesheet {
+                    layer.insert(FluidLayer(FlaggedSpriteRender {
+                        sprite_sheet: sprite_sheet.clone(),
+                        sprite_number: water_sprite_number,
+                    }));
+                    animations.insert(
+                        *id,
+                        AnimatedTile {
+                            layer: TileLayer::Fluid,
+                            frames: water_frames.to_vec(),
+                            frame_duration: water_frame_duration,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
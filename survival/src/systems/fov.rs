@@ -0,0 +1,64 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::math::Vector3,
+    ecs::{Entities, Entity, Join, ReadExpect, ReadStorage},
+};
+use std::collections::HashMap;
+
+use crate::components::{Obstruction, Player, TilePosition, Visibility};
+use crate::tiles::{ReadTiles, TileId, Tiles, WriteTiles};
+
+/// Recomputes the field of view of each player whenever they move to a new tile.
+#[derive(Default)]
+pub struct System {
+    last_positions: HashMap<Entity, Vector3<u32>>,
+    visible: HashMap<u8, Vec<TileId>>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, TilePosition>,
+        ReadTiles<'s, Obstruction>,
+        WriteTiles<'s, Visibility>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, tiles, players, tile_positions, obstructions, mut visibility): Self::SystemData,
+    ) {
+        for (entity, player, tile_position) in (&entities, &players, &tile_positions).join() {
+            if self.last_positions.get(&entity) == Some(&tile_position.coord) {
+                continue;
+            }
+            self.last_positions.insert(entity, tile_position.coord);
+
+            let visible = self.visible.entry(player.index).or_insert_with(Vec::new);
+            for tile_id in visible.drain(..) {
+                if let Some(tile) = visibility.get_mut(tile_id) {
+                    tile.hide(player.index);
+                }
+            }
+
+            crate::fov::shadowcast(
+                *tiles,
+                tiles.id_from_vector(tile_position.coord),
+                player.sight_range,
+                |tile_id| match obstructions.get(tile_id) {
+                    Some(Obstruction::Impassable) => true,
+                    _ => false,
+                },
+                |tile_id| visible.push(tile_id),
+            );
+
+            for tile_id in visible.iter() {
+                visibility.get_mut_or_default(*tile_id).reveal(player.index);
+            }
+        }
+
+        self.last_positions
+            .retain(|entity, _| entities.is_alive(*entity));
+    }
+}
@@ -1,25 +1,178 @@
 #![allow(clippy::module_name_repetitions)]
-use crate::components;
-use crate::game_data::SurvivalState;
-use crate::settings::Context;
-use amethyst::ecs::{ReadExpect, Resources, SystemData, Write, WriteStorage};
+use amethyst::ecs::{Entities, Join, Read, ReadStorage, WriteStorage};
+use hibitset::BitSet;
+use rayon::prelude::*;
+use std::collections::HashMap;
 
-use crate::goap::Planner;
+use crate::components::{
+    ai::{
+        scheduled_sleep_urgency, Activity, Blackboard, Needs, Pawn, Perception, Personality,
+        Schedule, Threat, AI,
+    },
+    TilePosition,
+};
+use crate::goap::{DistanceCost, Drives, GoalSelector, Planner};
+use crate::jobs::JobBoard;
+use crate::systems::time::TimeState;
 
+/// Plans for every entity whose `AI` needs a new plan, as a parallel batch on the rayon thread
+/// pool. Each entity plans towards the goal its `GoalSelector` scores highest, or its claimed job
+/// or a goal to fight or flee a `Threat` if either scores higher, starting from the conditions
+/// its `Perception` remembers holding, with actions priced by how far away it remembers what they
+/// need it near. A `Schedule` limits the goals to those fitting the hour of the day, and the mood
+/// of its `Personality` weighs labor against idling. The entities it remembers satisfying each
+/// condition are bound on its `Blackboard`.
 #[derive(Default)]
 pub struct System;
 impl<'s> amethyst::ecs::System<'s> for System {
     type SystemData = (
-        ReadExpect<'s, Context>,
-        Write<'s, SurvivalState>,
-        WriteExpect<'s, Planner>
+        Entities<'s>,
+        Read<'s, TimeState>,
+        Read<'s, Planner>,
+        Read<'s, GoalSelector>,
+        Read<'s, JobBoard>,
+        ReadStorage<'s, Pawn>,
+        ReadStorage<'s, Needs>,
+        ReadStorage<'s, Schedule>,
+        ReadStorage<'s, Personality>,
+        ReadStorage<'s, Threat>,
+        ReadStorage<'s, Perception>,
+        ReadStorage<'s, TilePosition>,
+        WriteStorage<'s, AI>,
+        WriteStorage<'s, Blackboard>,
     );
 
-    fn setup(&mut self, res: &mut Resources) {
-        Self::SystemData::setup(res);
-    }
+    fn run(
+        &mut self,
+        (
+            entities,
+            time,
+            planner,
+            selector,
+            jobs,
+            pawns,
+            needs,
+            schedules,
+            personalities,
+            threats,
+            perceptions,
+            tile_positions,
+            mut ais,
+            mut blackboards,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+        let hour = time.hour();
+        let no_labor = HashMap::new();
+
+        let requests = (
+            &entities,
+            &ais,
+            pawns.maybe(),
+            needs.maybe(),
+            schedules.maybe(),
+            personalities.maybe(),
+            threats.maybe(),
+            perceptions.maybe(),
+            tile_positions.maybe(),
+        )
+            .join()
+            .filter(|(_, ai, _, _, _, _, _, _, _)| ai.needs_plan(now))
+            .map(
+                |(entity, ai, pawn, needs, schedule, personality, threat, perception, position)| {
+                    let activity =
+                        schedule.map_or(Activity::Anything, |schedule| schedule.activity(hour));
+                    let mut fatigue = needs.map_or(0.0, |needs| needs.fatigue.urgency());
+                    if activity == Activity::Sleep {
+                        fatigue = fatigue.max(scheduled_sleep_urgency);
+                    }
+                    let drives = Drives {
+                        hunger: needs.map_or(0.0, |needs| needs.hunger.urgency()),
+                        thirst: needs.map_or(0.0, |needs| needs.thirst.urgency()),
+                        fatigue,
+                        cold: needs.map_or(0.0, |needs| needs.cold.urgency()),
+                        danger: if threat.is_some() { 1.0 } else { 0.0 },
+                        labor_priorities: pawn.map_or(&no_labor, |pawn| &pawn.labor_priorities),
+                        mood: personality.map_or(0.0, Personality::mood),
+                    };
+                    let job = ai.job.and_then(|job| jobs.get(job)).map(|job| job.goal());
+                    let threat = threat.map(Threat::goal);
+                    let candidates = job
+                        .iter()
+                        .chain(threat.iter())
+                        .chain(selector.goals())
+                        .filter(|goal| activity.allows(&goal.motive));
+                    let goal = GoalSelector::select_goals(candidates, &planner, &drives).map(
+                        |(goal, conditions)| {
+                            let on_job = job.as_ref().map_or(false, |job| std::ptr::eq(goal, job));
+                            (goal.name.clone(), conditions, on_job)
+                        },
+                    );
+                    let available = planner.available_to(&ai.available_actions, pawn.is_some());
+                    let mut blackboard = Blackboard::default();
+                    let (state, costs) = match (perception, position) {
+                        (Some(perception), Some(position)) => {
+                            let referenced = planner.referenced(&available);
+                            for condition in &referenced {
+                                if let Some(fact) = perception.binding(condition, position.coord) {
+                                    blackboard.bind(
+                                        (*condition).clone(),
+                                        fact.entity,
+                                        fact.position,
+                                    );
+                                }
+                            }
+                            let state = planner.evaluate(&available, |condition| {
+                                perception.satisfies(condition, position.coord)
+                            });
+                            (state, perception.distance_cost(referenced, position.coord))
+                        }
+                        _ => (BitSet::new(), DistanceCost::default()),
+                    };
+                    (entity, available, state, costs, goal, blackboard)
+                },
+            )
+            .collect::<Vec<_>>();
+        if requests.is_empty() {
+            return;
+        }
 
-    fn run(&mut self, (_, mut state, planner): Self::SystemData) {
+        let planner = &*planner;
+        let plans = requests
+            .into_par_iter()
+            .map(
+                |(entity, available_actions, state, costs, goal, blackboard)| {
+                    let plan = goal.map(|(name, conditions, on_job)| {
+                        let plan =
+                            planner.plan_costed(&available_actions, &conditions, state, &costs);
+                        (name, plan, on_job)
+                    });
+                    (entity, plan, blackboard)
+                },
+            )
+            .collect::<Vec<_>>();
 
+        for (entity, plan, blackboard) in plans {
+            let ai = ais.get_mut(entity).unwrap();
+            match plan {
+                Some((goal, Some(ref actions), on_job)) if !actions.is_empty() => {
+                    blackboards.insert(entity, blackboard).unwrap();
+                    ai.set_plan(actions);
+                    ai.goal = Some(goal);
+                    ai.on_job = on_job;
+                }
+                // Having nothing to do waits out the backoff, the same as failing to plan
+                Some((goal, _, on_job)) => {
+                    ai.fail(now);
+                    ai.goal = Some(goal);
+                    ai.on_job = on_job;
+                }
+                None => {
+                    ai.fail(now);
+                    ai.goal = None;
+                    ai.on_job = false;
+                }
+            }
+        }
     }
 }
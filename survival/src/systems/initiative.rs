@@ -1,33 +1,109 @@
 #![allow(clippy::module_name_repetitions)]
-use crate::components;
+use crate::components::{IsTurn, Player, TimeAvailable};
 use crate::game_data::SurvivalState;
 use crate::settings::Context;
-use amethyst::ecs::{ReadExpect, Resources, SystemData, Write, WriteStorage};
+use crate::systems::time::turn_time;
+use amethyst::ecs::{
+    Entities, Entity, Join, ReadExpect, ReadStorage, Resources, SystemData, Write, WriteStorage,
+};
 
+/// Hands out turns to the actors with a turn's worth of `TimeAvailable`, most time first. Other
+/// actors go together, but a player takes its turn alone: the game is paused until the player
+/// spends its time, and runs again once it has.
 #[derive(Default)]
 pub struct System;
 impl<'s> amethyst::ecs::System<'s> for System {
     type SystemData = (
         ReadExpect<'s, Context>,
         Write<'s, SurvivalState>,
-        WriteStorage<'s, components::IsTurn>,
+        Entities<'s>,
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, TimeAvailable>,
+        WriteStorage<'s, IsTurn>,
     );
 
     fn setup(&mut self, res: &mut Resources) {
         Self::SystemData::setup(res);
     }
 
-    fn run(&mut self, (_, mut state, _): Self::SystemData) {
-        match state.clone() {
-            SurvivalState::Paused => {
-                // do nothing?
-            }
-            SurvivalState::Running => {
-                // Handle monster initiative, and handing it back to the player.
-                *state = SurvivalState::Paused;
-                //slog_trace!(context.logs.root, "AI turn finished, pausing");
+    fn run(&mut self, (_, mut state, entities, players, times, mut turns): Self::SystemData) {
+        if *state == SurvivalState::Level {
+            return;
+        }
+
+        // Turns end once taken, except a player's, which lasts until the time is spent
+        let finished = (&entities, &turns)
+            .join()
+            .filter(|(entity, _)| {
+                !players.contains(*entity)
+                    || times.get(*entity).map_or(true, |time| !time.has(turn_time))
+            })
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in finished {
+            turns.remove(entity);
+        }
+
+        if (&players, &turns).join().next().is_none() {
+            let ready = (&entities, &times, !&turns)
+                .join()
+                .filter(|(_, time, _)| time.has(turn_time))
+                .map(|(entity, time, _)| (entity, time.0, players.contains(entity)))
+                .collect::<Vec<_>>();
+            for entity in next_turns(ready) {
+                turns.insert(entity, IsTurn).unwrap();
             }
-            _ => {}
         }
+
+        *state = if (&players, &turns).join().next().is_some() {
+            SurvivalState::Paused
+        } else {
+            SurvivalState::Running
+        };
+    }
+}
+
+/// Who of the `ready` actors, given as their time available and whether they are a player, takes
+/// a turn next. Actors with the most time go first, every one before the first player together,
+/// or that player alone if it comes first.
+pub fn next_turns(mut ready: Vec<(Entity, u64, bool)>) -> Vec<Entity> {
+    ready.sort_by(|(a, a_time, _), (b, b_time, _)| b_time.cmp(a_time).then(a.id().cmp(&b.id())));
+    match ready.first() {
+        Some((player, _, true)) => vec![*player],
+        _ => ready
+            .iter()
+            .take_while(|(_, _, is_player)| !is_player)
+            .map(|(entity, _, _)| *entity)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst::ecs::{Builder, World};
+
+    #[test]
+    fn turn_order() {
+        let mut world = World::new();
+        let player = world.create_entity().build();
+        let fast = world.create_entity().build();
+        let slow = world.create_entity().build();
+        let slower = world.create_entity().build();
+
+        let ready = vec![
+            (player, 5, true),
+            (slow, 2, false),
+            (fast, 8, false),
+            (slower, 1, false),
+        ];
+        assert_eq!(next_turns(ready), vec![fast]);
+
+        let ready = vec![(player, 5, true), (slow, 2, false), (slower, 2, false)];
+        assert_eq!(next_turns(ready), vec![player]);
+
+        let ready = vec![(slower, 2, false), (slow, 2, false), (fast, 3, false)];
+        assert_eq!(next_turns(ready), vec![fast, slow, slower]);
+        assert!(next_turns(Vec::new()).is_empty());
     }
 }
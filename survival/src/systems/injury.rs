@@ -0,0 +1,80 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{Entities, Join, LazyUpdate, Read, ReadStorage, Write, WriteStorage},
+    shrev::EventChannel,
+};
+
+use crate::butchery::leave_corpse;
+use crate::components::{
+    ai::{Creature, Perception, ThoughtEvent, ThoughtKind},
+    TilePosition,
+};
+use crate::injury::Body;
+use crate::systems::time::TimeState;
+
+/// Bleeds, festers and heals the wounds of every `Body` as game time passes. Those which bleed
+/// out or lose a vital part die, troubling everyone who knew where they were, and creatures leave
+/// a corpse behind.
+#[derive(Default)]
+pub struct System {
+    last_time: Option<u64>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, TimeState>,
+        Read<'s, LazyUpdate>,
+        Write<'s, EventChannel<ThoughtEvent>>,
+        ReadStorage<'s, Perception>,
+        ReadStorage<'s, Creature>,
+        ReadStorage<'s, TilePosition>,
+        WriteStorage<'s, Body>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            time,
+            lazy,
+            mut thoughts,
+            perceptions,
+            creatures,
+            tile_positions,
+            mut bodies,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+        let elapsed = now.saturating_sub(self.last_time.unwrap_or(now));
+        self.last_time = Some(now);
+        if elapsed == 0 {
+            return;
+        }
+
+        let mut dead = Vec::new();
+        for (entity, body) in (&entities, &mut bodies).join() {
+            body.update(elapsed);
+
+            if body.is_dead() {
+                log::info!("{:?} died of their wounds", entity);
+                if let (Some(creature), Some(position)) =
+                    (creatures.get(entity), tile_positions.get(entity))
+                {
+                    leave_corpse(&lazy, &creature.kind, position.coord);
+                }
+                entities.delete(entity).unwrap();
+                dead.push(entity);
+            }
+        }
+
+        for (entity, perception) in (&entities, &perceptions).join() {
+            for _ in dead.iter().filter(|dead| perception.get(**dead).is_some()) {
+                thoughts.single_write(ThoughtEvent {
+                    entity,
+                    kind: ThoughtKind::SawCorpse,
+                });
+            }
+        }
+    }
+}
@@ -1,10 +1,12 @@
 #![allow(clippy::module_name_repetitions)]
 
-
-use crate::actions::PlayerInputAction;
-use crate::components;
+use crate::actions::{Action, Direction, PlayerInputAction, Target};
+use crate::components::{self, ai::Blackboard};
+use crate::designations::{self, Brush, Designation, DesignationTool};
 use crate::game_data::SurvivalState;
+use crate::selection::{RegionSelected, TileSelected};
 use crate::settings::Context;
+use crate::tiles::{Tiles, ViewZ, WriteTiles};
 use amethyst::{
     core::transform::Transform,
     ecs::{
@@ -15,20 +17,34 @@ use amethyst::{
     shrev::{EventChannel, ReaderId},
 };
 
+/// Zooms the camera and changes the viewed z level, and on the player's turn turns the movement
+/// keys into a `Move` a step in that direction for the `PlayerControlled` entities. The
+/// designation keys pick up a brush, which is painted over the tiles selected with the mouse.
 #[derive(Default)]
 pub struct System {
     input_reader: Option<ReaderId<InputEvent<PlayerInputAction>>>,
+    tile_reader: Option<ReaderId<TileSelected>>,
+    region_reader: Option<ReaderId<RegionSelected>>,
 }
 impl<'s> amethyst::ecs::System<'s> for System {
     type SystemData = (
         ReadExpect<'s, Context>,
-        Write<'s, SurvivalState>,
+        Read<'s, SurvivalState>,
         Read<'s, InputHandler<PlayerInputAction, PlayerInputAction>>,
         Read<'s, EventChannel<InputEvent<PlayerInputAction>>>,
         Entities<'s>,
         WriteStorage<'s, components::Actionable>,
+        WriteStorage<'s, Blackboard>,
+        ReadStorage<'s, components::PlayerControlled>,
+        ReadStorage<'s, components::IsTurn>,
         ReadStorage<'s, Camera>,
         WriteStorage<'s, Transform>,
+        ReadExpect<'s, Tiles>,
+        Write<'s, ViewZ>,
+        Read<'s, EventChannel<TileSelected>>,
+        Read<'s, EventChannel<RegionSelected>>,
+        Write<'s, DesignationTool>,
+        WriteTiles<'s, Designation>,
     );
 
     fn setup(&mut self, res: &mut Resources) {
@@ -37,9 +53,11 @@ impl<'s> amethyst::ecs::System<'s> for System {
         self.input_reader = Some(
             Write::<EventChannel<InputEvent<PlayerInputAction>>>::fetch(&res).register_reader(),
         );
+        self.tile_reader = Some(Write::<EventChannel<TileSelected>>::fetch(&res).register_reader());
+        self.region_reader =
+            Some(Write::<EventChannel<RegionSelected>>::fetch(&res).register_reader());
     }
 
-    #[allow(clippy::cast_possible_truncation)]
     fn run(
         &mut self,
         (
@@ -49,57 +67,103 @@ impl<'s> amethyst::ecs::System<'s> for System {
             input_events,
             entities,
             mut actionables,
+            mut blackboards,
+            controlled,
+            turns,
             cameras,
-            mut transforms, // for debuging
+            mut transforms,
+            tiles,
+            mut view_z,
+            tiles_selected,
+            regions_selected,
+            mut tool,
+            mut designations,
         ): Self::SystemData,
     ) {
-        if *state == SurvivalState::Paused {
-            for (_, _actionable) in (&entities, &mut actionables).join() {
-                let got_input = false;
+        // hold-down key actions go here
+        if input.action_is_down(&PlayerInputAction::ZoomIn).unwrap() {
+            if let Some((_, transform)) = (&cameras, &mut transforms).join().next() {
+                *transform.scale_mut() = transform.scale() * 1.1;
+            }
+        }
+        if input.action_is_down(&PlayerInputAction::ZoomOut).unwrap() {
+            if let Some((_, transform)) = (&cameras, &mut transforms).join().next() {
+                *transform.scale_mut() = transform.scale() * 0.9;
+            }
+        }
+
+        // Tiles selected with a brush held are painted with it
+        let selected = tiles_selected
+            .read(self.tile_reader.as_mut().unwrap())
+            .map(|selected| (selected.coord, selected.coord))
+            .chain(
+                regions_selected
+                    .read(self.region_reader.as_mut().unwrap())
+                    .map(|selected| (selected.min, selected.max)),
+            );
+        for (from, to) in selected {
+            if let Some(brush) = tool.brush {
+                designations::paint(&mut designations, *tiles, from, to, brush);
+            }
+        }
 
-                // hold-down key actions go here
-                if input.action_is_down(&PlayerInputAction::MoveUp).unwrap() {
-                    if let Some((_, transform)) = (&cameras, &mut transforms).join().next() {
-                        transform.move_up(5.0);
-                    }
+        // Single shot event actions go here
+        for event in input_events.read(self.input_reader.as_mut().unwrap()) {
+            let action = match event {
+                InputEvent::ActionPressed(action) => action,
+                _ => continue,
+            };
+            let direction = match action {
+                PlayerInputAction::DesignateChop => {
+                    tool.toggle(Brush::Designate(Designation::ChopTree));
+                    continue;
                 }
-                if input.action_is_down(&PlayerInputAction::MoveDown).unwrap() {
-                    if let Some((_, transform)) = (&cameras, &mut transforms).join().next() {
-                        transform.move_down(5.0);
-                    }
+                PlayerInputAction::DesignateMine => {
+                    tool.toggle(Brush::Designate(Designation::Mine));
+                    continue;
                 }
-                if input.action_is_down(&PlayerInputAction::MoveLeft).unwrap() {
-                    if let Some((_, transform)) = (&cameras, &mut transforms).join().next() {
-                        transform.move_left(5.0);
-                    }
+                PlayerInputAction::DesignateGather => {
+                    tool.toggle(Brush::Designate(Designation::GatherPlants));
+                    continue;
                 }
-                if input.action_is_down(&PlayerInputAction::MoveRight).unwrap() {
-                    if let Some((_, transform)) = (&cameras, &mut transforms).join().next() {
-                        transform.move_right(5.0);
-                    }
+                PlayerInputAction::DesignateDeconstruct => {
+                    tool.toggle(Brush::Designate(Designation::Deconstruct));
+                    continue;
                 }
-
-                if input.action_is_down(&PlayerInputAction::ZoomIn).unwrap() {
-                    if let Some((_, transform)) = (&cameras, &mut transforms).join().next() {
-                        *transform.scale_mut() = transform.scale() * 1.1;
-                    }
+                PlayerInputAction::EraseDesignation => {
+                    tool.toggle(Brush::Erase);
+                    continue;
                 }
-                if input.action_is_down(&PlayerInputAction::ZoomOut).unwrap() {
-                    if let Some((_, transform)) = (&cameras, &mut transforms).join().next() {
-                        *transform.scale_mut() = transform.scale() * 0.9;
-                    }
+                PlayerInputAction::ZLevelUp => {
+                    view_z.up(*tiles);
+                    continue;
                 }
-
-                // Single shot event actions go here
-                if !got_input {
-                    for event in input_events.read(self.input_reader.as_mut().unwrap()) {
-                        if let InputEvent::ActionPressed(action) = event {
-                            match action {
-                                _ => {}
-                            }
-                        }
-                    }
+                PlayerInputAction::ZLevelDown => {
+                    view_z.down();
+                    continue;
                 }
+                PlayerInputAction::MoveUp => Direction::N,
+                PlayerInputAction::MoveDown => Direction::S,
+                PlayerInputAction::MoveLeft => Direction::W,
+                PlayerInputAction::MoveRight => Direction::E,
+                _ => continue,
+            };
+
+            // Moves are only taken on the player's turn
+            if *state != SurvivalState::Paused {
+                continue;
+            }
+            for (_, actionable, blackboard, _, _) in (
+                &entities,
+                &mut actionables,
+                &mut blackboards,
+                &controlled,
+                &turns,
+            )
+                .join()
+            {
+                blackboard.target = Some(Target::Direction(direction));
+                actionable.channel.single_write(Action::Move);
             }
         }
     }
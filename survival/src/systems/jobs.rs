@@ -0,0 +1,80 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::ecs::{Entities, Join, Read, ReadStorage, Write, WriteStorage};
+
+use crate::components::ai::{Activity, Pawn, PlanStatus, Schedule, AI};
+use crate::jobs::JobBoard;
+use crate::systems::time::TimeState;
+
+/// Settles the jobs pawns finished or gave up on, then matches pawns waiting for a plan to the
+/// claimable job their labor priorities favour most. Pawns whose `Schedule` has them off work
+/// give back jobs they haven't started.
+#[derive(Default)]
+pub struct System;
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, TimeState>,
+        Write<'s, JobBoard>,
+        ReadStorage<'s, Pawn>,
+        ReadStorage<'s, Schedule>,
+        WriteStorage<'s, AI>,
+    );
+
+    fn run(&mut self, (entities, time, mut board, pawns, schedules, mut ais): Self::SystemData) {
+        let now = time.current_time;
+        let hour = time.hour();
+
+        board.release_abandoned(|entity| entities.is_alive(entity));
+
+        for (entity, pawn, schedule, ai) in (&entities, &pawns, schedules.maybe(), &mut ais).join()
+        {
+            if let (Some(job), true) = (ai.job, ai.on_job) {
+                match ai.status {
+                    PlanStatus::Completed => {
+                        board.complete(job);
+                        ai.job = None;
+                        ai.on_job = false;
+                    }
+                    // Someone else may manage it
+                    PlanStatus::Failed => {
+                        board.release(job);
+                        ai.job = None;
+                        ai.on_job = false;
+                    }
+                    PlanStatus::Idle | PlanStatus::Executing => {}
+                }
+            }
+
+            // The job may have been finished by someone else, or taken off the board
+            if ai.job.map_or(false, |job| {
+                board
+                    .get(job)
+                    .map_or(true, |job| job.claimed_by() != Some(entity))
+            }) {
+                ai.job = None;
+                ai.on_job = false;
+            }
+
+            let working = schedule.map_or(true, |schedule| {
+                let activity = schedule.activity(hour);
+                activity == Activity::Work || activity == Activity::Anything
+            });
+            if !working {
+                if let (Some(job), false) = (ai.job, ai.on_job) {
+                    board.release(job);
+                    ai.job = None;
+                }
+                continue;
+            }
+
+            if ai.job.is_none() && ai.needs_plan(now) {
+                if let Some(job) = board.best_for(&pawn.labor_priorities).map(|job| job.id()) {
+                    if board.claim(job, entity) {
+                        ai.job = Some(job);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::math::Vector3,
+    ecs::{Entities, Entity, Join, ReadExpect, ReadStorage},
+};
+use std::collections::HashMap;
+
+use crate::components::{LightLevel, Obstruction, TilePosition};
+use crate::lighting::{fire_light_radius, illuminate};
+use crate::temperature::HeatSource;
+use crate::tiles::{ReadTiles, TileId, Tiles, WriteTiles};
+
+/// Lights the tiles about every `HeatSource`, as fires shed light, into their `LightLevel`. The
+/// light is only recast when a source is lit, put out or moved.
+#[derive(Default)]
+pub struct System {
+    sources: HashMap<Entity, Vector3<u32>>,
+    lit: Vec<TileId>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        ReadStorage<'s, HeatSource>,
+        ReadStorage<'s, TilePosition>,
+        ReadTiles<'s, Obstruction>,
+        WriteTiles<'s, LightLevel>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, tiles, heat_sources, tile_positions, obstructions, mut light): Self::SystemData,
+    ) {
+        let sources = (&entities, &heat_sources, &tile_positions)
+            .join()
+            .map(|(entity, _, position)| (entity, position.coord))
+            .collect::<HashMap<_, _>>();
+        if sources == self.sources {
+            return;
+        }
+        self.sources = sources;
+
+        for id in self.lit.drain(..) {
+            light.remove(id);
+        }
+
+        let blocked = |id| match obstructions.get(id) {
+            Some(Obstruction::Impassable) => true,
+            _ => false,
+        };
+        let mut levels = HashMap::<TileId, f32>::new();
+        for source in self.sources.values() {
+            for (id, level) in illuminate(*tiles, *source, fire_light_radius, blocked) {
+                let brightest = levels.entry(id).or_insert(0.0);
+                *brightest = brightest.max(level);
+            }
+        }
+        for (id, level) in levels {
+            light.insert(id, LightLevel(level));
+            self.lit.push(id);
+        }
+    }
+}
@@ -0,0 +1,329 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    assets::AssetStorage,
+    core::ParentHierarchy,
+    ecs::{
+        Entities, Entity, Join, Read, ReadExpect, ReadStorage, Resources, SystemData, Write,
+        WriteStorage,
+    },
+    shrev::EventChannel,
+};
+
+use crate::actions::{Action, ActionResult, Target};
+use crate::assets::{
+    self,
+    material::{MaterialState, Materials},
+};
+use crate::components::{
+    self,
+    ai::{Blackboard, CurrentAction, Needs},
+    max_fluid_depth, Container, FluidDepth, TilePosition,
+};
+use crate::goap::{GoalSelector, Planner};
+use crate::inventory::{self, Contents};
+use crate::liquids::{
+    self, default_liquid_density, draught, draught_thirst, hot_drink_warmth, puddle_volume,
+    river_liquid, room, Liquid, LiquidSource,
+};
+use crate::pathfinding::distance;
+use crate::temperature::{normal_body_temperature, BodyTemperature, HeatSource};
+use crate::tiles::{Tiles, WriteTiles};
+use crate::utils::ComponentEventReader;
+
+/// Fills, boils, drinks and pours out the `Liquid` in containers carried by pawns, and adds the
+/// planner actions and goals for drinking. `Fill` draws from the targeted `LiquidSource` next to
+/// the pawn, or from standing fluid such as a river beside it, into the first carried container
+/// which can hold liquid and has room for it, by volume and by weight. `Boil` needs a
+/// `HeatSource` next to the pawn. `Drink` quenches thirst, and warms the pawn too if what it
+/// drinks was boiled. `Pour` empties a container into the targeted one, or out on the ground,
+/// leaving a puddle if there is enough of it.
+#[derive(Default)]
+pub struct System {
+    action_reader: ComponentEventReader<components::Actionable, Action>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        ReadExpect<'s, ParentHierarchy>,
+        Read<'s, AssetStorage<assets::Item>>,
+        Read<'s, Materials>,
+        Write<'s, EventChannel<ActionResult>>,
+        WriteTiles<'s, FluidDepth>,
+        ReadStorage<'s, Blackboard>,
+        ReadStorage<'s, CurrentAction>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, Container>,
+        ReadStorage<'s, components::Item>,
+        ReadStorage<'s, LiquidSource>,
+        ReadStorage<'s, HeatSource>,
+        WriteStorage<'s, Liquid>,
+        WriteStorage<'s, Needs>,
+        WriteStorage<'s, BodyTemperature>,
+        WriteStorage<'s, components::Actionable>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.action_reader.setup(res);
+
+        Planner::setup_pawn_actions(res, liquids::actions());
+        let mut selector = res
+            .entry::<GoalSelector>()
+            .or_insert_with(GoalSelector::default);
+        for goal in liquids::goals() {
+            selector.insert(goal);
+        }
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            hierarchy,
+            details,
+            materials,
+            mut results,
+            mut fluids,
+            blackboards,
+            current_actions,
+            tile_positions,
+            containers,
+            items,
+            sources,
+            heat_sources,
+            mut liquids,
+            mut needs,
+            mut bodies,
+            mut actionables,
+        ): Self::SystemData,
+    ) {
+        self.action_reader.maintain(&entities, &mut actionables);
+        let mut requests = Vec::new();
+        for (entity, actionable) in (&entities, &mut actionables).join() {
+            for action in self.action_reader.read(entity, actionable) {
+                match action {
+                    Action::Fill | Action::Drink | Action::Pour | Action::Boil => {
+                        requests.push((entity, *action))
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        for (entity, action) in requests {
+            let here = match tile_positions.get(entity) {
+                Some(position) => position.coord,
+                None => {
+                    results.single_write(ActionResult::failed(entity, action));
+                    continue;
+                }
+            };
+            let target = match blackboards
+                .get(entity)
+                .and_then(|blackboard| blackboard.target)
+            {
+                Some(Target::Entity(target)) => Some(target),
+                _ => None,
+            };
+            let carried = inventory::get_all_items(entity, &hierarchy, &containers, &items);
+            let reachable = |target: Entity| {
+                carried.contains(target.id())
+                    || tile_positions
+                        .get(target)
+                        .map_or(false, |position| distance(here, position.coord) <= 1)
+            };
+            let held = (&entities, &containers, &carried)
+                .join()
+                .map(|(container, _, _)| container)
+                .collect::<Vec<_>>();
+            let filled = |liquids: &WriteStorage<'_, Liquid>, container: &Entity| {
+                liquids
+                    .get(*container)
+                    .map_or(false, |liquid| !liquid.is_empty())
+            };
+
+            let worked = match action {
+                Action::Fill => {
+                    let id = tiles.id_from_vector(here);
+                    let beside_fluid = std::iter::once(id)
+                        .chain(tiles.neighbors8(id))
+                        .any(|id| fluids.get(id).map_or(false, |depth| depth.0 > 0));
+                    let material = match target
+                        .filter(|target| reachable(*target))
+                        .and_then(|target| sources.get(target))
+                    {
+                        Some(source) => source.0.clone(),
+                        None if beside_fluid => river_liquid.to_string(),
+                        None => {
+                            results.single_write(ActionResult::failed(entity, action));
+                            continue;
+                        }
+                    };
+                    let density = materials
+                        .get(&material)
+                        .and_then(|material| material.density(&MaterialState::Liquid))
+                        .map_or(default_liquid_density, |density| density as f32);
+                    let fill = {
+                        let contents = Contents {
+                            hierarchy: &hierarchy,
+                            containers: &containers,
+                            items: &items,
+                            liquids: &liquids,
+                            details: &details,
+                        };
+                        held.iter().find_map(|container| {
+                            let volume = room(
+                                containers.get(*container)?,
+                                contents.held(*container),
+                                liquids.get(*container),
+                                &material,
+                                density,
+                            )
+                            .ok()?;
+                            Some((*container, volume)).filter(|_| volume > 0.0)
+                        })
+                    };
+                    match fill {
+                        Some((container, volume)) => {
+                            let liquid = Liquid::new(&material, volume, density);
+                            pour_into(&mut liquids, container, liquid);
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                Action::Boil => {
+                    let heated = (&heat_sources, &tile_positions)
+                        .join()
+                        .any(|(_, position)| distance(here, position.coord) <= 1);
+                    let unboiled = held.iter().cloned().find(|container| {
+                        filled(&liquids, container) && !liquids.get(*container).unwrap().boiled
+                    });
+                    match unboiled {
+                        Some(container) if heated => {
+                            liquids.get_mut(container).unwrap().boiled = true;
+                            true
+                        }
+                        _ => false,
+                    }
+                }
+                Action::Drink => {
+                    // Something hot first, for those who boiled it
+                    let drink = held
+                        .iter()
+                        .cloned()
+                        .filter(|container| filled(&liquids, container))
+                        .max_by_key(|container| liquids.get(*container).unwrap().boiled);
+                    match drink {
+                        Some(container) => {
+                            let liquid = liquids.get_mut(container).unwrap();
+                            let drunk = liquid.take(draught) / draught;
+                            let boiled = liquid.boiled;
+                            if liquid.is_empty() {
+                                liquids.remove(container);
+                            }
+
+                            if let Some(needs) = needs.get_mut(entity) {
+                                needs.thirst.satisfy(draught_thirst * drunk);
+                            }
+                            if let Some(body) = bodies.get_mut(entity).filter(|_| boiled) {
+                                if body.celsius < normal_body_temperature {
+                                    body.celsius = (body.celsius + hot_drink_warmth * drunk)
+                                        .min(normal_body_temperature);
+                                }
+                            }
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                Action::Pour => {
+                    let from = match held
+                        .iter()
+                        .cloned()
+                        .find(|container| filled(&liquids, container))
+                    {
+                        Some(from) => from,
+                        None => {
+                            results.single_write(ActionResult::failed(entity, action));
+                            continue;
+                        }
+                    };
+                    let into = target.filter(|into| {
+                        *into != from && containers.contains(*into) && reachable(*into)
+                    });
+                    let mut liquid = liquids.remove(from).unwrap();
+                    let poured = match into {
+                        Some(into) => {
+                            let volume = {
+                                let contents = Contents {
+                                    hierarchy: &hierarchy,
+                                    containers: &containers,
+                                    items: &items,
+                                    liquids: &liquids,
+                                    details: &details,
+                                };
+                                room(
+                                    containers.get(into).unwrap(),
+                                    contents.held(into),
+                                    liquids.get(into),
+                                    &liquid.material,
+                                    liquid.density,
+                                )
+                            };
+                            match volume {
+                                Ok(volume) if volume > 0.0 => {
+                                    let volume = liquid.take(volume);
+                                    let poured = Liquid {
+                                        volume,
+                                        ..liquid.clone()
+                                    };
+                                    pour_into(&mut liquids, into, poured);
+                                    true
+                                }
+                                _ => false,
+                            }
+                        }
+                        // Out on the ground, where enough of it leaves a puddle
+                        None => {
+                            if liquid.volume >= puddle_volume {
+                                let id = tiles.id_from_vector(here);
+                                let depth = fluids.get(id).map_or(0, |depth| depth.0);
+                                fluids.insert(id, FluidDepth((depth + 1).min(max_fluid_depth)));
+                            }
+                            liquid.volume = 0.0;
+                            true
+                        }
+                    };
+                    if !liquid.is_empty() {
+                        liquids.insert(from, liquid).unwrap();
+                    }
+                    poured
+                }
+                _ => false,
+            };
+
+            if !worked {
+                results.single_write(ActionResult::failed(entity, action));
+                continue;
+            }
+            if !current_actions.contains(entity) {
+                results.single_write(ActionResult::completed(entity, action));
+            }
+        }
+    }
+}
+
+/// Adds `liquid` to what `container` holds, which `room` has checked is the same liquid, if any.
+fn pour_into(liquids: &mut WriteStorage<'_, Liquid>, container: Entity, liquid: Liquid) {
+    match liquids.get_mut(container) {
+        Some(held) if !held.is_empty() => held.add(liquid.volume, liquid.boiled),
+        _ => {
+            liquids.insert(container, liquid).unwrap();
+        }
+    }
+}
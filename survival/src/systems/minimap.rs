@@ -0,0 +1,145 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{
+        storage::{ComponentEvent, Tracked},
+        Component, Join, ReadExpect, ReadStorage, Write,
+    },
+    shrev::ReaderId,
+};
+use specs_static::Storage;
+use std::collections::HashSet;
+
+use crate::components::{
+    ai::{Creature, Pawn},
+    TerrainLayer, TileLayerSprite, TilePosition, Visibility,
+};
+use crate::fov::local_player;
+use crate::minimap::{creature_blip_color, pawn_blip_color, shade, unexplored_color, Minimap};
+use crate::tiles::{TileId, Tiles, WriteTiles};
+
+/// Readers of the tile storages the minimap is drawn from.
+struct Readers {
+    terrain: ReaderId<ComponentEvent>,
+    visibility: ReaderId<ComponentEvent>,
+}
+
+/// Keeps the `Minimap` of the map as explored by the local player, recoloring only the cells of
+/// tiles whose terrain or visibility changed, and puts blips on it for the player's pawns and the
+/// creatures in their sight. The tile storages are only registered once the level loads, so the
+/// minimap is drawn whole on the first run.
+#[derive(Default)]
+pub struct System {
+    readers: Option<Readers>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        ReadExpect<'s, Tiles>,
+        Write<'s, Minimap>,
+        WriteTiles<'s, TerrainLayer>,
+        WriteTiles<'s, Visibility>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, Pawn>,
+        ReadStorage<'s, Creature>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            tiles,
+            mut minimap,
+            mut terrain,
+            mut visibility,
+            tile_positions,
+            pawns,
+            creatures,
+        ): Self::SystemData,
+    ) {
+        let cells = if let Some(readers) = &mut self.readers {
+            let mut cells = HashSet::new();
+            changed_cells(*tiles, &minimap, &terrain, &mut readers.terrain, &mut cells);
+            changed_cells(
+                *tiles,
+                &minimap,
+                &visibility,
+                &mut readers.visibility,
+                &mut cells,
+            );
+            cells
+        } else {
+            *minimap = Minimap::new(*tiles);
+            self.readers = Some(Readers {
+                terrain: terrain.channel_mut().register_reader(),
+                visibility: visibility.channel_mut().register_reader(),
+            });
+            (0..minimap.height)
+                .flat_map(|y| (0..minimap.width).map(move |x| (x, y)))
+                .collect()
+        };
+
+        let depth = tiles.dimensions().z;
+        for (x, y) in cells {
+            // The topmost terrain of the column explored
+            let (sample_x, sample_y) = minimap.sample(*tiles, x, y);
+            let color = (0..depth)
+                .rev()
+                .map(|z| (z, tiles.id(sample_x, sample_y, z)))
+                .filter(|(_, id)| {
+                    visibility
+                        .get(*id)
+                        .map_or(false, |seen| seen.is_explored(local_player))
+                })
+                .find_map(|(z, id)| {
+                    terrain
+                        .get(id)
+                        .map(|layer| shade(layer.sprite().sprite_number, z, depth))
+                })
+                .unwrap_or(unexplored_color);
+            minimap.set_pixel(x, y, color);
+        }
+
+        let in_sight = |position: &TilePosition| {
+            visibility
+                .get(tiles.id_from_vector(position.coord))
+                .map_or(false, |seen| seen.is_visible(local_player))
+        };
+        let pawn_blips = (&tile_positions, &pawns)
+            .join()
+            .map(|(position, _)| (position.coord, pawn_blip_color));
+        let creature_blips = (&tile_positions, &creatures)
+            .join()
+            .filter(|(position, _)| in_sight(position))
+            .map(|(position, _)| (position.coord, creature_blip_color));
+        let blips = pawn_blips
+            .chain(creature_blips)
+            .map(|(coord, color)| {
+                let (x, y) = minimap.cell_of(coord);
+                (x, y, color)
+            })
+            .collect();
+        minimap.blips = blips;
+    }
+}
+
+/// Adds the cells of the tiles changed in `storage` since `reader` last read it to `cells`.
+fn changed_cells<C>(
+    tiles: Tiles,
+    minimap: &Minimap,
+    storage: &Storage<C, C::Storage, TileId>,
+    reader: &mut ReaderId<ComponentEvent>,
+    cells: &mut HashSet<(u32, u32)>,
+) where
+    C: Component,
+    C::Storage: Tracked,
+{
+    for event in storage.channel().read(reader) {
+        let slot = match event {
+            ComponentEvent::Inserted(slot)
+            | ComponentEvent::Modified(slot)
+            | ComponentEvent::Removed(slot) => *slot,
+        };
+        if let Some(id) = storage.id_of_slot(slot) {
+            cells.insert(minimap.cell_of(tiles.coord(id)));
+        }
+    }
+}
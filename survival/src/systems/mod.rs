@@ -3,9 +3,120 @@ pub mod ui;
 pub mod tile_position;
 pub use tile_position::System as TilePositionSystem;
 
+pub mod fov;
+pub use fov::System as FovSystem;
+
+pub mod connectivity;
+pub use connectivity::System as ConnectivitySystem;
+
+pub mod path_cache;
+pub use path_cache::System as PathCacheSystem;
+
+pub mod pathfinding;
+pub use pathfinding::System as PathfindingSystem;
+
+pub mod mood;
+pub use mood::System as MoodSystem;
+
+pub mod needs;
+pub use needs::System as NeedsSystem;
+
+pub mod temperature;
+pub use temperature::System as TemperatureSystem;
+
+pub mod injury;
+pub use injury::System as InjurySystem;
+
+pub mod combat;
+pub use combat::System as CombatSystem;
+
+pub mod sensor;
+pub use sensor::System as SensorSystem;
+
+pub mod jobs;
+pub use jobs::System as JobSystem;
+
+pub mod creature;
+pub use creature::System as CreatureSystem;
+
+pub mod goap_planner;
+pub use goap_planner::System as GoapPlannerSystem;
+
+pub mod plan_execution;
+pub use plan_execution::System as PlanExecutionSystem;
+
+pub mod action_progress;
+pub use action_progress::System as ActionProgressSystem;
+
+pub mod crafting;
+pub use crafting::System as CraftingSystem;
+
+pub mod spoilage;
+pub use spoilage::System as SpoilageSystem;
+
+pub mod liquids;
+pub use liquids::System as LiquidSystem;
+
+pub mod butchery;
+pub use butchery::System as ButcherySystem;
+
+pub mod eating;
+pub use eating::System as EatingSystem;
+
+pub mod durability;
+pub use durability::System as DurabilitySystem;
+
+pub mod asset_reload;
+pub use asset_reload::System as AssetReloadSystem;
+
+pub mod fluids;
+pub use fluids::System as FluidSystem;
+
+pub mod weather;
+pub use weather::System as WeatherSystem;
+
+pub mod render_chunks;
+pub use render_chunks::System as RenderChunkSystem;
+
+pub mod autotile;
+pub use autotile::System as AutotileSystem;
+
+pub mod animated_tiles;
+pub use animated_tiles::System as AnimatedTileSystem;
+
+pub mod minimap;
+pub use minimap::System as MinimapSystem;
+
+pub mod lighting;
+pub use lighting::System as LightingSystem;
+
+pub mod selection;
+pub use selection::System as SelectionSystem;
+
+pub mod farming;
+pub use farming::System as FarmingSystem;
+
+pub mod plant_growth;
+pub use plant_growth::System as PlantGrowthSystem;
+
+pub mod trees;
+pub use trees::System as TreeSystem;
+
+pub mod designations;
+pub use designations::System as DesignationSystem;
+
+pub mod tile_entity;
+pub use tile_entity::System as TileEntityCleanupSystem;
+
+pub mod threat;
+pub use threat::System as ThreatSystem;
+
 pub mod time;
 pub use time::System as TimeSystem;
 
+pub mod autosave;
+pub use autosave::System as AutosaveSystem;
+
 pub mod script;
 pub use script::System as ScriptSystem;
 
@@ -19,9 +130,15 @@ pub use nutrition::System as NutritionSystem;
 pub mod movement;
 pub use movement::System as MovementSystem;
 
+pub mod doors;
+pub use doors::System as DoorSystem;
+
 pub mod input;
 pub use input::System as InputSystem;
 
+pub mod camera_follow;
+pub use camera_follow::System as CameraFollowSystem;
+
 pub mod initiative;
 pub use initiative::System as InitiativeSystem;
 
@@ -0,0 +1,100 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{Join, Read, ReadExpect, ReadStorage, Resources, SystemData, Write, WriteStorage},
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::components::{
+    ai::{Activity, Personality, Schedule, ThoughtEvent, ThoughtKind},
+    Bed, TilePosition,
+};
+use crate::systems::time::TimeState;
+use crate::tiles::{ReadTiles, TileEntities, Tiles};
+
+/// Gives pawns the thoughts sent as `ThoughtEvent`s, fades their thoughts as game time passes,
+/// and has pawns think about where they slept when a scheduled sleep ends.
+#[derive(Default)]
+pub struct System {
+    thought_reader: Option<ReaderId<ThoughtEvent>>,
+    last_time: Option<u64>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        ReadExpect<'s, Tiles>,
+        Read<'s, TimeState>,
+        Read<'s, EventChannel<ThoughtEvent>>,
+        ReadTiles<'s, TileEntities>,
+        ReadStorage<'s, Bed>,
+        ReadStorage<'s, Schedule>,
+        ReadStorage<'s, TilePosition>,
+        WriteStorage<'s, Personality>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.thought_reader =
+            Some(Write::<EventChannel<ThoughtEvent>>::fetch(&res).register_reader());
+    }
+
+    fn run(
+        &mut self,
+        (
+            tiles,
+            time,
+            thought_events,
+            occupants,
+            beds,
+            schedules,
+            tile_positions,
+            mut personalities,
+        ): Self::SystemData,
+    ) {
+        for event in thought_events.read(self.thought_reader.as_mut().unwrap()) {
+            if let Some(personality) = personalities.get_mut(event.entity) {
+                personality.think(event.kind);
+            }
+        }
+
+        let now = time.current_time;
+        let last = TimeState {
+            current_time: self.last_time.unwrap_or(now),
+            ..*time
+        };
+        self.last_time = Some(now);
+        let elapsed = now.saturating_sub(last.current_time);
+        if elapsed == 0 {
+            return;
+        }
+
+        let (hour, last_hour) = (time.hour(), last.hour());
+        for (personality, schedule, position) in (
+            &mut personalities,
+            schedules.maybe(),
+            tile_positions.maybe(),
+        )
+            .join()
+        {
+            personality.tick(elapsed);
+
+            let woke = schedule.map_or(false, |schedule| {
+                hour != last_hour
+                    && schedule.activity(last_hour) == Activity::Sleep
+                    && schedule.activity(hour) != Activity::Sleep
+            });
+            if woke {
+                let in_bed = position
+                    .and_then(|position| occupants.get(tiles.id_from_vector(position.coord)))
+                    .map_or(false, |occupants| {
+                        occupants.0.iter().any(|entity| beds.contains(*entity))
+                    });
+                personality.think(if in_bed {
+                    ThoughtKind::SleptInBed
+                } else {
+                    ThoughtKind::SleptOnFloor
+                });
+            }
+        }
+    }
+}
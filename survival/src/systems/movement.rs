@@ -1,34 +1,72 @@
 #![allow(clippy::module_name_repetitions)]
-use crate::settings::Context;
 use amethyst::{
-    core::components::Transform,
-    ecs::{Entities, Join, Read, ReadExpect, Resources, SystemData, WriteStorage},
+    core::{components::Transform, math::Vector3},
+    ecs::{
+        Entities, Entity, Join, Read, ReadExpect, ReadStorage, Resources, SystemData, Write,
+        WriteStorage,
+    },
+    shrev::EventChannel,
 };
+use std::collections::HashMap;
 
-use crate::actions::{Action};
-use crate::components;
+use crate::actions::{Action, ActionOutcome, ActionResult, Target};
+use crate::components::{
+    self,
+    ai::{Blackboard, Creature},
+    Doorway, Obstruction, Path, PathFailed, TilePosition, TimeAvailable,
+};
+use crate::pathfinding::{distance, MovementProfile, PathFlags, PathRequest, PathRequests};
+use crate::settings::Config;
+use crate::systems::time::TimeState;
+use crate::tiles::{ReadTiles, TileEntities, Tiles, WriteTiles};
 use crate::utils::ComponentEventReader;
+use crate::weather::{sheltered, Weather};
 
+/// Game time taken to move a tile.
+pub const step_time: u64 = 1;
 
+/// A `Move` or `MoveTo` being carried out, finished once its path runs out.
+#[derive(Clone, Copy, Debug)]
+struct Moving {
+    action: Action,
+    goal: Vector3<u32>,
+}
 
-use crate::settings::Config;
-use crate::tiles::{ReadTiles, Tiles};
-
+/// Moves entities along their `Path`, a tile per `step_time`, spending their `TimeAvailable` or
+/// else the game time passed. `Move` and `MoveTo` events request a path to the target their
+/// `Blackboard` holds, a single step for a `Direction`, and report an `ActionResult` once
+/// there. Stepping onto an impassable tile fails the path instead, unless it is a shut door which
+/// will open for them, where they wait. Steps out in the open take longer through lying snow,
+/// by the `Weather`. The `Transform`, `TilePosition` and `TileEntities` of the tiles are all
+/// updated together.
 #[derive(Default)]
 pub struct System {
     action_reader: ComponentEventReader<components::Actionable, Action>,
+    moving: HashMap<Entity, Moving>,
+    /// Time already put towards a step too slow to take at once, such as through snow.
+    wading: HashMap<Entity, u64>,
+    last_time: Option<u64>,
 }
 impl<'s> amethyst::ecs::System<'s> for System {
     type SystemData = (
-        ReadExpect<'s, Context>,
-        Read<'s, Config>,
-        ReadExpect<'s, Tiles>,
         Entities<'s>,
-        WriteStorage<'s, components::TimeAvailable>,
+        ReadExpect<'s, Config>,
+        ReadExpect<'s, Tiles>,
+        Read<'s, TimeState>,
+        Read<'s, Weather>,
+        Read<'s, PathRequests>,
+        Write<'s, EventChannel<ActionResult>>,
+        ReadTiles<'s, Obstruction>,
+        ReadTiles<'s, Doorway>,
+        WriteTiles<'s, TileEntities>,
+        ReadStorage<'s, Blackboard>,
+        ReadStorage<'s, Creature>,
         WriteStorage<'s, components::Actionable>,
+        WriteStorage<'s, TimeAvailable>,
         WriteStorage<'s, Transform>,
-        // Tile storages
-        ReadTiles<'s, components::Obstruction>,
+        WriteStorage<'s, TilePosition>,
+        WriteStorage<'s, Path>,
+        WriteStorage<'s, PathFailed>,
     );
 
     fn setup(&mut self, res: &mut Resources) {
@@ -40,73 +78,189 @@ impl<'s> amethyst::ecs::System<'s> for System {
     fn run(
         &mut self,
         (
-            _context,
-            _game_config,
-            _tiles,
             entities,
-            mut times,
+            game_config,
+            tiles,
+            time,
+            weather,
+            requests,
+            mut results,
+            obstructions,
+            doorways,
+            mut occupants,
+            blackboards,
+            creatures,
             mut actionables,
+            mut times,
             mut transforms,
-            _tile_impassable,
+            mut tile_positions,
+            mut paths,
+            mut path_failures,
         ): Self::SystemData,
     ) {
+        let now = time.current_time;
+        let elapsed = now.saturating_sub(self.last_time.unwrap_or(now));
+        self.last_time = Some(now);
+
         self.action_reader.maintain(&entities, &mut actionables);
+        self.moving.retain(|entity, _| entities.is_alive(*entity));
+        self.wading.retain(|entity, _| entities.is_alive(*entity));
 
-        // Read components...
-        for (entity, _time_comp, actionable, _transform) in
-            (&entities, &mut times, &mut actionables, &mut transforms).join()
+        for (entity, actionable, position) in (&entities, &mut actionables, &tile_positions).join()
         {
-            for _event in self.action_reader.read(entity, actionable) {
-                /*
-                if let Action::Move(direction) = event {
-                    if crate::systems::time::has_time(1, entity, time_comp) {
-                        //slog_trace!(context.logs.root, "Moving entity: {}", direction);
-                        // Once its confirmed they can do it, run it
-                        crate::systems::time::consume_time(1, entity, time_comp);
+            for action in self.action_reader.read(entity, actionable) {
+                match action {
+                    Action::Move | Action::MoveTo => (),
+                    _ => continue,
+                }
+                let target = blackboards
+                    .get(entity)
+                    .and_then(|blackboard| blackboard.target);
+                let goal = target.and_then(|target| match target {
+                    Target::Entity(target) => {
+                        tile_positions.get(target).map(|position| position.coord)
+                    }
+                    Target::Location(location) => Some(Vector3::new(
+                        location.x.max(0.0) as u32,
+                        location.y.max(0.0) as u32,
+                        location.z.max(0.0) as u32,
+                    )),
+                    Target::Direction(direction) => {
+                        let (x, y) = direction.offset();
+                        tiles
+                            .offset(tiles.id_from_vector(position.coord), x, y, 0)
+                            .map(|id| tiles.coord(id))
+                    }
+                    Target::Under | Target::SelfTarget => Some(position.coord),
+                });
+                let goal = match goal {
+                    Some(goal) => goal,
+                    None => {
+                        results.single_write(ActionResult::failed(entity, *action));
+                        continue;
+                    }
+                };
 
-                        // And finally, move one tile in the given direction
-                        let mut target = transform.clone();
+                match target {
+                    // A single step needs no pathfinding
+                    Some(Target::Direction(_)) => {
+                        paths
+                            .insert(entity, Path::new(vec![position.coord, goal], 1.0))
+                            .unwrap();
+                    }
+                    _ => {
+                        paths.remove(entity);
+                        path_failures.remove(entity);
+                        requests.request(PathRequest {
+                            entity,
+                            start: position.coord,
+                            goal,
+                            flags: PathFlags::None,
+                            profile: MovementProfile::default(),
+                        });
+                    }
+                }
+                self.moving.insert(
+                    entity,
+                    Moving {
+                        action: *action,
+                        goal,
+                    },
+                );
+            }
+        }
 
-                        match direction {
-                            Direction::N => {
-                                target.move_up(5.0);
-                            }
-                            Direction::S => {
-                                target.move_down(5.0);
-                            }
-                            Direction::E => {
-                                target.move_right(5.0);
-                            }
-                            Direction::W => {
-                                target.move_left(5.0);
-                            }
-                            _ => {
-                                slog_error!(context.logs.root, "Unsupported direction");
-                            }
-                        }
+        let roofed = |id| match obstructions.get(id) {
+            Some(Obstruction::Impassable) => true,
+            _ => false,
+        };
+        for (entity, path, transform, position, mut time) in (
+            &entities,
+            &mut paths,
+            &mut transforms,
+            &mut tile_positions,
+            (&mut times).maybe(),
+        )
+            .join()
+        {
+            let mut budget = time.as_ref().map_or(elapsed, |time| time.0);
+            while budget >= step_time {
+                let next = match path.next() {
+                    Some(next) => next,
+                    None => break,
+                };
+                let next_id = tiles.id_from_vector(next);
+                match (obstructions.get(next_id), doorways.get(next_id)) {
+                    (Some(Obstruction::Impassable), Some(doorway))
+                        if doorway.admits(creatures.contains(entity)) =>
+                    {
+                        break;
+                    }
+                    (Some(Obstruction::Impassable), _) => {
+                        path_failures.insert(entity, PathFailed).unwrap();
+                        path.current = path.points.len();
+                        break;
+                    }
+                    _ => (),
+                }
 
-                        // Can we actually go to the target?
-                        let target_tile = tiles.world_to_tile(target.translation(), &game_config);
+                let cost = if sheltered(*tiles, next, roofed) {
+                    step_time
+                } else {
+                    (step_time as f32 * weather.movement_cost()).round() as u64
+                };
+                let spent = self.wading.remove(&entity).unwrap_or(0);
+                if spent + budget < cost {
+                    self.wading.insert(entity, spent + budget);
+                    if let Some(time) = time.as_mut() {
+                        time.consume(budget);
+                    }
+                    break;
+                }
+                let owed = cost.saturating_sub(spent);
 
-                        if tile_impassable
-                            .get(tiles.id_from_vector(target_tile))
-                            .is_some()
-                        {
-                            // We cant do the move!
-                            slog_warn!(
-                                context.logs.root,
-                                "Attempted to move to impassable tile: ({},{})",
-                                target_tile.x,
-                                target_tile.y
-                            );
-                            continue;
-                        }
+                if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(position.coord)) {
+                    occupants.0.remove(&entity);
+                }
+                if let Some(occupants) = occupants.get_mut(next_id) {
+                    occupants.0.insert(entity);
+                }
+                let world = tiles.tile_to_world(next, &game_config);
+                transform.set_translation_x(world.x);
+                transform.set_translation_y(world.y);
+                position.coord = next;
 
-                        *transform = target;
-                    }
+                path.advance();
+                budget -= owed;
+                if let Some(time) = time.as_mut() {
+                    time.consume(owed);
                 }
-                */
             }
         }
+
+        // Report the moves which arrived, or can't. Goals which can't be stood on, such as a
+        // tree, are arrived at once the path ends next to them.
+        let moving = self.moving.drain().collect::<Vec<_>>();
+        for (entity, moving) in moving {
+            let here = tile_positions.get(entity).map(|position| position.coord);
+            let arrived = here.map_or(false, |here| {
+                here == moving.goal
+                    || (paths.get(entity).map_or(false, Path::is_finished)
+                        && distance(here, moving.goal) <= 1)
+            });
+            let outcome = if path_failures.contains(entity) {
+                ActionOutcome::Failed
+            } else if arrived {
+                ActionOutcome::Completed
+            } else {
+                self.moving.insert(entity, moving);
+                continue;
+            };
+            results.single_write(ActionResult {
+                entity,
+                action: moving.action,
+                outcome,
+            });
+        }
     }
 }
@@ -0,0 +1,90 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{Entities, Join, LazyUpdate, Read, ReadStorage, Write, WriteStorage},
+    shrev::EventChannel,
+};
+
+use crate::butchery::leave_corpse;
+use crate::components::{
+    ai::{Creature, CurrentAction, Needs, Perception, ThoughtEvent, ThoughtKind, AI},
+    TilePosition,
+};
+use crate::goap::Motive;
+use crate::systems::time::TimeState;
+
+/// Grows the `Needs` of every pawn as game time passes. A need becoming urgent interrupts the
+/// current plan so the goal selector can weigh it, unless the `CurrentAction` is nearly done, and
+/// pawns left at a lethal extreme for too long die, troubling everyone who knew where they were.
+/// Creatures which die of it leave a corpse behind.
+#[derive(Default)]
+pub struct System {
+    last_time: Option<u64>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, TimeState>,
+        Read<'s, LazyUpdate>,
+        Write<'s, EventChannel<ThoughtEvent>>,
+        ReadStorage<'s, Perception>,
+        ReadStorage<'s, CurrentAction>,
+        ReadStorage<'s, Creature>,
+        ReadStorage<'s, TilePosition>,
+        WriteStorage<'s, Needs>,
+        WriteStorage<'s, AI>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            time,
+            lazy,
+            mut thoughts,
+            perceptions,
+            current_actions,
+            creatures,
+            tile_positions,
+            mut needs,
+            mut ais,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+        let elapsed = now.saturating_sub(self.last_time.unwrap_or(now));
+        self.last_time = Some(now);
+        if elapsed == 0 {
+            return;
+        }
+
+        let mut dead = Vec::new();
+        for (entity, needs, ai) in (&entities, &mut needs, (&mut ais).maybe()).join() {
+            if needs.tick(elapsed) {
+                // Every need interrupts alike
+                if let Some(ai) = ai {
+                    ai.interrupt_for(&Motive::Hunger, current_actions.get(entity));
+                }
+            }
+
+            if needs.is_dying() {
+                log::info!("{:?} died of deprivation: {:?}", entity, needs);
+                if let (Some(creature), Some(position)) =
+                    (creatures.get(entity), tile_positions.get(entity))
+                {
+                    leave_corpse(&lazy, &creature.kind, position.coord);
+                }
+                entities.delete(entity).unwrap();
+                dead.push(entity);
+            }
+        }
+
+        for (entity, perception) in (&entities, &perceptions).join() {
+            for _ in dead.iter().filter(|dead| perception.get(**dead).is_some()) {
+                thoughts.single_write(ThoughtEvent {
+                    entity,
+                    kind: ThoughtKind::SawCorpse,
+                });
+            }
+        }
+    }
+}
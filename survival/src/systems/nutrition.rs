@@ -1,8 +1,10 @@
 #![allow(clippy::module_name_repetitions)]
+use crate::components::ai::{ThoughtEvent, ThoughtKind};
 use crate::settings::Context;
 use amethyst::{
     ecs::{
-        Component, DenseVecStorage, Entity, Read, ReadExpect, Resources, SystemData, WriteStorage,
+        Component, DenseVecStorage, Entity, Read, ReadExpect, Resources, SystemData, Write,
+        WriteStorage,
     },
     shrev::{EventChannel, ReaderId},
 };
@@ -11,12 +13,19 @@ use specs_derive::Component;
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 enum Vitamin {}
 
-#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Food {
+    #[serde(default)]
     pub vitamins: [f32; 25],
     pub calories: u32,
     pub sugars: u32,
     pub fats: u32,
+    /// How well it was cooked, from 0 for raw food to 1.
+    #[serde(default)]
+    pub quality: f32,
+    /// Game time it keeps for at `reference_spoil_temperature`, if it spoils at all.
+    #[serde(default)]
+    pub shelf_life: Option<u64>,
 }
 
 #[derive(Component, Default, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -67,6 +76,20 @@ impl Default for Nutrition {
         }
     }
 }
+impl Nutrition {
+    /// Takes in the vitamins and calories of something eaten.
+    pub fn eat(&mut self, food: &Food) {
+        for (vitamin, amount) in self.vitamins.iter_mut().zip(food.vitamins.iter()) {
+            *vitamin += amount;
+        }
+        self.caloric_balance += food.calories as f32 / self.caloric_track as f32;
+    }
+}
+
+/// Meals with at least these calories, cooked at least this well, are a good meal to the pawn
+/// eating them.
+pub const good_meal_calories: u32 = 600;
+pub const good_meal_quality: f32 = 0.5;
 
 #[derive(Default)]
 pub struct System {
@@ -76,6 +99,7 @@ impl<'s> amethyst::ecs::System<'s> for System {
     type SystemData = (
         ReadExpect<'s, Context>,
         Read<'s, EventChannel<(Entity, Food)>>,
+        Write<'s, EventChannel<ThoughtEvent>>,
         WriteStorage<'s, Nutrition>,
     );
 
@@ -88,5 +112,20 @@ impl<'s> amethyst::ecs::System<'s> for System {
         );
     }
 
-    fn run(&mut self, _: Self::SystemData) {}
+    fn run(&mut self, (_, meals, mut thoughts, mut nutrition): Self::SystemData) {
+        for (entity, food) in meals.read(self.consume_reader_id.as_mut().unwrap()) {
+            if let Some(nutrition) = nutrition.get_mut(*entity) {
+                nutrition.eat(food);
+            }
+            let kind = if food.calories >= good_meal_calories && food.quality >= good_meal_quality {
+                ThoughtKind::AteGoodMeal
+            } else {
+                ThoughtKind::AtePoorMeal
+            };
+            thoughts.single_write(ThoughtEvent {
+                entity: *entity,
+                kind,
+            });
+        }
+    }
 }
@@ -0,0 +1,86 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{Read, ReadExpect, Resources, SystemData, Write, WriteExpect},
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::components::{Doorway, FluidDepth, Footing, Obstruction, ZTransition};
+use crate::pathfinding::{FlowFields, HierarchicalGraph, MovementProfile, PathCache, TerrainCost};
+use crate::tiles::{ReadTiles, TileChangeEvent, TileEntities, Tiles};
+
+/// Builds the hierarchical path graph and z-transition cache, and rebuilds them as tiles change.
+/// Flow fields affected by a change are dropped to be rebuilt on their next use, and every field
+/// is dropped when the stairs change.
+#[derive(Default)]
+pub struct System {
+    change_reader: Option<ReaderId<TileChangeEvent>>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        ReadExpect<'s, Tiles>,
+        Read<'s, EventChannel<TileChangeEvent>>,
+        ReadTiles<'s, Obstruction>,
+        ReadTiles<'s, Footing>,
+        ReadTiles<'s, FluidDepth>,
+        ReadTiles<'s, Doorway>,
+        ReadTiles<'s, TileEntities>,
+        ReadTiles<'s, ZTransition>,
+        WriteExpect<'s, HierarchicalGraph>,
+        Write<'s, PathCache>,
+        Write<'s, FlowFields>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.change_reader =
+            Some(Write::<EventChannel<TileChangeEvent>>::fetch(&res).register_reader());
+    }
+
+    fn run(
+        &mut self,
+        (
+            tiles,
+            changes,
+            obstructions,
+            footing,
+            fluids,
+            doors,
+            occupants,
+            z_transitions,
+            mut graph,
+            mut path_cache,
+            mut flow_fields,
+        ): Self::SystemData,
+    ) {
+        let terrain = TerrainCost {
+            obstructions: &obstructions,
+            footing: &footing,
+            fluids: &fluids,
+            doors: &doors,
+            occupants: &occupants,
+        };
+        let cost = |id| terrain.settled_cost(MovementProfile::default(), id);
+
+        let mut stairs_changed = false;
+        for event in changes.read(self.change_reader.as_mut().unwrap()) {
+            graph.invalidate(event.id);
+            flow_fields.invalidate(*tiles, event.id);
+            stairs_changed |= z_transitions.get(event.id).is_some()
+                || path_cache.transition(tiles.coord(event.id)).is_some();
+        }
+
+        if !graph.is_built() {
+            graph.build(&cost);
+        } else {
+            graph.rebuild_dirty(&cost);
+        }
+
+        if stairs_changed || !path_cache.is_built() {
+            path_cache.rebuild_from(*tiles, &z_transitions, &obstructions);
+            // Fields follow the stairs in the cache, so any of them may now lead elsewhere
+            flow_fields.clear();
+        }
+    }
+}
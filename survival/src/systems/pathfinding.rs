@@ -0,0 +1,343 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{
+        Entities, Entity, Join, ParJoin, Read, ReadExpect, Resources, SystemData, Write,
+        WriteStorage,
+    },
+    shrev::{EventChannel, ReaderId},
+};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use crate::components::{Doorway, FluidDepth, Footing, Obstruction, Path, PathFailed, Replanner};
+use crate::pathfinding::{
+    smooth_path, DStarLite, FlowFields, FlowGoal, HierarchicalGraph, MovementProfile, PathCache,
+    PathFlags, PathRequest, PathRequests, Pathfinding, TerrainCost, TerrainSnapshot,
+};
+use crate::tiles::{ReadTiles, TileChangeEvent, TileEntities, TileId, Tiles};
+
+/// A request, and the path and any replanner found for it.
+type Found = (PathRequest, Option<Path>, Option<Replanner>);
+
+/// Services queued `PathRequests`, and repairs incremental paths as tiles change. Flow fields are
+/// shared between requests, so are followed at once. Other requests are searched as a batch on
+/// the rayon thread pool, over a snapshot of the terrain, while the game carries on; the results
+/// are collected in a later frame, and the next batch started.
+#[derive(Default)]
+pub struct System {
+    change_reader: Option<ReaderId<TileChangeEvent>>,
+    /// Requests waiting for the batch being searched, the latest for each entity.
+    queued: HashMap<Entity, PathRequest>,
+    /// The batch being searched.
+    searching: Option<Receiver<Vec<Found>>>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        Read<'s, PathRequests>,
+        Read<'s, EventChannel<TileChangeEvent>>,
+        ReadTiles<'s, Obstruction>,
+        ReadTiles<'s, Footing>,
+        ReadTiles<'s, FluidDepth>,
+        ReadTiles<'s, Doorway>,
+        ReadTiles<'s, TileEntities>,
+        Read<'s, PathCache>,
+        Read<'s, Pathfinding>,
+        ReadExpect<'s, HierarchicalGraph>,
+        Write<'s, FlowFields>,
+        WriteStorage<'s, Path>,
+        WriteStorage<'s, PathFailed>,
+        WriteStorage<'s, Replanner>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.change_reader =
+            Some(Write::<EventChannel<TileChangeEvent>>::fetch(&res).register_reader());
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            requests,
+            changes,
+            obstructions,
+            footing,
+            fluids,
+            doors,
+            occupants,
+            path_cache,
+            pathfinding,
+            graph,
+            mut flow_fields,
+            mut paths,
+            mut failed,
+            mut replanners,
+        ): Self::SystemData,
+    ) {
+        let terrain = TerrainCost {
+            obstructions: &obstructions,
+            footing: &footing,
+            fluids: &fluids,
+            doors: &doors,
+            occupants: &occupants,
+        };
+
+        let changed = changes
+            .read(self.change_reader.as_mut().unwrap())
+            .map(|event| event.id)
+            .collect::<Vec<_>>();
+        if !changed.is_empty() {
+            repair_paths(
+                *tiles,
+                &changed,
+                &path_cache,
+                &terrain,
+                &entities,
+                &mut paths,
+                &mut failed,
+                &mut replanners,
+            );
+        }
+
+        for request in requests.drain() {
+            self.queued.insert(request.entity, request);
+        }
+
+        let mut found = match self.searching.as_ref().map(Receiver::try_recv) {
+            Some(Ok(found)) => {
+                self.searching = None;
+                found
+            }
+            Some(Err(TryRecvError::Disconnected)) => {
+                self.searching = None;
+                Vec::new()
+            }
+            Some(Err(TryRecvError::Empty)) | None => Vec::new(),
+        };
+
+        // A path is still followed until a newer request for the entity replaces it, but its
+        // failure says nothing of the newer request. The same request again needn't be searched.
+        found.retain(
+            |(request, path, _)| match self.queued.get(&request.entity) {
+                Some(newer) if newer == request => {
+                    self.queued.remove(&request.entity);
+                    true
+                }
+                Some(_) => path.is_some(),
+                None => true,
+            },
+        );
+
+        let flow_requests = self
+            .queued
+            .values()
+            .filter(|request| request.flags.contains(PathFlags::Flow))
+            .cloned()
+            .collect::<Vec<_>>();
+        for request in flow_requests {
+            self.queued.remove(&request.entity);
+            let start = tiles.id_from_vector(request.start);
+            let field = flow_fields.get_or_build(
+                *tiles,
+                FlowGoal::Tile(tiles.id_from_vector(request.goal)),
+                request.profile,
+                Some(&path_cache),
+                &|id| terrain.cost(request.profile, id),
+            );
+            let path = field.path_from(start).map(|(path, total)| {
+                Path::new(path.into_iter().map(|id| tiles.coord(id)).collect(), total)
+            });
+            found.push((request, path, None));
+        }
+
+        if self.searching.is_none() && !self.queued.is_empty() {
+            let batch = self
+                .queued
+                .drain()
+                .map(|(_, request)| request)
+                .collect::<Vec<_>>();
+            let (tiles, pathfinding) = (*tiles, *pathfinding);
+            let (graph, path_cache) = (graph.clone(), path_cache.clone());
+            let terrain = terrain.snapshot(tiles);
+            let (sender, receiver) = mpsc::channel();
+            rayon::spawn(move || {
+                let found = batch
+                    .par_iter()
+                    .map(|request| {
+                        search(tiles, request, pathfinding, &graph, &path_cache, &terrain)
+                    })
+                    .collect::<Vec<_>>();
+                // Nobody is waiting once the world is gone
+                let _ = sender.send(found);
+            });
+            self.searching = Some(receiver);
+        }
+
+        for (request, path, replanner) in found {
+            let entity = request.entity;
+            if !entities.is_alive(entity) {
+                continue;
+            }
+
+            match replanner {
+                Some(replanner) => {
+                    replanners.insert(entity, replanner).unwrap();
+                }
+                None => {
+                    replanners.remove(entity);
+                }
+            }
+
+            match path {
+                Some(path) => {
+                    failed.remove(entity);
+                    paths.insert(entity, path).unwrap();
+                }
+                None => {
+                    paths.remove(entity);
+                    failed.insert(entity, PathFailed).unwrap();
+                }
+            }
+        }
+    }
+}
+
+fn search(
+    tiles: Tiles,
+    request: &PathRequest,
+    pathfinding: Pathfinding,
+    graph: &HierarchicalGraph,
+    path_cache: &PathCache,
+    terrain: &TerrainSnapshot,
+) -> Found {
+    if request.flags.contains(PathFlags::Incremental) {
+        let (path, replanner) = plan_incremental(tiles, request, path_cache, terrain);
+        (*request, path, Some(replanner))
+    } else {
+        let path = find_path(tiles, request, pathfinding, graph, path_cache, terrain);
+        (*request, path, None)
+    }
+}
+
+fn find_path(
+    tiles: Tiles,
+    request: &PathRequest,
+    pathfinding: Pathfinding,
+    graph: &HierarchicalGraph,
+    path_cache: &PathCache,
+    terrain: &TerrainSnapshot,
+) -> Option<Path> {
+    let start = tiles.id_from_vector(request.start);
+    let goal = tiles.id_from_vector(request.goal);
+    let cost = |id: TileId| terrain.cost(request.profile, id);
+
+    // The abstract graph only knows walkable routes
+    let (path, total) = if request.flags.contains(PathFlags::Hierarchical)
+        && !request
+            .profile
+            .intersects(MovementProfile::Swim | MovementProfile::Fly | MovementProfile::Dig)
+        && request.start.z == request.goal.z
+        && graph.is_built()
+    {
+        graph.find_path(start, goal, &cost)?
+    } else {
+        pathfinding.find(tiles, start, goal, Some(path_cache), &cost)?
+    };
+
+    let path = if request.flags.contains(PathFlags::Smooth) {
+        smooth_path(tiles, &path, |id| cost(id).is_none())
+    } else {
+        path
+    };
+
+    Some(Path::new(
+        path.into_iter().map(|id| tiles.coord(id)).collect(),
+        total,
+    ))
+}
+
+fn plan_incremental(
+    tiles: Tiles,
+    request: &PathRequest,
+    path_cache: &PathCache,
+    terrain: &TerrainSnapshot,
+) -> (Option<Path>, Replanner) {
+    let cost = |id: TileId| terrain.cost(request.profile, id);
+    let planner = DStarLite::new(
+        tiles,
+        tiles.id_from_vector(request.start),
+        tiles.id_from_vector(request.goal),
+        Some(path_cache),
+        &cost,
+    );
+    let path = planner
+        .path(tiles, Some(path_cache), &cost)
+        .map(|(path, total)| {
+            Path::new(path.into_iter().map(|id| tiles.coord(id)).collect(), total)
+        });
+
+    (
+        path,
+        Replanner {
+            planner,
+            profile: request.profile,
+        },
+    )
+}
+
+/// Repairs the plans of every entity following an incremental path from where it stands now.
+#[allow(clippy::too_many_arguments)]
+fn repair_paths(
+    tiles: Tiles,
+    changed: &[TileId],
+    path_cache: &PathCache,
+    terrain: &TerrainCost,
+    entities: &Entities,
+    paths: &mut WriteStorage<Path>,
+    failed: &mut WriteStorage<PathFailed>,
+    replanners: &mut WriteStorage<Replanner>,
+) {
+    (&mut *replanners, &mut *paths)
+        .par_join()
+        .for_each(|(replanner, path)| {
+            let cost = |id: TileId| terrain.cost(replanner.profile, id);
+
+            if let Some(position) = path.current.checked_sub(1).and_then(|i| path.points.get(i)) {
+                replanner
+                    .planner
+                    .advance(tiles, tiles.id_from_vector(*position));
+            }
+            replanner
+                .planner
+                .update(tiles, changed, Some(path_cache), &cost);
+
+            match replanner.planner.path(tiles, Some(path_cache), &cost) {
+                Some((points, total)) => {
+                    *path = Path::new(
+                        points.into_iter().map(|id| tiles.coord(id)).collect(),
+                        total,
+                    )
+                }
+                None => *path = Path::default(),
+            }
+        });
+
+    // Paths which were cut off entirely
+    let cut_off = (&**entities, &*paths, &*replanners)
+        .join()
+        .filter(|(_, path, _)| path.points.is_empty())
+        .map(|(entity, _, _)| entity)
+        .collect::<Vec<_>>();
+    for entity in cut_off {
+        paths.remove(entity);
+        replanners.remove(entity);
+        failed.insert(entity, PathFailed).unwrap();
+    }
+}
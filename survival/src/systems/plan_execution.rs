@@ -0,0 +1,148 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{Entities, Join, Read, ReadStorage, Resources, SystemData, Write, WriteStorage},
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::actions::{ActionOutcome, ActionResult, Target};
+use crate::components::{
+    ai::{Blackboard, CurrentAction, Needs, Personality, PlanStatus, Skills, AI},
+    Actionable, PathFailed,
+};
+use crate::goap::Planner;
+use crate::systems::time::TimeState;
+
+/// Carries out the plans queued on `AI` components, one action at a time. Each action's event is
+/// sent to the entity's `Actionable` channel, targeting the entity its `Blackboard` bound to the
+/// action, and started as its `CurrentAction`, taking longer or shorter by how fast the entity
+/// works. The next is started once an `ActionResult` reports it completed. A failed action, or a
+/// path which can no longer be followed, invalidates the rest of the plan so the planner replans
+/// after a backoff.
+#[derive(Default)]
+pub struct System {
+    result_reader: Option<ReaderId<ActionResult>>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, Planner>,
+        Read<'s, TimeState>,
+        Read<'s, EventChannel<ActionResult>>,
+        ReadStorage<'s, Needs>,
+        ReadStorage<'s, Personality>,
+        ReadStorage<'s, Skills>,
+        WriteStorage<'s, PathFailed>,
+        WriteStorage<'s, AI>,
+        WriteStorage<'s, Actionable>,
+        WriteStorage<'s, Blackboard>,
+        WriteStorage<'s, CurrentAction>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.result_reader =
+            Some(Write::<EventChannel<ActionResult>>::fetch(&res).register_reader());
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            planner,
+            time,
+            results,
+            needs,
+            personalities,
+            skills,
+            mut path_failures,
+            mut ais,
+            mut actionables,
+            mut blackboards,
+            mut current_actions,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+
+        for result in results.read(self.result_reader.as_mut().unwrap()) {
+            let ai = match ais.get_mut(result.entity) {
+                Some(ai) => ai,
+                None => continue,
+            };
+
+            // Only results of the action the plan is waiting on count
+            let current = ai.current.and_then(|id| planner.get(id));
+            if current.map_or(true, |action| action.event() != result.action) {
+                continue;
+            }
+
+            match result.outcome {
+                ActionOutcome::Completed => ai.current = None,
+                ActionOutcome::Failed => ai.fail(now),
+            }
+        }
+
+        // The world changed under the action being carried out
+        let blocked = (&entities, &path_failures, &ais)
+            .join()
+            .filter(|(_, _, ai)| ai.status == PlanStatus::Executing)
+            .map(|(entity, _, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in blocked {
+            path_failures.remove(entity);
+            ais.get_mut(entity).unwrap().fail(now);
+        }
+
+        for (entity, ai, actionable, mut blackboard) in (
+            &entities,
+            &mut ais,
+            &mut actionables,
+            (&mut blackboards).maybe(),
+        )
+            .join()
+        {
+            if ai.status != PlanStatus::Executing || ai.current.is_some() {
+                continue;
+            }
+
+            let action = match ai.action_queue.pop() {
+                Ok(next) => match planner.get(next) {
+                    Some(action) => action,
+                    None => {
+                        ai.fail(now);
+                        continue;
+                    }
+                },
+                Err(_) => {
+                    ai.complete();
+                    continue;
+                }
+            };
+
+            if let Some(blackboard) = blackboard.as_mut() {
+                let target = action
+                    .target()
+                    .and_then(|condition| blackboard.get(condition));
+                // What the planner reasoned about is gone
+                if target.map_or(false, |target| !entities.is_alive(target)) {
+                    ai.fail(now);
+                    continue;
+                }
+                blackboard.target = target.map(Target::Entity);
+            }
+            let speed = needs.get(entity).map_or(1.0, Needs::work_speed)
+                * personalities
+                    .get(entity)
+                    .map_or(1.0, Personality::work_speed)
+                * skills
+                    .get(entity)
+                    .map_or(1.0, |skills| skills.speed(action.catagory()));
+            current_actions
+                .insert(entity, CurrentAction::new(action, speed))
+                .unwrap();
+            ai.current = Some(action.id());
+            actionable.channel.single_write(action.event());
+        }
+    }
+}
@@ -0,0 +1,97 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::math::Vector3,
+    ecs::{Join, Read, ReadExpect, ReadStorage, WriteStorage},
+    renderer::SpriteRender,
+};
+
+use crate::assets::{GetStorage, PlantStorage};
+use crate::components::{FluidDepth, TilePosition};
+use crate::farming::{irrigation_range, untilled_growth, Plant, Tilled};
+use crate::systems::time::TimeState;
+use crate::temperature::TemperatureGrid;
+use crate::tiles::{ReadTiles, Tiles};
+
+/// Grows every `Plant` by the game time passed, at the rate its kind grows at in the temperature
+/// of its tile and slower through the short days of autumn and winter. Plants with no fluid within `irrigation_range` grow as well as their drought
+/// tolerance allows, and those on ground which isn't `Tilled` slower still. Reaching a new stage
+/// changes the sprite of the plant.
+#[derive(Default)]
+pub struct System {
+    last_time: Option<u64>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        ReadExpect<'s, Tiles>,
+        ReadExpect<'s, PlantStorage>,
+        Read<'s, TimeState>,
+        Read<'s, TemperatureGrid>,
+        ReadTiles<'s, FluidDepth>,
+        ReadTiles<'s, Tilled>,
+        ReadStorage<'s, TilePosition>,
+        WriteStorage<'s, Plant>,
+        WriteStorage<'s, SpriteRender>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            tiles,
+            plant_storage,
+            time,
+            grid,
+            fluids,
+            tilled,
+            tile_positions,
+            mut plants,
+            mut sprites,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+        let elapsed = now.saturating_sub(self.last_time.unwrap_or(now));
+        self.last_time = Some(now);
+        if elapsed == 0 {
+            return;
+        }
+
+        let kinds = plant_storage.borrow();
+        let season = time.season();
+        for (plant, position, sprite) in
+            (&mut plants, &tile_positions, (&mut sprites).maybe()).join()
+        {
+            let kind = match kinds.data.get(&plant.kind) {
+                Some(kind) => kind,
+                None => continue,
+            };
+
+            let coord = position.coord;
+            let min = Vector3::new(
+                coord.x.saturating_sub(irrigation_range),
+                coord.y.saturating_sub(irrigation_range),
+                coord.z,
+            );
+            let max = Vector3::new(
+                coord.x + irrigation_range,
+                coord.y + irrigation_range,
+                coord.z,
+            );
+            let watered = tiles
+                .iter_volume(min, max)
+                .any(|id| fluids.get(id).map_or(false, |depth| depth.0 > 0));
+            let mut rate = kind.growth_rate(grid.at(coord), watered) * season.plant_growth();
+            if tilled.get(tiles.id_from_vector(coord)).is_none() {
+                rate *= untilled_growth;
+            }
+            plant.growth += rate * elapsed as f32;
+
+            let stage = kind.stage(plant.growth);
+            if stage != plant.stage {
+                plant.stage = stage;
+                if let Some(sprite) = sprite {
+                    sprite.sprite_number = kind.stages[stage].sprite_number;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{BitSet, Join, Read, ReadExpect, Resources, SystemData, Write},
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::components::{
+    ConstructionLayer, FloorLayer, FluidDepth, FluidLayer, LightLevel, TerrainLayer, Visibility,
+};
+use crate::decals::Decals;
+use crate::designations::Designation;
+use crate::render::tiles::DirtyChunks;
+use crate::tiles::{TileChangeEvent, Tiles, TrackedTiles, WriteTiles};
+
+/// Marks the chunks of tiles whose sprites, fluid, visibility, designation, light or decals
+/// changed as `DirtyChunks`, for the tile pass to rebuild. Tiles recolored through their `Rgba`,
+/// which isn't flagged, are marked by the `TileChangeEvent` published with it.
+#[derive(Default)]
+pub struct System {
+    terrain: TrackedTiles<TerrainLayer>,
+    floor: TrackedTiles<FloorLayer>,
+    construction: TrackedTiles<ConstructionLayer>,
+    fluid: TrackedTiles<FluidLayer>,
+    fluid_depth: TrackedTiles<FluidDepth>,
+    visibility: TrackedTiles<Visibility>,
+    designation: TrackedTiles<Designation>,
+    light: TrackedTiles<LightLevel>,
+    decals: TrackedTiles<Decals>,
+    change_reader: Option<ReaderId<TileChangeEvent>>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        ReadExpect<'s, Tiles>,
+        Read<'s, EventChannel<TileChangeEvent>>,
+        Write<'s, DirtyChunks>,
+        WriteTiles<'s, TerrainLayer>,
+        WriteTiles<'s, FloorLayer>,
+        WriteTiles<'s, ConstructionLayer>,
+        WriteTiles<'s, FluidLayer>,
+        WriteTiles<'s, FluidDepth>,
+        WriteTiles<'s, Visibility>,
+        WriteTiles<'s, Designation>,
+        WriteTiles<'s, LightLevel>,
+        WriteTiles<'s, Decals>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.change_reader =
+            Some(Write::<EventChannel<TileChangeEvent>>::fetch(&res).register_reader());
+    }
+
+    fn run(
+        &mut self,
+        (
+            tiles,
+            changes,
+            mut dirty,
+            mut terrain,
+            mut floor,
+            mut construction,
+            mut fluid,
+            mut fluid_depth,
+            mut visibility,
+            mut designation,
+            mut light,
+            mut decals,
+        ): Self::SystemData,
+    ) {
+        let (tiles, dirty) = (*tiles, &mut *dirty);
+        mark(tiles, dirty, self.terrain.changed(&mut terrain));
+        mark(tiles, dirty, self.floor.changed(&mut floor));
+        mark(tiles, dirty, self.construction.changed(&mut construction));
+        mark(tiles, dirty, self.fluid.changed(&mut fluid));
+        mark(tiles, dirty, self.fluid_depth.changed(&mut fluid_depth));
+        mark(tiles, dirty, self.visibility.changed(&mut visibility));
+        mark(tiles, dirty, self.designation.changed(&mut designation));
+        mark(tiles, dirty, self.light.changed(&mut light));
+        mark(tiles, dirty, self.decals.changed(&mut decals));
+
+        for event in changes.read(self.change_reader.as_mut().unwrap()) {
+            dirty.mark(tiles.coord(event.id));
+        }
+    }
+}
+
+/// Marks the chunks of the `changed` tiles of a storage. The first time a storage is looked at
+/// there are none, as the tile pass builds every chunk once the level loads.
+fn mark(tiles: Tiles, dirty: &mut DirtyChunks, changed: Option<&BitSet>) {
+    if let Some(changed) = changed {
+        for id in changed.join() {
+            dirty.mark(tiles.coord(tiles.id_at(id)));
+        }
+    }
+}
@@ -0,0 +1,94 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::{math::Vector3, transform::Transform},
+    ecs::{Join, Read, ReadExpect, ReadStorage, Resources, SystemData, Write},
+    input::{InputEvent, InputHandler},
+    renderer::{Camera, ScreenDimensions},
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::actions::PlayerInputAction;
+use crate::render::camera::screen_to_tile;
+use crate::selection::{region, RegionSelected, Selection, TileSelected};
+use crate::settings::Config;
+use crate::tiles::{Tiles, ViewZ};
+
+/// Keeps the `Selection` up with the tile under the mouse on the viewed slice, and on releasing
+/// `Select` publishes the tile clicked as a `TileSelected`, or the rectangle dragged out since it
+/// was pressed as a `RegionSelected`. A drag let go of off the map selects nothing.
+#[derive(Default)]
+pub struct System {
+    input_reader: Option<ReaderId<InputEvent<PlayerInputAction>>>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Read<'s, InputHandler<PlayerInputAction, PlayerInputAction>>,
+        Read<'s, EventChannel<InputEvent<PlayerInputAction>>>,
+        ReadStorage<'s, Camera>,
+        ReadStorage<'s, Transform>,
+        ReadExpect<'s, ScreenDimensions>,
+        ReadExpect<'s, Tiles>,
+        ReadExpect<'s, Config>,
+        Read<'s, ViewZ>,
+        Write<'s, Selection>,
+        Write<'s, EventChannel<TileSelected>>,
+        Write<'s, EventChannel<RegionSelected>>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.input_reader = Some(
+            Write::<EventChannel<InputEvent<PlayerInputAction>>>::fetch(&res).register_reader(),
+        );
+    }
+
+    fn run(
+        &mut self,
+        (
+            input,
+            input_events,
+            cameras,
+            transforms,
+            dimensions,
+            tiles,
+            config,
+            view_z,
+            mut selection,
+            mut tiles_selected,
+            mut regions_selected,
+        ): Self::SystemData,
+    ) {
+        selection.hover = match (
+            input.mouse_position(),
+            (&cameras, &transforms).join().next(),
+        ) {
+            (Some((x, y)), Some(camera)) => {
+                screen_to_tile((x as f32, y as f32), &dimensions, camera, *tiles, &config)
+                    .map(|coord| Vector3::new(coord.x, coord.y, view_z.z))
+            }
+            _ => None,
+        };
+
+        for event in input_events.read(self.input_reader.as_mut().unwrap()) {
+            match event {
+                InputEvent::ActionPressed(PlayerInputAction::Select) => {
+                    selection.drag_start = selection.hover;
+                }
+                InputEvent::ActionReleased(PlayerInputAction::Select) => {
+                    let from = selection.drag_start.take();
+                    if let (Some(from), Some(to)) = (from, selection.hover) {
+                        if from == to {
+                            tiles_selected.single_write(TileSelected { coord: to });
+                        } else {
+                            let (min, max) = region(from, to);
+                            regions_selected.single_write(RegionSelected { min, max });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::ecs::{Entities, Join, ParJoin, Read, ReadExpect, ReadStorage, WriteStorage};
+use rayon::prelude::*;
+
+use crate::butchery::Corpse;
+use crate::components::{
+    ai::{Creature, Pawn, Percept, Perception},
+    Item, Obstruction, TilePosition, Tree,
+};
+use crate::cooking::Spoiling;
+use crate::designations::Designated;
+use crate::factions::{Faction, Opinions, Relations};
+use crate::farming::{self, FarmPlot};
+use crate::goap::ConditionValue;
+use crate::liquids::LiquidSource;
+use crate::systems::time::TimeState;
+use crate::temperature::HeatSource;
+use crate::tiles::{ReadTiles, TileEntities, Tiles};
+
+/// Periodically scans the tiles each perceiving entity can see, recording what is on them, such as
+/// trees, items, farm plots, designations, fires, spoiling food, wells and corpses, into its
+/// `Perception`, and forgetting what it hasn't seen for too long.
+#[derive(Default)]
+pub struct System;
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        Read<'s, TimeState>,
+        Read<'s, Relations>,
+        ReadTiles<'s, Obstruction>,
+        ReadTiles<'s, TileEntities>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, Tree>,
+        ReadStorage<'s, Item>,
+        ReadStorage<'s, FarmPlot>,
+        ReadStorage<'s, Designated>,
+        ReadStorage<'s, HeatSource>,
+        ReadStorage<'s, Spoiling>,
+        ReadStorage<'s, LiquidSource>,
+        ReadStorage<'s, Corpse>,
+        ReadStorage<'s, Pawn>,
+        ReadStorage<'s, Creature>,
+        ReadStorage<'s, Faction>,
+        ReadStorage<'s, Opinions>,
+        WriteStorage<'s, Perception>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            time,
+            relations,
+            obstructions,
+            occupants,
+            tile_positions,
+            trees,
+            items,
+            plots,
+            designated,
+            heat_sources,
+            spoiling,
+            liquid_sources,
+            corpses,
+            pawns,
+            creatures,
+            factions,
+            opinions,
+            mut perceptions,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+
+        (&entities, &tile_positions, &mut perceptions)
+            .par_join()
+            .filter(|(_, _, perception)| perception.needs_scan(now))
+            .for_each(|(me, tile_position, perception)| {
+                perception.last_scan = Some(now);
+                perception.forget(now);
+
+                crate::fov::shadowcast(
+                    *tiles,
+                    tiles.id_from_vector(tile_position.coord),
+                    perception.sight_range,
+                    |tile_id| match obstructions.get(tile_id) {
+                        Some(Obstruction::Impassable) => true,
+                        _ => false,
+                    },
+                    |tile_id| {
+                        let seen = match occupants.get(tile_id) {
+                            Some(seen) => seen,
+                            None => return,
+                        };
+                        for entity in seen.0.iter().filter(|entity| **entity != me) {
+                            let mut percepts = Vec::new();
+                            if trees.contains(*entity) {
+                                percepts.push(Percept::Thing(ConditionValue::Tree));
+                            }
+                            if let Some(item) = items.get(*entity) {
+                                percepts.extend(item.properties.iter().map(|property| {
+                                    Percept::Thing(ConditionValue::Property(property.clone()))
+                                }));
+                            }
+                            if let Some(plot) = plots.get(*entity) {
+                                percepts
+                                    .push(Percept::Thing(farming::field(&plot.crop, plot.state)));
+                            }
+                            if let Some(designated) = designated.get(*entity) {
+                                percepts
+                                    .push(Percept::Thing(ConditionValue::Designated(designated.0)));
+                            }
+                            if heat_sources.contains(*entity) {
+                                percepts.push(Percept::Thing(ConditionValue::Heat));
+                            }
+                            if spoiling.contains(*entity) {
+                                percepts.push(Percept::Thing(ConditionValue::Spoiling));
+                            }
+                            if liquid_sources.contains(*entity) {
+                                percepts.push(Percept::Thing(ConditionValue::LiquidSource));
+                            }
+                            if corpses.contains(*entity) {
+                                percepts.push(Percept::Thing(ConditionValue::Corpse));
+                            }
+                            if pawns.contains(*entity) || creatures.contains(*entity) {
+                                percepts.push(Percept::Creature);
+                            }
+                            if relations.is_hostile(
+                                factions.get(me),
+                                opinions.get(me),
+                                factions.get(*entity),
+                            ) {
+                                percepts.push(Percept::Thing(ConditionValue::Hostile));
+                            }
+
+                            if percepts.is_empty() {
+                                continue;
+                            }
+                            perception.observe(*entity, percepts, tiles.coord(tile_id), now);
+                        }
+                    },
+                );
+            });
+
+        // Forget entities which no longer exist
+        for perception in (&mut perceptions).join() {
+            let dead = perception
+                .facts()
+                .filter(|fact| !entities.is_alive(fact.entity))
+                .map(|fact| fact.entity)
+                .collect::<Vec<_>>();
+            for entity in dead {
+                perception.forget_entity(entity);
+            }
+        }
+    }
+}
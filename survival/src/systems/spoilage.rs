@@ -0,0 +1,350 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    assets::AssetStorage,
+    core::{
+        components::{Parent, Transform},
+        math::Vector3,
+    },
+    ecs::{
+        Entities, Entity, Join, Read, ReadExpect, ReadStorage, Resources, SystemData, Write,
+        WriteStorage,
+    },
+    shrev::EventChannel,
+};
+use std::collections::HashMap;
+
+use crate::actions::{Action, ActionOutcome, ActionResult, Target};
+use crate::assets;
+use crate::components::{
+    self,
+    ai::{Blackboard, CurrentAction},
+    Obstruction, PathFailed, TilePosition,
+};
+use crate::cooking::{
+    self, cold_storage_range, cold_storage_temperature, Meal, Perishable, Spoiling,
+};
+use crate::goap::{ActionCatagory, Planner};
+use crate::jobs::{JobBoard, JobId};
+use crate::pathfinding::distance;
+use crate::pathfinding::{MovementProfile, PathFlags, PathRequest, PathRequests};
+use crate::settings::Config;
+use crate::systems::time::TimeState;
+use crate::temperature::{cell_size, TemperatureGrid};
+use crate::tiles::{ReadTiles, TileEntities, Tiles, WriteTiles};
+use crate::utils::ComponentEventReader;
+
+/// Food being carried to cold storage.
+#[derive(Clone, Copy, Debug)]
+struct Haul {
+    food: Entity,
+    goal: Vector3<u32>,
+}
+
+/// Ages every `Perishable` by the temperature where it is kept, rotting it away once past its
+/// shelf life. Food left lying anywhere warmer than `cold_storage_temperature` is marked
+/// `Spoiling`, with a job posted to haul it to the nearest cold tile if there is one. A `Haul`
+/// event picks up the targeted food next to the pawn and walks it there, finishing once it is
+/// set down.
+#[derive(Default)]
+pub struct System {
+    action_reader: ComponentEventReader<components::Actionable, Action>,
+    orders: HashMap<Entity, JobId>,
+    hauls: HashMap<Entity, Haul>,
+    last_time: Option<u64>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        ReadExpect<'s, Config>,
+        Read<'s, TimeState>,
+        Read<'s, TemperatureGrid>,
+        Read<'s, AssetStorage<assets::Item>>,
+        Read<'s, PathRequests>,
+        Write<'s, JobBoard>,
+        Write<'s, EventChannel<ActionResult>>,
+        ReadTiles<'s, Obstruction>,
+        WriteTiles<'s, TileEntities>,
+        ReadStorage<'s, Blackboard>,
+        ReadStorage<'s, CurrentAction>,
+        ReadStorage<'s, PathFailed>,
+        ReadStorage<'s, components::Item>,
+        ReadStorage<'s, Meal>,
+        WriteStorage<'s, Perishable>,
+        WriteStorage<'s, Spoiling>,
+        WriteStorage<'s, Parent>,
+        WriteStorage<'s, TilePosition>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, components::Actionable>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.action_reader.setup(res);
+
+        Planner::setup_pawn_actions(res, cooking::actions());
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            config,
+            time,
+            grid,
+            details,
+            requests,
+            mut board,
+            mut results,
+            obstructions,
+            mut occupants,
+            blackboards,
+            current_actions,
+            path_failures,
+            items,
+            meals,
+            mut perishables,
+            mut spoiling,
+            mut parents,
+            mut tile_positions,
+            mut transforms,
+            mut actionables,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+        let elapsed = now.saturating_sub(self.last_time.unwrap_or(now));
+        self.last_time = Some(now);
+
+        // Age food, and whatever else rots, by where it is kept, carried or not
+        let mut rotten = Vec::new();
+        for (entity, perishable, item) in (&entities, &mut perishables, &items).join() {
+            let shelf_life = match meals.get(entity) {
+                Some(meal) => meal.0.shelf_life,
+                None => details.get(&item.handle).and_then(assets::Item::shelf_life),
+            };
+            let shelf_life = match shelf_life {
+                Some(shelf_life) => shelf_life,
+                None => continue,
+            };
+            if let Some(coord) = position_of(entity, &parents, &tile_positions) {
+                perishable.update(elapsed, grid.at(coord));
+            }
+            if perishable.is_spoiled(shelf_life) {
+                rotten.push(entity);
+            }
+        }
+        for food in rotten {
+            log::info!("{:?} rotted away", food);
+            if let Some(position) = tile_positions.get(food) {
+                if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(position.coord)) {
+                    occupants.0.remove(&food);
+                }
+            }
+            entities.delete(food).unwrap();
+        }
+
+        // Drop the jobs of food which is gone or stored, and post them for food left to spoil
+        let stored = (&entities, &spoiling, &tile_positions, !&parents)
+            .join()
+            .filter(|(_, _, position, _)| grid.at(position.coord) <= cold_storage_temperature)
+            .map(|(food, _, _, _)| food)
+            .collect::<Vec<_>>();
+        for food in stored {
+            spoiling.remove(food);
+        }
+        let finished = self
+            .orders
+            .iter()
+            .filter(|(food, _)| !entities.is_alive(**food) || !spoiling.contains(**food))
+            .map(|(food, job)| (*food, *job))
+            .collect::<Vec<_>>();
+        for (food, job) in finished {
+            self.orders.remove(&food);
+            board.complete(job);
+        }
+        let loose = (&entities, &perishables, &tile_positions, !&parents)
+            .join()
+            .filter(|(_, _, position, _)| grid.at(position.coord) > cold_storage_temperature)
+            .map(|(food, _, position, _)| (food, position.coord))
+            .collect::<Vec<_>>();
+        // Cold storage is searched for once per temperature cell, which it varies by
+        let mut near_cold = HashMap::new();
+        for (food, coord) in loose {
+            if let Some(job) = self.orders.get_mut(&food) {
+                if board.get(*job).is_none() {
+                    *job = board.post(
+                        ActionCatagory::HaulingFood,
+                        vec![cooking::stored()],
+                        Some(coord),
+                    );
+                }
+                continue;
+            }
+            let cell = (coord.x / cell_size, coord.y / cell_size, coord.z);
+            let reachable = *near_cold
+                .entry(cell)
+                .or_insert_with(|| cold_storage(*tiles, &grid, &obstructions, coord).is_some());
+            if !reachable {
+                continue;
+            }
+            spoiling.insert(food, Spoiling).unwrap();
+            let job = board.post(
+                ActionCatagory::HaulingFood,
+                vec![cooking::stored()],
+                Some(coord),
+            );
+            self.orders.insert(food, job);
+        }
+
+        // Pick up the food to haul
+        self.action_reader.maintain(&entities, &mut actionables);
+        let mut hauls = Vec::new();
+        for (entity, actionable) in (&entities, &mut actionables).join() {
+            for action in self.action_reader.read(entity, actionable) {
+                if *action == Action::Haul {
+                    hauls.push(entity);
+                }
+            }
+        }
+        for pawn in hauls {
+            let here = tile_positions.get(pawn).map(|position| position.coord);
+            let food = match blackboards
+                .get(pawn)
+                .and_then(|blackboard| blackboard.target)
+            {
+                Some(Target::Entity(food))
+                    if spoiling.contains(food)
+                        && !parents.contains(food)
+                        && tile_positions.get(food).map_or(false, |position| {
+                            here.map_or(false, |here| distance(here, position.coord) <= 1)
+                        }) =>
+                {
+                    food
+                }
+                _ => {
+                    results.single_write(ActionResult::failed(pawn, Action::Haul));
+                    continue;
+                }
+            };
+            let (here, goal) = match here
+                .and_then(|here| Some((here, cold_storage(*tiles, &grid, &obstructions, here)?)))
+            {
+                Some(found) => found,
+                None => {
+                    results.single_write(ActionResult::failed(pawn, Action::Haul));
+                    continue;
+                }
+            };
+
+            let coord = tile_positions.remove(food).unwrap().coord;
+            if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(coord)) {
+                occupants.0.remove(&food);
+            }
+            parents.insert(food, Parent { entity: pawn }).unwrap();
+            if let Some(transform) = transforms.get_mut(food) {
+                *transform = Transform::default();
+            }
+            requests.request(PathRequest {
+                entity: pawn,
+                start: here,
+                goal,
+                flags: PathFlags::None,
+                profile: MovementProfile::default(),
+            });
+            self.hauls.insert(pawn, Haul { food, goal });
+        }
+
+        // Set it down once there, or wherever the pawn gave up
+        let hauls = self.hauls.drain().collect::<Vec<_>>();
+        for (pawn, haul) in hauls {
+            if !entities.is_alive(haul.food) {
+                if entities.is_alive(pawn) {
+                    results.single_write(ActionResult::failed(pawn, Action::Haul));
+                }
+                continue;
+            }
+            let here = position_of(pawn, &parents, &tile_positions);
+            let hauling = entities.is_alive(pawn)
+                && current_actions
+                    .get(pawn)
+                    .map_or(false, |current| current.event == Action::Haul);
+            let outcome = if !hauling || path_failures.contains(pawn) {
+                ActionOutcome::Failed
+            } else if here == Some(haul.goal) {
+                ActionOutcome::Completed
+            } else {
+                self.hauls.insert(pawn, haul);
+                continue;
+            };
+
+            parents.remove(haul.food);
+            if let Some(coord) = here {
+                tile_positions
+                    .insert(haul.food, TilePosition::new(coord))
+                    .unwrap();
+                if let Some(transform) = transforms.get_mut(haul.food) {
+                    transform.set_translation(tiles.tile_to_world(coord, &config));
+                }
+                if let Some(occupants) = occupants.get_mut(tiles.id_from_vector(coord)) {
+                    occupants.0.insert(haul.food);
+                }
+            }
+            if outcome == ActionOutcome::Completed {
+                spoiling.remove(haul.food);
+            }
+            if hauling {
+                results.single_write(ActionResult {
+                    entity: pawn,
+                    action: Action::Haul,
+                    outcome,
+                });
+            }
+        }
+    }
+}
+
+/// Where an entity is, or whatever carries it.
+fn position_of(
+    entity: Entity,
+    parents: &WriteStorage<'_, Parent>,
+    tile_positions: &WriteStorage<'_, TilePosition>,
+) -> Option<Vector3<u32>> {
+    let mut current = entity;
+    loop {
+        if let Some(position) = tile_positions.get(current) {
+            return Some(position.coord);
+        }
+        current = parents.get(current)?.entity;
+    }
+}
+
+/// The nearest passable tile to `from` on its level cold enough to keep food in.
+fn cold_storage(
+    tiles: Tiles,
+    grid: &TemperatureGrid,
+    obstructions: &ReadTiles<'_, Obstruction>,
+    from: Vector3<u32>,
+) -> Option<Vector3<u32>> {
+    let min = Vector3::new(
+        from.x.saturating_sub(cold_storage_range),
+        from.y.saturating_sub(cold_storage_range),
+        from.z,
+    );
+    let max = Vector3::new(
+        from.x + cold_storage_range,
+        from.y + cold_storage_range,
+        from.z,
+    );
+    tiles
+        .iter_volume(min, max)
+        .filter(|id| match obstructions.get(*id) {
+            Some(Obstruction::Impassable) => false,
+            _ => true,
+        })
+        .map(|id| tiles.coord(id))
+        .filter(|coord| grid.at(*coord) <= cold_storage_temperature)
+        .min_by_key(|coord| distance(from, *coord))
+}
@@ -0,0 +1,94 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::ecs::{Entities, Join, Read, ReadExpect, ReadStorage, Write, WriteStorage};
+
+use crate::components::{
+    ai::{CurrentAction, Needs, AI},
+    FluidDepth, TilePosition,
+};
+use crate::goap::Motive;
+use crate::systems::time::TimeState;
+use crate::temperature::{BodyTemperature, HeatSource, TemperatureGrid};
+use crate::tiles::{ReadTiles, Tiles};
+
+/// Steps the `TemperatureGrid` with the heat of every `HeatSource` and the warmth of the season,
+/// then draws each `BodyTemperature` towards the air around it, insulated by the clothes its
+/// `Needs` record and soaked by standing in water. Hypothermia and heatstroke become the cold and
+/// heat needs, and either becoming urgent interrupts the current plan.
+#[derive(Default)]
+pub struct System {
+    last_time: Option<u64>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        Read<'s, TimeState>,
+        Write<'s, TemperatureGrid>,
+        ReadTiles<'s, FluidDepth>,
+        ReadStorage<'s, HeatSource>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, CurrentAction>,
+        WriteStorage<'s, BodyTemperature>,
+        WriteStorage<'s, Needs>,
+        WriteStorage<'s, AI>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            time,
+            mut grid,
+            fluids,
+            heat_sources,
+            tile_positions,
+            current_actions,
+            mut bodies,
+            mut needs,
+            mut ais,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+        let elapsed = now.saturating_sub(self.last_time.unwrap_or(now));
+        self.last_time = Some(now);
+        if elapsed == 0 {
+            return;
+        }
+
+        let sources = (&heat_sources, &tile_positions)
+            .join()
+            .map(|(heat, position)| (position.coord, heat.0))
+            .collect::<Vec<_>>();
+        grid.set_climate_offset(time.season().temperature_offset());
+        grid.step(elapsed, &sources);
+
+        for (entity, body, position, mut needs, ai) in (
+            &entities,
+            &mut bodies,
+            &tile_positions,
+            (&mut needs).maybe(),
+            (&mut ais).maybe(),
+        )
+            .join()
+        {
+            let in_water = fluids
+                .get(tiles.id_from_vector(position.coord))
+                .map_or(false, |depth| depth.0 > 0);
+            let insulation = needs.as_ref().map_or(0.0, |needs| needs.insulation);
+            body.update(elapsed, grid.at(position.coord), insulation, in_water);
+
+            if let Some(needs) = needs.as_mut() {
+                let was_urgent = needs.cold.is_urgent() || needs.heat.is_urgent();
+                needs.cold.value = body.hypothermia();
+                needs.heat.value = body.heatstroke();
+                if !was_urgent && (needs.cold.is_urgent() || needs.heat.is_urgent()) {
+                    if let Some(ai) = ai {
+                        ai.interrupt_for(&Motive::Cold, current_actions.get(entity));
+                    }
+                }
+            }
+        }
+    }
+}
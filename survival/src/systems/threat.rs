@@ -0,0 +1,157 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::ParentHierarchy,
+    ecs::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, Write, WriteStorage},
+};
+
+use crate::assets::item::Property;
+use crate::components::{
+    ai::{combat_strength, threat_range, CurrentAction, Percept, Perception, Threat, AI},
+    equipment::Equipped,
+    Container, Health, Item, TilePosition,
+};
+use crate::factions::{Faction, FactionThreats, Opinions, Relation, Relations};
+use crate::goap::{ConditionValue, Motive};
+use crate::inventory::get_all_items;
+use crate::pathfinding::distance;
+
+/// Gathers the hostiles perceived by the members of each faction, then has every member with
+/// hostiles in range weigh the strength of its side and its allies against theirs, to fight or to
+/// flee. A changed decision interrupts the current plan.
+#[derive(Default)]
+pub struct System;
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, ParentHierarchy>,
+        Read<'s, Relations>,
+        Write<'s, FactionThreats>,
+        ReadStorage<'s, Faction>,
+        ReadStorage<'s, Opinions>,
+        ReadStorage<'s, Perception>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, Health>,
+        ReadStorage<'s, Container>,
+        ReadStorage<'s, Item>,
+        ReadStorage<'s, Equipped>,
+        ReadStorage<'s, CurrentAction>,
+        WriteStorage<'s, Threat>,
+        WriteStorage<'s, AI>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            hierarchy,
+            relations,
+            mut faction_threats,
+            factions,
+            opinions,
+            perceptions,
+            tile_positions,
+            healths,
+            containers,
+            items,
+            equipped,
+            current_actions,
+            mut threats,
+            mut ais,
+        ): Self::SystemData,
+    ) {
+        let hostile = Percept::Thing(ConditionValue::Hostile);
+        faction_threats.clear();
+        for (faction, perception) in (&factions, &perceptions).join() {
+            for fact in perception
+                .facts()
+                .filter(|fact| fact.percepts.contains(&hostile))
+            {
+                if entities.is_alive(fact.entity) {
+                    faction_threats.insert(faction.0, fact.entity);
+                }
+            }
+        }
+
+        let health = |entity| healths.get(entity).map_or(1.0, Health::fraction);
+        let strength = |entity| {
+            let carried = get_all_items(entity, &hierarchy, &containers, &items);
+            let weapon = (&items, &carried)
+                .join()
+                .flat_map(|(item, _)| item.properties.iter().filter_map(Property::weapon_quality))
+                .fold(0.0, f32::max);
+            let armor = equipped.get(entity).map_or(0.0, Equipped::body_armor);
+            combat_strength(health(entity), weapon, armor)
+        };
+
+        let members = (&entities, &factions, &tile_positions)
+            .join()
+            .map(|(entity, faction, position)| (entity, *faction, position.coord))
+            .collect::<Vec<_>>();
+        for (entity, faction, here) in &members {
+            let hostiles = faction_threats
+                .get(faction.0)
+                // Members may think better of some than the rest of their faction
+                .filter(|hostile| {
+                    relations.is_hostile(
+                        Some(faction),
+                        opinions.get(*entity),
+                        factions.get(*hostile),
+                    )
+                })
+                .filter_map(|hostile| {
+                    let position = tile_positions.get(hostile)?;
+                    Some((hostile, distance(*here, position.coord)))
+                })
+                .filter(|(_, d)| *d <= threat_range)
+                .collect::<Vec<_>>();
+            let nearest = match hostiles.iter().min_by_key(|(_, d)| *d) {
+                Some((nearest, _)) => *nearest,
+                None => {
+                    if threats.remove(*entity).is_some() {
+                        interrupt(&mut ais, &current_actions, *entity);
+                    }
+                    continue;
+                }
+            };
+
+            let allied: f32 = members
+                .iter()
+                .filter(|(_, other, there)| {
+                    relations.get(faction.0, other.0) == Relation::Ally
+                        && distance(*here, *there) <= threat_range
+                })
+                .map(|(ally, _, _)| strength(*ally))
+                .sum();
+            let hostile: f32 = hostiles.iter().map(|(hostile, _)| strength(*hostile)).sum();
+            let threat = Threat::assess(allied, hostile, health(*entity), nearest);
+
+            if threats
+                .get(*entity)
+                .map_or(true, |old| old.response != threat.response)
+            {
+                interrupt(&mut ais, &current_actions, *entity);
+            }
+            threats.insert(*entity, threat).unwrap();
+        }
+
+        // Entities which no longer belong to a faction
+        let unaligned = (&entities, &threats, !&factions)
+            .join()
+            .map(|(entity, _, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in unaligned {
+            threats.remove(entity);
+        }
+    }
+}
+
+fn interrupt(
+    ais: &mut WriteStorage<AI>,
+    current_actions: &ReadStorage<CurrentAction>,
+    entity: Entity,
+) {
+    if let Some(ai) = ais.get_mut(entity) {
+        ai.interrupt_for(&Motive::Danger, current_actions.get(entity));
+    }
+}
@@ -0,0 +1,26 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::ecs::{Entities, Join, ReadStorage};
+
+use crate::tiles::{InertTile, TileEntity, TileRef, WriteTiles};
+
+/// Deletes tile entities which have returned to an inert state and unlinks them from their tile.
+#[derive(Default)]
+pub struct System;
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, TileRef>,
+        ReadStorage<'s, InertTile>,
+        WriteTiles<'s, TileEntity>,
+    );
+
+    fn run(&mut self, (entities, tile_refs, inert, mut tile_entities): Self::SystemData) {
+        for (entity, tile_ref, _) in (&entities, &tile_refs, &inert).join() {
+            if tile_entities.get(tile_ref.0) == Some(&TileEntity(entity)) {
+                tile_entities.remove(tile_ref.0);
+            }
+            entities.delete(entity).unwrap();
+        }
+    }
+}
@@ -87,19 +87,10 @@ impl<'s> amethyst::ecs::System<'s> for System {
                 // Finally, update the tileposition on the entity
                 tile_position.coord = Vector3::new(new_position.x as u32, new_position.y as u32, 0);
 
-                if let Some(entities_list) =
-                    tile_entities_map.get_mut(tiles.id_from_vector(new_position))
-                {
-                    entities_list.0.insert(entity);
-                } else {
-                    slog_error!(
-                        context.logs.root,
-                        "({}, {}) - E:{} - Invalid tile for a position insertion!?",
-                        new_position.x,
-                        new_position.y,
-                        entity.id()
-                    );
-                }
+                tile_entities_map
+                    .get_mut_or_default(tiles.id_from_vector(new_position))
+                    .0
+                    .insert(entity);
             }
         }
     }
@@ -1,25 +1,259 @@
 #![allow(clippy::module_name_repetitions)]
 
 use crate::components;
+use crate::game_data::SurvivalState;
 use crate::settings::Context;
-use amethyst::ecs::{Entities, Entity, ReadExpect, Write, WriteStorage};
+use amethyst::ecs::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, Write, WriteStorage};
 
-#[derive(Default, Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub const hours_per_day: u64 = 24;
+pub const days_per_season: u64 = 30;
+pub const seasons_per_year: u64 = 4;
+/// Game time in an hour, and in a day, at the default day length.
+pub const hour_length: u64 = 100;
+pub const day_length: u64 = hour_length * hours_per_day;
+/// Game time in a season, and in a year, at the default day length.
+pub const season_length: u64 = day_length * days_per_season;
+pub const year_length: u64 = season_length * seasons_per_year;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+impl Season {
+    /// Degrees the climate is warmer, or colder, than its yearly average.
+    pub fn temperature_offset(self) -> f32 {
+        match self {
+            Season::Spring => 0.0,
+            Season::Summer => 8.0,
+            Season::Autumn => -2.0,
+            Season::Winter => -12.0,
+        }
+    }
+
+    /// Share of their growth rate plants keep, by the length of the days.
+    pub fn plant_growth(self) -> f32 {
+        match self {
+            Season::Spring | Season::Summer => 1.0,
+            Season::Autumn => 0.6,
+            Season::Winter => 0.2,
+        }
+    }
+
+    /// Color multiplier the world is drawn with, on top of the daylight.
+    pub fn tint(self) -> (f32, f32, f32) {
+        match self {
+            Season::Spring => (0.95, 1.0, 0.95),
+            Season::Summer => (1.0, 1.0, 0.9),
+            Season::Autumn => (1.0, 0.9, 0.8),
+            Season::Winter => (0.85, 0.9, 1.0),
+        }
+    }
+}
+
+/// Color of the daylight through the day, by the hour: dark blue nights, reddening towards
+/// dawn and dusk either side of the white of noon.
+const daylight: [(f32, (f32, f32, f32)); 7] = [
+    (0.0, (0.2, 0.25, 0.45)),
+    (5.0, (0.25, 0.3, 0.5)),
+    (7.0, (0.95, 0.7, 0.6)),
+    (12.0, (1.0, 1.0, 1.0)),
+    (17.0, (1.0, 0.95, 0.9)),
+    (19.0, (0.9, 0.55, 0.45)),
+    (24.0, (0.2, 0.25, 0.45)),
+];
+
+impl std::fmt::Display for Season {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// How fast game time passes while the game is running.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TimeSpeed {
+    Paused,
+    Normal,
+    Fast,
+}
+impl Default for TimeSpeed {
+    fn default() -> Self {
+        TimeSpeed::Normal
+    }
+}
+impl TimeSpeed {
+    /// Game time passed each frame.
+    pub fn ticks_per_frame(self) -> u64 {
+        match self {
+            TimeSpeed::Paused => 0,
+            TimeSpeed::Normal => 1,
+            TimeSpeed::Fast => 3,
+        }
+    }
+}
+
+/// A moment of game time as it reads on the calendar, counting years and days from 1.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Calendar {
+    pub year: u64,
+    pub season: Season,
+    /// The day of the season.
+    pub day: u64,
+    /// The hour of the day, from 0 to 23.
+    pub hour: usize,
+}
+impl std::fmt::Display for Calendar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Year {}, {} day {}, {:02}:00",
+            self.year, self.season, self.day, self.hour
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct TimeState {
     pub current_time: u64,
+    /// Game time in a day, which hours, seasons and years are all counted in.
+    pub day_length: u64,
+    pub speed: TimeSpeed,
+}
+impl Default for TimeState {
+    fn default() -> Self {
+        Self::new(day_length)
+    }
 }
+impl TimeState {
+    pub fn new(day_length: u64) -> Self {
+        Self {
+            current_time: 0,
+            day_length: day_length.max(hours_per_day),
+            speed: TimeSpeed::default(),
+        }
+    }
+
+    pub fn hour_length(&self) -> u64 {
+        self.day_length / hours_per_day
+    }
+
+    pub fn season_length(&self) -> u64 {
+        self.day_length * days_per_season
+    }
 
+    pub fn year_length(&self) -> u64 {
+        self.season_length() * seasons_per_year
+    }
+
+    /// Days passed since the game began.
+    pub fn day(&self) -> u64 {
+        self.current_time / self.day_length
+    }
+
+    /// The hour of the day, from 0 to 23.
+    pub fn hour(&self) -> usize {
+        ((self.current_time % self.day_length) / self.hour_length()).min(hours_per_day - 1) as usize
+    }
+
+    /// The season of the year, starting in spring.
+    pub fn season(&self) -> Season {
+        match (self.current_time % self.year_length()) / self.season_length() {
+            0 => Season::Spring,
+            1 => Season::Summer,
+            2 => Season::Autumn,
+            _ => Season::Winter,
+        }
+    }
+
+    /// The hours into the day, with their fraction, from 0.0 up to 24.0.
+    pub fn time_of_day(&self) -> f32 {
+        (self.current_time % self.day_length) as f32 / self.day_length as f32 * hours_per_day as f32
+    }
+
+    /// The color of the light the world is seen in, by the time of day and the season.
+    pub fn ambient(&self) -> (f32, f32, f32) {
+        let hour = self.time_of_day();
+        let next = daylight
+            .iter()
+            .position(|(at, _)| *at > hour)
+            .unwrap_or(daylight.len() - 1)
+            .max(1);
+        let ((from, (r0, g0, b0)), (to, (r1, g1, b1))) = (daylight[next - 1], daylight[next]);
+        let t = ((hour - from) / (to - from)).min(1.0).max(0.0);
+        let (season_r, season_g, season_b) = self.season().tint();
+        (
+            (r0 + (r1 - r0) * t) * season_r,
+            (g0 + (g1 - g0) * t) * season_g,
+            (b0 + (b1 - b0) * t) * season_b,
+        )
+    }
+
+    pub fn calendar(&self) -> Calendar {
+        Calendar {
+            year: self.current_time / self.year_length() + 1,
+            season: self.season(),
+            day: self.day() % days_per_season + 1,
+            hour: self.hour(),
+        }
+    }
+}
+
+/// Time an actor needs available to take a turn.
+pub const turn_time: u64 = 1;
+/// Time an actor can bank while idle, so it doesn't store up turns forever.
+pub const max_time_available: u64 = 10;
+/// Advances game time at its `TimeSpeed` while the game is running and no player is taking a
+/// turn, giving every actor `TimeAvailable` by the speed of its `Initiative`. Time stops as soon
+/// as a player has a turn ready, for `InitiativeSystem` to hand it over.
 #[derive(Default)]
 pub struct System;
 impl<'s> amethyst::ecs::System<'s> for System {
     type SystemData = (
         ReadExpect<'s, Context>,
+        Read<'s, SurvivalState>,
         Write<'s, TimeState>,
         Entities<'s>,
+        ReadStorage<'s, components::Player>,
+        ReadStorage<'s, components::IsTurn>,
+        WriteStorage<'s, components::Initiative>,
         WriteStorage<'s, components::TimeAvailable>,
     );
 
-    fn run(&mut self, (_, _time_state, _entities, _time_avialables): Self::SystemData) {}
+    fn run(
+        &mut self,
+        (
+            _,
+            state,
+            mut time_state,
+            entities,
+            players,
+            turns,
+            mut initiatives,
+            mut time_availables,
+        ): Self::SystemData,
+    ) {
+        if *state != SurvivalState::Running || (&players, &turns).join().next().is_some() {
+            return;
+        }
+
+        for _ in 0..time_state.speed.ticks_per_frame() {
+            time_state.current_time += 1;
+
+            let mut player_ready = false;
+            for (entity, time, initiative) in
+                (&entities, &mut time_availables, (&mut initiatives).maybe()).join()
+            {
+                let earned = initiative.map_or(1, |initiative| initiative.earn(1));
+                time.add_capped(earned, max_time_available);
+                player_ready |= players.contains(entity) && time.has(turn_time);
+            }
+            if player_ready {
+                break;
+            }
+        }
+    }
 }
 
 pub fn has_time(time: u64, _entity: Entity, time_comp: &mut components::TimeAvailable) -> bool {
@@ -29,3 +263,57 @@ pub fn has_time(time: u64, _entity: Entity, time_comp: &mut components::TimeAvai
 pub fn consume_time(time: u64, _entity: Entity, time_comp: &mut components::TimeAvailable) {
     time_comp.consume(time);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calendar() {
+        let mut time = TimeState::default();
+        assert_eq!(
+            time.calendar(),
+            Calendar {
+                year: 1,
+                season: Season::Spring,
+                day: 1,
+                hour: 0,
+            }
+        );
+
+        time.current_time = season_length + day_length * 2 + hour_length * 14 + 1;
+        assert_eq!(time.season(), Season::Summer);
+        assert_eq!(time.calendar().to_string(), "Year 1, Summer day 3, 14:00");
+        time.current_time += year_length;
+        assert_eq!(time.calendar().year, 2);
+
+        // Shorter days make for shorter years, by the same calendar
+        let mut short = TimeState::new(240);
+        short.current_time = 240 * days_per_season * 3 + 10 * 7;
+        assert_eq!(short.season(), Season::Winter);
+        assert_eq!(short.hour(), 7);
+        assert_eq!(TimeSpeed::Paused.ticks_per_frame(), 0);
+        assert!(TimeSpeed::Fast.ticks_per_frame() > TimeSpeed::Normal.ticks_per_frame());
+    }
+
+    #[test]
+    fn ambient() {
+        let mut time = TimeState::default();
+        let midnight = time.ambient();
+        time.current_time = hour_length * 12;
+        let noon = time.ambient();
+        assert!((time.time_of_day() - 12.0).abs() < 1e-4);
+        assert!(midnight.0 < noon.0 && midnight.1 < noon.1);
+        assert_eq!(noon, Season::Spring.tint());
+
+        // Dusk reddens
+        time.current_time = hour_length * 19;
+        let dusk = time.ambient();
+        assert!(dusk.0 > dusk.2);
+
+        // Winter is bluer
+        time.current_time = season_length * 3 + hour_length * 12;
+        let winter = time.ambient();
+        assert!(winter.2 > winter.0);
+    }
+}
@@ -0,0 +1,278 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    assets::AssetStorage,
+    core::{math::Vector3, ParentHierarchy},
+    ecs::{
+        Entities, Entity, Join, LazyUpdate, Read, ReadExpect, ReadStorage, Resources, SystemData,
+        Write, WriteStorage,
+    },
+    shrev::{EventChannel, ReaderId},
+};
+use std::collections::HashMap;
+
+use crate::actions::{Action, ActionOutcome, ActionResult, Target};
+use crate::assets::{self, item::Property};
+use crate::components::{
+    self,
+    ai::{Blackboard, CurrentAction},
+    sapling_size, stump_regrowth_time, Container, Stump, TilePosition, Tree, Wear,
+};
+use crate::goap::{
+    self, ActionCatagory, Condition, ConditionEquality, ConditionType, ConditionValue, Planner,
+};
+use crate::initializers::{spawn_item, spawn_tree, SpawnType};
+use crate::inventory;
+use crate::pathfinding::distance;
+use crate::systems::durability::tool_wear;
+use crate::systems::time::TimeState;
+use crate::tiles::{Tiles, WriteTiles};
+use crate::utils::ComponentEventReader;
+
+/// Game time felling a fully grown tree takes, with an axe of quality 1.
+pub const chop_time: f32 = 60.0;
+/// Chopping quality of bare hands, for those without an axe.
+pub const bare_hands_chopping: f32 = 0.2;
+
+/// Grows every `Tree` by the season, and has pawns fell them with a `Chop`. Felling takes longer
+/// the bigger the tree and the worse the axe carried, and once done yields logs and branches by
+/// its size, wears the axe, and leaves a `Stump` on its tile which sprouts a sapling again after
+/// `stump_regrowth_time`.
+#[derive(Default)]
+pub struct System {
+    action_reader: ComponentEventReader<components::Actionable, Action>,
+    result_reader: Option<ReaderId<ActionResult>>,
+    /// The tree, and axe if any, of each entity part way through felling one.
+    chopping: HashMap<Entity, (Entity, Option<Entity>)>,
+    last_time: Option<u64>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        ReadExpect<'s, ParentHierarchy>,
+        Read<'s, TimeState>,
+        Read<'s, AssetStorage<assets::Item>>,
+        Read<'s, LazyUpdate>,
+        Write<'s, EventChannel<ActionResult>>,
+        Write<'s, EventChannel<Wear>>,
+        WriteTiles<'s, Stump>,
+        ReadStorage<'s, Blackboard>,
+        ReadStorage<'s, TilePosition>,
+        ReadStorage<'s, Container>,
+        ReadStorage<'s, components::Item>,
+        WriteStorage<'s, Tree>,
+        WriteStorage<'s, CurrentAction>,
+        WriteStorage<'s, components::Actionable>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.action_reader.setup(res);
+        self.result_reader =
+            Some(Write::<EventChannel<ActionResult>>::fetch(&res).register_reader());
+
+        Planner::setup_pawn_actions(res, woodcutting_actions());
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            hierarchy,
+            time,
+            details,
+            lazy,
+            mut results,
+            mut wear,
+            mut stumps,
+            blackboards,
+            tile_positions,
+            containers,
+            items,
+            mut trees,
+            mut current_actions,
+            mut actionables,
+        ): Self::SystemData,
+    ) {
+        let now = time.current_time;
+        let elapsed = now.saturating_sub(self.last_time.unwrap_or(now));
+        self.last_time = Some(now);
+        if elapsed > 0 {
+            let season = time.season();
+            for tree in (&mut trees).join() {
+                tree.grow(elapsed, season);
+            }
+        }
+
+        let sprouting = stumps
+            .ids()
+            .filter(|id| {
+                stumps
+                    .get(*id)
+                    .map_or(false, |stump| now >= stump.regrows_at)
+            })
+            .collect::<Vec<_>>();
+        for id in sprouting {
+            if let Some(stump) = stumps.remove(id) {
+                let coord = tiles.coord(id);
+                lazy.exec_mut(move |world| {
+                    spawn_tree(world, coord, stump.kind, sapling_size);
+                });
+            }
+        }
+
+        let mut felling = Vec::new();
+        for result in results.read(self.result_reader.as_mut().unwrap()) {
+            if result.action != Action::Chop {
+                continue;
+            }
+            if let Some(chop) = self.chopping.remove(&result.entity) {
+                if result.outcome == ActionOutcome::Completed {
+                    felling.push(chop);
+                }
+            }
+        }
+
+        self.action_reader.maintain(&entities, &mut actionables);
+        let mut requests = Vec::new();
+        for (entity, actionable) in (&entities, &mut actionables).join() {
+            for action in self.action_reader.read(entity, actionable) {
+                if *action == Action::Chop {
+                    requests.push(entity);
+                }
+            }
+        }
+
+        for entity in requests {
+            let here = tile_positions.get(entity).map(|position| position.coord);
+            let tree = match blackboards
+                .get(entity)
+                .and_then(|blackboard| blackboard.target)
+            {
+                Some(Target::Entity(tree))
+                    if trees.contains(tree)
+                        && tile_positions.get(tree).map_or(false, |position| {
+                            here.map_or(false, |here| distance(here, position.coord) <= 1)
+                        }) =>
+                {
+                    tree
+                }
+                _ => {
+                    results.single_write(ActionResult::failed(entity, Action::Chop));
+                    continue;
+                }
+            };
+
+            // The best axe carried, else bare hands
+            let carried = inventory::get_all_items(entity, &hierarchy, &containers, &items);
+            let axe = (&entities, &items, &carried)
+                .join()
+                .filter_map(|(item, component, _)| {
+                    let details = details.get(&component.handle)?;
+                    details
+                        .properties
+                        .iter()
+                        .chain(component.properties.iter())
+                        .filter_map(|property| match property {
+                            Property::Chopping(quality) => Some(quality.into_inner()),
+                            _ => None,
+                        })
+                        .fold(None, |best: Option<f32>, quality| {
+                            Some(best.map_or(quality, |best| best.max(quality)))
+                        })
+                        .map(|quality| (item, quality))
+                })
+                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+            let quality = axe.map_or(bare_hands_chopping, |(_, quality)| quality);
+
+            match current_actions.get_mut(entity) {
+                Some(current) => {
+                    let scale = trees.get(tree).unwrap().chop_time(quality);
+                    current.required = (current.required as f32 * scale).ceil() as u64;
+                    self.chopping
+                        .insert(entity, (tree, axe.map(|(axe, _)| axe)));
+                }
+                None => {
+                    felling.push((tree, axe.map(|(axe, _)| axe)));
+                    results.single_write(ActionResult::completed(entity, Action::Chop));
+                }
+            }
+        }
+
+        for (tree, axe) in felling {
+            let (felled, coord) = match (trees.get(tree), tile_positions.get(tree)) {
+                (Some(felled), Some(position)) if entities.is_alive(tree) => {
+                    (felled.clone(), position.coord)
+                }
+                // Felled by someone else first
+                _ => continue,
+            };
+            entities.delete(tree).unwrap();
+            fell(&lazy, &felled, coord);
+            stumps.insert(
+                tiles.id_from_vector(coord),
+                Stump {
+                    kind: felled.kind(),
+                    regrows_at: now + stump_regrowth_time,
+                },
+            );
+            if let Some(item) = axe.filter(|axe| entities.is_alive(*axe)) {
+                wear.single_write(Wear {
+                    item,
+                    amount: tool_wear,
+                });
+            }
+        }
+    }
+}
+
+/// Moving to a tree, and felling it.
+fn woodcutting_actions() -> Vec<goap::Action> {
+    let near_tree = Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Near(1),
+        ConditionValue::Tree,
+    );
+    let logs = Condition::new(
+        ConditionEquality::Is,
+        ConditionType::Has,
+        ConditionValue::Material {
+            material: "log".to_string(),
+            count: 1,
+        },
+    );
+
+    vec![
+        goap::Action::new(
+            "Move To Tree".to_string(),
+            ActionCatagory::Woodcutting,
+            Action::MoveTo,
+            1.0,
+            Vec::new(),
+            vec![(near_tree.clone(), true)],
+        ),
+        goap::Action::new(
+            "Chop Tree".to_string(),
+            ActionCatagory::Woodcutting,
+            Action::Chop,
+            chop_time,
+            vec![near_tree],
+            vec![(logs, true)],
+        ),
+    ]
+}
+
+/// Drops the logs and branches of a felled tree where it stood.
+fn fell(lazy: &LazyUpdate, tree: &Tree, coord: Vector3<u32>) {
+    let (logs, branches) = tree.yields();
+    lazy.exec_mut(move |world| {
+        for (item, count) in &[("log", logs), ("branch", branches)] {
+            for _ in 0..*count {
+                spawn_item(world, SpawnType::TilePosition(coord), item, None);
+            }
+        }
+    });
+}
@@ -0,0 +1,65 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{LazyUpdate, Read, Write},
+    shrev::EventChannel,
+};
+use amethyst_imgui::imgui::{self, im_str, ImString};
+use std::sync::Arc;
+
+use crate::assets::Localization;
+use crate::systems::time::{TimeSpeed, TimeState};
+use crate::systems::ui::ImGuiDraw;
+
+/// Shows the calendar date and hour of the game, with buttons to pause game time or run it at
+/// normal or triple speed.
+#[derive(Default)]
+pub struct System;
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Write<'s, EventChannel<ImGuiDraw>>,
+        Read<'s, TimeState>,
+        Read<'s, Localization>,
+    );
+
+    fn run(&mut self, (mut imgui_draw, time, locale): Self::SystemData) {
+        let calendar = time.calendar();
+        let speed = time.speed;
+        let title = ImString::new(locale.get("ui.clock.title"));
+        let speeds = [
+            ("ui.clock.pause", TimeSpeed::Paused),
+            ("ui.clock.normal", TimeSpeed::Normal),
+            ("ui.clock.fast", TimeSpeed::Fast),
+        ]
+        .iter()
+        .map(|(key, choice)| (locale.get(key).to_string(), *choice))
+        .collect::<Vec<_>>();
+
+        imgui_draw.single_write(Arc::new(
+            move |ui: &amethyst_imgui::imgui::Ui, lazy: &LazyUpdate| {
+                ui.window(&title)
+                    .size((220.0, 80.0), imgui::ImGuiCond::FirstUseEver)
+                    .build(|| {
+                        ui.text(calendar.to_string());
+
+                        for (i, (label, choice)) in speeds.iter().enumerate() {
+                            if i > 0 {
+                                ui.same_line(0.0);
+                            }
+                            let label = if *choice == speed {
+                                im_str!("[{}]", label)
+                            } else {
+                                im_str!("{}", label)
+                            };
+                            if ui.button(&label, (0.0, 0.0)) {
+                                let choice = *choice;
+                                lazy.exec_mut(move |world| {
+                                    world.write_resource::<TimeState>().speed = choice;
+                                });
+                            }
+                        }
+                    })
+            },
+        ));
+    }
+}
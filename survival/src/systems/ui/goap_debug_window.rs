@@ -0,0 +1,141 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{world::Index, Entities, Join, LazyUpdate, Read, ReadStorage, Write},
+    shrev::EventChannel,
+};
+use amethyst_imgui::imgui::{self, im_str, ImString};
+use hibitset::{BitSet, BitSetLike};
+use std::sync::{Arc, Mutex};
+
+use crate::components::{
+    ai::{Pawn, Perception, AI},
+    TilePosition,
+};
+use crate::goap::Planner;
+use crate::systems::ui::ImGuiDraw;
+
+/// What the window shows of the selected entity, gathered each frame.
+#[derive(Clone, Default, Debug)]
+struct GoapDebugInfo {
+    goal: String,
+    status: String,
+    state: Vec<String>,
+    plan: Vec<String>,
+    current: String,
+}
+
+/// Shows the planner state of an entity picked from every entity with an `AI`: its goal, the
+/// conditions its `Perception` evaluates as holding, its plan and the action being carried out.
+#[derive(Default)]
+pub struct System {
+    selected: Arc<Mutex<i32>>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        Write<'s, EventChannel<ImGuiDraw>>,
+        Read<'s, Planner>,
+        ReadStorage<'s, AI>,
+        ReadStorage<'s, Pawn>,
+        ReadStorage<'s, Perception>,
+        ReadStorage<'s, TilePosition>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut imgui_draw, planner, ais, pawns, perceptions, tile_positions): Self::SystemData,
+    ) {
+        let candidates = (&entities, &ais)
+            .join()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        let names = candidates
+            .iter()
+            .map(|entity| ImString::new(format!("{:?}", entity)))
+            .collect::<Vec<_>>();
+
+        let selected = *self.selected.lock().unwrap();
+        let info = candidates.get(selected as usize).map(|entity| {
+            describe(
+                &planner,
+                ais.get(*entity).unwrap(),
+                pawns.contains(*entity),
+                perceptions.get(*entity),
+                tile_positions.get(*entity),
+            )
+        });
+
+        let state = self.selected.clone();
+        imgui_draw.single_write(Arc::new(
+            move |ui: &amethyst_imgui::imgui::Ui, _lazy: &LazyUpdate| {
+                ui.window(im_str!("GOAP Debug"))
+                    .size((400.0, 500.0), imgui::ImGuiCond::FirstUseEver)
+                    .build(|| {
+                        let refs = names.iter().map(|name| &**name).collect::<Vec<_>>();
+                        let mut selected = state.lock().unwrap();
+                        ui.list_box(im_str!("Entities"), &mut *selected, refs.as_slice(), 6);
+
+                        let info = match &info {
+                            Some(info) => info,
+                            None => return,
+                        };
+                        ui.separator();
+                        ui.text(format!("Goal: {}", info.goal));
+                        ui.text(format!("Status: {}", info.status));
+                        ui.text(format!("Current: {}", info.current));
+
+                        ui.separator();
+                        ui.text("State:");
+                        for condition in &info.state {
+                            ui.bullet_text(&ImString::new(condition.as_str()));
+                        }
+
+                        ui.separator();
+                        ui.text("Plan:");
+                        for action in &info.plan {
+                            ui.bullet_text(&ImString::new(action.as_str()));
+                        }
+                    })
+            },
+        ));
+    }
+}
+
+fn describe(
+    planner: &Planner,
+    ai: &AI,
+    pawn: bool,
+    perception: Option<&Perception>,
+    position: Option<&TilePosition>,
+) -> GoapDebugInfo {
+    let action_name = |id: Index| {
+        planner
+            .get_action_name(id)
+            .map_or_else(|| format!("#{}", id), str::to_string)
+    };
+
+    // The same state the planner starts from
+    let state = match (perception, position) {
+        (Some(perception), Some(position)) => planner.evaluate(
+            &planner.available_to(&ai.available_actions, pawn),
+            |condition| perception.satisfies(condition, position.coord),
+        ),
+        _ => BitSet::new(),
+    };
+
+    GoapDebugInfo {
+        goal: ai.goal.clone().unwrap_or_else(|| "None".to_string()),
+        status: format!("{:?} ({} failures)", ai.status, ai.failures),
+        state: state
+            .iter()
+            .map(|id| {
+                planner
+                    .condition(id)
+                    .map_or_else(|| format!("#{}", id), ToString::to_string)
+            })
+            .collect(),
+        plan: ai.plan.iter().cloned().map(&action_name).collect(),
+        current: ai.current.map_or_else(|| "None".to_string(), &action_name),
+    }
+}
@@ -0,0 +1,129 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::{math::Vector3, Transform},
+    ecs::{Join, LazyUpdate, Read, Write},
+    renderer::{Camera, Rgba},
+    shrev::EventChannel,
+};
+use amethyst_imgui::imgui::{self, im_str, ImString};
+use std::sync::Arc;
+
+use crate::assets::Localization;
+use crate::minimap::Minimap;
+use crate::render::camera::tile_to_world;
+use crate::settings::Config;
+use crate::systems::ui::ImGuiDraw;
+use crate::tiles::Tiles;
+
+/// Screen pixels along each side of a cell of the minimap.
+const cell_pixels: f32 = 2.0;
+/// Screen pixels along each side of a blip.
+const blip_pixels: f32 = 4.0;
+
+/// Shows the `Minimap` in a window, clicking on which moves the camera over that part of the
+/// map.
+#[derive(Default)]
+pub struct System;
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Write<'s, EventChannel<ImGuiDraw>>,
+        Read<'s, Minimap>,
+        Read<'s, Localization>,
+    );
+
+    fn run(&mut self, (mut imgui_draw, minimap, locale): Self::SystemData) {
+        if minimap.width == 0 || minimap.height == 0 {
+            return;
+        }
+        let minimap = Minimap::clone(&minimap);
+        let (width, height) = (
+            minimap.width as f32 * cell_pixels,
+            minimap.height as f32 * cell_pixels,
+        );
+        // Room about the map for the window's padding and title bar
+        let window_size = (width + 16.0, height + 36.0);
+        let title = ImString::new(locale.get("ui.minimap.title"));
+
+        imgui_draw.single_write(Arc::new(
+            move |ui: &amethyst_imgui::imgui::Ui, lazy: &LazyUpdate| {
+                ui.window(&title)
+                    .size(window_size, imgui::ImGuiCond::FirstUseEver)
+                    .build(|| {
+                        let (left, top) = ui.get_cursor_screen_pos();
+                        let draw_list = ui.get_window_draw_list();
+
+                        // Runs of cells of one color along a row are drawn as one rectangle
+                        for y in 0..minimap.height {
+                            let mut start = 0;
+                            for x in 1..=minimap.width {
+                                let color = minimap.pixel(start, y);
+                                if x < minimap.width && minimap.pixel(x, y) == color {
+                                    continue;
+                                }
+                                let row = top + y as f32 * cell_pixels;
+                                draw_list
+                                    .add_rect(
+                                        (left + start as f32 * cell_pixels, row),
+                                        (left + x as f32 * cell_pixels, row + cell_pixels),
+                                        to_color(color),
+                                    )
+                                    .filled(true)
+                                    .build();
+                                start = x;
+                            }
+                        }
+                        for (x, y, color) in &minimap.blips {
+                            let centre_x = left + (*x as f32 + 0.5) * cell_pixels;
+                            let centre_y = top + (*y as f32 + 0.5) * cell_pixels;
+                            let half = blip_pixels / 2.0;
+                            draw_list
+                                .add_rect(
+                                    (centre_x - half, centre_y - half),
+                                    (centre_x + half, centre_y + half),
+                                    to_color(*color),
+                                )
+                                .filled(true)
+                                .build();
+                        }
+
+                        if ui.invisible_button(im_str!("minimap"), (width, height)) {
+                            let (mouse_x, mouse_y) = ui.imgui().mouse_pos();
+                            let cell_x = ((mouse_x - left) / cell_pixels).max(0.0) as u32;
+                            let cell_y = ((mouse_y - top) / cell_pixels).max(0.0) as u32;
+                            let cell_x = cell_x.min(minimap.width - 1);
+                            let cell_y = cell_y.min(minimap.height - 1);
+                            let cell = minimap.cell;
+                            lazy.exec_mut(move |world| {
+                                look_at(world, cell_x * cell + cell / 2, cell_y * cell + cell / 2);
+                            });
+                        }
+                    })
+            },
+        ));
+    }
+}
+
+fn to_color(color: Rgba) -> [f32; 4] {
+    [color.0, color.1, color.2, color.3]
+}
+
+/// Moves the camera over the tile at `(x, y)`.
+fn look_at(world: &mut amethyst::ecs::World, x: u32, y: u32) {
+    let target = {
+        let tiles = world.read_resource::<Tiles>();
+        let coord = Vector3::new(
+            x.min(tiles.dimensions().x - 1),
+            y.min(tiles.dimensions().y - 1),
+            0,
+        );
+        tile_to_world(coord, &world.read_resource::<Config>())
+    };
+
+    let cameras = world.read_storage::<Camera>();
+    let mut transforms = world.write_storage::<Transform>();
+    for (_, transform) in (&cameras, &mut transforms).join() {
+        transform.set_translation_x(target.x);
+        transform.set_translation_y(target.y);
+    }
+}
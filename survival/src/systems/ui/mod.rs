@@ -1,6 +1,21 @@
 pub mod imgui;
 pub mod ui;
 
+pub mod goap_debug_window;
+pub use goap_debug_window::System as GoapDebugWindow;
+
+pub mod clock_window;
+pub use clock_window::System as ClockWindow;
+
+pub mod minimap_window;
+pub use minimap_window::System as MinimapWindow;
+
+pub mod save_window;
+pub use save_window::System as SaveWindow;
+
+pub mod tile_window;
+pub use tile_window::System as TileWindow;
+
 pub mod inventory_window;
 pub use inventory_window::System as InventoryWindowSystem;
 
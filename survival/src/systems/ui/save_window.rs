@@ -0,0 +1,168 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    ecs::{LazyUpdate, Read, ReadExpect, Write},
+    shrev::EventChannel,
+};
+use amethyst_imgui::imgui::{self, im_str, ImString};
+use slog::slog_error;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::assets::Localization;
+use crate::persistence::{self, LoadRequest, SaveInfo, Thumbnail};
+use crate::settings::{Config, Context};
+use crate::systems::ui::ImGuiDraw;
+
+/// Screen pixels along each side of a cell of the thumbnails.
+const thumbnail_pixels: f32 = 1.0;
+/// Characters a slot name can be typed with.
+const name_capacity: usize = 64;
+
+/// The saves listed, read again from the save directory once a save is made or deleted.
+type Listing = Arc<Mutex<Option<Arc<Vec<(PathBuf, SaveInfo)>>>>>;
+
+/// Saves the game to a slot named in the window, and lists the saves with the minimap of each,
+/// the calendar date and the pawns of the colony, to load or delete them.
+pub struct System {
+    name: Arc<Mutex<ImString>>,
+    saves: Listing,
+}
+impl Default for System {
+    fn default() -> Self {
+        Self {
+            name: Arc::new(Mutex::new(ImString::with_capacity(name_capacity))),
+            saves: Listing::default(),
+        }
+    }
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Write<'s, EventChannel<ImGuiDraw>>,
+        ReadExpect<'s, Config>,
+        ReadExpect<'s, Context>,
+        Read<'s, Localization>,
+    );
+
+    fn run(&mut self, (mut imgui_draw, config, context, locale): Self::SystemData) {
+        let saves = self
+            .saves
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| {
+                Arc::new(persistence::list(&PathBuf::from(&config.saves.directory)))
+            })
+            .clone();
+        let descriptions = saves
+            .iter()
+            .map(|(_, info)| {
+                [
+                    info.name.clone(),
+                    info.time.calendar().to_string(),
+                    locale.format("ui.saves.pawns", &[&info.pawns.to_string()]),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let title = ImString::new(locale.get("ui.saves.title"));
+        let name_label = ImString::new(locale.get("ui.saves.name"));
+        let save_label = locale.get("ui.saves.save").to_string();
+        let load_label = locale.get("ui.saves.load").to_string();
+        let delete_label = locale.get("ui.saves.delete").to_string();
+        let none = locale.get("ui.saves.none").to_string();
+        let log = context.logs.root.clone();
+        let name = self.name.clone();
+        let listing = self.saves.clone();
+
+        imgui_draw.single_write(Arc::new(
+            move |ui: &amethyst_imgui::imgui::Ui, lazy: &LazyUpdate| {
+                ui.window(&title)
+                    .size((320.0, 400.0), imgui::ImGuiCond::FirstUseEver)
+                    .build(|| {
+                        let mut name = name.lock().unwrap();
+                        ui.input_text(&name_label, &mut name).build();
+                        let slot = name.to_str().trim().to_string();
+                        if ui.button(&im_str!("{}", save_label), (0.0, 0.0)) && !slot.is_empty() {
+                            let (log, listing) = (log.clone(), listing.clone());
+                            lazy.exec_mut(move |world| {
+                                if let Err(e) = persistence::save_slot(world, &slot) {
+                                    slog_error!(log, "Failed to save {:?}: {}", slot, e);
+                                }
+                                *listing.lock().unwrap() = None;
+                            });
+                        }
+
+                        ui.separator();
+                        if saves.is_empty() {
+                            ui.text(&none);
+                        }
+                        for (i, ((path, info), lines)) in
+                            saves.iter().zip(&descriptions).enumerate()
+                        {
+                            for line in lines {
+                                ui.text(line);
+                            }
+                            draw_thumbnail(ui, &info.thumbnail, i);
+
+                            if ui.button(&im_str!("{}##load{}", load_label, i), (0.0, 0.0)) {
+                                let path = path.clone();
+                                lazy.exec_mut(move |world| {
+                                    world.write_resource::<LoadRequest>().0 = Some(path);
+                                });
+                            }
+                            ui.same_line(0.0);
+                            if ui.button(&im_str!("{}##delete{}", delete_label, i), (0.0, 0.0)) {
+                                if let Err(e) = std::fs::remove_file(path) {
+                                    slog_error!(log, "Failed to delete {:?}: {}", path, e);
+                                }
+                                *listing.lock().unwrap() = None;
+                            }
+                            ui.separator();
+                        }
+                    })
+            },
+        ));
+    }
+}
+
+/// Draws `thumbnail` at the cursor, runs of cells of one color along a row as one rectangle.
+pub fn draw_thumbnail(ui: &amethyst_imgui::imgui::Ui, thumbnail: &Thumbnail, index: usize) {
+    if thumbnail.width == 0 || thumbnail.height == 0 {
+        return;
+    }
+    let (left, top) = ui.get_cursor_screen_pos();
+    let draw_list = ui.get_window_draw_list();
+    for y in 0..thumbnail.height {
+        let mut start = 0;
+        for x in 1..=thumbnail.width {
+            let color = thumbnail.pixel(start, y);
+            if x < thumbnail.width && thumbnail.pixel(x, y) == color {
+                continue;
+            }
+            let row = top + y as f32 * thumbnail_pixels;
+            draw_list
+                .add_rect(
+                    (left + start as f32 * thumbnail_pixels, row),
+                    (left + x as f32 * thumbnail_pixels, row + thumbnail_pixels),
+                    [
+                        f32::from(color[0]) / 255.0,
+                        f32::from(color[1]) / 255.0,
+                        f32::from(color[2]) / 255.0,
+                        1.0,
+                    ],
+                )
+                .filled(true)
+                .build();
+            start = x;
+        }
+    }
+
+    // Takes up the room drawn over, for what follows to be laid out below it
+    ui.invisible_button(
+        &im_str!("##thumbnail{}", index),
+        (
+            thumbnail.width as f32 * thumbnail_pixels,
+            thumbnail.height as f32 * thumbnail_pixels,
+        ),
+    );
+}
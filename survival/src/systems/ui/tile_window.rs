@@ -0,0 +1,162 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::{
+    core::{math::Vector3, Transform},
+    ecs::{Join, LazyUpdate, Read, ReadExpect, ReadStorage, Resources, SystemData, Write},
+    renderer::{Camera, ScreenDimensions},
+    shrev::{EventChannel, ReaderId},
+};
+use amethyst_imgui::imgui::{self, ImString};
+use std::sync::Arc;
+
+use crate::assets::Localization;
+use crate::components::{FluidDepth, LightLevel};
+use crate::designations::Designation;
+use crate::render::camera::{tile_to_world, tile_world_size, world_to_screen};
+use crate::selection::{RegionSelected, TileSelected};
+use crate::settings::Config;
+use crate::systems::ui::ImGuiDraw;
+use crate::tiles::{ReadTiles, TileEntities, Tiles};
+
+/// Shows what is on the tile, or in the region, last selected with the mouse: its designation,
+/// fluid and light, and how many things are there. The window is moved beside the selection as
+/// it's made. Nothing is shown until the level is loaded and something selected.
+#[derive(Default)]
+pub struct System {
+    tile_reader: Option<ReaderId<TileSelected>>,
+    region_reader: Option<ReaderId<RegionSelected>>,
+    selected: Option<(Vector3<u32>, Vector3<u32>)>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Write<'s, EventChannel<ImGuiDraw>>,
+        Read<'s, EventChannel<TileSelected>>,
+        Read<'s, EventChannel<RegionSelected>>,
+        Option<ReadExpect<'s, Tiles>>,
+        ReadExpect<'s, Config>,
+        Read<'s, Localization>,
+        ReadExpect<'s, ScreenDimensions>,
+        ReadStorage<'s, Camera>,
+        ReadStorage<'s, Transform>,
+        ReadTiles<'s, Designation>,
+        ReadTiles<'s, FluidDepth>,
+        ReadTiles<'s, LightLevel>,
+        ReadTiles<'s, TileEntities>,
+    );
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.tile_reader = Some(Write::<EventChannel<TileSelected>>::fetch(&res).register_reader());
+        self.region_reader =
+            Some(Write::<EventChannel<RegionSelected>>::fetch(&res).register_reader());
+    }
+
+    fn run(
+        &mut self,
+        (
+            mut imgui_draw,
+            tiles_selected,
+            regions_selected,
+            tiles,
+            config,
+            locale,
+            dimensions,
+            cameras,
+            transforms,
+            designations,
+            fluid_depths,
+            light,
+            occupants,
+        ): Self::SystemData,
+    ) {
+        let mut newly_selected = false;
+        for selected in tiles_selected.read(self.tile_reader.as_mut().unwrap()) {
+            self.selected = Some((selected.coord, selected.coord));
+            newly_selected = true;
+        }
+        for selected in regions_selected.read(self.region_reader.as_mut().unwrap()) {
+            self.selected = Some((selected.min, selected.max));
+            newly_selected = true;
+        }
+        let (tiles, (min, max)) = match (tiles, self.selected) {
+            (Some(tiles), Some(selected)) => (tiles, selected),
+            _ => return,
+        };
+
+        // A new selection brings the window to the screen past its south east corner
+        let anchor = if newly_selected {
+            let half = tile_world_size(&config) / 2.0;
+            let corner = tile_to_world(max, &config) + Vector3::new(half, -half, 0.0);
+            (&cameras, &transforms)
+                .join()
+                .next()
+                .and_then(|camera| world_to_screen(corner, &dimensions, camera))
+        } else {
+            None
+        };
+
+        let mut lines = Vec::new();
+        let things = tiles
+            .iter_volume(min, max)
+            .filter_map(|id| occupants.get(id))
+            .map(|occupants| occupants.0.len())
+            .sum::<usize>();
+        if min == max {
+            let id = tiles.id_from_vector(min);
+            lines.push(locale.format(
+                "ui.tile.position",
+                &[&min.x.to_string(), &min.y.to_string(), &min.z.to_string()],
+            ));
+            if let Some(designation) = designations.get(id) {
+                lines.push(locale.format("ui.tile.designated", &[&designation.to_string()]));
+            }
+            if let Some(depth) = fluid_depths.get(id) {
+                lines.push(locale.format("ui.tile.fluid_depth", &[&depth.0.to_string()]));
+            }
+            if let Some(light) = light.get(id) {
+                let lit = format!("{:.0}", light.0 * 100.0);
+                lines.push(locale.format("ui.tile.lit", &[&lit]));
+            }
+        } else {
+            let designated = tiles
+                .iter_volume(min, max)
+                .filter(|id| designations.get(*id).is_some())
+                .count();
+            lines.push(locale.format(
+                "ui.tile.region",
+                &[
+                    &min.x.to_string(),
+                    &min.y.to_string(),
+                    &max.x.to_string(),
+                    &max.y.to_string(),
+                    &min.z.to_string(),
+                ],
+            ));
+            let size = (max.x - min.x + 1) * (max.y - min.y + 1);
+            lines.push(locale.format(
+                "ui.tile.region_size",
+                &[&size.to_string(), &designated.to_string()],
+            ));
+        }
+        lines.push(locale.format("ui.tile.things", &[&things.to_string()]));
+        let title = ImString::new(locale.get("ui.tile.title"));
+
+        imgui_draw.single_write(Arc::new(
+            move |ui: &amethyst_imgui::imgui::Ui, _: &LazyUpdate| {
+                let window = ui
+                    .window(&title)
+                    .size((240.0, 120.0), imgui::ImGuiCond::FirstUseEver);
+                let window = match anchor {
+                    Some(anchor) => window.position(anchor, imgui::ImGuiCond::Always),
+                    None => window,
+                };
+                window.build(|| {
+                    for line in &lines {
+                        ui.text(line);
+                    }
+                })
+            },
+        ));
+    }
+}
@@ -1,26 +1,150 @@
 #![allow(clippy::module_name_repetitions)]
 
-use crate::components;
-use crate::settings::Context;
 use amethyst::{
+    assets::AssetStorage,
     core::{Parent, ParentHierarchy},
-    ecs::{Entities, ReadExpect, WriteStorage},
+    ecs::{
+        Entities, Entity, Join, Read, ReadExpect, ReadStorage, Resources, SystemData, Write,
+        WriteStorage,
+    },
+    shrev::EventChannel,
 };
 
+use crate::actions::{Action, ActionResult, Target};
+use crate::assets;
+use crate::components::{
+    self,
+    ai::{Blackboard, CurrentAction, Needs},
+    equipment::{EquipError, Equipped},
+};
+use crate::inventory;
+use crate::utils::ComponentEventReader;
+
+/// Carries out `Equip` and `Unequip` on the item entity targeted by the `Blackboard`. Only items
+/// the entity carries can be put on, and are then held by the wearer itself rather than any
+/// container. Items no longer carried are forgotten, and the insulation of what is worn is kept
+/// on the wearer's `Needs`.
 #[derive(Default)]
-pub struct System;
+pub struct System {
+    action_reader: ComponentEventReader<components::Actionable, Action>,
+}
 impl<'s> amethyst::ecs::System<'s> for System {
     type SystemData = (
-        ReadExpect<'s, Context>,
-        ReadExpect<'s, ParentHierarchy>,
         Entities<'s>,
-        WriteStorage<'s, components::Item>,
-        WriteStorage<'s, components::Container>,
+        ReadExpect<'s, ParentHierarchy>,
+        Read<'s, AssetStorage<assets::Item>>,
+        Write<'s, EventChannel<ActionResult>>,
+        ReadStorage<'s, Blackboard>,
+        ReadStorage<'s, CurrentAction>,
+        ReadStorage<'s, components::Item>,
+        ReadStorage<'s, components::Container>,
+        WriteStorage<'s, components::Actionable>,
+        WriteStorage<'s, Equipped>,
+        WriteStorage<'s, Needs>,
         WriteStorage<'s, Parent>,
     );
 
-    fn run(&mut self, _: Self::SystemData) {
-        //let (entity, _) = (&entities, &items).join().next().unwrap();
-        //let container_bitset = inventory::get_all_containers(entity, &hierarchy, &containers);
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        self.action_reader.setup(res);
+    }
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            hierarchy,
+            details,
+            mut results,
+            blackboards,
+            current_actions,
+            items,
+            containers,
+            mut actionables,
+            mut equipped,
+            mut needs,
+            mut parents,
+        ): Self::SystemData,
+    ) {
+        self.action_reader.maintain(&entities, &mut actionables);
+
+        let mut requests = Vec::new();
+        for (entity, actionable) in (&entities, &mut actionables).join() {
+            for action in self.action_reader.read(entity, actionable) {
+                match action {
+                    Action::Equip | Action::Unequip => requests.push((entity, *action)),
+                    _ => (),
+                }
+            }
+        }
+
+        for (entity, action) in requests {
+            let item = match blackboards
+                .get(entity)
+                .and_then(|blackboard| blackboard.target)
+            {
+                Some(Target::Entity(item)) => item,
+                _ => {
+                    results.single_write(ActionResult::failed(entity, action));
+                    continue;
+                }
+            };
+
+            let outcome = if action == Action::Equip {
+                let carried = inventory::get_all_items(entity, &hierarchy, &containers, &items);
+                let wearable = items
+                    .get(item)
+                    .filter(|_| carried.contains(item.id()))
+                    .and_then(|item| details.get(&item.handle))
+                    .and_then(|details| details.wearable)
+                    .ok_or(EquipError::NotWearable(item));
+                wearable.and_then(|wearable| {
+                    equipped
+                        .entry(entity)
+                        .unwrap()
+                        .or_insert_with(Equipped::default)
+                        .equip(item, wearable)
+                })
+            } else {
+                equipped
+                    .get_mut(entity)
+                    .ok_or(EquipError::NotEquipped(item))
+                    .and_then(|worn| worn.unequip(item))
+                    .map(|_| ())
+            };
+
+            match outcome {
+                Ok(()) => {
+                    // Worn items come out of their container, but are still carried.
+                    if action == Action::Equip {
+                        parents.insert(item, Parent { entity }).unwrap();
+                    }
+                    // Pawns finish once the time the action takes is up
+                    if !current_actions.contains(entity) {
+                        results.single_write(ActionResult::completed(entity, action));
+                    }
+                }
+                Err(_) => results.single_write(ActionResult::failed(entity, action)),
+            }
+        }
+
+        for (entity, worn) in (&entities, &mut equipped).join() {
+            worn.retain(|item| entities.is_alive(item) && is_carried_by(item, entity, &hierarchy));
+            if let Some(needs) = needs.get_mut(entity) {
+                needs.insulation = worn.insulation();
+            }
+        }
+    }
+}
+
+fn is_carried_by(item: Entity, wearer: Entity, hierarchy: &ParentHierarchy) -> bool {
+    let mut parent = hierarchy.parent(item);
+    while let Some(entity) = parent {
+        if entity == wearer {
+            return true;
+        }
+        parent = hierarchy.parent(entity);
     }
+    false
 }
@@ -0,0 +1,103 @@
+#![allow(clippy::module_name_repetitions)]
+
+use amethyst::ecs::{Entities, Join, Read, ReadExpect, ReadStorage, Write, WriteStorage};
+use rand::Rng;
+
+use crate::components::{self, max_fluid_depth, FluidDepth, Obstruction, TilePosition};
+use crate::systems::time::TimeState;
+use crate::temperature::HeatSource;
+use crate::tiles::{ReadTiles, Tiles, WriteTiles};
+use crate::weather::{
+    douse_intensity, landing, rain_drops, rain_interval, sheltered, Precipitation, RegionClimate,
+    Weather,
+};
+
+/// Moves the `Weather` on at the start of every day, by the `RegionClimate` and the season.
+/// While it rains, every `rain_interval` drops of water land on random columns of the map for
+/// the fluid system to run off, and heavy rain puts out the fires of items left out under the
+/// open sky.
+#[derive(Default)]
+pub struct System {
+    last_day: Option<u64>,
+    last_rain: Option<u64>,
+}
+impl<'s> amethyst::ecs::System<'s> for System {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Tiles>,
+        Read<'s, TimeState>,
+        Read<'s, RegionClimate>,
+        Write<'s, Weather>,
+        ReadTiles<'s, Obstruction>,
+        WriteTiles<'s, FluidDepth>,
+        ReadStorage<'s, components::Item>,
+        ReadStorage<'s, TilePosition>,
+        WriteStorage<'s, HeatSource>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            tiles,
+            time,
+            climate,
+            mut weather,
+            obstructions,
+            mut fluids,
+            items,
+            tile_positions,
+            mut heat_sources,
+        ): Self::SystemData,
+    ) {
+        let mut rng = rand::thread_rng();
+
+        let day = time.day();
+        if self.last_day != Some(day) {
+            self.last_day = Some(day);
+            weather.evolve(&climate, time.season(), &mut rng);
+            log::info!("The weather today: {:?}", *weather);
+        }
+
+        if weather.precipitation != Precipitation::Rain {
+            return;
+        }
+        let now = time.current_time;
+        if self
+            .last_rain
+            .map_or(false, |last| now < last + rain_interval)
+        {
+            return;
+        }
+        self.last_rain = Some(now);
+
+        let blocked = |id| match obstructions.get(id) {
+            Some(Obstruction::Impassable) => true,
+            _ => false,
+        };
+        let dimensions = tiles.dimensions();
+        let drops = (weather.intensity * rain_drops as f32).round() as usize;
+        for _ in 0..drops {
+            let x = ((rng.gen::<f32>() * dimensions.x as f32) as u32).min(dimensions.x - 1);
+            let y = ((rng.gen::<f32>() * dimensions.y as f32) as u32).min(dimensions.y - 1);
+            if let Some(id) = landing(*tiles, x, y, blocked) {
+                let depth = fluids.get(id).map_or(0, |depth| depth.0);
+                if depth < max_fluid_depth {
+                    fluids.insert(id, FluidDepth(depth + 1));
+                }
+            }
+        }
+
+        if weather.intensity >= douse_intensity {
+            let doused = (&entities, &items, &heat_sources, &tile_positions)
+                .join()
+                .filter(|(_, _, _, position)| !sheltered(*tiles, position.coord, blocked))
+                .map(|(fire, _, _, _)| fire)
+                .collect::<Vec<_>>();
+            for fire in doused {
+                log::info!("The rain put out {:?}", fire);
+                heat_sources.remove(fire);
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use amethyst::{
+    core::math::Vector3,
+    ecs::{Component, DenseVecStorage},
+};
+use specs_derive::Component;
+
+/// Width, height and depth in tiles of a cell of the `TemperatureGrid`.
+pub const cell_size: u32 = 8;
+/// Temperature of places no climate map covers, in degrees celsius.
+pub const default_surface_temperature: f32 = 15.0;
+/// Share of the difference to its neighbours a cell takes on per unit of game time.
+pub const diffusion_rate: f32 = 0.1;
+/// Share of the difference to its climate, plus any heat sources, a cell takes on per unit of
+/// game time.
+pub const relaxation_rate: f32 = 0.05;
+/// Most units of game time the grid is stepped through at once, the rest being skipped.
+pub const max_steps: u64 = 10;
+
+/// Ambient temperature, in degrees celsius, over coarse cells of tiles. Each cell settles
+/// towards its climate, shifted by the season, plus the heat given off in it, while heat spreads
+/// between neighbours.
+#[derive(Clone, Debug, Default)]
+pub struct TemperatureGrid {
+    dimensions: Vector3<u32>,
+    climate: Vec<f32>,
+    values: Vec<f32>,
+    /// Degrees added to the climate of every cell.
+    climate_offset: f32,
+}
+impl TemperatureGrid {
+    /// A grid over `tiles` tiles, its climate given by `surface` at each column of cells.
+    pub fn new<F>(tiles: Vector3<u32>, surface: F) -> Self
+    where
+        F: Fn(u32, u32) -> f32,
+    {
+        let cells = |tiles: u32| (tiles + cell_size - 1) / cell_size;
+        let dimensions = Vector3::new(cells(tiles.x), cells(tiles.y), cells(tiles.z));
+
+        let mut climate = Vec::with_capacity((dimensions.x * dimensions.y * dimensions.z) as usize);
+        for _ in 0..dimensions.z {
+            for y in 0..dimensions.y {
+                for x in 0..dimensions.x {
+                    climate.push(surface(x * cell_size, y * cell_size));
+                }
+            }
+        }
+
+        Self {
+            dimensions,
+            values: climate.clone(),
+            climate,
+            climate_offset: 0.0,
+        }
+    }
+
+    /// Shifts the climate every cell settles towards, such as by the season.
+    pub fn set_climate_offset(&mut self, offset: f32) {
+        self.climate_offset = offset;
+    }
+
+    fn index(&self, cell: Vector3<u32>) -> usize {
+        ((cell.z * self.dimensions.y + cell.y) * self.dimensions.x + cell.x) as usize
+    }
+
+    fn cell(&self, coord: Vector3<u32>) -> Vector3<u32> {
+        Vector3::new(
+            (coord.x / cell_size).min(self.dimensions.x.saturating_sub(1)),
+            (coord.y / cell_size).min(self.dimensions.y.saturating_sub(1)),
+            (coord.z / cell_size).min(self.dimensions.z.saturating_sub(1)),
+        )
+    }
+
+    /// The temperature at a tile.
+    pub fn at(&self, coord: Vector3<u32>) -> f32 {
+        self.values
+            .get(self.index(self.cell(coord)))
+            .cloned()
+            .unwrap_or(default_surface_temperature)
+    }
+
+    /// Advances the grid over `elapsed` game time, with `sources` giving off heat at tiles.
+    pub fn step(&mut self, elapsed: u64, sources: &[(Vector3<u32>, f32)]) {
+        if self.values.is_empty() {
+            return;
+        }
+
+        let mut targets = self
+            .climate
+            .iter()
+            .map(|climate| climate + self.climate_offset)
+            .collect::<Vec<_>>();
+        for (coord, output) in sources {
+            let index = self.index(self.cell(*coord));
+            targets[index] += output;
+        }
+
+        let d = self.dimensions;
+        for _ in 0..elapsed.min(max_steps) {
+            let mut next = self.values.clone();
+            for z in 0..d.z {
+                for y in 0..d.y {
+                    for x in 0..d.x {
+                        let index = self.index(Vector3::new(x, y, z));
+                        let value = self.values[index];
+                        let neighbours = [
+                            (x > 0, Vector3::new(x.wrapping_sub(1), y, z)),
+                            (x + 1 < d.x, Vector3::new(x + 1, y, z)),
+                            (y > 0, Vector3::new(x, y.wrapping_sub(1), z)),
+                            (y + 1 < d.y, Vector3::new(x, y + 1, z)),
+                            (z > 0, Vector3::new(x, y, z.wrapping_sub(1))),
+                            (z + 1 < d.z, Vector3::new(x, y, z + 1)),
+                        ];
+                        let (sum, count) = neighbours.iter().filter(|(exists, _)| *exists).fold(
+                            (0.0, 0),
+                            |(sum, count), (_, cell)| {
+                                (sum + self.values[self.index(*cell)], count + 1)
+                            },
+                        );
+                        let spread = if count > 0 {
+                            sum / count as f32 - value
+                        } else {
+                            0.0
+                        };
+
+                        next[index] = value
+                            + diffusion_rate * spread
+                            + relaxation_rate * (targets[index] - value);
+                    }
+                }
+            }
+            self.values = next;
+        }
+    }
+}
+
+/// Heat given off by something, such as a fire or magma, in degrees celsius added to the climate
+/// of its cell of the `TemperatureGrid`.
+#[derive(Component, Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct HeatSource(pub f32);
+
+pub const fire_heat: f32 = 25.0;
+pub const magma_heat: f32 = 200.0;
+
+pub const normal_body_temperature: f32 = 37.0;
+/// Body temperatures at which hypothermia and heatstroke are at their extreme.
+pub const lethal_cold: f32 = 28.0;
+pub const lethal_heat: f32 = 42.0;
+/// How much warmer than the air a body keeps itself, unclothed.
+pub const metabolic_heat: f32 = 15.0;
+/// Share of the difference to its equilibrium a body takes on per unit of game time.
+pub const heat_exchange_rate: f32 = 0.01;
+/// Wetness lost per unit of game time.
+pub const drying_rate: f32 = 0.002;
+
+/// Temperature of a living body, drawn towards the air around it. Clothing holds in more of its
+/// own heat, unless soaked, and being wet speeds up the exchange.
+#[derive(Component, Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct BodyTemperature {
+    pub celsius: f32,
+    /// From 0 when dry to 1 when soaked.
+    pub wetness: f32,
+}
+impl Default for BodyTemperature {
+    fn default() -> Self {
+        Self {
+            celsius: normal_body_temperature,
+            wetness: 0.0,
+        }
+    }
+}
+impl BodyTemperature {
+    /// The temperature the body settles at in `ambient` air.
+    pub fn equilibrium(&self, ambient: f32, insulation: f32) -> f32 {
+        let insulation = insulation.max(0.0) * (1.0 - self.wetness);
+        ambient + metabolic_heat * (1.0 + insulation)
+    }
+
+    pub fn update(&mut self, elapsed: u64, ambient: f32, insulation: f32, in_water: bool) {
+        let target = self.equilibrium(ambient, insulation);
+        let rate = heat_exchange_rate * (1.0 + self.wetness) * elapsed as f32;
+        self.celsius += (target - self.celsius) * rate.min(1.0);
+
+        self.wetness = if in_water {
+            1.0
+        } else {
+            (self.wetness - drying_rate * elapsed as f32).max(0.0)
+        };
+    }
+
+    /// From 0 at a normal temperature to 1 when lethally cold.
+    pub fn hypothermia(&self) -> f32 {
+        ((normal_body_temperature - self.celsius) / (normal_body_temperature - lethal_cold))
+            .max(0.0)
+            .min(1.0)
+    }
+
+    /// From 0 at a normal temperature to 1 when lethally hot.
+    pub fn heatstroke(&self) -> f32 {
+        ((self.celsius - normal_body_temperature) / (lethal_heat - normal_body_temperature))
+            .max(0.0)
+            .min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffusion() {
+        // Cold to the west, warm to the east
+        let surface = |x: u32, _: u32| if x < 32 { 0.0 } else { 20.0 };
+        let mut grid = TemperatureGrid::new(Vector3::new(64, 8, 8), surface);
+        assert_eq!(grid.at(Vector3::new(0, 0, 0)), 0.0);
+        assert_eq!(grid.at(Vector3::new(100, 100, 100)), 20.0);
+
+        let fire = Vector3::new(0, 0, 0);
+        grid.step(1000, &[(fire, fire_heat)]);
+        let warmed = grid.at(fire);
+        assert!(warmed > 0.0 && warmed < fire_heat);
+        // Heat spreads, fading with distance
+        assert!(grid.at(Vector3::new(8, 0, 0)) < warmed);
+        assert!(grid.at(Vector3::new(63, 0, 0)) <= 20.0);
+
+        // A cold season draws everything colder
+        let far = Vector3::new(63, 0, 0);
+        let before = grid.at(far);
+        grid.set_climate_offset(-12.0);
+        grid.step(1000, &[]);
+        assert!(grid.at(far) < before);
+    }
+
+    #[test]
+    fn body_temperature() {
+        let settle = |insulation, in_water| {
+            let mut body = BodyTemperature::default();
+            for _ in 0..100 {
+                body.update(10, 0.0, insulation, in_water);
+            }
+            body
+        };
+
+        let bare = settle(0.0, false);
+        let dressed = settle(1.0, false);
+        let soaked = settle(1.0, true);
+        assert!(bare.hypothermia() > 0.5);
+        assert!(dressed.hypothermia() < bare.hypothermia());
+        assert!(soaked.celsius < dressed.celsius);
+        assert_eq!(dressed.heatstroke(), 0.0);
+
+        let mut body = BodyTemperature::default();
+        for _ in 0..100 {
+            body.update(10, 40.0, 1.0, false);
+        }
+        assert_eq!(body.heatstroke(), 1.0);
+    }
+}
@@ -1,33 +1,82 @@
 use amethyst::{
     core::math::{Point3, Vector2, Vector3, Vector4},
-    ecs::{BitSet, Component, DenseVecStorage, Entity, Join, ParJoin, Read, Write},
+    ecs::{
+        storage::{ComponentEvent, Tracked},
+        BitSet, Component, DenseVecStorage, Entities, Entity, Join, NullStorage, ParJoin, Read,
+        SystemData, World, Write, WriteStorage,
+    },
+    shrev::ReaderId,
 };
 
 use specs_derive::Component;
 use specs_static::{Id, Storage};
 use std::collections::HashSet;
 
+use crate::render::camera;
+
 #[derive(Component, Clone, Debug, Default)]
 #[storage(DenseVecStorage)]
 pub struct TileEntities(pub HashSet<Entity>);
 
+/// The lazily spawned entity backing a tile which needs full ECS features, such as burning,
+/// constructions or damage. See `get_or_create_tile_entity`.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[storage(DenseVecStorage)]
+pub struct TileEntity(pub Entity);
+
+/// Links a tile entity back to the tile it represents.
+#[derive(Component, Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[storage(DenseVecStorage)]
+pub struct TileRef(pub TileId);
+
+/// Marks a tile entity as back to an inert state; it is deleted and unlinked from its tile by
+/// `TileEntityCleanupSystem`.
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq)]
+#[storage(NullStorage)]
+pub struct InertTile;
+
+/// Tiles along each side of a region, the square of tiles of one z-level numbered together.
+pub const region_size: u32 = 16;
+/// Bits of a `TileId` holding the index of the tile within its region, `region_size` squared.
+const local_bits: u32 = 8;
+
+/// A tile, as the region it is in and its index within that region, in the high and low bits,
+/// and the generation of the `Tiles` it is of. The tiles of a region are numbered together, row
+/// by row, and the regions row by row and z-level by z-level. Tile storages give each region
+/// room of its own, so they are sized by the regions with components rather than the whole map,
+/// and only find components at ids of the generation of their region.
 #[derive(
     Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize,
 )]
-pub struct TileId(u32);
+pub struct TileId {
+    index: u32,
+    generation: u32,
+}
 
 impl TileId {
+    pub fn new(region: u32, local: u32, generation: u32) -> Self {
+        debug_assert!(local < 1 << local_bits);
+        Self {
+            index: (region << local_bits) | local,
+            generation,
+        }
+    }
+
+    /// The region the tile is in, see `Tiles::regions`.
+    pub fn region(self) -> u32 {
+        self.index >> local_bits
+    }
+
+    /// The index of the tile within its region.
+    pub fn local(self) -> u32 {
+        self.index & ((1 << local_bits) - 1)
+    }
+
     #[inline]
-    // (z * xMax * yMax) + (y * xMax) + x;
     pub fn coords(self, dimensions: Vector3<u32>) -> (f32, f32, f32) {
-        let z = self.0 / (dimensions.x * dimensions.y);
-        let idx = self.0 - (z * dimensions.x * dimensions.y);
+        let coord = Tiles::new(dimensions.x, dimensions.y, dimensions.z).coord(self);
 
-        (
-            (idx % dimensions.y) as f32,
-            (idx / dimensions.y) as f32,
-            z as f32,
-        )
+        (coord.x as f32, coord.y as f32, coord.z as f32)
     }
 
     #[inline]
@@ -38,59 +87,117 @@ impl TileId {
 }
 
 impl Id for TileId {
+    const GROUP_BITS: u32 = local_bits;
+
     fn from_u32(value: u32) -> Self {
-        Self(value)
+        Self::with_generation(value, 0)
     }
 
     fn id(&self) -> u32 {
-        self.0
+        self.index
+    }
+
+    fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    fn with_generation(index: u32, generation: u32) -> Self {
+        Self { index, generation }
     }
 }
 
-#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+const neighbor_offsets_4: [(i32, i32, i32); 4] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0)];
+const neighbor_offsets_8: [(i32, i32, i32); 8] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (1, 1, 0),
+    (-1, -1, 0),
+    (1, -1, 0),
+    (-1, 1, 0),
+];
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Tiles {
     dimensions: Vector3<u32>,
+    /// The generation of the ids of these tiles, so ids kept from the tiles of another level
+    /// find nothing in the storages of this one.
+    generation: u32,
 }
 
 impl Tiles {
     pub fn new(x: u32, y: u32, z: u32) -> Self {
         Self {
             dimensions: Vector3::new(x, y, z),
+            generation: 0,
         }
     }
 
+    /// These tiles, with ids of `generation`.
+    pub fn with_generation(self, generation: u32) -> Self {
+        Self { generation, ..self }
+    }
+
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+
     pub fn id(self, x: u32, y: u32, z: u32) -> TileId {
-        TileId((z * self.dimensions.x * self.dimensions.y) + (y * self.dimensions.x) + x)
+        let regions = self.regions();
+        let region = (z * regions.y + y / region_size) * regions.x + x / region_size;
+
+        TileId::new(
+            region,
+            (y % region_size) * region_size + x % region_size,
+            self.generation,
+        )
+    }
+
+    /// The id of the tile numbered `index` in tile storages and the bitsets over them.
+    pub fn id_at(self, index: u32) -> TileId {
+        TileId::with_generation(index, self.generation)
     }
 
     pub fn id_from_vector(&self, vector: Vector3<u32>) -> TileId {
-        //(z * xMax * yMax) + (y * xMax) + x;
-        TileId(
-            (vector.z * self.dimensions.x * self.dimensions.y)
-                + (vector.y * self.dimensions.x)
-                + vector.x,
-        )
+        self.id(vector.x, vector.y, vector.z)
     }
 
     pub fn id_from_point(&self, vector: Point3<u32>) -> TileId {
-        //(z * xMax * yMax) + (y * xMax) + x;
-        TileId(
-            (vector.z * self.dimensions.x * self.dimensions.y)
-                + (vector.y * self.dimensions.x)
-                + vector.x,
+        self.id(vector.x, vector.y, vector.z)
+    }
+
+    /// The number of regions along each axis. Those at the far edges of the map are cut off by
+    /// it where its sides aren't multiples of `region_size`, their ids past it left unused.
+    pub fn regions(self) -> Vector3<u32> {
+        Vector3::new(
+            (self.dimensions.x + region_size - 1) / region_size,
+            (self.dimensions.y + region_size - 1) / region_size,
+            self.dimensions.z,
         )
     }
 
+    /// One past the highest id of the tiles of the map, for sizing what is indexed by id.
+    pub fn id_bound(self) -> u32 {
+        let regions = self.regions();
+        (regions.x * regions.y * regions.z) << local_bits
+    }
+
     pub fn world_to_tile(
         self,
         vector: &Vector3<amethyst::core::Float>,
         game_settings: &crate::settings::Config,
     ) -> Vector3<u32> {
-        Vector3::new(
-            (vector.x / 20. / game_settings.graphics.scale) as u32,
-            (vector.y / 20. / game_settings.graphics.scale).abs() as u32,
-            (vector.z / 20. / game_settings.graphics.scale).abs() as u32,
-        )
+        camera::world_to_tile(vector, game_settings)
+    }
+
+    /// The world position of the centre of the tile at `coord`, which `world_to_tile` maps back.
+    pub fn tile_to_world(
+        self,
+        coord: Vector3<u32>,
+        game_settings: &crate::settings::Config,
+    ) -> Vector3<f32> {
+        camera::tile_to_world(coord, game_settings)
     }
 
     pub fn world_to_id(
@@ -98,15 +205,13 @@ impl Tiles {
         vector: &Vector3<f32>,
         game_settings: &crate::settings::Config,
     ) -> TileId {
-        self.id_from_vector(Vector3::new(
-            (vector.x / 20. / game_settings.graphics.scale) as u32,
-            (vector.y / 20. / game_settings.graphics.scale).abs() as u32,
-            (vector.z / 20. / game_settings.graphics.scale).abs() as u32,
-        ))
+        self.id_from_vector(camera::world_to_tile(vector, game_settings))
     }
 
+    /// Every tile of the lowest z-level.
     pub fn iter_all(self) -> impl Iterator<Item = TileId> {
-        (0..self.dimensions.x * self.dimensions.y - 1).map(TileId)
+        (0..self.dimensions.y)
+            .flat_map(move |y| (0..self.dimensions.x).map(move |x| self.id(x, y, 0)))
     }
 
     pub fn iter_region(self, region: Vector4<u32>, z_level: u32) -> impl Iterator<Item = TileId> {
@@ -116,6 +221,168 @@ impl Tiles {
     pub fn dimensions(self) -> Vector3<u32> {
         self.dimensions
     }
+
+    /// The integer coordinate of `id`.
+    pub fn coord(self, id: TileId) -> Vector3<u32> {
+        let regions = self.regions();
+        let layer = regions.x * regions.y;
+        let (region, local) = (id.region() % layer, id.local());
+
+        Vector3::new(
+            (region % regions.x) * region_size + local % region_size,
+            (region / regions.x) * region_size + local / region_size,
+            id.region() / layer,
+        )
+    }
+
+    /// Whether `id` is of a tile of the map, rather than one past its edge in a region cut off
+    /// by it.
+    pub fn contains_id(self, id: TileId) -> bool {
+        let coord = self.coord(id);
+        coord.x < self.dimensions.x && coord.y < self.dimensions.y && coord.z < self.dimensions.z
+    }
+
+    pub fn contains(self, coord: Vector3<i64>) -> bool {
+        coord.x >= 0
+            && coord.y >= 0
+            && coord.z >= 0
+            && coord.x < i64::from(self.dimensions.x)
+            && coord.y < i64::from(self.dimensions.y)
+            && coord.z < i64::from(self.dimensions.z)
+    }
+
+    /// Offsets `id`, returning `None` if the result falls outside the map.
+    pub fn offset(self, id: TileId, x: i32, y: i32, z: i32) -> Option<TileId> {
+        let coord = self.coord(id);
+        let target = Vector3::new(
+            i64::from(coord.x) + i64::from(x),
+            i64::from(coord.y) + i64::from(y),
+            i64::from(coord.z) + i64::from(z),
+        );
+
+        if self.contains(target) {
+            Some(self.id(target.x as u32, target.y as u32, target.z as u32))
+        } else {
+            None
+        }
+    }
+
+    /// The orthogonal neighbors of `id` on its z-level. Edge tiles have fewer neighbors.
+    pub fn neighbors4(self, id: TileId) -> impl Iterator<Item = TileId> {
+        neighbor_offsets_4
+            .iter()
+            .filter_map(move |(x, y, z)| self.offset(id, *x, *y, *z))
+    }
+
+    /// The orthogonal and diagonal neighbors of `id` on its z-level.
+    pub fn neighbors8(self, id: TileId) -> impl Iterator<Item = TileId> {
+        neighbor_offsets_8
+            .iter()
+            .filter_map(move |(x, y, z)| self.offset(id, *x, *y, *z))
+    }
+
+    /// All 26 neighbors of `id`, including the levels above and below.
+    pub fn neighbors3d(self, id: TileId) -> impl Iterator<Item = TileId> {
+        (-1..=1)
+            .flat_map(|z| (-1..=1).flat_map(move |y| (-1..=1).map(move |x| (x, y, z))))
+            .filter(|offset| *offset != (0, 0, 0))
+            .filter_map(move |(x, y, z)| self.offset(id, x, y, z))
+    }
+
+    /// The tiles on the 3D Bresenham line from `from` to `to`, inclusive of both ends.
+    pub fn line(self, from: TileId, to: TileId) -> Vec<TileId> {
+        let start = self.coord(from);
+        let end = self.coord(to);
+
+        let mut point = [
+            i64::from(start.x),
+            i64::from(start.y),
+            i64::from(start.z),
+        ];
+        let target = [i64::from(end.x), i64::from(end.y), i64::from(end.z)];
+        let delta = [
+            (target[0] - point[0]).abs(),
+            (target[1] - point[1]).abs(),
+            (target[2] - point[2]).abs(),
+        ];
+        let step = [
+            (target[0] - point[0]).signum(),
+            (target[1] - point[1]).signum(),
+            (target[2] - point[2]).signum(),
+        ];
+
+        // Step along the driving (longest) axis, accumulating error on the other two
+        let axis = if delta[0] >= delta[1] && delta[0] >= delta[2] {
+            0
+        } else if delta[1] >= delta[2] {
+            1
+        } else {
+            2
+        };
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let mut error_a = 2 * delta[a] - delta[axis];
+        let mut error_b = 2 * delta[b] - delta[axis];
+
+        let mut line = Vec::with_capacity(delta[axis] as usize + 1);
+        line.push(from);
+        for _ in 0..delta[axis] {
+            point[axis] += step[axis];
+            if error_a > 0 {
+                point[a] += step[a];
+                error_a -= 2 * delta[axis];
+            }
+            if error_b > 0 {
+                point[b] += step[b];
+                error_b -= 2 * delta[axis];
+            }
+            error_a += 2 * delta[a];
+            error_b += 2 * delta[b];
+
+            line.push(self.id(point[0] as u32, point[1] as u32, point[2] as u32));
+        }
+
+        line
+    }
+
+    /// Walks the line from `from` to `to`, returning the first tile for which `blocked` is true.
+    /// The starting tile itself is never considered blocking.
+    pub fn raycast<F>(self, from: TileId, to: TileId, blocked: F) -> Option<TileId>
+    where
+        F: Fn(TileId) -> bool,
+    {
+        self.line(from, to).into_iter().skip(1).find(|id| blocked(*id))
+    }
+
+    /// Whether `to` can be seen from `from`. The target tile may itself be blocking, e.g. a wall
+    /// is visible even though it can't be seen through.
+    pub fn line_of_sight<F>(self, from: TileId, to: TileId, blocked: F) -> bool
+    where
+        F: Fn(TileId) -> bool,
+    {
+        match self.raycast(from, to, blocked) {
+            Some(hit) => hit == to,
+            None => true,
+        }
+    }
+
+    /// Every tile in the inclusive box between `min` and `max`, clamped to the map, a z-level at
+    /// a time and row by row.
+    pub fn iter_volume(self, min: Vector3<u32>, max: Vector3<u32>) -> impl Iterator<Item = TileId> {
+        let max = Vector3::new(
+            max.x.min(self.dimensions.x - 1),
+            max.y.min(self.dimensions.y - 1),
+            max.z.min(self.dimensions.z - 1),
+        );
+
+        (min.z..=max.z).flat_map(move |z| {
+            (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| self.id(x, y, z)))
+        })
+    }
+
+    /// Every tile in the column at `(x, y)`, from z = 0 up.
+    pub fn iter_column(self, x: u32, y: u32) -> impl Iterator<Item = TileId> {
+        (0..self.dimensions.z).map(move |z| self.id(x, y, z))
+    }
 }
 
 impl<'a> Join for &'a Tiles {
@@ -127,8 +394,8 @@ impl<'a> Join for &'a Tiles {
         (BitSet::new(), self)
     }
 
-    unsafe fn get(_v: &mut &'a Tiles, idx: u32) -> TileId {
-        TileId::from_u32(idx)
+    unsafe fn get(v: &mut &'a Tiles, idx: u32) -> TileId {
+        v.id_at(idx)
     }
 }
 
@@ -173,7 +440,380 @@ impl Iterator for RegionIter {
     }
 }
 
+/// Returns the entity backing tile `id`, spawning it if the tile doesn't have one yet.
+pub fn get_or_create_tile_entity(world: &mut World, id: TileId) -> Entity {
+    let (entities, mut tile_entities, mut tile_refs): (
+        Entities,
+        WriteTiles<TileEntity>,
+        WriteStorage<TileRef>,
+    ) = SystemData::fetch(&world.res);
+
+    get_or_create_tile_entity_with(&entities, &mut tile_entities, &mut tile_refs, id)
+}
+
+/// `get_or_create_tile_entity` for use from within systems.
+pub fn get_or_create_tile_entity_with(
+    entities: &Entities,
+    tile_entities: &mut Storage<TileEntity, DenseVecStorage<TileEntity>, TileId>,
+    tile_refs: &mut WriteStorage<TileRef>,
+    id: TileId,
+) -> Entity {
+    if let Some(TileEntity(entity)) = tile_entities.get(id) {
+        if entities.is_alive(*entity) {
+            return *entity;
+        }
+    }
+
+    let entity = entities.create();
+    tile_refs
+        .insert(entity, TileRef(id))
+        .expect("Unreachable: entity was just created");
+    tile_entities.insert(id, TileEntity(entity));
+
+    entity
+}
+
+/// Published whenever the contents of a tile change in a way other systems may cache, such as
+/// its passability.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileChangeEvent {
+    pub id: TileId,
+}
+
+/// Every tile reachable from `start` through `passable` tiles on its z-level, including `start`.
+/// Returns nothing if `start` itself is not passable.
+pub fn flood_fill<F>(tiles: Tiles, start: TileId, passable: F) -> Vec<TileId>
+where
+    F: Fn(TileId) -> bool,
+{
+    if !passable(start) {
+        return Vec::new();
+    }
+
+    let mut visited = HashSet::new();
+    let mut open = vec![start];
+    visited.insert(start);
+
+    let mut filled = Vec::new();
+    while let Some(id) = open.pop() {
+        filled.push(id);
+        for neighbor in tiles.neighbors8(id) {
+            if passable(neighbor) && visited.insert(neighbor) {
+                open.push(neighbor);
+            }
+        }
+    }
+
+    filled
+}
+
+/// The z-level the camera is viewing, and how far below it the tile pass draws.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ViewZ {
+    pub z: u32,
+    /// Number of levels below `z` drawn underneath the current slice.
+    pub peek_levels: u32,
+    /// Color multiplier applied per level of depth below the current slice.
+    pub peek_darkening: f32,
+}
+impl Default for ViewZ {
+    fn default() -> Self {
+        Self {
+            z: 0,
+            peek_levels: 2,
+            peek_darkening: 0.6,
+        }
+    }
+}
+impl ViewZ {
+    pub fn up(&mut self, tiles: Tiles) {
+        self.z = (self.z + 1).min(tiles.dimensions().z - 1);
+    }
+
+    pub fn down(&mut self) {
+        self.z = self.z.saturating_sub(1);
+    }
+
+    /// The tint multiplier for a tile `depth` levels below the current slice.
+    pub fn tint(&self, depth: u32) -> f32 {
+        self.peek_darkening.powi(depth as i32)
+    }
+
+    /// The tile of the column at `(x, y)` drawn for the current slice, and how many levels below
+    /// it that is: the first `filled` one from the slice down, no more than `peek_levels` deep.
+    pub fn peek<F>(&self, tiles: Tiles, x: u32, y: u32, filled: F) -> Option<(TileId, u32)>
+    where
+        F: Fn(TileId) -> bool,
+    {
+        let z = self.z.min(tiles.dimensions().z - 1);
+        (0..=self.peek_levels.min(z))
+            .map(|depth| (tiles.id(x, y, z - depth), depth))
+            .find(|(id, _)| filled(*id))
+    }
+}
+
+#[allow(clippy::module_name_repetitions)]
+pub type TileStorage<C> = Storage<C, <C as Component>::Storage, TileId>;
 #[allow(clippy::module_name_repetitions)]
 pub type ReadTiles<'a, C> = Read<'a, Storage<C, <C as Component>::Storage, TileId>>;
 #[allow(clippy::module_name_repetitions)]
 pub type WriteTiles<'a, C> = Write<'a, Storage<C, <C as Component>::Storage, TileId>>;
+
+/// The tiles inserted, modified or removed in the flagged tile storage of `C` since a system last
+/// looked, read from its events with a reader kept here. As the tile storages are only registered
+/// once the level loads, the reader is taken the first time the storage is looked at. Looking
+/// every frame keeps the tiles of the events as those they were sent for, see `Storage::maintain`.
+#[allow(clippy::module_name_repetitions)]
+pub struct TrackedTiles<C> {
+    reader: Option<ReaderId<ComponentEvent>>,
+    dirty: BitSet,
+    phantom: std::marker::PhantomData<C>,
+}
+impl<C> Default for TrackedTiles<C> {
+    fn default() -> Self {
+        Self {
+            reader: None,
+            dirty: BitSet::new(),
+            phantom: std::marker::PhantomData,
+        }
+    }
+}
+impl<C> TrackedTiles<C>
+where
+    C: Component,
+    C::Storage: Tracked,
+{
+    /// The ids of the tiles changed in `storage` since the last call, or `None` on the first,
+    /// which starts tracking it, for everything to be taken as changed.
+    pub fn changed(&mut self, storage: &mut TileStorage<C>) -> Option<&BitSet> {
+        let reader = match &mut self.reader {
+            Some(reader) => reader,
+            None => {
+                self.reader = Some(storage.channel_mut().register_reader());
+                return None;
+            }
+        };
+
+        self.dirty.clear();
+        for event in storage.channel().read(reader) {
+            let slot = match event {
+                ComponentEvent::Inserted(slot)
+                | ComponentEvent::Modified(slot)
+                | ComponentEvent::Removed(slot) => *slot,
+            };
+            if let Some(id) = storage.id_of_slot(slot) {
+                self.dirty.add(id.id());
+            }
+        }
+        Some(&self.dirty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbors_clamp_at_edges() {
+        let tiles = Tiles::new(10, 10, 3);
+
+        assert_eq!(tiles.neighbors4(tiles.id(0, 0, 0)).count(), 2);
+        assert_eq!(tiles.neighbors8(tiles.id(0, 0, 0)).count(), 3);
+        assert_eq!(tiles.neighbors8(tiles.id(5, 5, 0)).count(), 8);
+        assert_eq!(tiles.neighbors8(tiles.id(9, 5, 0)).count(), 5);
+        assert_eq!(tiles.neighbors3d(tiles.id(5, 5, 1)).count(), 26);
+        assert_eq!(tiles.neighbors3d(tiles.id(0, 0, 0)).count(), 7);
+
+        // No wrapping onto the next row
+        assert!(tiles
+            .neighbors8(tiles.id(9, 5, 0))
+            .all(|id| tiles.coord(id).x >= 8));
+    }
+
+    #[test]
+    fn region_ids() {
+        let tiles = Tiles::new(40, 20, 3);
+        assert_eq!(tiles.regions(), Vector3::new(3, 2, 3));
+        assert_eq!(tiles.id_bound(), 3 * 2 * 3 * region_size * region_size);
+
+        let id = tiles.id(17, 3, 1);
+        assert_eq!(id.region(), 6 + 1);
+        assert_eq!(id.local(), 3 * region_size + 1);
+        assert_eq!(tiles.id(16, 3, 1).id() + 1, id.id());
+
+        for coord in tiles
+            .iter_volume(Vector3::new(0, 0, 0), Vector3::new(39, 19, 2))
+            .map(|id| tiles.coord(id))
+            .step_by(7)
+        {
+            let id = tiles.id_from_vector(coord);
+            assert_eq!(tiles.coord(id), coord);
+            assert!(tiles.contains_id(id));
+            assert!(id.id() < tiles.id_bound());
+        }
+
+        // Past the right edge of the map, in the last region of the row
+        assert!(!tiles.contains_id(TileId::new(2, 8, 0)));
+    }
+
+    #[test]
+    fn stale_ids() {
+        use crate::components::FluidDepth;
+
+        let tiles = Tiles::new(40, 20, 1);
+        let next = tiles.with_generation(1);
+        let mut depths = TileStorage::<FluidDepth>::default();
+        depths.insert(tiles.id(3, 4, 0), FluidDepth(2));
+        depths.insert(tiles.id(30, 4, 0), FluidDepth(5));
+
+        // Tiles of the next generation take over the regions they are inserted into
+        depths.insert(next.id(4, 4, 0), FluidDepth(1));
+        assert!(depths.get(tiles.id(3, 4, 0)).is_none());
+        assert!(depths.get(next.id(3, 4, 0)).is_none());
+        assert_eq!(depths.get(next.id(4, 4, 0)).map(|depth| depth.0), Some(1));
+        assert_eq!(depths.get(tiles.id(30, 4, 0)).map(|depth| depth.0), Some(5));
+        assert_eq!(
+            depths.ids().collect::<Vec<_>>(),
+            vec![next.id(4, 4, 0), tiles.id(30, 4, 0)]
+        );
+    }
+
+    #[test]
+    fn tracked_across_regions() {
+        use crate::components::FluidDepth;
+
+        let tiles = Tiles::new(40, 20, 1);
+        let (dried, wet) = (tiles.id(3, 4, 0), tiles.id(30, 4, 0));
+        let mut depths = TileStorage::<FluidDepth>::default();
+        let mut tracked = TrackedTiles::<FluidDepth>::default();
+        assert!(tracked.changed(&mut depths).is_none());
+        depths.insert(dried, FluidDepth(1));
+        tracked.changed(&mut depths);
+
+        // The water dries up in one region as it rises in another, before the changes are read
+        depths.remove(dried);
+        depths.insert(wet, FluidDepth(1));
+        let changed = tracked.changed(&mut depths).unwrap();
+        assert!(changed.contains(dried.id()));
+        assert!(changed.contains(wet.id()));
+    }
+
+    #[test]
+    fn world_position_round_trip() {
+        let tiles = Tiles::new(10, 10, 3);
+        let mut config = crate::settings::Config::default();
+        config.graphics.scale = 1.5;
+
+        for coord in &[
+            Vector3::new(0, 0, 0),
+            Vector3::new(3, 7, 0),
+            Vector3::new(9, 9, 2),
+        ] {
+            let world = tiles.tile_to_world(*coord, &config);
+            assert_eq!(tiles.world_to_tile(&world, &config), *coord);
+        }
+    }
+
+    #[test]
+    fn raycast() {
+        let tiles = Tiles::new(10, 10, 3);
+        let wall = tiles.id(5, 2, 0);
+        let blocked = |id| id == wall;
+
+        let line = tiles.line(tiles.id(0, 0, 0), tiles.id(9, 4, 2));
+        assert_eq!(line.len(), 10);
+        assert_eq!(line[0], tiles.id(0, 0, 0));
+        assert_eq!(line[9], tiles.id(9, 4, 2));
+
+        assert_eq!(
+            tiles.raycast(tiles.id(2, 2, 0), tiles.id(8, 2, 0), blocked),
+            Some(wall)
+        );
+        assert_eq!(
+            tiles.raycast(tiles.id(2, 3, 0), tiles.id(8, 3, 0), blocked),
+            None
+        );
+        assert!(tiles.line_of_sight(tiles.id(2, 2, 0), wall, blocked));
+        assert!(!tiles.line_of_sight(tiles.id(2, 2, 0), tiles.id(8, 2, 0), blocked));
+    }
+
+    #[test]
+    fn volume() {
+        let tiles = Tiles::new(10, 10, 3);
+
+        let volume = tiles
+            .iter_volume(Vector3::new(2, 2, 1), Vector3::new(4, 3, 5))
+            .collect::<Vec<_>>();
+        assert_eq!(volume.len(), 3 * 2 * 2);
+        assert_eq!(tiles.coord(volume[0]), Vector3::new(2, 2, 1));
+        assert_eq!(tiles.coord(volume[11]), Vector3::new(4, 3, 2));
+    }
+
+    #[test]
+    fn tracked_tiles() {
+        use crate::components::FluidDepth;
+
+        let tiles = Tiles::new(10, 10, 1);
+        let mut depths = TileStorage::<FluidDepth>::default();
+        let mut tracked = TrackedTiles::<FluidDepth>::default();
+        depths.insert(tiles.id(1, 1, 0), FluidDepth(1));
+
+        assert!(tracked.changed(&mut depths).is_none());
+        depths.insert(tiles.id(2, 1, 0), FluidDepth(2));
+        depths.get_mut(tiles.id(1, 1, 0)).unwrap().0 += 1;
+        depths.remove(tiles.id(2, 1, 0));
+        let changed = tracked
+            .changed(&mut depths)
+            .unwrap()
+            .join()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changed,
+            vec![tiles.id(1, 1, 0).id(), tiles.id(2, 1, 0).id()]
+        );
+
+        assert_eq!(tracked.changed(&mut depths).unwrap().join().count(), 0);
+    }
+
+    #[test]
+    fn flood() {
+        let tiles = Tiles::new(10, 10, 1);
+        let wall = |id| tiles.coord(id).x == 5;
+
+        assert_eq!(flood_fill(tiles, tiles.id(0, 0, 0), |id| !wall(id)).len(), 50);
+        assert_eq!(flood_fill(tiles, tiles.id(7, 3, 0), |id| !wall(id)).len(), 40);
+        assert!(flood_fill(tiles, tiles.id(5, 3, 0), |id| !wall(id)).is_empty());
+    }
+
+    #[test]
+    fn column() {
+        let tiles = Tiles::new(10, 10, 3);
+        let column = tiles.iter_column(4, 7).collect::<Vec<_>>();
+
+        assert_eq!(column.len(), 3);
+        assert_eq!(tiles.coord(column[2]), Vector3::new(4, 7, 2));
+    }
+
+    #[test]
+    fn peek() {
+        let tiles = Tiles::new(4, 4, 6);
+        let floor = [tiles.id(1, 1, 5), tiles.id(2, 2, 3), tiles.id(3, 3, 0)];
+        let filled = |id| floor.contains(&id);
+        let view_z = ViewZ {
+            z: 5,
+            ..ViewZ::default()
+        };
+
+        assert_eq!(
+            view_z.peek(tiles, 1, 1, filled),
+            Some((tiles.id(1, 1, 5), 0))
+        );
+        assert_eq!(
+            view_z.peek(tiles, 2, 2, filled),
+            Some((tiles.id(2, 2, 3), 2))
+        );
+        // Too deep to see down to
+        assert_eq!(view_z.peek(tiles, 3, 3, filled), None);
+        assert!(view_z.tint(2) < view_z.tint(1));
+    }
+}
@@ -0,0 +1,214 @@
+use amethyst::core::math::Vector3;
+use rand::Rng;
+
+use crate::actions::Direction;
+use crate::map::WorldMap;
+use crate::systems::time::Season;
+use crate::temperature::default_surface_temperature;
+use crate::tiles::{TileId, Tiles};
+
+/// Temperature in degrees celsius at or below which precipitation falls as snow.
+pub const snow_temperature: f32 = 0.0;
+/// Chance of a day bringing rain or snow, at the wettest climate.
+pub const precipitation_chance: f32 = 0.6;
+/// Chance of rain or snow keeping on into the next day.
+pub const precipitation_persistence: f32 = 0.5;
+/// Chance of the wind turning a point of the compass from one day to the next.
+pub const wind_shift_chance: f32 = 0.3;
+/// Snow cover a day of the heaviest snowfall lays down.
+pub const snowfall_per_day: f32 = 0.25;
+/// Snow cover melted per day, for each degree above `snow_temperature`.
+pub const melt_per_degree: f32 = 0.02;
+/// Extra steps' worth of time walking through the deepest snow takes.
+pub const snow_slowdown: f32 = 2.0;
+/// Game time between raindrops landing.
+pub const rain_interval: u64 = 10;
+/// Raindrops landing across the map each `rain_interval`, at the heaviest rain.
+pub const rain_drops: usize = 20;
+/// Intensity of rain from which fires left out in it are put out.
+pub const douse_intensity: f32 = 0.6;
+
+/// The compass, clockwise from north, for the wind to turn around.
+const compass: [Direction; 8] = [
+    Direction::N,
+    Direction::NE,
+    Direction::E,
+    Direction::SE,
+    Direction::S,
+    Direction::SW,
+    Direction::W,
+    Direction::NW,
+];
+
+/// The yearly average climate of the region being played, which the weather is drawn from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RegionClimate {
+    /// Degrees celsius.
+    pub temperature: f32,
+    /// `0.0..=1.0`, from desert to swamp.
+    pub moisture: f32,
+}
+impl Default for RegionClimate {
+    fn default() -> Self {
+        Self {
+            temperature: default_surface_temperature,
+            moisture: 0.5,
+        }
+    }
+}
+impl RegionClimate {
+    /// The climate of the world `map` at a point of it.
+    pub fn from_map(map: &WorldMap, x: f64, y: f64) -> Self {
+        Self {
+            temperature: map.surface_temperature(x, y),
+            moisture: map.surface_moisture(x, y),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum Precipitation {
+    None,
+    Rain,
+    Snow,
+}
+impl Default for Precipitation {
+    fn default() -> Self {
+        Precipitation::None
+    }
+}
+
+/// The weather of the day, changed each day by the `RegionClimate` and season. Rain fills the
+/// map with water and puts out fires, lying snow slows walking, and the wind blows from its
+/// `Direction`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Weather {
+    pub precipitation: Precipitation,
+    /// `0.0..=1.0` of how hard it rains or snows.
+    pub intensity: f32,
+    /// Where the wind blows towards.
+    pub wind: Direction,
+    /// `0.0..=1.0`, from calm to gale.
+    pub wind_speed: f32,
+    /// `0.0..=1.0` of how deep the snow lies.
+    pub snow_cover: f32,
+}
+impl Weather {
+    /// Moves the weather on to the next day. Wetter climates rain more often, rain or snow
+    /// tending to last a few days, and falls as snow once the season makes it cold enough.
+    /// Snow lies until it is warm enough to melt.
+    pub fn evolve<R: Rng>(&mut self, climate: &RegionClimate, season: Season, rng: &mut R) {
+        let temperature = climate.temperature + season.temperature_offset();
+
+        let persists = self.precipitation != Precipitation::None
+            && rng.gen::<f32>() < precipitation_persistence;
+        let falls = persists || rng.gen::<f32>() < climate.moisture * precipitation_chance;
+        if falls {
+            self.precipitation = if temperature <= snow_temperature {
+                Precipitation::Snow
+            } else {
+                Precipitation::Rain
+            };
+            self.intensity = (climate.moisture + rng.gen::<f32>()) / 2.0;
+        } else {
+            self.precipitation = Precipitation::None;
+            self.intensity = 0.0;
+        }
+
+        if rng.gen::<f32>() < wind_shift_chance {
+            let point = compass.iter().position(|d| *d == self.wind).unwrap_or(0);
+            // A point clockwise, or seven on round to one anticlockwise
+            let turn = if rng.gen::<f32>() < 0.5 { 1 } else { 7 };
+            self.wind = compass[(point + turn) % compass.len()];
+        }
+        self.wind_speed = (self.wind_speed + rng.gen::<f32>()) / 2.0;
+
+        if self.precipitation == Precipitation::Snow {
+            self.snow_cover = (self.snow_cover + self.intensity * snowfall_per_day).min(1.0);
+        } else if temperature > snow_temperature {
+            let melt = (temperature - snow_temperature) * melt_per_degree;
+            self.snow_cover = (self.snow_cover - melt).max(0.0);
+        }
+    }
+
+    /// How many times longer than usual a step out in the open takes, through the snow lying.
+    pub fn movement_cost(&self) -> f32 {
+        1.0 + self.snow_cover * snow_slowdown
+    }
+}
+
+/// Whether anything `roofed` is over the tile at `coord`, keeping the weather off it.
+pub fn sheltered<F>(tiles: Tiles, coord: Vector3<u32>, roofed: F) -> bool
+where
+    F: Fn(TileId) -> bool,
+{
+    (coord.z + 1..tiles.dimensions().z).any(|z| roofed(tiles.id(coord.x, coord.y, z)))
+}
+
+/// The tile of the column at `(x, y)` rain falling from the sky lands in, on the first `blocked`
+/// tile from the top, or none if the top itself is.
+pub fn landing<F>(tiles: Tiles, x: u32, y: u32, blocked: F) -> Option<TileId>
+where
+    F: Fn(TileId) -> bool,
+{
+    (0..tiles.dimensions().z)
+        .rev()
+        .map(|z| tiles.id(x, y, z))
+        .take_while(|id| !blocked(*id))
+        .last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn weather() {
+        let mut rng = StdRng::from_seed([7; 32]);
+
+        let desert = RegionClimate {
+            temperature: 30.0,
+            moisture: 0.0,
+        };
+        let mut weather = Weather::default();
+        for _ in 0..100 {
+            weather.evolve(&desert, Season::Summer, &mut rng);
+            assert_eq!(weather.precipitation, Precipitation::None);
+        }
+
+        let tundra = RegionClimate {
+            temperature: 0.0,
+            moisture: 1.0,
+        };
+        for _ in 0..100 {
+            weather.evolve(&tundra, Season::Winter, &mut rng);
+            assert_ne!(weather.precipitation, Precipitation::Rain);
+        }
+        assert!(weather.snow_cover > 0.5);
+        assert!(weather.movement_cost() > 2.0);
+
+        // A warm spell thaws it all
+        for _ in 0..10 {
+            weather.evolve(&tundra, Season::Summer, &mut rng);
+        }
+        assert!(weather.snow_cover.abs() < 1e-6);
+        assert!((weather.movement_cost() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn shelter() {
+        let tiles = Tiles::new(4, 4, 4);
+        let roof = tiles.id(1, 1, 3);
+        let floor = tiles.id(2, 2, 0);
+        let blocked = |id| id == roof || id == floor;
+
+        assert!(sheltered(tiles, Vector3::new(1, 1, 1), blocked));
+        assert!(!sheltered(tiles, Vector3::new(1, 1, 3), blocked));
+        assert!(!sheltered(tiles, Vector3::new(2, 2, 1), blocked));
+
+        assert_eq!(landing(tiles, 1, 1, blocked), None);
+        assert_eq!(landing(tiles, 2, 2, blocked), Some(tiles.id(2, 2, 1)));
+        assert_eq!(landing(tiles, 3, 3, blocked), Some(tiles.id(3, 3, 0)));
+    }
+}
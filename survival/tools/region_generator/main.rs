@@ -15,7 +15,9 @@ use amethyst::{
 };
 
 use amethyst_imgui::{imgui, imgui::im_str};
-use survival::mapgen::{CellData, Generator, GeneratorSettings, IslandGeneratorSettings};
+use survival::mapgen::{
+    CellData, Generator, GeneratorSettings, IslandGeneratorSettings, NoiseGeneratorSettings,
+};
 
 struct UiState {
     seed: imgui::ImString,
@@ -190,8 +192,18 @@ fn generate_new_map(
     generator.create_island(config, settings, &mut cells);
 
     let mut worldmap = WorldMap::new(&config);
-    worldmap.heightmap = generator.generate_height_map(&config, &cells).unwrap();
-    worldmap.moisture = generator.generate_moisture_map(&config, &cells).unwrap();
+    let noise = NoiseGeneratorSettings::default();
+
+    worldmap.heightmap = generator
+        .generate_height_map_f32(&config, &cells)
+        .unwrap();
+    generator.apply_height_detail(&config, &noise, &mut worldmap.heightmap);
+    worldmap.moisture = generator
+        .generate_moisture_map(&config, &noise.moisture, &cells)
+        .unwrap();
+    worldmap.temperature = generator
+        .generate_temperature_map(&config, &noise.temperature, &worldmap.heightmap)
+        .unwrap();
 
     let _region = worldmap.generate_chunk(0);
 
@@ -14,7 +14,9 @@ use amethyst::{
 };
 
 use amethyst_imgui::{imgui, imgui::im_str, ImguiState};
-use survival::mapgen::{CellData, Generator, GeneratorSettings, IslandGeneratorSettings};
+use survival::mapgen::{
+    CellData, CoastGeneratorSettings, Generator, GeneratorSettings, IslandGeneratorSettings,
+};
 
 #[derive(Default)]
 pub struct ImguiBeginFrameSystem;
@@ -81,6 +83,8 @@ pub struct ImguiEndFrameSystem {
     world_pixels: f32,
     num_points: i32,
     num_lloyd: i32,
+    sea_level: f32,
+    hypsometric_exponent: f32,
 }
 impl<'s> amethyst::ecs::System<'s> for ImguiEndFrameSystem {
     type SystemData = ();
@@ -96,6 +100,8 @@ impl<'s> amethyst::ecs::System<'s> for ImguiEndFrameSystem {
         self.world_pixels = g_d.world_pixels as f32;
         self.num_points = g_d.num_points as i32;
         self.num_lloyd = g_d.num_lloyd as i32;
+        self.sea_level = g_d.sea_level as f32;
+        self.hypsometric_exponent = g_d.hypsometric_exponent as f32;
     }
 
     fn run(&mut self, _: Self::SystemData) {
@@ -122,6 +128,8 @@ impl<'s> amethyst::ecs::System<'s> for ImguiEndFrameSystem {
                             world_pixels: f64::from(self.world_pixels),
                             num_points: self.num_points as usize,
                             num_lloyd: self.num_lloyd as usize,
+                            sea_level: f64::from(self.sea_level),
+                            hypsometric_exponent: f64::from(self.hypsometric_exponent),
                             ..GeneratorSettings::default()
                         };
 
@@ -143,6 +151,16 @@ impl<'s> amethyst::ecs::System<'s> for ImguiEndFrameSystem {
                         .build();
                     ui.slider_float(im_str!("Sharpness"), &mut self.sharpness, 0.1, 2.0)
                         .build();
+                    ui.separator();
+                    ui.slider_float(im_str!("Sea Level"), &mut self.sea_level, 0.0, 1.0)
+                        .build();
+                    ui.slider_float(
+                        im_str!("Hypsometric Curve"),
+                        &mut self.hypsometric_exponent,
+                        0.25,
+                        4.0,
+                    )
+                    .build();
                 });
         }
     }
@@ -209,6 +227,7 @@ fn generate_new_map(
 
     let mut cells = generator.gen_voronoi::<CellData>(&config);
     generator.create_island(config, settings, &mut cells);
+    generator.shape_coast(config, &CoastGeneratorSettings::default(), &mut cells);
 
     generator
         .save_heightmap_image(